@@ -0,0 +1,34 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use spinning::output::{save_png_bands, save_png_rows};
+
+const SIZE: u32 = 4096;
+
+// Smooth gradients with some texture, compressing about as well as a render.
+fn fill_row(y: u32, row: &mut [u8]) {
+    for (x, rgb) in row.chunks_exact_mut(3).enumerate() {
+        let x = x as u32;
+        rgb.copy_from_slice(&[
+            (x / 16) as u8,
+            (y / 16) as u8,
+            ((x * 7 + y * 13) % 256 / 32 * 32) as u8 ^ (x ^ y) as u8 & 3,
+        ]);
+    }
+}
+
+fn encode(c: &mut Criterion) {
+    let path = std::env::temp_dir().join(format!("spinning-encode-{}.png", std::process::id()));
+    let path = path.to_str().unwrap();
+    let mut group = c.benchmark_group("encode 4096x4096");
+    group.sample_size(10);
+    group.bench_function("rows", |b| {
+        b.iter(|| save_png_rows(path, SIZE, SIZE, &[], None, fill_row).unwrap())
+    });
+    group.bench_function("bands", |b| {
+        b.iter(|| save_png_bands(path, SIZE, SIZE, &[], None, fill_row).unwrap())
+    });
+    group.finish();
+    std::fs::remove_file(path).unwrap();
+}
+
+criterion_group!(benches, encode);
+criterion_main!(benches);
@@ -0,0 +1,21 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use spinning::{Params, make_image};
+
+// Whole runs at small sizes, with the default parameters otherwise.
+fn end_to_end(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generate");
+    group.sample_size(10);
+    for size in [64, 128, 256] {
+        let params = Params {
+            size,
+            ..Params::default()
+        };
+        group.bench_with_input(BenchmarkId::from_parameter(size), &params, |b, params| {
+            b.iter(|| make_image(params))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, end_to_end);
+criterion_main!(benches);
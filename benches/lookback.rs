@@ -0,0 +1,56 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use rand::prelude::*;
+use spinning::lookback::{Eviction, Lookback, Query};
+use spinning::{Pixel, color_dist_sq};
+
+use std::hint::black_box;
+
+fn filled(n: usize, rng: &mut StdRng) -> (Lookback, Vec<Pixel>) {
+    let mut lookback = Lookback::new(n, Eviction::Fifo.policy());
+    let mut pixels = vec![];
+    for i in 0..n {
+        let pixel = Pixel {
+            color: rng.random(),
+            loc: [i % 1000, i / 1000],
+            center: [0, 0],
+            center_id: 0,
+        };
+        lookback.push(pixel, rng);
+        pixels.push(pixel);
+    }
+    // Newest first, as in the lookback.
+    pixels.reverse();
+    (lookback, pixels)
+}
+
+// The planar, chunked scan against the plain scan over pixels it replaced.
+fn nearest(c: &mut Criterion) {
+    let mut group = c.benchmark_group("nearest");
+    let mut rng = StdRng::seed_from_u64(0);
+    for n in [1_000, 10_000, 100_000] {
+        let (lookback, pixels) = filled(n, &mut rng);
+        let color: [u8; 3] = rng.random();
+        group.bench_with_input(BenchmarkId::new("planes", n), &n, |b, &n| {
+            b.iter(|| {
+                lookback.nearest(&Query {
+                    color: black_box(color),
+                    take: n,
+                    cursor: [0, 0],
+                    spatial_weight: 0.0,
+                    channel: None,
+                })
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("scalar", n), &n, |b, _| {
+            b.iter(|| {
+                pixels
+                    .iter()
+                    .min_by_key(|pixel| color_dist_sq(black_box(color), pixel.color))
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, nearest);
+criterion_main!(benches);
@@ -0,0 +1,96 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use rand::prelude::*;
+use spinning::openindex::OpenIndex;
+
+const SIZE: usize = 512;
+
+// A 512x512 canvas with all but a share of its cells closed at random, as
+// a run leaves it on the way to the endgame, and the points to query from.
+fn canvas(open_share: f64) -> (OpenIndex, Vec<bool>, Vec<[usize; 2]>) {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut cells: Vec<[usize; 2]> = (0..SIZE)
+        .flat_map(|x| (0..SIZE).map(move |y| [x, y]))
+        .collect();
+    let mut index = OpenIndex::new(SIZE, &cells);
+    let mut open = vec![true; SIZE * SIZE];
+    cells.shuffle(&mut rng);
+    let closed = ((1.0 - open_share) * cells.len() as f64) as usize;
+    for &loc in &cells[..closed] {
+        index.remove(loc);
+        open[loc[0] * SIZE + loc[1]] = false;
+    }
+    let queries = (0..256)
+        .map(|_| [rng.random_range(0..SIZE), rng.random_range(0..SIZE)])
+        .collect();
+    (index, open, queries)
+}
+
+// What the index replaces: square rings outward until one holds an open
+// cell, then the closest cell in that ring and the next two.
+fn ring_scan(open: &[bool], loc: [usize; 2]) -> Option<[usize; 2]> {
+    let mut best: Option<(usize, [usize; 2])> = None;
+    for r in 0..SIZE as isize {
+        if let Some((d, _)) = best
+            && (r as usize).pow(2) > d
+        {
+            break;
+        }
+        for dx in -r..=r {
+            for dy in -r..=r {
+                if dx.abs() != r && dy.abs() != r {
+                    continue;
+                }
+                let (Some(x), Some(y)) = (
+                    loc[0].checked_add_signed(dx).filter(|&x| x < SIZE),
+                    loc[1].checked_add_signed(dy).filter(|&y| y < SIZE),
+                ) else {
+                    continue;
+                };
+                let d = (dx * dx + dy * dy) as usize;
+                if open[x * SIZE + y] && best.is_none_or(|(b, _)| d < b) {
+                    best = Some((d, [x, y]));
+                }
+            }
+        }
+    }
+    best.map(|(_, loc)| loc)
+}
+
+fn nearest(c: &mut Criterion) {
+    let mut group = c.benchmark_group("openindex/nearest");
+    for open_share in [0.5, 0.1, 0.01, 0.001, 0.0001] {
+        let (index, open, queries) = canvas(open_share);
+        let label = format!("{}% open", open_share * 100.0);
+        group.bench_with_input(BenchmarkId::new("index", &label), &queries, |b, queries| {
+            b.iter(|| queries.iter().filter_map(|&q| index.nearest(q)).count())
+        });
+        group.bench_with_input(
+            BenchmarkId::new("ring scan", &label),
+            &queries,
+            |b, queries| b.iter(|| queries.iter().filter_map(|&q| ring_scan(&open, q)).count()),
+        );
+    }
+    group.finish();
+}
+
+// Closing every cell, as a run does over its placements.
+fn removal(c: &mut Criterion) {
+    let mut cells: Vec<[usize; 2]> = (0..SIZE)
+        .flat_map(|x| (0..SIZE).map(move |y| [x, y]))
+        .collect();
+    cells.shuffle(&mut StdRng::seed_from_u64(0));
+    c.bench_function("openindex/remove", |b| {
+        b.iter_batched(
+            || OpenIndex::new(SIZE, &cells),
+            |mut index| {
+                for &loc in &cells {
+                    index.remove(loc);
+                }
+            },
+            criterion::BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group!(benches, nearest, removal);
+criterion_main!(benches);
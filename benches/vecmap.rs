@@ -0,0 +1,49 @@
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use rand::prelude::*;
+use spinning::VecMap;
+
+fn locations(size: usize) -> Vec<[usize; 2]> {
+    (0..size)
+        .flat_map(|i| (0..size).map(move |j| [i, j]))
+        .collect()
+}
+
+// Emptying a 256x256 set, as a run does with its open locations.
+fn removal(c: &mut Criterion) {
+    let size = 256;
+    c.bench_function("vecmap/new", |b| {
+        b.iter_batched(
+            || locations(size),
+            VecMap::new_from_vec,
+            BatchSize::LargeInput,
+        )
+    });
+    c.bench_function("vecmap/remove_random", |b| {
+        b.iter_batched(
+            || {
+                (
+                    VecMap::new_from_vec(locations(size)),
+                    StdRng::seed_from_u64(0),
+                )
+            },
+            |(mut set, mut rng)| while set.remove_random(&mut rng).is_some() {},
+            BatchSize::LargeInput,
+        )
+    });
+    c.bench_function("vecmap/remove", |b| {
+        let mut order = locations(size);
+        order.shuffle(&mut StdRng::seed_from_u64(0));
+        b.iter_batched(
+            || VecMap::new_from_vec(locations(size)),
+            |mut set| {
+                for loc in &order {
+                    set.remove(loc);
+                }
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group!(benches, removal);
+criterion_main!(benches);
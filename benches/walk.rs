@@ -0,0 +1,42 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use spinning::walk::{OrbitShape, WalkSettings, walk};
+
+use std::hint::black_box;
+
+// Walks that find nothing open, so each runs its whole orbit.
+fn full_orbit(c: &mut Criterion) {
+    let mut group = c.benchmark_group("walk");
+    let size = 1000;
+    for radius in [10, 100, 400] {
+        for (name, shape) in [
+            ("circle", OrbitShape::Circle),
+            (
+                "epicycle",
+                OrbitShape::Epicycle {
+                    ratio: 0.3,
+                    speed: 4.0,
+                    phase: 0.0,
+                },
+            ),
+        ] {
+            let center = [500, 500];
+            let start = [500 + radius, 500];
+            let orbit = shape.orbit(center, start, 1.0, 0.0, 0.0, 0.0);
+            group.bench_with_input(BenchmarkId::new(name, radius), &radius, |b, _| {
+                b.iter(|| {
+                    walk(
+                        orbit.as_ref(),
+                        black_box(start),
+                        size,
+                        &WalkSettings::default(),
+                        |_| false,
+                    )
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, full_orbit);
+criterion_main!(benches);
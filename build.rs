@@ -0,0 +1,41 @@
+fn main() {
+    #[cfg(feature = "header")]
+    header();
+}
+
+#[cfg(feature = "header")]
+fn header() {
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    let dir = std::env::var("CARGO_MANIFEST_DIR").expect("set by cargo");
+    let config = cbindgen::Config {
+        language: cbindgen::Language::C,
+        include_guard: Some("SPINNING_H".to_string()),
+        header: Some(
+            "/* Generated from src/ffi.rs by `cargo build --features header`. */".to_string(),
+        ),
+        export: cbindgen::ExportConfig {
+            rename: [
+                ("Params", "SpinningParams"),
+                ("Generator", "SpinningGenerator"),
+            ]
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .into(),
+            // Only the functions and the types behind their pointers; the
+            // crate's other public constants are not part of the interface.
+            item_types: vec![
+                cbindgen::ItemType::Functions,
+                cbindgen::ItemType::OpaqueItems,
+            ],
+            // Opaque only because it is public, never behind a pointer here.
+            exclude: vec!["Deficiency".to_string()],
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    cbindgen::Builder::new()
+        .with_crate(&dir)
+        .with_config(config)
+        .generate()
+        .expect("generated header")
+        .write_to_file(format!("{dir}/include/spinning.h"));
+}
@@ -0,0 +1,82 @@
+/* Generated from src/ffi.rs by `cargo build --features header`. */
+
+#ifndef SPINNING_H
+#define SPINNING_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef struct SpinningGenerator SpinningGenerator;
+
+typedef struct SpinningParams SpinningParams;
+
+/**
+ * Parses command-line style arguments, as given to the spinning binary,
+ * not counting the program name. Returns NULL on error; see
+ * spinning_last_error.
+ */
+struct SpinningParams *spinning_params_new(int argc, const char *const *argv);
+
+/**
+ * The width of the image the parameters describe, and its height but for
+ * a panorama.
+ */
+uint32_t spinning_params_size(const struct SpinningParams *params);
+
+/**
+ * The height of the image the parameters describe: the size, or the rows of
+ * a panorama's band.
+ */
+uint32_t spinning_params_height(const struct SpinningParams *params);
+
+void spinning_params_free(struct SpinningParams *params);
+
+/**
+ * Runs a whole generation into buffer, which needs size * height * 3 bytes.
+ * Returns 0 on success and -1 on error.
+ */
+int spinning_generate(const struct SpinningParams *params, uint8_t *buffer, uintptr_t len);
+
+/**
+ * A generator to run a step at a time. The parameters can be freed after.
+ * Returns NULL on error; see spinning_last_error.
+ */
+struct SpinningGenerator *spinning_generator_new(const struct SpinningParams *params);
+
+/**
+ * Places up to count pixels, returning how many were placed; fewer than
+ * count once the image is done, or on error, which sets
+ * spinning_last_error. After an error the generator can only be freed.
+ */
+uintptr_t spinning_generator_step(struct SpinningGenerator *generator, uintptr_t count);
+
+/**
+ * Fraction of the pixels placed so far, from 0 to 1.
+ */
+double spinning_generator_coverage(const struct SpinningGenerator *generator);
+
+/**
+ * Copies the image so far into buffer, which needs size * height * 3 bytes.
+ * Returns 0 on success and -1 on error.
+ */
+int spinning_generator_copy_rgb(const struct SpinningGenerator *generator,
+                                uint8_t *buffer,
+                                uintptr_t len);
+
+void spinning_generator_free(struct SpinningGenerator *generator);
+
+/**
+ * The JSON Schema of the generation parameters, as printed by `spinning
+ * schema`. Valid for the life of the program.
+ */
+const char *spinning_schema(void);
+
+/**
+ * The message of the last error on this thread, or NULL. Valid until the
+ * next call that fails.
+ */
+const char *spinning_last_error(void);
+
+#endif  /* SPINNING_H */
@@ -0,0 +1,213 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use image::RgbImage;
+
+use crate::compare::{Difference, difference, load};
+
+// A case file is a small subset of TOML, like palette files:
+//
+//     # comment
+//     [[case]]
+//     name = "default"
+//     args = "--size 64 --seed 1"
+//
+// Each [[case]] table is one render, its args split on whitespace. Cases
+// leave out --out and --out-dir, since the harness picks where renders go.
+// A case without a seed gets CASE_SEED, so both sides don't pick their own
+// from the clock.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Case {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+pub const CASE_SEED: u64 = 19;
+
+pub fn load_cases(path: &str) -> Result<Vec<Case>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("could not read {path}: {e}"))?;
+    parse_cases(&text).map_err(|e| format!("{path}: {e}"))
+}
+
+pub fn parse_cases(text: &str) -> Result<Vec<Case>, String> {
+    let mut cases: Vec<(Option<String>, Option<Vec<String>>)> = vec![];
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line == "[[case]]" {
+            cases.push((None, None));
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or(format!("unexpected line {line}"))?;
+        let value = value
+            .trim()
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .ok_or(format!("expected a quoted string, got {}", value.trim()))?;
+        let case = cases
+            .last_mut()
+            .ok_or("a key outside of a [[case]] table")?;
+        match key.trim() {
+            "name" => case.0 = Some(value.to_string()),
+            "args" => case.1 = Some(value.split_whitespace().map(String::from).collect()),
+            key => return Err(format!("unknown key {key}, expected name or args")),
+        }
+    }
+    let mut named: Vec<Case> = vec![];
+    for (n, (name, args)) in cases.into_iter().enumerate() {
+        let name = name.ok_or(format!("case {} has no name", n + 1))?;
+        let args = args.ok_or(format!("case {name} has no args"))?;
+        if name.is_empty() || name.contains(['/', '\\']) {
+            return Err(format!("case name {name:?} can't be a file name"));
+        }
+        if named.iter().any(|case| case.name == name) {
+            return Err(format!("case {name} is given twice"));
+        }
+        if args.iter().any(|a| a == "--out" || a == "--out-dir") {
+            return Err(format!("case {name} sets --out or --out-dir"));
+        }
+        let mut args = args;
+        if !args
+            .iter()
+            .any(|a| ["--seed", "--seed-text", "--seed-file"].contains(&a.as_str()))
+        {
+            args.extend(["--seed".to_string(), CASE_SEED.to_string()]);
+        }
+        named.push(Case { name, args });
+    }
+    if named.is_empty() {
+        return Err("no cases".to_string());
+    }
+    Ok(named)
+}
+
+// A baseline built from a git revision, in a worktree removed on drop.
+pub struct Baseline {
+    pub exe: PathBuf,
+    worktree: Option<PathBuf>,
+}
+
+impl Baseline {
+    // A path to an existing file is used as the binary; anything else is a
+    // git revision of the repository in the working directory, checked out
+    // into a temporary worktree and built in release mode.
+    pub fn prepare(spec: &str) -> Result<Self, String> {
+        if Path::new(spec).is_file() {
+            return Ok(Self {
+                exe: PathBuf::from(spec),
+                worktree: None,
+            });
+        }
+        let dir = std::env::temp_dir().join(format!("spinning-ab-{}", std::process::id()));
+        let dir_str = dir.to_str().ok_or("the temporary directory isn't UTF-8")?;
+        run(Command::new("git").args(["worktree", "add", "--detach", dir_str, spec]))
+            .map_err(|e| format!("could not check out {spec}: {e}"))?;
+        let baseline = Self {
+            exe: dir.join("target/release/spinning"),
+            worktree: Some(dir.clone()),
+        };
+        run(Command::new("cargo")
+            .args(["build", "--release", "--bin", "spinning"])
+            .current_dir(&dir))
+        .map_err(|e| format!("could not build {spec}: {e}"))?;
+        Ok(baseline)
+    }
+}
+
+impl Drop for Baseline {
+    fn drop(&mut self) {
+        if let Some(dir) = &self.worktree {
+            let _ = Command::new("git")
+                .args(["worktree", "remove", "--force"])
+                .arg(dir)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+        }
+    }
+}
+
+// Runs a command to completion, failing with the last line of its stderr.
+fn run(command: &mut Command) -> Result<(), String> {
+    let output = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .output()
+        .map_err(|e| e.to_string())?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(stderr
+        .lines()
+        .rev()
+        .find(|l| !l.trim().is_empty())
+        .map_or_else(|| output.status.to_string(), String::from))
+}
+
+// Renders case with exe into path.
+pub fn render(exe: &Path, case: &Case, path: &Path) -> Result<RgbImage, String> {
+    let out = path.to_str().ok_or("the output path isn't UTF-8")?;
+    run(Command::new(exe).args(&case.args).args(["--out", out]))
+        .map_err(|e| format!("case {} failed with {}: {e}", case.name, exe.display()))?;
+    load(out)
+}
+
+pub struct Outcome {
+    pub name: String,
+    // Pixels that differ at all; 0 when the renders match exactly.
+    pub differing: usize,
+    // None when the sizes differ.
+    pub difference: Option<Difference>,
+}
+
+impl Outcome {
+    pub fn identical(&self) -> bool {
+        self.differing == 0 && self.difference.is_some()
+    }
+}
+
+pub fn outcome(name: &str, baseline: &RgbImage, current: &RgbImage) -> Outcome {
+    if baseline.dimensions() != current.dimensions() {
+        return Outcome {
+            name: name.to_string(),
+            differing: (baseline.width() * baseline.height())
+                .max(current.width() * current.height()) as usize,
+            difference: None,
+        };
+    }
+    let differing = baseline
+        .pixels()
+        .zip(current.pixels())
+        .filter(|(a, b)| a != b)
+        .count();
+    Outcome {
+        name: name.to_string(),
+        differing,
+        difference: Some(difference(baseline, current)),
+    }
+}
+
+// A line per case, then the count of exact matches.
+pub fn report(outcomes: &[Outcome]) -> String {
+    let mut out = String::new();
+    for o in outcomes {
+        let line = match &o.difference {
+            _ if o.identical() => "identical".to_string(),
+            None => "different sizes".to_string(),
+            Some(d) => format!(
+                "{} pixels differ, mean difference {:.2} of 255, ssim {:.4}, \
+                 hash distance {} of 64",
+                o.differing, d.mean_abs, d.ssim, d.hash_distance
+            ),
+        };
+        writeln!(out, "{}: {line}", o.name).expect("writes to a string");
+    }
+    let same = outcomes.iter().filter(|o| o.identical()).count();
+    write!(out, "{same} of {} cases match exactly", outcomes.len()).expect("writes to a string");
+    out
+}
@@ -0,0 +1,77 @@
+use image::{Rgb, RgbImage};
+
+use crate::Params;
+use crate::params::SeedSource;
+use crate::stencil::draw_text;
+
+const GAP: u32 = 6;
+const BACKGROUND: Rgb<u8> = Rgb([24, 24, 24]);
+const INK: Rgb<u8> = Rgb([220, 220, 220]);
+
+// The command that renders params again: its arguments, with the seed added
+// when it was left at the default.
+pub fn recipe(params: &Params) -> String {
+    let mut words = vec!["spinning".to_string()];
+    words.extend(params.args.iter().cloned());
+    if params.seed_source == SeedSource::Default {
+        words.extend(["--seed".to_string(), params.seed.to_string()]);
+    }
+    words.join(" ")
+}
+
+// Text split at spaces into lines of at most width characters; longer words
+// are split wherever they run out of room.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines: Vec<String> = vec![];
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        if !line.is_empty() && line.chars().count() + 1 + word.len() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        while line.chars().count() + word.len() > width {
+            let rest = word.split_off(width - line.chars().count());
+            line.extend(word);
+            lines.push(std::mem::take(&mut line));
+            word = rest;
+        }
+        line.extend(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+// The image with a margin below it holding text, wrapped to the image's
+// width. The font grows with the image, one dot per pixel up to 512 pixels
+// wide. filled, indexed [x][y], grows to match, the margin counted as filled.
+pub fn annotate(image: &RgbImage, filled: &[Vec<bool>], text: &str) -> (RgbImage, Vec<Vec<bool>>) {
+    let (width, height) = image.dimensions();
+    let scale = (width / 512).max(1);
+    let lines = wrap(
+        text,
+        ((width.saturating_sub(2 * GAP)) / (6 * scale)) as usize,
+    );
+    let line_height = 7 * scale + GAP;
+    let margin = lines.len() as u32 * line_height + GAP;
+    let mut annotated = RgbImage::from_pixel(width, height + margin, BACKGROUND);
+    image::imageops::replace(&mut annotated, image, 0, 0);
+    for (i, line) in lines.iter().enumerate() {
+        let top = height + GAP + i as u32 * line_height;
+        draw_text(&mut annotated, line, [GAP, top], scale, INK);
+    }
+    let filled = filled
+        .iter()
+        .map(|column| {
+            let mut column = column.clone();
+            column.resize((height + margin) as usize, true);
+            column
+        })
+        .collect();
+    (annotated, filled)
+}
@@ -0,0 +1,123 @@
+use image::RgbaImage;
+
+use crate::{Color, Location, Pixel, Placement};
+
+// An existing image to build on. Pixels at or above the alpha threshold are
+// kept as they are; generation fills the rest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BaseImage {
+    image: RgbaImage,
+    threshold: u8,
+    // Pixels of exactly this color are filled too, as if transparent.
+    pub key: Option<Color>,
+}
+
+impl BaseImage {
+    // The image must be square.
+    pub fn load(path: &str, threshold: u8) -> Result<Self, String> {
+        let image = image::open(path)
+            .map_err(|e| format!("could not open {path}: {e}"))?
+            .to_rgba8();
+        if image.width() != image.height() {
+            return Err(format!(
+                "base image must be square, got {}x{}",
+                image.width(),
+                image.height()
+            ));
+        }
+        Ok(Self {
+            image,
+            threshold,
+            key: None,
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.image.width() as usize
+    }
+
+    // The color kept at loc, or None if it is to be filled.
+    pub fn kept(&self, loc: Location) -> Option<Color> {
+        let [r, g, b, a] = self.image.get_pixel(loc[0] as u32, loc[1] as u32).0;
+        (a >= self.threshold.max(1) && self.key != Some([r, g, b])).then_some([r, g, b])
+    }
+
+    // The kept pixels as placements at the middles of their cells, for
+    // rasterizing under the run's own.
+    pub fn kept_placements(&self) -> Vec<Placement> {
+        let size = self.size();
+        (0..size)
+            .flat_map(|y| (0..size).map(move |x| [x, y]))
+            .filter_map(|loc| {
+                let color = self.kept(loc)?;
+                Some(Placement {
+                    pixel: Pixel {
+                        color,
+                        loc,
+                        center: loc,
+                        center_id: 0,
+                    },
+                    parent: None,
+                    pos: loc.map(|c| c as f64 + 0.5),
+                    steps: 0,
+                })
+            })
+            .collect()
+    }
+}
+
+// The kept pixels bordering each hole, paired with the centroid of the hole,
+// so that walks from them circle into it.
+pub fn hole_borders(base: &BaseImage) -> Vec<(Location, Location)> {
+    let kept = |loc| base.kept(loc).is_some();
+    borders(base.size(), |loc| !kept(loc), kept)
+}
+
+// The solid cells beside each connected hole of a canvas of side size, paired
+// with the hole's centroid.
+pub fn borders(
+    size: usize,
+    is_hole: impl Fn(Location) -> bool,
+    is_solid: impl Fn(Location) -> bool,
+) -> Vec<(Location, Location)> {
+    let neighbors = |[x, y]: Location| {
+        [[1, 0], [-1, 0], [0, 1], [0, -1]]
+            .into_iter()
+            .map(move |[dx, dy]| [x as isize + dx, y as isize + dy])
+            .filter(move |n| n.iter().all(|&c| c >= 0 && c < size as isize))
+            .map(|n| [n[0] as usize, n[1] as usize])
+    };
+    // Label each hole by flood fill and find its centroid.
+    let mut hole = vec![usize::MAX; size * size];
+    let mut centroids = vec![];
+    for start in (0..size).flat_map(|y| (0..size).map(move |x| [x, y])) {
+        if !is_hole(start) || hole[start[1] * size + start[0]] != usize::MAX {
+            continue;
+        }
+        let id = centroids.len();
+        let mut stack = vec![start];
+        hole[start[1] * size + start[0]] = id;
+        let (mut sum, mut count) = ([0, 0], 0);
+        while let Some(loc) = stack.pop() {
+            sum = [sum[0] + loc[0], sum[1] + loc[1]];
+            count += 1;
+            for n in neighbors(loc) {
+                if is_hole(n) && hole[n[1] * size + n[0]] == usize::MAX {
+                    hole[n[1] * size + n[0]] = id;
+                    stack.push(n);
+                }
+            }
+        }
+        centroids.push([sum[0] / count, sum[1] / count]);
+    }
+    (0..size)
+        .flat_map(|y| (0..size).map(move |x| [x, y]))
+        .filter(|&loc| is_solid(loc))
+        .filter_map(|loc| {
+            neighbors(loc)
+                .map(|n| hole[n[1] * size + n[0]])
+                .find(|&id| id != usize::MAX)
+                .map(|id| (loc, centroids[id]))
+        })
+        .collect()
+}
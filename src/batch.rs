@@ -0,0 +1,215 @@
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::Params;
+
+// A batch file lists one render per line, as command line arguments split
+// on whitespace. Blank lines and lines starting with # are skipped. Each
+// job runs as its own process, so a panic or a kill only loses that job.
+pub fn load_jobs(path: &str) -> Result<Vec<Vec<String>>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("could not read {path}: {e}"))?;
+    Ok(parse_jobs(&text))
+}
+
+pub fn parse_jobs(text: &str) -> Vec<Vec<String>> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.split_whitespace().map(String::from).collect())
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct BatchSettings {
+    // The spinning binary each job runs.
+    pub exe: PathBuf,
+    pub jobs: usize,
+    pub timeout: Option<Duration>,
+    // Further attempts after a failure or timeout.
+    pub retries: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Done,
+    // The arguments didn't parse, so the job never ran.
+    Invalid,
+    Failed,
+    TimedOut,
+}
+
+impl Status {
+    pub fn name(self) -> &'static str {
+        match self {
+            Status::Done => "done",
+            Status::Invalid => "invalid",
+            Status::Failed => "failed",
+            Status::TimedOut => "timed out",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct JobResult {
+    // Line of the job among the batch's jobs, from 1.
+    pub index: usize,
+    pub status: Status,
+    pub attempts: usize,
+    pub seconds: f64,
+    pub output: String,
+    // The last line of stderr for a job that didn't finish.
+    pub error: Option<String>,
+}
+
+// Runs the jobs on up to settings.jobs workers, calling report as each one
+// finishes. Results come back in batch order.
+pub fn run_batch(
+    jobs: &[Vec<String>],
+    settings: &BatchSettings,
+    report: impl Fn(&JobResult) + Sync,
+) -> Vec<JobResult> {
+    let queue = Mutex::new((0..jobs.len()).collect::<VecDeque<usize>>());
+    let results = Mutex::new(vec![None; jobs.len()]);
+    thread::scope(|scope| {
+        for _ in 0..settings.jobs.clamp(1, jobs.len().max(1)) {
+            scope.spawn(|| {
+                loop {
+                    let Some(index) = queue.lock().expect("queue").pop_front() else {
+                        break;
+                    };
+                    let result = run_job(index + 1, &jobs[index], settings);
+                    report(&result);
+                    results.lock().expect("results")[index] = Some(result);
+                }
+            });
+        }
+    });
+    results
+        .into_inner()
+        .expect("results")
+        .into_iter()
+        .map(|result| result.expect("every job ran"))
+        .collect()
+}
+
+fn run_job(index: usize, args: &[String], settings: &BatchSettings) -> JobResult {
+    let start = Instant::now();
+    let mut result = JobResult {
+        index,
+        status: Status::Invalid,
+        attempts: 0,
+        seconds: 0.0,
+        output: String::new(),
+        error: None,
+    };
+    // An unseeded job gets its clock seed here rather than in each attempt,
+    // so its output is named as reported and retries render the same image.
+    let args = match Params::from_args_with_clock_seed(args.to_vec()) {
+        Ok(params) => {
+            result.output = params.filename();
+            params.args
+        }
+        Err(e) => {
+            result.error = Some(e);
+            return result;
+        }
+    };
+    while result.attempts <= settings.retries {
+        result.attempts += 1;
+        let (status, error) = attempt(&args, &settings.exe, settings.timeout);
+        result.status = status;
+        result.error = error;
+        if status == Status::Done {
+            break;
+        }
+    }
+    result.seconds = start.elapsed().as_secs_f64();
+    result
+}
+
+// How often a running job is checked for its timeout.
+const POLL: Duration = Duration::from_millis(20);
+
+fn attempt(args: &[String], exe: &Path, timeout: Option<Duration>) -> (Status, Option<String>) {
+    let log = std::env::temp_dir().join(format!(
+        "spinning-batch-{}-{:?}.log",
+        std::process::id(),
+        thread::current().id()
+    ));
+    let spawned = std::fs::File::create(&log).and_then(|stderr| {
+        Command::new(exe)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(stderr)
+            .spawn()
+    });
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(e) => {
+            return (
+                Status::Failed,
+                Some(format!("could not run {}: {e}", exe.display())),
+            );
+        }
+    };
+    let start = Instant::now();
+    let exit = loop {
+        match child.try_wait() {
+            Ok(Some(exit)) => break Some(exit),
+            Ok(None) if timeout.is_some_and(|t| start.elapsed() >= t) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                break None;
+            }
+            Ok(None) => thread::sleep(POLL),
+            Err(e) => return (Status::Failed, Some(format!("lost the job: {e}"))),
+        }
+    };
+    let stderr = std::fs::read_to_string(&log).unwrap_or_default();
+    let _ = std::fs::remove_file(&log);
+    let last_line = stderr.lines().rev().find(|l| !l.trim().is_empty());
+    match exit {
+        None => (Status::TimedOut, None),
+        Some(exit) if exit.success() => (Status::Done, None),
+        Some(exit) => (
+            Status::Failed,
+            Some(last_line.map_or_else(|| exit.to_string(), String::from)),
+        ),
+    }
+}
+
+// A table of every job, then the totals.
+pub fn summary(results: &[JobResult]) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "{:>4}  {:<10}{:>9}{:>10}  output",
+        "job", "status", "attempts", "seconds"
+    )
+    .expect("writes to a string");
+    for r in results {
+        write!(
+            out,
+            "{:>4}  {:<10}{:>9}{:>10.2}  {}",
+            r.index,
+            r.status.name(),
+            r.attempts,
+            r.seconds,
+            if r.output.is_empty() { "-" } else { &r.output }
+        )
+        .expect("writes to a string");
+        if let Some(error) = &r.error {
+            write!(out, " ({error})").expect("writes to a string");
+        }
+        out.push('\n');
+    }
+    let done = results.iter().filter(|r| r.status == Status::Done).count();
+    write!(out, "{done} of {} jobs done", results.len()).expect("writes to a string");
+    out
+}
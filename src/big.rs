@@ -0,0 +1,195 @@
+use memmap2::MmapMut;
+use rand::prelude::*;
+
+use std::fs::OpenOptions;
+
+use crate::canvas::Canvas;
+use crate::lookback::{Lookback, Query};
+use crate::output::save_png_rows;
+use crate::params::Params;
+use crate::rng::{Rngs, Stream};
+use crate::walk::{WalkSettings, walk};
+use crate::{Color, Location, Pixel, color_dist_sq};
+
+// The grid and open set of a big render, in a memory-mapped scratch file so
+// the OS can page them out. The file holds, one after another:
+// - each cell's color and a filled flag, 4 bytes per cell, row by row;
+// - the open cells, 4 bytes each, in the same order as VecMap would keep them;
+// - each cell's position in that list, 4 bytes each, or u32::MAX once filled.
+struct MappedGrid {
+    size: usize,
+    map: MmapMut,
+    num_open: usize,
+}
+
+impl MappedGrid {
+    fn create(path: &str, size: usize) -> Result<Self, String> {
+        let cells = size * size;
+        if cells > u32::MAX as usize {
+            return Err(format!(
+                "--big supports up to 65535 pixels across, got {size}"
+            ));
+        }
+        let error = |e: std::io::Error| format!("could not map {path}: {e}");
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .map_err(error)?;
+        file.set_len(12 * cells as u64).map_err(error)?;
+        // Safety: the scratch file is ours alone for the run.
+        let map = unsafe { MmapMut::map_mut(&file) }.map_err(error)?;
+        let mut grid = Self {
+            size,
+            map,
+            num_open: cells,
+        };
+        for cell in 0..cells {
+            grid.set_word(1, cell, cell as u32);
+            grid.set_word(2, cell, cell as u32);
+        }
+        Ok(grid)
+    }
+
+    // The word at index within a section, numbered as laid out above.
+    fn word(&self, section: usize, index: usize) -> u32 {
+        let at = 4 * (section * self.size * self.size + index);
+        u32::from_le_bytes(self.map[at..at + 4].try_into().expect("4 bytes"))
+    }
+
+    fn set_word(&mut self, section: usize, index: usize, value: u32) {
+        let at = 4 * (section * self.size * self.size + index);
+        self.map[at..at + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    // Open cells are numbered as Canvas::locations orders them.
+    fn cell(&self, loc: Location) -> usize {
+        loc[0] * self.size + loc[1]
+    }
+
+    fn is_open(&self, loc: Location) -> bool {
+        self.map[4 * (loc[1] * self.size + loc[0]) + 3] == 0
+    }
+
+    fn set(&mut self, loc: Location, color: Color) {
+        let at = 4 * (loc[1] * self.size + loc[0]);
+        self.map[at..at + 3].copy_from_slice(&color);
+        self.map[at + 3] = 1;
+    }
+
+    fn remove_at(&mut self, index: usize) -> usize {
+        let last = self.word(1, self.num_open - 1);
+        let out = self.word(1, index);
+        self.set_word(1, index, last);
+        self.set_word(2, last as usize, index as u32);
+        self.set_word(2, out as usize, u32::MAX);
+        self.num_open -= 1;
+        out as usize
+    }
+
+    fn remove(&mut self, loc: Location) {
+        let index = self.word(2, self.cell(loc));
+        if index != u32::MAX {
+            self.remove_at(index as usize);
+        }
+    }
+
+    fn remove_random<R: Rng>(&mut self, rng: &mut R) -> Option<Location> {
+        if self.num_open == 0 {
+            return None;
+        }
+        let cell = self.remove_at(rng.random_range(0..self.num_open));
+        Some([cell / self.size, cell % self.size])
+    }
+}
+
+// Generates into a memory-mapped grid and encodes the PNG from it row by row,
+// for prints too large for the usual in-memory grid. The result matches the
+// usual generator's, but only the core options apply: the size, centers,
+// lookback, spreads, color mode, seed, eviction, spatial weight,
+// maximum match distance and the walk settings.
+pub fn render_big(params: &Params, scratch: &str, path: &str) -> Result<(), String> {
+    let size = params.size;
+    let mut rngs = if params.streams {
+        Rngs::split(params.rng, params.seed)
+    } else {
+        Rngs::single(params.rng, params.seed)
+    };
+    let mut grid = MappedGrid::create(scratch, size)?;
+    let mut lookback = Lookback::new(params.num_lookback, params.eviction.policy());
+    let settings = WalkSettings {
+        neighborhood: params.neighborhood,
+        gap_jump: params.gap_jump,
+        reflect: params.reflect,
+        wrap: false,
+    };
+    let total = size * size;
+    for i in 0..total {
+        let color = params.mode.next(rngs.get(Stream::Color), i, total);
+        let nearest = (i >= params.num_centers).then(|| {
+            let cursor = lookback.front().expect("nonempty").loc;
+            *lookback
+                .nearest(&Query {
+                    color,
+                    take: params.num_lookback,
+                    cursor,
+                    spatial_weight: params.spatial_weight,
+                    channel: params.mode.match_channel(),
+                })
+                .expect("find one")
+        });
+        let nearest = nearest.filter(|nearest| {
+            params.max_match_distance.is_none_or(|max_dist| {
+                color_dist_sq(color, nearest.color) as f64 <= max_dist * max_dist
+            })
+        });
+        let walked = nearest.and_then(|nearest| {
+            let orbit =
+                params
+                    .orbit
+                    .orbit(nearest.center, nearest.loc, 1.0, 0.0, 0.0, params.spiral);
+            let loc = walk(orbit.as_ref(), nearest.loc, size, &settings, |loc| {
+                grid.is_open(loc)
+            })?;
+            let dist = (color_dist_sq(color, nearest.color) as f64).sqrt();
+            let width = ((dist * params.cont_spread) as usize).max(1);
+            let rng = rngs.get(Stream::Jitter);
+            let center = nearest
+                .center
+                .map(|c| rng.random_range(c.saturating_sub(width)..=(c + width).min(size)));
+            grid.remove(loc);
+            Some((loc, center))
+        });
+        let (loc, center) = walked.unwrap_or_else(|| {
+            let loc = grid
+                .remove_random(rngs.get(Stream::Locations))
+                .expect("nonempty");
+            let width = (size as f64 * params.start_spread) as usize;
+            let center = Canvas::Square.layout(loc, width, size, rngs.get(Stream::Layout));
+            (loc, center)
+        });
+        grid.set(loc, color);
+        let pixel = Pixel {
+            color,
+            loc,
+            center,
+            center_id: 0,
+        };
+        lookback.push(pixel, rngs.get(Stream::Lookback));
+    }
+    save_png_rows(
+        path,
+        size as u32,
+        size as u32,
+        &params.metadata(),
+        params.thumbnail,
+        |y, row| {
+            let start = 4 * y as usize * size;
+            for (x, rgb) in row.chunks_exact_mut(3).enumerate() {
+                rgb.copy_from_slice(&grid.map[start + 4 * x..start + 4 * x + 3]);
+            }
+        },
+    )
+}
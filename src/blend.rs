@@ -0,0 +1,132 @@
+use std::str::FromStr;
+use std::thread;
+
+use image::{Rgb, RgbImage};
+
+use crate::{Color, Params, Run, generate};
+
+// How the runs of several seeds combine at each pixel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Blend {
+    // The weighted mean of each channel.
+    #[default]
+    Mean,
+    // The weighted median of each channel.
+    Median,
+    // The color of the run that filled the pixel first, as a share of its
+    // placements, divided by its weight.
+    Oldest,
+}
+
+impl FromStr for Blend {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "mean" => Ok(Blend::Mean),
+            "median" => Ok(Blend::Median),
+            "oldest" => Ok(Blend::Oldest),
+            _ => Err(format!("unknown blend {s}")),
+        }
+    }
+}
+
+// The seeds after the first of a --blend-seeds run, in order.
+pub fn seeds(params: &Params) -> Vec<u64> {
+    (1..params.blend_seeds.unwrap_or(1) as u64)
+        .map(|k| params.seed.wrapping_add(k))
+        .collect()
+}
+
+// Blends first, the run of the parameters' own seed, with runs of the
+// following seeds. The other runs are generated in parallel; the result
+// depends only on the parameters.
+pub fn blend_seeds(params: &Params, first: &Run) -> RgbImage {
+    let record = params.blend == Blend::Oldest;
+    let others: Vec<Run> = thread::scope(|scope| {
+        let handles: Vec<_> = seeds(params)
+            .into_iter()
+            .map(|seed| {
+                let params = Params {
+                    seed,
+                    blend_seeds: None,
+                    ..params.clone()
+                };
+                scope.spawn(move || generate(&params, record))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("seed run finished"))
+            .collect()
+    });
+    let runs: Vec<&Run> = std::iter::once(first).chain(&others).collect();
+    let weights = match params.blend_weights.as_slice() {
+        [] => vec![1.0; runs.len()],
+        weights => weights.to_vec(),
+    };
+    blend(&runs, &weights, params.blend, params.unfilled.color())
+}
+
+// Empty pixels are left out, and background where no run filled them.
+pub fn blend(runs: &[&Run], weights: &[f64], mode: Blend, background: Color) -> RgbImage {
+    let (width, height) = runs[0].image.dimensions();
+    // Placement order of each pixel of each run, as a share of the run.
+    let ages: Vec<Vec<f64>> = match mode {
+        Blend::Oldest => runs
+            .iter()
+            .map(|run| {
+                let mut age = vec![f64::INFINITY; (width * height) as usize];
+                let n = run.placements.len().max(1) as f64;
+                for (i, p) in run.placements.iter().enumerate() {
+                    age[p.pixel.loc[1] * width as usize + p.pixel.loc[0]] = (i + 1) as f64 / n;
+                }
+                age
+            })
+            .collect(),
+        _ => vec![],
+    };
+    let mut samples: Vec<(Color, f64)> = Vec::with_capacity(runs.len());
+    RgbImage::from_fn(width, height, |x, y| {
+        samples.clear();
+        for (run, &w) in runs.iter().zip(weights) {
+            if w > 0.0 && run.filled[x as usize][y as usize] {
+                samples.push((run.image.get_pixel(x, y).0, w));
+            }
+        }
+        if samples.is_empty() {
+            return Rgb(background);
+        }
+        Rgb(match mode {
+            Blend::Mean => {
+                let total: f64 = samples.iter().map(|&(_, w)| w).sum();
+                [0, 1, 2].map(|c| {
+                    let sum: f64 = samples.iter().map(|&(color, w)| color[c] as f64 * w).sum();
+                    (sum / total).round() as u8
+                })
+            }
+            Blend::Median => [0, 1, 2].map(|c| {
+                let mut values: Vec<(u8, f64)> =
+                    samples.iter().map(|&(color, w)| (color[c], w)).collect();
+                values.sort_by_key(|&(v, _)| v);
+                let half = values.iter().map(|&(_, w)| w).sum::<f64>() / 2.0;
+                let mut seen = 0.0;
+                values
+                    .iter()
+                    .find(|&&(_, w)| {
+                        seen += w;
+                        seen >= half
+                    })
+                    .map_or(values[values.len() - 1].0, |&(v, _)| v)
+            }),
+            Blend::Oldest => {
+                let at = y as usize * width as usize + x as usize;
+                let age = |k: usize| ages[k][at] / weights[k];
+                (0..runs.len())
+                    .filter(|&k| weights[k] > 0.0 && runs[k].filled[x as usize][y as usize])
+                    .min_by(|&a, &b| age(a).total_cmp(&age(b)))
+                    .map(|k| runs[k].image.get_pixel(x, y).0)
+                    .expect("a run filled it")
+            }
+        })
+    })
+}
@@ -0,0 +1,175 @@
+use image::{RgbImage, RgbaImage};
+use rand::prelude::*;
+
+use std::f64::consts::TAU;
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::color::parse_color;
+use crate::{Color, Location, Pixel, Placement};
+
+// The set of cells that get filled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Canvas {
+    #[default]
+    Square,
+    // Only the cells inside the inscribed circle.
+    Disc,
+    // Only the given number of rows across the middle, wrapping around the
+    // left and right edges, for --panorama.
+    Band(usize),
+}
+
+impl FromStr for Canvas {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "square" => Ok(Canvas::Square),
+            "disc" => Ok(Canvas::Disc),
+            _ => Err(format!("unknown canvas {s}")),
+        }
+    }
+}
+
+impl Canvas {
+    pub fn contains(self, loc: Location, size: usize) -> bool {
+        match self {
+            Canvas::Square => true,
+            Canvas::Band(_) => self.rows(size).contains(&loc[1]),
+            Canvas::Disc => {
+                let half = size as f64 / 2.0;
+                let dx = loc[0] as f64 + 0.5 - half;
+                let dy = loc[1] as f64 + 0.5 - half;
+                dx * dx + dy * dy <= half * half
+            }
+        }
+    }
+
+    // The rows holding cells of the canvas.
+    pub fn rows(self, size: usize) -> Range<usize> {
+        match self {
+            Canvas::Band(height) => {
+                let top = (size - height.min(size)) / 2;
+                top..top + height.min(size)
+            }
+            Canvas::Square | Canvas::Disc => 0..size,
+        }
+    }
+
+    // Whether walks and centers wrap around the left and right edges.
+    pub fn wraps(self) -> bool {
+        matches!(self, Canvas::Band(_))
+    }
+
+    // Placements moved up with the rows of the canvas, so they line up with
+    // its cropped image.
+    pub fn crop_placements(self, placements: &[Placement], size: usize) -> Vec<Placement> {
+        let top = self.rows(size).start;
+        let up = |[x, y]: Location| [x, y.saturating_sub(top)];
+        placements
+            .iter()
+            .map(|p| Placement {
+                pixel: Pixel {
+                    loc: up(p.pixel.loc),
+                    center: up(p.pixel.center),
+                    ..p.pixel
+                },
+                parent: p.parent.map(up),
+                pos: [p.pos[0], p.pos[1] - top as f64],
+                ..*p
+            })
+            .collect()
+    }
+
+    pub fn locations(self, size: usize) -> Vec<Location> {
+        (0..size)
+            .flat_map(|i| (0..size).map(move |j| [i, j]))
+            .filter(|&loc| self.contains(loc, size))
+            .collect()
+    }
+
+    // A center for a pixel placed at random, within width of loc.
+    // On a disc the offset is drawn in polar coordinates, and the center
+    // is pulled back inside the disc if it lands outside. On a band it wraps
+    // around the sides and keeps to the band's rows.
+    pub fn layout<R: Rng>(self, loc: Location, width: usize, size: usize, rng: &mut R) -> Location {
+        match self {
+            Canvas::Square => [
+                rng.random_range(loc[0].saturating_sub(width)..=(loc[0] + width).min(size - 1)),
+                rng.random_range(loc[1].saturating_sub(width)..=(loc[1] + width).min(size - 1)),
+            ],
+            Canvas::Disc => {
+                let half = size as f64 / 2.0;
+                let r = rng.random_range(0.0..=width as f64);
+                let theta = rng.random_range(0.0..TAU);
+                let mut x = loc[0] as f64 + 0.5 + r * theta.cos() - half;
+                let mut y = loc[1] as f64 + 0.5 + r * theta.sin() - half;
+                let dist = x.hypot(y);
+                if dist > half {
+                    x *= half / dist;
+                    y *= half / dist;
+                }
+                let clamp = |v: f64| ((v + half) as usize).min(size - 1);
+                [clamp(x), clamp(y)]
+            }
+            Canvas::Band(_) => {
+                let rows = self.rows(size);
+                let dx = rng.random_range(0..=2 * width);
+                [
+                    (loc[0] + size * (width / size + 1) + dx - width) % size,
+                    rng.random_range(
+                        loc[1].saturating_sub(width).max(rows.start)
+                            ..=(loc[1] + width).min(rows.end - 1),
+                    ),
+                ]
+            }
+        }
+    }
+}
+
+// What cells left without a pixel become in the output: cells outside the
+// canvas, and those skipped by an early stop.
+// Written as `black`, `transparent` or `rrggbb`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Unfilled {
+    #[default]
+    Black,
+    Color(Color),
+    Transparent,
+}
+
+impl FromStr for Unfilled {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "black" => Ok(Unfilled::Black),
+            "transparent" => Ok(Unfilled::Transparent),
+            _ => parse_color(s).map(Unfilled::Color).map_err(|_| {
+                format!("unknown unfilled policy {s}, expected black, transparent or rrggbb")
+            }),
+        }
+    }
+}
+
+impl Unfilled {
+    pub fn color(self) -> Color {
+        match self {
+            Unfilled::Color(color) => color,
+            Unfilled::Black | Unfilled::Transparent => [0, 0, 0],
+        }
+    }
+}
+
+// The image with the cells not in filled made transparent.
+// filled is indexed like the generator's grid, by x then y.
+pub fn with_alpha(img: &RgbImage, filled: &[Vec<bool>]) -> RgbaImage {
+    RgbaImage::from_fn(img.width(), img.height(), |x, y| {
+        let [r, g, b] = img.get_pixel(x, y).0;
+        let a = if filled[x as usize][y as usize] {
+            255
+        } else {
+            0
+        };
+        image::Rgba([r, g, b, a])
+    })
+}
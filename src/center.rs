@@ -0,0 +1,495 @@
+use rand::prelude::*;
+
+use std::collections::{HashMap, VecDeque};
+use std::f64::consts::TAU;
+use std::str::FromStr;
+
+use crate::Location;
+use crate::canvas::Canvas;
+use crate::scene::{SceneReader, SceneWriter};
+
+// How each center moves as its pixels are placed, in pixels per placement.
+// Written as `walk:SPEED`, `vector:DX,DY` or `field:SPEED`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CenterDrift {
+    // A random step of up to SPEED along each axis.
+    Walk(f64),
+    // A constant velocity.
+    Vector([f64; 2]),
+    // Along a smooth flow field covering the canvas.
+    Field(f64),
+}
+
+impl FromStr for CenterDrift {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        let number = |v: &str| {
+            v.trim()
+                .parse::<f64>()
+                .map_err(|_| format!("invalid center drift {s}"))
+        };
+        match s.split_once(':') {
+            Some(("walk", speed)) => Ok(CenterDrift::Walk(number(speed)?)),
+            Some(("field", speed)) => Ok(CenterDrift::Field(number(speed)?)),
+            Some(("vector", v)) => {
+                let (dx, dy) = v
+                    .split_once(',')
+                    .ok_or(format!("--center-drift vector expects DX,DY, got {v}"))?;
+                Ok(CenterDrift::Vector([number(dx)?, number(dy)?]))
+            }
+            _ => Err(format!(
+                "unknown center drift {s}, expected walk:SPEED, vector:DX,DY or field:SPEED"
+            )),
+        }
+    }
+}
+
+impl CenterDrift {
+    // The move of a center now at pos on a canvas of the given size.
+    pub fn step<R: Rng + ?Sized>(self, pos: [f64; 2], size: usize, rng: &mut R) -> [f64; 2] {
+        match self {
+            CenterDrift::Walk(speed) => [0, 1].map(|_| rng.random_range(-1.0..=1.0) * speed),
+            CenterDrift::Vector(v) => v,
+            CenterDrift::Field(speed) => {
+                // Three waves across the canvas each way.
+                let scale = 3.0 * TAU / size as f64;
+                let angle = TAU * ((pos[0] * scale).sin() + (pos[1] * scale).cos()) / 2.0;
+                [angle.cos() * speed, angle.sin() * speed]
+            }
+        }
+    }
+}
+
+// A number drawn afresh for each center. Written as `V` for always V or
+// `LO..HI` for uniform between them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Draw {
+    pub lo: f64,
+    pub hi: f64,
+}
+
+impl FromStr for Draw {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        let number = |v: &str| {
+            v.trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite())
+                .ok_or(format!("expected V or LO..HI, got {s}"))
+        };
+        let (lo, hi) = match s.split_once("..") {
+            Some((lo, hi)) => (number(lo)?, number(hi)?),
+            None => (number(s)?, number(s)?),
+        };
+        if lo > hi {
+            return Err(format!("{s} runs backwards"));
+        }
+        Ok(Self { lo, hi })
+    }
+}
+
+impl Draw {
+    pub fn draw<R: Rng + ?Sized>(self, rng: &mut R) -> f64 {
+        if self.lo == self.hi {
+            self.lo
+        } else {
+            rng.random_range(self.lo..self.hi)
+        }
+    }
+}
+
+// Where the initial centers start. Written as `random` or `relaxed`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CenterLayout {
+    // Each around its own random pixel, spread by --start-spread.
+    #[default]
+    Random,
+    // Random points evened out by Lloyd relaxation.
+    Relaxed,
+}
+
+impl FromStr for CenterLayout {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "random" => Ok(CenterLayout::Random),
+            "relaxed" => Ok(CenterLayout::Relaxed),
+            _ => Err(format!("unknown center layout {s}")),
+        }
+    }
+}
+
+// Cells sampled per side when relaxing, however large the canvas.
+const RELAX_SAMPLES: usize = 256;
+
+// What reading a scene naming a center it doesn't hold reports.
+pub const UNKNOWN_CENTER: &str = "the scene has a center that doesn't exist";
+
+// count random points of the canvas, each moved iters times to the centroid
+// of the cells nearest it. More iterations give a more even layout.
+pub fn relaxed_layout<R: Rng + ?Sized>(
+    canvas: Canvas,
+    size: usize,
+    count: usize,
+    iters: usize,
+    rng: &mut R,
+) -> Vec<Location> {
+    if count == 0 {
+        return vec![];
+    }
+    let mut points: Vec<[f64; 2]> = (0..count)
+        .map(|_| {
+            loop {
+                let p = [0, 1].map(|_| rng.random_range(0.0..size as f64));
+                if canvas.contains(p.map(|c| c as usize), size) {
+                    break p;
+                }
+            }
+        })
+        .collect();
+    let step = size.div_ceil(RELAX_SAMPLES);
+    let cells: Vec<[f64; 2]> = (0..size)
+        .step_by(step)
+        .flat_map(|x| (0..size).step_by(step).map(move |y| [x, y]))
+        .filter(|&loc| canvas.contains(loc, size))
+        .map(|loc| loc.map(|c| c as f64 + 0.5))
+        .collect();
+    for _ in 0..iters {
+        let mut sums = vec![[0.0, 0.0, 0.0]; count];
+        for cell in &cells {
+            let dist = |p: &[f64; 2]| (p[0] - cell[0]).powi(2) + (p[1] - cell[1]).powi(2);
+            let nearest = (0..count)
+                .min_by(|&a, &b| dist(&points[a]).total_cmp(&dist(&points[b])))
+                .expect("some points");
+            let sum = &mut sums[nearest];
+            sum[0] += cell[0];
+            sum[1] += cell[1];
+            sum[2] += 1.0;
+        }
+        for (point, sum) in points.iter_mut().zip(&sums) {
+            if sum[2] > 0.0 {
+                *point = [sum[0] / sum[2], sum[1] / sum[2]];
+            }
+        }
+    }
+    points
+        .into_iter()
+        .map(|p| p.map(|c| (c as usize).min(size - 1)))
+        .collect()
+}
+
+// A center that pixels orbit. Each random placement starts one, and the
+// pixels continuing from it refer to it by id.
+#[derive(Debug, Clone, Copy)]
+pub struct Center {
+    // Where it was laid out.
+    pub position: Location,
+    // Index of the per-center palette its pixels draw from, if any.
+    pub palette: usize,
+    // Placement index at which it started.
+    pub born: usize,
+    // Pixels placed around it so far.
+    pub pixels: usize,
+    // How far it has drifted since it started.
+    pub offset: [f64; 2],
+    // Retired centers have no pixels left in the lookback.
+    pub retired: bool,
+    // Its orbits are ellipses stretch times as long along the direction tilt
+    // radians from the x axis as across it.
+    pub tilt: f64,
+    pub stretch: f64,
+    // The width its orbits' radii snap to a multiple of, if above 0.
+    pub ring: f64,
+}
+
+// Live centers bucketed by where they were laid out into square cells gap
+// wide, so those within gap of a point are in the 3x3 cells around it.
+#[derive(Debug, Clone)]
+struct Spacing {
+    gap: f64,
+    cells: HashMap<[usize; 2], Vec<usize>>,
+}
+
+impl Spacing {
+    fn cell(&self, position: Location) -> [usize; 2] {
+        position.map(|c| (c as f64 / self.gap) as usize)
+    }
+
+    fn insert(&mut self, id: usize, position: Location) {
+        let cell = self.cell(position);
+        self.cells.entry(cell).or_default().push(id);
+    }
+
+    fn remove(&mut self, id: usize, position: Location) {
+        let cell = self.cell(position);
+        if let Some(ids) = self.cells.get_mut(&cell) {
+            ids.retain(|&other| other != id);
+        }
+    }
+}
+
+// Every center of a run, indexed by id.
+#[derive(Debug, Clone, Default)]
+pub struct Centers {
+    centers: Vec<Center>,
+    // Live centers in order of birth, for expiry by age.
+    births: VecDeque<usize>,
+    // Walks of one center that ended against another's pixels, by pair.
+    collisions: HashMap<[usize; 2], usize>,
+    // For --center-gap.
+    spacing: Option<Spacing>,
+}
+
+impl Centers {
+    pub fn len(&self) -> usize {
+        self.centers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.centers.is_empty()
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Center> {
+        self.centers.get(id)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Center> {
+        self.centers.iter()
+    }
+
+    // Keeps the live centers at least gap apart, as far as clear_of is
+    // asked before placing them.
+    pub fn with_gap(mut self, gap: Option<f64>) -> Self {
+        self.spacing = gap.map(|gap| Spacing {
+            gap,
+            cells: HashMap::new(),
+        });
+        if let Some(spacing) = &mut self.spacing {
+            for (id, center) in self.centers.iter().enumerate() {
+                if !center.retired {
+                    spacing.insert(id, center.position);
+                }
+            }
+        }
+        self
+    }
+
+    // Whether no live center but except lies within the gap of position.
+    pub fn clear_of(&self, position: Location, except: Option<usize>) -> bool {
+        let Some(spacing) = &self.spacing else {
+            return true;
+        };
+        let [cx, cy] = spacing.cell(position);
+        let near = |id: &usize| {
+            let other = self.centers[*id].position;
+            let [dx, dy] = [0, 1].map(|k| other[k].abs_diff(position[k]) as f64);
+            Some(*id) != except && dx.hypot(dy) < spacing.gap
+        };
+        (cx.saturating_sub(1)..=cx + 1)
+            .flat_map(|x| (cy.saturating_sub(1)..=cy + 1).map(move |y| [x, y]))
+            .filter_map(|cell| spacing.cells.get(&cell))
+            .all(|ids| !ids.iter().any(near))
+    }
+
+    // Returns the new center's id.
+    pub fn spawn(&mut self, position: Location, palette: usize, born: usize) -> usize {
+        let id = self.centers.len();
+        if let Some(spacing) = &mut self.spacing {
+            spacing.insert(id, position);
+        }
+        self.centers.push(Center {
+            position,
+            palette,
+            born,
+            pixels: 0,
+            offset: [0.0; 2],
+            retired: false,
+            tilt: 0.0,
+            stretch: 1.0,
+            ring: 0.0,
+        });
+        self.births.push_back(id);
+        id
+    }
+
+    pub fn set_shape(&mut self, id: usize, tilt: f64, stretch: f64) {
+        let center = &mut self.centers[id];
+        center.tilt = tilt;
+        center.stretch = stretch;
+    }
+
+    pub fn set_ring(&mut self, id: usize, ring: f64) {
+        self.centers[id].ring = ring;
+    }
+
+    // A location recorded for one of the center's pixels, moved by its drift.
+    pub fn drifted(&self, id: usize, loc: Location, size: usize) -> Location {
+        match self.centers.get(id) {
+            Some(center) if center.offset != [0.0; 2] => {
+                [0, 1].map(|k| (loc[k] as f64 + center.offset[k]).clamp(0.0, size as f64) as usize)
+            }
+            _ => loc,
+        }
+    }
+
+    // Counts a pixel placed around the center, drifting it if asked.
+    // Returns the center's pixel count.
+    pub fn attach<R: Rng + ?Sized>(
+        &mut self,
+        id: usize,
+        drift: Option<(CenterDrift, Location)>,
+        size: usize,
+        rng: &mut R,
+    ) -> usize {
+        let Some(center) = self.centers.get_mut(id) else {
+            return 0;
+        };
+        if let Some((drift, at)) = drift {
+            let step = drift.step(at.map(|c| c as f64), size, rng);
+            center.offset[0] += step[0];
+            center.offset[1] += step[1];
+        }
+        center.pixels += 1;
+        center.pixels
+    }
+
+    // Ids of up to n live centers with the most pixels, heaviest first.
+    pub fn heaviest(&self, n: usize) -> Vec<usize> {
+        let mut ids: Vec<usize> = (0..self.centers.len())
+            .filter(|&id| !self.centers[id].retired && self.centers[id].pixels > 0)
+            .collect();
+        ids.sort_by_key(|&id| std::cmp::Reverse(self.centers[id].pixels));
+        ids.truncate(n);
+        ids
+    }
+
+    // Counts a walk of center a that ended against a pixel of center b,
+    // either way round. Returns the pair's count so far.
+    pub fn collide(&mut self, a: usize, b: usize) -> usize {
+        let count = self.collisions.entry([a.min(b), a.max(b)]).or_default();
+        *count += 1;
+        *count
+    }
+
+    // Folds center gone into keep: keep moves to the mean of their positions,
+    // weighted by pixels, and gets their pixels; gone retires. Returns where
+    // keep now is.
+    pub fn merge(&mut self, keep: usize, gone: usize, size: usize) -> Location {
+        let weight = |c: &Center| c.pixels.max(1) as f64;
+        let at = |c: &Center| [0, 1].map(|k| c.position[k] as f64 + c.offset[k]);
+        let (a, b) = (self.centers[keep], self.centers[gone]);
+        let (wa, wb) = (weight(&a), weight(&b));
+        let (pa, pb) = (at(&a), at(&b));
+        let position = [0, 1]
+            .map(|k| ((pa[k] * wa + pb[k] * wb) / (wa + wb)).clamp(0.0, size as f64) as usize);
+        if let Some(spacing) = &mut self.spacing {
+            spacing.remove(keep, a.position);
+            spacing.insert(keep, position);
+        }
+        let center = &mut self.centers[keep];
+        center.position = position;
+        center.offset = [0.0; 2];
+        center.pixels += b.pixels;
+        self.retire(gone);
+        position
+    }
+
+    pub fn set_palette(&mut self, id: usize, palette: usize) {
+        self.centers[id].palette = palette;
+    }
+
+    pub fn retire(&mut self, id: usize) {
+        let center = &mut self.centers[id];
+        if !center.retired
+            && let Some(spacing) = &mut self.spacing
+        {
+            spacing.remove(id, center.position);
+        }
+        center.retired = true;
+    }
+
+    pub fn write_scene(&self, w: &mut SceneWriter) {
+        w.usize(self.centers.len());
+        for center in &self.centers {
+            w.location(center.position);
+            w.usize(center.palette);
+            w.usize(center.born);
+            w.usize(center.pixels);
+            center.offset.into_iter().for_each(|v| w.f64(v));
+            w.bool(center.retired);
+            w.f64(center.tilt);
+            w.f64(center.stretch);
+            w.f64(center.ring);
+        }
+        w.usize(self.births.len());
+        self.births.iter().for_each(|&id| w.usize(id));
+        let mut collisions: Vec<_> = self.collisions.iter().collect();
+        collisions.sort();
+        w.usize(collisions.len());
+        for (&[a, b], &count) in collisions {
+            w.usize(a);
+            w.usize(b);
+            w.usize(count);
+        }
+    }
+
+    pub fn read_scene(r: &mut SceneReader) -> Result<Self, String> {
+        let centers = (0..r.count()?)
+            .map(|_| {
+                let mut center = Center {
+                    position: r.location()?,
+                    palette: r.usize()?,
+                    born: r.usize()?,
+                    pixels: r.usize()?,
+                    offset: [r.f64()?, r.f64()?],
+                    retired: r.bool()?,
+                    tilt: 0.0,
+                    stretch: 1.0,
+                    ring: 0.0,
+                };
+                // Centers were round before version 2, and without rings
+                // before version 3.
+                if r.version >= 2 {
+                    (center.tilt, center.stretch) = (r.f64()?, r.f64()?);
+                }
+                if r.version >= 3 {
+                    center.ring = r.f64()?;
+                }
+                Ok(center)
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let known = |id: usize| match id < centers.len() {
+            true => Ok(id),
+            false => Err(UNKNOWN_CENTER.to_string()),
+        };
+        let births = (0..r.count()?)
+            .map(|_| known(r.usize()?))
+            .collect::<Result<_, _>>()?;
+        let collisions = (0..r.count()?)
+            .map(|_| Ok(([known(r.usize()?)?, known(r.usize()?)?], r.usize()?)))
+            .collect::<Result<_, String>>()?;
+        Ok(Self {
+            centers,
+            births,
+            collisions,
+            spacing: None,
+        })
+    }
+
+    // Retires the live centers born at least max_age placements before now,
+    // returning their ids.
+    pub fn expire(&mut self, now: usize, max_age: usize) -> Vec<usize> {
+        let mut expired = vec![];
+        while let Some(&id) = self.births.front()
+            && now.saturating_sub(self.centers[id].born) >= max_age
+        {
+            self.births.pop_front();
+            if !self.centers[id].retired {
+                self.retire(id);
+                expired.push(id);
+            }
+        }
+        expired
+    }
+}
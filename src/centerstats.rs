@@ -0,0 +1,149 @@
+use image::{Rgb, RgbImage};
+
+use crate::Placement;
+use crate::color::hue_chroma;
+use crate::stencil::draw_text;
+
+// Hue bins of 10 degrees, for each center's dominant hue.
+const HUE_BINS: usize = 36;
+
+// How many of the largest centers --center-labels marks.
+pub const LABELS: usize = 32;
+
+const MARK: Rgb<u8> = Rgb([255, 255, 255]);
+const BACKING: Rgb<u8> = Rgb([0, 0, 0]);
+
+// What a center's pixels came to, as the run left them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CenterStats {
+    pub id: usize,
+    pub pixels: usize,
+    // The mean of the orbit centers its pixels were placed around, which
+    // jitter and drift spread about where it was laid out.
+    pub center: [f64; 2],
+    // Mean distance of its pixels from the orbit centers they were placed
+    // around.
+    pub mean_radius: f64,
+    // The 10 degree bin of hues holding the most chroma, by its middle; None
+    // for centers of grays alone.
+    pub hue: Option<f64>,
+    // The smallest and largest x and y of its pixels.
+    pub bounds: [usize; 4],
+}
+
+// The stats of every center with pixels, largest first.
+pub fn center_stats(placements: &[Placement]) -> Vec<CenterStats> {
+    struct Sums {
+        pixels: usize,
+        center: [f64; 2],
+        radius: f64,
+        hues: [f64; HUE_BINS],
+        bounds: [usize; 4],
+    }
+    let mut sums: Vec<Option<Sums>> = vec![];
+    for placement in placements {
+        let pixel = &placement.pixel;
+        let id = pixel.center_id;
+        if sums.len() <= id {
+            sums.resize_with(id + 1, || None);
+        }
+        let [x, y] = pixel.loc;
+        let s = sums[id].get_or_insert(Sums {
+            pixels: 0,
+            center: [0.0; 2],
+            radius: 0.0,
+            hues: [0.0; HUE_BINS],
+            bounds: [x, y, x, y],
+        });
+        s.pixels += 1;
+        let center = pixel.center.map(|c| c as f64);
+        s.center = [0, 1].map(|k| s.center[k] + center[k]);
+        let d = [0, 1].map(|k| pixel.loc[k] as f64 + 0.5 - center[k]);
+        s.radius += d[0].hypot(d[1]);
+        let (hue, chroma) = hue_chroma(pixel.color);
+        s.hues[(hue / 360.0 * HUE_BINS as f64) as usize % HUE_BINS] += chroma;
+        s.bounds = [
+            s.bounds[0].min(x),
+            s.bounds[1].min(y),
+            s.bounds[2].max(x),
+            s.bounds[3].max(y),
+        ];
+    }
+    let mut stats: Vec<CenterStats> = sums
+        .into_iter()
+        .enumerate()
+        .filter_map(|(id, s)| {
+            let s = s?;
+            let n = s.pixels as f64;
+            let (bin, &chroma) = s
+                .hues
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.total_cmp(b.1))
+                .expect("bins");
+            Some(CenterStats {
+                id,
+                pixels: s.pixels,
+                center: s.center.map(|c| c / n),
+                mean_radius: s.radius / n,
+                hue: (chroma > 0.0).then(|| (bin as f64 + 0.5) * 360.0 / HUE_BINS as f64),
+                bounds: s.bounds,
+            })
+        })
+        .collect();
+    stats.sort_by_key(|s| (std::cmp::Reverse(s.pixels), s.id));
+    stats
+}
+
+pub fn stats_csv(stats: &[CenterStats]) -> String {
+    let mut csv = "id,pixels,center_x,center_y,mean_radius,dominant_hue,min_x,min_y,max_x,max_y\n"
+        .to_string();
+    for s in stats {
+        let hue = s.hue.map_or(String::new(), |h| format!("{h:.0}"));
+        let [x0, y0, x1, y1] = s.bounds;
+        csv += &format!(
+            "{},{},{:.1},{:.1},{:.2},{hue},{x0},{y0},{x1},{y1}\n",
+            s.id, s.pixels, s.center[0], s.center[1], s.mean_radius
+        );
+    }
+    csv
+}
+
+// Sets a pixel, ignoring those off the image.
+fn put(image: &mut RgbImage, x: i64, y: i64, color: Rgb<u8>) {
+    if (0..image.width() as i64).contains(&x) && (0..image.height() as i64).contains(&y) {
+        image.put_pixel(x as u32, y as u32, color);
+    }
+}
+
+// The image with the largest count centers marked by a cross at their mean
+// center and labeled with their ids, for picking out regions to crop.
+pub fn label_overlay(image: &RgbImage, stats: &[CenterStats], count: usize) -> RgbImage {
+    let mut overlay = image.clone();
+    let scale = (overlay.width().max(overlay.height()) / 512).max(1);
+    let arm = 4 * scale as i64;
+    for s in stats.iter().take(count) {
+        let [cx, cy] = s.center.map(|c| c as i64);
+        // A white cross on a black one a pixel wider, to show on any color.
+        for (color, width) in [(BACKING, 1), (MARK, 0)] {
+            for t in -arm..=arm {
+                for w in -width..=width {
+                    put(&mut overlay, cx + t, cy + w, color);
+                    put(&mut overlay, cx + w, cy + t, color);
+                }
+            }
+        }
+        let label = format!("#{}", s.id);
+        let [left, top] = [cx + arm + 2 * scale as i64, cy - 4 * scale as i64];
+        let box_width = (6 * label.len() as i64 + 1) * scale as i64;
+        for y in top - scale as i64..top + 8 * scale as i64 {
+            for x in left - scale as i64..left + box_width {
+                put(&mut overlay, x, y, BACKING);
+            }
+        }
+        if left >= 0 && top >= 0 {
+            draw_text(&mut overlay, &label, [left as u32, top as u32], scale, MARK);
+        }
+    }
+    overlay
+}
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use crate::{Color, Location, Placement};
+
+// A run of continuations, each starting where the previous one landed.
+// A chain is everything grown, directly or indirectly, from one random
+// insertion; branching chains are split into several polylines.
+#[derive(Debug, Clone)]
+pub struct Polyline {
+    pub chain: usize,
+    pub color: Color,
+    pub points: Vec<Location>,
+}
+
+// The chain of each placement, numbered in order of the random insertions.
+// A placement whose parent isn't in the record, like one continuing from a
+// pixel around an inpainted hole or from before a resumed scene, starts a
+// chain of its own.
+pub fn chain_ids(placements: &[Placement]) -> Vec<usize> {
+    let mut chain_of: HashMap<Location, usize> = HashMap::new();
+    let mut next = 0;
+    placements
+        .iter()
+        .map(|placement| {
+            let known = placement
+                .parent
+                .and_then(|parent| chain_of.get(&parent).copied());
+            let chain = match known {
+                Some(chain) => chain,
+                None => {
+                    next += 1;
+                    next - 1
+                }
+            };
+            chain_of.insert(placement.pixel.loc, chain);
+            chain
+        })
+        .collect()
+}
+
+pub fn polylines(placements: &[Placement]) -> Vec<Polyline> {
+    let mut tips: HashMap<Location, usize> = HashMap::new();
+    let mut sums: Vec<[u64; 3]> = vec![];
+    let mut lines: Vec<Polyline> = vec![];
+    for (placement, chain) in placements.iter().zip(chain_ids(placements)) {
+        let pixel = placement.pixel;
+        let Some(parent) = placement.parent else {
+            continue;
+        };
+        let index = match tips.remove(&parent) {
+            Some(index) => index,
+            None => {
+                lines.push(Polyline {
+                    chain,
+                    color: pixel.color,
+                    points: vec![parent],
+                });
+                sums.push([0; 3]);
+                lines.len() - 1
+            }
+        };
+        lines[index].points.push(pixel.loc);
+        for (sum, c) in sums[index].iter_mut().zip(pixel.color) {
+            *sum += c as u64;
+        }
+        tips.insert(pixel.loc, index);
+    }
+    // Stroke each polyline with the mean color of its placements.
+    for (line, sum) in lines.iter_mut().zip(sums) {
+        let n = line.points.len() as u64 - 1;
+        line.color = sum.map(|s| (s / n) as u8);
+    }
+    lines.sort_by_key(|line| line.chain);
+    lines
+}
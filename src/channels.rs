@@ -0,0 +1,62 @@
+use std::thread;
+
+use image::RgbImage;
+
+use crate::{Params, Run, generate};
+
+// Builds each channel of the image from a run of its own seed: red from the
+// first of seeds, green from the second, blue from the third. A seed equal to
+// the parameters' own reuses first, the run of that seed; the others are
+// generated in parallel. A channel takes the background's value where its run
+// left the pixel empty. Returns the image and which cells any run filled.
+pub fn split_channels(params: &Params, first: &Run, seeds: [u64; 3]) -> (RgbImage, Vec<Vec<bool>>) {
+    let others: Vec<(u64, Run)> = thread::scope(|scope| {
+        let mut todo = seeds.to_vec();
+        todo.sort();
+        todo.dedup();
+        todo.retain(|&seed| seed != params.seed);
+        let handles: Vec<_> = todo
+            .into_iter()
+            .map(|seed| {
+                let params = Params {
+                    seed,
+                    channel_seeds: None,
+                    ..params.clone()
+                };
+                scope.spawn(move || (seed, generate(&params, false)))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("channel run finished"))
+            .collect()
+    });
+    let runs: Vec<&Run> = seeds
+        .iter()
+        .map(|&seed| {
+            others
+                .iter()
+                .find(|(s, _)| *s == seed)
+                .map_or(first, |(_, run)| run)
+        })
+        .collect();
+    let background = params.unfilled.color();
+    let mut image = first.image.clone();
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        for (ch, run) in runs.iter().enumerate() {
+            pixel.0[ch] = if run.filled[x as usize][y as usize] {
+                run.image.get_pixel(x, y).0[ch]
+            } else {
+                background[ch]
+            };
+        }
+    }
+    let filled = (0..first.filled.len())
+        .map(|x| {
+            (0..first.filled[x].len())
+                .map(|y| runs.iter().any(|run| run.filled[x][y]))
+                .collect()
+        })
+        .collect();
+    (image, filled)
+}
@@ -0,0 +1,191 @@
+use rand::prelude::*;
+
+use std::sync::Arc;
+
+use crate::Color;
+use crate::curve::Curve;
+use crate::harmony::Harmony;
+use crate::linear::{from_linear, to_linear};
+use crate::noise::NoiseField;
+use crate::palette::Inks;
+
+// Where candidate colors come from.
+#[derive(Debug, Clone)]
+pub enum ColorSource {
+    Uniform,
+    // Uniform choice among a fixed set of colors.
+    Palette(Vec<Color>),
+    // Uniform luminance, all channels equal.
+    Grayscale,
+    // Uniform position on the line between two colors.
+    Duotone(Color, Color),
+    // Uniform position along a piecewise-linear colormap.
+    Colormap(Vec<Color>),
+    // Position along a colormap given by placement progress, so the image
+    // records its own growth from the first stop to the last.
+    Timed(Vec<Color>),
+    // Another source, with each channel shifted by up to the given amount.
+    Jittered(Box<ColorSource>, u8),
+    // Consecutive points of a space-filling curve through color space.
+    Curve(Curve),
+    // Slowly drifting noise over placement time.
+    Noise(NoiseField),
+    // Fixed colors, each used for its share of the run.
+    Inks(Inks),
+    // Hues from a color wheel rule around a base hue.
+    Harmony(Harmony),
+    // Given colors, one per placement in order, starting over at the end.
+    Sequence(Arc<Vec<Color>>),
+}
+
+impl ColorSource {
+    // The color for placement i of total.
+    pub fn next<R: Rng>(&self, rng: &mut R, i: usize, total: usize) -> Color {
+        match self {
+            ColorSource::Uniform => [rng.random(), rng.random(), rng.random()],
+            ColorSource::Palette(palette) => palette[rng.random_range(0..palette.len())],
+            ColorSource::Grayscale => [rng.random(); 3],
+            ColorSource::Duotone(a, b) => lerp(*a, *b, rng.random::<u8>() as f64 / 255.0),
+            ColorSource::Colormap(stops) => sample_colormap(stops, rng.random()),
+            ColorSource::Timed(stops) => {
+                sample_colormap(stops, i as f64 / (total - 1).max(1) as f64)
+            }
+            ColorSource::Jittered(source, jitter) => {
+                let jitter = *jitter as i32;
+                source
+                    .next(rng, i, total)
+                    .map(|c| (c as i32 + rng.random_range(-jitter..=jitter)).clamp(0, 255) as u8)
+            }
+            ColorSource::Curve(curve) => curve.color(i, total),
+            ColorSource::Noise(noise) => noise.color(i, total),
+            ColorSource::Inks(inks) => inks.pick(i),
+            ColorSource::Harmony(harmony) => harmony.next(rng),
+            ColorSource::Sequence(colors) => colors[i % colors.len()],
+        }
+    }
+
+    // For one-dimensional sources, the channel along which colors are ordered,
+    // so matching can compare that channel alone.
+    pub fn match_channel(&self) -> Option<usize> {
+        match self {
+            ColorSource::Grayscale => Some(0),
+            ColorSource::Duotone(a, b) => (0..3).max_by_key(|&ch| a[ch].abs_diff(b[ch])),
+            _ => None,
+        }
+    }
+}
+
+// t in [0, 1].
+pub fn sample_colormap(stops: &[Color], t: f64) -> Color {
+    if stops.len() == 1 {
+        return stops[0];
+    }
+    let pos = t.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+    let index = (pos.floor() as usize).min(stops.len() - 2);
+    lerp(stops[index], stops[index + 1], pos - index as f64)
+}
+
+// Mixes in linear light.
+pub fn lerp(a: Color, b: Color, t: f64) -> Color {
+    [0, 1, 2].map(|ch| {
+        let (a, b) = (to_linear(a[ch]), to_linear(b[ch]));
+        from_linear(a + (b - a) * t as f32)
+    })
+}
+
+// Adds Gaussian noise of standard deviation sigma to each channel, as
+// film grain.
+pub fn grain<R: Rng + ?Sized>(color: Color, sigma: f64, rng: &mut R) -> Color {
+    color.map(|c| {
+        // Box-Muller; 1 - u keeps the logarithm finite.
+        let (u, v): (f64, f64) = (rng.random(), rng.random());
+        let normal = (-2.0 * (1.0 - u).ln()).sqrt() * (std::f64::consts::TAU * v).cos();
+        (c as f64 + sigma * normal).round().clamp(0.0, 255.0) as u8
+    })
+}
+
+// Rotates the color's hue about the gray axis, by a fraction of a full turn.
+pub fn rotate_hue(color: Color, turns: f64) -> Color {
+    let (sin, cos) = (turns * std::f64::consts::TAU).sin_cos();
+    let third = (1.0 - cos) / 3.0;
+    let root = (1.0f64 / 3.0).sqrt() * sin;
+    let [r, g, b] = color.map(|c| c as f64);
+    let mix = |a: f64, b: f64, c: f64| (cos + third) * a + (third - root) * b + (third + root) * c;
+    [mix(r, g, b), mix(g, b, r), mix(b, r, g)].map(|v| v.round().clamp(0.0, 255.0) as u8)
+}
+
+// Hue in degrees, saturation and value in 0..=1.
+pub fn hsv_to_rgb(hue: f64, saturation: f64, value: f64) -> Color {
+    let c = value * saturation;
+    let h = hue.rem_euclid(360.0) / 60.0;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as usize {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = value - c;
+    [r, g, b].map(|v| ((v + m) * 255.0).round().clamp(0.0, 255.0) as u8)
+}
+
+// The hue in degrees, as hsv_to_rgb takes it, and the chroma in 0..=1; the
+// hue of a gray is 0.
+pub fn hue_chroma(color: Color) -> (f64, f64) {
+    let [r, g, b] = color.map(|c| c as f64 / 255.0);
+    let max = r.max(g).max(b);
+    let chroma = max - r.min(g).min(b);
+    if chroma == 0.0 {
+        return (0.0, 0.0);
+    }
+    let h = if max == r {
+        ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / chroma + 2.0
+    } else {
+        (r - g) / chroma + 4.0
+    };
+    (h * 60.0, chroma)
+}
+
+// n of the candidates, each the farthest from those picked before it,
+// starting from the first.
+pub fn spread_colors(candidates: &[Color], n: usize) -> Vec<Color> {
+    let Some(&first) = candidates.first() else {
+        return vec![];
+    };
+    let mut picked = vec![first];
+    let mut nearest: Vec<i64> = candidates
+        .iter()
+        .map(|&c| crate::color_dist_sq(c, first))
+        .collect();
+    while picked.len() < n {
+        let (k, _) = nearest
+            .iter()
+            .enumerate()
+            .max_by_key(|&(k, &d)| (d, std::cmp::Reverse(k)))
+            .expect("candidates");
+        let color = candidates[k];
+        picked.push(color);
+        for (d, &c) in nearest.iter_mut().zip(candidates) {
+            *d = (*d).min(crate::color_dist_sq(c, color));
+        }
+    }
+    picked
+}
+
+// Parses `rrggbb` or `#rrggbb`.
+pub fn parse_color(s: &str) -> Result<Color, String> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    let channel = |i: usize| {
+        hex.get(i..i + 2)
+            .and_then(|h| u8::from_str_radix(h, 16).ok())
+            .ok_or(format!("invalid color {s}, expected rrggbb"))
+    };
+    if hex.len() != 6 {
+        return Err(format!("invalid color {s}, expected rrggbb"));
+    }
+    Ok([channel(0)?, channel(2)?, channel(4)?])
+}
@@ -0,0 +1,122 @@
+use image::imageops::{FilterType, grayscale, resize};
+use image::{DynamicImage, RgbImage};
+
+use crate::color::sample_colormap;
+use crate::fit::ssim;
+use crate::palette::MAGMA;
+use crate::{Color, color_dist_sq};
+
+// Statistics are taken at this size, so images of any size compare.
+const COMPARE: u32 = 64;
+
+// A 64-bit difference hash: for each of 8 rows of a 9x8 grayscale thumbnail,
+// whether each pixel is brighter than the next. Similar images have hashes
+// a small Hamming distance apart.
+pub fn dhash(img: &RgbImage) -> u64 {
+    let small = resize(&grayscale(img), 9, 8, FilterType::Triangle);
+    let mut hash = 0;
+    for y in 0..8 {
+        for x in 0..8 {
+            let brighter = small.get_pixel(x, y).0[0] > small.get_pixel(x + 1, y).0[0];
+            hash = hash << 1 | brighter as u64;
+        }
+    }
+    hash
+}
+
+pub struct Difference {
+    // Hamming distance between the difference hashes, from 0 to 64.
+    pub hash_distance: u32,
+    // Mean absolute channel difference, from 0 to 255.
+    pub mean_abs: f64,
+    pub ssim: f64,
+}
+
+pub fn difference(a: &RgbImage, b: &RgbImage) -> Difference {
+    let small_a = resize(a, COMPARE, COMPARE, FilterType::Triangle);
+    let small_b = resize(b, COMPARE, COMPARE, FilterType::Triangle);
+    let total: u64 = small_a
+        .as_raw()
+        .iter()
+        .zip(small_b.as_raw())
+        .map(|(&x, &y)| x.abs_diff(y) as u64)
+        .sum();
+    Difference {
+        hash_distance: (dhash(a) ^ dhash(b)).count_ones(),
+        mean_abs: total as f64 / small_a.as_raw().len() as f64,
+        ssim: ssim(&small_a, &small_b),
+    }
+}
+
+pub fn load(path: &str) -> Result<RgbImage, String> {
+    image::open(path)
+        .map(DynamicImage::into_rgb8)
+        .map_err(|e| format!("could not open {path}: {e}"))
+}
+
+// For each image in order, the earlier kept image whose hash is within
+// threshold of its own and nearest, if any. Images without one are kept.
+pub fn duplicates(hashes: &[u64], threshold: u32) -> Vec<Option<(usize, u32)>> {
+    let mut kept: Vec<usize> = vec![];
+    hashes
+        .iter()
+        .enumerate()
+        .map(|(i, &hash)| {
+            let nearest = kept
+                .iter()
+                .map(|&k| (k, (hashes[k] ^ hash).count_ones()))
+                .filter(|&(_, distance)| distance <= threshold)
+                .min_by_key(|&(_, distance)| distance);
+            if nearest.is_none() {
+                kept.push(i);
+            }
+            nearest
+        })
+        .collect()
+}
+
+// Distance between the colors at each pixel, from 0 for equal colors to 1
+// for black against white, row by row.
+pub fn pixel_distances(a: &RgbImage, b: &RgbImage) -> Vec<f64> {
+    let farthest = (color_dist_sq([0; 3], [255; 3]) as f64).sqrt();
+    a.pixels()
+        .zip(b.pixels())
+        .map(|(x, y)| (color_dist_sq(x.0, y.0) as f64).sqrt() / farthest)
+        .collect()
+}
+
+// How two renders differ: a heatmap of the distance at each pixel, dark
+// where they agree, or the two overlaid as ghosts, where agreeing pixels
+// show at full strength and disagreeing ones fade out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffStyle {
+    Heat,
+    Ghost,
+}
+
+impl std::str::FromStr for DiffStyle {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "heat" => Ok(DiffStyle::Heat),
+            "ghost" => Ok(DiffStyle::Ghost),
+            _ => Err(format!("unknown diff style {s}")),
+        }
+    }
+}
+
+pub fn diff_image(a: &RgbImage, b: &RgbImage, style: DiffStyle) -> RgbImage {
+    let distances = pixel_distances(a, b);
+    let width = a.width() as usize;
+    RgbImage::from_fn(a.width(), a.height(), |x, y| {
+        let d = distances[y as usize * width + x as usize];
+        image::Rgb(match style {
+            DiffStyle::Heat => sample_colormap(MAGMA, d),
+            DiffStyle::Ghost => {
+                let (p, q) = (a.get_pixel(x, y).0, b.get_pixel(x, y).0);
+                let mean: Color = [0, 1, 2].map(|c| ((p[c] as u16 + q[c] as u16) / 2) as u8);
+                mean.map(|c| (c as f64 * (1.0 - d)).round() as u8)
+            }
+        })
+    })
+}
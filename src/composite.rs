@@ -0,0 +1,109 @@
+use image::{RgbImage, RgbaImage};
+
+use std::str::FromStr;
+
+use crate::canvas::with_alpha;
+use crate::linear::{from_linear, to_linear};
+use crate::params::Params;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Blend {
+    // The layer over what is below, by its alpha.
+    #[default]
+    Normal,
+    Multiply,
+    Screen,
+    Lighten,
+}
+
+impl FromStr for Blend {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "normal" => Ok(Blend::Normal),
+            "multiply" => Ok(Blend::Multiply),
+            "screen" => Ok(Blend::Screen),
+            "lighten" => Ok(Blend::Lighten),
+            _ => Err(format!("unknown blend mode {s}")),
+        }
+    }
+}
+
+impl Blend {
+    fn apply(self, below: f32, above: f32) -> f32 {
+        match self {
+            Blend::Normal => above,
+            Blend::Multiply => below * above,
+            Blend::Screen => 1.0 - (1.0 - below) * (1.0 - above),
+            Blend::Lighten => below.max(above),
+        }
+    }
+}
+
+// An extra pass generated with its own parameters and blended onto the image.
+// Written as `BLEND[@OPACITY] FLAGS...`, e.g. `screen@0.6 --seed 4 --centers 3`.
+#[derive(Debug, Clone)]
+pub struct Layer {
+    pub params: Params,
+    pub blend: Blend,
+    pub opacity: f32,
+}
+
+impl FromStr for Layer {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        let mut words = s.split_whitespace();
+        let mode = words.next().ok_or("empty layer")?;
+        let (blend, opacity) = match mode.split_once('@') {
+            Some((blend, opacity)) => (
+                blend.parse()?,
+                opacity
+                    .parse()
+                    .map_err(|_| format!("invalid layer opacity {opacity}"))?,
+            ),
+            None => (mode.parse()?, 1.0),
+        };
+        let params = Params::from_args(words.map(String::from))?;
+        if !params.layers.is_empty() {
+            return Err("layers cannot have layers of their own".to_string());
+        }
+        Ok(Self {
+            params,
+            blend,
+            opacity,
+        })
+    }
+}
+
+// Blends above onto below in place, weighting by above's alpha and opacity.
+pub fn blend(below: &mut RgbaImage, above: &RgbaImage, mode: Blend, opacity: f32) {
+    for (b, a) in below.pixels_mut().zip(above.pixels()) {
+        let weight = a.0[3] as f32 / 255.0 * opacity.clamp(0.0, 1.0);
+        for ch in 0..3 {
+            let lo = to_linear(b.0[ch]);
+            let hi = to_linear(a.0[ch]);
+            b.0[ch] = from_linear(lo + (mode.apply(lo, hi) - lo) * weight);
+        }
+        let alpha = b.0[3] as f32 / 255.0;
+        b.0[3] = ((alpha + weight * (1.0 - alpha)) * 255.0).round() as u8;
+    }
+}
+
+// Generates each layer at the base size and blends it onto the base image.
+pub fn composite(base: &RgbImage, filled: &[Vec<bool>], params: &Params) -> RgbaImage {
+    let mut out = with_alpha(base, filled);
+    for layer in &params.layers {
+        let layer_params = Params {
+            size: params.size,
+            ..layer.params.clone()
+        };
+        let run = crate::generate(&layer_params, false);
+        blend(
+            &mut out,
+            &with_alpha(&run.image, &run.filled),
+            layer.blend,
+            layer.opacity,
+        );
+    }
+    out
+}
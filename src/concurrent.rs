@@ -0,0 +1,91 @@
+use rand::prelude::*;
+
+use std::thread;
+
+use image::{Rgb, RgbImage};
+
+use crate::canvas::Canvas;
+use crate::params::Params;
+use crate::rng::SeededRng;
+use crate::topology::{Topology, grow};
+use crate::walk::{WalkSettings, walk};
+use crate::{Location, Run};
+
+// The usual flat square canvas, as a topology for walkers.
+struct Flat<'a> {
+    params: &'a Params,
+}
+
+impl Topology for Flat<'_> {
+    type Center = Location;
+
+    fn side(&self) -> usize {
+        self.params.size
+    }
+
+    fn slots(&self) -> usize {
+        self.params.size * self.params.size
+    }
+
+    fn slot(&self, [x, y]: Location) -> usize {
+        y * self.params.size + x
+    }
+
+    fn location(&self, slot: usize) -> Location {
+        let size = self.params.size;
+        [slot % size, slot / size]
+    }
+
+    fn start_center(&self, loc: Location, width: f64, rng: &mut SeededRng) -> Location {
+        Canvas::Square.layout(loc, width as usize, self.params.size, rng)
+    }
+
+    fn jitter(&self, center: Location, width: f64, rng: &mut SeededRng) -> Location {
+        let (size, width) = (self.params.size, width as usize);
+        center.map(|c| rng.random_range(c.saturating_sub(width)..=(c + width).min(size)))
+    }
+
+    fn walk(
+        &self,
+        center: Location,
+        start: Location,
+        is_open: impl Fn(Location) -> bool,
+    ) -> Option<Location> {
+        let params = self.params;
+        let orbit = params
+            .orbit
+            .orbit(center, start, 1.0, 0.0, 0.0, params.spiral);
+        let settings = WalkSettings {
+            neighborhood: params.neighborhood,
+            gap_jump: params.gap_jump,
+            reflect: params.reflect,
+            wrap: false,
+        };
+        walk(orbit.as_ref(), start, params.size, &settings, is_open)
+    }
+}
+
+// An experiment in placing pixels in parallel: the usual flat canvas, shared
+// by walkers each running the process on its own colors, as topology::grow
+// describes, on as many threads as the machine has cores. The result is
+// deterministic for a seed and a number of walkers, and unlike a single run
+// for any other number.
+pub fn generate_concurrent(params: &Params, walkers: usize) -> Run {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    generate_concurrent_on(params, walkers, threads)
+}
+
+// The same on a given number of threads, which changes only the time taken.
+pub fn generate_concurrent_on(params: &Params, walkers: usize, threads: usize) -> Run {
+    let size = params.size;
+    let colors = grow(params, &Flat { params }, walkers, threads);
+    let mut image = RgbImage::new(size as u32, size as u32);
+    for (i, &color) in colors.iter().enumerate() {
+        image.put_pixel((i % size) as u32, (i / size) as u32, Rgb(color));
+    }
+    Run {
+        image,
+        filled: vec![vec![true; size]; size],
+        placements: vec![],
+    }
+}
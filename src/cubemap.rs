@@ -0,0 +1,267 @@
+use image::{Rgb, RgbImage};
+use rand::prelude::*;
+
+use std::str::FromStr;
+
+#[cfg(feature = "files")]
+use crate::output::save_png;
+use crate::params::Params;
+use crate::rng::SeededRng;
+use crate::topology::{Topology, grow};
+use crate::voxel::Voxel;
+use crate::{Color, Location};
+
+// How a cube map is written out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    // Six images, named after their faces: _px, _nx, _py, _ny, _pz and _nz.
+    Faces,
+    // One image four faces wide and three tall, the faces folding up into the
+    // cube around the front.
+    Cross,
+}
+
+impl FromStr for Layout {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "faces" => Ok(Layout::Faces),
+            "cross" => Ok(Layout::Cross),
+            _ => Err(format!(
+                "unknown cube map layout {s}, expected faces or cross"
+            )),
+        }
+    }
+}
+
+// The faces in the order they're stored, as a viewer at the middle of the
+// cube sees them: x runs right, y down and z ahead, and the front is +z.
+pub const FACES: [&str; 6] = ["px", "nx", "py", "ny", "pz", "nz"];
+
+// Where each face goes in the cross, in faces from the top left.
+const CROSS: [[u32; 2]; 6] = [[2, 1], [0, 1], [1, 0], [1, 2], [1, 1], [3, 1]];
+
+// The voxel shown at (u, v) of face, on a cube of side n.
+fn voxel(face: usize, [u, v]: [usize; 2], n: usize) -> Voxel {
+    let m = n - 1;
+    match face {
+        0 => [m, v, m - u],
+        1 => [0, v, u],
+        // Up is y = 0, since y runs down.
+        2 => [u, 0, v],
+        3 => [u, m, m - v],
+        4 => [u, v, m],
+        _ => [m - u, v, 0],
+    }
+}
+
+// The first face showing a surface voxel, and where on it.
+fn face_of(loc: Voxel, n: usize) -> (usize, [usize; 2]) {
+    (0..6)
+        .find_map(|face| {
+            let (on, at) = face_of_on(face, loc, n);
+            on.then_some((face, at))
+        })
+        .expect("on the surface")
+}
+
+fn on_surface(loc: Voxel, n: usize) -> bool {
+    loc.iter().any(|&c| c == 0 || c == n - 1)
+}
+
+// The six faces of a cube map, each size pixels square. Voxels on the edges
+// and corners show on every face they border, so the faces agree there.
+pub struct CubeMap {
+    pub size: usize,
+    pub faces: [RgbImage; 6],
+}
+
+impl CubeMap {
+    fn set(&mut self, loc: Voxel, color: Color) {
+        let n = self.size;
+        for (face, image) in self.faces.iter_mut().enumerate() {
+            let (f, [u, v]) = face_of_on(face, loc, n);
+            if f {
+                image.put_pixel(u as u32, v as u32, Rgb(color));
+            }
+        }
+    }
+
+    pub fn cross(&self) -> RgbImage {
+        let n = self.size as u32;
+        let mut out = RgbImage::new(4 * n, 3 * n);
+        for (face, [col, row]) in self.faces.iter().zip(CROSS) {
+            image::imageops::replace(&mut out, face, (col * n) as i64, (row * n) as i64);
+        }
+        out
+    }
+
+    // The faces as path's stem with _px and so on, or the cross at path.
+    #[cfg(feature = "files")]
+    pub fn save(
+        &self,
+        layout: Layout,
+        path: &str,
+        metadata: &[(String, String)],
+    ) -> Result<Vec<String>, String> {
+        match layout {
+            Layout::Cross => {
+                save_png(&self.cross(), path, metadata).map(|_| vec![path.to_string()])
+            }
+            Layout::Faces => {
+                let (stem, ext) = path.rsplit_once('.').unwrap_or((path, "png"));
+                let mut paths = vec![];
+                for (face, name) in self.faces.iter().zip(FACES) {
+                    let path = format!("{stem}_{name}.{ext}");
+                    save_png(face, &path, metadata)?;
+                    paths.push(path);
+                }
+                Ok(paths)
+            }
+        }
+    }
+}
+
+// Whether loc shows on face, and where.
+fn face_of_on(face: usize, loc: Voxel, n: usize) -> (bool, [usize; 2]) {
+    let m = n - 1;
+    let [x, y, z] = loc;
+    match face {
+        0 => (x == m, [m - z, y]),
+        1 => (x == 0, [z, y]),
+        2 => (y == 0, [x, z]),
+        3 => (y == m, [x, m - z]),
+        4 => (z == m, [x, y]),
+        _ => (z == 0, [m - x, y]),
+    }
+}
+
+// The surface of a cube of voxels, its cells laid out face by face: a voxel
+// is the cell [u, face * n + v] of the first face it shows on. Centers may lie
+// anywhere in the cube.
+struct Cube {
+    n: usize,
+}
+
+impl Cube {
+    fn voxel(&self, [u, fv]: Location) -> Voxel {
+        voxel(fv / self.n, [u, fv % self.n], self.n)
+    }
+
+    fn cell(&self, loc: Voxel) -> Location {
+        let (face, [u, v]) = face_of(loc, self.n);
+        [u, face * self.n + v]
+    }
+
+    fn near(&self, c: usize, width: f64, rng: &mut SeededRng) -> usize {
+        let width = width as usize;
+        rng.random_range(c.saturating_sub(width)..=(c + width).min(self.n - 1))
+    }
+}
+
+impl Topology for Cube {
+    type Center = Voxel;
+
+    fn side(&self) -> usize {
+        self.n
+    }
+
+    fn slots(&self) -> usize {
+        6 * self.n * self.n
+    }
+
+    fn slot(&self, [u, fv]: Location) -> usize {
+        fv * self.n + u
+    }
+
+    fn location(&self, slot: usize) -> Location {
+        [slot % self.n, slot / self.n]
+    }
+
+    // Voxels on an edge or corner have a slot on every face they show on,
+    // and a cell only in the first.
+    fn is_cell(&self, slot: usize) -> bool {
+        let loc = self.location(slot);
+        self.cell(self.voxel(loc)) == loc
+    }
+
+    fn start_center(&self, loc: Location, width: f64, rng: &mut SeededRng) -> Voxel {
+        self.voxel(loc).map(|c| self.near(c, width, rng))
+    }
+
+    fn jitter(&self, center: Voxel, width: f64, rng: &mut SeededRng) -> Voxel {
+        center.map(|c| self.near(c, width, rng))
+    }
+
+    fn walk(
+        &self,
+        center: Voxel,
+        start: Location,
+        is_open: impl Fn(Location) -> bool,
+    ) -> Option<Location> {
+        let found = walk(center, self.voxel(start), self.n, |loc| {
+            is_open(self.cell(loc))
+        })?;
+        Some(self.cell(found))
+    }
+}
+
+// The original process on the surface of a cube of voxels. Centers may lie
+// anywhere in the cube; each walk keeps to the surface, along where it meets
+// the sphere about the center through the walk's start, so orbits are circles
+// on a face and bend over the edges onto the next. The placing itself is
+// topology::grow's, with a single walker.
+pub fn generate_cubemap(params: &Params) -> CubeMap {
+    let n = params.size;
+    let cube = Cube { n };
+    let colors = grow(params, &cube, 1, 1);
+    let mut map = CubeMap {
+        size: n,
+        faces: std::array::from_fn(|_| RgbImage::new(n as u32, n as u32)),
+    };
+    for (slot, &color) in colors.iter().enumerate() {
+        if cube.is_cell(slot) {
+            map.set(cube.voxel(cube.location(slot)), color);
+        }
+    }
+    map
+}
+
+// Steps over the surface from start, keeping as near as it can to the sphere
+// about center through start, until an open voxel is found. Gives up on
+// returning to start or running too long.
+fn walk(center: Voxel, start: Voxel, n: usize, is_open: impl Fn(Voxel) -> bool) -> Option<Voxel> {
+    let center = center.map(|c| c as f64);
+    let dist = |p: [isize; 3]| {
+        let d = [0, 1, 2].map(|k| p[k] as f64 - center[k]);
+        (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt()
+    };
+    let start = start.map(|c| c as isize);
+    let radius = dist(start);
+    let inside = |p: &[isize; 3]| p.iter().all(|&c| c >= 0 && c < n as isize);
+    let mut last = start;
+    let mut cur = start;
+    for _ in 0..(8.0 * radius).ceil() as usize + 8 {
+        let next = (-1..=1)
+            .flat_map(|x| (-1..=1).flat_map(move |y| (-1..=1).map(move |z| [x, y, z])))
+            .filter(|d| *d != [0, 0, 0])
+            .map(|d: [isize; 3]| [cur[0] + d[0], cur[1] + d[1], cur[2] + d[2]])
+            .filter(|p| *p != last && inside(p))
+            .filter(|p| on_surface(p.map(|c| c as usize), n))
+            .min_by(|&a, &b| {
+                (dist(a) - radius)
+                    .abs()
+                    .total_cmp(&(dist(b) - radius).abs())
+            })?;
+        if next == start {
+            return None;
+        }
+        let loc = next.map(|c| c as usize);
+        if is_open(loc) {
+            return Some(loc);
+        }
+        last = cur;
+        cur = next;
+    }
+    None
+}
@@ -0,0 +1,155 @@
+use std::str::FromStr;
+
+use crate::Color;
+use crate::linear::from_linear;
+
+// Bits per axis: the curve visits every 8-bit color once.
+const BITS: u32 = 8;
+const LEN: u64 = 1 << (3 * BITS);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurveKind {
+    // Each step moves one unit along one axis.
+    Hilbert,
+    // Bit-interleaved Z order: cheaper, but with occasional long jumps.
+    Morton,
+}
+
+// The cube the curve fills, with its three axes scaled to 0..=255.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurveSpace {
+    Rgb,
+    // Lightness, chroma and hue; colors outside sRGB are clipped.
+    Lch,
+}
+
+// Colors in order along a space-filling curve through a color cube, so
+// consecutive colors are always close. The walk turns back at either end
+// of the curve. Written as `hilbert`, `morton`, `hilbert:lch` and so on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Curve {
+    pub kind: CurveKind,
+    pub space: CurveSpace,
+    // Curve cells advanced per color; by default the run covers the whole
+    // curve once.
+    pub step: Option<u64>,
+    // Where the walk starts, as a fraction of the curve.
+    pub offset: f64,
+}
+
+impl FromStr for Curve {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        let (kind, space) = s.split_once(':').unwrap_or((s, "rgb"));
+        let kind = match kind {
+            "hilbert" => CurveKind::Hilbert,
+            "morton" => CurveKind::Morton,
+            _ => return Err(format!("unknown curve {kind}, expected hilbert or morton")),
+        };
+        let space = match space {
+            "rgb" => CurveSpace::Rgb,
+            "lch" => CurveSpace::Lch,
+            _ => return Err(format!("unknown curve space {space}, expected rgb or lch")),
+        };
+        Ok(Curve {
+            kind,
+            space,
+            step: None,
+            offset: 0.0,
+        })
+    }
+}
+
+impl Curve {
+    // The color for placement i of total.
+    pub fn color(&self, i: usize, total: usize) -> Color {
+        let step = self
+            .step
+            .unwrap_or_else(|| (LEN / total.max(1) as u64).max(1));
+        let start = (self.offset * LEN as f64) as u64;
+        // The walk repeats every 2 * LEN cells, so reducing first keeps any
+        // step from overflowing.
+        let period = 2 * LEN;
+        let pos = (start + (i as u64 % period) * (step % period)) % period;
+        let index = if pos < LEN { pos } else { 2 * LEN - 1 - pos };
+        let point = match self.kind {
+            CurveKind::Hilbert => hilbert_point(index),
+            CurveKind::Morton => morton_point(index),
+        };
+        match self.space {
+            CurveSpace::Rgb => point.map(|c| c as u8),
+            CurveSpace::Lch => lch_to_rgb(
+                point[0] as f64 / 255.0 * 100.0,
+                point[1] as f64 / 255.0 * 100.0,
+                point[2] as f64 / 256.0 * 360.0,
+            ),
+        }
+    }
+}
+
+// Skilling's transpose-to-axes method: spread the index's bits over the
+// axes, then undo the Gray code and the rotations of each level.
+pub fn hilbert_point(index: u64) -> [u32; 3] {
+    let mut x = [0u32; 3];
+    for b in 0..BITS {
+        for (k, axis) in x.iter_mut().enumerate() {
+            let bit = (index >> (3 * b + 2 - k as u32)) & 1;
+            *axis |= (bit as u32) << b;
+        }
+    }
+    let t = x[2] >> 1;
+    for k in (1..3).rev() {
+        x[k] ^= x[k - 1];
+    }
+    x[0] ^= t;
+    let mut q = 2;
+    while q != 1 << BITS {
+        let p = q - 1;
+        for k in (0..3).rev() {
+            if x[k] & q != 0 {
+                x[0] ^= p;
+            } else {
+                let t = (x[0] ^ x[k]) & p;
+                x[0] ^= t;
+                x[k] ^= t;
+            }
+        }
+        q <<= 1;
+    }
+    x
+}
+
+pub fn morton_point(index: u64) -> [u32; 3] {
+    let mut x = [0u32; 3];
+    for b in 0..BITS {
+        for (k, axis) in x.iter_mut().enumerate() {
+            *axis |= (((index >> (3 * b + 2 - k as u32)) & 1) as u32) << b;
+        }
+    }
+    x
+}
+
+// CIE LCh(ab) under D65 to sRGB, clipping each channel.
+fn lch_to_rgb(l: f64, chroma: f64, hue: f64) -> Color {
+    let (a, b) = (
+        chroma * hue.to_radians().cos(),
+        chroma * hue.to_radians().sin(),
+    );
+    let fy = (l + 16.0) / 116.0;
+    let f_inv = |f: f64| {
+        if f > 6.0 / 29.0 {
+            f * f * f
+        } else {
+            3.0 * (6.0f64 / 29.0).powi(2) * (f - 4.0 / 29.0)
+        }
+    };
+    let x = 0.95047 * f_inv(fy + a / 500.0);
+    let y = f_inv(fy);
+    let z = 1.08883 * f_inv(fy - b / 200.0);
+    [
+        3.2406 * x - 1.5372 * y - 0.4986 * z,
+        -0.9689 * x + 1.8758 * y + 0.0415 * z,
+        0.0557 * x - 0.2040 * y + 1.0570 * z,
+    ]
+    .map(|v| from_linear(v as f32))
+}
@@ -0,0 +1,108 @@
+use image::{Rgb, RgbImage};
+
+use std::str::FromStr;
+
+use crate::Color;
+use crate::linear::{color_from_linear, color_to_linear};
+
+// The color vision deficiencies --simulate-cvd can show an image under, each
+// the complete loss of one kind of cone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Deficiency {
+    // No long-wavelength cones: reds darken and merge with greens.
+    Protanopia,
+    // No medium-wavelength cones: reds and greens merge.
+    Deuteranopia,
+    // No short-wavelength cones: blues merge with greens, yellows with pinks.
+    Tritanopia,
+}
+
+pub const DEFICIENCIES: [Deficiency; 3] = [
+    Deficiency::Protanopia,
+    Deficiency::Deuteranopia,
+    Deficiency::Tritanopia,
+];
+
+impl FromStr for Deficiency {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "protanopia" | "protan" => Ok(Deficiency::Protanopia),
+            "deuteranopia" | "deutan" => Ok(Deficiency::Deuteranopia),
+            "tritanopia" | "tritan" => Ok(Deficiency::Tritanopia),
+            _ => Err(format!(
+                "unknown color vision deficiency {s}, expected protanopia, deuteranopia or \
+                 tritanopia"
+            )),
+        }
+    }
+}
+
+impl Deficiency {
+    pub fn name(self) -> &'static str {
+        match self {
+            Deficiency::Protanopia => "protanopia",
+            Deficiency::Deuteranopia => "deuteranopia",
+            Deficiency::Tritanopia => "tritanopia",
+        }
+    }
+
+    // Machado, Oliveira and Fernandes (2009) at full severity, on linear
+    // sRGB. Each row sums to 1, so grays are unchanged.
+    fn matrix(self) -> [[f32; 3]; 3] {
+        match self {
+            Deficiency::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            Deficiency::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            Deficiency::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        }
+    }
+
+    // How color looks to someone with this deficiency.
+    pub fn simulate(self, color: Color) -> Color {
+        let linear = color_to_linear(color);
+        let matrix = self.matrix();
+        color_from_linear(matrix.map(|row| (0..3).map(|k| row[k] * linear[k]).sum()))
+    }
+
+    pub fn simulate_image(self, image: &RgbImage) -> RgbImage {
+        let mut out = image.clone();
+        for pixel in out.pixels_mut() {
+            *pixel = Rgb(self.simulate(pixel.0));
+        }
+        out
+    }
+}
+
+// "all", or a comma separated list of deficiencies.
+pub fn parse_deficiencies(s: &str) -> Result<Vec<Deficiency>, String> {
+    if s == "all" {
+        return Ok(DEFICIENCIES.to_vec());
+    }
+    let mut list: Vec<Deficiency> = vec![];
+    for part in s.split(',') {
+        let deficiency = part.trim().parse()?;
+        if !list.contains(&deficiency) {
+            list.push(deficiency);
+        }
+    }
+    Ok(list)
+}
+
+// The companion of the image at path showing it under deficiency, beside it
+// with the deficiency's name appended to the stem.
+pub fn companion_path(path: &str, deficiency: Deficiency) -> String {
+    let (stem, ext) = path.rsplit_once('.').unwrap_or((path, "png"));
+    format!("{stem}-{}.{ext}", deficiency.name())
+}
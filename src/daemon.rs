@@ -0,0 +1,162 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+// Images the daemon writes are named PREFIX-SECONDS-SEED.png, so sorting
+// their names sorts them by age.
+pub const PREFIX: &str = "wallpaper";
+
+// A duration such as 90, 90s, 30m, 1h or 1.5d; a bare number is seconds.
+pub fn parse_interval(value: &str) -> Result<Duration, String> {
+    let split = value
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let unit = match unit {
+        "" | "s" => 1.0,
+        "m" => 60.0,
+        "h" => 3600.0,
+        "d" => 86400.0,
+        _ => {
+            return Err(format!(
+                "--interval takes a unit of s, m, h or d, got {value}"
+            ));
+        }
+    };
+    number
+        .parse::<f64>()
+        .ok()
+        .map(|n| n * unit)
+        .filter(|s| *s > 0.0)
+        .and_then(|s| Duration::try_from_secs_f64(s).ok())
+        .ok_or(format!("invalid value for --interval: {value}"))
+}
+
+pub fn wallpaper_name(seconds: u64, seed: u64) -> String {
+    format!("{PREFIX}-{seconds:012}-{seed}.png")
+}
+
+// The daemon's images in dir, oldest first.
+fn wallpapers(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries =
+        std::fs::read_dir(dir).map_err(|e| format!("could not read {}: {e}", dir.display()))?;
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(&format!("{PREFIX}-")) && n.ends_with(".png"))
+        })
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+// Deletes all but the newest keep of the daemon's images in dir, returning
+// those deleted. Other files are left alone.
+pub fn prune(dir: &Path, keep: usize) -> Result<Vec<PathBuf>, String> {
+    let mut paths = wallpapers(dir)?;
+    let old = paths.len().saturating_sub(keep);
+    let removed: Vec<PathBuf> = paths.drain(..old).collect();
+    for path in &removed {
+        std::fs::remove_file(path)
+            .map_err(|e| format!("could not delete {}: {e}", path.display()))?;
+    }
+    Ok(removed)
+}
+
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// The first WxH, as in 2560x1440 or 2560 x 1440, in text.
+fn find_resolution(text: &str) -> Option<[usize; 2]> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    words.iter().enumerate().find_map(|(i, word)| {
+        let dims = match word.split_once('x') {
+            Some((w, h)) if !h.is_empty() => [w, h],
+            _ if words.get(i + 1) == Some(&"x") => [*word, *words.get(i + 2)?],
+            _ => return None,
+        };
+        let [w, h] = dims.map(|d| d.trim_end_matches(',').parse().ok());
+        Some([w?, h?])
+    })
+}
+
+// The main screen's size in pixels, where the platform says.
+pub fn screen_resolution() -> Option<[usize; 2]> {
+    if cfg!(target_os = "macos") {
+        let text = output("system_profiler", &["SPDisplaysDataType"])?;
+        let line = text
+            .lines()
+            .find(|l| l.trim_start().starts_with("Resolution:"))?;
+        find_resolution(line)
+    } else if cfg!(target_os = "windows") {
+        let text = output(
+            "powershell",
+            &[
+                "-NoProfile",
+                "-Command",
+                "Add-Type -AssemblyName System.Windows.Forms; \
+                 $b = [System.Windows.Forms.Screen]::PrimaryScreen.Bounds; \
+                 \"$($b.Width)x$($b.Height)\"",
+            ],
+        )?;
+        find_resolution(&text)
+    } else {
+        let text = output("xrandr", &["--current"])?;
+        let line = text.lines().find(|l| l.contains(" current "))?;
+        find_resolution(line.split(" current ").nth(1)?)
+    }
+}
+
+// Makes the image at path the desktop wallpaper: through System Events on
+// macOS, SystemParametersInfo on Windows, and elsewhere GNOME's settings,
+// falling back to feh.
+pub fn set_wallpaper(path: &Path) -> Result<(), String> {
+    let path = std::fs::canonicalize(path)
+        .map_err(|e| format!("could not find {}: {e}", path.display()))?;
+    let path = path.to_string_lossy();
+    let ok = if cfg!(target_os = "macos") {
+        let script = format!(
+            "tell application \"System Events\" to tell every desktop to set picture to \"{path}\""
+        );
+        output("osascript", &["-e", &script]).is_some()
+    } else if cfg!(target_os = "windows") {
+        let script = format!(
+            "Add-Type -TypeDefinition 'using System.Runtime.InteropServices; public class W {{ \
+             [DllImport(\"user32.dll\")] public static extern int SystemParametersInfo(int a, int b, string c, int d); }}'; \
+             [W]::SystemParametersInfo(20, 0, '{path}', 3)"
+        );
+        output("powershell", &["-NoProfile", "-Command", &script]).is_some()
+    } else {
+        let uri = format!("file://{path}");
+        let gnome = output(
+            "gsettings",
+            &["set", "org.gnome.desktop.background", "picture-uri", &uri],
+        )
+        .is_some();
+        if gnome {
+            // Only newer GNOME has a separate dark-mode wallpaper.
+            output(
+                "gsettings",
+                &[
+                    "set",
+                    "org.gnome.desktop.background",
+                    "picture-uri-dark",
+                    &uri,
+                ],
+            );
+        }
+        gnome || output("feh", &["--bg-fill", &path]).is_some()
+    };
+    if ok {
+        Ok(())
+    } else {
+        Err(format!("could not set {path} as the wallpaper"))
+    }
+}
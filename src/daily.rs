@@ -0,0 +1,69 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::explore::random_args;
+use crate::rng::seed_from_bytes;
+
+// Days from 1970-01-01 to a date of the proleptic Gregorian calendar, and
+// back, after Howard Hinnant's civil calendar algorithms.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = month as i64;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+pub fn date_from_days(days: i64) -> String {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+// Today's date in UTC, so a bot posts the same piece wherever it runs.
+pub fn today() -> String {
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    date_from_days(seconds.div_euclid(86400))
+}
+
+// Checks a YYYY-MM-DD date names a real day.
+pub fn parse_date(s: &str) -> Result<String, String> {
+    let invalid = || format!("expected a date as YYYY-MM-DD, got {s}");
+    let parts: Vec<&str> = s.split('-').collect();
+    let [year, month, day] = parts[..] else {
+        return Err(invalid());
+    };
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return Err(invalid());
+    }
+    let year: i64 = year.parse().map_err(|_| invalid())?;
+    let month: u32 = month.parse().map_err(|_| invalid())?;
+    let day: u32 = day.parse().map_err(|_| invalid())?;
+    if !(1..=12).contains(&month) || day == 0 {
+        return Err(invalid());
+    }
+    // A day past the end of its month comes back as another date.
+    if date_from_days(days_from_civil(year, month, day)) != s {
+        return Err(format!("{s} is not a day of the calendar"));
+    }
+    Ok(s.to_string())
+}
+
+// The arguments of the day's piece in namespace: random parameters as for
+// explore, drawn from a generator seeded by the namespace and the date, so
+// every namespace gets its own canonical piece each day.
+pub fn daily_args(date: &str, namespace: &str) -> Vec<String> {
+    let seed = seed_from_bytes(format!("{namespace}\n{date}").as_bytes());
+    random_args(&mut ChaCha8Rng::seed_from_u64(seed))
+}
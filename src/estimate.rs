@@ -0,0 +1,267 @@
+use std::mem::size_of;
+use std::time::Instant;
+
+use crate::grid::Grid;
+use crate::openindex::OpenIndex;
+use crate::{Generator, Location, Params, Pixel, Placement};
+
+// Fewest placements worth timing, whatever the fraction.
+const MIN_SAMPLE: usize = 2000;
+// Side of the small run that measures how much slower placements get as the
+// canvas fills.
+const PROXY_SIZE: usize = 256;
+// Coverage bands of the small run, each with its own slowdown.
+const BANDS: usize = 20;
+// Small runs, with their own seeds, whose slowdowns are the median.
+const PROXY_RUNS: u64 = 3;
+
+// What a run is expected to cost, from a calibration run of its first
+// placements.
+#[derive(Debug, Clone)]
+pub struct Estimate {
+    pub sampled: usize,
+    pub target: usize,
+    // Building the generator: seeding, layouts, any multiscale pass.
+    pub setup_seconds: f64,
+    pub sample_seconds: f64,
+    // How much slower the rest of the run is for the canvas filling up,
+    // found from small runs to full coverage; None where one can't stand in.
+    pub crowding: Option<f64>,
+    // The whole run, setup included.
+    pub seconds: f64,
+    // The largest allocations, in bytes, at their peak.
+    pub memory: Vec<(&'static str, usize)>,
+    // Peak resident memory of the calibration itself, where the OS reports it.
+    pub measured_peak: Option<usize>,
+}
+
+impl Estimate {
+    pub fn peak_memory(&self) -> usize {
+        self.memory.iter().map(|&(_, bytes)| bytes).sum()
+    }
+
+    pub fn report(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "Timed {} of {} placements in {}, after {} of setup",
+            self.sampled,
+            self.target,
+            duration(self.sample_seconds),
+            duration(self.setup_seconds)
+        )];
+        lines.push(match self.crowding {
+            Some(factor) => format!("Slowdown as the canvas fills: {factor:.2}x"),
+            None => "Slowdown as the canvas fills not measured; expect a longer run".to_string(),
+        });
+        lines.push(format!("Estimated run time: {}", duration(self.seconds)));
+        lines.push(format!(
+            "Estimated peak memory: {}",
+            bytes(self.peak_memory())
+        ));
+        for &(name, size) in &self.memory {
+            lines.push(format!("  {name:<20}{}", bytes(size)));
+        }
+        if let Some(peak) = self.measured_peak {
+            lines.push(format!("Calibration peak memory: {}", bytes(peak)));
+        }
+        lines
+    }
+}
+
+// The rest of the run is taken to go at the rate of the last quarter of the
+// calibration, slowed in each coverage band as much as the same run slows
+// on a small canvas, relative to the same stretch of its own calibration.
+pub fn estimate(params: &Params, fraction: f64) -> Estimate {
+    let memory = memory(params);
+    // Timing on past the point the lookback fills, as the rate depends on
+    // how many pixels each placement searches.
+    let mut calibration = calibrate(params, fraction, MIN_SAMPLE.max(2 * params.num_lookback));
+    let measured_peak = peak_resident();
+    let (sampled, target) = (calibration.sampled, calibration.target);
+    // Calibrated over the same share of its placements.
+    let covered = sampled as f64 / target.max(1) as f64;
+    let slowdown = proxy(params).map(|mut proxy| {
+        let runs: Vec<Vec<f64>> = (0..PROXY_RUNS)
+            .map(|k| {
+                proxy.seed = params.seed.wrapping_add(k);
+                slowdown(&proxy, covered)
+            })
+            .collect();
+        (0..BANDS)
+            .map(|band| {
+                let mut factors: Vec<f64> = runs.iter().map(|run| run[band]).collect();
+                factors.sort_by(f64::total_cmp);
+                factors[factors.len() / 2]
+            })
+            .collect::<Vec<f64>>()
+    });
+    let mut plain = 0.0;
+    let mut crowded = 0.0;
+    for band in 0..BANDS {
+        let from = (target * band / BANDS).max(sampled);
+        let to = (target * (band + 1) / BANDS).max(sampled);
+        let seconds = (to - from) as f64 * calibration.rate;
+        plain += seconds;
+        crowded += seconds * slowdown.as_ref().map_or(1.0, |s| s[band]);
+    }
+    // Nothing left to time, so free the canvas now.
+    calibration.generator = None;
+    Estimate {
+        sampled,
+        target,
+        setup_seconds: calibration.setup_seconds,
+        sample_seconds: calibration.sample_seconds,
+        crowding: slowdown.map(|_| if plain > 0.0 { crowded / plain } else { 1.0 }),
+        seconds: calibration.setup_seconds + calibration.sample_seconds + crowded,
+        memory,
+        measured_peak,
+    }
+}
+
+// The same run on a small canvas, where nothing else depends on the size.
+fn proxy(params: &Params) -> Option<Params> {
+    if params.size <= PROXY_SIZE
+        || params.regions.is_some()
+        || params.stencil.is_some()
+        || params.density.is_some()
+        || params.base.is_some()
+    {
+        return None;
+    }
+    let scale = (PROXY_SIZE * PROXY_SIZE) as f64 / (params.size * params.size) as f64;
+    let shrink = |n: usize| ((n as f64 * scale).round() as usize).max(1);
+    let mut proxy = params.clone();
+    proxy.size = PROXY_SIZE;
+    proxy.num_centers = shrink(params.num_centers);
+    proxy.max_pixels = params.max_pixels.map(shrink);
+    proxy.max_seconds = None;
+    Some(proxy)
+}
+
+// Each coverage band's rate over the calibration rate, for a whole run.
+fn slowdown(params: &Params, fraction: f64) -> Vec<f64> {
+    let mut calibration = calibrate(params, fraction, 1);
+    let target = calibration.target;
+    let mut generator = calibration.generator.take().expect("still running");
+    (0..BANDS)
+        .map(|band| {
+            let from = generator.placed();
+            let start = Instant::now();
+            while generator.placed() < target * (band + 1) / BANDS && generator.step() {}
+            let count = generator.placed() - from;
+            if count == 0 || calibration.rate <= 0.0 {
+                return 1.0;
+            }
+            let rate = start.elapsed().as_secs_f64() / count as f64;
+            (rate / calibration.rate).max(1.0)
+        })
+        .collect()
+}
+
+struct Calibration {
+    // Left where the calibration stopped.
+    generator: Option<Generator>,
+    sampled: usize,
+    target: usize,
+    setup_seconds: f64,
+    sample_seconds: f64,
+    // Seconds per placement over the last quarter of the calibration.
+    rate: f64,
+}
+
+fn calibrate(params: &Params, fraction: f64, min_sample: usize) -> Calibration {
+    let start = Instant::now();
+    let mut generator = Generator::new(params, false);
+    let setup_seconds = start.elapsed().as_secs_f64();
+    let target = generator.target();
+    let sampled = ((target as f64 * fraction).ceil() as usize)
+        .max(min_sample)
+        .min(target);
+    let sample_start = Instant::now();
+    while generator.placed() < sampled * 3 / 4 && generator.step() {}
+    let tail_start = Instant::now();
+    let tail_from = generator.placed();
+    while generator.placed() < sampled && generator.step() {}
+    let tail = generator.placed() - tail_from;
+    Calibration {
+        sampled: generator.placed(),
+        target,
+        setup_seconds,
+        sample_seconds: sample_start.elapsed().as_secs_f64(),
+        rate: tail_start.elapsed().as_secs_f64() / tail.max(1) as f64,
+        generator: Some(generator),
+    }
+}
+
+// The largest allocations of a run of these parameters, at their peak.
+pub fn memory(params: &Params) -> Vec<(&'static str, usize)> {
+    let size = params.size;
+    let cells = size * size;
+    // Hash tables keep at least one eighth of their buckets free, each
+    // with a control byte.
+    let buckets = (cells * 8 / 7).next_power_of_two();
+    let mut memory = vec![
+        ("grid", Grid::bytes(size)),
+        (
+            "open locations",
+            cells * size_of::<Location>()
+                + buckets * (size_of::<Location>() + size_of::<usize>() + 1),
+        ),
+        ("open index", OpenIndex::bytes(size)),
+        (
+            "lookback",
+            params.num_lookback.next_power_of_two() * (size_of::<Pixel>() + 3),
+        ),
+    ];
+    if params.needs_placements() {
+        // Grown by doubling.
+        memory.push((
+            "placements",
+            cells.next_power_of_two() * size_of::<Placement>(),
+        ));
+    }
+    if params.walk_map.is_some() {
+        memory.push(("walk map", cells * size_of::<u32>()));
+    }
+    if !params.streams_output() {
+        // The image and which cells are filled.
+        memory.push((
+            "output image",
+            cells * 3 + cells + size * size_of::<Vec<bool>>(),
+        ));
+    }
+    memory
+}
+
+// VmHWM on Linux; None elsewhere.
+fn peak_resident() -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    let kb: usize = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+pub fn duration(seconds: f64) -> String {
+    let whole = seconds.round() as u64;
+    match whole {
+        _ if seconds < 10.0 => format!("{seconds:.2}s"),
+        0..3600 => format!("{}m {:02}s", whole / 60, whole % 60),
+        _ => format!("{}h {:02}m", whole / 3600, whole % 3600 / 60),
+    }
+}
+
+pub fn bytes(n: usize) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if n < 1024 {
+        return format!("{n} B");
+    }
+    let mut value = n as f64;
+    let mut unit = "B";
+    for u in UNITS {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = u;
+    }
+    format!("{value:.1} {unit}")
+}
@@ -0,0 +1,83 @@
+use rand::prelude::*;
+#[cfg(feature = "files")]
+use rand_chacha::ChaCha8Rng;
+
+#[cfg(feature = "files")]
+use std::{fmt::Write, fs, path::Path};
+
+#[cfg(feature = "files")]
+use crate::gallery::add_to_gallery;
+#[cfg(feature = "files")]
+use crate::output::save_png;
+use crate::palette::NAMES;
+#[cfg(feature = "files")]
+use crate::{Params, generate};
+
+// Random arguments from ranges that tend to give interesting images. Spreads,
+// center counts and lookbacks are log-uniform, as their effect is relative.
+pub fn random_args<R: Rng>(rng: &mut R) -> Vec<String> {
+    let mut args: Vec<String> = vec![
+        "--centers".into(),
+        (log_uniform(rng, 3.0, 200.0).round() as usize).to_string(),
+        "--lookback".into(),
+        (log_uniform(rng, 20.0, 5000.0).round() as usize).to_string(),
+        "--start-spread".into(),
+        format!("{:.3}", log_uniform(rng, 0.01, 1.0)),
+        "--cont-spread".into(),
+        format!("{:.3}", log_uniform(rng, 0.005, 1.0)),
+    ];
+    if rng.random_bool(0.25) {
+        let ratio = rng.random_range(0.1..0.6);
+        let speed = rng.random_range(1.0..8.0);
+        args.extend(["--orbit".into(), format!("epicycle:{ratio:.2}:{speed:.1}")]);
+    }
+    if rng.random_bool(0.2) {
+        let direction = if rng.random_bool(0.5) { "out" } else { "in" };
+        let rate = log_uniform(rng, 0.005, 0.2);
+        args.extend(["--spiral".into(), format!("{direction}:{rate:.3}")]);
+    }
+    if rng.random_bool(0.2) {
+        let neighborhood = ["4", "knight", "ring2"].choose(rng).expect("nonempty");
+        args.extend(["--neighborhood".into(), neighborhood.to_string()]);
+    }
+    if rng.random_bool(0.6) {
+        let palette = NAMES.choose(rng).expect("nonempty");
+        args.extend(["--palette".into(), palette.to_string()]);
+    }
+    args.extend(["--seed".into(), rng.random::<u32>().to_string()]);
+    args
+}
+
+pub(crate) fn log_uniform<R: Rng>(rng: &mut R, lo: f64, hi: f64) -> f64 {
+    rng.random_range(lo.ln()..hi.ln()).exp()
+}
+
+// Renders count random previews into dir, adding each to the gallery there,
+// and lists their arguments in dir/explore.txt for rendering again larger.
+#[cfg(feature = "files")]
+pub fn explore(count: usize, size: usize, seed: u64, dir: &str) -> Result<(), String> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    fs::create_dir_all(dir).map_err(|e| format!("could not create {dir}: {e}"))?;
+    let mut list = String::new();
+    for i in 1..=count {
+        let args = random_args(&mut rng);
+        let name = format!("explore-{i:03}.png");
+        let preview = [
+            "--size".to_string(),
+            size.to_string(),
+            "--out-dir".into(),
+            dir.into(),
+            "--out".into(),
+            name.clone(),
+            "--gallery".into(),
+        ];
+        let params = Params::from_args(args.iter().cloned().chain(preview))?;
+        let path = params.filename();
+        println!("{name}: {}", args.join(" "));
+        save_png(&generate(&params, false).image, &path, &params.metadata())?;
+        add_to_gallery(dir, &path, &params)?;
+        writeln!(list, "{name} {}", args.join(" ")).expect("writes to a string");
+    }
+    let path = Path::new(dir).join("explore.txt");
+    fs::write(&path, list).map_err(|e| format!("could not write {}: {e}", path.display()))
+}
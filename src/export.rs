@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::chains::chain_ids;
+use crate::{Location, Pixel, Placement};
+
+// The binary format starts with MAGIC, then the version, canvas size and
+// record count as little-endian u32s, then one RECORD-byte record per
+// placement, in order:
+// x, y, center x, center y: u16; red, green, blue, 0: u8;
+// center id, parent index (u32::MAX for none), chain: u32;
+// the orbit's position x, y: f32; the walk's steps: u32.
+// Version 1 files, without the position, and version 2, without the steps,
+// are still read.
+pub const MAGIC: &[u8; 4] = b"SPNR";
+pub const VERSION: u32 = 3;
+pub const RECORD: usize = 36;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    NdJson,
+    Binary,
+}
+
+impl ExportFormat {
+    // ND-JSON for .ndjson and .jsonl files, binary for anything else.
+    pub fn from_path(path: &str) -> Self {
+        if path.ends_with(".ndjson") || path.ends_with(".jsonl") {
+            ExportFormat::NdJson
+        } else {
+            ExportFormat::Binary
+        }
+    }
+}
+
+// Writes every placement with its index, location, color, center, the index
+// of the placement it continued from, the chain it belongs to and the length
+// of its walk. A parent the record doesn't hold, like a pixel kept around an
+// inpainted hole, is written as none.
+pub fn write_placements(placements: &[Placement], size: usize, path: &str) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("could not create {path}: {e}"))?;
+    let mut out = BufWriter::new(file);
+    let chains = chain_ids(placements);
+    let mut index_of: HashMap<Location, usize> = HashMap::new();
+    let format = ExportFormat::from_path(path);
+    let error = |e: std::io::Error| format!("could not write {path}: {e}");
+    if format == ExportFormat::Binary {
+        out.write_all(MAGIC).map_err(error)?;
+        for n in [VERSION, size as u32, placements.len() as u32] {
+            out.write_all(&n.to_le_bytes()).map_err(error)?;
+        }
+    }
+    for (i, (placement, chain)) in placements.iter().zip(chains).enumerate() {
+        let pixel = placement.pixel;
+        let parent = placement.parent.and_then(|loc| index_of.get(&loc).copied());
+        index_of.insert(pixel.loc, i);
+        let [x, y] = pixel.loc;
+        let [cx, cy] = pixel.center;
+        let [r, g, b] = pixel.color;
+        let [px, py] = placement.pos;
+        match format {
+            ExportFormat::NdJson => {
+                let parent = parent.map_or("null".to_string(), |p| p.to_string());
+                writeln!(
+                    out,
+                    r#"{{"index":{i},"loc":[{x},{y}],"color":[{r},{g},{b}],"center":[{cx},{cy}],"center_id":{},"parent":{parent},"chain":{chain},"pos":[{px:.3},{py:.3}],"steps":{}}}"#,
+                    pixel.center_id,
+                    placement.steps
+                )
+            }
+            ExportFormat::Binary => {
+                let mut record = Vec::with_capacity(RECORD);
+                for v in [x, y, cx, cy] {
+                    record.extend((v as u16).to_le_bytes());
+                }
+                record.extend([r, g, b, 0]);
+                let parent = parent.map_or(u32::MAX, |p| p as u32);
+                for v in [pixel.center_id as u32, parent, chain as u32] {
+                    record.extend(v.to_le_bytes());
+                }
+                for v in [px, py] {
+                    record.extend((v as f32).to_le_bytes());
+                }
+                record.extend((placement.steps as u32).to_le_bytes());
+                out.write_all(&record)
+            }
+        }
+        .map_err(error)?;
+    }
+    out.flush().map_err(error)
+}
+
+// Reads a binary export back, as the canvas size and the placements.
+pub fn read_placements(path: &str) -> Result<(usize, Vec<Placement>), String> {
+    let data = std::fs::read(path).map_err(|e| format!("could not read {path}: {e}"))?;
+    if data.len() < 16 || &data[..4] != MAGIC {
+        return Err(format!("{path} is not a binary placement export"));
+    }
+    let word = |at: usize| u32::from_le_bytes(data[at..at + 4].try_into().expect("4 bytes"));
+    let record_len = match word(4) {
+        1 => 24,
+        2 => 32,
+        VERSION => RECORD,
+        version => return Err(format!("{path} has unsupported version {version}")),
+    };
+    let (size, count) = (word(8) as usize, word(12) as usize);
+    let records = &data[16..];
+    if records.len() != count * record_len {
+        return Err(format!("{path} is truncated"));
+    }
+    let mut placements: Vec<Placement> = Vec::with_capacity(count);
+    for record in records.chunks_exact(record_len) {
+        let half = |k: usize| u16::from_le_bytes([record[2 * k], record[2 * k + 1]]) as usize;
+        let word = |at: usize| u32::from_le_bytes(record[at..at + 4].try_into().expect("4 bytes"));
+        let loc = [half(0), half(1)];
+        if loc[0] >= size || loc[1] >= size {
+            return Err(format!("{path} has a pixel outside the canvas"));
+        }
+        let parent = match word(16) {
+            u32::MAX => None,
+            index => Some(
+                placements
+                    .get(index as usize)
+                    .ok_or(format!("{path} has a parent after its child"))?
+                    .pixel
+                    .loc,
+            ),
+        };
+        placements.push(Placement {
+            pixel: Pixel {
+                color: [record[8], record[9], record[10]],
+                loc,
+                center: [half(2), half(3)],
+                center_id: word(12) as usize,
+            },
+            parent,
+            pos: if record_len >= 32 {
+                [24, 28].map(|at| {
+                    f32::from_le_bytes(record[at..at + 4].try_into().expect("4 bytes")) as f64
+                })
+            } else {
+                loc.map(|c| c as f64 + 0.5)
+            },
+            steps: if record_len == RECORD {
+                word(32) as usize
+            } else {
+                0
+            },
+        });
+    }
+    Ok((size, placements))
+}
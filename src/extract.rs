@@ -0,0 +1,76 @@
+use image::RgbImage;
+
+use std::collections::HashMap;
+
+use crate::Color;
+use crate::print::{from_lab, lab};
+use crate::quantize::median_cut;
+
+// Most pixels clustered; larger images are sampled on an even grid.
+const SAMPLE: usize = 1 << 16;
+const MAX_ITERS: usize = 64;
+
+// The n colors that best summarize the image, by k-means in CIELAB, from
+// dark to light. Clusters start from a median cut, so the result depends
+// only on the image. Images with fewer colors give all of them.
+pub fn extract_palette(img: &RgbImage, n: usize) -> Vec<Color> {
+    let pixels = img.as_raw().chunks_exact(3);
+    let stride = (pixels.len() / SAMPLE).max(1);
+    let mut counts: HashMap<Color, usize> = HashMap::new();
+    for p in pixels.step_by(stride) {
+        *counts.entry([p[0], p[1], p[2]]).or_default() += 1;
+    }
+    let mut colors: Vec<(Color, usize)> = counts.into_iter().collect();
+    colors.sort_unstable();
+    if colors.len() <= n {
+        return by_lightness(colors.into_iter().map(|(c, _)| c).collect());
+    }
+    let samples: Vec<([f64; 3], f64)> = colors.iter().map(|&(c, k)| (lab(c), k as f64)).collect();
+    let spread: Vec<Color> = colors
+        .iter()
+        .flat_map(|&(c, k)| std::iter::repeat_n(c, k))
+        .collect();
+    let mut centers: Vec<[f64; 3]> = median_cut(&spread, n).into_iter().map(lab).collect();
+    let mut assigned = vec![usize::MAX; samples.len()];
+    for _ in 0..MAX_ITERS {
+        let mut changed = false;
+        for (slot, (sample, _)) in assigned.iter_mut().zip(&samples) {
+            let nearest = (0..centers.len())
+                .min_by(|&a, &b| {
+                    dist_sq(sample, &centers[a]).total_cmp(&dist_sq(sample, &centers[b]))
+                })
+                .expect("nonempty");
+            changed |= *slot != nearest;
+            *slot = nearest;
+        }
+        if !changed {
+            break;
+        }
+        let mut sums = vec![([0.0; 3], 0.0); centers.len()];
+        for (&k, (sample, weight)) in assigned.iter().zip(&samples) {
+            for (sum, v) in sums[k].0.iter_mut().zip(sample) {
+                *sum += v * weight;
+            }
+            sums[k].1 += weight;
+        }
+        // An empty cluster keeps its center.
+        for (center, (sum, weight)) in centers.iter_mut().zip(sums) {
+            if weight > 0.0 {
+                *center = sum.map(|s| s / weight);
+            }
+        }
+    }
+    let mut palette: Vec<Color> = centers.into_iter().map(from_lab).collect();
+    palette.sort_unstable();
+    palette.dedup();
+    by_lightness(palette)
+}
+
+fn dist_sq(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    (0..3).map(|c| (a[c] - b[c]).powi(2)).sum()
+}
+
+fn by_lightness(mut colors: Vec<Color>) -> Vec<Color> {
+    colors.sort_by(|&a, &b| lab(a)[0].total_cmp(&lab(b)[0]).then(a.cmp(&b)));
+    colors
+}
@@ -0,0 +1,194 @@
+// A C interface to the generator, for embedding the engine elsewhere.
+// Every function taking a pointer expects one returned by this interface,
+// not yet freed. The header is include/spinning.h. Panics are caught at the
+// boundary and reported as errors, since unwinding into C aborts the host.
+#![allow(clippy::missing_safety_doc)]
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char, c_int};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::ptr;
+use std::sync::OnceLock;
+
+use crate::{Generator, Params};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(message: impl Into<String>) {
+    let message = message.into().replace('\0', " ");
+    LAST_ERROR.with(|e| *e.borrow_mut() = CString::new(message).ok());
+}
+
+// Runs f, returning failed with the panic's message as the last error if
+// it panics. A generator that panicked mid-step can only be freed.
+fn guard<T>(failed: T, f: impl FnOnce() -> T) -> T {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        set_error(format!("panicked: {message}"));
+        failed
+    })
+}
+
+// Fills buffer, which needs room for size * height * 3 bytes, with generator's
+// pixels as RGB24, row by row.
+unsafe fn copy_rgb(generator: &Generator, buffer: *mut u8, len: usize) -> c_int {
+    let image = generator.image();
+    let data = image.as_raw();
+    if buffer.is_null() || len < data.len() {
+        set_error(format!("the buffer needs {} bytes", data.len()));
+        return -1;
+    }
+    // Safety: the caller provides at least len writable bytes.
+    unsafe { ptr::copy_nonoverlapping(data.as_ptr(), buffer, data.len()) };
+    0
+}
+
+/// Parses command-line style arguments, as given to the spinning binary,
+/// not counting the program name. Returns NULL on error; see
+/// spinning_last_error.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spinning_params_new(
+    argc: c_int,
+    argv: *const *const c_char,
+) -> *mut Params {
+    let mut args = vec![];
+    for i in 0..argc.max(0) as usize {
+        // Safety: the caller provides argc valid C strings.
+        let arg = unsafe { CStr::from_ptr(*argv.add(i)) };
+        match arg.to_str() {
+            Ok(arg) => args.push(arg.to_string()),
+            Err(_) => {
+                set_error(format!("argument {i} is not UTF-8"));
+                return ptr::null_mut();
+            }
+        }
+    }
+    guard(ptr::null_mut(), || {
+        match Params::from_args(args.into_iter()) {
+            Ok(params) => Box::into_raw(Box::new(params)),
+            Err(e) => {
+                set_error(e);
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// The width of the image the parameters describe, and its height but for
+/// a panorama.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spinning_params_size(params: *const Params) -> u32 {
+    // Safety: see the top of this file.
+    unsafe { &*params }.size as u32
+}
+
+/// The height of the image the parameters describe: the size, or the rows of
+/// a panorama's band.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spinning_params_height(params: *const Params) -> u32 {
+    // Safety: see the top of this file.
+    let params = unsafe { &*params };
+    params.canvas.rows(params.size).len() as u32
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spinning_params_free(params: *mut Params) {
+    if !params.is_null() {
+        // Safety: see the top of this file.
+        drop(unsafe { Box::from_raw(params) });
+    }
+}
+
+/// Runs a whole generation into buffer, which needs size * height * 3 bytes.
+/// Returns 0 on success and -1 on error.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spinning_generate(
+    params: *const Params,
+    buffer: *mut u8,
+    len: usize,
+) -> c_int {
+    // Safety: see the top of this file.
+    let params = unsafe { &*params };
+    guard(-1, || {
+        let mut generator = Generator::new(params, false);
+        while generator.step() {}
+        unsafe { copy_rgb(&generator, buffer, len) }
+    })
+}
+
+/// A generator to run a step at a time. The parameters can be freed after.
+/// Returns NULL on error; see spinning_last_error.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spinning_generator_new(params: *const Params) -> *mut Generator {
+    // Safety: see the top of this file.
+    let params = unsafe { &*params };
+    guard(ptr::null_mut(), || {
+        Box::into_raw(Box::new(Generator::new(params, false)))
+    })
+}
+
+/// Places up to count pixels, returning how many were placed; fewer than
+/// count once the image is done, or on error, which sets
+/// spinning_last_error. After an error the generator can only be freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spinning_generator_step(generator: *mut Generator, count: usize) -> usize {
+    // Safety: see the top of this file.
+    let generator = unsafe { &mut *generator };
+    let mut placed = 0;
+    guard((), || {
+        while placed < count && generator.step() {
+            placed += 1;
+        }
+    });
+    placed
+}
+
+/// Fraction of the pixels placed so far, from 0 to 1.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spinning_generator_coverage(generator: *const Generator) -> f64 {
+    // Safety: see the top of this file.
+    unsafe { &*generator }.coverage()
+}
+
+/// Copies the image so far into buffer, which needs size * height * 3 bytes.
+/// Returns 0 on success and -1 on error.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spinning_generator_copy_rgb(
+    generator: *const Generator,
+    buffer: *mut u8,
+    len: usize,
+) -> c_int {
+    // Safety: see the top of this file.
+    let generator = unsafe { &*generator };
+    guard(-1, || unsafe { copy_rgb(generator, buffer, len) })
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spinning_generator_free(generator: *mut Generator) {
+    if !generator.is_null() {
+        // Safety: see the top of this file.
+        drop(unsafe { Box::from_raw(generator) });
+    }
+}
+
+/// The JSON Schema of the generation parameters, as printed by `spinning
+/// schema`. Valid for the life of the program.
+#[unsafe(no_mangle)]
+pub extern "C" fn spinning_schema() -> *const c_char {
+    static SCHEMA: OnceLock<CString> = OnceLock::new();
+    SCHEMA
+        .get_or_init(|| CString::new(crate::schema::json_schema()).expect("no NUL"))
+        .as_ptr()
+}
+
+/// The message of the last error on this thread, or NULL. Valid until the
+/// next call that fails.
+#[unsafe(no_mangle)]
+pub extern "C" fn spinning_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
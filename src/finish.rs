@@ -0,0 +1,70 @@
+use image::{Rgb, RgbImage, RgbaImage};
+
+use crate::canvas::Unfilled;
+use crate::linear::{color_from_linear, color_to_linear};
+
+// Frame finishing: the outermost fade pixels and, with vignette, the corners
+// blend out to a color or to transparency.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Finish {
+    // Width of the border fade in output pixels.
+    pub fade: usize,
+    // 1.0 fades the corners out entirely.
+    pub vignette: f64,
+    pub to: Unfilled,
+}
+
+impl Finish {
+    pub fn is_none(&self) -> bool {
+        self.fade == 0 && self.vignette == 0.0
+    }
+
+    // How much of the image shows at pixel (x, y) of a width by height output,
+    // from 1 inside to 0 at the edge.
+    pub fn weight(&self, x: u32, y: u32, width: u32, height: u32) -> f64 {
+        let mut weight = 1.0;
+        if self.fade > 0 {
+            let edge = x.min(y).min(width - 1 - x).min(height - 1 - y) as f64 + 0.5;
+            let t = (edge / self.fade as f64).min(1.0);
+            weight *= t * t * (3.0 - 2.0 * t);
+        }
+        if self.vignette > 0.0 {
+            let (cx, cy) = (width as f64 / 2.0, height as f64 / 2.0);
+            let dx = x as f64 + 0.5 - cx;
+            let dy = y as f64 + 0.5 - cy;
+            weight *= (1.0 - self.vignette * (dx * dx + dy * dy) / (cx * cx + cy * cy)).max(0.0);
+        }
+        weight
+    }
+
+    // Blends the image toward the fade color, in linear light. Fading to
+    // transparency leaves the colors alone; see alpha.
+    pub fn apply(&self, image: &mut RgbImage) {
+        if self.is_none() || self.to == Unfilled::Transparent {
+            return;
+        }
+        let to = color_to_linear(self.to.color());
+        let (width, height) = image.dimensions();
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            let weight = self.weight(x, y, width, height) as f32;
+            if weight < 1.0 {
+                let color = color_to_linear(pixel.0);
+                *pixel = Rgb(color_from_linear(std::array::from_fn(|ch| {
+                    to[ch] + weight * (color[ch] - to[ch])
+                })));
+            }
+        }
+    }
+
+    // Scales the alpha of an image fading to transparency.
+    pub fn alpha(&self, image: &mut RgbaImage) {
+        if self.is_none() || self.to != Unfilled::Transparent {
+            return;
+        }
+        let (width, height) = image.dimensions();
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            let weight = self.weight(x, y, width, height);
+            pixel.0[3] = (pixel.0[3] as f64 * weight).round() as u8;
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use image::imageops::{FilterType, resize};
+use image::{Rgb, RgbImage};
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+
+use std::time::{Duration, Instant};
+
+use crate::explore::log_uniform;
+use crate::quantize::quantize_image;
+use crate::{Params, generate};
+
+// Images are compared at this size, where the texture of single walks has
+// been averaged away and only the larger structure and colors remain.
+const COMPARE: u32 = 64;
+const WINDOW: u32 = 8;
+const PALETTE: usize = 8;
+
+// The searched parameters, with the ranges explore draws from.
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    centers: f64,
+    lookback: f64,
+    start_spread: f64,
+    cont_spread: f64,
+    seed: u32,
+}
+
+const RANGES: [(f64, f64); 4] = [(3.0, 200.0), (20.0, 5000.0), (0.01, 1.0), (0.005, 1.0)];
+
+impl Candidate {
+    fn random<R: Rng>(rng: &mut R) -> Self {
+        let [c, l, s, t] = RANGES.map(|(lo, hi)| log_uniform(rng, lo, hi));
+        Self::from_values([c, l, s, t], rng.random())
+    }
+
+    fn from_values(values: [f64; 4], seed: u32) -> Self {
+        let [centers, lookback, start_spread, cont_spread] = values;
+        Self {
+            centers,
+            lookback,
+            start_spread,
+            cont_spread,
+            seed,
+        }
+    }
+
+    // Scales each value by up to a factor of e^±0.4, keeping the seed half
+    // the time.
+    fn mutate<R: Rng>(&self, rng: &mut R) -> Self {
+        let values = [
+            self.centers,
+            self.lookback,
+            self.start_spread,
+            self.cont_spread,
+        ];
+        let mut next = [0.0; 4];
+        for (k, (&(lo, hi), v)) in RANGES.iter().zip(values).enumerate() {
+            next[k] = (v * rng.random_range(-0.4f64..0.4).exp()).clamp(lo, hi);
+        }
+        let seed = if rng.random_bool(0.5) {
+            self.seed
+        } else {
+            rng.random()
+        };
+        Self::from_values(next, seed)
+    }
+
+    fn args(&self, palette: &str) -> Vec<String> {
+        [
+            "--centers".to_string(),
+            (self.centers.round() as usize).to_string(),
+            "--lookback".into(),
+            (self.lookback.round() as usize).to_string(),
+            "--start-spread".into(),
+            format!("{:.3}", self.start_spread),
+            "--cont-spread".into(),
+            format!("{:.3}", self.cont_spread),
+            "--palette".into(),
+            palette.into(),
+            "--seed".into(),
+            self.seed.to_string(),
+        ]
+        .into()
+    }
+}
+
+pub struct Fit {
+    pub args: Vec<String>,
+    pub image: RgbImage,
+    // One minus the mean SSIM against the target, from 0 for a match.
+    pub loss: f64,
+    pub tried: usize,
+}
+
+// Searches for the arguments whose image at size looks most like target,
+// until the time budget runs out. Colors are drawn from the target's own
+// palette; the search mixes random candidates with variations of the best
+// so far. on_better is called with each improvement.
+pub fn fit(
+    target: &RgbImage,
+    size: usize,
+    budget: Duration,
+    seed: u64,
+    mut on_better: impl FnMut(&Fit),
+) -> Result<Fit, String> {
+    let start = Instant::now();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let small = resize(target, COMPARE, COMPARE, FilterType::Triangle);
+    // Sampled without filtering, so the palette holds the target's own colors
+    // rather than blends of them.
+    let sample = resize(target, 256, 256, FilterType::Nearest);
+    let (palette, _) = quantize_image(&sample, PALETTE);
+    let palette: Vec<String> = palette
+        .iter()
+        .map(|c| format!("{:02x}{:02x}{:02x}", c[0], c[1], c[2]))
+        .collect();
+    let palette = palette.join(",");
+    let mut best: Option<(Candidate, Fit)> = None;
+    let mut tried = 0;
+    while tried == 0 || start.elapsed() < budget {
+        let candidate = match &best {
+            Some((best, _)) if rng.random_bool(0.7) => best.mutate(&mut rng),
+            _ => Candidate::random(&mut rng),
+        };
+        let args = candidate.args(&palette);
+        let size_arg = ["--size".to_string(), size.to_string()];
+        let params = Params::from_args(args.iter().cloned().chain(size_arg))?;
+        let image = generate(&params, false).image;
+        let loss = 1.0
+            - ssim(
+                &resize(&image, COMPARE, COMPARE, FilterType::Triangle),
+                &small,
+            );
+        tried += 1;
+        if best.as_ref().is_none_or(|(_, fit)| loss < fit.loss) {
+            let fit = Fit {
+                args,
+                image,
+                loss,
+                tried,
+            };
+            on_better(&fit);
+            best = Some((candidate, fit));
+        }
+    }
+    let (_, mut fit) = best.expect("tried at least once");
+    fit.tried = tried;
+    Ok(fit)
+}
+
+// Mean structural similarity over 8x8 windows at half-window steps, averaged
+// over the three channels. 1 for identical images.
+pub fn ssim(a: &RgbImage, b: &RgbImage) -> f64 {
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+    let (width, height) = a.dimensions();
+    let mut total = 0.0;
+    let mut count = 0;
+    for top in (0..=height.saturating_sub(WINDOW)).step_by(WINDOW as usize / 2) {
+        for left in (0..=width.saturating_sub(WINDOW)).step_by(WINDOW as usize / 2) {
+            for channel in 0..3 {
+                let values = |img: &RgbImage| {
+                    (top..(top + WINDOW).min(height))
+                        .flat_map(|y| (left..(left + WINDOW).min(width)).map(move |x| (x, y)))
+                        .map(|(x, y)| {
+                            let Rgb(p) = *img.get_pixel(x, y);
+                            p[channel] as f64
+                        })
+                        .collect::<Vec<f64>>()
+                };
+                let (xs, ys) = (values(a), values(b));
+                let n = xs.len() as f64;
+                let mean_x = xs.iter().sum::<f64>() / n;
+                let mean_y = ys.iter().sum::<f64>() / n;
+                let (mut var_x, mut var_y, mut cov) = (0.0, 0.0, 0.0);
+                for (x, y) in xs.iter().zip(&ys) {
+                    var_x += (x - mean_x) * (x - mean_x) / n;
+                    var_y += (y - mean_y) * (y - mean_y) / n;
+                    cov += (x - mean_x) * (y - mean_y) / n;
+                }
+                total += (2.0 * mean_x * mean_y + C1) * (2.0 * cov + C2)
+                    / ((mean_x * mean_x + mean_y * mean_y + C1) * (var_x + var_y + C2));
+                count += 1;
+            }
+        }
+    }
+    total / count.max(1) as f64
+}
@@ -0,0 +1,113 @@
+#[cfg(feature = "files")]
+use std::{fs, path::Path};
+
+#[cfg(feature = "files")]
+use crate::output::save_rgba_png;
+#[cfg(feature = "files")]
+use crate::params::Params;
+
+#[cfg(feature = "files")]
+const THUMBNAIL: u32 = 200;
+
+// Adds the image at path to the gallery in dir: a thumbnail in dir/thumbs,
+// an entry in dir/gallery.json and a regenerated dir/index.html. The manifest
+// holds one entry per line, so entries are replaced by file without parsing.
+#[cfg(feature = "files")]
+pub fn add_to_gallery(dir: &str, path: &str, params: &Params) -> Result<(), String> {
+    let file = Path::new(path)
+        .strip_prefix(dir)
+        .unwrap_or(Path::new(path))
+        .to_string_lossy()
+        .into_owned();
+    let thumbnail = format!("thumbs/{}", file.replace(['/', '\\'], "_"));
+    let thumbs = Path::new(dir).join("thumbs");
+    fs::create_dir_all(&thumbs)
+        .map_err(|e| format!("could not create {}: {e}", thumbs.display()))?;
+    let image = image::open(path).map_err(|e| format!("could not open {path}: {e}"))?;
+    save_rgba_png(
+        &image.thumbnail(THUMBNAIL, THUMBNAIL).to_rgba8(),
+        &Path::new(dir).join(&thumbnail).to_string_lossy(),
+        &[],
+    )?;
+
+    let key = format!("{{\"file\": {}", json_string(&file));
+    let entry = format!(
+        "{key}, \"thumbnail\": {}, \"size\": {}, \"seed\": {}, \"args\": {}}}",
+        json_string(&thumbnail),
+        params.size,
+        params.seed,
+        json_string(&params.args.join(" ")),
+    );
+    let manifest = Path::new(dir).join("gallery.json");
+    let mut entries: Vec<String> = fs::read_to_string(&manifest)
+        .unwrap_or_default()
+        .lines()
+        .map(|line| line.trim().trim_end_matches(',').to_string())
+        .filter(|line| line.starts_with('{'))
+        .collect();
+    match entries.iter_mut().find(|line| line.starts_with(&key)) {
+        Some(line) => *line = entry,
+        None => entries.push(entry),
+    }
+    let list = format!("[\n  {}\n]\n", entries.join(",\n  "));
+    let write = |path: &Path, contents: &str| {
+        fs::write(path, contents).map_err(|e| format!("could not write {}: {e}", path.display()))
+    };
+    write(&manifest, &list)?;
+    write(
+        &Path::new(dir).join("index.html"),
+        &INDEX.replace("GALLERY", list.trim_end()),
+    )
+}
+
+// Safe to embed in a script element, as < is always escaped.
+pub fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c < ' ' || c == '<' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(feature = "files")]
+const INDEX: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Gallery</title>
+<style>
+body { background: #111; color: #ccc; font: 13px sans-serif; margin: 16px; }
+main { display: flex; flex-wrap: wrap; gap: 16px; }
+figure { margin: 0; width: 200px; }
+figure img { display: block; max-width: 200px; }
+figcaption { margin-top: 4px; word-break: break-all; }
+</style>
+</head>
+<body>
+<main></main>
+<script>
+const gallery = GALLERY;
+const main = document.querySelector("main");
+for (const entry of gallery) {
+  const figure = document.createElement("figure");
+  const link = document.createElement("a");
+  link.href = entry.file;
+  const img = document.createElement("img");
+  img.src = entry.thumbnail;
+  img.alt = entry.file;
+  link.append(img);
+  const caption = document.createElement("figcaption");
+  caption.textContent = entry.file + (entry.args ? " — " + entry.args : "");
+  figure.append(link, caption);
+  main.append(figure);
+}
+</script>
+</body>
+</html>
+"#;
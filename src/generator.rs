@@ -0,0 +1,1191 @@
+use image::imageops::FilterType;
+use image::{ImageBuffer, RgbImage};
+use rand::prelude::*;
+use tracing::{info, info_span};
+
+use std::collections::VecDeque;
+
+use crate::base::{borders, hole_borders};
+use crate::center::{CenterLayout, Centers, UNKNOWN_CENTER, relaxed_layout};
+use crate::color::{ColorSource, grain, lerp, rotate_hue, sample_colormap, spread_colors};
+use crate::grid::{Grid, Occupancy};
+use crate::linear;
+use crate::lookback::{Lookback, LookbackScale, Query};
+use crate::observer::Observer;
+use crate::openindex::OpenIndex;
+#[cfg(feature = "files")]
+use crate::output::{encodes_in_parallel, save_png_bands, save_png_rows};
+use crate::palette::{CenterPalettes, MAGMA, random_palette};
+use crate::params::{CenterPaletteSpec, Params, SeedColors};
+use crate::phase::Phase;
+use crate::quantize::Quantize;
+use crate::region::RegionMap;
+use crate::rng::{RngKind, Rngs, SeededRng, Stream, splitmix64};
+use crate::sampler::Sampler;
+use crate::scene::{SceneReader, SceneWriter};
+use crate::series::{Target, modulate};
+use crate::strategy::{Context, Decision, Fallback, PlacementStrategy};
+use crate::{Color, Location, Pixel, Placement, Run, VecMap, color_dist_sq};
+
+const CENTER_CANDIDATES: usize = 8;
+// Layouts drawn for a new center before settling for one within the
+// --center-gap of another.
+const CENTER_GAP_TRIES: usize = 16;
+// Placements before a requeued color is tried again.
+const REQUEUE_DELAY: usize = 64;
+
+// Gravity pulls toward this many of the heaviest centers, found again every
+// HEAVY_REFRESH placements.
+const HEAVY_CENTERS: usize = 16;
+const HEAVY_REFRESH: usize = 1024;
+// Fewest rows of the image assembled on a thread of their own.
+const PARALLEL_BAND: usize = 128;
+
+// Mixed into the seed of the grain's generator.
+const GRAIN_SEED: u64 = 0x0067_7261_696e;
+// Seeds the draws --seed-colors spread picks from, apart from the run's.
+const SPREAD_SEED: u64 = 0x7370_7265_6164;
+// How many colors --seed-colors spread picks the seed colors from.
+const SPREAD_CANDIDATES: usize = 4096;
+
+// The placement loop, one pixel per step.
+pub struct Generator {
+    params: Params,
+    rngs: Rngs,
+    regions: RegionMap,
+    colors: ColorSource,
+    center_palettes: Option<CenterPalettes>,
+    grid: Grid,
+    lookback: Lookback,
+    // Shrinks the searched part of the lookback, for --adaptive-lookback.
+    lookback_scale: Option<LookbackScale>,
+    strategy: Box<dyn PlacementStrategy>,
+    open_locs: VecMap<Location>,
+    // The same cells, for nearest open cell queries.
+    open_index: OpenIndex,
+    // Weighted choice of random insertion points, if not uniform.
+    sampler: Option<Sampler>,
+    // A coarse pass scaled up to full size, for multiscale runs.
+    guide: Option<RgbImage>,
+    // Number of cells to fill.
+    total: usize,
+    // Stop after this many, for a coverage below 1 or a pixel budget.
+    target: usize,
+    record: bool,
+    placements: Vec<Placement>,
+    // Visits to each cell by walks, for --walk-map.
+    walks: Option<Vec<u32>>,
+    // Noise for --color-jitter, apart from every other stream so the grain
+    // leaves the layout alone.
+    grain: Option<SeededRng>,
+    // The colors of the first num_centers pixels, used in turn.
+    seed_colors: Vec<Color>,
+    // Collisions of the last walk, for --merge-centers.
+    collisions: Option<Vec<[usize; 2]>>,
+    i: usize,
+    // Colors drawn so far; ahead of i by the colors set aside.
+    drawn: usize,
+    // Colors set aside by --fallback requeue: the color, its tries so far
+    // and the placement from which to try again.
+    requeued: VecDeque<(Color, usize, usize)>,
+    // Pixels placed at random because no walk found a spot.
+    forced: usize,
+    // How each cell was filled, for --refine: 0 if not by this run, 1 by a
+    // walk and 2 at random. Indexed by y then x.
+    origins: Option<Vec<u8>>,
+    // Set once the refinement pass has begun; the colors it has left to
+    // place again, which keep their colors exactly.
+    refined: bool,
+    refill: Vec<Color>,
+    merged: usize,
+    centers: Centers,
+    // Positions and pixel counts of the heaviest centers, for gravity.
+    heavy: Vec<([f64; 2], f64)>,
+    // Where the initial centers start: the stencil's outline, shuffled, or
+    // the relaxed layout.
+    center_points: Vec<Location>,
+    observers: Vec<Box<dyn Observer>>,
+}
+
+impl Generator {
+    pub fn new(params: &Params, record: bool) -> Self {
+        Self::with_strategy(params, record, params.algorithm.strategy())
+    }
+
+    // A generator placing colors with a strategy of the caller's own.
+    pub fn with_strategy(
+        params: &Params,
+        record: bool,
+        strategy: Box<dyn PlacementStrategy>,
+    ) -> Self {
+        let _span = info_span!("seeding", size = params.size).entered();
+        let size = params.size;
+        let mut rngs = if params.streams {
+            Rngs::split(params.rng, params.seed)
+        } else {
+            Rngs::single(params.rng, params.seed)
+        };
+        let regions = match &params.regions {
+            Some(regions) => regions.clone(),
+            None => RegionMap::uniform(params),
+        };
+        let borders = match &params.base {
+            Some(base) if params.inpaint => hole_borders(base),
+            _ => vec![],
+        };
+        let mut params = params.clone();
+        if !borders.is_empty() {
+            // The lookback starts with the pixels around the holes instead.
+            params.num_centers = 0;
+            if matches!(params.mode, ColorSource::Uniform) {
+                let base = params.base.as_ref().expect("inpainting has a base");
+                params.mode = ColorSource::Palette(
+                    borders
+                        .iter()
+                        .map(|&(loc, _)| base.kept(loc).expect("kept"))
+                        .collect(),
+                );
+            }
+        }
+        // A warm start places its own centers, among its samples.
+        let warm_centers = params.num_centers.max(1);
+        if params.warm_start.is_some() {
+            params.num_centers = 0;
+        }
+        let params = &params;
+        let colors = match (params.max_colors, params.quantize) {
+            (Some(n), Quantize::Generation) => {
+                let rng = rngs.get(Stream::Color);
+                ColorSource::Palette((0..n).map(|k| params.mode.next(rng, k, n)).collect())
+            }
+            _ => params.mode.clone(),
+        };
+        let seed_colors = match &params.seed_colors {
+            Some(SeedColors::Given(given)) => given.clone(),
+            Some(SeedColors::Spread) => {
+                let rng = &mut params.rng.seeded(splitmix64(params.seed ^ SPREAD_SEED));
+                let candidates: Vec<Color> = (0..SPREAD_CANDIDATES)
+                    .map(|k| colors.next(rng, k, SPREAD_CANDIDATES))
+                    .collect();
+                spread_colors(&candidates, params.num_centers)
+            }
+            None => vec![],
+        };
+        let center_palettes = params.center_palettes.as_ref().map(|spec| {
+            let palettes = match spec {
+                CenterPaletteSpec::Random(k) => (0..params.num_centers.max(1))
+                    .map(|_| random_palette(*k, rngs.get(Stream::Color)))
+                    .collect(),
+                CenterPaletteSpec::Given(palettes) => palettes.clone(),
+            };
+            CenterPalettes::new(palettes, params.palette_drift)
+        });
+        let mut grid = Grid::new(size);
+        let mut sampler = Sampler::new(&params.sampling, params.canvas, size);
+        let mut open = params.canvas.locations(size);
+        if let Some(base) = &params.base {
+            open.retain(|&loc| match base.kept(loc) {
+                Some(color) => {
+                    grid.set(
+                        loc,
+                        Pixel {
+                            color,
+                            loc,
+                            center: loc,
+                            center_id: 0,
+                        },
+                    );
+                    if let Some(sampler) = &mut sampler {
+                        sampler.remove(loc);
+                    }
+                    false
+                }
+                None => true,
+            });
+        }
+        let open_index = OpenIndex::new(size, &open);
+        let open_locs = VecMap::new_from_vec(open);
+        let guide = (params.multiscale > 0.0).then(|| {
+            let coarse = Params {
+                size: (size / 4).max(1),
+                multiscale: 0.0,
+                coverage: 1.0,
+                base: None,
+                inpaint: false,
+                ..params.clone()
+            };
+            let image = crate::make_image(&coarse);
+            linear::resize(&image, size as u32, size as u32, FilterType::Triangle)
+        });
+        let mut generator = Self {
+            params: params.clone(),
+            rngs,
+            lookback: match params.lookback_pools {
+                Some(pools) => {
+                    Lookback::pooled(regions.max_lookback(), pools, || params.eviction.policy())
+                }
+                None => Lookback::new(regions.max_lookback(), params.eviction.policy()),
+            },
+            strategy,
+            regions,
+            colors,
+            center_palettes,
+            grid,
+            sampler,
+            guide,
+            total: open_locs.len(),
+            target: ((open_locs.len() as f64 * params.coverage).ceil() as usize)
+                .min(params.max_pixels.unwrap_or(usize::MAX)),
+            open_locs,
+            open_index,
+            record,
+            placements: vec![],
+            walks: params.walk_map.is_some().then(|| vec![0; size * size]),
+            collisions: params.merge_centers.map(|_| vec![]),
+            grain: (params.color_jitter > 0.0)
+                .then(|| params.rng.seeded(splitmix64(params.seed ^ GRAIN_SEED))),
+            seed_colors,
+            i: 0,
+            forced: 0,
+            origins: params.refine.is_some().then(|| vec![0; size * size]),
+            refined: false,
+            refill: vec![],
+            merged: 0,
+            drawn: 0,
+            requeued: VecDeque::new(),
+            lookback_scale: params.adaptive_lookback.map(LookbackScale::new),
+            centers: Centers::default().with_gap(params.center_gap),
+            heavy: vec![],
+            center_points: vec![],
+            observers: vec![],
+        };
+        if let Some(stencil) = &params.stencil {
+            let mut points = stencil.outline(size);
+            points.retain(|&loc| params.canvas.contains(loc, size));
+            points.shuffle(generator.rngs.get(Stream::Layout));
+            generator.center_points = points;
+        }
+        if params.center_layout == CenterLayout::Relaxed {
+            generator.center_points = relaxed_layout(
+                params.canvas,
+                size,
+                params.num_centers,
+                params.relax_iters,
+                generator.rngs.get(Stream::Layout),
+            );
+        }
+        if let Some(base) = &params.base {
+            // The pixels around the holes share the first center.
+            if !borders.is_empty() {
+                generator.spawn_center(borders[0].1, 0);
+            }
+            let mut borders = borders;
+            borders.shuffle(generator.rngs.get(Stream::Layout));
+            for (loc, center) in borders {
+                let pixel = Pixel {
+                    color: base.kept(loc).expect("kept"),
+                    loc,
+                    center,
+                    center_id: 0,
+                };
+                generator
+                    .lookback
+                    .push(pixel, generator.rngs.get(Stream::Lookback));
+            }
+        }
+        if let Some(warm) = &params.warm_start {
+            let n = params.warm_samples.unwrap_or(params.num_lookback);
+            let rng = generator.rngs.get(Stream::Layout);
+            let mut cells = generator.open_locs.iter().copied().choose_multiple(rng, n);
+            cells.shuffle(rng);
+            let centers: Vec<Location> = cells.iter().take(warm_centers).copied().collect();
+            let palettes = generator.center_palettes.as_ref().map_or(1, |p| p.len());
+            for (k, &loc) in centers.iter().enumerate() {
+                generator.spawn_center(loc, k % palettes);
+            }
+            for loc in cells {
+                let dist = |c: &Location| {
+                    let [dx, dy] = [0, 1].map(|k| c[k].abs_diff(loc[k]));
+                    dx * dx + dy * dy
+                };
+                let id = (0..centers.len())
+                    .min_by_key(|&id| dist(&centers[id]))
+                    .expect("a center per sample up to warm_centers");
+                let pixel = Pixel {
+                    color: warm.at(loc, size),
+                    loc,
+                    center: centers[id],
+                    center_id: id,
+                };
+                generator
+                    .lookback
+                    .push(pixel, generator.rngs.get(Stream::Lookback));
+            }
+        }
+        generator
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.i >= self.target.min(self.total)
+            && self.refill.is_empty()
+            && (self.refined || self.params.refine.is_none())
+    }
+
+    pub fn placed(&self) -> usize {
+        self.i
+    }
+
+    // Number of pixels the run places, with any early stop.
+    pub fn target(&self) -> usize {
+        self.target.min(self.total)
+    }
+
+    // Number of cells to fill, ignoring any early stop.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    // Fraction of the cells to fill that have been placed.
+    pub fn coverage(&self) -> f64 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.i as f64 / self.total as f64
+        }
+    }
+
+    // Places one pixel. Returns false once every pixel has been placed.
+    pub fn step(&mut self) -> bool {
+        if self.is_done() {
+            return false;
+        }
+        let refilling = self.i >= self.target.min(self.total);
+        if refilling && !self.refined {
+            self.refine();
+            if self.refill.is_empty() {
+                return false;
+            }
+        }
+        let i = self.i;
+        if !refilling && i > 0 && i.is_multiple_of(self.total.div_ceil(10)) {
+            let coverage = self.coverage();
+            info!(
+                coverage,
+                forced_rate = self.forced as f64 / i as f64,
+                "milestone"
+            );
+            for observer in &mut self.observers {
+                observer.on_milestone(coverage);
+            }
+        }
+        if !refilling {
+            self.i += 1;
+        }
+        let due = self
+            .requeued
+            .front()
+            .is_some_and(|&(_, _, at)| at <= i || self.drawn >= self.target.min(self.total));
+        let (color, tries) = if refilling {
+            (self.refill.pop().expect("refilling"), 0)
+        } else if due {
+            let (color, tries, _) = self.requeued.pop_front().expect("nonempty");
+            (color, tries)
+        } else {
+            let mut color = self
+                .colors
+                .next(self.rngs.get(Stream::Color), self.drawn, self.total);
+            self.drawn += 1;
+            if !self.params.modulations.is_empty() {
+                color = rotate_hue(color, self.modulated(Target::Hue));
+            }
+            if i < self.params.num_centers && !self.seed_colors.is_empty() {
+                // Drawn all the same, so the colors after the seeds don't change.
+                color = self.seed_colors[i % self.seed_colors.len()];
+            }
+            (color, 0)
+        };
+        if let Some(max_age) = self.params.center_max_age {
+            for id in self.centers.expire(i, max_age) {
+                self.lookback.retain(|pixel| pixel.center_id != id);
+            }
+        }
+        if i < self.params.num_centers || self.lookback.is_empty() {
+            // Once every center has expired, a fresh one starts.
+            let n = if i < self.params.num_centers {
+                i
+            } else {
+                self.centers.len()
+            };
+            let palette = self.center_palettes.as_ref().map_or(0, |p| n % p.len());
+            if i < self.params.num_centers && !self.center_points.is_empty() {
+                let point = self.center_points[i % self.center_points.len()];
+                self.insert_at(color, palette, point);
+            } else {
+                self.insert_random(color, palette);
+            }
+            return true;
+        }
+        self.merge_collided();
+        let query = self.query(color);
+        let coverage = self.coverage();
+        let decision = self.strategy.place(
+            &mut Context {
+                params: &self.params,
+                grid: &self.grid,
+                lookback: &mut self.lookback,
+                rngs: &mut self.rngs,
+                centers: &self.centers,
+                regions: &self.regions,
+                open: &self.open_index,
+                walks: self.walks.as_deref_mut(),
+                collisions: self.collisions.as_mut(),
+                query,
+                coverage,
+            },
+            color,
+        );
+        let (loc, center, center_id, parent, exact, steps) = match decision {
+            Decision::Place {
+                loc,
+                center,
+                center_id,
+                parent,
+                exact,
+                steps,
+            } => (loc, center, center_id, parent, exact, steps),
+            Decision::Random { palette } => {
+                if let Fallback::Requeue(limit) = self.params.fallback
+                    && tries < limit
+                    && !refilling
+                {
+                    // Nothing placed this time.
+                    self.i -= 1;
+                    self.requeued
+                        .push_back((color, tries + 1, i + REQUEUE_DELAY));
+                    return true;
+                }
+                self.forced += 1;
+                self.insert_random(color, palette);
+                self.observe_lookback(true);
+                return true;
+            }
+        };
+        self.observe_lookback(false);
+        let center = if self.params.gravity > 0.0 {
+            self.attract(center, center_id)
+        } else {
+            center
+        };
+        let palette = self.centers.get(center_id).map_or(0, |c| c.palette);
+        let color = self.palette_color(palette, color);
+        let pixel = Pixel {
+            color,
+            loc,
+            center,
+            center_id,
+        };
+        self.close(loc);
+        self.place(pixel, Some(parent), exact, steps);
+        true
+    }
+
+    // Merges the pairs of centers whose walks have ended against each other's
+    // pixels often enough, the lighter into the heavier.
+    fn merge_collided(&mut self) {
+        let (Some(after), Some(collisions)) = (self.params.merge_centers, &mut self.collisions)
+        else {
+            return;
+        };
+        for [a, b] in std::mem::take(collisions) {
+            let (Some(ca), Some(cb)) = (self.centers.get(a).copied(), self.centers.get(b).copied())
+            else {
+                continue;
+            };
+            if ca.retired || cb.retired || self.centers.collide(a, b) < after {
+                continue;
+            }
+            let (keep, gone) = if ca.pixels >= cb.pixels {
+                (a, b)
+            } else {
+                (b, a)
+            };
+            let palettes = [keep, gone].map(|id| self.centers.get(id).expect("live").palette);
+            let at = self.centers.merge(keep, gone, self.params.size);
+            if let Some(p) = &mut self.center_palettes {
+                self.centers
+                    .set_palette(keep, p.union(palettes[0], palettes[1]));
+            }
+            self.lookback.reassign(gone, keep, at);
+            self.merged += 1;
+            info!(keep, gone, "merged centers");
+        }
+    }
+
+    // Clears the blocks where too many pixels were placed at random, and
+    // sets their colors to be placed again, walking in around the centroid of
+    // each cleared patch from the pixels bordering it.
+    fn refine(&mut self) {
+        self.refined = true;
+        let (Some(threshold), Some(origins)) = (self.params.refine, self.origins.take()) else {
+            return;
+        };
+        let size = self.params.size;
+        let block = self.params.refine_block;
+        let index = |[x, y]: Location| y * size + x;
+        let mut cleared = vec![false; size * size];
+        for by in (0..size).step_by(block) {
+            for bx in (0..size).step_by(block) {
+                let cells: Vec<Location> = (by..(by + block).min(size))
+                    .flat_map(|y| (bx..(bx + block).min(size)).map(move |x| [x, y]))
+                    .filter(|&loc| origins[index(loc)] != 0)
+                    .collect();
+                let random = cells.iter().filter(|&&loc| origins[index(loc)] == 2);
+                if !cells.is_empty() && random.count() as f64 >= threshold * cells.len() as f64 {
+                    cells.into_iter().for_each(|loc| cleared[index(loc)] = true);
+                }
+            }
+        }
+        let grid = &self.grid;
+        let mut borders = borders(
+            size,
+            |loc| cleared[index(loc)],
+            |loc| !cleared[index(loc)] && grid.is_filled(loc),
+        );
+        for loc in (0..size).flat_map(|y| (0..size).map(move |x| [x, y])) {
+            if cleared[index(loc)] {
+                let pixel = self.grid.clear(loc).expect("placed by the run");
+                self.refill.push(pixel.color);
+                self.open_locs.insert(loc);
+                self.open_index.insert(loc);
+            }
+        }
+        if self.refill.is_empty() {
+            return;
+        }
+        info!(cleared = self.refill.len(), "refining");
+        self.refill.shuffle(self.rngs.get(Stream::Color));
+        self.placements
+            .retain(|placement| !cleared[index(placement.pixel.loc)]);
+        // Walks that find no spot fill the open cell nearest their match
+        // rather than starting over at random.
+        self.params.fallback = Fallback::Nearest;
+        self.lookback.retain(|_| false);
+        borders.shuffle(self.rngs.get(Stream::Layout));
+        let mut patches: Vec<(Location, usize)> = vec![];
+        for (loc, centroid) in borders {
+            let center_id = match patches.iter().find(|&&(c, _)| c == centroid) {
+                Some(&(_, id)) => id,
+                None => {
+                    let id = self.spawn_center(centroid, 0);
+                    patches.push((centroid, id));
+                    id
+                }
+            };
+            let pixel = Pixel {
+                color: self.grid.get(loc).expect("filled").color,
+                loc,
+                center: centroid,
+                center_id,
+            };
+            self.lookback.push(pixel, self.rngs.get(Stream::Lookback));
+        }
+    }
+
+    // Pairs of centers merged so far.
+    pub fn merged(&self) -> usize {
+        self.merged
+    }
+
+    fn observe_lookback(&mut self, forced: bool) {
+        let coverage = self.coverage();
+        if let Some(scale) = &mut self.lookback_scale {
+            scale.observe(forced, coverage);
+        }
+    }
+
+    fn query(&self, color: Color) -> Query {
+        let cursor = self.lookback.front().expect("nonempty").loc;
+        let recent = self.regions.at(cursor, self.params.size);
+        let take = if self.params.modulations.is_empty() {
+            recent.num_lookback
+        } else {
+            let factor = self.modulated(Target::Lookback);
+            ((recent.num_lookback as f64 * factor).round() as usize).max(1)
+        };
+        Query {
+            color,
+            take: match &self.lookback_scale {
+                Some(scale) => scale.apply(take),
+                None => take,
+            },
+            cursor,
+            spatial_weight: self.params.spatial_weight,
+            channel: self.params.mode.match_channel(),
+        }
+    }
+
+    // Takes loc out of the open cells; false if it wasn't open.
+    fn close(&mut self, loc: Location) -> bool {
+        self.open_index.remove(loc);
+        self.open_locs.remove(&loc)
+    }
+
+    fn palette_color(&mut self, palette: usize, color: Color) -> Color {
+        match &mut self.center_palettes {
+            Some(palettes) if !self.refined => {
+                palettes.assign(palette, color, self.rngs.get(Stream::Color))
+            }
+            _ => color,
+        }
+    }
+
+    // Starts a center now, with an orbit shape of its own if asked.
+    fn spawn_center(&mut self, position: Location, palette: usize) -> usize {
+        let id = self.centers.spawn(position, palette, self.i);
+        if let Some(stretch) = self.params.center_stretch {
+            let rng = self.rngs.get(Stream::Layout);
+            let tilt = self.params.center_tilt.draw(rng).to_radians();
+            self.centers.set_shape(id, tilt, stretch.draw(rng));
+        }
+        if let Some(ring) = self.params.ring_width {
+            let ring = ring.draw(self.rngs.get(Stream::Layout));
+            self.centers.set_ring(id, ring);
+        }
+        id
+    }
+
+    // Starts a center at a laid out point, with its first pixel placed near
+    // it, or anywhere if that spot is taken.
+    fn insert_at(&mut self, color: Color, palette: usize, point: Location) {
+        let size = self.params.size;
+        let width = ((size as f64 * self.params.start_spread) as usize).max(1);
+        let canvas = self.params.canvas;
+        let loc = canvas.layout(point, width, size, self.rngs.get(Stream::Layout));
+        if !self.close(loc) {
+            self.insert_random(color, palette);
+            return;
+        }
+        let color = self.palette_color(palette, color);
+        let center_id = self.spawn_center(point, palette);
+        let pixel = Pixel {
+            color,
+            loc,
+            center: point,
+            center_id,
+        };
+        self.place(pixel, None, None, 0);
+    }
+
+    fn insert_random(&mut self, color: Color, palette: usize) {
+        let size = self.params.size;
+        let rng = self.rngs.get(Stream::Locations);
+        let loc = match &self.sampler {
+            Some(sampler) => {
+                let loc = sampler.sample(rng).expect("nonempty");
+                self.open_locs.remove(&loc);
+                loc
+            }
+            None => self.open_locs.remove_random(rng).expect("nonempty"),
+        };
+        self.open_index.remove(loc);
+        let color = self.palette_color(palette, color);
+        //let center = [rng.random_range(0..size), rng.random_range(0..size)];
+        let start_spread = self.params.start_spread * self.modulated(Target::StartSpread);
+        let width = (size as f64 * start_spread) as usize;
+        let canvas = self.params.canvas;
+        let rng = self.rngs.get(Stream::Layout);
+        let density = self.params.density.as_ref();
+        let guide = self.guide.as_ref();
+        let centers = &self.centers;
+        let center = if density.is_none() && guide.is_none() {
+            let mut center = canvas.layout(loc, width, size, rng);
+            for _ in 1..CENTER_GAP_TRIES {
+                if centers.clear_of(center, None) {
+                    break;
+                }
+                center = canvas.layout(loc, width, size, rng);
+            }
+            center
+        } else {
+            // Of a few candidate centers, prefer denser ones, and ones where
+            // the coarse pass has a similar color.
+            let candidates: Vec<Location> = (0..CENTER_CANDIDATES)
+                .map(|_| canvas.layout(loc, width, size, rng))
+                .collect();
+            // Candidates within the gap of another center count only if
+            // all are.
+            let crowded = !candidates.iter().any(|&c| centers.clear_of(c, None));
+            let weights: Vec<f64> = candidates
+                .iter()
+                .map(|&c| {
+                    if !crowded && !centers.clear_of(c, None) {
+                        return 0.0;
+                    }
+                    let density = density.map_or(1.0, |d| d.at(c, size) + 0.001);
+                    let similarity = guide.map_or(1.0, |g| {
+                        let near = g.get_pixel(c[0] as u32, c[1] as u32).0;
+                        1.0 / (1.0 + color_dist_sq(color, near) as f64 / 1024.0)
+                    });
+                    density * similarity
+                })
+                .collect();
+            let mut target = rng.random_range(0.0..weights.iter().sum::<f64>());
+            *candidates
+                .iter()
+                .zip(&weights)
+                .find(|(_, w)| {
+                    target -= **w;
+                    target < 0.0
+                })
+                .map_or(&candidates[CENTER_CANDIDATES - 1], |(c, _)| c)
+        };
+        let center_id = self.spawn_center(center, palette);
+        let pixel = Pixel {
+            color,
+            loc,
+            center,
+            center_id,
+        };
+        self.place(pixel, None, None, 0);
+    }
+
+    // exact is the orbit's position at the pixel, if it came from a walk, and
+    // steps the cells the walk tried.
+    fn place(
+        &mut self,
+        mut pixel: Pixel,
+        parent: Option<Location>,
+        exact: Option<[f64; 2]>,
+        steps: usize,
+    ) {
+        let loc = pixel.loc;
+        if let Some(guide) = &self.guide
+            && !self.refined
+        {
+            let near = guide.get_pixel(loc[0] as u32, loc[1] as u32).0;
+            pixel.color = lerp(pixel.color, near, self.params.multiscale.min(1.0));
+        }
+        // Only the image gets the grain; matches are made against the color
+        // as drawn.
+        let shown = match &mut self.grain {
+            Some(rng) if !self.refined => Pixel {
+                color: grain(pixel.color, self.params.color_jitter, rng),
+                ..pixel
+            },
+            _ => pixel,
+        };
+        self.grid.set(loc, shown);
+        if let Some(origins) = &mut self.origins {
+            origins[loc[1] * self.params.size + loc[0]] = if parent.is_some() { 1 } else { 2 };
+        }
+        if let Some(sampler) = &mut self.sampler {
+            sampler.remove(loc);
+        }
+        if self.record || !self.observers.is_empty() {
+            let placement = Placement {
+                pixel: shown,
+                parent,
+                pos: exact.unwrap_or(loc.map(|c| c as f64 + 0.5)),
+                steps,
+            };
+            for observer in &mut self.observers {
+                observer.on_placement(&placement);
+                if parent.is_none() {
+                    observer.on_random_insert(&shown);
+                }
+            }
+            if self.record {
+                self.placements.push(placement);
+            }
+        }
+        self.lookback.push(pixel, self.rngs.get(Stream::Lookback));
+        let size = self.params.size;
+        let drift = self.params.center_drift.map(|drift| {
+            let at = self.centers.drifted(pixel.center_id, pixel.center, size);
+            (drift, at)
+        });
+        let pixels =
+            self.centers
+                .attach(pixel.center_id, drift, size, self.rngs.get(Stream::Jitter));
+        if self.params.center_max_pixels == Some(pixels) {
+            self.centers.retire(pixel.center_id);
+            self.lookback.retain(|p| p.center_id != pixel.center_id);
+        }
+    }
+
+    // Pulls a jittered center toward the heaviest centers, each by a fraction
+    // gravity * pixels / distance^2 of the way, softened near each center
+    // and at most all the way.
+    fn attract(&mut self, center: Location, center_id: usize) -> Location {
+        let size = self.params.size;
+        if self.heavy.is_empty() || self.i.is_multiple_of(HEAVY_REFRESH) {
+            self.heavy = self
+                .centers
+                .heaviest(HEAVY_CENTERS)
+                .into_iter()
+                .map(|id| {
+                    let c = self.centers.get(id).expect("live center");
+                    let at = self.centers.drifted(id, c.position, size);
+                    ([at[0] as f64, at[1] as f64], c.pixels as f64)
+                })
+                .collect();
+        }
+        let at = self.centers.drifted(center_id, center, size);
+        let soft = (size as f64 / 50.0).powi(2).max(1.0);
+        let mut shift = [0.0; 2];
+        for &(pos, mass) in &self.heavy {
+            let d = [pos[0] - at[0] as f64, pos[1] - at[1] as f64];
+            let pull = (self.params.gravity * mass / (d[0] * d[0] + d[1] * d[1] + soft)).min(1.0);
+            shift[0] += d[0] * pull;
+            shift[1] += d[1] * pull;
+        }
+        [0, 1].map(|k| {
+            (center[k] as f64 + shift[k])
+                .round()
+                .clamp(0.0, size as f64) as usize
+        })
+    }
+
+    fn modulated(&self, target: Target) -> f64 {
+        modulate(&self.params.modulations, target, self.coverage())
+    }
+
+    // Calls observer back from every step from now on.
+    pub fn observe(&mut self, observer: Box<dyn Observer>) {
+        self.observers.push(observer);
+    }
+
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    // Everything the run has done so far, in the order read_scene reads it.
+    // What the parameters alone determine is left out.
+    pub fn write_scene(&self, w: &mut SceneWriter) {
+        w.usize(self.params.size);
+        for n in [self.i, self.drawn, self.forced, self.merged] {
+            w.usize(n);
+        }
+        let rng = |w: &mut SceneWriter, (kind, seed, used): (RngKind, u64, u64)| {
+            w.str(kind.name());
+            w.u64(seed);
+            w.u64(used);
+        };
+        let states = self.rngs.states();
+        w.usize(states.len());
+        states.into_iter().for_each(|state| rng(w, state));
+        w.bool(self.grain.is_some());
+        if let Some(grain) = &self.grain {
+            rng(w, grain.state());
+        }
+        w.usize(self.grid.occupancy().count());
+        self.grid.pixels().for_each(|pixel| w.pixel(pixel));
+        w.usize(self.open_locs.len());
+        self.open_locs.iter().for_each(|&loc| w.location(loc));
+        w.bool(self.sampler.is_some());
+        if let Some(sampler) = &self.sampler {
+            sampler.write_scene(w);
+        }
+        self.lookback.write_scene(w);
+        w.bool(self.lookback_scale.is_some());
+        if let Some(scale) = &self.lookback_scale {
+            scale.state().into_iter().for_each(|v| w.f64(v));
+        }
+        self.centers.write_scene(w);
+        w.bool(self.center_palettes.is_some());
+        if let Some(palettes) = &self.center_palettes {
+            palettes.write_scene(w);
+        }
+        w.usize(self.requeued.len());
+        for &(color, tries, at) in &self.requeued {
+            w.color(color);
+            w.usize(tries);
+            w.usize(at);
+        }
+        w.usize(self.heavy.len());
+        for &(pos, mass) in &self.heavy {
+            pos.into_iter().for_each(|v| w.f64(v));
+            w.f64(mass);
+        }
+        w.usize(self.center_points.len());
+        self.center_points.iter().for_each(|&loc| w.location(loc));
+        w.bool(self.walks.is_some());
+        if let Some(walks) = &self.walks {
+            walks.iter().for_each(|&n| w.u32(n));
+        }
+        w.bool(self.collisions.is_some());
+        if let Some(collisions) = &self.collisions {
+            w.usize(collisions.len());
+            collisions.iter().flatten().for_each(|&id| w.usize(id));
+        }
+    }
+
+    // Takes up a run where a scene left it. The generator must be fresh, from
+    // parameters with the scene's size; with reseed its random streams are
+    // kept rather than restored.
+    pub fn read_scene(&mut self, r: &mut SceneReader, reseed: bool) -> Result<(), String> {
+        let size = r.usize()?;
+        if size != self.params.size {
+            return Err(format!(
+                "the scene is {size} pixels across; resuming cannot change --size"
+            ));
+        }
+        self.i = r.usize()?;
+        self.drawn = r.usize()?;
+        self.forced = r.usize()?;
+        self.merged = r.usize()?;
+        let rng = |r: &mut SceneReader| -> Result<(RngKind, u64, u64), String> {
+            Ok((r.str()?.parse()?, r.u64()?, r.u64()?))
+        };
+        let states = (0..r.count()?)
+            .map(|_| rng(r))
+            .collect::<Result<Vec<_>, _>>()?;
+        let grain = match r.bool()? {
+            true => Some(rng(r)?),
+            false => None,
+        };
+        if !reseed {
+            let fresh = self.rngs.states();
+            if states.len() != fresh.len() {
+                return Err("resuming cannot change --streams without a new --seed".to_string());
+            }
+            if states.iter().zip(&fresh).any(|(a, b)| a.0 != b.0) {
+                return Err("resuming cannot change --rng without a new --seed".to_string());
+            }
+            self.rngs = Rngs::resumed(&states);
+            if let (Some(rng), Some((kind, seed, used))) = (&mut self.grain, grain) {
+                *rng = SeededRng::resumed(kind, seed, used);
+            }
+        }
+        for _ in 0..r.count()? {
+            let pixel = r.pixel()?;
+            if pixel.loc.iter().any(|&c| c >= size) {
+                return Err("the scene has a pixel off the canvas".to_string());
+            }
+            self.grid.set(pixel.loc, pixel);
+        }
+        let open = (0..r.count()?)
+            .map(|_| r.location())
+            .collect::<Result<Vec<_>, _>>()?;
+        if open.iter().any(|loc| loc.iter().any(|&c| c >= size)) {
+            return Err("the scene has an open cell off the canvas".to_string());
+        }
+        self.open_index = OpenIndex::new(size, &open);
+        self.open_locs = VecMap::new_from_vec(open);
+        if r.present(self.sampler.is_some(), "--sampling")? {
+            self.sampler.as_mut().expect("present").read_scene(r)?;
+        }
+        self.lookback.read_scene(r)?;
+        if r.bool()? {
+            let state = [r.f64()?, r.f64()?];
+            if let Some(scale) = &mut self.lookback_scale {
+                scale.restore(state);
+            }
+        }
+        self.centers = Centers::read_scene(r)?.with_gap(self.params.center_gap);
+        // Kept base pixels name center 0 even before any center starts.
+        let known = |pixel: &Pixel| pixel.center_id < self.centers.len().max(1);
+        if !self.grid.pixels().all(known) || !self.lookback.all(known) {
+            return Err(UNKNOWN_CENTER.to_string());
+        }
+        if r.present(self.center_palettes.is_some(), "--center-palettes")? {
+            self.center_palettes
+                .as_mut()
+                .expect("present")
+                .read_scene(r)?;
+        }
+        self.requeued = (0..r.count()?)
+            .map(|_| Ok((r.color()?, r.usize()?, r.usize()?)))
+            .collect::<Result<_, String>>()?;
+        self.heavy = (0..r.count()?)
+            .map(|_| Ok(([r.f64()?, r.f64()?], r.f64()?)))
+            .collect::<Result<_, String>>()?;
+        self.center_points = (0..r.count()?)
+            .map(|_| r.location())
+            .collect::<Result<_, _>>()?;
+        if r.bool()? {
+            let walks = (0..size * size)
+                .map(|_| r.u32())
+                .collect::<Result<Vec<_>, _>>()?;
+            if let Some(own) = &mut self.walks {
+                *own = walks;
+            }
+        }
+        if r.bool()? {
+            let collisions = (0..r.count()?)
+                .map(|_| Ok([r.usize()?, r.usize()?]))
+                .collect::<Result<Vec<_>, String>>()?;
+            if collisions
+                .iter()
+                .flatten()
+                .any(|&id| id >= self.centers.len())
+            {
+                return Err(UNKNOWN_CENTER.to_string());
+            }
+            if let Some(own) = &mut self.collisions {
+                *own = collisions;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn centers(&self) -> &Centers {
+        &self.centers
+    }
+
+    pub fn size(&self) -> usize {
+        self.params.size
+    }
+
+    pub fn pixel(&self, loc: Location) -> Option<&Pixel> {
+        self.grid.get(loc)
+    }
+
+    // Whether loc is still waiting to be filled.
+    pub fn is_open(&self, loc: Location) -> bool {
+        self.open_locs.contains(&loc)
+    }
+
+    // Image row y, as RGB bytes, with the phase applied. Leaves out the
+    // rest of the generator, which can't be shared between threads.
+    fn row_filler(&self) -> impl Fn(usize, &mut [u8]) + Sync + '_ {
+        let (grid, phase) = (&self.grid, self.params.phase);
+        let background = self.params.unfilled.color();
+        move |y, row| {
+            for (x, rgb) in row.chunks_exact_mut(3).enumerate() {
+                let pixel = grid.get([x, y]);
+                rgb.copy_from_slice(&pixel.map_or(background, |p| shown(phase, p.color)));
+            }
+        }
+    }
+
+    // The rows of the canvas, assembled in bands of rows, one per core. A
+    // panorama's image is only its band.
+    pub fn image(&self) -> RgbImage {
+        let size = self.params.size;
+        let rows = self.params.canvas.rows(size);
+        let mut img: RgbImage = ImageBuffer::new(size as u32, rows.len() as u32);
+        let row_len = size * 3;
+        if row_len == 0 {
+            return img;
+        }
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let band = rows.len().div_ceil(threads).max(PARALLEL_BAND);
+        let fill_row = &self.row_filler();
+        let top = rows.start;
+        if band >= rows.len() {
+            for (y, row) in img.chunks_exact_mut(row_len).enumerate() {
+                fill_row(top + y, row);
+            }
+            return img;
+        }
+        std::thread::scope(|scope| {
+            for (k, rows) in img.chunks_mut(band * row_len).enumerate() {
+                scope.spawn(move || {
+                    for (i, row) in rows.chunks_exact_mut(row_len).enumerate() {
+                        fill_row(top + k * band + i, row);
+                    }
+                });
+            }
+        });
+        img
+    }
+
+    // Encodes the image as PNG straight from the grid: a row at a time, or
+    // in bands on every core for large images.
+    #[cfg(feature = "files")]
+    pub fn save_png(&self, path: &str, metadata: &[(String, String)]) -> Result<(), String> {
+        let size = self.params.size as u32;
+        let thumbnail = self.params.thumbnail;
+        let fill_row = self.row_filler();
+        if encodes_in_parallel(size, size) {
+            return save_png_bands(path, size, size, metadata, thumbnail, |y, row| {
+                fill_row(y as usize, row)
+            });
+        }
+        save_png_rows(path, size, size, metadata, thumbnail, |y, row| {
+            fill_row(y as usize, row)
+        })
+    }
+
+    // Colors of the placed pixels, in no particular order.
+    pub fn placed_colors(&self) -> impl Iterator<Item = Color> + '_ {
+        self.grid.pixels().map(|pixel| pixel.color)
+    }
+
+    // Which cells of the image hold a pixel, indexed by x then y.
+    pub fn filled(&self) -> Vec<Vec<bool>> {
+        let size = self.params.size;
+        let rows = self.params.canvas.rows(size);
+        (0..size)
+            .map(|x| rows.clone().map(|y| self.grid.is_filled([x, y])).collect())
+            .collect()
+    }
+
+    // Which cells hold a pixel, base pixels included, for counts over areas.
+    pub fn occupancy(&self) -> &Occupancy {
+        self.grid.occupancy()
+    }
+
+    // Fraction of each region's cells that hold a pixel, row-major like the
+    // regions themselves.
+    pub fn region_coverage(&self) -> Vec<f64> {
+        let occupancy = self.grid.occupancy();
+        self.regions
+            .bounds(self.params.size)
+            .into_iter()
+            .map(|(xs, ys)| {
+                let cells = xs.len() * ys.len();
+                if cells == 0 {
+                    return 1.0;
+                }
+                occupancy.count_in(xs, ys) as f64 / cells as f64
+            })
+            .collect()
+    }
+
+    // How often walks passed through each cell, on a log scale from black
+    // for none to the top of the magma colormap for the busiest cell.
+    pub fn walk_map(&self) -> Option<RgbImage> {
+        let walks = self.walks.as_ref()?;
+        let size = self.params.size;
+        let rows = self.params.canvas.rows(size);
+        let most = (*walks.iter().max().unwrap_or(&0) as f64).ln_1p().max(1e-9);
+        Some(ImageBuffer::from_fn(
+            size as u32,
+            rows.len() as u32,
+            |x, y| {
+                let visits = walks[x as usize * size + rows.start + y as usize] as f64;
+                image::Rgb(sample_colormap(MAGMA, visits.ln_1p() / most))
+            },
+        ))
+    }
+
+    // The recorded placements, for when the image has been saved already.
+    pub fn into_placements(self) -> Vec<Placement> {
+        self.placements
+    }
+
+    // The run as its image shows it, with a panorama's placements moved up
+    // with its band.
+    pub fn into_run(self) -> Run {
+        let canvas = self.params.canvas;
+        Run {
+            image: self.image(),
+            filled: self.filled(),
+            placements: if canvas.wraps() {
+                canvas.crop_placements(&self.placements, self.params.size)
+            } else {
+                self.placements
+            },
+        }
+    }
+}
+
+// A hue phase turns the finished colors, leaving the structure alone.
+fn shown(phase: Option<Phase>, color: Color) -> Color {
+    match phase {
+        Some(Phase::Hue(turns)) if turns != 0.0 => rotate_hue(color, turns),
+        _ => color,
+    }
+}
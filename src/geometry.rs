@@ -0,0 +1,326 @@
+use std::f64::consts::TAU;
+
+use crate::Location;
+use crate::walk::Neighborhood;
+
+// The rule a walk follows around a center. At each step the walker moves to
+// the neighboring lattice point with the lowest score.
+pub trait Orbit {
+    fn score(&self, p: [isize; 2], step: usize) -> f64;
+    // The point of the ideal curve that the lattice point p stands for.
+    fn point(&self, p: [isize; 2], step: usize) -> [f64; 2];
+    // The point of the ideal curve t steps into the walk, for walks that
+    // leave the lattice.
+    fn curve(&self, t: f64) -> [f64; 2];
+    // The walk gives up after this many steps.
+    fn max_steps(&self) -> f64;
+}
+
+// The axes an orbit is measured along: turned by a tilt, kept as its cosine
+// and sine, with the second scaled by aspect. A nonzero ring snaps the
+// orbit's radius to the nearest multiple of it.
+#[derive(Debug, Clone, Copy)]
+pub struct Frame {
+    aspect: f64,
+    turn: [f64; 2],
+    ring: f64,
+}
+
+impl Frame {
+    pub fn new(aspect: f64, tilt: f64, ring: f64) -> Self {
+        Self {
+            aspect,
+            turn: [tilt.cos(), tilt.sin()],
+            ring,
+        }
+    }
+
+    // The radius an orbit through a point at radius keeps to.
+    fn snap(self, radius: f64) -> f64 {
+        if self.ring > 0.0 {
+            (radius / self.ring).round().max(1.0) * self.ring
+        } else {
+            radius
+        }
+    }
+
+    // An offset from the center in the frame.
+    fn measure(self, d: [f64; 2]) -> [f64; 2] {
+        let [cos, sin] = self.turn;
+        [
+            d[0] * cos + d[1] * sin,
+            (d[1] * cos - d[0] * sin) * self.aspect,
+        ]
+    }
+
+    // The offset from the center of a point given in the frame.
+    fn offset(self, f: [f64; 2]) -> [f64; 2] {
+        let [cos, sin] = self.turn;
+        let v = f[1] / self.aspect;
+        [f[0] * cos - v * sin, f[0] * sin + v * cos]
+    }
+}
+
+// The original rule: stay as close as possible to the squared distance of the
+// starting point from the center, measured in the frame.
+// With a nonzero drift the target radius changes by `drift` pixels per step,
+// spiraling outward when positive and inward when negative.
+pub struct Circle {
+    center: [f64; 2],
+    frame: Frame,
+    radius: f64,
+    drift: f64,
+    // The start's angle in the frame.
+    start_angle: f64,
+}
+
+impl Circle {
+    pub fn new(center: [f64; 2], start: [f64; 2], frame: Frame, drift: f64) -> Self {
+        let [u, v] = frame.measure([0, 1].map(|k| start[k] - center[k]));
+        let mut radius = u * u + v * v;
+        if frame.ring > 0.0 {
+            radius = frame.snap(radius.sqrt()).powi(2);
+        }
+        Self {
+            center,
+            frame,
+            radius,
+            drift,
+            start_angle: v.atan2(u),
+        }
+    }
+    fn dist(&self, p: [isize; 2]) -> f64 {
+        let d = [0, 1].map(|k| p[k] as f64 - self.center[k]);
+        let [u, v] = self.frame.measure(d);
+        u * u + v * v
+    }
+}
+
+impl Orbit for Circle {
+    fn score(&self, p: [isize; 2], step: usize) -> f64 {
+        if self.drift == 0.0 {
+            return (self.dist(p) - self.radius).abs();
+        }
+        let radius = (self.radius.sqrt() + self.drift * step as f64).max(0.0);
+        (self.dist(p) - radius * radius).abs()
+    }
+    // p moved along its ray from the center onto the circle.
+    fn point(&self, p: [isize; 2], step: usize) -> [f64; 2] {
+        let radius = (self.radius.sqrt() + self.drift * step as f64).max(0.0);
+        let dist = self.dist(p).sqrt();
+        let p = [p[0] as f64, p[1] as f64];
+        if dist == 0.0 {
+            return p;
+        }
+        let k = radius / dist;
+        [0, 1].map(|i| self.center[i] + (p[i] - self.center[i]) * k)
+    }
+    // Turning about a pixel per step at the starting radius.
+    fn curve(&self, t: f64) -> [f64; 2] {
+        let start = self.radius.sqrt();
+        let radius = (start + self.drift * t).max(0.0);
+        let angle = self.start_angle + t / start.max(1.0);
+        let d = self
+            .frame
+            .offset([radius * angle.cos(), radius * angle.sin()]);
+        [0, 1].map(|k| self.center[k] + d[k])
+    }
+    fn max_steps(&self) -> f64 {
+        8.0 * self.radius
+    }
+}
+
+// The walker circles a point which itself circles the center.
+// The secondary circle has `ratio` times the starting radius and turns
+// `speed` times as fast as the primary one, tracing rosettes and loops,
+// starting `phase` radians ahead. Drift scales the whole figure, and the
+// frame stretches it, as for Circle.
+pub struct Epicycle {
+    center: [f64; 2],
+    frame: Frame,
+    primary: f64,
+    secondary: f64,
+    speed: f64,
+    drift: f64,
+    phase: f64,
+    start_angle: f64,
+    // Primary angle advanced per step, so the target moves about a pixel.
+    step_angle: f64,
+}
+
+impl Epicycle {
+    pub fn new(
+        center: [f64; 2],
+        start: [f64; 2],
+        frame: Frame,
+        ratio: f64,
+        speed: f64,
+        drift: f64,
+        phase: f64,
+    ) -> Self {
+        let d = [0, 1].map(|k| start[k] - center[k]);
+        let [dx, dy] = frame.measure(d);
+        let radius = frame.snap(dx.hypot(dy));
+        let secondary = radius * ratio.clamp(0.0, 1.0);
+        let primary = radius - secondary;
+        Self {
+            center,
+            frame,
+            primary,
+            secondary,
+            speed,
+            drift,
+            phase,
+            start_angle: dy.atan2(dx),
+            step_angle: 1.0 / (primary + secondary * speed.abs()).max(1.0),
+        }
+    }
+}
+
+impl Epicycle {
+    // Where the walker is heading at this step, relative to the center, in
+    // the orbit's frame.
+    fn target(&self, step: f64) -> [f64; 2] {
+        let theta = step * self.step_angle;
+        let a = self.start_angle + theta;
+        let b = self.start_angle + self.phase + self.speed * theta;
+        let radius = self.primary + self.secondary;
+        let scale = if radius > 0.0 {
+            ((radius + self.drift * step) / radius).max(0.0)
+        } else {
+            1.0
+        };
+        [
+            scale * (self.primary * a.cos() + self.secondary * b.cos()),
+            scale * (self.primary * a.sin() + self.secondary * b.sin()),
+        ]
+    }
+}
+
+impl Orbit for Epicycle {
+    fn score(&self, p: [isize; 2], step: usize) -> f64 {
+        let target = self.target(step as f64);
+        let d = [0, 1].map(|k| p[k] as f64 - self.center[k]);
+        let [u, v] = self.frame.measure(d);
+        let [du, dv] = [u - target[0], v - target[1]];
+        du * du + dv * dv
+    }
+    // The target is the ideal point, whichever lattice point is nearest it.
+    fn point(&self, _: [isize; 2], step: usize) -> [f64; 2] {
+        self.curve(step as f64)
+    }
+    fn curve(&self, t: f64) -> [f64; 2] {
+        let d = self.frame.offset(self.target(t));
+        [0, 1].map(|k| self.center[k] + d[k])
+    }
+    fn max_steps(&self) -> f64 {
+        TAU / self.step_angle
+    }
+}
+
+impl<O: Orbit + ?Sized> Orbit for &O {
+    fn score(&self, p: [isize; 2], step: usize) -> f64 {
+        (**self).score(p, step)
+    }
+    fn point(&self, p: [isize; 2], step: usize) -> [f64; 2] {
+        (**self).point(p, step)
+    }
+    fn curve(&self, t: f64) -> [f64; 2] {
+        (**self).curve(t)
+    }
+    fn max_steps(&self) -> f64 {
+        (**self).max_steps()
+    }
+}
+
+// The lattice points a walk along an orbit passes through from start: each
+// step moves to the neighboring point the orbit scores lowest, never straight
+// back. Points are unbounded, so a walk keeping to a canvas folds or wraps
+// them itself. Ends on returning to start, or once the orbit's max_steps have
+// been run, a leap counting as one step.
+pub struct OrbitWalker<O> {
+    orbit: O,
+    start: [isize; 2],
+    last: [isize; 2],
+    cur: [isize; 2],
+    neighborhood: Neighborhood,
+    max_steps: f64,
+    // Points passed so far, and those of them leapt beyond the first of
+    // each leap, which cost nothing.
+    count: usize,
+    free: usize,
+}
+
+impl OrbitWalker<Circle> {
+    // Around the circle about center through start, its distances measured in
+    // metric.
+    pub fn new(center: Location, start: Location, metric: Frame) -> Self {
+        let [c, s] = [center, start].map(|p| p.map(|v| v as f64));
+        Self::along(Circle::new(c, s, metric, 0.0), start)
+    }
+}
+
+impl<O: Orbit> OrbitWalker<O> {
+    pub fn along(orbit: O, start: Location) -> Self {
+        let start = [start[0] as isize, start[1] as isize];
+        Self {
+            max_steps: orbit.max_steps(),
+            orbit,
+            start,
+            last: start,
+            cur: start,
+            neighborhood: Neighborhood::Eight,
+            count: 0,
+            free: 0,
+        }
+    }
+
+    pub fn neighborhood(self, neighborhood: Neighborhood) -> Self {
+        Self {
+            neighborhood,
+            ..self
+        }
+    }
+
+    pub fn orbit(&self) -> &O {
+        &self.orbit
+    }
+
+    // The orbit's step at the last point given, as Orbit::point takes it.
+    pub fn step(&self) -> usize {
+        self.count * self.neighborhood.reach()
+    }
+
+    // Passes the next n points without giving them, for a single step of
+    // the budget. False if the walk ends on the way.
+    pub fn leap(&mut self, n: usize) -> bool {
+        self.free += n.saturating_sub(1);
+        (0..n).all(|_| self.next().is_some())
+    }
+}
+
+impl<O: Orbit> Iterator for OrbitWalker<O> {
+    type Item = [isize; 2];
+
+    fn next(&mut self) -> Option<[isize; 2]> {
+        let reach = self.neighborhood.reach();
+        let j = self.count + 1;
+        let t = j * reach;
+        let cur = self.cur;
+        let next = self
+            .neighborhood
+            .offsets()
+            .iter()
+            .map(|d| [cur[0] + d[0], cur[1] + d[1]])
+            .filter(|&n| n != self.last)
+            .min_by(|&a, &b| self.orbit.score(a, t).total_cmp(&self.orbit.score(b, t)))
+            .expect("Still one left");
+        if next == self.start || (j.saturating_sub(self.free) * reach) as f64 > self.max_steps {
+            return None;
+        }
+        self.count = j;
+        self.last = cur;
+        self.cur = next;
+        Some(next)
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::VecDeque;
+
+use crate::{Color, Pixel};
+
+const WORKGROUP: usize = 256;
+
+// Lookback queries at least this deep are answered on the GPU.
+pub const MIN_TAKE: usize = 8192;
+
+// A mirror of the lookback buffer in GPU memory. Each entry occupies a slot
+// holding its packed color and an age, counting up from 1; age 0 is empty.
+// Newer entries win ties, matching the CPU search.
+pub struct GpuLookback {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    bind_group: wgpu::BindGroup,
+    slot_buffer: wgpu::Buffer,
+    query: wgpu::Buffer,
+    partials: wgpu::Buffer,
+    readback: wgpu::Buffer,
+    // Slot and age of each lookback entry, in lookback order.
+    entries: VecDeque<(u32, u32)>,
+    free: Vec<u32>,
+    pixels: Vec<Option<Pixel>>,
+    next_age: u32,
+}
+
+fn pack(color: Color) -> u32 {
+    color[0] as u32 | (color[1] as u32) << 8 | (color[2] as u32) << 16
+}
+
+impl GpuLookback {
+    // None if no adapter is available, so the caller can stay on the CPU.
+    pub fn new(capacity: usize) -> Option<Self> {
+        pollster::block_on(Self::init(capacity + 1))
+    }
+
+    async fn init(num_slots: usize) -> Option<Self> {
+        let instance = wgpu::Instance::default();
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .await
+            .ok()?;
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor::default())
+            .await
+            .ok()?;
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("nearest"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("nearest.wgsl").into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("nearest"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        let num_groups = num_slots.div_ceil(WORKGROUP);
+        let buffer = |label, size: usize, usage| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: size as u64,
+                usage,
+                mapped_at_creation: false,
+            })
+        };
+        use wgpu::BufferUsages as U;
+        let slot_buffer = buffer("slots", num_slots * 8, U::STORAGE | U::COPY_DST);
+        let query = buffer("query", 16, U::UNIFORM | U::COPY_DST);
+        let partials = buffer("partials", num_groups * 16, U::STORAGE | U::COPY_SRC);
+        let readback = buffer("readback", num_groups * 16, U::MAP_READ | U::COPY_DST);
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("nearest"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: slot_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: query.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: partials.as_entire_binding(),
+                },
+            ],
+        });
+        Some(Self {
+            device,
+            queue,
+            pipeline,
+            bind_group,
+            slot_buffer,
+            query,
+            partials,
+            readback,
+            entries: VecDeque::with_capacity(num_slots),
+            free: (0..num_slots as u32).rev().collect(),
+            pixels: vec![None; num_slots],
+            next_age: 1,
+        })
+    }
+
+    fn write_slot(&self, slot: u32, color: u32, age: u32) {
+        let data: Vec<u8> = [color, age].iter().flat_map(|v| v.to_le_bytes()).collect();
+        self.queue
+            .write_buffer(&self.slot_buffer, slot as u64 * 8, &data);
+    }
+
+    pub fn push_front(&mut self, pixel: Pixel) {
+        let slot = self.free.pop().expect("a free slot");
+        let age = self.next_age;
+        self.next_age += 1;
+        self.write_slot(slot, pack(pixel.color), age);
+        self.pixels[slot as usize] = Some(pixel);
+        self.entries.push_front((slot, age));
+    }
+
+    // Changes what each pixel records besides its color.
+    pub fn update(&mut self, f: &dyn Fn(&mut Pixel)) {
+        self.pixels.iter_mut().flatten().for_each(f);
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        let (slot, _) = self.entries.remove(index).expect("in range");
+        self.write_slot(slot, 0, 0);
+        self.pixels[slot as usize] = None;
+        self.free.push(slot);
+    }
+
+    // Best match for color among the newest `take` entries.
+    pub fn nearest(&self, color: Color, take: usize) -> Option<&Pixel> {
+        let take = take.min(self.entries.len());
+        if take == 0 {
+            return None;
+        }
+        let min_age = self.entries[take - 1].1;
+        let count = self.pixels.len() as u32;
+        let query: Vec<u8> = [pack(color), min_age, count, 0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        self.queue.write_buffer(&self.query, 0, &query);
+        let num_groups = self.pixels.len().div_ceil(WORKGROUP);
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&Default::default());
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &self.bind_group, &[]);
+            pass.dispatch_workgroups(num_groups as u32, 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&self.partials, 0, &self.readback, 0, None);
+        self.queue.submit([encoder.finish()]);
+        let slice = self.readback.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |result| result.expect("mapped"));
+        self.device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .expect("gpu finished");
+        let best = {
+            let data = slice.get_mapped_range().expect("mapped");
+            data.chunks_exact(16)
+                .map(|c| {
+                    let word =
+                        |k: usize| u32::from_le_bytes(c[4 * k..4 * k + 4].try_into().unwrap());
+                    (word(0), word(1), word(2))
+                })
+                .filter(|&(_, age, _)| age != 0)
+                .min_by_key(|&(dist, age, _)| (dist, std::cmp::Reverse(age)))
+        };
+        self.readback.unmap();
+        let (_, _, slot) = best?;
+        self.pixels[slot as usize].as_ref()
+    }
+}
@@ -0,0 +1,165 @@
+use std::ops::Range;
+
+use crate::{Location, Pixel};
+
+// Which cells of a square canvas hold a pixel, a bit per cell. Each column
+// of constant x starts on a word, so counts over a rectangle are popcounts.
+#[derive(Debug, Clone)]
+pub struct Occupancy {
+    size: usize,
+    // Words per column.
+    stride: usize,
+    bits: Vec<u64>,
+    filled: usize,
+}
+
+impl Occupancy {
+    pub fn new(size: usize) -> Self {
+        let stride = size.div_ceil(64);
+        Self {
+            size,
+            stride,
+            bits: vec![0; size * stride],
+            filled: 0,
+        }
+    }
+
+    pub fn is_filled(&self, loc: Location) -> bool {
+        self.bits[loc[0] * self.stride + loc[1] / 64] & (1 << (loc[1] % 64)) != 0
+    }
+
+    // Returns whether loc was empty before.
+    pub fn fill(&mut self, loc: Location) -> bool {
+        let word = &mut self.bits[loc[0] * self.stride + loc[1] / 64];
+        let bit = 1 << (loc[1] % 64);
+        if *word & bit != 0 {
+            return false;
+        }
+        *word |= bit;
+        self.filled += 1;
+        true
+    }
+
+    // Returns whether loc was filled before.
+    pub fn clear(&mut self, loc: Location) -> bool {
+        let word = &mut self.bits[loc[0] * self.stride + loc[1] / 64];
+        let bit = 1 << (loc[1] % 64);
+        if *word & bit == 0 {
+            return false;
+        }
+        *word &= !bit;
+        self.filled -= 1;
+        true
+    }
+
+    pub fn count(&self) -> usize {
+        self.filled
+    }
+
+    // Filled cells with x in xs and y in ys.
+    pub fn count_in(&self, xs: Range<usize>, ys: Range<usize>) -> usize {
+        let xs = xs.start..xs.end.min(self.size);
+        let ys = ys.start..ys.end.min(self.size);
+        if ys.is_empty() {
+            return 0;
+        }
+        let (first, last) = (ys.start / 64, (ys.end - 1) / 64);
+        // The bits of the first and last words that fall in ys.
+        let low = u64::MAX << (ys.start % 64);
+        let high = u64::MAX >> (63 - (ys.end - 1) % 64);
+        xs.map(|x| {
+            let column = &self.bits[x * self.stride..(x + 1) * self.stride];
+            (first..=last)
+                .map(|w| {
+                    let mut word = column[w];
+                    if w == first {
+                        word &= low;
+                    }
+                    if w == last {
+                        word &= high;
+                    }
+                    word.count_ones() as usize
+                })
+                .sum::<usize>()
+        })
+        .sum()
+    }
+
+    // Bytes held, for estimates.
+    pub fn bytes(size: usize) -> usize {
+        size * size.div_ceil(64) * 8
+    }
+}
+
+// The placed pixels, indexed by x then y. Whether a cell is filled lives in
+// the occupancy bits rather than an Option per cell, which would add a word
+// of padding to every pixel.
+#[derive(Debug, Clone)]
+pub struct Grid {
+    size: usize,
+    pixels: Vec<Pixel>,
+    occupancy: Occupancy,
+}
+
+// What empty cells hold; never read as a pixel.
+const EMPTY: Pixel = Pixel {
+    color: [0; 3],
+    loc: [0, 0],
+    center: [0, 0],
+    center_id: 0,
+};
+
+impl Grid {
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            pixels: vec![EMPTY; size * size],
+            occupancy: Occupancy::new(size),
+        }
+    }
+
+    pub fn get(&self, loc: Location) -> Option<&Pixel> {
+        self.occupancy
+            .is_filled(loc)
+            .then(|| &self.pixels[loc[0] * self.size + loc[1]])
+    }
+
+    pub fn is_filled(&self, loc: Location) -> bool {
+        self.occupancy.is_filled(loc)
+    }
+
+    // Filling a cell again replaces its pixel.
+    pub fn set(&mut self, loc: Location, pixel: Pixel) {
+        self.pixels[loc[0] * self.size + loc[1]] = pixel;
+        self.occupancy.fill(loc);
+    }
+
+    // Empties loc, giving back the pixel it held.
+    pub fn clear(&mut self, loc: Location) -> Option<Pixel> {
+        let pixel = self.get(loc).copied();
+        self.occupancy.clear(loc);
+        pixel
+    }
+
+    pub fn occupancy(&self) -> &Occupancy {
+        &self.occupancy
+    }
+
+    // The column of constant x, as the cells from y = 0 up.
+    pub fn column(&self, x: usize) -> impl Iterator<Item = Option<&Pixel>> + '_ {
+        (0..self.size).map(move |y| self.get([x, y]))
+    }
+
+    pub fn pixels(&self) -> impl Iterator<Item = &Pixel> + '_ {
+        let size = self.size;
+        self.pixels
+            .iter()
+            .enumerate()
+            .filter(move |&(i, _)| self.occupancy.is_filled([i / size, i % size]))
+            .map(|(_, pixel)| pixel)
+    }
+
+    pub fn bytes(size: usize) -> usize {
+        size * size * size_of::<Pixel>() + Occupancy::bytes(size)
+    }
+}
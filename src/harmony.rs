@@ -0,0 +1,82 @@
+use rand::prelude::*;
+
+use std::str::FromStr;
+
+use crate::Color;
+use crate::color::hsv_to_rgb;
+
+// Hue offsets in degrees from the base hue, by the classic color wheel rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HarmonyRule {
+    Monochromatic,
+    Analogous,
+    Complementary,
+    SplitComplementary,
+    Triadic,
+    Tetradic,
+}
+
+impl FromStr for HarmonyRule {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "monochromatic" => Ok(HarmonyRule::Monochromatic),
+            "analogous" => Ok(HarmonyRule::Analogous),
+            "complementary" => Ok(HarmonyRule::Complementary),
+            "split-complementary" => Ok(HarmonyRule::SplitComplementary),
+            "triadic" => Ok(HarmonyRule::Triadic),
+            "tetradic" => Ok(HarmonyRule::Tetradic),
+            _ => Err(format!(
+                "unknown harmony {s}, expected monochromatic, analogous, complementary, \
+                 split-complementary, triadic or tetradic"
+            )),
+        }
+    }
+}
+
+impl HarmonyRule {
+    pub fn offsets(self) -> &'static [f64] {
+        match self {
+            HarmonyRule::Monochromatic => &[0.0],
+            HarmonyRule::Analogous => &[-30.0, 0.0, 30.0],
+            HarmonyRule::Complementary => &[0.0, 180.0],
+            HarmonyRule::SplitComplementary => &[0.0, 150.0, 210.0],
+            HarmonyRule::Triadic => &[0.0, 120.0, 240.0],
+            HarmonyRule::Tetradic => &[0.0, 90.0, 180.0, 270.0],
+        }
+    }
+}
+
+// Degrees of hue either side of each of the rule's hues.
+const HUE_SPREAD: f64 = 12.0;
+
+// Colors around the hues a rule derives from one base hue, at random
+// saturation and value, kept away from gray and black so the hues read.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Harmony {
+    pub rule: HarmonyRule,
+    // In degrees.
+    pub base_hue: f64,
+}
+
+impl Harmony {
+    pub fn hues(&self) -> Vec<f64> {
+        self.rule
+            .offsets()
+            .iter()
+            .map(|o| (self.base_hue + o).rem_euclid(360.0))
+            .collect()
+    }
+
+    pub fn next<R: Rng>(&self, rng: &mut R) -> Color {
+        let offsets = self.rule.offsets();
+        let hue = self.base_hue
+            + offsets[rng.random_range(0..offsets.len())]
+            + rng.random_range(-HUE_SPREAD..=HUE_SPREAD);
+        hsv_to_rgb(
+            hue.rem_euclid(360.0),
+            rng.random_range(0.35..=1.0),
+            rng.random_range(0.3..=1.0),
+        )
+    }
+}
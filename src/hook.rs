@@ -0,0 +1,149 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+#[cfg(feature = "cli")]
+use std::time::Duration;
+
+use crate::gallery::json_string;
+
+// How long a webhook may take before the render moves on.
+#[cfg(feature = "cli")]
+const POST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Something to notify when a run finishes or passes a milestone: a URL,
+// written with http:// or https://, gets the event POSTed as JSON, and
+// anything else runs as a shell command with the JSON on its stdin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Hook {
+    Command(String),
+    Post(String),
+}
+
+impl FromStr for Hook {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        if s.trim().is_empty() {
+            Err("empty hook".to_string())
+        } else if s.starts_with("http://") || s.starts_with("https://") {
+            Ok(Hook::Post(s.to_string()))
+        } else {
+            Ok(Hook::Command(s.to_string()))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    Milestone,
+    Complete,
+}
+
+impl EventKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            EventKind::Milestone => "milestone",
+            EventKind::Complete => "complete",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Event<'a> {
+    pub kind: EventKind,
+    // The final output, even for a milestone.
+    pub output: &'a str,
+    pub coverage: f64,
+    pub placed: usize,
+    pub total: usize,
+    pub seconds: f64,
+    pub seed: u64,
+    // The command line the run was started with.
+    pub args: &'a [String],
+}
+
+impl Event<'_> {
+    // A one-line summary, also sent as `content` so chat webhooks such as
+    // Discord's show it as the message.
+    pub fn message(&self) -> String {
+        match self.kind {
+            EventKind::Milestone => format!(
+                "{} is {:.0}% done after {:.1}s",
+                self.output,
+                self.coverage * 100.0,
+                self.seconds
+            ),
+            EventKind::Complete => format!(
+                "Finished {}: {} of {} pixels in {:.1}s",
+                self.output, self.placed, self.total, self.seconds
+            ),
+        }
+    }
+
+    pub fn json(&self) -> String {
+        let args: Vec<String> = self.args.iter().map(|a| json_string(a)).collect();
+        format!(
+            "{{\"event\":\"{}\",\"content\":{},\"output\":{},\"coverage\":{:.4},\
+             \"placed\":{},\"total\":{},\"seconds\":{:.3},\"seed\":{},\"args\":[{}]}}",
+            self.kind.name(),
+            json_string(&self.message()),
+            json_string(self.output),
+            self.coverage,
+            self.placed,
+            self.total,
+            self.seconds,
+            self.seed,
+            args.join(",")
+        )
+    }
+}
+
+impl Hook {
+    // Waits for the command or request to finish.
+    pub fn fire(&self, event: &Event) -> Result<(), String> {
+        match self {
+            Hook::Command(command) => {
+                let mut child = Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .env("SPINNING_EVENT", event.kind.name())
+                    .env("SPINNING_OUTPUT", event.output)
+                    .env("SPINNING_COVERAGE", format!("{:.4}", event.coverage))
+                    .stdin(Stdio::piped())
+                    // Keeps stdout for --raw-stdout frames.
+                    .stdout(std::io::stderr())
+                    .spawn()
+                    .map_err(|e| format!("could not run hook {command}: {e}"))?;
+                // A command that doesn't read its stdin closes it early, which is fine.
+                let _ = child
+                    .stdin
+                    .take()
+                    .expect("piped stdin")
+                    .write_all(event.json().as_bytes());
+                let status = child
+                    .wait()
+                    .map_err(|e| format!("hook {command} failed: {e}"))?;
+                if !status.success() {
+                    return Err(format!("hook {command} failed: {status}"));
+                }
+                Ok(())
+            }
+            #[cfg(not(feature = "cli"))]
+            Hook::Post(url) => Err(format!(
+                "posting to {url} needs spinning built with --features cli"
+            )),
+            #[cfg(feature = "cli")]
+            Hook::Post(url) => {
+                let agent: ureq::Agent = ureq::Agent::config_builder()
+                    .timeout_global(Some(POST_TIMEOUT))
+                    .build()
+                    .into();
+                agent
+                    .post(url)
+                    .header("Content-Type", "application/json")
+                    .send(event.json())
+                    .map_err(|e| format!("could not post to {url}: {e}"))?;
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use crate::{Location, Placement, color_dist_sq};
+
+// Where one pixel of a placement record came from.
+#[derive(Debug, Clone)]
+pub struct Provenance {
+    pub index: usize,
+    pub total: usize,
+    pub placement: Placement,
+    // The lookback pixel it continued from, with its index; None for the
+    // first pixel of a center, or one continuing from a pixel the record
+    // doesn't hold.
+    pub matched: Option<(usize, Placement)>,
+}
+
+// The placement that left its pixel at loc, the last one there, or None if
+// the cell was never filled.
+pub fn provenance(placements: &[Placement], loc: Location) -> Option<Provenance> {
+    let index = placements.iter().rposition(|p| p.pixel.loc == loc)?;
+    let placement = placements[index];
+    let matched = placement.parent.and_then(|parent| {
+        let at = placements[..index]
+            .iter()
+            .rposition(|p| p.pixel.loc == parent)?;
+        Some((at, placements[at]))
+    });
+    Some(Provenance {
+        index,
+        total: placements.len(),
+        placement,
+        matched,
+    })
+}
+
+impl Provenance {
+    pub fn report(&self) -> Vec<String> {
+        let pixel = self.placement.pixel;
+        let [x, y] = pixel.loc;
+        let [cx, cy] = pixel.center;
+        let [px, py] = self.placement.pos;
+        let mut lines = vec![
+            format!(
+                "Pixel {x},{y}: placement {} of {} ({:.2}% in)",
+                self.index,
+                self.total,
+                100.0 * self.index as f64 / self.total.max(1) as f64
+            ),
+            format!("  color {}", hex(pixel.color)),
+            format!("  center {} at {cx},{cy}", pixel.center_id),
+        ];
+        lines.push(match self.placement.steps {
+            0 => "  not walked to".to_string(),
+            steps => format!("  walk of {steps} cells, ending at {px:.2},{py:.2}"),
+        });
+        lines.push(match &self.matched {
+            Some((at, parent)) => {
+                let [mx, my] = parent.pixel.loc;
+                format!(
+                    "  matched {mx},{my}: placement {at}, color {}, distance {:.1}",
+                    hex(parent.pixel.color),
+                    (color_dist_sq(pixel.color, parent.pixel.color) as f64).sqrt()
+                )
+            }
+            None => "  started its center, matching nothing".to_string(),
+        });
+        lines
+    }
+}
+
+fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
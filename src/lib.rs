@@ -0,0 +1,146 @@
+use image::RgbImage;
+
+pub mod ab;
+pub mod annotate;
+pub mod base;
+pub mod batch;
+#[cfg(feature = "files")]
+pub mod big;
+pub mod blend;
+pub mod canvas;
+pub mod center;
+pub mod centerstats;
+pub mod chains;
+pub mod channels;
+pub mod color;
+pub mod compare;
+pub mod composite;
+pub mod concurrent;
+pub mod cubemap;
+pub mod curve;
+pub mod cvd;
+pub mod daemon;
+pub mod daily;
+pub mod estimate;
+pub mod explore;
+pub mod export;
+pub mod extract;
+pub mod ffi;
+pub mod finish;
+pub mod fit;
+pub mod gallery;
+pub mod generator;
+pub mod geometry;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+pub mod grid;
+pub mod harmony;
+pub mod hook;
+pub mod inspect;
+pub mod linear;
+pub mod logging;
+pub mod lookback;
+pub mod morph;
+pub mod mosaic;
+pub mod noise;
+pub mod observer;
+pub mod openindex;
+pub mod output;
+pub mod palette;
+pub mod panels;
+pub mod paper;
+pub mod params;
+pub mod phase;
+pub mod plot;
+pub mod post;
+pub mod presets;
+pub mod print;
+pub mod quantize;
+pub mod raster;
+pub mod region;
+pub mod replay;
+pub mod rng;
+pub mod runs;
+pub mod sampler;
+pub mod scene;
+pub mod schema;
+pub mod series;
+pub mod shader;
+pub mod shard;
+pub mod smear;
+pub mod sphere;
+pub mod stencil;
+pub mod stereo;
+pub mod strategy;
+pub mod svg;
+pub mod termpreview;
+pub mod topology;
+pub mod trace;
+pub mod tune;
+pub mod vecmap;
+pub mod voxel;
+pub mod walk;
+pub mod warm;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "cli")]
+pub mod watermark;
+
+pub use generator::Generator;
+pub use params::Params;
+pub use vecmap::VecMap;
+
+pub type Color = [u8; 3];
+pub type Location = [usize; 2];
+
+pub fn color_dist_sq(a: Color, b: Color) -> i64 {
+    a.iter()
+        .zip(b)
+        .map(|(&c, pc)| (c as i64 - pc as i64).pow(2))
+        .sum::<i64>()
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Pixel {
+    pub color: Color,
+    pub loc: Location,
+    // Where walks continuing from this pixel orbit: its center's position,
+    // jittered by each continuation.
+    pub center: Location,
+    // Id of the center this pixel belongs to, in the generator's Centers.
+    pub center_id: usize,
+}
+
+// One placed pixel, in placement order.
+// `parent` is the location of the lookback pixel it continued from,
+// or None if it was placed at a random open location.
+// `pos` is where the orbit itself passed, in canvas units with cell [x, y]
+// spanning x..x+1 and y..y+1; the middle of the cell for random placements.
+#[derive(Debug, Clone, Copy)]
+pub struct Placement {
+    pub pixel: Pixel,
+    pub parent: Option<Location>,
+    pub pos: [f64; 2],
+    // Cells the walk tried, the landing one included; 0 for pixels that
+    // weren't walked to.
+    pub steps: usize,
+}
+
+pub struct Run {
+    pub image: RgbImage,
+    // Which cells hold a pixel, indexed by x then y.
+    pub filled: Vec<Vec<bool>>,
+    // Empty unless recording was requested.
+    pub placements: Vec<Placement>,
+}
+
+pub fn make_image(params: &Params) -> RgbImage {
+    generate(params, false).image
+}
+
+pub fn generate(params: &Params, record: bool) -> Run {
+    let mut generator = Generator::new(params, record);
+    let _span = tracing::info_span!("placement").entered();
+    while generator.step() {}
+    generator.into_run()
+}
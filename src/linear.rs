@@ -0,0 +1,68 @@
+use image::imageops::{self, FilterType};
+use image::{ImageBuffer, Rgb, Rgb32FImage, RgbImage};
+
+use std::sync::LazyLock;
+
+use crate::Color;
+
+// Colors are stored and written as sRGB bytes, but blending, interpolation
+// and averaging happen on linear light, so that a mix of black and white
+// looks as bright as the pair does from a distance.
+
+static TO_LINEAR: LazyLock<[f32; 256]> = LazyLock::new(|| {
+    std::array::from_fn(|v| {
+        let c = v as f64 / 255.0;
+        (if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }) as f32
+    })
+});
+
+pub fn to_linear(v: u8) -> f32 {
+    TO_LINEAR[v as usize]
+}
+
+pub fn from_linear(v: f32) -> u8 {
+    let v = (v as f64).clamp(0.0, 1.0);
+    let c = if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    };
+    (c * 255.0).round() as u8
+}
+
+pub fn color_to_linear(color: Color) -> [f32; 3] {
+    color.map(to_linear)
+}
+
+pub fn color_from_linear(color: [f32; 3]) -> Color {
+    color.map(from_linear)
+}
+
+pub fn image_to_linear(img: &RgbImage) -> Rgb32FImage {
+    ImageBuffer::from_fn(img.width(), img.height(), |x, y| {
+        Rgb(color_to_linear(img.get_pixel(x, y).0))
+    })
+}
+
+pub fn image_from_linear(img: &Rgb32FImage) -> RgbImage {
+    ImageBuffer::from_fn(img.width(), img.height(), |x, y| {
+        Rgb(color_from_linear(img.get_pixel(x, y).0))
+    })
+}
+
+pub fn resize(img: &RgbImage, width: u32, height: u32, filter: FilterType) -> RgbImage {
+    image_from_linear(&imageops::resize(
+        &image_to_linear(img),
+        width,
+        height,
+        filter,
+    ))
+}
+
+pub fn blur(img: &RgbImage, sigma: f32) -> RgbImage {
+    image_from_linear(&imageops::blur(&image_to_linear(img), sigma))
+}
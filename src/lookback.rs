@@ -0,0 +1,726 @@
+use noisy_float::prelude::*;
+use rand::prelude::*;
+
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+use crate::scene::{SceneReader, SceneWriter};
+use crate::{Color, Location, Pixel, color_dist_sq};
+
+// Decides which entry leaves a full lookback buffer.
+// Entries are ordered newest first; index 0 is the pixel just added.
+pub trait EvictionPolicy {
+    fn observe(&mut self, _pixel: &Pixel) {}
+    fn evict(&mut self, entries: &VecDeque<Pixel>, rng: &mut dyn RngCore) -> usize;
+    // What the policy has learned from the pixels so far, for scenes.
+    fn state(&self) -> Vec<f64> {
+        vec![]
+    }
+    fn restore(&mut self, _state: &[f64]) {}
+}
+
+pub struct Fifo;
+impl EvictionPolicy for Fifo {
+    fn evict(&mut self, entries: &VecDeque<Pixel>, _rng: &mut dyn RngCore) -> usize {
+        entries.len() - 1
+    }
+}
+
+pub struct RandomEviction;
+impl EvictionPolicy for RandomEviction {
+    fn evict(&mut self, entries: &VecDeque<Pixel>, rng: &mut dyn RngCore) -> usize {
+        rng.random_range(1..entries.len())
+    }
+}
+
+// Drops the entry most similar in color to the incoming pixel,
+// keeping the buffer's colors diverse.
+pub struct Diverse;
+impl EvictionPolicy for Diverse {
+    fn evict(&mut self, entries: &VecDeque<Pixel>, _rng: &mut dyn RngCore) -> usize {
+        let incoming = entries[0];
+        entries
+            .iter()
+            .enumerate()
+            .skip(1)
+            .min_by_key(|(_, pixel)| color_dist_sq(incoming.color, pixel.color))
+            .expect("nonempty")
+            .0
+    }
+}
+
+// Drops the entry farthest from a moving average of recent placements.
+pub struct Spatial {
+    cursor: Option<[f64; 2]>,
+}
+impl EvictionPolicy for Spatial {
+    fn observe(&mut self, pixel: &Pixel) {
+        let loc = [pixel.loc[0] as f64, pixel.loc[1] as f64];
+        self.cursor = Some(match self.cursor {
+            None => loc,
+            Some(c) => [0.9 * c[0] + 0.1 * loc[0], 0.9 * c[1] + 0.1 * loc[1]],
+        });
+    }
+    fn state(&self) -> Vec<f64> {
+        self.cursor.map_or(vec![], Vec::from)
+    }
+    fn restore(&mut self, state: &[f64]) {
+        self.cursor = state.try_into().ok();
+    }
+    fn evict(&mut self, entries: &VecDeque<Pixel>, _rng: &mut dyn RngCore) -> usize {
+        let cursor = self.cursor.expect("observed");
+        entries
+            .iter()
+            .enumerate()
+            .skip(1)
+            .max_by(|(_, a), (_, b)| {
+                let da =
+                    (a.loc[0] as f64 - cursor[0]).powi(2) + (a.loc[1] as f64 - cursor[1]).powi(2);
+                let db =
+                    (b.loc[0] as f64 - cursor[0]).powi(2) + (b.loc[1] as f64 - cursor[1]).powi(2);
+                da.total_cmp(&db)
+            })
+            .expect("nonempty")
+            .0
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Eviction {
+    #[default]
+    Fifo,
+    Random,
+    Diverse,
+    Spatial,
+}
+
+impl FromStr for Eviction {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "fifo" => Ok(Eviction::Fifo),
+            "random" => Ok(Eviction::Random),
+            "diverse" => Ok(Eviction::Diverse),
+            "spatial" => Ok(Eviction::Spatial),
+            _ => Err(format!("unknown eviction policy {s}")),
+        }
+    }
+}
+
+impl Eviction {
+    pub fn policy(self) -> Box<dyn EvictionPolicy> {
+        match self {
+            Eviction::Fifo => Box::new(Fifo),
+            Eviction::Random => Box::new(RandomEviction),
+            Eviction::Diverse => Box::new(Diverse),
+            Eviction::Spatial => Box::new(Spatial { cursor: None }),
+        }
+    }
+}
+
+// Least share of the lookback searched, unless given.
+const DEFAULT_FLOOR: f64 = 0.05;
+// Placements the forced rate is averaged over.
+const FORCED_WINDOW: f64 = 1000.0;
+// Relative change in the searched share per placement under `forced`.
+const FORCED_STEP: f64 = 0.002;
+
+// Searches fewer of the newest lookback pixels as the canvas crowds, so
+// matches stay about as local in the endgame as early on. Written as
+// `open[:FLOOR]`, searching the share of the lookback that the share of the
+// canvas still open gives, or `forced[:RATE[:FLOOR]]`, shrinking the share
+// while more than RATE of recent placements were forced to random spots and
+// growing it back while fewer were. Either stops at FLOOR of the lookback.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AdaptiveLookback {
+    Open { floor: f64 },
+    Forced { rate: f64, floor: f64 },
+}
+
+impl FromStr for AdaptiveLookback {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        let mut parts = s.split(':');
+        let mode = parts.next().unwrap_or_default();
+        let mut number = |name: &str, default: f64| -> Result<f64, String> {
+            let Some(part) = parts.next() else {
+                return Ok(default);
+            };
+            let value: f64 = part
+                .parse()
+                .map_err(|_| format!("invalid adaptive lookback {name} {part}"))?;
+            if !(value > 0.0 && value <= 1.0) {
+                return Err(format!(
+                    "adaptive lookback {name} must be in (0, 1], got {value}"
+                ));
+            }
+            Ok(value)
+        };
+        let adaptive = match mode {
+            "open" => AdaptiveLookback::Open {
+                floor: number("floor", DEFAULT_FLOOR)?,
+            },
+            "forced" => AdaptiveLookback::Forced {
+                rate: number("rate", 0.02)?,
+                floor: number("floor", DEFAULT_FLOOR)?,
+            },
+            _ => {
+                return Err(format!(
+                    "unknown adaptive lookback {mode}, expected open or forced"
+                ));
+            }
+        };
+        if parts.next().is_some() {
+            return Err(format!("too many adaptive lookback arguments in {s}"));
+        }
+        Ok(adaptive)
+    }
+}
+
+// The share of the lookback searched as a run goes on.
+#[derive(Debug, Clone)]
+pub struct LookbackScale {
+    mode: AdaptiveLookback,
+    share: f64,
+    // Moving average of whether placements were forced.
+    forced_rate: f64,
+}
+
+impl LookbackScale {
+    pub fn new(mode: AdaptiveLookback) -> Self {
+        Self {
+            mode,
+            share: 1.0,
+            forced_rate: 0.0,
+        }
+    }
+
+    pub fn share(&self) -> f64 {
+        self.share
+    }
+
+    // After each placement from the lookback, at the coverage it left.
+    pub fn observe(&mut self, forced: bool, coverage: f64) {
+        match self.mode {
+            AdaptiveLookback::Open { floor } => self.share = (1.0 - coverage).max(floor),
+            AdaptiveLookback::Forced { rate, floor } => {
+                let forced = if forced { 1.0 } else { 0.0 };
+                self.forced_rate += (forced - self.forced_rate) / FORCED_WINDOW;
+                let step = if self.forced_rate > rate {
+                    1.0 - FORCED_STEP
+                } else {
+                    1.0 + FORCED_STEP
+                };
+                self.share = (self.share * step).clamp(floor, 1.0);
+            }
+        }
+    }
+
+    // The searched share and forced rate, for scenes.
+    pub fn state(&self) -> [f64; 2] {
+        [self.share, self.forced_rate]
+    }
+
+    pub fn restore(&mut self, [share, forced_rate]: [f64; 2]) {
+        self.share = share;
+        self.forced_rate = forced_rate;
+    }
+
+    // The number of pixels to search out of take.
+    pub fn apply(&self, take: usize) -> usize {
+        ((take as f64 * self.share).round() as usize).max(1)
+    }
+}
+
+// What sorts colors into the pools of a split lookback.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorClass {
+    // Equal arcs of the hue circle; grays go by what hue they lean to.
+    #[default]
+    Hue,
+    // Equal bands of Rec. 709 luma.
+    Luma,
+}
+
+// Splits the lookback into count pools by color class, each holding an
+// equal share of it. A color is matched only within its own pool, or the
+// nearest pool holding anything, which makes each search count times
+// shorter and keeps color families apart. Written `N[:hue|luma]`; one pool
+// is the plain lookback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LookbackPools {
+    pub count: usize,
+    pub class: ColorClass,
+}
+
+impl FromStr for LookbackPools {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        let (count, class) = match s.split_once(':') {
+            Some((count, "hue")) => (count, ColorClass::Hue),
+            Some((count, "luma")) => (count, ColorClass::Luma),
+            Some((_, class)) => return Err(format!("unknown color class {class}")),
+            None => (s, ColorClass::Hue),
+        };
+        let count = count.parse().ok().filter(|&n| n > 0).ok_or(format!(
+            "pool count must be a positive integer, got {count}"
+        ))?;
+        Ok(Self { count, class })
+    }
+}
+
+impl LookbackPools {
+    // The pool a color belongs to.
+    pub fn pool(&self, color: Color) -> usize {
+        let [r, g, b] = color.map(|c| c as f64);
+        let at = match self.class {
+            ColorClass::Hue => {
+                let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+                let chroma = max - min;
+                let sextant = if chroma == 0.0 {
+                    0.0
+                } else if max == r {
+                    ((g - b) / chroma).rem_euclid(6.0)
+                } else if max == g {
+                    (b - r) / chroma + 2.0
+                } else {
+                    (r - g) / chroma + 4.0
+                };
+                sextant / 6.0
+            }
+            ColorClass::Luma => (0.2126 * r + 0.7152 * g + 0.0722 * b) / 256.0,
+        };
+        ((at * self.count as f64) as usize).min(self.count - 1)
+    }
+
+    // How many pools apart two are; hues wrap around.
+    fn apart(&self, a: usize, b: usize) -> usize {
+        let d = a.abs_diff(b);
+        match self.class {
+            ColorClass::Hue => d.min(self.count - d),
+            ColorClass::Luma => d,
+        }
+    }
+}
+
+pub struct Query {
+    pub color: Color,
+    pub take: usize,
+    // With a nonzero spatial weight, squared distance from cursor
+    // is added to the squared color distance.
+    pub cursor: Location,
+    pub spatial_weight: f64,
+    // Compare only this channel, for color sources that vary along one axis.
+    pub channel: Option<usize>,
+}
+
+// The lookback colors as separate channel planes, so the distance scan runs
+// over contiguous bytes in fixed-size chunks that the compiler vectorizes.
+// Each plane is a ring, oldest first from head, so evicting the oldest or
+// newest color moves nothing and others move the shorter side.
+struct ColorPlanes {
+    channels: [Vec<u8>; 3],
+    head: usize,
+    len: usize,
+}
+
+const LANES: usize = 16;
+
+impl Query {
+    // What nearest minimizes.
+    fn distance(&self, pixel: &Pixel) -> N64 {
+        let color_dist = match self.channel {
+            Some(ch) => (self.color[ch] as i64 - pixel.color[ch] as i64).pow(2),
+            None => color_dist_sq(self.color, pixel.color),
+        };
+        let spatial_dist_sq = pixel
+            .loc
+            .iter()
+            .zip(self.cursor)
+            .map(|(&l, c)| (l as f64 - c as f64).powi(2))
+            .sum::<f64>();
+        n64(color_dist as f64 + self.spatial_weight * spatial_dist_sq)
+    }
+}
+
+impl ColorPlanes {
+    fn new(capacity: usize) -> Self {
+        Self {
+            channels: std::array::from_fn(|_| vec![0; capacity + 1]),
+            head: 0,
+            len: 0,
+        }
+    }
+    // Where the color at position p, counting from the oldest, is kept.
+    fn slot(&self, p: usize) -> usize {
+        (self.head + p) % self.channels[0].len()
+    }
+    fn push_newest(&mut self, color: Color) {
+        let at = self.slot(self.len);
+        for (channel, c) in self.channels.iter_mut().zip(color) {
+            channel[at] = c;
+        }
+        self.len += 1;
+    }
+    // Index counts from the newest entry, as in the lookback deque.
+    fn remove(&mut self, index: usize) {
+        let p = self.len - 1 - index;
+        if p < self.len / 2 {
+            // Move the older ones up a place, over it.
+            for q in (0..p).rev() {
+                let (from, to) = (self.slot(q), self.slot(q + 1));
+                self.channels.iter_mut().for_each(|c| c[to] = c[from]);
+            }
+            self.head = self.slot(1);
+        } else {
+            for q in p + 1..self.len {
+                let (from, to) = (self.slot(q), self.slot(q - 1));
+                self.channels.iter_mut().for_each(|c| c[to] = c[from]);
+            }
+        }
+        self.len -= 1;
+    }
+    // Index of the nearest of the newest `take` colors, newest first.
+    // Ties go to the newer entry, as with a scan from the front of the deque.
+    // The colors wrap round the end of the planes at most once, so the scan
+    // is over one or two runs of contiguous bytes.
+    fn nearest(&self, color: Color, take: usize) -> Option<usize> {
+        let start = self.len - take.min(self.len);
+        let (first, count) = (self.slot(start), self.len - start);
+        let end = (first + count).min(self.channels[0].len());
+        let runs = [first..end, 0..count - (end - first)];
+        let mut best: Option<(u32, usize)> = None;
+        let mut offset = 0;
+        for run in runs {
+            let [r, g, b] = self.channels.each_ref().map(|c| &c[run.clone()]);
+            if let Some((d, i)) = nearest_in(r, g, b, color)
+                && best.is_none_or(|(bd, _)| d <= bd)
+            {
+                best = Some((d, offset + i));
+            }
+            offset += run.len();
+        }
+        best.map(|(_, i)| self.len - 1 - (start + i))
+    }
+}
+
+// The least squared distance from color among the colors of the planes r, g
+// and b, and the last index at which it occurs. Each lane keeps its own best
+// so the loop body is branch-free.
+fn nearest_in(r: &[u8], g: &[u8], b: &[u8], color: Color) -> Option<(u32, usize)> {
+    let [cr, cg, cb] = color.map(|c| c as i32);
+    let dist = |i: usize| {
+        let dr = r[i] as i32 - cr;
+        let dg = g[i] as i32 - cg;
+        let db = b[i] as i32 - cb;
+        (dr * dr + dg * dg + db * db) as u32
+    };
+    let mut lane_dist = [u32::MAX; LANES];
+    let mut lane_chunk = [0; LANES];
+    let whole = r.len() / LANES * LANES;
+    for k in 0..whole / LANES {
+        let r: &[u8; LANES] = r[k * LANES..][..LANES].try_into().expect("whole chunk");
+        let g: &[u8; LANES] = g[k * LANES..][..LANES].try_into().expect("whole chunk");
+        let b: &[u8; LANES] = b[k * LANES..][..LANES].try_into().expect("whole chunk");
+        for i in 0..LANES {
+            let dr = r[i] as i32 - cr;
+            let dg = g[i] as i32 - cg;
+            let db = b[i] as i32 - cb;
+            let d = (dr * dr + dg * dg + db * db) as u32;
+            let better = d <= lane_dist[i];
+            lane_dist[i] = if better { d } else { lane_dist[i] };
+            lane_chunk[i] = if better { k } else { lane_chunk[i] };
+        }
+    }
+    let mut best: Option<(u32, usize)> = None;
+    let lanes = (0..LANES)
+        .filter(|&i| lane_dist[i] != u32::MAX)
+        .map(|i| (lane_dist[i], lane_chunk[i] * LANES + i));
+    for (d, i) in lanes.chain((whole..r.len()).map(|i| (dist(i), i))) {
+        if best.is_none_or(|(bd, bi)| d < bd || (d == bd && i > bi)) {
+            best = Some((d, i));
+        }
+    }
+    best
+}
+
+pub struct Lookback {
+    entries: VecDeque<Pixel>,
+    planes: ColorPlanes,
+    capacity: usize,
+    policy: Box<dyn EvictionPolicy>,
+    #[cfg(feature = "gpu")]
+    gpu: Option<crate::gpu::GpuLookback>,
+    // When split, the entries live in these instead.
+    split: Option<Split>,
+}
+
+struct Split {
+    pools: LookbackPools,
+    lookbacks: Vec<Lookback>,
+    // The push count at each pool's last push, to find the newest entry.
+    pushed: Vec<usize>,
+    pushes: usize,
+}
+
+impl Split {
+    // The pool searched for a color: its own, or the nearest one holding
+    // anything.
+    fn searched(&self, color: Color) -> Option<&Lookback> {
+        let own = self.pools.pool(color);
+        (0..self.lookbacks.len())
+            .filter(|&k| !self.lookbacks[k].is_empty())
+            .min_by_key(|&k| (self.pools.apart(own, k), k))
+            .map(|k| &self.lookbacks[k])
+    }
+}
+
+impl Lookback {
+    pub fn new(capacity: usize, policy: Box<dyn EvictionPolicy>) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity + 1),
+            planes: ColorPlanes::new(capacity),
+            capacity,
+            policy,
+            // Without an adapter, searches stay on the CPU.
+            #[cfg(feature = "gpu")]
+            gpu: if capacity >= crate::gpu::MIN_TAKE {
+                crate::gpu::GpuLookback::new(capacity)
+            } else {
+                None
+            },
+            split: None,
+        }
+    }
+    // A lookback of the given capacity in all, split into pools, each with
+    // its own eviction policy.
+    pub fn pooled(
+        capacity: usize,
+        pools: LookbackPools,
+        policy: impl Fn() -> Box<dyn EvictionPolicy>,
+    ) -> Self {
+        if pools.count == 1 {
+            return Lookback::new(capacity, policy());
+        }
+        let each = capacity.div_ceil(pools.count);
+        let mut lookback = Lookback::new(0, policy());
+        lookback.split = Some(Split {
+            pools,
+            lookbacks: (0..pools.count)
+                .map(|_| Lookback::new(each, policy()))
+                .collect(),
+            pushed: vec![0; pools.count],
+            pushes: 0,
+        });
+        lookback
+    }
+    pub fn push(&mut self, pixel: Pixel, rng: &mut dyn RngCore) {
+        if let Some(split) = &mut self.split {
+            let k = split.pools.pool(pixel.color);
+            split.lookbacks[k].push(pixel, rng);
+            split.pushes += 1;
+            split.pushed[k] = split.pushes;
+            return;
+        }
+        self.push_entry(pixel);
+        self.policy.observe(&pixel);
+        if self.entries.len() > self.capacity {
+            let index = if self.capacity == 0 {
+                0
+            } else {
+                self.policy.evict(&self.entries, rng)
+            };
+            self.remove(index);
+        }
+    }
+    fn push_entry(&mut self, pixel: Pixel) {
+        self.entries.push_front(pixel);
+        self.planes.push_newest(pixel.color);
+        #[cfg(feature = "gpu")]
+        if let Some(gpu) = &mut self.gpu {
+            gpu.push_front(pixel);
+        }
+    }
+    fn remove(&mut self, index: usize) {
+        self.entries.remove(index);
+        self.planes.remove(index);
+        #[cfg(feature = "gpu")]
+        if let Some(gpu) = &mut self.gpu {
+            gpu.remove(index);
+        }
+    }
+    // Drops the entries for which keep returns false.
+    pub fn retain(&mut self, keep: impl Fn(&Pixel) -> bool) {
+        match &mut self.split {
+            Some(split) => split
+                .lookbacks
+                .iter_mut()
+                .for_each(|lookback| lookback.retain_entries(&keep)),
+            None => self.retain_entries(&keep),
+        }
+    }
+    fn retain_entries(&mut self, keep: &dyn Fn(&Pixel) -> bool) {
+        for index in (0..self.entries.len()).rev() {
+            if !keep(&self.entries[index]) {
+                self.remove(index);
+            }
+        }
+    }
+    // Whether every entry passes check.
+    pub fn all(&self, check: impl Fn(&Pixel) -> bool) -> bool {
+        match &self.split {
+            Some(split) => split
+                .lookbacks
+                .iter()
+                .all(|lookback| lookback.entries.iter().all(&check)),
+            None => self.entries.iter().all(check),
+        }
+    }
+    // Has the pixels of centers gone and keep orbit keep, at at, when the
+    // two merge.
+    pub fn reassign(&mut self, gone: usize, keep: usize, at: Location) {
+        let update = |pixel: &mut Pixel| {
+            if pixel.center_id == gone || pixel.center_id == keep {
+                pixel.center_id = keep;
+                pixel.center = at;
+            }
+        };
+        if let Some(split) = &mut self.split {
+            for lookback in &mut split.lookbacks {
+                lookback.reassign(gone, keep, at);
+            }
+            return;
+        }
+        self.entries.iter_mut().for_each(update);
+        #[cfg(feature = "gpu")]
+        if let Some(gpu) = &mut self.gpu {
+            gpu.update(&update);
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        match &self.split {
+            Some(split) => split.lookbacks.iter().all(Lookback::is_empty),
+            None => self.entries.is_empty(),
+        }
+    }
+    // The newest entry; when split, the newest of the pool pushed to last.
+    pub fn front(&self) -> Option<&Pixel> {
+        match &self.split {
+            Some(split) => (0..split.lookbacks.len())
+                .filter(|&k| !split.lookbacks[k].is_empty())
+                .max_by_key(|&k| split.pushed[k])
+                .and_then(|k| split.lookbacks[k].front()),
+            None => self.entries.front(),
+        }
+    }
+    // The n best matches among the newest `take` entries, best first.
+    pub fn ranked(&self, query: &Query, n: usize) -> Vec<&Pixel> {
+        if let Some(split) = &self.split {
+            return split
+                .searched(query.color)
+                .map_or(vec![], |lookback| lookback.ranked(query, n));
+        }
+        let mut candidates: Vec<(N64, &Pixel)> = self
+            .entries
+            .iter()
+            .take(query.take)
+            .map(|pixel| (query.distance(pixel), pixel))
+            .collect();
+        candidates.sort_by_key(|&(distance, _)| distance);
+        candidates.into_iter().take(n).map(|(_, p)| p).collect()
+    }
+    // The pool count, then each pool's pushes and entries, oldest first, and
+    // what its eviction policy has learned.
+    pub fn write_scene(&self, w: &mut SceneWriter) {
+        match &self.split {
+            Some(split) => {
+                w.usize(split.lookbacks.len());
+                w.usize(split.pushes);
+                for (lookback, &pushed) in split.lookbacks.iter().zip(&split.pushed) {
+                    w.usize(pushed);
+                    lookback.write_entries(w);
+                }
+            }
+            None => {
+                w.usize(1);
+                w.usize(0);
+                w.usize(0);
+                self.write_entries(w);
+            }
+        }
+    }
+    fn write_entries(&self, w: &mut SceneWriter) {
+        let state = self.policy.state();
+        w.usize(state.len());
+        state.into_iter().for_each(|v| w.f64(v));
+        w.usize(self.entries.len());
+        self.entries.iter().rev().for_each(|pixel| w.pixel(pixel));
+    }
+    // Replaces the entries with a scene's. A smaller capacity keeps the
+    // newest of them.
+    pub fn read_scene(&mut self, r: &mut SceneReader) -> Result<(), String> {
+        let count = self.split.as_ref().map_or(1, |split| split.lookbacks.len());
+        if r.usize()? != count {
+            return Err("resuming cannot change --lookback-pools".to_string());
+        }
+        let pushes = r.usize()?;
+        match &mut self.split {
+            Some(split) => {
+                split.pushes = pushes;
+                for (lookback, pushed) in split.lookbacks.iter_mut().zip(&mut split.pushed) {
+                    *pushed = r.usize()?;
+                    lookback.read_entries(r)?;
+                }
+                Ok(())
+            }
+            None => {
+                r.usize()?;
+                self.read_entries(r)
+            }
+        }
+    }
+    fn read_entries(&mut self, r: &mut SceneReader) -> Result<(), String> {
+        let state = (0..r.count()?)
+            .map(|_| r.f64())
+            .collect::<Result<Vec<_>, _>>()?;
+        self.retain_entries(&|_| false);
+        let n = r.count()?;
+        for k in 0..n {
+            let pixel = r.pixel()?;
+            if n - k <= self.capacity {
+                self.push_entry(pixel);
+            }
+        }
+        self.policy.restore(&state);
+        Ok(())
+    }
+    // Best match for the query color among the newest `take` entries.
+    pub fn nearest(&self, query: &Query) -> Option<&Pixel> {
+        if let Some(split) = &self.split {
+            return split.searched(query.color)?.nearest(query);
+        }
+        #[cfg(feature = "gpu")]
+        if let Some(gpu) = &self.gpu
+            && query.take >= crate::gpu::MIN_TAKE
+            && query.spatial_weight == 0.0
+            && query.channel.is_none()
+        {
+            return gpu.nearest(query.color, query.take);
+        }
+        let color_dist = |pixel: &Pixel| match query.channel {
+            Some(ch) => (query.color[ch] as i64 - pixel.color[ch] as i64).pow(2),
+            None => color_dist_sq(query.color, pixel.color),
+        };
+        let candidates = self.entries.iter().take(query.take);
+        if query.spatial_weight == 0.0 {
+            if query.channel.is_none() {
+                return self
+                    .planes
+                    .nearest(query.color, query.take)
+                    .map(|index| &self.entries[index]);
+            }
+            return candidates.min_by_key(|pixel| color_dist(pixel));
+        }
+        candidates.min_by_key(|pixel| query.distance(pixel))
+    }
+}
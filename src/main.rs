@@ -1,212 +1,1654 @@
-use image::{ImageBuffer, RgbImage};
-use noisy_float::prelude::*;
-use rand::prelude::*;
-
-use std::collections::{HashMap, VecDeque};
-use std::hash::Hash;
-
-type Color = [u8; 3];
-type Location = [usize; 2];
-
-#[derive(Debug, Clone, Copy)]
-struct Pixel {
-    color: Color,
-    loc: Location,
-    center: Location,
-}
-
-struct VecMap<T> {
-    vec: Vec<T>,
-    map: HashMap<T, usize>,
-}
-impl<T: Copy + Eq + Hash> VecMap<T> {
-    fn new_from_vec(vec: Vec<T>) -> Self {
-        let map = vec.iter().enumerate().map(|(i, &v)| (v, i)).collect();
-        Self { vec, map }
-    }
-    fn remove_random<R: Rng>(&mut self, rng: &mut R) -> Option<T> {
-        if self.vec.is_empty() {
-            return None;
-        }
-        let index = rng.random_range(0..self.vec.len());
-        let last = *self.vec.last().unwrap();
-        let out = self.vec.swap_remove(index);
-        self.map.remove(&out);
-        if index != self.vec.len() {
-            self.map.insert(last, index);
-        }
-        Some(out)
-    }
-    fn remove(&mut self, item: &T) -> bool {
-        let maybe_index = self.map.remove(item);
-        match maybe_index {
-            Some(index) => {
-                let last = *self.vec.last().unwrap();
-                self.vec.swap_remove(index);
-                if index != self.vec.len() {
-                    self.map.insert(last, index);
+use image::{DynamicImage, RgbImage};
+use tracing::info_span;
+
+use std::io::Write;
+use std::time::Instant;
+
+use spinning::annotate::{annotate, recipe};
+use spinning::big::render_big;
+use spinning::blend::blend_seeds;
+use spinning::canvas::{Unfilled, with_alpha};
+use spinning::centerstats::{LABELS, center_stats, label_overlay, stats_csv};
+use spinning::channels::split_channels;
+use spinning::color::{ColorSource, parse_color};
+use spinning::composite::composite;
+use spinning::concurrent::generate_concurrent;
+use spinning::cubemap::generate_cubemap;
+use spinning::cvd::companion_path;
+use spinning::export::{read_placements, write_placements};
+use spinning::gallery::add_to_gallery;
+use spinning::hook::{Event, EventKind, Hook};
+use spinning::inspect::provenance;
+use spinning::logging;
+use spinning::mosaic::mosaic;
+use spinning::output::{
+    COLOR_SPACE_KEY, ColorSpace, Thumbnail, save_gray_png, save_indexed_png, save_png,
+    save_rgba_png, thumbnail_path, write_raw_stdout,
+};
+use spinning::params::{SeedSource, parse, parse_list};
+use spinning::plot::{PlotFormat, PlotSettings, write_plot};
+use spinning::post;
+use spinning::presets::{PRESETS, sample_sheet};
+use spinning::print::{save_cmyk_tiff, separate};
+use spinning::quantize::quantize_image;
+use spinning::raster::rasterize;
+use spinning::replay::{Style, parse_palette, render_replay};
+use spinning::runs::{RunRecord, load_runs, record_run};
+use spinning::scene::{Scene, parse_seeds, save_scene};
+use spinning::shard::{self, Part};
+use spinning::smear::smear;
+use spinning::sphere::generate_sphere;
+use spinning::stereo::{depth_map, stereo_image};
+use spinning::svg::write_svg;
+use spinning::trace::write_trace;
+use spinning::voxel::generate_volume;
+use spinning::watermark::{self, Key};
+use spinning::{Generator, Params};
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match args.first().map(String::as_str) {
+        Some("presets") => Some(presets(&args[1..])),
+        Some("explore") => Some(explore(&args[1..])),
+        Some("daily") => Some(daily(&args[1..])),
+        Some("layout") => Some(layout(&args[1..])),
+        Some("fit") => Some(fit(&args[1..])),
+        Some("compare") => Some(compare(&args[1..])),
+        Some("diff") => Some(diff(&args[1..])),
+        Some("morph") => Some(morph(&args[1..])),
+        Some("loop") => Some(render_loop(&args[1..])),
+        Some("render-replay") => Some(replay(&args[1..])),
+        Some("shader") => Some(shader(&args[1..])),
+        Some("inspect") => Some(inspect(&args[1..])),
+        Some("batch") => Some(batch(&args[1..])),
+        Some("estimate") => Some(estimate(&args[1..])),
+        Some("tune") => Some(tune(&args[1..])),
+        Some("palette") => Some(palette(&args[1..])),
+        Some("branch") => Some(branch(&args[1..])),
+        Some("keygen") => Some(keygen(&args[1..])),
+        Some("verify") => Some(verify(&args[1..])),
+        Some("daemon") => Some(daemon(&args[1..])),
+        Some("schema") => Some(schema(&args[1..])),
+        Some("shard") => Some(shard(&args[1..])),
+        Some("merge") => Some(merge(&args[1..])),
+        Some("ab") => Some(ab(&args[1..])),
+        Some("runs") => Some(runs(&args[1..])),
+        Some("panels") => Some(panels(&args[1..])),
+        _ => None,
+    };
+    if let Some(result) = command {
+        if let Err(e) = result {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+    // `resume SCENE [FLAGS...]` carries on from a saved scene, with FLAGS
+    // added to its arguments. A new seed among them branches the run.
+    let (args, resumed) = if args.first().map(String::as_str) == Some("resume") {
+        let Some(path) = args.get(1) else {
+            eprintln!("usage: spinning resume SCENE [FLAGS...]");
+            std::process::exit(1);
+        };
+        let scene = Scene::load(path).unwrap_or_else(|e| {
+            eprintln!("{e}");
+            std::process::exit(1);
+        });
+        let extra = &args[2..];
+        let reseed = extra.iter().any(|a| a == "--seed" || a == "--seed-text");
+        (scene.args_with(extra), Some((path.clone(), scene, reseed)))
+    } else {
+        (args, None)
+    };
+    let params = Params::from_args_with_clock_seed(args).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1);
+    });
+    if let Some(format) = params.log_format {
+        logging::init(format);
+    }
+    for warning in params.warnings() {
+        eprintln!("warning: {warning}");
+    }
+    if params.seed_source == SeedSource::Clock {
+        status(
+            &params,
+            &format!(
+                "Seed {0} (from the clock; --seed {0} renders this again)",
+                params.seed
+            ),
+        );
+    }
+    if let Some(dir) = &params.out_dir {
+        std::fs::create_dir_all(dir).expect("created output directory");
+    }
+    let start = Instant::now();
+    if params.sphere {
+        let filename = params.filename();
+        status(&params, &format!("Start {filename}"));
+        save_png(&generate_sphere(&params), &filename, &params.metadata()).expect("saved");
+        let total = 2 * params.size.pow(2);
+        notify(
+            &params,
+            &params.on_complete,
+            EventKind::Complete,
+            &filename,
+            1.0,
+            (total, total),
+            start,
+        );
+        return;
+    }
+    if let Some(layout) = params.cubemap {
+        let filename = params.filename();
+        status(&params, &format!("Start {filename}"));
+        let paths = generate_cubemap(&params)
+            .save(layout, &filename, &params.metadata())
+            .expect("saved cube map");
+        let total = params.size.pow(3) - params.size.saturating_sub(2).pow(3);
+        notify(
+            &params,
+            &params.on_complete,
+            EventKind::Complete,
+            &paths[0],
+            1.0,
+            (total, total),
+            start,
+        );
+        return;
+    }
+    if params.voxels {
+        status(&params, &format!("Start {}^3 volume", params.size));
+        let volume = generate_volume(&params);
+        if let Some(dir) = &params.slices {
+            volume
+                .save_slices(dir, &params.metadata())
+                .expect("saved slices");
+        }
+        if let Some(path) = &params.nrrd {
+            volume.save_nrrd(path).expect("saved volume");
+        }
+        let output = params.slices.as_ref().or(params.nrrd.as_ref());
+        let total = params.size.pow(3);
+        notify(
+            &params,
+            &params.on_complete,
+            EventKind::Complete,
+            output.expect("checked"),
+            1.0,
+            (total, total),
+            start,
+        );
+        return;
+    }
+    let filename = params.filename();
+    status(&params, &format!("Start {filename}"));
+    if let Some(scratch) = &params.big {
+        render_big(&params, scratch, &filename).expect("saved");
+        let total = params.size * params.size;
+        notify(
+            &params,
+            &params.on_complete,
+            EventKind::Complete,
+            &filename,
+            1.0,
+            (total, total),
+            start,
+        );
+        return;
+    }
+    if let Some(walkers) = params.concurrent_walkers {
+        let run = generate_concurrent(&params, walkers);
+        let total = params.size * params.size;
+        status(
+            &params,
+            &format!(
+                "Placed {total} pixels with {walkers} walkers in {:.2}s",
+                start.elapsed().as_secs_f64()
+            ),
+        );
+        let mut image = run.image;
+        post::apply(&mut image, &params.post);
+        params.finish().apply(&mut image);
+        save(&image, &run.filled, &filename, &params);
+        notify(
+            &params,
+            &params.on_complete,
+            EventKind::Complete,
+            &filename,
+            1.0,
+            (total, total),
+            start,
+        );
+        return;
+    }
+    let record = params.needs_placements();
+    let mut generator = match &resumed {
+        Some((path, scene, reseed)) => {
+            let generator = scene
+                .generator(&params, record, *reseed)
+                .unwrap_or_else(|e| {
+                    eprintln!("{path}: {e}");
+                    std::process::exit(1);
+                });
+            status(
+                &params,
+                &format!(
+                    "Resumed {path} at {} of {} pixels",
+                    generator.placed(),
+                    generator.total()
+                ),
+            );
+            generator
+        }
+        None => {
+            if let Some(path) = &params.progressive {
+                let preview = params.preview();
+                let run = spinning::generate(&preview, false);
+                let mut image = run.image;
+                post::apply(&mut image, &preview.post);
+                preview.finish().apply(&mut image);
+                save(&image, &run.filled, path, &preview);
+                term_preview(&params, &image);
+                status(
+                    &params,
+                    &format!(
+                        "Saved a {} pixel preview to {path} in {:.2}s",
+                        preview.size,
+                        start.elapsed().as_secs_f64()
+                    ),
+                );
+            }
+            Generator::new(&params, record)
+        }
+    };
+    // A resumed run has passed the checkpoints and milestones up to where
+    // its scene was saved.
+    let start_coverage = generator.coverage();
+    let passed = |&&coverage: &&f64| resumed.is_some() && coverage <= start_coverage;
+    let mut checkpoints = params.checkpoints.iter().skip_while(passed).peekable();
+    let mut milestones = params.milestones.iter().skip_while(passed).peekable();
+    let mut out_of_time = false;
+    let placement = info_span!("placement").entered();
+    while generator.step() {
+        while let Some(&&coverage) = checkpoints.peek()
+            && generator.coverage() >= coverage
+        {
+            let mut image = generator.image();
+            post::apply(&mut image, &params.post);
+            save(
+                &image,
+                &generator.filled(),
+                &params.checkpoint_filename(coverage),
+                &params,
+            );
+            term_preview(&params, &image);
+            checkpoints.next();
+        }
+        while let Some(&&coverage) = milestones.peek()
+            && generator.coverage() >= coverage
+        {
+            let counts = (generator.placed(), generator.total());
+            notify(
+                &params,
+                &params.on_milestone,
+                EventKind::Milestone,
+                &filename,
+                coverage,
+                counts,
+                start,
+            );
+            milestones.next();
+        }
+        if let Some(every) = params.raw_every
+            && generator.placed().is_multiple_of(every)
+        {
+            write_raw_stdout(&generator.image()).expect("wrote frame");
+        }
+        if let Some(max_seconds) = params.max_seconds
+            && start.elapsed().as_secs_f64() >= max_seconds
+        {
+            out_of_time = true;
+            break;
+        }
+    }
+    placement.exit();
+    if let Some(path) = &params.save_scene {
+        save_scene(&generator, path).expect("saved scene");
+        status(&params, &format!("Saved the scene to {path}"));
+    }
+    let stopped = if out_of_time {
+        ", out of time"
+    } else if params.max_pixels == Some(generator.placed()) && generator.coverage() < 1.0 {
+        ", out of pixels"
+    } else {
+        ""
+    };
+    status(
+        &params,
+        &format!(
+            "Placed {} of {} pixels in {:.2}s{stopped}",
+            generator.placed(),
+            generator.total(),
+            start.elapsed().as_secs_f64()
+        ),
+    );
+    let (coverage, counts) = (
+        generator.coverage(),
+        (generator.placed(), generator.total()),
+    );
+    if let ColorSource::Inks(inks) = &params.mode {
+        for line in inks.report(generator.placed_colors()) {
+            status(&params, &line);
+        }
+    }
+    if let Some(path) = &params.walk_map {
+        let map = generator.walk_map().expect("recorded walks");
+        save_png(&map, path, &params.metadata()).expect("saved walk map");
+    }
+    let placements = if params.streams_output() {
+        if params.term_preview.is_some() {
+            term_preview(&params, &generator.image());
+        }
+        if params.raster {
+            let _span = info_span!("encoding", path = filename).entered();
+            generator
+                .save_png(&filename, &params.metadata())
+                .expect("saved");
+        }
+        generator.into_placements()
+    } else {
+        let mut run = generator.into_run();
+        if let Some(seeds) = params.blend_seeds {
+            let _span = info_span!("blending").entered();
+            run.image = blend_seeds(&params, &run);
+            status(&params, &format!("Blended {seeds} seeds"));
+        }
+        if let Some(seeds) = params.channel_seeds {
+            let _span = info_span!("channels").entered();
+            (run.image, run.filled) = split_channels(&params, &run, seeds);
+            status(&params, &format!("Split the channels over seeds {seeds:?}"));
+        }
+        if !params.layers.is_empty() {
+            run.image =
+                DynamicImage::ImageRgba8(composite(&run.image, &run.filled, &params)).to_rgb8();
+        }
+        if let Some(format) = params.stereo {
+            run.image = stereo_image(
+                &run.placements,
+                params.size,
+                format,
+                params.depth,
+                params.parallax,
+                params.unfilled.color(),
+            );
+        }
+        if params.custom_raster() {
+            // The base's kept pixels aren't placements, so they go in first.
+            let placements = match &params.base {
+                Some(base) => [base.kept_placements(), run.placements.clone()].concat(),
+                None => run.placements.clone(),
+            };
+            run.image = rasterize(
+                &placements,
+                params.size,
+                &params.raster_settings(),
+                |color| color,
+            );
+        }
+        if let Some(length) = params.smear {
+            run.image = smear(&run.image, &run.placements, length, params.smear_strength);
+        }
+        if let Some(threshold) = params.mosaic {
+            let sized = run.image.dimensions() == (params.size as u32, params.size as u32);
+            run.image = mosaic(&run.image, sized.then_some(&run.filled[..]), threshold);
+        }
+        post::apply(&mut run.image, &params.post);
+        params.finish().apply(&mut run.image);
+        if let Some(path) = &params.trace {
+            let sized = run.image.dimensions() == (params.size as u32, params.size as u32);
+            let transparent = params.unfilled == Unfilled::Transparent;
+            let filled = (sized && transparent).then_some(&run.filled[..]);
+            write_trace(&run.image, filled, params.trace_colors, path).expect("saved trace");
+        }
+        if let Some(path) = &params.center_labels {
+            let stats = center_stats(&run.placements);
+            save_png(
+                &label_overlay(&run.image, &stats, LABELS),
+                path,
+                &params.metadata(),
+            )
+            .expect("saved center labels");
+        }
+        term_preview(&params, &run.image);
+        if params.raw_stdout {
+            write_raw_stdout(&run.image).expect("wrote frame");
+        }
+        (run.image, run.filled) = mark(run.image, run.filled, &params).unwrap_or_else(|e| {
+            eprintln!("{e}");
+            std::process::exit(1);
+        });
+        if params.raster {
+            save(&run.image, &run.filled, &filename, &params);
+        }
+        for &deficiency in &params.simulate_cvd {
+            let path = companion_path(&filename, deficiency);
+            save_png(
+                &deficiency.simulate_image(&run.image),
+                &path,
+                &params.metadata(),
+            )
+            .expect("saved color vision simulation");
+        }
+        if let Some(path) = &params.cmyk {
+            save_cmyk(&run.image, path, &params).expect("saved cmyk");
+        }
+        run.placements
+    };
+    if let Some(dir) = &params.out_dir
+        && params.gallery
+        && params.raster
+    {
+        add_to_gallery(dir, &filename, &params).expect("updated gallery");
+    }
+    let (width, height) = (params.size, params.canvas.rows(params.size).len());
+    if let Some(path) = &params.depth_map {
+        save_gray_png(
+            &depth_map(&placements, width, height),
+            path,
+            &params.metadata(),
+        )
+        .expect("saved depth map");
+    }
+    if let Some(path) = &params.center_stats {
+        std::fs::write(path, stats_csv(&center_stats(&placements)))
+            .unwrap_or_else(|e| panic!("could not write {path}: {e}"));
+    }
+    if let Some(path) = &params.export {
+        write_placements(&placements, params.size, path).expect("exported placements");
+    }
+    if let Some(svg) = &params.svg {
+        write_svg(&placements, width, height, svg).expect("saved svg");
+    }
+    let plot_settings = PlotSettings {
+        pen_levels: params.pen_levels,
+        width_mm: params.plot_width_mm,
+    };
+    for (path, format) in [
+        (&params.gcode, PlotFormat::Gcode),
+        (&params.hpgl, PlotFormat::Hpgl),
+    ] {
+        if let Some(path) = path {
+            write_plot(&placements, width, height, format, &plot_settings, path)
+                .expect("saved plot");
+        }
+    }
+    notify(
+        &params,
+        &params.on_complete,
+        EventKind::Complete,
+        &filename,
+        coverage,
+        counts,
+        start,
+    );
+}
+
+// Fires each hook in turn; a failing hook is reported but leaves the run alone.
+fn notify(
+    params: &Params,
+    hooks: &[Hook],
+    kind: EventKind,
+    output: &str,
+    coverage: f64,
+    (placed, total): (usize, usize),
+    start: Instant,
+) {
+    let event = Event {
+        kind,
+        output,
+        coverage,
+        placed,
+        total,
+        seconds: start.elapsed().as_secs_f64(),
+        seed: params.seed,
+        args: &params.args,
+    };
+    for hook in hooks {
+        if let Err(e) = hook.fire(&event) {
+            eprintln!("warning: {e}");
+        }
+    }
+    if kind == EventKind::Complete
+        && let Some(db) = &params.run_db
+        && let Err(e) = record_run(db, &event)
+    {
+        eprintln!("warning: {e}");
+    }
+}
+
+// The annotation margin and signature asked for, added to the finished image.
+fn mark(
+    mut image: RgbImage,
+    mut filled: Vec<Vec<bool>>,
+    params: &Params,
+) -> Result<(RgbImage, Vec<Vec<bool>>), String> {
+    if params.annotate {
+        (image, filled) = annotate(&image, &filled, &recipe(params));
+    }
+    if let Some(path) = &params.sign {
+        let key = Key::load(path)?;
+        let author = params.author.as_deref().unwrap_or_default();
+        watermark::embed(&mut image, &key, author, &recipe(params))?;
+    }
+    Ok((image, filled))
+}
+
+fn save(image: &RgbImage, filled: &[Vec<bool>], path: &str, params: &Params) {
+    let _span = info_span!("encoding", path).entered();
+    let (width, height) = image.dimensions();
+    let thumbnail = match params.max_colors {
+        Some(n) => {
+            let (palette, indices) = quantize_image(image, n);
+            save_indexed_png(&palette, &indices, width, height, path, &params.metadata())
+                .expect("saved");
+            let image: Vec<u8> = indices.iter().flat_map(|&i| palette[i as usize]).collect();
+            params
+                .thumbnail
+                .map(|size| Thumbnail::of_image(&image, width, height, 3, size))
+        }
+        None if params.unfilled == Unfilled::Transparent
+            || params.finish().to == Unfilled::Transparent =>
+        {
+            let mut image = if params.unfilled == Unfilled::Transparent {
+                with_alpha(image, filled)
+            } else {
+                DynamicImage::ImageRgb8(image.clone()).to_rgba8()
+            };
+            params.finish().alpha(&mut image);
+            save_rgba_png(&image, path, &params.metadata()).expect("saved");
+            params
+                .thumbnail
+                .map(|size| Thumbnail::of_image(image.as_raw(), width, height, 4, size))
+        }
+        None => {
+            save_png(image, path, &params.metadata()).expect("saved");
+            params
+                .thumbnail
+                .map(|size| Thumbnail::of_image(image.as_raw(), width, height, 3, size))
+        }
+    };
+    if let Some(thumbnail) = thumbnail {
+        thumbnail
+            .save(&thumbnail_path(path), &params.metadata())
+            .expect("saved thumbnail");
+    }
+}
+
+// `presets list` prints the built-in presets, and `presets render` draws them
+// all on one labeled sheet.
+fn presets(args: &[String]) -> Result<(), String> {
+    match args.first().map(String::as_str) {
+        Some("list") | None => {
+            for preset in PRESETS {
+                println!("{:<12}{}", preset.name, preset.args.join(" "));
+            }
+            Ok(())
+        }
+        Some("render") => {
+            let mut size = 256;
+            let mut out = "presets.png".to_string();
+            let mut rest = args[1..].iter();
+            while let Some(flag) = rest.next() {
+                let value = rest.next().ok_or(format!("missing value for {flag}"))?;
+                match flag.as_str() {
+                    "--size" => size = parse(flag, value)?,
+                    "--out" => out = value.clone(),
+                    _ => return Err(format!("unknown flag {flag}")),
                 }
-                true
             }
-            None => false,
-        }
-    }
-}
-
-fn make_image(
-    size: usize,
-    num_centers: usize,
-    num_lookback: usize,
-    start_spread: f64,
-    cont_spread: f64,
-    seed: u64,
-) -> RgbImage {
-    let mut rng = StdRng::seed_from_u64(seed);
-    let mut grid: Vec<Vec<Option<Pixel>>> = vec![vec![None; size]; size];
-    let mut lookback: VecDeque<Pixel> = VecDeque::new();
-    let mut open_locs: VecMap<Location> = VecMap::new_from_vec(
-        (0..size)
-            .flat_map(|i| (0..size).map(move |j| [i, j]))
-            .collect(),
+            println!("Rendering {} presets at {size} pixels", PRESETS.len());
+            save_png(&sample_sheet(size)?, &out, &[])?;
+            println!("Saved {out}");
+            Ok(())
+        }
+        Some(other) => Err(format!(
+            "unknown presets command {other}, expected list or render"
+        )),
+    }
+}
+
+// `explore` renders random previews for picking parameters to render large.
+fn explore(args: &[String]) -> Result<(), String> {
+    let mut count = 20;
+    let mut size = 256;
+    let mut seed = 0;
+    let mut dir = "explore".to_string();
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        let value = rest.next().ok_or(format!("missing value for {flag}"))?;
+        match flag.as_str() {
+            "--count" => count = parse(flag, value)?,
+            "--size" => size = parse(flag, value)?,
+            "--seed" => seed = parse(flag, value)?,
+            "--out-dir" => dir = value.clone(),
+            _ => return Err(format!("unknown flag {flag}")),
+        }
+    }
+    spinning::explore::explore(count, size, seed, &dir)
+}
+
+// `daily` renders the day's piece, its parameters drawn from the date and
+// namespace alone.
+fn daily(args: &[String]) -> Result<(), String> {
+    use spinning::daily::{daily_args, parse_date, today};
+    let mut date = None;
+    let mut namespace = String::new();
+    let mut size = None;
+    let mut out = None;
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        let value = rest.next().ok_or(format!("missing value for {flag}"))?;
+        match flag.as_str() {
+            "--date" => date = Some(parse_date(value)?),
+            "--namespace" => namespace = value.clone(),
+            "--size" => size = Some(value.clone()),
+            "--out" => out = Some(value.clone()),
+            _ => return Err(format!("unknown flag {flag}")),
+        }
+    }
+    let date = date.unwrap_or_else(today);
+    let args = daily_args(&date, &namespace);
+    let out = out.unwrap_or_else(|| match namespace.as_str() {
+        "" => format!("daily-{date}.png"),
+        namespace => format!("daily-{namespace}-{date}.png"),
+    });
+    let extra = size
+        .map(|size| vec!["--size".to_string(), size])
+        .unwrap_or_default()
+        .into_iter()
+        .chain(["--out".to_string(), out.clone()]);
+    let params = Params::from_args(args.iter().cloned().chain(extra))?;
+    println!("{date}: {}", args.join(" "));
+    save_png(
+        &spinning::generate(&params, false).image,
+        &out,
+        &params.metadata(),
+    )?;
+    println!("Saved {out}");
+    Ok(())
+}
+
+// `layout IMAGE --paper A2` centers IMAGE on a sheet of paper for printing.
+fn layout(args: &[String]) -> Result<(), String> {
+    use spinning::output::save_png_at_dpi;
+    use spinning::paper::{Sheet, parse_length};
+    let (path, args) = args.split_first().ok_or(
+        "usage: spinning layout IMAGE --paper SIZE [--dpi N] [--margin LENGTH] [--landscape] \
+         [--resample nearest|lanczos] [--background rrggbb] [--out PATH]",
+    )?;
+    let mut paper = None;
+    let mut sheet = Sheet {
+        paper: "a4".parse()?,
+        landscape: false,
+        dpi: 300.0,
+        margin_mm: 10.0,
+        resample: None,
+        background: [255; 3],
+    };
+    let mut out = None;
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        if flag == "--landscape" {
+            sheet.landscape = true;
+            continue;
+        }
+        let value = rest.next().ok_or(format!("missing value for {flag}"))?;
+        match flag.as_str() {
+            "--paper" => paper = Some(value.parse()?),
+            "--dpi" => sheet.dpi = parse(flag, value)?,
+            "--margin" => sheet.margin_mm = parse_length(value)?,
+            "--resample" => sheet.resample = Some(value.parse()?),
+            "--background" => sheet.background = parse_color(value)?,
+            "--out" => out = Some(value.clone()),
+            _ => return Err(format!("unknown flag {flag}")),
+        }
+    }
+    sheet.paper = paper.ok_or("layout needs --paper")?;
+    if !(sheet.dpi >= 1.0 && sheet.dpi.is_finite()) {
+        return Err(format!("--dpi must be at least 1, got {}", sheet.dpi));
+    }
+    let image = spinning::compare::load(path)?;
+    let laid_out = sheet.lay_out(&image)?;
+    let out = out.unwrap_or_else(|| {
+        let stem = path
+            .rsplit_once('.')
+            .map_or(path.as_str(), |(stem, _)| stem);
+        format!("{stem}-print.png")
+    });
+    let (width, height) = laid_out.dimensions();
+    let metadata = [(
+        "Print".to_string(),
+        format!(
+            "{width}x{height} at {} dpi, {:.0}x{:.0}mm",
+            sheet.dpi,
+            width as f64 / sheet.dpi * 25.4,
+            height as f64 / sheet.dpi * 25.4
+        ),
+    )];
+    save_png_at_dpi(&laid_out, &out, &metadata, sheet.dpi)?;
+    println!("Saved {out}, {width}x{height} pixels at {} dpi", sheet.dpi);
+    Ok(())
+}
+
+// `fit TARGET` searches for parameters giving an image like TARGET.
+fn fit(args: &[String]) -> Result<(), String> {
+    let (target, args) = args
+        .split_first()
+        .ok_or("usage: spinning fit TARGET [--seconds S] [--size N] [--seed S] [--out PATH]")?;
+    let mut budget = std::time::Duration::from_secs(60);
+    let mut size = 128;
+    let mut seed = 0;
+    let mut out = "fit.png".to_string();
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        let value = rest.next().ok_or(format!("missing value for {flag}"))?;
+        match flag.as_str() {
+            "--seconds" => {
+                let seconds: f64 = parse(flag, value)?;
+                if seconds < 0.0 || !seconds.is_finite() {
+                    return Err(format!("--seconds must be at least 0, got {seconds}"));
+                }
+                budget = std::time::Duration::try_from_secs_f64(seconds)
+                    .map_err(|_| format!("--seconds {value} is too long"))?;
+            }
+            "--size" => size = parse(flag, value)?,
+            "--seed" => seed = parse(flag, value)?,
+            "--out" => out = value.clone(),
+            _ => return Err(format!("unknown flag {flag}")),
+        }
+    }
+    let target = image::open(target)
+        .map_err(|e| format!("could not open {target}: {e}"))?
+        .to_rgb8();
+    let fit = spinning::fit::fit(&target, size, budget, seed, |fit| {
+        println!(
+            "{:>6} loss {:.4}: {}",
+            fit.tried,
+            fit.loss,
+            fit.args.join(" ")
+        );
+    })?;
+    save_png(&fit.image, &out, &[])?;
+    println!(
+        "Tried {} parameter sets; the best, loss {:.4}, is in {out}:",
+        fit.tried, fit.loss
+    );
+    println!("{}", fit.args.join(" "));
+    Ok(())
+}
+
+// `palette IMAGE [--colors N] [--out PATH]` writes the main colors of an image
+// as a palette file, to stdout unless --out is given.
+fn palette(args: &[String]) -> Result<(), String> {
+    use spinning::extract::extract_palette;
+    use spinning::palette::format_palettes;
+    let (path, args) = args
+        .split_first()
+        .ok_or("usage: spinning palette IMAGE [--colors N] [--out PATH]")?;
+    let mut colors = 8;
+    let mut out = None;
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        let value = rest.next().ok_or(format!("missing value for {flag}"))?;
+        match flag.as_str() {
+            "--colors" => colors = parse(flag, value)?,
+            "--out" => out = Some(value.clone()),
+            _ => return Err(format!("unknown flag {flag}")),
+        }
+    }
+    if colors == 0 {
+        return Err("--colors must be at least 1".to_string());
+    }
+    let img = spinning::compare::load(path)?;
+    let palette = extract_palette(&img, colors);
+    let text = format!(
+        "# {} colors of {path}\n{}",
+        palette.len(),
+        format_palettes(&[palette])
     );
-    for i in 0..size * size {
-        let color = [rng.random(), rng.random(), rng.random()];
-        let insert_random = &mut |open_locs: &mut VecMap<Location>,
-                                  grid: &mut Vec<Vec<Option<Pixel>>>,
-                                  lookback: &mut VecDeque<Pixel>| {
-            let loc = open_locs.remove_random(&mut rng).expect("nonempty");
-            //let center = [rng.random_range(0..size), rng.random_range(0..size)];
-            let width = (size as f64 * start_spread) as usize;
-            let center = [
-                rng.random_range(loc[0].saturating_sub(width)..=(loc[0] + width).min(size - 1)),
-                rng.random_range(loc[1].saturating_sub(width)..=(loc[1] + width).min(size - 1)),
-            ];
-            let pixel = Pixel { color, loc, center };
-            grid[loc[0]][loc[1]] = Some(pixel);
-            lookback.push_front(pixel);
-            lookback.truncate(num_lookback);
+    match out {
+        Some(out) => {
+            std::fs::write(&out, text).map_err(|e| format!("could not write {out}: {e}"))?;
+            eprintln!("Saved the palette to {out}");
+        }
+        None => print!("{text}"),
+    }
+    Ok(())
+}
+
+// `diff [--seed-a A] [--seed-b B] [--style heat|ghost] [FLAGS...]` renders the
+// flags with two seeds and shows where the images agree.
+fn diff(args: &[String]) -> Result<(), String> {
+    use spinning::compare::{DiffStyle, diff_image, difference, pixel_distances};
+    let (mut seed_a, mut seed_b) = (None, None);
+    let mut style = DiffStyle::Heat;
+    let mut flags = vec![];
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        let mut value = || rest.next().ok_or(format!("missing value for {flag}"));
+        match flag.as_str() {
+            "--seed-a" => seed_a = Some(parse(flag, value()?)?),
+            "--seed-b" => seed_b = Some(parse(flag, value()?)?),
+            "--style" => style = value()?.parse()?,
+            _ => flags.push(flag.clone()),
+        }
+    }
+    let params = Params::from_args(flags.into_iter())?;
+    if params.voxels || params.big.is_some() {
+        return Err("diff cannot be combined with --voxels or --big".to_string());
+    }
+    let seed_a: u64 = seed_a.unwrap_or(params.seed);
+    let seed_b = seed_b.unwrap_or(seed_a.wrapping_add(1));
+    let render = |seed| {
+        let mut image = spinning::make_image(&Params {
+            seed,
+            ..params.clone()
+        });
+        post::apply(&mut image, &params.post);
+        image
+    };
+    let (a, b) = (render(seed_a), render(seed_b));
+    let out = params
+        .out
+        .clone()
+        .unwrap_or_else(|| format!("diff-{seed_a}-{seed_b}.png"));
+    save_png(&diff_image(&a, &b, style), &out, &params.metadata())?;
+    let distances = pixel_distances(&a, &b);
+    let same = distances.iter().filter(|&&d| d == 0.0).count();
+    let mean = distances.iter().sum::<f64>() / distances.len().max(1) as f64;
+    println!("Seeds {seed_a} and {seed_b}, in {out}:");
+    println!(
+        "{:.1}% of pixels the same, mean distance {mean:.3} of 1",
+        100.0 * same as f64 / distances.len().max(1) as f64
+    );
+    println!("ssim {:.4}", difference(&a, &b).ssim);
+    Ok(())
+}
+
+// `compare A B` prints how different two images are; `compare --dedup
+// FILES...` lists the images that nearly duplicate an earlier one.
+fn compare(args: &[String]) -> Result<(), String> {
+    use spinning::compare::{dhash, difference, duplicates, load};
+    let mut dedup = false;
+    let mut threshold = 6;
+    let mut paths = vec![];
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--dedup" => dedup = true,
+            "--threshold" => {
+                threshold = parse(arg, rest.next().ok_or("missing value for --threshold")?)?
+            }
+            _ => paths.push(arg.clone()),
+        }
+    }
+    if !dedup {
+        let [a, b] = &paths[..] else {
+            return Err("usage: spinning compare A B, or compare --dedup FILES...".to_string());
+        };
+        let d = difference(&load(a)?, &load(b)?);
+        println!("hash distance {} of 64", d.hash_distance);
+        println!("mean difference {:.2} of 255", d.mean_abs);
+        println!("ssim {:.4}", d.ssim);
+        return Ok(());
+    }
+    let hashes = paths
+        .iter()
+        .map(|path| load(path).map(|img| dhash(&img)))
+        .collect::<Result<Vec<u64>, String>>()?;
+    let duplicates = duplicates(&hashes, threshold);
+    for (path, duplicate) in paths.iter().zip(&duplicates) {
+        if let Some((original, distance)) = duplicate {
+            println!(
+                "{path} duplicates {} (distance {distance})",
+                paths[*original]
+            );
+        }
+    }
+    let distinct = duplicates.iter().filter(|d| d.is_none()).count();
+    eprintln!("{distinct} of {} images are distinct", paths.len());
+    Ok(())
+}
+
+// `morph --from A --to B` renders frames stepping between two parameter files.
+fn morph(args: &[String]) -> Result<(), String> {
+    let (mut from, mut to) = (None, None);
+    let mut frames = 120;
+    let mut dir = "morph".to_string();
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        let value = rest.next().ok_or(format!("missing value for {flag}"))?;
+        match flag.as_str() {
+            "--from" => from = Some(value.clone()),
+            "--to" => to = Some(value.clone()),
+            "--frames" => frames = parse(flag, value)?,
+            "--out-dir" => dir = value.clone(),
+            _ => return Err(format!("unknown flag {flag}")),
+        }
+    }
+    let (Some(from), Some(to)) = (from, to) else {
+        return Err("morph needs --from and --to".to_string());
+    };
+    spinning::morph::morph(&from, &to, frames, &dir)
+}
+
+// `loop --phase TARGET [FLAGS...]` renders frames of a seamless loop; the
+// other flags are those of a single image.
+fn render_loop(args: &[String]) -> Result<(), String> {
+    let mut frames = 60;
+    let mut target = None;
+    let mut dir = "loop".to_string();
+    let mut flags = vec![];
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        let mut value = || rest.next().ok_or(format!("missing value for {flag}"));
+        match flag.as_str() {
+            "--frames" => frames = parse(flag, value()?)?,
+            "--phase" => target = Some(value()?.clone()),
+            "--out-dir" => dir = value()?.clone(),
+            _ => flags.push(flag.clone()),
+        }
+    }
+    let target = target.ok_or("loop needs --phase hue or --phase epicycle")?;
+    spinning::phase::render_loop(&flags, &target, frames, &dir)
+}
+
+// `render-replay FILE` redraws a binary --export with another style.
+fn replay(args: &[String]) -> Result<(), String> {
+    let (path, args) = args.split_first().ok_or(
+        "usage: spinning render-replay FILE [--scale K] [--stroke W] [--kernel square|disc] \
+         [--subpixel] [--accumulate] [--palette NAME] [--background rrggbb] [--crop x,y,w,h] \
+         [--out PATH]",
+    )?;
+    let mut style = Style::default();
+    let raster = &mut style.raster;
+    let mut out = "replay.png".to_string();
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        let switch = match flag.as_str() {
+            "--subpixel" => Some(&mut raster.subpixel),
+            "--accumulate" => Some(&mut raster.accumulate),
+            _ => None,
         };
-        if i < num_centers {
-            insert_random(&mut open_locs, &mut grid, &mut lookback);
+        if let Some(switch) = switch {
+            *switch = true;
             continue;
         }
-        let nearest = lookback
-            .iter()
-            .min_by_key(|pixel| {
-                let pcolor = pixel.color;
-                color
-                    .iter()
-                    .zip(pcolor)
-                    .map(|(&c, pc)| (c as i64 - pc as i64).pow(2))
-                    .sum::<i64>()
-            })
-            .expect("find one");
-        // Walk around the circle until an open pixel is found,
-        // or a boundary is encountered,
-        // or reach start.
-        let dist = &|loc: [isize; 2]| {
-            loc.iter()
-                .zip(nearest.center)
-                .map(|(&l, cl)| (l as f64 - cl as f64).powi(2))
-                .sum()
+        let value = rest.next().ok_or(format!("missing value for {flag}"))?;
+        match flag.as_str() {
+            "--scale" => raster.scale = parse(flag, value)?,
+            "--stroke" => raster.stroke = parse(flag, value)?,
+            "--kernel" => raster.kernel = value.parse()?,
+            "--background" => raster.background = parse_color(value)?,
+            "--crop" => raster.crop = Some(value.parse()?),
+            "--palette" => style.palette = Some(parse_palette(value)?),
+            "--out" => out = value.clone(),
+            _ => return Err(format!("unknown flag {flag}")),
+        }
+    }
+    if style.raster.scale == 0 {
+        return Err("--scale must be at least 1".to_string());
+    }
+    let (size, placements) = read_placements(path)?;
+    if let Some(crop) = style.raster.crop {
+        crop.check(size)?;
+    }
+    save_png(&render_replay(&placements, size, &style), &out, &[])?;
+    println!("Saved {out}");
+    Ok(())
+}
+
+// `shader FILE` turns a binary --export into a buffer and reference shaders
+// for playing the run back on the GPU.
+fn shader(args: &[String]) -> Result<(), String> {
+    use spinning::shader::{GLSL, WGSL, replay_buffer};
+    let (path, args) = args
+        .split_first()
+        .ok_or("usage: spinning shader FILE [--out-dir DIR]")?;
+    let mut dir = "shader".to_string();
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        let value = rest.next().ok_or(format!("missing value for {flag}"))?;
+        match flag.as_str() {
+            "--out-dir" => dir = value.clone(),
+            _ => return Err(format!("unknown flag {flag}")),
+        }
+    }
+    let (size, placements) = read_placements(path)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("could not make {dir}: {e}"))?;
+    let files = [
+        ("replay.bin", replay_buffer(&placements, size)),
+        ("replay.wgsl", WGSL.as_bytes().to_vec()),
+        ("replay.glsl", GLSL.as_bytes().to_vec()),
+    ];
+    for (name, bytes) in files {
+        let out = format!("{dir}/{name}");
+        std::fs::write(&out, bytes).map_err(|e| format!("could not write {out}: {e}"))?;
+        println!("Saved {out}");
+    }
+    Ok(())
+}
+
+// `inspect FILE --at X,Y` reports where pixels of a binary --export came
+// from. --at may be given more than once.
+fn inspect(args: &[String]) -> Result<(), String> {
+    let (path, args) = args
+        .split_first()
+        .ok_or("usage: spinning inspect FILE --at X,Y [--at X,Y ...]")?;
+    let mut at = vec![];
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        let value = rest.next().ok_or(format!("missing value for {flag}"))?;
+        match flag.as_str() {
+            "--at" => match parse_list::<usize>(flag, value)?[..] {
+                [x, y] => at.push([x, y]),
+                _ => return Err(format!("--at takes X,Y, got {value}")),
+            },
+            _ => return Err(format!("unknown flag {flag}")),
+        }
+    }
+    if at.is_empty() {
+        return Err("inspect needs --at X,Y".to_string());
+    }
+    let (size, placements) = read_placements(path)?;
+    for [x, y] in at {
+        if x >= size || y >= size {
+            return Err(format!("{x},{y} is outside the {size}x{size} canvas"));
+        }
+        match provenance(&placements, [x, y]) {
+            Some(found) => found.report().iter().for_each(|line| println!("{line}")),
+            None => println!("Pixel {x},{y}: never filled"),
+        }
+    }
+    Ok(())
+}
+
+// `estimate [--sample F] [FLAGS...]` times the first placements of a run and
+// predicts how long the whole run takes and how much memory it needs.
+fn estimate(args: &[String]) -> Result<(), String> {
+    let mut sample = 0.01;
+    let mut flags = vec![];
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        match flag.as_str() {
+            "--sample" => {
+                sample = parse(flag, rest.next().ok_or("missing value for --sample")?)?;
+                if !(sample > 0.0 && sample <= 1.0) {
+                    return Err(format!("--sample must be in (0, 1], got {sample}"));
+                }
+            }
+            _ => flags.push(flag.clone()),
+        }
+    }
+    let params = Params::from_args(flags.into_iter())?;
+    if params.voxels || params.big.is_some() {
+        return Err("estimate cannot be combined with --voxels or --big".to_string());
+    }
+    let memory = spinning::estimate::memory(&params);
+    let peak = memory.iter().map(|&(_, bytes)| bytes).sum();
+    println!(
+        "Calibrating on {:.1}% of the placements; the run needs about {}",
+        sample * 100.0,
+        spinning::estimate::bytes(peak)
+    );
+    for line in spinning::estimate::estimate(&params, sample).report() {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+// `tune [FLAGS...]` adjusts the main parameters against a live preview in the
+// terminal, then renders the chosen ones at full size.
+#[cfg(feature = "tui")]
+fn tune(args: &[String]) -> Result<(), String> {
+    let mut tuner = spinning::tune::Tuner::new(args)?;
+    if !spinning::tune::run(&mut tuner)? {
+        return Ok(());
+    }
+    let args = tuner.args();
+    println!("spinning {}", args.join(" "));
+    // A fresh process, so the render is exactly the printed command.
+    let exe = std::env::current_exe().map_err(|e| format!("could not find spinning: {e}"))?;
+    let status = std::process::Command::new(exe)
+        .args(&args)
+        .status()
+        .map_err(|e| format!("could not start the render: {e}"))?;
+    if !status.success() {
+        return Err(format!("render failed: {status}"));
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "tui"))]
+fn tune(_args: &[String]) -> Result<(), String> {
+    Err("tune needs spinning built with --features tui".to_string())
+}
+
+// `branch SCENE --seeds 1..8 [FLAGS...]` finishes a saved scene once for
+// each seed, with FLAGS added to its arguments, giving a family of images
+// that share their start.
+fn branch(args: &[String]) -> Result<(), String> {
+    let (path, args) = args
+        .split_first()
+        .ok_or("usage: spinning branch SCENE --seeds A..B [FLAGS...]")?;
+    let mut seeds = None;
+    let mut flags = vec![];
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        match flag.as_str() {
+            "--seeds" => {
+                seeds = Some(parse_seeds(
+                    rest.next().ok_or("missing value for --seeds")?,
+                )?)
+            }
+            "--seed" | "--seed-text" | "--save-scene" => {
+                return Err(format!("branch cannot be combined with {flag}"));
+            }
+            _ => flags.push(flag.clone()),
+        }
+    }
+    let seeds = seeds.ok_or("branch needs --seeds")?;
+    let scene = Scene::load(path)?;
+    for seed in seeds {
+        let (params, mut generator) = scene
+            .branch(seed, &flags)
+            .map_err(|e| format!("{path}: {e}"))?;
+        if let Some(dir) = &params.out_dir {
+            std::fs::create_dir_all(dir).map_err(|e| format!("could not create {dir}: {e}"))?;
+        }
+        while generator.step() {}
+        // With --out every branch would share one file, so each gets its seed.
+        let filename = match &params.out {
+            Some(_) => {
+                let filename = params.filename();
+                let (stem, ext) = filename.rsplit_once('.').unwrap_or((&filename, "png"));
+                format!("{stem}-{seed}.{ext}")
+            }
+            None => params.filename(),
         };
-        let start = [nearest.loc[0] as isize, nearest.loc[1] as isize];
-        let mut last = start.clone();
-        let mut cur = start.clone();
-        let radius: f64 = dist(cur);
-        let mut j = 0;
-        loop {
-            j += 1;
-            let neighbors = [
-                [cur[0] + 1, cur[1] + 1],
-                [cur[0], cur[1] + 1],
-                [cur[0] - 1, cur[1] + 1],
-                [cur[0] + 1, cur[1]],
-                [cur[0] - 1, cur[1]],
-                [cur[0] + 1, cur[1] - 1],
-                [cur[0], cur[1] - 1],
-                [cur[0] - 1, cur[1] - 1],
-            ];
-            let next = neighbors
-                .into_iter()
-                .filter(|&n| n != last)
-                .min_by_key(|&n| n64((dist(n) - radius).abs()))
-                .expect("Still one left");
-            if next == start
-                || next[0] < 0
-                || next[0] >= size as isize
-                || next[1] < 0
-                || next[1] >= size as isize
-                || j as f64 > 8.0 * radius
-            {
-                insert_random(&mut open_locs, &mut grid, &mut lookback);
-                break;
+        let mut image = generator.image();
+        post::apply(&mut image, &params.post);
+        let (image, filled) = mark(image, generator.filled(), &params)?;
+        save(&image, &filled, &filename, &params);
+        println!("Seed {seed}: saved {filename}");
+    }
+    Ok(())
+}
+
+// `keygen KEYFILE` writes a new key for --sign and prints its public half,
+// which the artist publishes.
+fn keygen(args: &[String]) -> Result<(), String> {
+    let [path] = args else {
+        return Err("usage: spinning keygen KEYFILE".to_string());
+    };
+    if std::path::Path::new(path).exists() {
+        return Err(format!("{path} already exists"));
+    }
+    let (key, text) = Key::generate()?;
+    std::fs::write(path, text + "\n").map_err(|e| format!("could not write {path}: {e}"))?;
+    println!("Saved the key to {path}; its public key is");
+    println!("{}", key.public_key());
+    Ok(())
+}
+
+// `verify IMAGE [--key PUBLIC]` checks the watermark of a signed image and
+// prints who signed it and how it was made; with --key, only a watermark
+// signed by that key is accepted.
+fn verify(args: &[String]) -> Result<(), String> {
+    let (path, args) = args
+        .split_first()
+        .ok_or("usage: spinning verify IMAGE [--key PUBLIC]")?;
+    let mut expected = None;
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        let value = rest.next().ok_or(format!("missing value for {flag}"))?;
+        match flag.as_str() {
+            "--key" => expected = Some(value.to_lowercase()),
+            _ => return Err(format!("unknown flag {flag}")),
+        }
+    }
+    let image = spinning::compare::load(path)?;
+    let found = watermark::verify(&image).map_err(|e| format!("{path}: {e}"))?;
+    let key = watermark::hex(&found.public_key);
+    if let Some(expected) = expected
+        && expected != key
+    {
+        return Err(format!("{path} was signed by another key, {key}"));
+    }
+    println!("Signed by {key}");
+    if !found.author.is_empty() {
+        println!("Author: {}", found.author);
+    }
+    println!("{}", found.recipe);
+    Ok(())
+}
+
+// `schema` prints the JSON Schema of the generation parameters, and
+// `schema check FLAGS...` checks FLAGS without rendering them.
+fn schema(args: &[String]) -> Result<(), String> {
+    match args.split_first() {
+        None => print!("{}", spinning::schema::json_schema()),
+        Some((check, flags)) if check == "check" => {
+            Params::from_args(flags.iter().cloned())?;
+            println!("ok");
+        }
+        Some(_) => return Err("usage: spinning schema [check FLAGS...]".to_string()),
+    }
+    Ok(())
+}
+
+// `shard COUNT INDEX [FLAGS...]` renders tile INDEX of FLAGS split into
+// COUNT shards, into shard_INDEX.part in --out-dir or the working directory.
+fn shard(args: &[String]) -> Result<(), String> {
+    let [count, index, flags @ ..] = args else {
+        return Err("usage: spinning shard COUNT INDEX [FLAGS...]".to_string());
+    };
+    let (count, index) = (parse("COUNT", count)?, parse("INDEX", index)?);
+    let params = Params::from_args(flags.iter().cloned())?;
+    let part = shard::render(flags, count, index)?;
+    let mut path = std::path::PathBuf::from(format!("shard_{index}.part"));
+    if let Some(dir) = &params.out_dir {
+        std::fs::create_dir_all(dir).map_err(|e| format!("could not create {dir}: {e}"))?;
+        path = std::path::Path::new(dir).join(path);
+    }
+    let path = path.to_string_lossy();
+    part.save(&path)?;
+    status(
+        &params,
+        &format!("Saved shard {index} of {count} to {path}"),
+    );
+    Ok(())
+}
+
+// `panels --vary FLAG --value A --value B... [FLAGS...]` renders a panel
+// for each value, sharing the seed, centers and colors, and with
+// --composite joins them into one image.
+fn panels(args: &[String]) -> Result<(), String> {
+    use spinning::panels::{Matte, compose, panel_args, panel_params, panel_path, render_panel};
+    let usage = "usage: spinning panels --vary FLAG --value A --value B... [--composite] \
+                 [--gap N] [--frame N] [--matte rrggbb] [FLAGS...]";
+    let (mut vary, mut values, mut flags) = (None, vec![], vec![]);
+    let mut composite = false;
+    let mut matte = Matte::default();
+    let mut matte_given = false;
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        let mut value = || rest.next().ok_or(format!("missing value for {flag}"));
+        match flag.as_str() {
+            "--vary" => vary = Some(value()?.clone()),
+            "--value" => values.push(value()?.clone()),
+            "--composite" => composite = true,
+            "--gap" => (matte.gap, matte_given) = (parse(flag, value()?)?, true),
+            "--frame" => (matte.frame, matte_given) = (parse(flag, value()?)?, true),
+            "--matte" => (matte.color, matte_given) = (parse_color(value()?)?, true),
+            _ => flags.push(flag.clone()),
+        }
+    }
+    let vary = vary.ok_or(usage)?;
+    if matte_given && !composite {
+        return Err("--gap, --frame and --matte need --composite".to_string());
+    }
+    let params: Vec<Params> = panel_args(&flags, &vary, &values)?
+        .iter()
+        .map(|args| panel_params(args))
+        .collect::<Result<_, _>>()?;
+    if let Some(dir) = &params[0].out_dir {
+        std::fs::create_dir_all(dir).map_err(|e| format!("could not create {dir}: {e}"))?;
+    }
+    let filename = params[0].filename();
+    let mut images = vec![];
+    for (k, (params, value)) in params.iter().zip(&values).enumerate() {
+        let image = render_panel(params);
+        let path = panel_path(&filename, k + 1);
+        save_png(&image, &path, &params.metadata())?;
+        status(params, &format!("Saved {path} with {vary} {value}"));
+        images.push(image);
+    }
+    if composite {
+        // The metadata of the first panel, with the varied values added.
+        let mut metadata = params[0].metadata();
+        metadata.push(("panels".to_string(), format!("{vary} {}", values.join(" "))));
+        save_png(&compose(&images, &matte)?, &filename, &metadata)?;
+        status(&params[0], &format!("Saved {filename}"));
+    }
+    Ok(())
+}
+
+// `merge [--out FILE] PARTS...` puts the parts of a sharded render
+// together, into merged.png unless --out says otherwise.
+fn merge(args: &[String]) -> Result<(), String> {
+    let mut out = "merged.png".to_string();
+    let mut paths = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => out = args.next().ok_or("missing value for --out")?.clone(),
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        return Err("usage: spinning merge [--out FILE] PARTS...".to_string());
+    }
+    let parts = paths
+        .iter()
+        .map(|path| Part::load(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let (image, filled) = shard::merge(&parts)?;
+    if parts[0].transparent {
+        save_rgba_png(&with_alpha(&image, &filled), &out, &[])?;
+    } else {
+        save_png(&image, &out, &[])?;
+    }
+    println!("Merged {} shards into {out}", parts.len());
+    Ok(())
+}
+
+// `daemon [--interval 1h] [--set-wallpaper] [--keep N] [--count N]
+// [FLAGS...]` renders FLAGS with a new seed every interval into --out-dir,
+// wallpapers by default, keeping the newest N images there.
+fn daemon(args: &[String]) -> Result<(), String> {
+    use spinning::daemon::{
+        parse_interval, prune, screen_resolution, set_wallpaper, wallpaper_name,
+    };
+    let mut interval = parse_interval("1h")?;
+    let mut set = false;
+    let mut keep = 10;
+    let mut count = None;
+    let mut flags = vec![];
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        let mut value = || rest.next().ok_or(format!("missing value for {flag}"));
+        match flag.as_str() {
+            "--interval" => interval = parse_interval(value()?)?,
+            "--set-wallpaper" => set = true,
+            "--keep" => keep = parse(flag, value()?)?,
+            "--count" => count = Some(parse::<usize>(flag, value()?)?),
+            "--seed" | "--seed-text" | "--seed-file" | "--out" => {
+                return Err(format!("daemon picks its own {}", &flag[2..]));
             }
-            if grid[next[0] as usize][next[1] as usize].is_none() {
-                let color_dist_sq = color
-                    .iter()
-                    .zip(nearest.color)
-                    .map(|(&c, pc)| (c as i64 - pc as i64).pow(2))
-                    .sum::<i64>();
-                let width = (((color_dist_sq as f64).sqrt() * cont_spread) as usize).max(1);
-                let center = //nearest.center;
-                [
-                    rng.random_range(
-                        nearest.center[0].saturating_sub(width)
-                            ..=(nearest.center[0] + width).min(size),
-                    ),
-                    rng.random_range(
-                        nearest.center[1].saturating_sub(width)
-                            ..=(nearest.center[1] + width).min(size),
-                    ),
-                ];
-                let loc = [next[0] as usize, next[1] as usize];
-                let pixel = Pixel { color, loc, center };
-                /*
-                if (pixel.loc[0] as isize - start[0]).abs()
-                    == (pixel.loc[1] as isize - start[1]).abs()
-                {
-                    println!("{i} {j}\n{pixel:?}\n{nearest:?}");
+            _ => flags.push(flag.clone()),
+        }
+    }
+    if keep == 0 {
+        return Err("--keep must be at least 1".to_string());
+    }
+    if !flags.iter().any(|f| f == "--size") {
+        // Square images as large as the screen's longer side cover it.
+        let [width, height] = screen_resolution().unwrap_or([1024, 1024]);
+        flags.extend(["--size".to_string(), width.max(height).to_string()]);
+    }
+    if !flags.iter().any(|f| f == "--out-dir") {
+        flags.extend(["--out-dir".to_string(), "wallpapers".to_string()]);
+    }
+    let mut done = 0;
+    loop {
+        let started = Instant::now();
+        let params = Params::from_args_with_clock_seed(flags.clone())?;
+        let dir = params.out_dir.clone().expect("set above");
+        std::fs::create_dir_all(&dir).map_err(|e| format!("could not create {dir}: {e}"))?;
+        let seconds = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let params = Params {
+            out: Some(wallpaper_name(seconds, params.seed)),
+            ..params
+        };
+        let filename = params.filename();
+        let mut run = spinning::generate(&params, false);
+        post::apply(&mut run.image, &params.post);
+        let (image, filled) = mark(run.image, run.filled, &params)?;
+        save(&image, &filled, &filename, &params);
+        println!(
+            "Saved {filename}, seed {}, in {:.1}s",
+            params.seed,
+            started.elapsed().as_secs_f64()
+        );
+        if set && let Err(e) = set_wallpaper(std::path::Path::new(&filename)) {
+            eprintln!("warning: {e}");
+        }
+        for path in prune(std::path::Path::new(&dir), keep)? {
+            println!("Deleted {}", path.display());
+        }
+        done += 1;
+        if count.is_some_and(|count| done >= count) {
+            return Ok(());
+        }
+        std::thread::sleep(interval.saturating_sub(started.elapsed()));
+    }
+}
+
+// `batch FILE` renders each line of FILE as its own job, several at once.
+fn batch(args: &[String]) -> Result<(), String> {
+    use spinning::batch::{BatchSettings, Status, load_jobs, run_batch, summary};
+    let (path, args) = args
+        .split_first()
+        .ok_or("usage: spinning batch FILE [--jobs N] [--timeout SECONDS] [--retries K]")?;
+    let mut settings = BatchSettings {
+        exe: std::env::current_exe().map_err(|e| format!("could not find spinning: {e}"))?,
+        jobs: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        timeout: None,
+        retries: 0,
+    };
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        let value = rest.next().ok_or(format!("missing value for {flag}"))?;
+        match flag.as_str() {
+            "--jobs" => settings.jobs = parse(flag, value)?,
+            "--timeout" => {
+                let seconds: f64 = parse(flag, value)?;
+                if seconds <= 0.0 || !seconds.is_finite() {
+                    return Err(format!("--timeout must be positive, got {seconds}"));
                 }
-                */
-                grid[loc[0]][loc[1]] = Some(pixel);
-                open_locs.remove(&loc);
-                lookback.push_front(pixel);
-                lookback.truncate(num_lookback);
-                break;
+                let timeout = std::time::Duration::try_from_secs_f64(seconds)
+                    .map_err(|_| format!("--timeout {value} is too long"))?;
+                settings.timeout = Some(timeout);
             }
-            last = cur;
-            cur = next;
+            "--retries" => settings.retries = parse(flag, value)?,
+            _ => return Err(format!("unknown flag {flag}")),
         }
     }
-    let mut img: RgbImage = ImageBuffer::new(size as u32, size as u32);
-    for (i, row) in grid.into_iter().enumerate() {
-        for (j, pixel) in row.into_iter().enumerate() {
-            if let Some(pixel) = pixel {
-                img.put_pixel(i as u32, j as u32, image::Rgb(pixel.color));
+    if settings.jobs == 0 {
+        return Err("--jobs must be at least 1".to_string());
+    }
+    let jobs = load_jobs(path)?;
+    println!("Running {} jobs, {} at a time", jobs.len(), settings.jobs);
+    let results = run_batch(&jobs, &settings, |r| {
+        println!(
+            "job {} {} after {:.2}s",
+            r.index,
+            r.status.name(),
+            r.seconds
+        );
+    });
+    println!("{}", summary(&results));
+    if results.iter().any(|r| r.status != Status::Done) {
+        return Err("some jobs did not finish".to_string());
+    }
+    Ok(())
+}
+
+// `runs list|show ID|re-render ID [--db FILE]` looks through the runs
+// recorded with --run-db, and renders one of them again.
+fn runs(args: &[String]) -> Result<(), String> {
+    let usage = "usage: spinning runs list [--with FLAG[=VALUE]] [--last N], \
+                 runs show ID [--thumbnail PATH] or runs re-render ID [--out PATH], \
+                 each with [--db FILE]";
+    let (command, args) = args.split_first().ok_or(usage)?;
+    let (id, args) = match command.as_str() {
+        "list" => (None, args),
+        "show" | "re-render" => {
+            let (id, args) = args.split_first().ok_or(usage)?;
+            (Some(parse::<u64>("ID", id)?), args)
+        }
+        _ => return Err(usage.to_string()),
+    };
+    let mut db = "runs.db".to_string();
+    let mut with: Vec<String> = vec![];
+    let mut last = None;
+    let mut thumbnail = None;
+    let mut out = None;
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        let value = rest.next().ok_or(format!("missing value for {flag}"))?;
+        match (command.as_str(), flag.as_str()) {
+            (_, "--db") => db = value.clone(),
+            ("list", "--with") => with.push(value.clone()),
+            ("list", "--last") => last = Some(parse::<usize>(flag, value)?),
+            ("show", "--thumbnail") => thumbnail = Some(value.clone()),
+            ("re-render", "--out") => out = Some(value.clone()),
+            _ => return Err(format!("unknown flag {flag}")),
+        }
+    }
+    let runs = load_runs(&db)?;
+    let Some(id) = id else {
+        let matching: Vec<&RunRecord> = runs
+            .iter()
+            .filter(|run| {
+                with.iter().all(|w| match w.split_once('=') {
+                    Some((flag, value)) => run.flag(flag) == Some(value),
+                    None => run.flag(w).is_some(),
+                })
+            })
+            .collect();
+        let skip = last.map_or(0, |n| matching.len().saturating_sub(n));
+        for run in &matching[skip..] {
+            println!("{}", run.summary());
+        }
+        return Ok(());
+    };
+    let run = runs
+        .iter()
+        .find(|run| run.id == id)
+        .ok_or(format!("{db} has no run {id}"))?;
+    if command == "show" {
+        println!("{}", run.details());
+        if let Some(path) = thumbnail {
+            if run.thumbnail.is_empty() {
+                return Err(format!("run {id} has no thumbnail"));
             }
+            std::fs::write(&path, &run.thumbnail)
+                .map_err(|e| format!("could not write {path}: {e}"))?;
         }
+        return Ok(());
+    }
+    let exe = std::env::current_exe().map_err(|e| format!("could not find spinning: {e}"))?;
+    let mut command = std::process::Command::new(exe);
+    command.args(&run.args);
+    if let Some(path) = &out {
+        command.args(["--out", path]);
+    }
+    let status = command
+        .status()
+        .map_err(|e| format!("could not render run {id}: {e}"))?;
+    if !status.success() {
+        return Err(format!("rendering run {id} failed: {status}"));
     }
-    img
+    Ok(())
 }
 
-fn main() {
-    let size = 1000;
-    let num_centers = 20;
-    let num_lookback = 1000;
-    let start_spread = 0.5;
-    let cont_spread = 0.1;
-    let seed = 19;
-    let filename =
-        format!("img-{size}-{num_centers}-{num_lookback}-{start_spread}-{cont_spread}-{seed}.png");
-    println!("Start {filename}");
-    let img = make_image(
-        size,
-        num_centers,
-        num_lookback,
-        start_spread,
-        cont_spread,
-        seed,
-    );
-    img.save(&filename).expect("saved");
+// `ab --baseline REV_OR_BINARY --cases FILE` renders every case with this
+// build and the baseline, and reports how the renders differ.
+fn ab(args: &[String]) -> Result<(), String> {
+    use spinning::ab::{Baseline, load_cases, outcome, render, report};
+    let usage = "usage: spinning ab --baseline REV_OR_BINARY --cases FILE [--out-dir DIR]";
+    let (mut baseline, mut cases, mut dir) = (None, None, "ab".to_string());
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        let value = rest.next().ok_or(format!("missing value for {flag}"))?;
+        match flag.as_str() {
+            "--baseline" => baseline = Some(value.clone()),
+            "--cases" => cases = Some(value.clone()),
+            "--out-dir" => dir = value.clone(),
+            _ => return Err(format!("unknown flag {flag}")),
+        }
+    }
+    let (Some(baseline), Some(cases)) = (baseline, cases) else {
+        return Err(usage.to_string());
+    };
+    let cases = load_cases(&cases)?;
+    let current = std::env::current_exe().map_err(|e| format!("could not find spinning: {e}"))?;
+    println!("Preparing the baseline {baseline}");
+    let baseline = Baseline::prepare(&baseline)?;
+    let dir = std::path::Path::new(&dir);
+    for side in ["baseline", "current"] {
+        std::fs::create_dir_all(dir.join(side))
+            .map_err(|e| format!("could not make {}: {e}", dir.join(side).display()))?;
+    }
+    let mut outcomes = vec![];
+    for case in &cases {
+        let file = format!("{}.png", case.name);
+        let before = render(&baseline.exe, case, &dir.join("baseline").join(&file))?;
+        let after = render(&current, case, &dir.join("current").join(&file))?;
+        outcomes.push(outcome(&case.name, &before, &after));
+    }
+    println!("{}", report(&outcomes));
+    if outcomes.iter().any(|o| !o.identical()) {
+        return Err("some cases differ from the baseline".to_string());
+    }
+    Ok(())
+}
+
+// Separates the image into inks, warning about colors the press can't reach.
+fn save_cmyk(image: &RgbImage, path: &str, params: &Params) -> Result<(), String> {
+    let profile = &params.print_profile;
+    let space = params.color_space.unwrap_or_default();
+    let separation = separate(image, space, profile)?;
+    save_cmyk_tiff(&separation, profile, path)?;
+    if let Some(proof) = &params.soft_proof {
+        // The proof is sRGB, whatever the image's space.
+        let mut metadata = params.metadata();
+        for (key, value) in &mut metadata {
+            if key == COLOR_SPACE_KEY {
+                *value = ColorSpace::Srgb.name().to_string();
+            }
+        }
+        save_png(&separation.proof, proof, &metadata)?;
+    }
+    if separation.out_of_gamut > 0 {
+        let total = (separation.width * separation.height) as f64;
+        eprintln!(
+            "warning: {} pixels ({:.1}%) are out of gamut for {}, by up to {:.1} delta E",
+            separation.out_of_gamut,
+            100.0 * separation.out_of_gamut as f64 / total,
+            profile.name(),
+            separation.worst
+        );
+    }
+    Ok(())
+}
+
+// Kept off stdout when it carries raw frames.
+fn term_preview(params: &Params, image: &RgbImage) {
+    let Some(preview) = &params.term_preview else {
+        return;
+    };
+    let escape = preview.encode(image);
+    let written = if params.raw_stdout {
+        std::io::stderr().write_all(escape.as_bytes())
+    } else {
+        std::io::stdout().write_all(escape.as_bytes())
+    };
+    if let Err(e) = written {
+        eprintln!("warning: could not draw the preview: {e}");
+    }
+}
+
+// Progress lines go to stderr when stdout carries frames.
+fn status(params: &Params, message: &str) {
+    if params.raw_stdout {
+        eprintln!("{message}");
+    } else {
+        println!("{message}");
+    }
 }
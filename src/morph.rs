@@ -0,0 +1,133 @@
+#[cfg(feature = "files")]
+use std::path::Path;
+
+#[cfg(feature = "files")]
+use crate::output::save_png;
+#[cfg(feature = "files")]
+use crate::{Params, generate};
+
+// A value in a parameter file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    // `key = true`, a flag without a value.
+    Flag,
+    // Whether it was written without a decimal point is kept, so
+    // interpolated counts stay whole.
+    Number(f64, bool),
+    Text(String),
+}
+
+// Reads a parameter file of `flag = value` lines, one per command-line flag
+// without its dashes, for example:
+//
+//     # comment
+//     start-spread = 0.3
+//     palette = "viridis"
+//     reflect = true
+//     checkpoints = [0.25, 0.5]
+//
+// Keys may use _ for -, and lists become comma-separated values.
+pub fn parse_params_file(text: &str) -> Result<Vec<(String, Value)>, String> {
+    let mut entries = vec![];
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or(format!("expected flag = value, got {line}"))?;
+        let key = key.trim().replace('_', "-");
+        let value = value.trim();
+        let value = if value == "true" {
+            Value::Flag
+        } else if let Some(list) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            let items: Vec<&str> = list
+                .split(',')
+                .map(|i| i.trim().trim_matches('"'))
+                .collect();
+            Value::Text(items.join(","))
+        } else if let Some(text) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+            Value::Text(text.to_string())
+        } else if let Ok(n) = value.parse::<f64>() {
+            Value::Number(n, !value.contains(['.', 'e', 'E']))
+        } else {
+            Value::Text(value.to_string())
+        };
+        if value != Value::Text("false".to_string()) {
+            entries.push((key, value));
+        }
+    }
+    Ok(entries)
+}
+
+pub fn load_params_file(path: &str) -> Result<Vec<(String, Value)>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("could not read {path}: {e}"))?;
+    parse_params_file(&text).map_err(|e| format!("{path}: {e}"))
+}
+
+// Flags that keep the first file's value throughout: the seed, so every frame
+// draws the same random numbers, and the size, so frames line up.
+const FIXED: &[&str] = &["seed", "seed-text", "seed-file", "size"];
+
+// The arguments at t from 0 to 1 between two parameter files. Numbers are
+// interpolated linearly; anything else switches from one to the other at the
+// halfway point.
+pub fn interpolate(from: &[(String, Value)], to: &[(String, Value)], t: f64) -> Vec<String> {
+    let mut keys: Vec<&String> = from.iter().map(|(k, _)| k).collect();
+    for (key, _) in to {
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    let find = |entries: &[(String, Value)], key: &str| {
+        entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+    };
+    let mut args = vec![];
+    for key in keys {
+        let (a, b) = (find(from, key), find(to, key));
+        let value = match (&a, &b) {
+            _ if FIXED.contains(&key.as_str()) => a.or(b),
+            (Some(Value::Number(x, xi)), Some(Value::Number(y, yi))) => {
+                let v = x + (y - x) * t;
+                Some(if *xi && *yi {
+                    Value::Number(v.round(), true)
+                } else {
+                    // Rounded so the arguments read cleanly.
+                    Value::Number((v * 1e6).round() / 1e6, false)
+                })
+            }
+            _ if t < 0.5 => a,
+            _ => b,
+        };
+        match value {
+            None => {}
+            Some(Value::Flag) => args.push(format!("--{key}")),
+            Some(Value::Number(n, _)) => args.extend([format!("--{key}"), n.to_string()]),
+            Some(Value::Text(text)) => args.extend([format!("--{key}"), text]),
+        }
+    }
+    args
+}
+
+// Renders frames images stepping from one parameter file to the other, as
+// frame-0000.png and on in dir. Separate random streams keep each frame's
+// colors and start locations from depending on how the others were used.
+#[cfg(feature = "files")]
+pub fn morph(from: &str, to: &str, frames: usize, dir: &str) -> Result<(), String> {
+    let (from, to) = (load_params_file(from)?, load_params_file(to)?);
+    std::fs::create_dir_all(dir).map_err(|e| format!("could not create {dir}: {e}"))?;
+    for frame in 0..frames {
+        let t = frame as f64 / (frames.max(2) - 1) as f64;
+        let mut args = interpolate(&from, &to, t);
+        args.push("--streams".to_string());
+        let params = Params::from_args(args.into_iter())?;
+        let path = Path::new(dir).join(format!("frame-{frame:04}.png"));
+        let path = path.to_string_lossy();
+        println!("{path}: {}", params.args.join(" "));
+        save_png(&generate(&params, false).image, &path, &params.metadata())?;
+    }
+    Ok(())
+}
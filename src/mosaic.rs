@@ -0,0 +1,84 @@
+use image::{Rgb, RgbImage};
+
+use crate::linear::{color_from_linear, color_to_linear};
+
+// Redraws the image as a quadtree of flat blocks. Starting from the whole
+// image, a block whose filled cells span more than threshold in any channel
+// splits into four; one that doesn't is painted with the mean of its filled
+// cells, in linear light. Unfilled cells are left as they are, and take no
+// part in the spread or the mean. filled is indexed [x][y]; pass None to count
+// every cell as filled.
+pub fn mosaic(image: &RgbImage, filled: Option<&[Vec<bool>]>, threshold: f64) -> RgbImage {
+    let mut out = image.clone();
+    let (width, height) = image.dimensions();
+    split(
+        image,
+        &mut out,
+        filled,
+        threshold,
+        [0, 0],
+        [width as usize, height as usize],
+    );
+    out
+}
+
+fn split(
+    image: &RgbImage,
+    out: &mut RgbImage,
+    filled: Option<&[Vec<bool>]>,
+    threshold: f64,
+    origin: [usize; 2],
+    [width, height]: [usize; 2],
+) {
+    if width == 0 || height == 0 {
+        return;
+    }
+    let is_filled = |x: usize, y: usize| filled.is_none_or(|f| f[x][y]);
+    let cells = || {
+        (origin[1]..origin[1] + height)
+            .flat_map(move |y| (origin[0]..origin[0] + width).map(move |x| (x, y)))
+            .filter(|&(x, y)| is_filled(x, y))
+    };
+    let mut low = [u8::MAX; 3];
+    let mut high = [u8::MIN; 3];
+    for (x, y) in cells() {
+        let Rgb(color) = *image.get_pixel(x as u32, y as u32);
+        for ch in 0..3 {
+            low[ch] = low[ch].min(color[ch]);
+            high[ch] = high[ch].max(color[ch]);
+        }
+    }
+    let spread = (0..3).map(|ch| high[ch].saturating_sub(low[ch])).max();
+    if (width > 1 || height > 1) && spread.is_some_and(|s| s as f64 > threshold) {
+        let (left, top) = (width.div_ceil(2), height.div_ceil(2));
+        for (dx, w) in [(0, left), (left, width - left)] {
+            for (dy, h) in [(0, top), (top, height - top)] {
+                split(
+                    image,
+                    out,
+                    filled,
+                    threshold,
+                    [origin[0] + dx, origin[1] + dy],
+                    [w, h],
+                );
+            }
+        }
+        return;
+    }
+    let mut sum = [0.0; 3];
+    let mut count = 0;
+    for (x, y) in cells() {
+        let color = color_to_linear(image.get_pixel(x as u32, y as u32).0);
+        for ch in 0..3 {
+            sum[ch] += color[ch];
+        }
+        count += 1;
+    }
+    if count == 0 {
+        return;
+    }
+    let mean = Rgb(color_from_linear(sum.map(|s| s / count as f32)));
+    for (x, y) in cells() {
+        out.put_pixel(x as u32, y as u32, mean);
+    }
+}
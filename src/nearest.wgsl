@@ -0,0 +1,54 @@
+// One invocation per lookback slot. Each workgroup writes its best
+// (distance, age, slot); the CPU picks the best of those.
+
+struct Query {
+    color: u32,
+    min_age: u32,
+    count: u32,
+    pad: u32,
+}
+
+@group(0) @binding(0) var<storage, read> slots: array<vec2<u32>>;
+@group(0) @binding(1) var<uniform> query: Query;
+@group(0) @binding(2) var<storage, read_write> partials: array<vec4<u32>>;
+
+var<workgroup> best: array<vec4<u32>, 256>;
+
+// Lower distance wins, then the newer entry.
+fn better(a: vec4<u32>, b: vec4<u32>) -> bool {
+    return a.x < b.x || (a.x == b.x && a.y > b.y);
+}
+
+fn channel(c: u32, shift: u32) -> i32 {
+    return i32((c >> shift) & 0xffu);
+}
+
+@compute @workgroup_size(256)
+fn main(
+    @builtin(global_invocation_id) gid: vec3<u32>,
+    @builtin(local_invocation_index) lid: u32,
+    @builtin(workgroup_id) wid: vec3<u32>,
+) {
+    var mine = vec4<u32>(0xffffffffu, 0u, 0u, 0u);
+    let i = gid.x;
+    if (i < query.count) {
+        let slot = slots[i];
+        if (slot.y != 0u && slot.y >= query.min_age) {
+            let dr = channel(slot.x, 0u) - channel(query.color, 0u);
+            let dg = channel(slot.x, 8u) - channel(query.color, 8u);
+            let db = channel(slot.x, 16u) - channel(query.color, 16u);
+            mine = vec4<u32>(u32(dr * dr + dg * dg + db * db), slot.y, i, 0u);
+        }
+    }
+    best[lid] = mine;
+    workgroupBarrier();
+    for (var stride = 128u; stride > 0u; stride = stride >> 1u) {
+        if (lid < stride && better(best[lid + stride], best[lid])) {
+            best[lid] = best[lid + stride];
+        }
+        workgroupBarrier();
+    }
+    if (lid == 0u) {
+        partials[wid.x] = best[0];
+    }
+}
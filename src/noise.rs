@@ -0,0 +1,53 @@
+use crate::Color;
+use crate::rng::splitmix64;
+
+const OCTAVES: usize = 3;
+
+// Colors from three octaves of gradient noise along placement time, one
+// noise per channel, so the hue drifts slowly through the run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoiseField {
+    // Noise lattice cells crossed over the run by the lowest octave.
+    pub scale: f64,
+    pub seed: u64,
+}
+
+impl NoiseField {
+    // The color for placement i of total.
+    pub fn color(&self, i: usize, total: usize) -> Color {
+        let t = i as f64 / total.max(1) as f64 * self.scale;
+        [0, 1, 2].map(|ch| {
+            let salt = splitmix64(self.seed ^ splitmix64(ch as u64 + 1));
+            let mut sum = 0.0;
+            let mut amplitude = 1.0;
+            let mut frequency = 1.0;
+            for octave in 0..OCTAVES {
+                let salt = splitmix64(salt.wrapping_add(octave as u64));
+                // A random shift, so channels and octaves don't all cross zero together.
+                let shift = unit(splitmix64(salt)) * 1024.0;
+                sum += amplitude * gradient_noise(t * frequency + shift, salt);
+                amplitude /= 2.0;
+                frequency *= 2.0;
+            }
+            // One-dimensional noise mostly stays within ±0.3; stretch that to the channel.
+            ((0.5 + sum * 1.5) * 255.0).round().clamp(0.0, 255.0) as u8
+        })
+    }
+}
+
+// Perlin noise in one dimension: random slopes at the integers, blended
+// with a quintic fade. Zero at every integer, within about ±0.5 between.
+pub fn gradient_noise(x: f64, salt: u64) -> f64 {
+    let cell = x.floor();
+    let f = x - cell;
+    let slope = |k: f64| unit(splitmix64(salt ^ splitmix64(k as i64 as u64))) * 2.0 - 1.0;
+    let fade = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);
+    let a = slope(cell) * f;
+    let b = slope(cell + 1.0) * (f - 1.0);
+    a + (b - a) * fade
+}
+
+// In 0..1.
+fn unit(h: u64) -> f64 {
+    (h >> 11) as f64 / (1u64 << 53) as f64
+}
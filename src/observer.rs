@@ -0,0 +1,17 @@
+use crate::{Pixel, Placement};
+
+// Callbacks from a generator as it runs, for progress displays, live views
+// or capturing data without a loop of one's own. Each does nothing unless
+// implemented, and runs on the thread stepping the generator.
+pub trait Observer: Send {
+    // Every pixel placed, as --export records it.
+    fn on_placement(&mut self, _placement: &Placement) {}
+
+    // A pixel starting a new center, placed at random or at a laid-out
+    // point rather than by a walk. on_placement sees it too.
+    fn on_random_insert(&mut self, _pixel: &Pixel) {}
+
+    // Each tenth of the cells placed, with the coverage so far, when the
+    // milestone log line is written.
+    fn on_milestone(&mut self, _coverage: f64) {}
+}
@@ -0,0 +1,140 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::Location;
+
+// The open cells of a square canvas, for finding the one nearest a point.
+// A bit per cell, then counts of open cells in blocks of 2x2, 4x4 and so on
+// up to one block for the canvas. A query visits blocks nearest first and
+// skips empty ones, so it stays cheap however few cells are left.
+#[derive(Debug, Clone)]
+pub struct OpenIndex {
+    size: usize,
+    bits: Vec<u64>,
+    // counts[k - 1] holds the blocks of side 2^k, row by row in x.
+    counts: Vec<Vec<u32>>,
+    len: usize,
+}
+
+impl OpenIndex {
+    pub fn new(size: usize, open: &[Location]) -> Self {
+        let mut counts = vec![];
+        let mut side = size;
+        while side > 1 {
+            side = side.div_ceil(2);
+            counts.push(vec![0; side * side]);
+        }
+        let mut index = Self {
+            size,
+            bits: vec![0; (size * size).div_ceil(64)],
+            counts,
+            len: 0,
+        };
+        for &loc in open {
+            index.insert(loc);
+        }
+        index
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn contains(&self, loc: Location) -> bool {
+        let i = loc[0] * self.size + loc[1];
+        self.bits[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    // Returns whether loc was closed before.
+    pub fn insert(&mut self, loc: Location) -> bool {
+        if self.contains(loc) {
+            return false;
+        }
+        let i = loc[0] * self.size + loc[1];
+        self.bits[i / 64] |= 1 << (i % 64);
+        self.adjust(loc, |c| *c += 1);
+        self.len += 1;
+        true
+    }
+
+    // Returns whether loc was open before.
+    pub fn remove(&mut self, loc: Location) -> bool {
+        if !self.contains(loc) {
+            return false;
+        }
+        let i = loc[0] * self.size + loc[1];
+        self.bits[i / 64] &= !(1 << (i % 64));
+        self.adjust(loc, |c| *c -= 1);
+        self.len -= 1;
+        true
+    }
+
+    fn adjust(&mut self, loc: Location, change: impl Fn(&mut u32)) {
+        let mut side = self.size;
+        for (k, counts) in self.counts.iter_mut().enumerate() {
+            side = side.div_ceil(2);
+            change(&mut counts[(loc[0] >> (k + 1)) * side + (loc[1] >> (k + 1))]);
+        }
+    }
+
+    fn count(&self, level: usize, block: Location) -> u32 {
+        if level == 0 {
+            return self.contains(block) as u32;
+        }
+        let side = self.size.div_ceil(1 << level);
+        self.counts[level - 1][block[0] * side + block[1]]
+    }
+
+    // The open cell closest to loc, the first in x then y among equals.
+    pub fn nearest(&self, loc: Location) -> Option<Location> {
+        if self.len == 0 {
+            return None;
+        }
+        if self.contains(loc) {
+            return Some(loc);
+        }
+        let top = self.counts.len();
+        // Blocks by their least possible squared distance from loc; at equal
+        // distances larger blocks open first, so equal cells come out in order.
+        let mut heap = BinaryHeap::from([Reverse((0, Reverse(top), [0, 0]))]);
+        while let Some(Reverse((_, Reverse(level), block))) = heap.pop() {
+            if level == 0 {
+                return Some(block);
+            }
+            let side = self.size.div_ceil(1 << (level - 1));
+            for dx in 0..2 {
+                for dy in 0..2 {
+                    let child = [block[0] * 2 + dx, block[1] * 2 + dy];
+                    if child[0] < side && child[1] < side && self.count(level - 1, child) > 0 {
+                        let distance = self.distance_sq(loc, level - 1, child);
+                        heap.push(Reverse((distance, Reverse(level - 1), child)));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn distance_sq(&self, loc: Location, level: usize, block: Location) -> usize {
+        let axis = |p: usize, b: usize| {
+            let (low, high) = (b << level, ((b + 1) << level) - 1);
+            low.saturating_sub(p) + p.saturating_sub(high)
+        };
+        axis(loc[0], block[0]).pow(2) + axis(loc[1], block[1]).pow(2)
+    }
+
+    // Bytes held, for estimates.
+    pub fn bytes(size: usize) -> usize {
+        let mut bytes = (size * size).div_ceil(64) * 8;
+        let mut side = size;
+        while side > 1 {
+            side = side.div_ceil(2);
+            bytes += side * side * 4;
+        }
+        bytes
+    }
+}
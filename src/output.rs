@@ -0,0 +1,571 @@
+use image::RgbImage;
+#[cfg(feature = "files")]
+use image::{GrayImage, RgbaImage};
+
+#[cfg(feature = "files")]
+use crate::Color;
+#[cfg(feature = "files")]
+use crate::linear::from_linear;
+use crate::linear::to_linear;
+
+use std::io::Write;
+use std::str::FromStr;
+#[cfg(feature = "files")]
+use std::{borrow::Cow, fs::File, io::BufWriter, thread};
+
+#[cfg(feature = "files")]
+use flate2::{Compress, Compression, FlushCompress, Status};
+#[cfg(feature = "files")]
+use simd_adler32::Adler32;
+
+// Images of at least this many pixels are encoded on every core.
+#[cfg(feature = "files")]
+const PARALLEL_PIXELS: u64 = 2048 * 2048;
+// Rows deflated together when encoding in parallel. Fixed, so the file
+// doesn't depend on the number of cores.
+#[cfg(feature = "files")]
+const BAND_ROWS: u32 = 256;
+
+// The RGB space the pixel values are in. Written as `srgb`, `display-p3` or
+// `adobe-rgb`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorSpace {
+    #[default]
+    Srgb,
+    DisplayP3,
+    AdobeRgb,
+}
+
+impl FromStr for ColorSpace {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "srgb" => Ok(ColorSpace::Srgb),
+            "display-p3" => Ok(ColorSpace::DisplayP3),
+            "adobe-rgb" => Ok(ColorSpace::AdobeRgb),
+            _ => Err(format!(
+                "unknown color space {s}, expected srgb, display-p3 or adobe-rgb"
+            )),
+        }
+    }
+}
+
+impl ColorSpace {
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorSpace::Srgb => "srgb",
+            ColorSpace::DisplayP3 => "display-p3",
+            ColorSpace::AdobeRgb => "adobe-rgb",
+        }
+    }
+
+    pub fn profile(self) -> moxcms::ColorProfile {
+        match self {
+            ColorSpace::Srgb => moxcms::ColorProfile::new_srgb(),
+            ColorSpace::DisplayP3 => moxcms::ColorProfile::new_display_p3(),
+            ColorSpace::AdobeRgb => moxcms::ColorProfile::new_adobe_rgb(),
+        }
+    }
+}
+
+// Metadata under this key, a color space name, also tags the PNG: an sRGB
+// chunk for srgb, the embedded ICC profile otherwise.
+pub const COLOR_SPACE_KEY: &str = "Color space";
+
+// Saves as PNG with the given key/value pairs stored as tEXt chunks, or
+// iTXt for values beyond Latin-1.
+#[cfg(feature = "files")]
+pub fn save_png(img: &RgbImage, path: &str, metadata: &[(String, String)]) -> Result<(), String> {
+    let (width, height) = img.dimensions();
+    if encodes_in_parallel(width, height) {
+        let row = width as usize * 3;
+        return save_png_bands(path, width, height, metadata, None, |y, out| {
+            out.copy_from_slice(&img.as_raw()[y as usize * row..(y as usize + 1) * row])
+        });
+    }
+    let mut encoder = encoder(path, img.width(), img.height(), metadata)?;
+    encoder.set_color(png::ColorType::Rgb);
+    write(encoder, img.as_raw(), path)
+}
+
+// Saves as PNG with a pHYs chunk saying it prints at dpi.
+#[cfg(feature = "files")]
+pub fn save_png_at_dpi(
+    img: &RgbImage,
+    path: &str,
+    metadata: &[(String, String)],
+    dpi: f64,
+) -> Result<(), String> {
+    let mut encoder = encoder(path, img.width(), img.height(), metadata)?;
+    encoder.set_color(png::ColorType::Rgb);
+    let per_meter = (dpi / 0.0254).round() as u32;
+    encoder.set_pixel_dims(Some(png::PixelDimensions {
+        xppu: per_meter,
+        yppu: per_meter,
+        unit: png::Unit::Meter,
+    }));
+    write(encoder, img.as_raw(), path)
+}
+
+#[cfg(feature = "files")]
+pub fn save_rgba_png(
+    img: &RgbaImage,
+    path: &str,
+    metadata: &[(String, String)],
+) -> Result<(), String> {
+    let mut encoder = encoder(path, img.width(), img.height(), metadata)?;
+    encoder.set_color(png::ColorType::Rgba);
+    write(encoder, img.as_raw(), path)
+}
+
+#[cfg(feature = "files")]
+pub fn save_gray_png(
+    img: &GrayImage,
+    path: &str,
+    metadata: &[(String, String)],
+) -> Result<(), String> {
+    let mut encoder = encoder(path, img.width(), img.height(), metadata)?;
+    encoder.set_color(png::ColorType::Grayscale);
+    write(encoder, img.as_raw(), path)
+}
+
+// Writes the pixels to stdout as raw RGB24, one frame after another.
+pub fn write_raw_stdout(img: &RgbImage) -> Result<(), String> {
+    let mut out = std::io::stdout().lock();
+    out.write_all(img.as_raw())
+        .and_then(|_| out.flush())
+        .map_err(|e| format!("could not write to stdout: {e}"))
+}
+
+// Saves an 8-bit indexed PNG.
+#[cfg(feature = "files")]
+pub fn save_indexed_png(
+    palette: &[Color],
+    indices: &[u8],
+    width: u32,
+    height: u32,
+    path: &str,
+    metadata: &[(String, String)],
+) -> Result<(), String> {
+    let mut encoder = encoder(path, width, height, metadata)?;
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_palette(palette.concat());
+    write(encoder, indices, path)
+}
+
+// Saves an RGB PNG whose rows are filled in one at a time by fill_row(y, row),
+// so the whole image never has to be in memory. A thumbnail, if given a size,
+// is built from the same rows.
+#[cfg(feature = "files")]
+pub fn save_png_rows(
+    path: &str,
+    width: u32,
+    height: u32,
+    metadata: &[(String, String)],
+    thumbnail: Option<u32>,
+    mut fill_row: impl FnMut(u32, &mut [u8]),
+) -> Result<(), String> {
+    let mut thumbnail = thumbnail.map(|size| Thumbnail::new(width, height, 3, size));
+    let error = |e: &dyn std::fmt::Display| format!("could not write {path}: {e}");
+    let mut encoder = encoder(path, width, height, metadata)?;
+    encoder.set_color(png::ColorType::Rgb);
+    let mut stream = encoder
+        .write_header()
+        .and_then(|w| w.into_stream_writer())
+        .map_err(|e| error(&e))?;
+    let mut row = vec![0; width as usize * 3];
+    for y in 0..height {
+        fill_row(y, &mut row);
+        stream.write_all(&row).map_err(|e| error(&e))?;
+        if let Some(thumbnail) = &mut thumbnail {
+            thumbnail.add_row(y, &row);
+        }
+    }
+    stream.finish().map_err(|e| error(&e))?;
+    match thumbnail {
+        Some(thumbnail) => thumbnail.save(&thumbnail_path(path), metadata),
+        None => Ok(()),
+    }
+}
+
+// Whether save_png and the generator encode on several threads. With
+// one core, banding only costs time.
+#[cfg(feature = "files")]
+pub fn encodes_in_parallel(width: u32, height: u32) -> bool {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    threads > 1 && width as u64 * height as u64 >= PARALLEL_PIXELS
+}
+
+// Saves an RGB PNG like save_png_rows, spreading the encoding over threads:
+// bands of rows are filtered and deflated on their own, each ending on a
+// byte boundary, and joined into one zlib stream. Rows get the same filters
+// as from the png crate, so only the deflate stream differs.
+#[cfg(feature = "files")]
+pub fn save_png_bands(
+    path: &str,
+    width: u32,
+    height: u32,
+    metadata: &[(String, String)],
+    thumbnail: Option<u32>,
+    fill_row: impl Fn(u32, &mut [u8]) + Sync,
+) -> Result<(), String> {
+    let error = |e: &dyn std::fmt::Display| format!("could not write {path}: {e}");
+    let mut encoder = encoder(path, width, height, metadata)?;
+    encoder.set_color(png::ColorType::Rgb);
+    let mut writer = encoder.write_header().map_err(|e| error(&e))?;
+    let bands = height.div_ceil(BAND_ROWS);
+    let threads = thread::available_parallelism().map_or(1, |n| n.get()) as u32;
+    let mut encoded: Vec<(u32, Band)> = thread::scope(|scope| {
+        let fill_row = &fill_row;
+        let handles: Vec<_> = (0..threads.min(bands))
+            .map(|first| {
+                scope.spawn(move || {
+                    (first..bands)
+                        .step_by(threads as usize)
+                        .map(|band| (band, encode_band(width, height, band, fill_row)))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("band encoded"))
+            .collect()
+    });
+    encoded.sort_by_key(|&(band, _)| band);
+    let mut checksum = 1;
+    for (band, encoded) in &encoded {
+        checksum = adler32_combine(checksum, encoded.adler, encoded.len);
+        let mut data = Vec::with_capacity(encoded.deflated.len() + 6);
+        if *band == 0 {
+            // Deflate with a 32K window at the default level.
+            data.extend([0x78, 0x9c]);
+        }
+        data.extend(&encoded.deflated);
+        if *band + 1 == bands {
+            data.extend(checksum.to_be_bytes());
+        }
+        writer
+            .write_chunk(png::chunk::IDAT, &data)
+            .map_err(|e| error(&e))?;
+    }
+    writer.finish().map_err(|e| error(&e))?;
+    match thumbnail {
+        Some(size) => {
+            let mut thumbnail = Thumbnail::new(width, height, 3, size);
+            let mut row = vec![0; width as usize * 3];
+            for y in 0..height {
+                fill_row(y, &mut row);
+                thumbnail.add_row(y, &row);
+            }
+            thumbnail.save(&thumbnail_path(path), metadata)
+        }
+        None => Ok(()),
+    }
+}
+
+// A band of rows, filtered and deflated.
+#[cfg(feature = "files")]
+struct Band {
+    deflated: Vec<u8>,
+    // Of the filtered bytes, for the zlib trailer.
+    adler: u32,
+    len: usize,
+}
+
+#[cfg(feature = "files")]
+fn encode_band(width: u32, height: u32, band: u32, fill_row: &impl Fn(u32, &mut [u8])) -> Band {
+    let row_len = width as usize * 3;
+    let rows = band * BAND_ROWS..((band + 1) * BAND_ROWS).min(height);
+    let mut prev = vec![0; row_len];
+    if rows.start > 0 {
+        fill_row(rows.start - 1, &mut prev);
+    }
+    let mut row = vec![0; row_len];
+    let mut filtered = Vec::with_capacity(rows.len() * (row_len + 1));
+    let mut out = vec![0; row_len];
+    for y in rows {
+        fill_row(y, &mut row);
+        let filter = adaptive_filter(&prev, &row, &mut out);
+        filtered.push(filter);
+        filtered.extend_from_slice(&out);
+        std::mem::swap(&mut prev, &mut row);
+    }
+    Band {
+        deflated: deflate(&filtered, band + 1 == height.div_ceil(BAND_ROWS)),
+        adler: {
+            let mut adler = Adler32::new();
+            adler.write(&filtered);
+            adler.finish()
+        },
+        len: filtered.len(),
+    }
+}
+
+// The png crate's adaptive choice: of Up, Sub, Average and Paeth, the last
+// with the smallest sum of filtered bytes taken as signed.
+#[cfg(feature = "files")]
+fn adaptive_filter(prev: &[u8], row: &[u8], out: &mut [u8]) -> u8 {
+    let mut best = (u64::MAX, 0);
+    for filter in [2, 1, 3, 4] {
+        filter_row(filter, prev, row, out);
+        let cost = out.iter().map(|&b| (b as i8).unsigned_abs() as u64).sum();
+        if cost <= best.0 {
+            best = (cost, filter);
+            if cost == 0 {
+                return filter;
+            }
+        }
+    }
+    if best.1 != 4 {
+        filter_row(best.1, prev, row, out);
+    }
+    best.1
+}
+
+// PNG filter type filter of one row of RGB bytes.
+#[cfg(feature = "files")]
+fn filter_row(filter: u8, prev: &[u8], row: &[u8], out: &mut [u8]) {
+    const BPP: usize = 3;
+    // The first pixel has nothing to its left.
+    let n = BPP.min(row.len());
+    for i in 0..n {
+        out[i] = row[i].wrapping_sub(match filter {
+            1 => 0,
+            3 => prev[i] / 2,
+            _ => prev[i],
+        });
+    }
+    let (rest, left) = (&mut out[n..], &row[..row.len() - n]);
+    let (up, up_left, row) = (&prev[n..], &prev[..prev.len() - n], &row[n..]);
+    match filter {
+        1 => {
+            for ((o, &r), &l) in rest.iter_mut().zip(row).zip(left) {
+                *o = r.wrapping_sub(l);
+            }
+        }
+        2 => {
+            for ((o, &r), &u) in rest.iter_mut().zip(row).zip(up) {
+                *o = r.wrapping_sub(u);
+            }
+        }
+        3 => {
+            for (((o, &r), &l), &u) in rest.iter_mut().zip(row).zip(left).zip(up) {
+                *o = r.wrapping_sub(((l as u16 + u as u16) / 2) as u8);
+            }
+        }
+        _ => {
+            for ((((o, &r), &l), &u), &ul) in
+                rest.iter_mut().zip(row).zip(left).zip(up).zip(up_left)
+            {
+                *o = r.wrapping_sub(paeth(l, u, ul));
+            }
+        }
+    }
+}
+
+// The spec's predictor in the branch-free form of stb_image.
+#[cfg(feature = "files")]
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let (a, b, c) = (a as i16, b as i16, c as i16);
+    let thresh = c * 3 - (a + b);
+    let (lo, hi) = (a.min(b), a.max(b));
+    let t0 = if hi <= thresh { lo } else { c };
+    (if thresh <= lo { hi } else { t0 }) as u8
+}
+
+// Raw deflate at the default level. Bands but the last end with a sync
+// flush, on a byte boundary, so the next band's blocks can follow.
+#[cfg(feature = "files")]
+fn deflate(data: &[u8], last: bool) -> Vec<u8> {
+    let mut compress = Compress::new(Compression::default(), false);
+    let flush = if last {
+        FlushCompress::Finish
+    } else {
+        FlushCompress::Sync
+    };
+    let mut out = Vec::with_capacity(data.len() / 2 + 1024);
+    loop {
+        let read = compress.total_in() as usize;
+        let status = compress
+            .compress_vec(&data[read..], &mut out, flush)
+            .expect("deflate never fails on a buffer");
+        let flushed =
+            !last && compress.total_in() as usize == data.len() && out.len() < out.capacity();
+        if status == Status::StreamEnd || flushed {
+            return out;
+        }
+        out.reserve(out.capacity().max(1024));
+    }
+}
+
+const ADLER: u64 = 65521;
+
+// The checksum of two pieces of data joined, from each one's and the length
+// of the second.
+pub fn adler32_combine(first: u32, second: u32, len: usize) -> u32 {
+    let (a1, b1) = ((first & 0xffff) as u64, (first >> 16) as u64);
+    let (a2, b2) = ((second & 0xffff) as u64, (second >> 16) as u64);
+    let rem = len as u64 % ADLER;
+    let a = (a1 + a2 + ADLER - 1) % ADLER;
+    let b = (b1 + b2 + rem * ((a1 + ADLER - 1) % ADLER)) % ADLER;
+    (b << 16 | a) as u32
+}
+
+// The companion of an output, as in img-thumb.png for img.png.
+pub fn thumbnail_path(path: &str) -> String {
+    let (stem, ext) = path.rsplit_once('.').unwrap_or((path, "png"));
+    format!("{stem}-thumb.{ext}")
+}
+
+// A downscaled copy whose longer side is at most `size`, built a row at a
+// time. Each thumbnail pixel is the average of the source pixels it covers,
+// in linear light.
+pub struct Thumbnail {
+    width: u32,
+    height: u32,
+    channels: usize,
+    thumb_width: u32,
+    thumb_height: u32,
+    sums: Vec<f64>,
+    counts: Vec<u64>,
+}
+
+impl Thumbnail {
+    pub fn new(width: u32, height: u32, channels: usize, size: u32) -> Self {
+        let scale = (size as f64 / width.max(height) as f64).min(1.0);
+        let thumb_width = ((width as f64 * scale).round() as u32).max(1);
+        let thumb_height = ((height as f64 * scale).round() as u32).max(1);
+        let cells = (thumb_width * thumb_height) as usize;
+        Self {
+            width,
+            height,
+            channels,
+            thumb_width,
+            thumb_height,
+            sums: vec![0.0; cells * channels],
+            counts: vec![0; cells],
+        }
+    }
+
+    // Adds image row y, `channels` bytes per pixel.
+    pub fn add_row(&mut self, y: u32, row: &[u8]) {
+        let ty = (y as u64 * self.thumb_height as u64 / self.height as u64) as usize;
+        for (x, pixel) in row.chunks_exact(self.channels).enumerate() {
+            let tx = x as u64 * self.thumb_width as u64 / self.width as u64;
+            let cell = ty * self.thumb_width as usize + tx as usize;
+            self.counts[cell] += 1;
+            // Colors are weighted by alpha, so clear pixels don't darken edges.
+            let weight = if self.channels == 4 {
+                pixel[3] as f64
+            } else {
+                1.0
+            };
+            let sums = &mut self.sums[cell * self.channels..(cell + 1) * self.channels];
+            for (k, (sum, &v)) in sums.iter_mut().zip(pixel).enumerate() {
+                *sum += if k < 3 {
+                    to_linear(v) as f64 * weight
+                } else {
+                    v as f64
+                };
+            }
+        }
+    }
+
+    pub fn of_image(data: &[u8], width: u32, height: u32, channels: usize, size: u32) -> Self {
+        let mut thumbnail = Self::new(width, height, channels, size);
+        for (y, row) in data.chunks_exact(width as usize * channels).enumerate() {
+            thumbnail.add_row(y as u32, row);
+        }
+        thumbnail
+    }
+
+    // Only the color space of the metadata is kept.
+    #[cfg(feature = "files")]
+    pub fn save(&self, path: &str, metadata: &[(String, String)]) -> Result<(), String> {
+        let data: Vec<u8> = self
+            .sums
+            .chunks_exact(self.channels)
+            .zip(&self.counts)
+            .flat_map(|(sums, &count)| {
+                let weight = if self.channels == 4 {
+                    sums[3]
+                } else {
+                    count as f64
+                };
+                let mut pixel: Vec<u8> = sums[..3]
+                    .iter()
+                    .map(|&sum| from_linear((sum / weight.max(1.0)) as f32))
+                    .collect();
+                if self.channels == 4 {
+                    pixel.push((sums[3] / count.max(1) as f64).round() as u8);
+                }
+                pixel
+            })
+            .collect();
+        let tag: Vec<(String, String)> = metadata
+            .iter()
+            .filter(|(key, _)| key == COLOR_SPACE_KEY)
+            .cloned()
+            .collect();
+        let mut encoder = encoder(path, self.thumb_width, self.thumb_height, &tag)?;
+        encoder.set_color(if self.channels == 4 {
+            png::ColorType::Rgba
+        } else {
+            png::ColorType::Rgb
+        });
+        write(encoder, &data, path)
+    }
+}
+
+#[cfg(feature = "files")]
+fn encoder(
+    path: &str,
+    width: u32,
+    height: u32,
+    metadata: &[(String, String)],
+) -> Result<png::Encoder<'static, BufWriter<File>>, String> {
+    let space = metadata
+        .iter()
+        .find(|(key, _)| key == COLOR_SPACE_KEY)
+        .map(|(_, value)| value.parse::<ColorSpace>())
+        .transpose()?;
+    let mut info = png::Info::with_size(width, height);
+    if let Some(space) = space
+        && space != ColorSpace::Srgb
+    {
+        let icc = space
+            .profile()
+            .encode()
+            .map_err(|e| format!("could not encode the {} profile: {e}", space.name()))?;
+        info.icc_profile = Some(Cow::Owned(icc));
+    }
+    let file = File::create(path).map_err(|e| format!("could not create {path}: {e}"))?;
+    let mut encoder = png::Encoder::with_info(BufWriter::new(file), info)
+        .map_err(|e| format!("could not write {path}: {e}"))?;
+    if space == Some(ColorSpace::Srgb) {
+        encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+    }
+    encoder.set_depth(png::BitDepth::Eight);
+    for (key, value) in metadata {
+        // tEXt only holds Latin-1; other text, like a --seed-text in any
+        // script, goes in an iTXt chunk as UTF-8.
+        if value.chars().all(|c| c as u32 <= 0xff) {
+            encoder.add_text_chunk(key.clone(), value.clone())
+        } else {
+            encoder.add_itxt_chunk(key.clone(), value.clone())
+        }
+        .map_err(|e| format!("invalid metadata {key}: {e}"))?;
+    }
+    Ok(encoder)
+}
+
+#[cfg(feature = "files")]
+fn write(encoder: png::Encoder<BufWriter<File>>, data: &[u8], path: &str) -> Result<(), String> {
+    let mut writer = encoder
+        .write_header()
+        .map_err(|e| format!("could not write {path}: {e}"))?;
+    writer
+        .write_image_data(data)
+        .map_err(|e| format!("could not write {path}: {e}"))
+}
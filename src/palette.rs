@@ -0,0 +1,501 @@
+use rand::prelude::*;
+
+use std::str::FromStr;
+
+use crate::color::parse_color;
+use crate::scene::{SceneReader, SceneWriter};
+use crate::{Color, color_dist_sq};
+
+// Palette files are a small subset of TOML:
+//
+//     # comment
+//     [[palette]]
+//     colors = ["#102040", "#f0c030", "#e05020"]
+//
+// Each [[palette]] table is one palette.
+pub fn load_palettes(path: &str) -> Result<Vec<Vec<Color>>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("could not read {path}: {e}"))?;
+    parse_palettes(&text).map_err(|e| format!("{path}: {e}"))
+}
+
+pub fn parse_palettes(text: &str) -> Result<Vec<Vec<Color>>, String> {
+    let mut palettes: Vec<Vec<Color>> = vec![];
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line == "[[palette]]" {
+            palettes.push(vec![]);
+            continue;
+        }
+        let Some(list) = line
+            .strip_prefix("colors")
+            .map(str::trim_start)
+            .and_then(|rest| rest.strip_prefix('='))
+        else {
+            return Err(format!("unexpected line {line}"));
+        };
+        let list = list
+            .trim()
+            .strip_prefix('[')
+            .and_then(|l| l.strip_suffix(']'))
+            .ok_or(format!("expected a list of colors, got {list}"))?;
+        let palette = palettes
+            .last_mut()
+            .ok_or("colors outside of a [[palette]] table")?;
+        for entry in list.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let quoted = entry
+                .strip_prefix('"')
+                .and_then(|e| e.strip_suffix('"'))
+                .ok_or(format!("expected a quoted color, got {entry}"))?;
+            palette.push(parse_color(quoted)?);
+        }
+    }
+    palettes.retain(|p| !p.is_empty());
+    if palettes.is_empty() {
+        return Err("no colors found".to_string());
+    }
+    Ok(palettes)
+}
+
+pub fn format_palettes(palettes: &[Vec<Color>]) -> String {
+    let mut out = String::new();
+    for palette in palettes {
+        let colors: Vec<String> = palette
+            .iter()
+            .map(|[r, g, b]| format!("\"#{r:02x}{g:02x}{b:02x}\""))
+            .collect();
+        out.push_str(&format!("[[palette]]\ncolors = [{}]\n", colors.join(", ")));
+    }
+    out
+}
+
+// Small random palette clustered around a random base color.
+pub fn random_palette<R: Rng>(size: usize, rng: &mut R) -> Vec<Color> {
+    let base: Color = [rng.random(), rng.random(), rng.random()];
+    (0..size)
+        .map(|_| base.map(|c| (c as i32 + rng.random_range(-48..=48)).clamp(0, 255) as u8))
+        .collect()
+}
+
+// One palette per initial center. Placements take the palette entry closest
+// to their candidate color; with drift, each entry random-walks as it is used.
+#[derive(Debug, Clone)]
+pub struct CenterPalettes {
+    palettes: Vec<Vec<Color>>,
+    // The palettes given, ahead of any unions of them.
+    given: usize,
+    drift: u8,
+}
+
+impl CenterPalettes {
+    pub fn new(palettes: Vec<Vec<Color>>, drift: u8) -> Self {
+        Self {
+            given: palettes.len(),
+            palettes,
+            drift,
+        }
+    }
+    // The number of palettes given, which new centers take in turn.
+    pub fn len(&self) -> usize {
+        self.given
+    }
+    pub fn is_empty(&self) -> bool {
+        self.given == 0
+    }
+    // Adds a palette with the entries of both, for merged centers, and
+    // returns its index.
+    pub fn union(&mut self, a: usize, b: usize) -> usize {
+        if a == b {
+            return a;
+        }
+        let mut palette = self.palettes[a].clone();
+        for &color in &self.palettes[b] {
+            if !palette.contains(&color) {
+                palette.push(color);
+            }
+        }
+        self.palettes.push(palette);
+        self.palettes.len() - 1
+    }
+
+    // Every palette, unions and drift included, for scenes.
+    pub fn write_scene(&self, w: &mut SceneWriter) {
+        w.usize(self.given);
+        w.usize(self.palettes.len());
+        for palette in &self.palettes {
+            w.usize(palette.len());
+            palette.iter().for_each(|&color| w.color(color));
+        }
+    }
+
+    pub fn read_scene(&mut self, r: &mut SceneReader) -> Result<(), String> {
+        self.given = r.usize()?;
+        self.palettes = (0..r.count()?)
+            .map(|_| (0..r.count()?).map(|_| r.color()).collect())
+            .collect::<Result<_, _>>()?;
+        Ok(())
+    }
+
+    pub fn assign<R: Rng>(&mut self, palette: usize, color: Color, rng: &mut R) -> Color {
+        let entries = &mut self.palettes[palette];
+        let entry = entries
+            .iter_mut()
+            .min_by_key(|entry| color_dist_sq(color, **entry))
+            .expect("palettes are nonempty");
+        let out = *entry;
+        if self.drift > 0 {
+            let drift = self.drift as i32;
+            for c in entry.iter_mut() {
+                *c = (*c as i32 + rng.random_range(-drift..=drift)).clamp(0, 255) as u8;
+            }
+        }
+        out
+    }
+}
+
+// A palette with a share of the run for each color, like a limited set of
+// inks. Written as `rrggbb:SHARE,...`; shares are relative, so `40,30,20,10`
+// and `0.4,0.3,0.2,0.1` are the same budget.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inks {
+    pub colors: Vec<Color>,
+    // Normalized to sum to 1.
+    pub shares: Vec<f64>,
+    // Start of the sequence that picks the inks, from the seed.
+    pub offset: f64,
+}
+
+impl FromStr for Inks {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        let mut colors = vec![];
+        let mut shares = vec![];
+        for entry in s.split(',').map(str::trim) {
+            let (color, share) = entry
+                .split_once(':')
+                .ok_or(format!("expected rrggbb:SHARE, got {entry}"))?;
+            let share: f64 = share
+                .parse()
+                .ok()
+                .filter(|v: &f64| *v > 0.0 && v.is_finite())
+                .ok_or(format!("ink shares must be positive, got {share}"))?;
+            colors.push(parse_color(color)?);
+            shares.push(share);
+        }
+        let sum: f64 = shares.iter().sum();
+        Ok(Inks {
+            colors,
+            shares: shares.into_iter().map(|v| v / sum).collect(),
+            offset: 0.0,
+        })
+    }
+}
+
+// The golden ratio's fractional part: multiples of it mod 1 spread evenly.
+const GOLDEN: f64 = 0.618_033_988_749_895;
+
+impl Inks {
+    // The ink for placement i. Picks follow a low-discrepancy sequence, so
+    // every prefix of the run keeps within a few pixels of the budget.
+    pub fn pick(&self, i: usize) -> Color {
+        let mut u = (self.offset + i as f64 * GOLDEN).fract();
+        for (color, share) in self.colors.iter().zip(&self.shares) {
+            if u < *share {
+                return *color;
+            }
+            u -= share;
+        }
+        *self.colors.last().expect("nonempty")
+    }
+
+    // Each ink's share of the given colors against its budget, one line per
+    // ink, then any colors not among the inks.
+    pub fn report(&self, colors: impl Iterator<Item = Color>) -> Vec<String> {
+        let mut counts = vec![0usize; self.colors.len()];
+        let mut other = 0;
+        let mut total = 0;
+        for color in colors {
+            match self.colors.iter().position(|&c| c == color) {
+                Some(k) => counts[k] += 1,
+                None => other += 1,
+            }
+            total += 1;
+        }
+        let percent = |n: usize| 100.0 * n as f64 / total.max(1) as f64;
+        let mut lines: Vec<String> = self
+            .colors
+            .iter()
+            .zip(&self.shares)
+            .zip(&counts)
+            .map(|(([r, g, b], share), &n)| {
+                format!(
+                    "ink {r:02x}{g:02x}{b:02x}: {n} pixels, {:.2}% (budget {:.2}%)",
+                    percent(n),
+                    100.0 * share
+                )
+            })
+            .collect();
+        if other > 0 {
+            lines.push(format!(
+                "other colors: {other} pixels, {:.2}%",
+                percent(other)
+            ));
+        }
+        lines
+    }
+}
+
+// Well-known color schemes for --palette. Colormaps are sampled continuously
+// along their stops; schemes are sets of discrete colors.
+pub enum Named {
+    Colormap(&'static [Color]),
+    Scheme(&'static [Color]),
+}
+
+pub const NAMES: &[&str] = &[
+    "viridis",
+    "magma",
+    "inferno",
+    "plasma",
+    "spectral",
+    "rdylbu",
+    "solarized",
+    "set1",
+    "dark2",
+    "okabe-ito",
+    "tol-bright",
+    "cividis",
+];
+
+// The named palettes that stay distinct under protanopia, deuteranopia and
+// tritanopia, which --colorblind-safe keeps to.
+pub const COLORBLIND_SAFE: &[&str] = &["okabe-ito", "tol-bright", "cividis", "viridis"];
+
+pub fn named(name: &str) -> Option<Named> {
+    Some(match name {
+        "viridis" => Named::Colormap(VIRIDIS),
+        "magma" => Named::Colormap(MAGMA),
+        "inferno" => Named::Colormap(INFERNO),
+        "plasma" => Named::Colormap(PLASMA),
+        "spectral" => Named::Colormap(SPECTRAL),
+        "rdylbu" => Named::Colormap(RDYLBU),
+        "solarized" => Named::Scheme(SOLARIZED),
+        "set1" => Named::Scheme(SET1),
+        "dark2" => Named::Scheme(DARK2),
+        "okabe-ito" => Named::Scheme(OKABE_ITO),
+        "tol-bright" => Named::Scheme(TOL_BRIGHT),
+        "cividis" => Named::Colormap(CIVIDIS),
+        _ => return None,
+    })
+}
+
+// Sampled from matplotlib and ColorBrewer.
+const VIRIDIS: &[Color] = &[
+    [0x44, 0x01, 0x54],
+    [0x48, 0x28, 0x78],
+    [0x3e, 0x49, 0x89],
+    [0x31, 0x68, 0x8e],
+    [0x26, 0x82, 0x8e],
+    [0x1f, 0x9e, 0x89],
+    [0x35, 0xb7, 0x79],
+    [0x6e, 0xce, 0x58],
+    [0xb5, 0xde, 0x2b],
+    [0xfd, 0xe7, 0x25],
+];
+
+pub const MAGMA: &[Color] = &[
+    [0x00, 0x00, 0x04],
+    [0x18, 0x0f, 0x3d],
+    [0x44, 0x0f, 0x76],
+    [0x72, 0x1f, 0x81],
+    [0x9e, 0x2f, 0x7f],
+    [0xcd, 0x40, 0x71],
+    [0xf1, 0x60, 0x5d],
+    [0xfd, 0x96, 0x68],
+    [0xfe, 0xca, 0x8d],
+    [0xfc, 0xfd, 0xbf],
+];
+
+const INFERNO: &[Color] = &[
+    [0x00, 0x00, 0x04],
+    [0x1b, 0x0c, 0x41],
+    [0x4a, 0x0c, 0x6b],
+    [0x78, 0x1c, 0x6d],
+    [0xa5, 0x2c, 0x60],
+    [0xcf, 0x44, 0x46],
+    [0xed, 0x69, 0x25],
+    [0xfb, 0x9b, 0x06],
+    [0xf7, 0xd1, 0x3d],
+    [0xfc, 0xff, 0xa4],
+];
+
+const PLASMA: &[Color] = &[
+    [0x0d, 0x08, 0x87],
+    [0x46, 0x03, 0x9f],
+    [0x72, 0x01, 0xa8],
+    [0x9c, 0x17, 0x9e],
+    [0xbd, 0x37, 0x86],
+    [0xd8, 0x57, 0x6b],
+    [0xed, 0x79, 0x53],
+    [0xfb, 0x9f, 0x3a],
+    [0xfd, 0xca, 0x26],
+    [0xf0, 0xf9, 0x21],
+];
+
+const SPECTRAL: &[Color] = &[
+    [0x9e, 0x01, 0x42],
+    [0xd5, 0x3e, 0x4f],
+    [0xf4, 0x6d, 0x43],
+    [0xfd, 0xae, 0x61],
+    [0xfe, 0xe0, 0x8b],
+    [0xff, 0xff, 0xbf],
+    [0xe6, 0xf5, 0x98],
+    [0xab, 0xdd, 0xa4],
+    [0x66, 0xc2, 0xa5],
+    [0x32, 0x88, 0xbd],
+    [0x5e, 0x4f, 0xa2],
+];
+
+const RDYLBU: &[Color] = &[
+    [0xa5, 0x00, 0x26],
+    [0xd7, 0x30, 0x27],
+    [0xf4, 0x6d, 0x43],
+    [0xfd, 0xae, 0x61],
+    [0xfe, 0xe0, 0x90],
+    [0xff, 0xff, 0xbf],
+    [0xe0, 0xf3, 0xf8],
+    [0xab, 0xd9, 0xe9],
+    [0x74, 0xad, 0xd1],
+    [0x45, 0x75, 0xb4],
+    [0x31, 0x36, 0x95],
+];
+
+const SOLARIZED: &[Color] = &[
+    [0x00, 0x2b, 0x36],
+    [0x07, 0x36, 0x42],
+    [0x58, 0x6e, 0x75],
+    [0x65, 0x7b, 0x83],
+    [0x83, 0x94, 0x96],
+    [0x93, 0xa1, 0xa1],
+    [0xee, 0xe8, 0xd5],
+    [0xfd, 0xf6, 0xe3],
+    [0xb5, 0x89, 0x00],
+    [0xcb, 0x4b, 0x16],
+    [0xdc, 0x32, 0x2f],
+    [0xd3, 0x36, 0x82],
+    [0x6c, 0x71, 0xc4],
+    [0x26, 0x8b, 0xd2],
+    [0x2a, 0xa1, 0x98],
+    [0x85, 0x99, 0x00],
+];
+
+const SET1: &[Color] = &[
+    [0xe4, 0x1a, 0x1c],
+    [0x37, 0x7e, 0xb8],
+    [0x4d, 0xaf, 0x4a],
+    [0x98, 0x4e, 0xa3],
+    [0xff, 0x7f, 0x00],
+    [0xff, 0xff, 0x33],
+    [0xa6, 0x56, 0x28],
+    [0xf7, 0x81, 0xbf],
+    [0x99, 0x99, 0x99],
+];
+
+const DARK2: &[Color] = &[
+    [0x1b, 0x9e, 0x77],
+    [0xd9, 0x5f, 0x02],
+    [0x75, 0x70, 0xb3],
+    [0xe7, 0x29, 0x8a],
+    [0x66, 0xa6, 0x1e],
+    [0xe6, 0xab, 0x02],
+    [0xa6, 0x76, 0x1d],
+    [0x66, 0x66, 0x66],
+];
+
+// Okabe and Ito's palette and Paul Tol's bright scheme, chosen to stay
+// apart for colorblind viewers, and matplotlib's cividis, which reads the
+// same under red-green deficiencies.
+pub const OKABE_ITO: &[Color] = &[
+    [0x00, 0x00, 0x00],
+    [0xe6, 0x9f, 0x00],
+    [0x56, 0xb4, 0xe9],
+    [0x00, 0x9e, 0x73],
+    [0xf0, 0xe4, 0x42],
+    [0x00, 0x72, 0xb2],
+    [0xd5, 0x5e, 0x00],
+    [0xcc, 0x79, 0xa7],
+];
+
+const TOL_BRIGHT: &[Color] = &[
+    [0x44, 0x77, 0xaa],
+    [0xee, 0x66, 0x77],
+    [0x22, 0x88, 0x33],
+    [0xcc, 0xbb, 0x44],
+    [0x66, 0xcc, 0xee],
+    [0xaa, 0x33, 0x77],
+    [0xbb, 0xbb, 0xbb],
+];
+
+const CIVIDIS: &[Color] = &[
+    [0x00, 0x22, 0x4e],
+    [0x12, 0x35, 0x70],
+    [0x3b, 0x49, 0x6c],
+    [0x57, 0x5d, 0x6d],
+    [0x70, 0x71, 0x73],
+    [0x8a, 0x87, 0x79],
+    [0xa6, 0x9d, 0x75],
+    [0xc4, 0xb5, 0x6c],
+    [0xe4, 0xcf, 0x5b],
+    [0xfe, 0xe8, 0x38],
+];
+
+// A color sequence file: one color per line, as rrggbb or r,g,b, in a file
+// ending in .csv or .txt, and raw RGB bytes, three to a color, in any other.
+// Blank lines and lines starting with # are skipped, as is a first line
+// naming columns.
+pub fn load_sequence(path: &str) -> Result<Vec<Color>, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("could not read {path}: {e}"))?;
+    let text = path.ends_with(".csv") || path.ends_with(".txt");
+    let colors = if text {
+        let text = String::from_utf8(bytes).map_err(|_| format!("{path} is not text"))?;
+        parse_sequence(&text).map_err(|e| format!("{path}: {e}"))?
+    } else {
+        if bytes.len() % 3 != 0 {
+            return Err(format!(
+                "{path} holds {} bytes, not a whole number of RGB colors",
+                bytes.len()
+            ));
+        }
+        bytes.chunks(3).map(|c| [c[0], c[1], c[2]]).collect()
+    };
+    if colors.is_empty() {
+        return Err(format!("{path} holds no colors"));
+    }
+    Ok(colors)
+}
+
+pub fn parse_sequence(text: &str) -> Result<Vec<Color>, String> {
+    let mut colors = vec![];
+    for (n, line) in text.lines().map(str::trim).enumerate() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let color = match fields[..] {
+            [hex] => parse_color(hex),
+            [r, g, b] => match [r, g, b].map(str::parse::<u8>) {
+                [Ok(r), Ok(g), Ok(b)] => Ok([r, g, b]),
+                _ => Err(format!("invalid color {line}, expected r,g,b in 0..=255")),
+            },
+            _ => Err(format!("expected rrggbb or r,g,b, got {line}")),
+        };
+        match color {
+            Ok(color) => colors.push(color),
+            // A header naming the columns.
+            Err(_) if colors.is_empty() && n == 0 => {}
+            Err(e) => return Err(format!("line {}: {e}", n + 1)),
+        }
+    }
+    Ok(colors)
+}
@@ -0,0 +1,109 @@
+use image::{Rgb, RgbImage};
+
+use crate::params::Params;
+use crate::{Color, generate, post};
+
+// Flags the panels must share for their structure to line up.
+const SHARED_FLAGS: &[&str] = &[
+    "--size",
+    "--seed",
+    "--seed-text",
+    "--seed-file",
+    "--out",
+    "--out-dir",
+];
+
+// How panels are joined into one image: side by side, gap pixels apart,
+// inside a frame pixels wide, both in the matte color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Matte {
+    pub gap: u32,
+    pub frame: u32,
+    pub color: Color,
+}
+
+impl Default for Matte {
+    fn default() -> Self {
+        Matte {
+            gap: 0,
+            frame: 0,
+            color: [255; 3],
+        }
+    }
+}
+
+// The arguments of each panel: flags with flag set to one of the values.
+// Split streams keep the centers and colors of every panel alike, however
+// the varied flag changes the walks.
+pub fn panel_args(
+    flags: &[String],
+    flag: &str,
+    values: &[String],
+) -> Result<Vec<Vec<String>>, String> {
+    if values.len() < 2 {
+        return Err("panels needs at least two --value".to_string());
+    }
+    if SHARED_FLAGS.contains(&flag) {
+        return Err(format!(
+            "panels cannot vary {flag}, which every panel shares"
+        ));
+    }
+    if flags.iter().any(|f| f == flag) {
+        return Err(format!("{flag} is varied, so it cannot also be given"));
+    }
+    Ok(values
+        .iter()
+        .map(|value| {
+            let mut args = flags.to_vec();
+            if !args.iter().any(|a| a == "--streams") {
+                args.push("--streams".to_string());
+            }
+            args.extend([flag.to_string(), value.clone()]);
+            args
+        })
+        .collect())
+}
+
+pub fn panel_params(args: &[String]) -> Result<Params, String> {
+    let params = Params::from_args(args.iter().cloned())?;
+    if params.voxels || params.big.is_some() || params.sphere || params.cubemap.is_some() {
+        return Err(
+            "panels cannot be combined with --voxels, --big, --sphere or --cubemap".to_string(),
+        );
+    }
+    Ok(params)
+}
+
+// Renders a panel as a plain run saves it, with its post-processing and
+// finish.
+pub fn render_panel(params: &Params) -> RgbImage {
+    let mut image = generate(params, false).image;
+    post::apply(&mut image, &params.post);
+    params.finish().apply(&mut image);
+    image
+}
+
+// Where panel index (from 1) of a piece saved to path goes.
+pub fn panel_path(path: &str, index: usize) -> String {
+    let (stem, ext) = path.rsplit_once('.').unwrap_or((path, "png"));
+    format!("{stem}-{index}.{ext}")
+}
+
+// The panels side by side in their matte.
+pub fn compose(panels: &[RgbImage], matte: &Matte) -> Result<RgbImage, String> {
+    let (width, height) = panels.first().ok_or("no panels to compose")?.dimensions();
+    if panels.iter().any(|p| p.dimensions() != (width, height)) {
+        return Err("the panels differ in size".to_string());
+    }
+    let n = panels.len() as u32;
+    let mut image = RgbImage::from_pixel(
+        n * width + (n - 1) * matte.gap + 2 * matte.frame,
+        height + 2 * matte.frame,
+        Rgb(matte.color),
+    );
+    for (k, panel) in panels.iter().enumerate() {
+        let left = matte.frame + k as u32 * (width + matte.gap);
+        image::imageops::replace(&mut image, panel, left as i64, matte.frame as i64);
+    }
+    Ok(image)
+}
@@ -0,0 +1,193 @@
+use std::str::FromStr;
+
+use image::imageops::{self, FilterType};
+use image::{Rgb, RgbImage};
+
+use crate::Color;
+
+const MM_PER_INCH: f64 = 25.4;
+// The most dots a sheet may have, 3 GiB as RGB.
+pub const MAX_SHEET_PIXELS: u64 = 1 << 30;
+
+// A sheet size in millimeters, portrait. Written as an ISO name from A0 to
+// A6, `letter`, `legal` or `tabloid`, or as `WxH` with a unit of mm, cm or
+// in, such as `500x700mm`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Paper {
+    pub width_mm: f64,
+    pub height_mm: f64,
+}
+
+impl FromStr for Paper {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        let named = match s.to_lowercase().as_str() {
+            "a0" => Some((841.0, 1189.0)),
+            "a1" => Some((594.0, 841.0)),
+            "a2" => Some((420.0, 594.0)),
+            "a3" => Some((297.0, 420.0)),
+            "a4" => Some((210.0, 297.0)),
+            "a5" => Some((148.0, 210.0)),
+            "a6" => Some((105.0, 148.0)),
+            "letter" => Some((215.9, 279.4)),
+            "legal" => Some((215.9, 355.6)),
+            "tabloid" => Some((279.4, 431.8)),
+            _ => None,
+        };
+        if let Some((width_mm, height_mm)) = named {
+            return Ok(Self {
+                width_mm,
+                height_mm,
+            });
+        }
+        let invalid = || {
+            format!(
+                "unknown paper {s}, expected A0 to A6, letter, legal, tabloid or WxH with a unit"
+            )
+        };
+        let (width, height) = s.split_once('x').ok_or_else(invalid)?;
+        let unit = height.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+        let height = &height[..height.len() - unit.len()];
+        let width_mm = parse_length(&format!("{width}{unit}")).map_err(|_| invalid())?;
+        let height_mm = parse_length(&format!("{height}{unit}")).map_err(|_| invalid())?;
+        if width_mm <= 0.0 || height_mm <= 0.0 {
+            return Err(invalid());
+        }
+        Ok(Self {
+            width_mm,
+            height_mm,
+        })
+    }
+}
+
+// A length such as `20mm`, `2cm` or `0.5in`, in millimeters.
+pub fn parse_length(s: &str) -> Result<f64, String> {
+    let (number, scale) = if let Some(n) = s.strip_suffix("mm") {
+        (n, 1.0)
+    } else if let Some(n) = s.strip_suffix("cm") {
+        (n, 10.0)
+    } else if let Some(n) = s.strip_suffix("in") {
+        (n, MM_PER_INCH)
+    } else {
+        return Err(format!("expected a length in mm, cm or in, got {s}"));
+    };
+    number
+        .parse::<f64>()
+        .ok()
+        .filter(|n| n.is_finite() && *n >= 0.0)
+        .map(|n| n * scale)
+        .ok_or(format!("expected a length in mm, cm or in, got {s}"))
+}
+
+// How an image is resized to fill the sheet. Written `nearest`, which keeps
+// pixels crisp, or `lanczos`, which smooths them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resample {
+    Nearest,
+    Lanczos,
+}
+
+impl FromStr for Resample {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "nearest" => Ok(Resample::Nearest),
+            "lanczos" => Ok(Resample::Lanczos),
+            _ => Err(format!(
+                "unknown resampling {s}, expected nearest or lanczos"
+            )),
+        }
+    }
+}
+
+// An image laid out on a sheet of paper for printing at dpi: as large as fits
+// inside the margin on every side, centered, on a background.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sheet {
+    pub paper: Paper,
+    pub landscape: bool,
+    pub dpi: f64,
+    pub margin_mm: f64,
+    // Without it, images are only ever scaled down, with Lanczos filtering.
+    pub resample: Option<Resample>,
+    pub background: Color,
+}
+
+impl Sheet {
+    fn dots(&self, mm: f64) -> u32 {
+        (mm / MM_PER_INCH * self.dpi).round() as u32
+    }
+
+    // The sheet's size in printed dots.
+    pub fn size(&self) -> (u32, u32) {
+        let (w, h) = (self.paper.width_mm, self.paper.height_mm);
+        let (w, h) = if self.landscape { (h, w) } else { (w, h) };
+        (self.dots(w), self.dots(h))
+    }
+
+    // The size a width by height image is printed at: the largest with its
+    // proportions inside the margins.
+    pub fn fit(&self, width: u32, height: u32) -> Result<(u32, u32), String> {
+        let (sheet_w, sheet_h) = self.size();
+        if sheet_w as u64 * sheet_h as u64 > MAX_SHEET_PIXELS {
+            return Err(format!(
+                "the sheet would be {sheet_w}x{sheet_h} dots at {} dpi, more than \
+                 {MAX_SHEET_PIXELS}; lower --dpi",
+                self.dpi
+            ));
+        }
+        let margin = self.dots(self.margin_mm).saturating_mul(2);
+        let (area_w, area_h) = (
+            sheet_w.saturating_sub(margin),
+            sheet_h.saturating_sub(margin),
+        );
+        if area_w == 0 || area_h == 0 {
+            return Err(format!(
+                "a {}mm margin leaves no room on the sheet",
+                self.margin_mm
+            ));
+        }
+        let scale = (area_w as f64 / width as f64).min(area_h as f64 / height as f64);
+        Ok((
+            ((width as f64 * scale).round() as u32).clamp(1, area_w),
+            ((height as f64 * scale).round() as u32).clamp(1, area_h),
+        ))
+    }
+
+    // The image on the sheet. An image with fewer pixels than the space it
+    // fills is refused unless a resampling is given, since it would print at
+    // less than dpi.
+    pub fn lay_out(&self, image: &RgbImage) -> Result<RgbImage, String> {
+        let (width, height) = image.dimensions();
+        let (fit_w, fit_h) = self.fit(width, height)?;
+        let filter = match self.resample {
+            _ if (fit_w, fit_h) == (width, height) => None,
+            Some(Resample::Nearest) => Some(FilterType::Nearest),
+            Some(Resample::Lanczos) => Some(FilterType::Lanczos3),
+            None if fit_w > width => {
+                let effective = self.dpi * width as f64 / fit_w as f64;
+                return Err(format!(
+                    "the image is {width}x{height} pixels, but filling the sheet at {} dpi takes \
+                     {fit_w}x{fit_h}; it would print at {effective:.0} dpi. Render it at --size \
+                     {}, or pass --resample nearest or lanczos",
+                    self.dpi,
+                    fit_w.max(fit_h)
+                ));
+            }
+            None => Some(FilterType::Lanczos3),
+        };
+        let resized = match filter {
+            Some(filter) => imageops::resize(image, fit_w, fit_h, filter),
+            None => image.clone(),
+        };
+        let (sheet_w, sheet_h) = self.size();
+        let mut sheet = RgbImage::from_pixel(sheet_w, sheet_h, Rgb(self.background));
+        imageops::replace(
+            &mut sheet,
+            &resized,
+            ((sheet_w - fit_w) / 2) as i64,
+            ((sheet_h - fit_h) / 2) as i64,
+        );
+        Ok(sheet)
+    }
+}
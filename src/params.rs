@@ -0,0 +1,1608 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::Color;
+use crate::base::BaseImage;
+use crate::blend::Blend;
+use crate::canvas::{Canvas, Unfilled};
+use crate::center::{CenterDrift, CenterLayout, Draw};
+use crate::color::{ColorSource, parse_color};
+use crate::composite::Layer;
+use crate::cubemap::Layout;
+use crate::cvd::{Deficiency, parse_deficiencies};
+use crate::finish::Finish;
+use crate::harmony::Harmony;
+use crate::hook::Hook;
+use crate::logging::LogFormat;
+use crate::lookback::{AdaptiveLookback, Eviction, LookbackPools};
+use crate::noise::NoiseField;
+use crate::output::{COLOR_SPACE_KEY, ColorSpace};
+use crate::palette::{self, Named, load_palettes, load_sequence};
+use crate::phase::Phase;
+use crate::post::{Step, steps_from_file};
+use crate::print::PrintProfile;
+use crate::quantize::Quantize;
+use crate::raster::{Crop, Kernel, Raster};
+use crate::region::RegionMap;
+use crate::rng::{RngKind, seed_from_bytes, splitmix64};
+use crate::sampler::{DensityMap, Sampling};
+use crate::schema;
+use crate::series::Modulation;
+use crate::stencil::Stencil;
+use crate::stereo::{Depth, StereoFormat};
+use crate::strategy::{Algorithm, Fallback};
+use crate::termpreview::TermPreview;
+use crate::walk::{Arms, Dashes, Neighborhood, OrbitShape, parse_spiral};
+use crate::warm::WarmStart;
+
+// The side of a --fast-profile miniature, small enough to render in well
+// under a second whatever the parameters, and of a volume's.
+pub const FAST_SIZE: usize = 96;
+pub const FAST_VOXELS: usize = 24;
+
+// The flags that choose colors other than by --palette.
+const COLOR_SOURCE_FLAGS: &[&str] = &[
+    "--mode",
+    "--center-palettes",
+    "--palette-file",
+    "--palette-jitter",
+    "--time-gradient",
+    "--noise-colors",
+    "--harmony",
+    "--inks",
+    "--color-sequence",
+    "--curve",
+];
+
+// The flags a --concurrent-walkers run follows.
+const CONCURRENT_FLAGS: &[&str] = &[
+    "--concurrent-walkers",
+    "--size",
+    "--fast-profile",
+    "--centers",
+    "--lookback",
+    "--start-spread",
+    "--cont-spread",
+    "--seed",
+    "--seed-text",
+    "--seed-file",
+    "--orbit",
+    "--spiral",
+    "--neighborhood",
+    "--reflect",
+    "--eviction",
+    "--spatial-weight",
+    "--max-match-distance",
+    "--rng",
+    "--streams",
+    "--mode",
+    "--palette",
+    "--palette-file",
+    "--palette-jitter",
+    "--time-gradient",
+    "--noise-colors",
+    "--harmony",
+    "--base-hue",
+    "--inks",
+    "--color-sequence",
+    "--curve",
+    "--curve-step",
+    "--curve-offset",
+    "--post",
+    "--post-file",
+    "--fade",
+    "--vignette",
+    "--fade-to",
+    "--max-colors",
+    "--quantize",
+    "--thumbnail",
+    "--out",
+    "--out-dir",
+    "--log-format",
+    "--strict",
+    "--on-complete",
+    "--run-db",
+];
+
+// A flag, or flags reported together, and whether the parameters set it.
+type FlagSet = (&'static str, fn(&Params) -> bool);
+
+// Flags that change the spinning algorithm's walks, which the other
+// algorithms don't have.
+const SPINNING_ONLY: &[FlagSet] = &[
+    ("--fallback", |p| p.fallback != Fallback::Random),
+    ("--endgame", |p| p.endgame.is_some()),
+    ("--arms", |p| p.arms.is_some()),
+    ("--merge-centers", |p| p.merge_centers.is_some()),
+    ("--center-stretch", |p| p.center_stretch.is_some()),
+    ("--ring-width", |p| p.ring_width.is_some()),
+    ("--center-gap", |p| p.center_gap.is_some()),
+    ("--smear", |p| p.smear.is_some()),
+    ("--skip-prob", |p| p.skip_prob > 0.0),
+    ("--skip-dashes", |p| p.skip_dashes.is_some()),
+    ("--walk-map", |p| p.walk_map.is_some()),
+];
+
+// Flags of the flat generator that the --voxels and --big runs don't have.
+const FLAT_ONLY: &[FlagSet] = &[
+    ("--fallback", |p| p.fallback != Fallback::Random),
+    ("--endgame", |p| p.endgame.is_some()),
+    ("--arms", |p| p.arms.is_some()),
+    ("--merge-centers", |p| p.merge_centers.is_some()),
+    ("--center-stretch", |p| p.center_stretch.is_some()),
+    ("--ring-width", |p| p.ring_width.is_some()),
+    ("--center-gap", |p| p.center_gap.is_some()),
+    ("--warm-start", |p| p.warm_start.is_some()),
+    ("--annotate", |p| p.annotate),
+    ("--sign", |p| p.sign.is_some()),
+    ("--save-scene", |p| p.save_scene.is_some()),
+    ("--lookback-pools", |p| p.lookback_pools.is_some()),
+    ("--trace", |p| p.trace.is_some()),
+    ("--mosaic", |p| p.mosaic.is_some()),
+    ("--color-jitter", |p| p.color_jitter > 0.0),
+    ("--gap-jump", |p| p.gap_jump > 0),
+    ("--skip-prob", |p| p.skip_prob > 0.0),
+    ("--skip-dashes", |p| p.skip_dashes.is_some()),
+    ("--walk-map", |p| p.walk_map.is_some()),
+    ("--term-preview", |p| p.term_preview.is_some()),
+    ("--adaptive-lookback", |p| p.adaptive_lookback.is_some()),
+    ("--fade and --vignette", |p| !p.finish().is_none()),
+];
+
+// Outputs drawn from the placements of a flat image, which the sphere and
+// cube map don't keep either.
+const PLANE_ONLY: &[FlagSet] = &[
+    ("--refine", |p| p.refine.is_some()),
+    ("--seed-colors", |p| p.seed_colors.is_some()),
+    ("--center-stats", |p| p.center_stats.is_some()),
+    ("--center-labels", |p| p.center_labels.is_some()),
+    ("--simulate-cvd", |p| !p.simulate_cvd.is_empty()),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SeedSource {
+    // The built-in seed, when none was given.
+    Default,
+    Number,
+    Text(String),
+    File(String),
+    // Picked from the clock for a run without a seed.
+    Clock,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SeedColors {
+    // Colors given to the first centers in turn.
+    Given(Vec<Color>),
+    // Colors drawn from the color source, each as far from the others as
+    // could be found.
+    Spread,
+}
+
+#[derive(Debug, Clone)]
+pub enum CenterPaletteSpec {
+    // A random palette of this many colors for each center.
+    Random(usize),
+    // Palettes assigned to centers in turn.
+    Given(Vec<Vec<Color>>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Params {
+    pub size: usize,
+    // Scale the run down to a miniature at most FAST_SIZE across.
+    pub fast_profile: bool,
+    // Where a miniature of the run, progressive_size across, is saved
+    // before the run itself starts.
+    pub progressive: Option<String>,
+    pub progressive_size: usize,
+    pub num_centers: usize,
+    pub num_lookback: usize,
+    pub start_spread: f64,
+    pub cont_spread: f64,
+    pub seed: u64,
+    pub seed_source: SeedSource,
+    pub rng: RngKind,
+    pub streams: bool,
+    pub regions: Option<RegionMap>,
+    pub algorithm: Algorithm,
+    pub orbit: OrbitShape,
+    // A cyclic offset, for looping animations.
+    pub phase: Option<Phase>,
+    // Change in orbit radius per walk step, in pixels.
+    pub spiral: f64,
+    // Sectors around each center that walks may place in.
+    pub arms: Option<Arms>,
+    // Open cells walks pass over: each with this chance, and outside the
+    // dashes.
+    pub skip_prob: f64,
+    pub skip_dashes: Option<Dashes>,
+    pub neighborhood: Neighborhood,
+    pub gap_jump: usize,
+    pub reflect: bool,
+    pub canvas: Canvas,
+    // Stop once this fraction of the cells is filled.
+    pub coverage: f64,
+    // Budgets after which generation stops and the partial image is saved.
+    pub max_pixels: Option<usize>,
+    pub max_seconds: Option<f64>,
+    // Where the generator's state goes once the run stops, to resume later.
+    pub save_scene: Option<String>,
+    pub log_format: Option<LogFormat>,
+    // Write frames to stdout as raw RGB24 instead of saving a PNG,
+    // every raw_every placements if set, and once at the end.
+    pub raw_stdout: bool,
+    pub raw_every: Option<usize>,
+    // Scratch file backing the grid of a big render.
+    pub big: Option<String>,
+    // Walkers placing pixels at once on a shared canvas, as an experiment.
+    pub concurrent_walkers: Option<usize>,
+    // Retire a center's pixels from the lookback once it has attracted this
+    // many, or this many placements after it started.
+    pub center_max_pixels: Option<usize>,
+    pub center_max_age: Option<usize>,
+    // Each center's orbits are ellipses this many times as long along a
+    // tilt, in degrees, as across it, both drawn per center.
+    pub center_stretch: Option<Draw>,
+    pub center_tilt: Draw,
+    // Each center's orbits keep to radii that are multiples of a width drawn
+    // per center.
+    pub ring_width: Option<Draw>,
+    // New centers, and the jittered centers pixels continue from, are kept
+    // at least this many pixels from every other live center.
+    pub center_gap: Option<f64>,
+    pub center_drift: Option<CenterDrift>,
+    // Merge two centers once this many walks of either ended against the
+    // other's pixels.
+    pub merge_centers: Option<usize>,
+    // Pull of heavy centers on the jittered centers of continuations.
+    pub gravity: f64,
+    // Parameters following time series over the run.
+    pub modulations: Vec<Modulation>,
+    // The initial centers are placed along its outline.
+    pub stencil: Option<Arc<Stencil>>,
+    pub center_layout: CenterLayout,
+    // Lloyd iterations for the relaxed layout.
+    pub relax_iters: usize,
+    // What the cells left empty become.
+    pub unfilled: Unfilled,
+    pub sampling: Sampling,
+    // Pulls new centers and random insertions toward bright areas.
+    pub density: Option<Arc<DensityMap>>,
+    // An image to sample the starting lookback and centers from, and how
+    // many pixels to sample, the lookback's size by default.
+    pub warm_start: Option<Arc<WarmStart>>,
+    pub warm_samples: Option<usize>,
+    // Generated after the main pass and blended over it, in order.
+    pub layers: Vec<Layer>,
+    // Existing artwork whose opaque pixels are kept; sets the size.
+    pub base: Option<Arc<BaseImage>>,
+    // Fill the base image's holes from the pixels around them.
+    pub inpaint: bool,
+    // Pull centers and colors toward a quarter-size pass by this much.
+    pub multiscale: f64,
+    // Coverage fractions at which to also save the image so far.
+    pub checkpoints: Vec<f64>,
+    // Runs this many seeds, from the given one up, and blends them.
+    pub blend_seeds: Option<usize>,
+    pub blend: Blend,
+    // One per seed; empty weighs them equally.
+    pub blend_weights: Vec<f64>,
+    // Seeds of the runs the red, green and blue channels are taken from.
+    pub channel_seeds: Option<[u64; 3]>,
+    // Draws the finished image, and each checkpoint, in the terminal.
+    pub term_preview: Option<TermPreview>,
+    // Notified when the run finishes, and at each milestone coverage.
+    pub on_complete: Vec<Hook>,
+    pub on_milestone: Vec<Hook>,
+    pub milestones: Vec<f64>,
+    // A run database each finished run is added to.
+    pub run_db: Option<String>,
+    // Grow a size^3 volume instead of an image.
+    pub voxels: bool,
+    // Render the six faces of a cube map instead, as a kind of voxel run.
+    pub cubemap: Option<Layout>,
+    // Render an equirectangular sphere, twice as wide as size, likewise.
+    pub sphere: bool,
+    pub slices: Option<String>,
+    pub nrrd: Option<String>,
+    pub eviction: Eviction,
+    pub lookback_pools: Option<LookbackPools>,
+    pub adaptive_lookback: Option<AdaptiveLookback>,
+    // What the orbit walk does when it finds no open pixel.
+    pub fallback: Fallback,
+    // Coverage past which colors skip the walk for the open cell nearest
+    // their match.
+    pub endgame: Option<f64>,
+    // Once the run is done, blocks of refine_block cells where at least this
+    // share of the pixels were placed at random are cleared and placed again.
+    pub refine: Option<f64>,
+    pub refine_block: usize,
+    pub spatial_weight: f64,
+    pub max_match_distance: Option<f64>,
+    pub mode: ColorSource,
+    pub center_palettes: Option<CenterPaletteSpec>,
+    // The colors of the first num_centers pixels, in place of the ones drawn
+    // for them.
+    pub seed_colors: Option<SeedColors>,
+    pub palette_drift: u8,
+    // Standard deviation of the noise added to each placed pixel's channels.
+    pub color_jitter: f64,
+    pub post: Vec<Step>,
+    // Channel spread under which a block of the finished image is flattened
+    // into one quadtree mosaic tile.
+    pub mosaic: Option<f64>,
+    // Pixels the finished image is smeared along the orbits either way, and
+    // how much of the smear is mixed in.
+    pub smear: Option<usize>,
+    pub smear_strength: f64,
+    // Border fade width, corner vignette and what they fade to, the unfilled
+    // policy unless given.
+    pub fade: usize,
+    pub vignette: f64,
+    pub fade_to: Option<Unfilled>,
+    pub max_colors: Option<usize>,
+    pub quantize: Quantize,
+    pub out: Option<String>,
+    // Longest side of a downscaled copy saved next to each raster output.
+    pub thumbnail: Option<u32>,
+    // Directory for every output file, made if missing.
+    pub out_dir: Option<String>,
+    // Add the raster output to out_dir's gallery.json and index.html.
+    pub gallery: bool,
+    // Print the arguments in a margin below the image.
+    pub annotate: bool,
+    // A key file to sign the image with, hiding the signature in its pixels,
+    // and the author named in it.
+    pub sign: Option<String>,
+    pub author: Option<String>,
+    // Refuse parameters with warnings rather than render them.
+    pub strict: bool,
+    // The arguments these parameters were parsed from.
+    pub args: Vec<String>,
+    pub svg: Option<String>,
+    // Layered SVG of the finished image's outlines, in this many colors.
+    pub trace: Option<String>,
+    pub trace_colors: usize,
+    // Each center's pixel count, mean radius and dominant hue, as a CSV,
+    // and the image with the largest centers labeled.
+    pub center_stats: Option<String>,
+    pub center_labels: Option<String>,
+    // Companions of the output showing it as colorblind viewers see it.
+    pub simulate_cvd: Vec<Deficiency>,
+    // Replace the raster output with a stereo image.
+    pub stereo: Option<StereoFormat>,
+    pub depth: Depth,
+    // Horizontal shift between the eyes for the nearest pixels.
+    pub parallax: f64,
+    // Grayscale companion image of distances from the centers.
+    pub depth_map: Option<String>,
+    // Heatmap of the cells every walk passed through.
+    pub walk_map: Option<String>,
+    // Every placement, as ND-JSON or binary records.
+    pub export: Option<String>,
+    // Redraw the recorded placements as set out in raster_settings.
+    pub crop: Option<Crop>,
+    pub scale: usize,
+    pub kernel: Kernel,
+    pub stroke: f64,
+    pub subpixel: bool,
+    pub accumulate: bool,
+    pub raster: bool,
+    pub gcode: Option<String>,
+    pub hpgl: Option<String>,
+    pub pen_levels: u8,
+    pub plot_width_mm: f64,
+    // Ink separation of the final image, as a CMYK TIFF, and how it prints.
+    pub cmyk: Option<String>,
+    pub print_profile: PrintProfile,
+    pub soft_proof: Option<String>,
+    // The space the pixel values are in, and PNGs are tagged with; untagged
+    // sRGB if unset.
+    pub color_space: Option<ColorSpace>,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            size: 1000,
+            fast_profile: false,
+            progressive: None,
+            progressive_size: FAST_SIZE,
+            num_centers: 20,
+            num_lookback: 1000,
+            start_spread: 0.5,
+            cont_spread: 0.1,
+            seed: 19,
+            seed_source: SeedSource::Default,
+            rng: RngKind::Std,
+            streams: false,
+            regions: None,
+            algorithm: Algorithm::Spinning,
+            orbit: OrbitShape::Circle,
+            phase: None,
+            spiral: 0.0,
+            arms: None,
+            skip_prob: 0.0,
+            skip_dashes: None,
+            neighborhood: Neighborhood::Eight,
+            gap_jump: 0,
+            reflect: false,
+            canvas: Canvas::Square,
+            coverage: 1.0,
+            max_pixels: None,
+            max_seconds: None,
+            save_scene: None,
+            log_format: None,
+            raw_stdout: false,
+            raw_every: None,
+            big: None,
+            concurrent_walkers: None,
+            center_max_pixels: None,
+            center_max_age: None,
+            center_stretch: None,
+            center_tilt: Draw { lo: 0.0, hi: 180.0 },
+            ring_width: None,
+            center_gap: None,
+            center_drift: None,
+            merge_centers: None,
+            gravity: 0.0,
+            modulations: vec![],
+            stencil: None,
+            center_layout: CenterLayout::Random,
+            relax_iters: 10,
+            unfilled: Unfilled::Black,
+            sampling: Sampling::Uniform,
+            density: None,
+            warm_start: None,
+            warm_samples: None,
+            layers: vec![],
+            base: None,
+            inpaint: false,
+            multiscale: 0.0,
+            checkpoints: vec![],
+            blend_seeds: None,
+            blend: Blend::Mean,
+            blend_weights: vec![],
+            channel_seeds: None,
+            term_preview: None,
+            on_complete: vec![],
+            on_milestone: vec![],
+            milestones: vec![],
+            run_db: None,
+            voxels: false,
+            cubemap: None,
+            sphere: false,
+            slices: None,
+            nrrd: None,
+            eviction: Eviction::Fifo,
+            lookback_pools: None,
+            adaptive_lookback: None,
+            fallback: Fallback::Random,
+            refine: None,
+            refine_block: 16,
+            endgame: None,
+            spatial_weight: 0.0,
+            max_match_distance: None,
+            mode: ColorSource::Uniform,
+            center_palettes: None,
+            seed_colors: None,
+            palette_drift: 0,
+            color_jitter: 0.0,
+            post: vec![],
+            mosaic: None,
+            smear: None,
+            smear_strength: 1.0,
+            fade: 0,
+            vignette: 0.0,
+            fade_to: None,
+            max_colors: None,
+            quantize: Quantize::Image,
+            out: None,
+            thumbnail: None,
+            out_dir: None,
+            gallery: false,
+            annotate: false,
+            sign: None,
+            author: None,
+            strict: false,
+            args: vec![],
+            svg: None,
+            trace: None,
+            trace_colors: 8,
+            center_stats: None,
+            center_labels: None,
+            simulate_cvd: vec![],
+            stereo: None,
+            depth: Depth::Radius,
+            parallax: 8.0,
+            depth_map: None,
+            walk_map: None,
+            export: None,
+            cmyk: None,
+            print_profile: PrintProfile::Swop,
+            soft_proof: None,
+            color_space: None,
+            crop: None,
+            scale: 1,
+            kernel: Kernel::Square,
+            stroke: 1.0,
+            subpixel: false,
+            accumulate: false,
+            raster: true,
+            gcode: None,
+            hpgl: None,
+            pen_levels: 2,
+            plot_width_mm: 200.0,
+        }
+    }
+}
+
+impl Params {
+    // The output filename with the coverage percentage before the extension,
+    // as in img-025.png.
+    pub fn checkpoint_filename(&self, coverage: f64) -> String {
+        let filename = self.filename();
+        let (stem, ext) = filename.rsplit_once('.').unwrap_or((&filename, "png"));
+        format!("{stem}-{:03}.{ext}", (coverage * 100.0).round() as usize)
+    }
+
+    // The raster output, inside out_dir if given.
+    pub fn filename(&self) -> String {
+        let name = self.base_filename();
+        match &self.out_dir {
+            Some(dir) => in_dir(dir, &name),
+            None => name,
+        }
+    }
+
+    fn base_filename(&self) -> String {
+        if let Some(out) = &self.out {
+            return out.clone();
+        }
+        let rng = match self.rng {
+            RngKind::Std => String::new(),
+            kind => format!("-{}", kind.name()),
+        };
+        let streams = if self.streams { "-streams" } else { "" };
+        format!(
+            "img-{}-{}-{}-{}-{}-{}{rng}{streams}.png",
+            self.size,
+            self.num_centers,
+            self.num_lookback,
+            self.start_spread,
+            self.cont_spread,
+            self.seed
+        )
+    }
+
+    // Plain output is encoded straight from the grid, without an image copy.
+    pub fn streams_output(&self) -> bool {
+        self.blend_seeds.is_none()
+            && self.channel_seeds.is_none()
+            && self.layers.is_empty()
+            && self.post.is_empty()
+            && self.mosaic.is_none()
+            && self.smear.is_none()
+            && self.trace.is_none()
+            && self.simulate_cvd.is_empty()
+            && self.center_labels.is_none()
+            && self.finish().is_none()
+            && !self.canvas.wraps()
+            && self.max_colors.is_none()
+            && self.unfilled != Unfilled::Transparent
+            && self.stereo.is_none()
+            && !self.custom_raster()
+            && self.cmyk.is_none()
+            && !self.raw_stdout
+            && !self.annotate
+            && self.sign.is_none()
+    }
+
+    // Combinations that render, but not as intended: each is a sentence on
+    // what goes wrong.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = vec![];
+        let cells = if self.sphere {
+            2 * self.size.pow(2)
+        } else if self.cubemap.is_some() {
+            self.size.pow(3) - self.size.saturating_sub(2).pow(3)
+        } else if self.voxels {
+            self.size.pow(3)
+        } else {
+            self.size * self.size
+        };
+        let pixels = ((cells as f64 * self.coverage).ceil() as usize)
+            .min(self.max_pixels.unwrap_or(usize::MAX));
+        if self.cont_spread >= 1.0 {
+            warnings.push(format!(
+                "--cont-spread {} is 1 or more, so each continued center jumps further than \
+                 its color is from its match and the orbits break up into noise",
+                self.cont_spread
+            ));
+        }
+        if self.num_lookback == 0 {
+            warnings.push(
+                "--lookback 0 leaves nothing to match, so every pixel starts a new center \
+                 at random"
+                    .to_string(),
+            );
+        } else if self.num_lookback > pixels {
+            warnings.push(format!(
+                "--lookback {} is more than the {pixels} pixels to place, so it never fills \
+                 and every color searches the whole image so far",
+                self.num_lookback
+            ));
+        }
+        if self.num_centers > pixels {
+            warnings.push(format!(
+                "--centers {} is more than the {pixels} pixels to place, so every pixel \
+                 starts its own center",
+                self.num_centers
+            ));
+        }
+        if (self.size as f64 * self.start_spread) < 1.0 {
+            warnings.push(format!(
+                "--start-spread {} is under one pixel at size {}, so new centers sit on \
+                 their first pixel and start with no orbit",
+                self.start_spread, self.size
+            ));
+        }
+        if let ColorSource::Sequence(colors) = &self.mode
+            && colors.len() < pixels
+        {
+            warnings.push(format!(
+                "--color-sequence holds {} colors, fewer than the {pixels} pixels to place, \
+                 so it starts over from the first",
+                colors.len()
+            ));
+        }
+        let colors = match &self.mode {
+            ColorSource::Palette(colors) | ColorSource::Colormap(colors) => {
+                colors.iter().collect::<HashSet<_>>().len()
+            }
+            ColorSource::Inks(inks) => inks.colors.iter().collect::<HashSet<_>>().len(),
+            ColorSource::Sequence(colors) => colors.iter().collect::<HashSet<_>>().len(),
+            ColorSource::Duotone(a, b) => 1 + (a != b) as usize,
+            _ => 2,
+        };
+        if colors == 1 {
+            warnings.push(
+                "the colors are all the same, so every match is a tie and the image is \
+                 one flat color"
+                    .to_string(),
+            );
+        }
+        warnings
+    }
+
+    pub fn needs_placements(&self) -> bool {
+        (self.blend_seeds.is_some() && self.blend == Blend::Oldest)
+            || self.svg.is_some()
+            || self.gcode.is_some()
+            || self.hpgl.is_some()
+            || self.stereo.is_some()
+            || self.depth_map.is_some()
+            || self.export.is_some()
+            || self.center_stats.is_some()
+            || self.center_labels.is_some()
+            || self.smear.is_some()
+            || self.custom_raster()
+    }
+
+    pub fn finish(&self) -> Finish {
+        Finish {
+            fade: self.fade,
+            vignette: self.vignette,
+            to: self.fade_to.unwrap_or(self.unfilled),
+        }
+    }
+
+    pub fn raster_settings(&self) -> Raster {
+        Raster {
+            crop: self.crop,
+            scale: self.scale,
+            kernel: self.kernel,
+            stroke: self.stroke,
+            subpixel: self.subpixel || self.algorithm == Algorithm::Continuous,
+            accumulate: self.accumulate,
+            background: self.unfilled.color(),
+        }
+    }
+
+    // The raster output is redrawn from the placements rather than taken
+    // from the grid. The continuous algorithm's positions lie in their own
+    // cells, so one output pixel per cell draws them as the grid has them.
+    pub fn custom_raster(&self) -> bool {
+        Raster {
+            subpixel: self.subpixel,
+            ..self.raster_settings()
+        }
+        .is_custom()
+    }
+
+    pub fn metadata(&self) -> Vec<(String, String)> {
+        let source = match &self.seed_source {
+            SeedSource::Default | SeedSource::Number => "number".to_string(),
+            SeedSource::Clock => "clock".to_string(),
+            SeedSource::Text(text) => format!("text: {text}"),
+            SeedSource::File(path) => format!("file: {path}"),
+        };
+        let mut metadata = vec![
+            ("Seed".to_string(), self.seed.to_string()),
+            ("Seed source".to_string(), source),
+        ];
+        if let Some(space) = self.color_space {
+            metadata.push((COLOR_SPACE_KEY.to_string(), space.name().to_string()));
+        }
+        metadata
+    }
+
+    // Checks parameters however they were made: each value against the
+    // range its flag has in the schema, then flags that need or rule out
+    // others. from_args calls it, so only parameters set field by field
+    // need it.
+    pub fn validate(&self) -> Result<(), String> {
+        schema::check_ranges(self)?;
+        if self.raw_every.is_some() && !self.raw_stdout {
+            return Err("--raw-every needs --raw-stdout".to_string());
+        }
+        if self.stereo.is_some()
+            && (self.unfilled == Unfilled::Transparent || !self.layers.is_empty())
+        {
+            return Err("--stereo cannot be combined with --transparent or --layer".to_string());
+        }
+        if let Some(crop) = self.crop {
+            crop.check(self.size)?;
+        }
+        if self.custom_raster()
+            && (self.stereo.is_some()
+                || self.unfilled == Unfilled::Transparent
+                || !self.layers.is_empty())
+        {
+            return Err(
+                "--crop, --scale, --kernel, --stroke, --subpixel and --accumulate \
+                 cannot be combined with --stereo, --transparent or --layer"
+                    .to_string(),
+            );
+        }
+        if self.soft_proof.is_some() && self.cmyk.is_none() {
+            return Err("--soft-proof needs --cmyk".to_string());
+        }
+        let flat = !(self.voxels || self.big.is_some());
+        let plane = flat && !self.sphere && self.cubemap.is_none();
+        for &(flag, set) in SPINNING_ONLY {
+            if set(self) && self.algorithm != Algorithm::Spinning {
+                return Err(format!("{flag} needs --algorithm spinning"));
+            }
+        }
+        for &(flag, set) in FLAT_ONLY {
+            if set(self) && !flat {
+                return Err(format!("{flag} cannot be combined with --voxels or --big"));
+            }
+        }
+        for &(flag, set) in PLANE_ONLY {
+            if set(self) && !plane {
+                return Err(format!(
+                    "{flag} cannot be combined with --voxels, --big, --sphere or --cubemap"
+                ));
+            }
+        }
+        match self.blend_seeds {
+            None if self.blend != Blend::Mean || !self.blend_weights.is_empty() => {
+                return Err("--blend and --blend-weights need --blend-seeds".to_string());
+            }
+            None => {}
+            Some(seeds) => {
+                if self.voxels || self.big.is_some() || self.stereo.is_some() {
+                    return Err(
+                        "--blend-seeds cannot be combined with --voxels, --big or --stereo"
+                            .to_string(),
+                    );
+                }
+                let weights = &self.blend_weights;
+                if !weights.is_empty() && weights.len() != seeds {
+                    return Err(format!(
+                        "--blend-weights needs {seeds} weights, got {}",
+                        weights.len()
+                    ));
+                }
+                if weights.iter().any(|&w| !(w >= 0.0 && w.is_finite()))
+                    || (!weights.is_empty() && weights.iter().all(|&w| w == 0.0))
+                {
+                    return Err("--blend-weights must be nonnegative and not all 0".to_string());
+                }
+            }
+        }
+        if self.channel_seeds.is_some()
+            && (self.voxels
+                || self.big.is_some()
+                || self.stereo.is_some()
+                || self.blend_seeds.is_some()
+                || self.custom_raster())
+        {
+            return Err(
+                "--channel-seeds cannot be combined with --voxels, --big, --stereo, \
+                 --blend-seeds or the raster settings"
+                    .to_string(),
+            );
+        }
+        if self.warm_samples.is_some() && self.warm_start.is_none() {
+            return Err("--warm-samples needs --warm-start".to_string());
+        }
+        if self.warm_start.is_some() && self.inpaint {
+            return Err("--warm-start cannot be combined with --inpaint".to_string());
+        }
+        if self.author.is_some() && self.sign.is_none() {
+            return Err("--author needs --sign".to_string());
+        }
+        if self.sign.is_some() && self.max_colors.is_some() {
+            return Err("--sign cannot be combined with --max-colors".to_string());
+        }
+        if self.canvas.wraps()
+            && (self.voxels
+                || self.big.is_some()
+                || self.base.is_some()
+                || self.stereo.is_some()
+                || self.custom_raster()
+                || self.arms.is_some()
+                || self.skip_dashes.is_some()
+                || self.smear.is_some()
+                || !self.layers.is_empty())
+        {
+            return Err(
+                "--panorama cannot be combined with --voxels, --big, --base, --inpaint, \
+                 --stereo, --arms, --skip-dashes, --smear, --layer or the raster settings"
+                    .to_string(),
+            );
+        }
+        if self.smear.is_some() {
+            if self.voxels || self.big.is_some() || self.stereo.is_some() || self.custom_raster() {
+                return Err(
+                    "--smear cannot be combined with --voxels, --big, --stereo, --crop, --scale, \
+                     --kernel, --stroke, --subpixel or --accumulate"
+                        .to_string(),
+                );
+            }
+        } else if self.smear_strength != 1.0 {
+            return Err("--smear-strength needs --smear".to_string());
+        }
+        if self.refine.is_some() {
+            if self.coverage < 1.0 || self.max_pixels.is_some() {
+                return Err(
+                    "--refine needs a full run, without --coverage or --max-pixels".to_string(),
+                );
+            }
+            if self.sampling != Sampling::Uniform {
+                return Err("--refine cannot be combined with --sampling or --density".to_string());
+            }
+            if self.save_scene.is_some() {
+                return Err("--refine cannot be combined with --save-scene".to_string());
+            }
+        }
+        if self.progressive.is_some() {
+            if self.voxels
+                || self.big.is_some()
+                || self.sphere
+                || self.cubemap.is_some()
+                || self.concurrent_walkers.is_some()
+            {
+                return Err(
+                    "--progressive cannot be combined with --voxels, --big, --sphere, \
+                     --cubemap or --concurrent-walkers"
+                        .to_string(),
+                );
+            }
+            if self.base.is_some() || self.crop.is_some() {
+                return Err("--progressive cannot be combined with --base or --crop".to_string());
+            }
+            if self.progressive_size == 0 {
+                return Err("--progressive-size must be above 0".to_string());
+            }
+        }
+        if self.seed_colors.is_some() {
+            if self.num_centers == 0 {
+                return Err("--seed-colors needs --centers above 0".to_string());
+            }
+            if self.warm_start.is_some() || self.inpaint {
+                return Err(
+                    "--seed-colors cannot be combined with --warm-start or --inpaint, \
+                     which start without centers"
+                        .to_string(),
+                );
+            }
+            if self.center_palettes.is_some() {
+                return Err("--seed-colors cannot be combined with --center-palettes".to_string());
+            }
+        }
+        if self.gallery && self.out_dir.is_none() {
+            return Err("--gallery needs --out-dir".to_string());
+        }
+        if self.concurrent_walkers.is_some()
+            && let Some(flag) = self
+                .args
+                .iter()
+                .find(|a| a.starts_with("--") && !CONCURRENT_FLAGS.contains(&a.as_str()))
+        {
+            return Err(format!(
+                "--concurrent-walkers only takes the core options, not {flag}"
+            ));
+        }
+        if let Some(walkers) = self.concurrent_walkers
+            && walkers > self.size * self.size
+        {
+            return Err(format!(
+                "--concurrent-walkers {walkers} is more than the {} cells",
+                self.size * self.size
+            ));
+        }
+        if self.algorithm != Algorithm::Spinning && (self.voxels || self.big.is_some()) {
+            return Err("--voxels and --big only use the spinning algorithm".to_string());
+        }
+        if self.center_layout != CenterLayout::Random
+            && (self.stencil.is_some() || self.voxels || self.big.is_some())
+        {
+            return Err(
+                "--center-layout relaxed cannot be combined with --stencil, --voxels or --big"
+                    .to_string(),
+            );
+        }
+        if self.cubemap.is_some() || self.sphere {
+            if self.slices.is_some() || self.nrrd.is_some() {
+                return Err(
+                    "--cubemap and --sphere cannot be combined with --slices or --nrrd".to_string(),
+                );
+            }
+            if self.cubemap.is_some() && self.sphere {
+                return Err("--cubemap and --sphere are exclusive".to_string());
+            }
+        } else if self.voxels && self.slices.is_none() && self.nrrd.is_none() {
+            return Err("--voxels needs --slices or --nrrd".to_string());
+        }
+        if self.unfilled == Unfilled::Transparent && self.max_colors.is_some() {
+            return Err("--transparent cannot be combined with --max-colors".to_string());
+        }
+        let finish = self.finish();
+        if finish.is_none() {
+            if self.fade_to.is_some() {
+                return Err("--fade-to needs --fade or --vignette".to_string());
+            }
+        } else if finish.to == Unfilled::Transparent && self.max_colors.is_some() {
+            return Err("fading to transparent cannot be combined with --max-colors".to_string());
+        }
+        Ok(())
+    }
+
+    // Scales a run down to at most FAST_SIZE across, keeping its look: counts
+    // of pixels and centers shrink with the area, lengths in pixels with the
+    // side.
+    // Plain voxel runs go down to FAST_VOXELS, their counts with the volume.
+    fn scale_to_fast(&mut self) {
+        let volume = self.voxels && self.cubemap.is_none() && !self.sphere;
+        let target = if volume { FAST_VOXELS } else { FAST_SIZE };
+        self.scale_down(target, volume);
+    }
+
+    // The run scaled down to progressive_size across, as scale_to_fast
+    // scales it but keeping its centers, with none of its outputs along the
+    // way.
+    pub fn preview(&self) -> Params {
+        let mut preview = Params {
+            progressive: None,
+            save_scene: None,
+            checkpoints: vec![],
+            milestones: vec![],
+            thumbnail: None,
+            ..self.clone()
+        };
+        preview.scale_down(self.progressive_size, false);
+        // Every center stays, so the regions fall where the run's will.
+        preview.num_centers = self.num_centers.min(preview.size * preview.size);
+        preview
+    }
+
+    fn scale_down(&mut self, target: usize, volume: bool) {
+        if self.size <= target {
+            return;
+        }
+        let side = target as f64 / self.size as f64;
+        let area = if volume { side.powi(3) } else { side * side };
+        let length = |n: usize| ((n as f64 * side).round() as usize).max(1);
+        let count = |n: usize| ((n as f64 * area).round() as usize).max(1);
+        self.size = target;
+        if let Canvas::Band(height) = self.canvas {
+            self.canvas = Canvas::Band(length(height));
+        }
+        self.num_lookback = count(self.num_lookback);
+        if self.num_centers > 0 {
+            self.num_centers = count(self.num_centers);
+        }
+        self.cont_spread *= side;
+        self.center_gap = self.center_gap.map(|gap| gap * side);
+        if self.gap_jump > 0 {
+            self.gap_jump = length(self.gap_jump);
+        }
+        self.center_max_pixels = self.center_max_pixels.map(count);
+        self.center_max_age = self.center_max_age.map(count);
+        self.ring_width = self.ring_width.map(|w| Draw {
+            lo: w.lo * side,
+            hi: w.hi * side,
+        });
+        self.max_pixels = self.max_pixels.map(count);
+        self.warm_samples = self.warm_samples.map(count);
+        self.smear = self.smear.map(length);
+        if self.fade > 0 {
+            self.fade = length(self.fade);
+        }
+    }
+
+    // Parses args like from_args, but without a seed among them picks one
+    // from the clock, so casual runs differ. The seed joins the arguments,
+    // so it is recorded wherever they are.
+    pub fn from_args_with_clock_seed(args: Vec<String>) -> Result<Self, String> {
+        let params = Params::from_args(args.iter().cloned())?;
+        if params.seed_source != SeedSource::Default {
+            return Ok(params);
+        }
+        let seed = clock_seed();
+        let mut with_seed = args;
+        with_seed.extend(["--seed".to_string(), seed.to_string()]);
+        Ok(Params {
+            seed_source: SeedSource::Clock,
+            ..Params::from_args(with_seed.into_iter())?
+        })
+    }
+
+    pub fn from_args<I: Iterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut params = Params {
+            args: args.collect(),
+            ..Params::default()
+        };
+        let mut args = params.args.clone().into_iter();
+        let mut grid: Option<(usize, usize)> = None;
+        let mut region_cont_spread: Vec<f64> = vec![];
+        let mut region_lookback: Vec<usize> = vec![];
+        let mut region_aspect: Vec<f64> = vec![];
+        let mut param_map: Option<String> = None;
+        let mut per_center = false;
+        let mut panorama = None;
+        let mut center_palette_size = 5;
+        let mut palette_file = None;
+        let mut center_tilt = None;
+        let mut palette_jitter = 0;
+        let mut milestones_given = false;
+        let mut curve_step: Option<u64> = None;
+        let mut curve_offset: Option<f64> = None;
+        let mut base_hue: Option<f64> = None;
+        let mut base = None;
+        let mut base_threshold = 128;
+        let mut key = None;
+        let mut trace_colors_given = false;
+        let mut palette_name = None;
+        let mut refine_block_given = false;
+        let mut progressive_size_given = false;
+        let mut colorblind_safe = false;
+        while let Some(flag) = args.next() {
+            let mut value = || args.next().ok_or(format!("missing value for {flag}"));
+            match flag.as_str() {
+                "--size" => params.size = parse(&flag, &value()?)?,
+                "--fast-profile" => params.fast_profile = true,
+                "--progressive" => params.progressive = Some(value()?),
+                "--progressive-size" => {
+                    params.progressive_size = parse(&flag, &value()?)?;
+                    progressive_size_given = true;
+                }
+                "--centers" => params.num_centers = parse(&flag, &value()?)?,
+                "--lookback" => params.num_lookback = parse(&flag, &value()?)?,
+                "--start-spread" => params.start_spread = parse(&flag, &value()?)?,
+                "--cont-spread" => params.cont_spread = parse(&flag, &value()?)?,
+                "--seed" => {
+                    params.seed = parse(&flag, &value()?)?;
+                    params.seed_source = SeedSource::Number;
+                }
+                "--seed-text" => {
+                    let text = value()?;
+                    params.seed = seed_from_bytes(text.as_bytes());
+                    params.seed_source = SeedSource::Text(text);
+                }
+                "--seed-file" => {
+                    let path = value()?;
+                    let bytes =
+                        std::fs::read(&path).map_err(|e| format!("could not read {path}: {e}"))?;
+                    params.seed = seed_from_bytes(&bytes);
+                    params.seed_source = SeedSource::File(path);
+                }
+                "--algorithm" => params.algorithm = value()?.parse()?,
+                "--orbit" => params.orbit = value()?.parse()?,
+                "--phase" => params.phase = Some(value()?.parse()?),
+                "--spiral" => params.spiral = parse_spiral(&value()?)?,
+                "--arms" => params.arms = Some(value()?.parse()?),
+                "--skip-prob" => params.skip_prob = parse(&flag, &value()?)?,
+                "--skip-dashes" => params.skip_dashes = Some(value()?.parse()?),
+                "--neighborhood" => params.neighborhood = value()?.parse()?,
+                "--gap-jump" => params.gap_jump = parse(&flag, &value()?)?,
+                "--reflect" => params.reflect = true,
+                "--canvas" => params.canvas = value()?.parse()?,
+                "--panorama" => {
+                    let v = value()?;
+                    let (width, height) = v
+                        .split_once('x')
+                        .ok_or(format!("--panorama expects WIDTHxHEIGHT, got {v}"))?;
+                    panorama = Some((parse(&flag, width)?, parse(&flag, height)?));
+                }
+                "--transparent" => params.unfilled = Unfilled::Transparent,
+                "--max-pixels" => params.max_pixels = Some(parse(&flag, &value()?)?),
+                "--max-seconds" => params.max_seconds = Some(parse(&flag, &value()?)?),
+                "--save-scene" => params.save_scene = Some(value()?),
+                "--center-max-pixels" => params.center_max_pixels = Some(parse(&flag, &value()?)?),
+                "--center-max-age" => params.center_max_age = Some(parse(&flag, &value()?)?),
+                "--center-stretch" => {
+                    let stretch: Draw = value()?.parse()?;
+                    if stretch.lo <= 0.0 {
+                        return Err(format!(
+                            "--center-stretch must be above 0, got {}",
+                            stretch.lo
+                        ));
+                    }
+                    params.center_stretch = Some(stretch);
+                }
+                "--center-tilt" => center_tilt = Some(value()?.parse()?),
+                "--ring-width" => {
+                    let width: Draw = value()?.parse()?;
+                    if width.lo <= 0.0 {
+                        return Err(format!("--ring-width must be above 0, got {}", width.lo));
+                    }
+                    params.ring_width = Some(width);
+                }
+                "--center-gap" => params.center_gap = Some(parse(&flag, &value()?)?),
+                "--center-drift" => params.center_drift = Some(value()?.parse()?),
+                "--merge-centers" => params.merge_centers = Some(parse(&flag, &value()?)?),
+                "--gravity" => params.gravity = parse(&flag, &value()?)?,
+                "--modulate" => params.modulations.push(value()?.parse()?),
+                "--stencil" => params.stencil = Some(Arc::new(value()?.parse()?)),
+                "--center-layout" => params.center_layout = value()?.parse()?,
+                "--relax-iters" => params.relax_iters = parse(&flag, &value()?)?,
+                "--big" => params.big = Some(value()?),
+                "--concurrent-walkers" => {
+                    params.concurrent_walkers = Some(parse(&flag, &value()?)?)
+                }
+                "--raw-stdout" => {
+                    params.raw_stdout = true;
+                    params.raster = false;
+                }
+                "--raw-every" => params.raw_every = Some(parse(&flag, &value()?)?),
+                "--log-format" => params.log_format = Some(value()?.parse()?),
+                "--unfilled" => params.unfilled = value()?.parse()?,
+                "--coverage" => params.coverage = parse(&flag, &value()?)?,
+                "--sampling" => params.sampling = value()?.parse()?,
+                "--base" => base = Some(value()?),
+                "--inpaint" => {
+                    base = Some(value()?);
+                    params.inpaint = true;
+                }
+                "--key" => key = Some(parse_color(&value()?)?),
+                "--base-threshold" => base_threshold = parse(&flag, &value()?)?,
+                "--multiscale" => params.multiscale = parse(&flag, &value()?)?,
+                "--layer" => params.layers.push(value()?.parse()?),
+                "--density" => params.density = Some(Arc::new(DensityMap::load(&value()?)?)),
+                "--warm-start" => params.warm_start = Some(Arc::new(WarmStart::load(&value()?)?)),
+                "--warm-samples" => params.warm_samples = Some(parse(&flag, &value()?)?),
+                "--voxels" => params.voxels = true,
+                "--cubemap" => params.cubemap = Some(value()?.parse()?),
+                "--sphere" => params.sphere = true,
+                "--slices" => params.slices = Some(value()?),
+                "--nrrd" => params.nrrd = Some(value()?),
+                "--eviction" => params.eviction = value()?.parse()?,
+                "--lookback-pools" => params.lookback_pools = Some(value()?.parse()?),
+                "--fallback" => params.fallback = value()?.parse()?,
+                "--refine" => params.refine = Some(parse(&flag, &value()?)?),
+                "--refine-block" => {
+                    params.refine_block = parse(&flag, &value()?)?;
+                    refine_block_given = true;
+                }
+                "--endgame" => params.endgame = Some(parse(&flag, &value()?)?),
+                "--adaptive-lookback" => params.adaptive_lookback = Some(value()?.parse()?),
+                "--spatial-weight" => params.spatial_weight = parse(&flag, &value()?)?,
+                "--max-match-distance" => {
+                    params.max_match_distance = Some(parse(&flag, &value()?)?)
+                }
+                "--rng" => params.rng = value()?.parse()?,
+                "--streams" => params.streams = true,
+                "--mode" => {
+                    params.mode = match value()?.as_str() {
+                        "rgb" => ColorSource::Uniform,
+                        "grayscale" => ColorSource::Grayscale,
+                        "duotone" => {
+                            let a = parse_color(&value()?)?;
+                            ColorSource::Duotone(a, parse_color(&value()?)?)
+                        }
+                        mode => return Err(format!("unknown mode {mode}")),
+                    }
+                }
+                "--center-palettes" => per_center = true,
+                "--colorblind-safe" => colorblind_safe = true,
+                "--center-palette-size" => center_palette_size = parse(&flag, &value()?)?,
+                "--palette-file" => palette_file = Some(load_palettes(&value()?)?),
+                "--palette" => {
+                    let name = value()?;
+                    palette_name = Some(name.clone());
+                    params.mode = match palette::named(&name) {
+                        Some(Named::Colormap(stops)) => ColorSource::Colormap(stops.to_vec()),
+                        Some(Named::Scheme(colors)) => ColorSource::Palette(colors.to_vec()),
+                        None if name.contains(',') => ColorSource::Palette(
+                            name.split(',')
+                                .map(|c| parse_color(c.trim()))
+                                .collect::<Result<_, _>>()?,
+                        ),
+                        // A single color, as fit gives for a target of one.
+                        None => match parse_color(name.trim()) {
+                            Ok(color) => ColorSource::Palette(vec![color]),
+                            Err(_) => {
+                                return Err(format!(
+                                    "unknown palette {name}, expected one of {} or rrggbb,...",
+                                    palette::NAMES.join(", ")
+                                ));
+                            }
+                        },
+                    }
+                }
+                "--seed-colors" => {
+                    let spec = value()?;
+                    params.seed_colors = Some(match palette::named(&spec) {
+                        _ if spec == "spread" => SeedColors::Spread,
+                        Some(Named::Colormap(colors) | Named::Scheme(colors)) => {
+                            SeedColors::Given(colors.to_vec())
+                        }
+                        None => SeedColors::Given(
+                            spec.split(',')
+                                .map(|c| parse_color(c.trim()))
+                                .collect::<Result<_, _>>()?,
+                        ),
+                    });
+                }
+                "--time-gradient" => {
+                    let spec = value()?;
+                    let stops = match palette::named(&spec) {
+                        Some(Named::Colormap(stops) | Named::Scheme(stops)) => stops.to_vec(),
+                        None => spec
+                            .split(',')
+                            .map(|c| parse_color(c.trim()))
+                            .collect::<Result<_, _>>()?,
+                    };
+                    params.mode = ColorSource::Timed(stops);
+                }
+                "--palette-jitter" => palette_jitter = parse(&flag, &value()?)?,
+                "--color-jitter" => params.color_jitter = parse(&flag, &value()?)?,
+                "--noise-colors" => {
+                    let scale: f64 = parse(&flag, &value()?)?;
+                    if !(scale > 0.0 && scale.is_finite()) {
+                        return Err(format!("--noise-colors must be above 0, got {scale}"));
+                    }
+                    params.mode = ColorSource::Noise(NoiseField { scale, seed: 0 });
+                }
+                "--harmony" => {
+                    params.mode = ColorSource::Harmony(Harmony {
+                        rule: value()?.parse()?,
+                        base_hue: 0.0,
+                    })
+                }
+                "--base-hue" => base_hue = Some(parse(&flag, &value()?)?),
+                "--inks" => params.mode = ColorSource::Inks(value()?.parse()?),
+                "--color-sequence" => {
+                    params.mode = ColorSource::Sequence(Arc::new(load_sequence(&value()?)?))
+                }
+                "--curve" => params.mode = ColorSource::Curve(value()?.parse()?),
+                "--curve-step" => {
+                    let step = parse(&flag, &value()?)?;
+                    if step == 0 {
+                        return Err("--curve-step must be at least 1".to_string());
+                    }
+                    curve_step = Some(step);
+                }
+                "--curve-offset" => {
+                    let offset = parse(&flag, &value()?)?;
+                    if !(0.0..1.0).contains(&offset) {
+                        return Err(format!("--curve-offset must be in 0..1, got {offset}"));
+                    }
+                    curve_offset = Some(offset);
+                }
+                "--palette-drift" => params.palette_drift = parse(&flag, &value()?)?,
+                "--post" => params.post.extend(parse_list::<Step>(&flag, &value()?)?),
+                "--mosaic" => params.mosaic = Some(parse(&flag, &value()?)?),
+                "--smear" => params.smear = Some(parse(&flag, &value()?)?),
+                "--smear-strength" => params.smear_strength = parse(&flag, &value()?)?,
+                "--fade" => params.fade = parse(&flag, &value()?)?,
+                "--vignette" => params.vignette = parse(&flag, &value()?)?,
+                "--fade-to" => params.fade_to = Some(value()?.parse()?),
+                "--post-file" => params.post.extend(steps_from_file(&value()?)?),
+                "--max-colors" => params.max_colors = Some(parse(&flag, &value()?)?),
+                "--quantize" => params.quantize = value()?.parse()?,
+                "--out" => params.out = Some(value()?),
+                "--thumbnail" => params.thumbnail = Some(parse(&flag, &value()?)?),
+                "--out-dir" => params.out_dir = Some(value()?),
+                "--gallery" => params.gallery = true,
+                "--annotate" => params.annotate = true,
+                "--sign" => params.sign = Some(value()?),
+                "--author" => params.author = Some(value()?),
+                "--strict" => params.strict = true,
+                "--blend-seeds" => params.blend_seeds = Some(parse(&flag, &value()?)?),
+                "--blend" => params.blend = value()?.parse()?,
+                "--blend-weights" => params.blend_weights = parse_list(&flag, &value()?)?,
+                "--channel-seeds" => {
+                    let seeds: Vec<u64> = parse_list(&flag, &value()?)?;
+                    let n = seeds.len();
+                    params.channel_seeds = Some(
+                        seeds
+                            .try_into()
+                            .map_err(|_| format!("--channel-seeds needs three seeds, got {n}"))?,
+                    );
+                }
+                "--term-preview" => params.term_preview = Some(value()?.parse()?),
+                "--checkpoints" => {
+                    let mut checkpoints: Vec<f64> = parse_list(&flag, &value()?)?;
+                    if let Some(c) = checkpoints.iter().find(|c| !(0.0..1.0).contains(*c)) {
+                        return Err(format!("--checkpoints must be in 0..1, got {c}"));
+                    }
+                    checkpoints.sort_by(f64::total_cmp);
+                    params.checkpoints = checkpoints;
+                }
+                "--on-complete" => params.on_complete.push(value()?.parse()?),
+                "--run-db" => params.run_db = Some(value()?),
+                "--on-milestone" => params.on_milestone.push(value()?.parse()?),
+                "--milestones" => {
+                    let mut milestones: Vec<f64> = parse_list(&flag, &value()?)?;
+                    if let Some(m) = milestones.iter().find(|m| !(0.0..1.0).contains(*m)) {
+                        return Err(format!("--milestones must be in 0..1, got {m}"));
+                    }
+                    milestones.sort_by(f64::total_cmp);
+                    milestones_given = true;
+                    params.milestones = milestones;
+                }
+                "--stereo" => params.stereo = Some(value()?.parse()?),
+                "--depth" => params.depth = value()?.parse()?,
+                "--export" => params.export = Some(value()?),
+                "--depth-map" => params.depth_map = Some(value()?),
+                "--walk-map" => params.walk_map = Some(value()?),
+                "--cmyk" => params.cmyk = Some(value()?),
+                "--print-profile" => params.print_profile = value()?.parse()?,
+                "--soft-proof" => params.soft_proof = Some(value()?),
+                "--color-space" => params.color_space = Some(value()?.parse()?),
+                "--crop" => params.crop = Some(value()?.parse()?),
+                "--scale" => params.scale = parse(&flag, &value()?)?,
+                "--kernel" => params.kernel = value()?.parse()?,
+                "--stroke" => params.stroke = parse(&flag, &value()?)?,
+                "--subpixel" => params.subpixel = true,
+                "--accumulate" => params.accumulate = true,
+                "--parallax" => params.parallax = parse(&flag, &value()?)?,
+                "--svg" => params.svg = Some(value()?),
+                "--trace" => params.trace = Some(value()?),
+                "--trace-colors" => {
+                    params.trace_colors = parse(&flag, &value()?)?;
+                    trace_colors_given = true;
+                }
+                "--center-stats" => params.center_stats = Some(value()?),
+                "--center-labels" => params.center_labels = Some(value()?),
+                "--simulate-cvd" => params.simulate_cvd = parse_deficiencies(&value()?)?,
+                "--no-raster" => params.raster = false,
+                "--gcode" => params.gcode = Some(value()?),
+                "--hpgl" => params.hpgl = Some(value()?),
+                "--pen-levels" => params.pen_levels = parse(&flag, &value()?)?,
+                "--plot-width" => params.plot_width_mm = parse(&flag, &value()?)?,
+                "--region-grid" => {
+                    let v = value()?;
+                    let (cols, rows) = v
+                        .split_once('x')
+                        .ok_or(format!("--region-grid expects COLSxROWS, got {v}"))?;
+                    grid = Some((parse(&flag, cols)?, parse(&flag, rows)?));
+                }
+                "--region-cont-spread" => region_cont_spread = parse_list(&flag, &value()?)?,
+                "--region-lookback" => region_lookback = parse_list(&flag, &value()?)?,
+                "--region-aspect" => region_aspect = parse_list(&flag, &value()?)?,
+                "--param-map" => param_map = Some(value()?),
+                _ => return Err(format!("unknown flag {flag}")),
+            }
+        }
+        if let Some(Phase::Epicycle(turns)) = params.phase {
+            match &mut params.orbit {
+                OrbitShape::Epicycle { phase, .. } => *phase = turns,
+                OrbitShape::Circle => {
+                    return Err("--phase epicycle needs --orbit epicycle".to_string());
+                }
+            }
+        }
+        if params.cubemap.is_some() || params.sphere {
+            params.voxels = true;
+        }
+        if let Some((width, height)) = panorama {
+            if height == 0 || height > width {
+                return Err(format!(
+                    "--panorama needs a height from 1 up to the width, got {width}x{height}"
+                ));
+            }
+            if params.canvas != Canvas::Square {
+                return Err("--panorama cannot be combined with --canvas".to_string());
+            }
+            params.size = width;
+            params.canvas = Canvas::Band(height);
+        }
+        if params.fast_profile {
+            if base.is_some() || params.crop.is_some() {
+                return Err("--fast-profile cannot be combined with --base or --crop".to_string());
+            }
+            params.scale_to_fast();
+        }
+        if progressive_size_given && params.progressive.is_none() {
+            return Err("--progressive-size needs --progressive".to_string());
+        }
+        if refine_block_given && params.refine.is_none() {
+            return Err("--refine-block needs --refine".to_string());
+        }
+        if trace_colors_given && params.trace.is_none() {
+            return Err("--trace-colors needs --trace".to_string());
+        }
+        if let Some(tilt) = center_tilt {
+            if params.center_stretch.is_none() {
+                return Err("--center-tilt needs --center-stretch".to_string());
+            }
+            params.center_tilt = tilt;
+        }
+        if !params.on_milestone.is_empty() {
+            if params.voxels || params.big.is_some() {
+                return Err("--on-milestone cannot be combined with --voxels or --big".to_string());
+            }
+            if !milestones_given {
+                params.milestones = vec![0.25, 0.5, 0.75];
+            }
+        } else if milestones_given {
+            return Err("--milestones needs --on-milestone".to_string());
+        }
+        if let Some(dir) = &params.out_dir {
+            for path in [
+                &mut params.svg,
+                &mut params.trace,
+                &mut params.center_stats,
+                &mut params.center_labels,
+                &mut params.progressive,
+                &mut params.depth_map,
+                &mut params.export,
+                &mut params.cmyk,
+                &mut params.soft_proof,
+                &mut params.gcode,
+                &mut params.hpgl,
+                &mut params.slices,
+                &mut params.nrrd,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                *path = in_dir(dir, path);
+            }
+        }
+        if let Some(path) = base {
+            let mut base = BaseImage::load(&path, base_threshold)?;
+            base.key = key;
+            params.size = base.size();
+            params.base = Some(Arc::new(base));
+        }
+        if let Some(density) = &params.density
+            && params.sampling == Sampling::Uniform
+        {
+            params.sampling = Sampling::Map(density.clone());
+        }
+        if colorblind_safe {
+            if let Some(flag) = params
+                .args
+                .iter()
+                .find(|a| COLOR_SOURCE_FLAGS.contains(&a.as_str()))
+            {
+                return Err(format!(
+                    "--colorblind-safe picks the colors itself and cannot be combined with {flag}"
+                ));
+            }
+            if matches!(params.seed_colors, Some(SeedColors::Given(_))) {
+                return Err("--colorblind-safe can only spread the seed colors".to_string());
+            }
+            match palette_name.as_deref() {
+                None => params.mode = ColorSource::Palette(palette::OKABE_ITO.to_vec()),
+                Some(name) if palette::COLORBLIND_SAFE.contains(&name) => {}
+                Some(name) => {
+                    return Err(format!(
+                        "--colorblind-safe needs one of the palettes {}, not {name}",
+                        palette::COLORBLIND_SAFE.join(", ")
+                    ));
+                }
+            }
+        }
+        if matches!(params.mode, ColorSource::Inks(_)) && (per_center || palette_file.is_some()) {
+            return Err(
+                "--inks cannot be combined with --palette-file or --center-palettes".to_string(),
+            );
+        }
+        match (per_center, palette_file) {
+            (true, Some(palettes)) => {
+                params.center_palettes = Some(CenterPaletteSpec::Given(palettes))
+            }
+            (true, None) => {
+                if center_palette_size == 0 {
+                    return Err("--center-palette-size must be positive".to_string());
+                }
+                params.center_palettes = Some(CenterPaletteSpec::Random(center_palette_size))
+            }
+            (false, Some(palettes)) => params.mode = ColorSource::Palette(palettes.concat()),
+            (false, None) => {}
+        }
+        if curve_step.is_some() || curve_offset.is_some() {
+            let ColorSource::Curve(curve) = &mut params.mode else {
+                return Err("--curve-step and --curve-offset need --curve".to_string());
+            };
+            curve.step = curve_step.or(curve.step);
+            curve.offset = curve_offset.unwrap_or(curve.offset);
+        }
+        // The noise, ink sequence and any unset base hue follow the seed,
+        // wherever it was given.
+        match &mut params.mode {
+            ColorSource::Harmony(harmony) => {
+                harmony.base_hue = base_hue.unwrap_or((splitmix64(params.seed) % 360) as f64)
+            }
+            _ if base_hue.is_some() => return Err("--base-hue needs --harmony".to_string()),
+            ColorSource::Noise(noise) => noise.seed = params.seed,
+            ColorSource::Inks(inks) => {
+                inks.offset = (splitmix64(params.seed) >> 11) as f64 / (1u64 << 53) as f64
+            }
+            _ => {}
+        }
+        if palette_jitter > 0 {
+            params.mode = ColorSource::Jittered(Box::new(params.mode), palette_jitter);
+        }
+        params.regions = match (param_map, grid) {
+            (Some(_), Some(_)) => {
+                return Err("--param-map and --region-grid are exclusive".to_string());
+            }
+            (Some(path), None) => Some(RegionMap::from_image(&path, &params)?),
+            (None, Some((cols, rows))) => Some(RegionMap::from_lists(
+                cols,
+                rows,
+                &region_cont_spread,
+                &region_lookback,
+                &region_aspect,
+                &params,
+            )?),
+            (None, None) => None,
+        };
+        params.validate()?;
+        let warnings = params.warnings();
+        if params.strict && !warnings.is_empty() {
+            return Err(format!(
+                "refusing to render with --strict: {}",
+                warnings.join("; ")
+            ));
+        }
+        Ok(params)
+    }
+}
+
+// Short enough to type back in; nanoseconds and the process id keep runs
+// started together apart.
+fn clock_seed() -> u64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64);
+    splitmix64(nanos ^ ((std::process::id() as u64) << 32)) % 1_000_000_000
+}
+
+pub fn parse<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {flag}: {value}"))
+}
+
+pub fn parse_list<T: std::str::FromStr>(flag: &str, value: &str) -> Result<Vec<T>, String> {
+    value.split(',').map(|v| parse(flag, v.trim())).collect()
+}
+
+fn in_dir(dir: &str, path: &str) -> String {
+    std::path::Path::new(dir)
+        .join(path)
+        .to_string_lossy()
+        .into_owned()
+}
@@ -0,0 +1,66 @@
+use std::str::FromStr;
+
+#[cfg(feature = "files")]
+use crate::output::save_png;
+#[cfg(feature = "files")]
+use crate::{Params, generate, post};
+
+// A cyclic parameter, in turns, for animations that loop: a whole turn gives
+// the same image as none. Written as `hue:TURNS` or `epicycle:TURNS`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    // The image's colors rotated about the gray axis.
+    Hue(f64),
+    // The starting angle of the epicycle orbit's secondary circle.
+    Epicycle(f64),
+}
+
+impl FromStr for Phase {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        let (target, turns) = s
+            .split_once(':')
+            .ok_or_else(|| format!("expected hue:TURNS or epicycle:TURNS, got {s}"))?;
+        let turns: f64 = turns
+            .parse()
+            .map_err(|_| format!("invalid phase {turns}"))?;
+        match target {
+            "hue" => Ok(Phase::Hue(0.0).at(turns)),
+            "epicycle" => Ok(Phase::Epicycle(0.0).at(turns)),
+            _ => Err(format!("unknown phase {target}, expected hue or epicycle")),
+        }
+    }
+}
+
+impl Phase {
+    // The same target at another phase, wrapped into 0..1 so that whole turns
+    // are exactly the starting phase.
+    pub fn at(self, turns: f64) -> Self {
+        let turns = turns.rem_euclid(1.0);
+        match self {
+            Phase::Hue(_) => Phase::Hue(turns),
+            Phase::Epicycle(_) => Phase::Epicycle(turns),
+        }
+    }
+}
+
+// Renders frames images of one run with the phase of target stepping through
+// a turn, as frame-0000.png and on in dir. The seed and everything else stay
+// fixed, and the frame after the last would be the first again, so the
+// frames loop seamlessly.
+#[cfg(feature = "files")]
+pub fn render_loop(args: &[String], target: &str, frames: usize, dir: &str) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("could not create {dir}: {e}"))?;
+    for frame in 0..frames {
+        let turns = frame as f64 / frames as f64;
+        let phase = ["--phase".to_string(), format!("{target}:{turns}")];
+        let params = Params::from_args(args.iter().cloned().chain(phase))?;
+        let path = std::path::Path::new(dir).join(format!("frame-{frame:04}.png"));
+        let path = path.to_string_lossy();
+        println!("{path}");
+        let mut image = generate(&params, false).image;
+        post::apply(&mut image, &params.post);
+        save_png(&image, &path, &params.metadata())?;
+    }
+    Ok(())
+}
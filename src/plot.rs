@@ -0,0 +1,162 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+
+use crate::chains::{Polyline, polylines};
+use crate::{Color, Placement};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlotFormat {
+    Gcode,
+    Hpgl,
+}
+
+pub struct PlotSettings {
+    // Levels per channel, so there are pen_levels^3 possible pens.
+    pub pen_levels: u8,
+    pub width_mm: f64,
+}
+
+fn quantize(color: Color, levels: u8) -> Color {
+    let levels = levels.max(2) as u32;
+    color.map(|c| {
+        let level = (c as u32 * levels / 256).min(levels - 1);
+        (level * 255 / (levels - 1)) as u8
+    })
+}
+
+// Greedy nearest-neighbor ordering, reversing polylines when their far end
+// is closer, to keep pen-up travel short. Endpoints are bucketed into square
+// cells, searched in growing rings around the pen position.
+fn order(lines: Vec<Polyline>) -> Vec<Polyline> {
+    const CELL: usize = 16;
+    let mut cells: HashMap<[usize; 2], Vec<(usize, bool)>> = HashMap::new();
+    let mut max_cell = 0;
+    for (i, line) in lines.iter().enumerate() {
+        for (p, reverse) in [
+            (line.points[0], false),
+            (*line.points.last().expect("nonempty"), true),
+        ] {
+            let cell = [p[0] / CELL, p[1] / CELL];
+            max_cell = max_cell.max(cell[0]).max(cell[1]);
+            cells.entry(cell).or_default().push((i, reverse));
+        }
+    }
+    let mut lines: Vec<Option<Polyline>> = lines.into_iter().map(Some).collect();
+    let mut ordered = Vec::with_capacity(lines.len());
+    let mut pos = [0usize, 0];
+    while ordered.len() < lines.len() {
+        let dist = |p: [usize; 2]| p[0].abs_diff(pos[0]).pow(2) + p[1].abs_diff(pos[1]).pow(2);
+        let center = [pos[0] / CELL, pos[1] / CELL];
+        let mut best: Option<(usize, usize, bool)> = None;
+        for ring in 0..=max_cell {
+            // Anything in this ring or beyond is at least this far away.
+            let near = (ring.saturating_sub(1) * CELL).pow(2);
+            if best.is_some_and(|(d, _, _)| d < near) {
+                break;
+            }
+            for cx in center[0].saturating_sub(ring)..=center[0] + ring {
+                for cy in center[1].saturating_sub(ring)..=center[1] + ring {
+                    if cx.abs_diff(center[0]).max(cy.abs_diff(center[1])) != ring {
+                        continue;
+                    }
+                    let Some(entries) = cells.get_mut(&[cx, cy]) else {
+                        continue;
+                    };
+                    entries.retain(|&(i, _)| lines[i].is_some());
+                    for &(i, reverse) in entries.iter() {
+                        let line = lines[i].as_ref().expect("retained");
+                        let p = if reverse {
+                            *line.points.last().expect("nonempty")
+                        } else {
+                            line.points[0]
+                        };
+                        let d = dist(p);
+                        if best.is_none_or(|(bd, bi, _)| (d, i) < (bd, bi)) {
+                            best = Some((d, i, reverse));
+                        }
+                    }
+                }
+            }
+        }
+        let (_, index, reverse) = best.expect("lines remain");
+        let mut line = lines[index].take().expect("unused");
+        if reverse {
+            line.points.reverse();
+        }
+        pos = *line.points.last().expect("nonempty");
+        ordered.push(line);
+    }
+    ordered
+}
+
+// Tool paths grouped by quantized color, one pen per group. Coordinates are
+// flipped so the image's top-left maps to the plot's top-left with y up.
+pub fn write_plot(
+    placements: &[Placement],
+    width: usize,
+    height: usize,
+    format: PlotFormat,
+    settings: &PlotSettings,
+    path: &str,
+) -> Result<(), String> {
+    let mut groups: BTreeMap<Color, Vec<Polyline>> = BTreeMap::new();
+    for line in polylines(placements) {
+        groups
+            .entry(quantize(line.color, settings.pen_levels))
+            .or_default()
+            .push(line);
+    }
+    let mm_per_pixel = settings.width_mm / width as f64;
+    let to_mm = |p: [usize; 2]| {
+        [
+            (p[0] as f64 + 0.5) * mm_per_pixel,
+            (height as f64 - p[1] as f64 - 0.5) * mm_per_pixel,
+        ]
+    };
+    let mut out = String::new();
+    match format {
+        PlotFormat::Gcode => out.push_str("G21\nG90\nG0 Z5\n"),
+        PlotFormat::Hpgl => out.push_str("IN;\n"),
+    }
+    for (pen, (color, lines)) in groups.into_iter().enumerate() {
+        let [r, g, b] = color;
+        match format {
+            PlotFormat::Gcode => {
+                writeln!(out, "; pen {} color #{r:02x}{g:02x}{b:02x}", pen + 1).unwrap();
+                out.push_str("M0\n");
+            }
+            PlotFormat::Hpgl => writeln!(out, "SP{};", pen + 1).unwrap(),
+        }
+        for line in order(lines) {
+            let points: Vec<[f64; 2]> = line.points.iter().map(|&p| to_mm(p)).collect();
+            match format {
+                PlotFormat::Gcode => {
+                    writeln!(out, "G0 X{:.2} Y{:.2}", points[0][0], points[0][1]).unwrap();
+                    out.push_str("G1 Z0 F1000\n");
+                    for p in &points[1..] {
+                        writeln!(out, "G1 X{:.2} Y{:.2} F3000", p[0], p[1]).unwrap();
+                    }
+                    out.push_str("G0 Z5\n");
+                }
+                PlotFormat::Hpgl => {
+                    // 40 plotter units per millimeter.
+                    let unit = |v: f64| (v * 40.0).round() as i64;
+                    writeln!(out, "PU{},{};", unit(points[0][0]), unit(points[0][1])).unwrap();
+                    out.push_str("PD");
+                    for (i, p) in points[1..].iter().enumerate() {
+                        if i > 0 {
+                            out.push(',');
+                        }
+                        write!(out, "{},{}", unit(p[0]), unit(p[1])).unwrap();
+                    }
+                    out.push_str(";\n");
+                }
+            }
+        }
+    }
+    match format {
+        PlotFormat::Gcode => out.push_str("G0 X0 Y0\nM2\n"),
+        PlotFormat::Hpgl => out.push_str("PU0,0;SP0;\n"),
+    }
+    std::fs::write(path, out).map_err(|e| format!("could not write {path}: {e}"))
+}
@@ -0,0 +1,295 @@
+use image::{RgbImage, imageops};
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::linear::{self, from_linear, image_to_linear};
+
+// One post-processing step, applied to the finished image.
+// Written as `name` or `name:arg:arg`, e.g. `blur:0.8` or `levels:10:240:1.2`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    // Floyd-Steinberg error diffusion down to `levels` values per channel.
+    Dither { levels: u8 },
+    Blur { sigma: f32 },
+    // Adds `amount` times the difference from a blurred copy.
+    Unsharp { sigma: f32, amount: f32 },
+    // Darkens toward the corners; 1.0 makes the corners black.
+    Vignette { strength: f32 },
+    // Maps black..white to 0..255, then applies gamma.
+    Levels { black: u8, white: u8, gamma: f32 },
+    // Scales distance from mid-gray.
+    Contrast { amount: f32 },
+    // Histogram equalization of luma, keeping each pixel's hue.
+    Equalize,
+    // A 3D color lookup table loaded from a .cube file.
+    Lut(Arc<Cube>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cube {
+    size: usize,
+    domain_min: [f32; 3],
+    domain_max: [f32; 3],
+    // Red varies fastest, then green, then blue.
+    table: Vec<[f32; 3]>,
+}
+
+impl Cube {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("could not read {path}: {e}"))?;
+        let mut size = None;
+        let mut domain_min = [0.0; 3];
+        let mut domain_max = [1.0; 3];
+        let mut table = vec![];
+        let triple = |rest: &[&str]| -> Result<[f32; 3], String> {
+            let v: Vec<f32> = rest
+                .iter()
+                .map(|s| {
+                    s.parse()
+                        .map_err(|_| format!("invalid number {s} in {path}"))
+                })
+                .collect::<Result<_, _>>()?;
+            v.try_into()
+                .map_err(|_| format!("expected three numbers in {path}"))
+        };
+        for line in text.lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.first() {
+                None => {}
+                Some(w) if w.starts_with('#') => {}
+                Some(&"TITLE") => {}
+                Some(&"LUT_1D_SIZE") => return Err(format!("{path}: 1D LUTs are not supported")),
+                Some(&"LUT_3D_SIZE") => {
+                    size = Some(
+                        words
+                            .get(1)
+                            .and_then(|s| s.parse().ok())
+                            .ok_or(format!("invalid LUT_3D_SIZE in {path}"))?,
+                    )
+                }
+                Some(&"DOMAIN_MIN") => domain_min = triple(&words[1..])?,
+                Some(&"DOMAIN_MAX") => domain_max = triple(&words[1..])?,
+                Some(_) => table.push(triple(&words)?),
+            }
+        }
+        let size: usize = size.ok_or(format!("missing LUT_3D_SIZE in {path}"))?;
+        if size < 2 || table.len() != size.pow(3) {
+            return Err(format!(
+                "{path}: expected {} entries, found {}",
+                size.pow(3),
+                table.len()
+            ));
+        }
+        Ok(Self {
+            size,
+            domain_min,
+            domain_max,
+            table,
+        })
+    }
+
+    // Trilinear interpolation.
+    fn lookup(&self, color: [u8; 3]) -> [u8; 3] {
+        let n = self.size - 1;
+        let mut base = [0; 3];
+        let mut frac = [0.0; 3];
+        for ch in 0..3 {
+            let v = color[ch] as f32 / 255.0;
+            let t = ((v - self.domain_min[ch]) / (self.domain_max[ch] - self.domain_min[ch]))
+                .clamp(0.0, 1.0)
+                * n as f32;
+            base[ch] = (t.floor() as usize).min(n - 1);
+            frac[ch] = t - base[ch] as f32;
+        }
+        let mut out = [0.0; 3];
+        for corner in 0..8 {
+            let mut weight = 1.0;
+            let mut index = 0;
+            for ch in 0..3 {
+                let bit = (corner >> ch) & 1;
+                weight *= if bit == 1 { frac[ch] } else { 1.0 - frac[ch] };
+                index += (base[ch] + bit) * self.size.pow(ch as u32);
+            }
+            for (o, v) in out.iter_mut().zip(self.table[index]) {
+                *o += weight * v;
+            }
+        }
+        out.map(|v| to_u8(v * 255.0))
+    }
+}
+
+impl FromStr for Step {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        let mut parts = s.trim().split(':');
+        let name = parts.next().expect("split is nonempty");
+        let args: Vec<&str> = parts.collect();
+        let arg = |i: usize, default: f32| -> Result<f32, String> {
+            match args.get(i) {
+                Some(a) => a
+                    .parse()
+                    .map_err(|_| format!("invalid argument {a} for {name}")),
+                None => Ok(default),
+            }
+        };
+        let step = match name {
+            "dither" => Step::Dither {
+                levels: arg(0, 2.0)?.clamp(2.0, 256.0) as u8,
+            },
+            "blur" => Step::Blur {
+                sigma: arg(0, 0.8)?,
+            },
+            "unsharp" => Step::Unsharp {
+                sigma: arg(0, 1.0)?,
+                amount: arg(1, 0.5)?,
+            },
+            "vignette" => Step::Vignette {
+                strength: arg(0, 0.4)?,
+            },
+            "levels" => Step::Levels {
+                black: arg(0, 0.0)?.clamp(0.0, 255.0) as u8,
+                white: arg(1, 255.0)?.clamp(0.0, 255.0) as u8,
+                gamma: arg(2, 1.0)?,
+            },
+            "contrast" => Step::Contrast {
+                amount: arg(0, 1.2)?,
+            },
+            "equalize" => Step::Equalize,
+            "lut" => Step::Lut(Arc::new(Cube::load(&args.join(":"))?)),
+            _ => return Err(format!("unknown post-processing step {name}")),
+        };
+        Ok(step)
+    }
+}
+
+// Steps from a file: one per line, blank lines and `#` comments ignored.
+pub fn steps_from_file(path: &str) -> Result<Vec<Step>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("could not read {path}: {e}"))?;
+    text.lines()
+        .map(|line| line.split('#').next().expect("split is nonempty").trim())
+        .filter(|line| !line.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+fn to_u8(v: f32) -> u8 {
+    v.round().clamp(0.0, 255.0) as u8
+}
+
+pub fn apply(img: &mut RgbImage, steps: &[Step]) {
+    for step in steps {
+        match *step {
+            Step::Dither { levels } => dither(img, levels),
+            Step::Blur { sigma } => *img = linear::blur(img, sigma),
+            Step::Unsharp { sigma, amount } => {
+                let sharp = image_to_linear(img);
+                let blurred = imageops::blur(&sharp, sigma);
+                for ((p, s), b) in img.pixels_mut().zip(sharp.pixels()).zip(blurred.pixels()) {
+                    for ch in 0..3 {
+                        p.0[ch] = from_linear(s.0[ch] + amount * (s.0[ch] - b.0[ch]));
+                    }
+                }
+            }
+            Step::Vignette { strength } => {
+                let (w, h) = img.dimensions();
+                let (cx, cy) = (w as f32 / 2.0, h as f32 / 2.0);
+                let max_sq = cx * cx + cy * cy;
+                for (x, y, p) in img.enumerate_pixels_mut() {
+                    let dx = x as f32 + 0.5 - cx;
+                    let dy = y as f32 + 0.5 - cy;
+                    let scale = 1.0 - strength * (dx * dx + dy * dy) / max_sq;
+                    for c in p.0.iter_mut() {
+                        *c = to_u8(*c as f32 * scale);
+                    }
+                }
+            }
+            Step::Levels {
+                black,
+                white,
+                gamma,
+            } => {
+                let range = (white as f32 - black as f32).max(1.0);
+                let table: Vec<u8> = (0..=255u8)
+                    .map(|v| {
+                        let t = ((v.saturating_sub(black)) as f32 / range).min(1.0);
+                        to_u8(255.0 * t.powf(1.0 / gamma))
+                    })
+                    .collect();
+                for c in img.iter_mut() {
+                    *c = table[*c as usize];
+                }
+            }
+            Step::Contrast { amount } => {
+                for c in img.iter_mut() {
+                    *c = to_u8(127.5 + amount * (*c as f32 - 127.5));
+                }
+            }
+            Step::Equalize => equalize(img),
+            Step::Lut(ref cube) => {
+                for p in img.pixels_mut() {
+                    p.0 = cube.lookup(p.0);
+                }
+            }
+        }
+    }
+}
+
+fn dither(img: &mut RgbImage, levels: u8) {
+    let (w, h) = (img.width() as usize, img.height() as usize);
+    let step = 255.0 / (levels - 1) as f32;
+    let mut buf: Vec<f32> = img.iter().map(|&c| c as f32).collect();
+    for y in 0..h {
+        for x in 0..w {
+            for ch in 0..3 {
+                let i = (y * w + x) * 3 + ch;
+                let old = buf[i];
+                let new = (old / step).round().clamp(0.0, (levels - 1) as f32) * step;
+                buf[i] = new;
+                let err = old - new;
+                let mut spread = |dx: isize, dy: usize, weight: f32| {
+                    let nx = x as isize + dx;
+                    if nx >= 0 && (nx as usize) < w && y + dy < h {
+                        buf[((y + dy) * w + nx as usize) * 3 + ch] += err * weight;
+                    }
+                };
+                spread(1, 0, 7.0 / 16.0);
+                spread(-1, 1, 3.0 / 16.0);
+                spread(0, 1, 5.0 / 16.0);
+                spread(1, 1, 1.0 / 16.0);
+            }
+        }
+    }
+    for (c, v) in img.iter_mut().zip(buf) {
+        *c = to_u8(v);
+    }
+}
+
+fn luma(c: [u8; 3]) -> f32 {
+    0.299 * c[0] as f32 + 0.587 * c[1] as f32 + 0.114 * c[2] as f32
+}
+
+fn equalize(img: &mut RgbImage) {
+    let mut histogram = [0u64; 256];
+    for p in img.pixels() {
+        histogram[to_u8(luma(p.0)) as usize] += 1;
+    }
+    let total = (img.width() as u64 * img.height() as u64).max(1);
+    let mut cdf = [0.0f32; 256];
+    let mut sum = 0;
+    for (c, h) in cdf.iter_mut().zip(histogram) {
+        sum += h;
+        *c = 255.0 * sum as f32 / total as f32;
+    }
+    for p in img.pixels_mut() {
+        let y = luma(p.0);
+        let target = cdf[to_u8(y) as usize];
+        let scale = if y > 0.0 { target / y } else { 0.0 };
+        p.0 = if y > 0.0 {
+            p.0.map(|c| to_u8(c as f32 * scale))
+        } else {
+            [to_u8(target); 3]
+        };
+    }
+}
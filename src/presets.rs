@@ -0,0 +1,130 @@
+use image::{Rgb, RgbImage};
+
+use crate::stencil::draw_text;
+use crate::{Params, generate};
+
+// A named set of arguments showing off one corner of the parameter space.
+pub struct Preset {
+    pub name: &'static str,
+    pub args: &'static [&'static str],
+}
+
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "classic",
+        args: &[],
+    },
+    Preset {
+        name: "tight",
+        args: &[
+            "--centers",
+            "60",
+            "--start-spread",
+            "0.05",
+            "--cont-spread",
+            "0.02",
+        ],
+    },
+    Preset {
+        name: "loose",
+        args: &[
+            "--centers",
+            "5",
+            "--start-spread",
+            "1",
+            "--cont-spread",
+            "0.5",
+        ],
+    },
+    Preset {
+        name: "epicycles",
+        args: &["--orbit", "epicycle:0.3:4", "--palette", "viridis"],
+    },
+    Preset {
+        name: "spirals",
+        args: &["--spiral", "out:0.05", "--palette", "magma"],
+    },
+    Preset {
+        name: "knight",
+        args: &["--neighborhood", "knight", "--palette", "set1"],
+    },
+    Preset {
+        name: "disc",
+        args: &[
+            "--canvas",
+            "disc",
+            "--palette",
+            "plasma",
+            "--start-spread",
+            "0.2",
+        ],
+    },
+    Preset {
+        name: "grayscale",
+        args: &["--mode", "grayscale", "--lookback", "100"],
+    },
+    Preset {
+        name: "sparse",
+        args: &[
+            "--sampling",
+            "sparse",
+            "--palette",
+            "spectral",
+            "--centers",
+            "40",
+        ],
+    },
+    Preset {
+        name: "drift",
+        args: &["--center-drift", "walk:1", "--palette", "inferno"],
+    },
+    Preset {
+        name: "gravity",
+        args: &["--gravity", "0.01", "--palette", "rdylbu"],
+    },
+    Preset {
+        name: "duotone",
+        args: &["--mode", "duotone", "1b3a6b", "f2c14e", "--lookback", "300"],
+    },
+];
+
+impl Preset {
+    pub fn params(&self, size: usize) -> Result<Params, String> {
+        let size = size.to_string();
+        let args = self.args.iter().copied().chain(["--size", size.as_str()]);
+        Params::from_args(args.map(String::from)).map_err(|e| format!("preset {}: {e}", self.name))
+    }
+}
+
+const GAP: u32 = 8;
+const LABEL_SCALE: u32 = 2;
+const BACKGROUND: Rgb<u8> = Rgb([24, 24, 24]);
+const INK: Rgb<u8> = Rgb([220, 220, 220]);
+
+// Every preset rendered at size, in a grid with its name under each tile.
+pub fn sample_sheet(size: usize) -> Result<RgbImage, String> {
+    let columns = (PRESETS.len() as f64).sqrt().ceil() as u32;
+    let rows = (PRESETS.len() as u32).div_ceil(columns);
+    let tile = size as u32;
+    let label = 7 * LABEL_SCALE + GAP;
+    let mut sheet = RgbImage::from_pixel(
+        columns * (tile + GAP) + GAP,
+        rows * (tile + label + GAP) + GAP,
+        BACKGROUND,
+    );
+    for (i, preset) in PRESETS.iter().enumerate() {
+        let params = preset.params(size)?;
+        let run = generate(&params, false);
+        let left = GAP + i as u32 % columns * (tile + GAP);
+        let top = GAP + i as u32 / columns * (tile + label + GAP);
+        image::imageops::replace(&mut sheet, &run.image, left as i64, top as i64);
+        draw_text(
+            &mut sheet,
+            preset.name,
+            [left, top + tile + GAP / 2],
+            LABEL_SCALE,
+            INK,
+        );
+    }
+    Ok(sheet)
+}
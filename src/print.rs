@@ -0,0 +1,336 @@
+use image::{ImageBuffer, RgbImage};
+use moxcms::{ColorProfile, DataColorSpace, Layout, RenderingIntent, TransformOptions};
+#[cfg(feature = "files")]
+use tiff::encoder::{TiffEncoder, colortype::CMYK8};
+#[cfg(feature = "files")]
+use tiff::tags::Tag;
+
+use std::collections::HashMap;
+#[cfg(feature = "files")]
+use std::fs::File;
+#[cfg(feature = "files")]
+use std::io::BufWriter;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::Color;
+use crate::linear::{from_linear, to_linear};
+use crate::output::ColorSpace;
+
+// Colors whose proof is further than this from the original, in CIE76
+// delta E, count as out of gamut.
+pub const GAMUT_TOLERANCE: f64 = 5.0;
+
+// How the image is separated into inks for --cmyk. Written as `swop`,
+// `fogra39` or the path of a CMYK ICC profile.
+#[derive(Debug, Clone, Default)]
+pub enum PrintProfile {
+    // Built-in approximations of common presses.
+    #[default]
+    Swop,
+    Fogra39,
+    Icc(Arc<IccProfile>),
+}
+
+#[derive(Debug)]
+pub struct IccProfile {
+    pub path: String,
+    // Embedded in the TIFF as is.
+    pub bytes: Vec<u8>,
+    pub profile: ColorProfile,
+}
+
+impl FromStr for PrintProfile {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "swop" => Ok(PrintProfile::Swop),
+            "fogra39" => Ok(PrintProfile::Fogra39),
+            path => {
+                let bytes =
+                    std::fs::read(path).map_err(|e| format!("could not read {path}: {e}"))?;
+                let profile = ColorProfile::new_from_slice(&bytes)
+                    .map_err(|e| format!("invalid ICC profile {path}: {e}"))?;
+                if profile.color_space != DataColorSpace::Cmyk {
+                    return Err(format!("{path} is not a CMYK profile"));
+                }
+                Ok(PrintProfile::Icc(Arc::new(IccProfile {
+                    path: path.to_string(),
+                    bytes,
+                    profile,
+                })))
+            }
+        }
+    }
+}
+
+impl PrintProfile {
+    pub fn name(&self) -> &str {
+        match self {
+            PrintProfile::Swop => "swop",
+            PrintProfile::Fogra39 => "fogra39",
+            PrintProfile::Icc(icc) => &icc.path,
+        }
+    }
+}
+
+// A simple press: each ink's color at full coverage, combined by
+// multiplying reflectances in linear light, and the most ink, summed over
+// the four, that the paper takes.
+struct Press {
+    inks: [Color; 4],
+    ink_limit: f64,
+}
+
+const SWOP: Press = Press {
+    inks: [[0, 174, 239], [236, 0, 140], [255, 242, 0], [35, 31, 32]],
+    ink_limit: 3.0,
+};
+
+const FOGRA39: Press = Press {
+    inks: [[0, 159, 227], [230, 0, 126], [255, 237, 0], [29, 29, 27]],
+    ink_limit: 3.3,
+};
+
+// Share of the gray component that black ink takes over.
+const GRAY_REPLACEMENT: f64 = 0.7;
+
+impl Press {
+    // Linear reflectance of paper under the given ink coverages.
+    fn reflect(&self, inks: [f64; 4]) -> [f64; 3] {
+        [0, 1, 2].map(|ch| {
+            (0..4)
+                .map(|k| 1.0 - inks[k] * (1.0 - to_linear(self.inks[k][ch]) as f64))
+                .product()
+        })
+    }
+
+    // Coverages printing as close to color as the press allows.
+    fn separate(&self, color: Color) -> [f64; 4] {
+        let target = color.map(|c| to_linear(c) as f64);
+        let max = *color.iter().max().expect("three channels") as f64 / 255.0;
+        let k = (1.0 - max) * GRAY_REPLACEMENT;
+        let mut cmy = [0, 1, 2].map(|ch| {
+            let naive = 1.0 - color[ch] as f64 / 255.0;
+            ((naive - k) / (1.0 - k).max(1e-6)).clamp(0.0, 1.0)
+        });
+        // Newton's method on the three coverages, each ink mainly
+        // darkening one channel.
+        for _ in 0..12 {
+            let at = [cmy[0], cmy[1], cmy[2], k];
+            let out = self.reflect(at);
+            let mut jacobian = [[0.0; 3]; 3];
+            for (ch, row) in jacobian.iter_mut().enumerate() {
+                for (j, d) in row.iter_mut().enumerate() {
+                    let absorb = 1.0 - to_linear(self.inks[j][ch]) as f64;
+                    let alone = 1.0 - at[j] * absorb;
+                    *d = if alone > 1e-9 {
+                        -absorb * out[ch] / alone
+                    } else {
+                        0.0
+                    };
+                }
+            }
+            let error = [0, 1, 2].map(|ch| target[ch] - out[ch]);
+            let Some(step) = solve3(jacobian, error) else {
+                break;
+            };
+            for j in 0..3 {
+                cmy[j] = (cmy[j] + step[j]).clamp(0.0, 1.0);
+            }
+        }
+        let total: f64 = cmy.iter().sum::<f64>() + k;
+        if total > self.ink_limit {
+            let scale = (self.ink_limit - k) / (total - k);
+            cmy = cmy.map(|c| c * scale);
+        }
+        [cmy[0], cmy[1], cmy[2], k]
+    }
+}
+
+// Cramer's rule; None for a singular matrix.
+fn solve3(m: [[f64; 3]; 3], b: [f64; 3]) -> Option<[f64; 3]> {
+    let det = |m: [[f64; 3]; 3]| {
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    };
+    let d = det(m);
+    if d.abs() < 1e-12 {
+        return None;
+    }
+    Some([0, 1, 2].map(|col| {
+        let mut replaced = m;
+        for row in 0..3 {
+            replaced[row][col] = b[row];
+        }
+        det(replaced) / d
+    }))
+}
+
+// CIELAB under D65 of an sRGB color.
+pub fn lab(color: Color) -> [f64; 3] {
+    let [r, g, b] = color.map(|c| to_linear(c) as f64);
+    let xyz = [
+        (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047,
+        0.2126 * r + 0.7152 * g + 0.0722 * b,
+        (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883,
+    ];
+    let f = xyz.map(|t| {
+        if t > (6.0f64 / 29.0).powi(3) {
+            t.cbrt()
+        } else {
+            t / (3.0 * (6.0f64 / 29.0).powi(2)) + 4.0 / 29.0
+        }
+    });
+    [
+        116.0 * f[1] - 16.0,
+        500.0 * (f[0] - f[1]),
+        200.0 * (f[1] - f[2]),
+    ]
+}
+
+// The sRGB color nearest a CIELAB one, the inverse of lab.
+pub fn from_lab([l, a, b]: [f64; 3]) -> Color {
+    let fy = (l + 16.0) / 116.0;
+    let [x, y, z] = [fy + a / 500.0, fy, fy - b / 200.0].map(|f| {
+        if f > 6.0 / 29.0 {
+            f.powi(3)
+        } else {
+            3.0 * (6.0f64 / 29.0).powi(2) * (f - 4.0 / 29.0)
+        }
+    });
+    let (x, z) = (x * 0.95047, z * 1.08883);
+    [
+        3.2406 * x - 1.5372 * y - 0.4986 * z,
+        -0.9689 * x + 1.8758 * y + 0.0415 * z,
+        0.0557 * x - 0.2040 * y + 1.0570 * z,
+    ]
+    .map(|c| from_linear(c as f32))
+}
+
+pub fn delta_e(a: Color, b: Color) -> f64 {
+    let (a, b) = (lab(a), lab(b));
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+// The image as inks, with a soft proof of how it prints.
+pub struct Separation {
+    pub width: u32,
+    pub height: u32,
+    // Interleaved C, M, Y, K.
+    pub cmyk: Vec<u8>,
+    pub proof: RgbImage,
+    pub out_of_gamut: usize,
+    // Largest delta E between a pixel and its proof.
+    pub worst: f64,
+}
+
+// The image's values are in the given space; the proof is always sRGB.
+pub fn separate(
+    image: &RgbImage,
+    space: ColorSpace,
+    profile: &PrintProfile,
+) -> Result<Separation, String> {
+    let options = TransformOptions {
+        rendering_intent: RenderingIntent::RelativeColorimetric,
+        ..Default::default()
+    };
+    let srgb = ColorProfile::new_srgb();
+    let mut colors: Vec<Color> = image.pixels().map(|p| p.0).collect();
+    // The built-in presses and the gamut check work in sRGB.
+    let original = colors.clone();
+    if space != ColorSpace::Srgb {
+        let error = |e| format!("could not convert from {}: {e}", space.name());
+        let to_srgb = space
+            .profile()
+            .create_transform_8bit(Layout::Rgb, &srgb, Layout::Rgb, options)
+            .map_err(error)?;
+        let mut converted = vec![[0; 3]; colors.len()];
+        to_srgb
+            .transform(original.as_flattened(), converted.as_flattened_mut())
+            .map_err(error)?;
+        colors = converted;
+    }
+    let (cmyk, proof): (Vec<u8>, Vec<Color>) = match profile {
+        PrintProfile::Swop | PrintProfile::Fogra39 => {
+            let press = match profile {
+                PrintProfile::Swop => &SWOP,
+                _ => &FOGRA39,
+            };
+            let mut cache: HashMap<Color, ([u8; 4], Color)> = HashMap::new();
+            let mut cmyk = Vec::with_capacity(colors.len() * 4);
+            let mut proof = Vec::with_capacity(colors.len());
+            for &color in &colors {
+                let (inks, shown) = *cache.entry(color).or_insert_with(|| {
+                    let inks = press.separate(color);
+                    let bytes = inks.map(|v| (v * 255.0).round() as u8);
+                    let printed = press.reflect(bytes.map(|v| v as f64 / 255.0));
+                    (bytes, printed.map(|v| from_linear(v as f32)))
+                });
+                cmyk.extend(inks);
+                proof.push(shown);
+            }
+            (cmyk, proof)
+        }
+        PrintProfile::Icc(icc) => {
+            let error = |e| format!("could not use {}: {e}", icc.path);
+            let forward = space
+                .profile()
+                .create_transform_8bit(Layout::Rgb, &icc.profile, Layout::Rgba, options)
+                .map_err(error)?;
+            let back = icc
+                .profile
+                .create_transform_8bit(Layout::Rgba, &srgb, Layout::Rgb, options)
+                .map_err(error)?;
+            let mut cmyk = vec![0; colors.len() * 4];
+            forward
+                .transform(original.as_flattened(), &mut cmyk)
+                .map_err(error)?;
+            let mut proof = vec![0; colors.len() * 3];
+            back.transform(&cmyk, &mut proof).map_err(error)?;
+            let proof = proof.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect();
+            (cmyk, proof)
+        }
+    };
+    let mut out_of_gamut = 0;
+    let mut worst: f64 = 0.0;
+    for (&color, &shown) in colors.iter().zip(&proof) {
+        let d = delta_e(color, shown);
+        worst = worst.max(d);
+        if d > GAMUT_TOLERANCE {
+            out_of_gamut += 1;
+        }
+    }
+    let (width, height) = image.dimensions();
+    Ok(Separation {
+        width,
+        height,
+        cmyk,
+        proof: ImageBuffer::from_vec(width, height, proof.concat()).expect("matching size"),
+        out_of_gamut,
+        worst,
+    })
+}
+
+// A CMYK TIFF, carrying the ICC profile when one was given.
+#[cfg(feature = "files")]
+pub fn save_cmyk_tiff(
+    separation: &Separation,
+    profile: &PrintProfile,
+    path: &str,
+) -> Result<(), String> {
+    let error = |e: tiff::TiffError| format!("could not write {path}: {e}");
+    let file = File::create(path).map_err(|e| format!("could not create {path}: {e}"))?;
+    let mut encoder = TiffEncoder::new(BufWriter::new(file)).map_err(error)?;
+    let mut image = encoder
+        .new_image::<CMYK8>(separation.width, separation.height)
+        .map_err(error)?;
+    if let PrintProfile::Icc(icc) = profile {
+        image
+            .encoder()
+            .write_tag(Tag::IccProfile, &icc.bytes[..])
+            .map_err(error)?;
+    }
+    image.write_data(&separation.cmyk).map_err(error)
+}
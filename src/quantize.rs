@@ -0,0 +1,112 @@
+use image::RgbImage;
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::{Color, color_dist_sq};
+
+// Whether --max-colors restricts the generated colors or the saved image.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Quantize {
+    Generation,
+    #[default]
+    Image,
+}
+
+impl FromStr for Quantize {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "generation" => Ok(Quantize::Generation),
+            "image" => Ok(Quantize::Image),
+            _ => Err(format!("unknown quantize mode {s}")),
+        }
+    }
+}
+
+// Median cut: repeatedly split the box with the widest channel range at its
+// median until there are n boxes, then use each box's mean color.
+pub fn median_cut(colors: &[Color], n: usize) -> Vec<Color> {
+    let mut boxes: Vec<Vec<Color>> = vec![colors.to_vec()];
+    while boxes.len() < n {
+        let range = |b: &Vec<Color>, ch: usize| {
+            let (lo, hi) = b
+                .iter()
+                .fold((255, 0), |(lo, hi), c| (c[ch].min(lo), c[ch].max(hi)));
+            hi.saturating_sub(lo)
+        };
+        let Some((index, channel)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.len() > 1)
+            .flat_map(|(i, b)| (0..3).map(move |ch| (i, ch, range(b, ch))))
+            .filter(|&(_, _, r)| r > 0)
+            .max_by_key(|&(i, _, r)| (r, std::cmp::Reverse(i)))
+            .map(|(i, ch, _)| (i, ch))
+        else {
+            break;
+        };
+        let mut b = boxes.swap_remove(index);
+        b.sort_unstable_by_key(|c| c[channel]);
+        let upper = b.split_off(b.len() / 2);
+        boxes.push(b);
+        boxes.push(upper);
+    }
+    boxes
+        .iter()
+        .filter(|b| !b.is_empty())
+        .map(|b| {
+            let mut sum = [0u64; 3];
+            for c in b {
+                for (s, &v) in sum.iter_mut().zip(c) {
+                    *s += v as u64;
+                }
+            }
+            sum.map(|s| (s / b.len() as u64) as u8)
+        })
+        .collect()
+}
+
+// Palette and per-pixel indices, if the image has at most max distinct colors.
+pub fn exact_palette(img: &RgbImage, max: usize) -> Option<(Vec<Color>, Vec<u8>)> {
+    let mut palette = vec![];
+    let mut index_of: HashMap<Color, u8> = HashMap::new();
+    let mut indices = Vec::with_capacity(img.width() as usize * img.height() as usize);
+    for pixel in img.pixels() {
+        let index = match index_of.get(&pixel.0) {
+            Some(&index) => index,
+            None => {
+                if palette.len() == max {
+                    return None;
+                }
+                palette.push(pixel.0);
+                let index = (palette.len() - 1) as u8;
+                index_of.insert(pixel.0, index);
+                index
+            }
+        };
+        indices.push(index);
+    }
+    Some((palette, indices))
+}
+
+// Reduces the image to at most n <= 256 colors.
+pub fn quantize_image(img: &RgbImage, n: usize) -> (Vec<Color>, Vec<u8>) {
+    if let Some(exact) = exact_palette(img, n) {
+        return exact;
+    }
+    let colors: Vec<Color> = img.pixels().map(|p| p.0).collect();
+    let palette = median_cut(&colors, n);
+    let mut cache: HashMap<Color, u8> = HashMap::new();
+    let indices = colors
+        .iter()
+        .map(|&c| {
+            *cache.entry(c).or_insert_with(|| {
+                (0..palette.len())
+                    .min_by_key(|&i| color_dist_sq(c, palette[i]))
+                    .expect("nonempty") as u8
+            })
+        })
+        .collect();
+    (palette, indices)
+}
@@ -0,0 +1,218 @@
+use image::{Rgb, RgbImage};
+
+use std::str::FromStr;
+
+use crate::linear::{from_linear, to_linear};
+use crate::params::parse_list;
+use crate::{Color, Placement};
+
+// A rectangle of the canvas, written as `x,y,w,h` in canvas pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Crop {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl FromStr for Crop {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match parse_list::<usize>("--crop", s)?[..] {
+            [x, y, width, height] if width > 0 && height > 0 => Ok(Crop {
+                x,
+                y,
+                width,
+                height,
+            }),
+            _ => Err(format!(
+                "--crop expects x,y,w,h with w and h positive, got {s}"
+            )),
+        }
+    }
+}
+
+impl Crop {
+    pub fn check(&self, size: usize) -> Result<(), String> {
+        if self.x + self.width > size || self.y + self.height > size {
+            return Err(format!(
+                "--crop {},{},{},{} reaches outside the {size}x{size} canvas",
+                self.x, self.y, self.width, self.height
+            ));
+        }
+        Ok(())
+    }
+}
+
+// The footprint splatted for each placement.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Kernel {
+    #[default]
+    Square,
+    Disc,
+}
+
+impl FromStr for Kernel {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "square" => Ok(Kernel::Square),
+            "disc" => Ok(Kernel::Disc),
+            _ => Err(format!("unknown kernel {s}")),
+        }
+    }
+}
+
+// How recorded placements become a raster. The placements live on the
+// canvas; the raster may show part of it, at any number of output pixels per
+// canvas pixel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Raster {
+    // Draw only this part of the canvas.
+    pub crop: Option<Crop>,
+    // Output pixels per canvas pixel.
+    pub scale: usize,
+    pub kernel: Kernel,
+    // Width of each footprint, in canvas pixels.
+    pub stroke: f64,
+    // Splat at the orbits' own positions rather than at the middles of the
+    // cells they filled.
+    pub subpixel: bool,
+    // Blend overlapping footprints by how much of each output pixel they
+    // cover, instead of letting the last one win.
+    pub accumulate: bool,
+    pub background: Color,
+}
+
+impl Default for Raster {
+    fn default() -> Self {
+        Self {
+            crop: None,
+            scale: 1,
+            kernel: Kernel::Square,
+            stroke: 1.0,
+            subpixel: false,
+            accumulate: false,
+            background: [0, 0, 0],
+        }
+    }
+}
+
+impl Raster {
+    // Anything but one cell to one output pixel.
+    pub fn is_custom(&self) -> bool {
+        *self
+            != Raster {
+                background: self.background,
+                ..Raster::default()
+            }
+    }
+}
+
+// Splats the placements in their recorded order, so later ones cover earlier
+// ones where footprints overlap. With the defaults this redraws the image the
+// generator made.
+pub fn rasterize(
+    placements: &[Placement],
+    size: usize,
+    raster: &Raster,
+    color: impl Fn(Color) -> Color,
+) -> RgbImage {
+    let crop = raster.crop.unwrap_or(Crop {
+        x: 0,
+        y: 0,
+        width: size,
+        height: size,
+    });
+    let scale = raster.scale as f64;
+    let (width, height) = (crop.width * raster.scale, crop.height * raster.scale);
+    let half = (raster.stroke * scale).max(1.0) / 2.0;
+    // Each footprint's middle in output pixels, and its color.
+    let splats = placements.iter().map(|placement| {
+        let pos = if raster.subpixel {
+            placement.pos
+        } else {
+            placement.pixel.loc.map(|c| c as f64 + 0.5)
+        };
+        let middle = [
+            (pos[0] - crop.x as f64) * scale,
+            (pos[1] - crop.y as f64) * scale,
+        ];
+        (middle, color(placement.pixel.color))
+    });
+    if raster.accumulate {
+        return accumulate(splats, width, height, half, raster);
+    }
+    let mut image = RgbImage::from_pixel(width as u32, height as u32, Rgb(raster.background));
+    for ([cx, cy], splat) in splats {
+        let span = |c: f64, len: usize| {
+            ((c - half).round().max(0.0) as usize)..((c + half).round().max(0.0) as usize).min(len)
+        };
+        let (xs, ys) = (span(cx, width), span(cy, height));
+        for y in ys {
+            for x in xs.clone() {
+                let inside = match raster.kernel {
+                    Kernel::Square => true,
+                    Kernel::Disc => {
+                        (x as f64 + 0.5 - cx).powi(2) + (y as f64 + 0.5 - cy).powi(2) <= half * half
+                    }
+                };
+                if inside {
+                    image.put_pixel(x as u32, y as u32, Rgb(splat));
+                }
+            }
+        }
+    }
+    image
+}
+
+// Sums each footprint's color weighted by the fraction of each output pixel
+// it covers, then resolves every pixel to the weighted mean, over the
+// background where the total cover is below one. All in linear light.
+fn accumulate(
+    splats: impl Iterator<Item = ([f64; 2], Color)>,
+    width: usize,
+    height: usize,
+    half: f64,
+    raster: &Raster,
+) -> RgbImage {
+    let mut sums = vec![[0.0f64; 4]; width * height];
+    for ([cx, cy], splat) in splats {
+        let span = |c: f64, len: usize| {
+            ((c - half).floor().max(0.0) as usize)..((c + half).ceil().max(0.0) as usize).min(len)
+        };
+        for y in span(cy, height) {
+            for x in span(cx, width) {
+                let cover = match raster.kernel {
+                    Kernel::Square => {
+                        let overlap = |p: f64, c: f64| {
+                            ((p + 1.0).min(c + half) - p.max(c - half)).clamp(0.0, 1.0)
+                        };
+                        overlap(x as f64, cx) * overlap(y as f64, cy)
+                    }
+                    // Antialiased edge, a pixel wide.
+                    Kernel::Disc => {
+                        let dist = (x as f64 + 0.5 - cx).hypot(y as f64 + 0.5 - cy);
+                        (half - dist + 0.5).clamp(0.0, 1.0)
+                    }
+                };
+                if cover > 0.0 {
+                    let sum = &mut sums[y * width + x];
+                    for k in 0..3 {
+                        sum[k] += to_linear(splat[k]) as f64 * cover;
+                    }
+                    sum[3] += cover;
+                }
+            }
+        }
+    }
+    RgbImage::from_fn(width as u32, height as u32, |x, y| {
+        let sum = sums[y as usize * width + x as usize];
+        let alpha = sum[3].min(1.0);
+        Rgb([0, 1, 2].map(|k| {
+            let mean = if sum[3] > 0.0 { sum[k] / sum[3] } else { 0.0 };
+            let background = to_linear(raster.background[k]) as f64;
+            from_linear((mean * alpha + background * (1.0 - alpha)) as f32)
+        }))
+    })
+}
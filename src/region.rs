@@ -0,0 +1,129 @@
+use std::ops::Range;
+
+use crate::Location;
+use crate::params::Params;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Region {
+    pub cont_spread: f64,
+    pub num_lookback: usize,
+    // Ratio of the orbit's second axis to its first: 1.0 is a circle.
+    pub aspect: f64,
+}
+
+// Coarse grid of regions covering the canvas, row-major.
+#[derive(Debug, Clone)]
+pub struct RegionMap {
+    cols: usize,
+    rows: usize,
+    regions: Vec<Region>,
+}
+
+impl RegionMap {
+    pub fn uniform(params: &Params) -> Self {
+        Self {
+            cols: 1,
+            rows: 1,
+            regions: vec![Region {
+                cont_spread: params.cont_spread,
+                num_lookback: params.num_lookback,
+                aspect: 1.0,
+            }],
+        }
+    }
+
+    // Each list has one entry per region, a single entry for all regions,
+    // or is empty to use the base parameter everywhere.
+    pub fn from_lists(
+        cols: usize,
+        rows: usize,
+        cont_spread: &[f64],
+        num_lookback: &[usize],
+        aspect: &[f64],
+        params: &Params,
+    ) -> Result<Self, String> {
+        let n = cols * rows;
+        if n == 0 {
+            return Err("region grid must be nonempty".to_string());
+        }
+        fn pick<T: Copy>(list: &[T], default: T, n: usize, i: usize) -> Result<T, String> {
+            match list.len() {
+                0 => Ok(default),
+                1 => Ok(list[0]),
+                len if len == n => Ok(list[i]),
+                len => Err(format!("expected 1 or {n} region values, got {len}")),
+            }
+        }
+        let regions = (0..n)
+            .map(|i| {
+                Ok(Region {
+                    cont_spread: pick(cont_spread, params.cont_spread, n, i)?,
+                    num_lookback: pick(num_lookback, params.num_lookback, n, i)?,
+                    aspect: pick(aspect, 1.0, n, i)?,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self {
+            cols,
+            rows,
+            regions,
+        })
+    }
+
+    // Each pixel of the map is one region. Channels scale the base parameters,
+    // with 128 meaning unchanged:
+    // red scales cont_spread, green scales num_lookback,
+    // blue sets the aspect to 2^((b - 128) / 64).
+    pub fn from_image(path: &str, params: &Params) -> Result<Self, String> {
+        let map = image::open(path)
+            .map_err(|e| format!("could not open {path}: {e}"))?
+            .to_rgb8();
+        let (cols, rows) = (map.width() as usize, map.height() as usize);
+        let mut regions = Vec::with_capacity(cols * rows);
+        for row in 0..rows {
+            for col in 0..cols {
+                let [r, g, b] = map.get_pixel(col as u32, row as u32).0;
+                regions.push(Region {
+                    cont_spread: params.cont_spread * r as f64 / 128.0,
+                    num_lookback: ((params.num_lookback as f64 * g as f64 / 128.0) as usize).max(1),
+                    aspect: 2f64.powf((b as f64 - 128.0) / 64.0),
+                });
+            }
+        }
+        Ok(Self {
+            cols,
+            rows,
+            regions,
+        })
+    }
+
+    pub fn max_lookback(&self) -> usize {
+        self.regions
+            .iter()
+            .map(|r| r.num_lookback)
+            .max()
+            .expect("nonempty")
+    }
+
+    // The x and y ranges of each region on a canvas of this size, row-major.
+    pub fn bounds(&self, size: usize) -> Vec<(Range<usize>, Range<usize>)> {
+        // The first cell whose region is at least k of n.
+        let edge = |k: usize, n: usize| (k * size).div_ceil(n);
+        (0..self.rows)
+            .flat_map(|row| {
+                (0..self.cols).map(move |col| {
+                    (
+                        edge(col, self.cols)..edge(col + 1, self.cols),
+                        edge(row, self.rows)..edge(row + 1, self.rows),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    pub fn at(&self, loc: Location, size: usize) -> &Region {
+        let col = (loc[0] * self.cols / size).min(self.cols - 1);
+        let row = (loc[1] * self.rows / size).min(self.rows - 1);
+        &self.regions[row * self.cols + col]
+    }
+}
@@ -0,0 +1,25 @@
+#version 300 es
+// Replays a run exported by `spinning shader`. Upload replay.bin, less its
+// 16-byte header, as a size by size RG32UI texture: red is the order each
+// cell was placed in, green its color. Each frame, set shown to how many
+// placements have happened so far, for example count * seconds / duration,
+// and draw a full-screen quad with uv running from 0 to 1, top left first.
+
+precision highp float;
+precision highp usampler2D;
+
+uniform usampler2D cells;
+uniform uint shown;
+uniform vec4 background;
+
+in vec2 uv;
+out vec4 color;
+
+void main() {
+    ivec2 size = textureSize(cells, 0);
+    ivec2 xy = min(ivec2(uv * vec2(size)), size - 1);
+    uvec2 cell = texelFetch(cells, xy, 0).rg;
+    // unpackUnorm4x8 is only in 3.1.
+    uvec4 rgba = (uvec4(cell.g) >> uvec4(0u, 8u, 16u, 24u)) & 0xffu;
+    color = cell.r >= shown ? background : vec4(rgba) / 255.0;
+}
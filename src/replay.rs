@@ -0,0 +1,44 @@
+use image::RgbImage;
+
+use crate::color::sample_colormap;
+use crate::palette::{self, Named};
+use crate::raster::{Raster, rasterize};
+use crate::{Color, Placement};
+
+// How a replay is drawn. The placements stay as recorded; only their look
+// changes.
+#[derive(Default)]
+pub struct Style {
+    pub raster: Raster,
+    // Recolor by brightness along a named palette.
+    pub palette: Option<Named>,
+}
+
+pub fn parse_palette(name: &str) -> Result<Named, String> {
+    palette::named(name).ok_or_else(|| {
+        format!(
+            "unknown palette {name}, expected one of {}",
+            palette::NAMES.join(", ")
+        )
+    })
+}
+
+pub fn render_replay(placements: &[Placement], size: usize, style: &Style) -> RgbImage {
+    rasterize(placements, size, &style.raster, |color| {
+        match &style.palette {
+            None => color,
+            Some(named) => recolor(named, color),
+        }
+    })
+}
+
+fn recolor(named: &Named, color: Color) -> Color {
+    let [r, g, b] = color.map(|c| c as f64 / 255.0);
+    let brightness = 0.299 * r + 0.587 * g + 0.114 * b;
+    match named {
+        Named::Colormap(stops) => sample_colormap(stops, brightness),
+        Named::Scheme(colors) => {
+            colors[((brightness * colors.len() as f64) as usize).min(colors.len() - 1)]
+        }
+    }
+}
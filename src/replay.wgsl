@@ -0,0 +1,38 @@
+// Replays a run exported by `spinning shader`. Bind replay.bin, less its
+// 16-byte header, as cells: one (order, color) pair per canvas cell, row by
+// row. Each frame, set shown to how many placements have happened so far,
+// for example count * seconds / duration, and draw a full-screen triangle.
+
+struct Replay {
+    size: u32,
+    shown: u32,
+    pad: vec2<u32>,
+    background: vec4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> replay: Replay;
+@group(0) @binding(1) var<storage, read> cells: array<vec2<u32>>;
+
+struct Varying {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vertex(@builtin(vertex_index) i: u32) -> Varying {
+    let uv = vec2<f32>(f32((i << 1u) & 2u), f32(i & 2u));
+    var out: Varying;
+    out.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+@fragment
+fn fragment(in: Varying) -> @location(0) vec4<f32> {
+    let xy = min(vec2<u32>(in.uv * f32(replay.size)), vec2<u32>(replay.size - 1u));
+    let cell = cells[xy.y * replay.size + xy.x];
+    if cell.x >= replay.shown {
+        return replay.background;
+    }
+    return unpack4x8unorm(cell.y);
+}
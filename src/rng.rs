@@ -0,0 +1,204 @@
+use rand::prelude::*;
+use rand_chacha::{ChaCha8Rng, ChaCha20Rng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use std::str::FromStr;
+
+// Random number generator backing a run.
+//
+// `StdRng` is the historical default, but its algorithm may change between
+// `rand` releases. The ChaCha generators are portable: for the same seed and
+// the same `rand`/`rand_chacha` major versions they produce the same stream on
+// every platform, so images published with `--rng chacha8` or
+// `--rng chacha20` stay reproducible. `tests/engine/golden.rs` pins their output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RngKind {
+    #[default]
+    Std,
+    ChaCha8,
+    ChaCha20,
+    Xoshiro,
+}
+
+impl FromStr for RngKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        [
+            RngKind::Std,
+            RngKind::ChaCha8,
+            RngKind::ChaCha20,
+            RngKind::Xoshiro,
+        ]
+        .into_iter()
+        .find(|kind| kind.name() == s)
+        .ok_or(format!("unknown rng {s}"))
+    }
+}
+
+impl RngKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            RngKind::Std => "std",
+            RngKind::ChaCha8 => "chacha8",
+            RngKind::ChaCha20 => "chacha20",
+            RngKind::Xoshiro => "xoshiro256pp",
+        }
+    }
+}
+
+// What a random draw is used for. With separate streams, changing how many
+// draws one concern makes leaves the others untouched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Color,
+    Jitter,
+    Locations,
+    Layout,
+    Lookback,
+    Skip,
+}
+
+const NUM_STREAMS: usize = 6;
+
+impl RngKind {
+    pub fn seeded(self, seed: u64) -> SeededRng {
+        SeededRng {
+            kind: self,
+            seed,
+            used: 0,
+            rng: match self {
+                RngKind::Std => Box::new(StdRng::seed_from_u64(seed)),
+                RngKind::ChaCha8 => Box::new(ChaCha8Rng::seed_from_u64(seed)),
+                RngKind::ChaCha20 => Box::new(ChaCha20Rng::seed_from_u64(seed)),
+                RngKind::Xoshiro => Box::new(Xoshiro256PlusPlus::seed_from_u64(seed)),
+            },
+        }
+    }
+
+    // Draws a next_u32, next_u64 or fill_bytes of n bytes uses up: 32-bit
+    // words for the block generators, steps for xoshiro.
+    fn draws(self, u32s: u64, u64s: u64, bytes: usize) -> u64 {
+        match self {
+            RngKind::Xoshiro => u32s + u64s + bytes.div_ceil(8) as u64,
+            _ => u32s + 2 * u64s + bytes.div_ceil(4) as u64,
+        }
+    }
+}
+
+// A seeded generator that counts its draws, so a scene can wind a fresh
+// one forward to the same point.
+pub struct SeededRng {
+    kind: RngKind,
+    seed: u64,
+    used: u64,
+    rng: Box<dyn RngCore + Send>,
+}
+
+impl SeededRng {
+    // The kind, seed and draws so far.
+    pub fn state(&self) -> (RngKind, u64, u64) {
+        (self.kind, self.seed, self.used)
+    }
+
+    // The generator of that kind and seed after used draws.
+    pub fn resumed(kind: RngKind, seed: u64, used: u64) -> Self {
+        let rng: Box<dyn RngCore + Send> = match kind {
+            RngKind::ChaCha8 => {
+                let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                rng.set_word_pos(used as u128);
+                Box::new(rng)
+            }
+            RngKind::ChaCha20 => {
+                let mut rng = ChaCha20Rng::seed_from_u64(seed);
+                rng.set_word_pos(used as u128);
+                Box::new(rng)
+            }
+            _ => {
+                let mut rng = kind.seeded(seed).rng;
+                for _ in 0..used {
+                    match kind {
+                        RngKind::Xoshiro => rng.next_u64(),
+                        _ => rng.next_u32() as u64,
+                    };
+                }
+                rng
+            }
+        };
+        Self {
+            kind,
+            seed,
+            used,
+            rng,
+        }
+    }
+}
+
+impl RngCore for SeededRng {
+    fn next_u32(&mut self) -> u32 {
+        self.used += self.kind.draws(1, 0, 0);
+        self.rng.next_u32()
+    }
+    fn next_u64(&mut self) -> u64 {
+        self.used += self.kind.draws(0, 1, 0);
+        self.rng.next_u64()
+    }
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.used += self.kind.draws(0, 0, dest.len());
+        self.rng.fill_bytes(dest)
+    }
+}
+
+pub struct Rngs {
+    rngs: Vec<SeededRng>,
+}
+
+impl Rngs {
+    // Every concern draws from one generator, as in the original algorithm.
+    pub fn single(kind: RngKind, seed: u64) -> Self {
+        Self {
+            rngs: vec![kind.seeded(seed)],
+        }
+    }
+    // Each concern gets its own generator, derived from the seed.
+    pub fn split(kind: RngKind, seed: u64) -> Self {
+        Self {
+            rngs: (0..NUM_STREAMS as u64)
+                .map(|stream| kind.seeded(splitmix64(seed ^ splitmix64(stream + 1))))
+                .collect(),
+        }
+    }
+    pub fn get(&mut self, stream: Stream) -> &mut SeededRng {
+        let index = if self.rngs.len() == 1 {
+            0
+        } else {
+            stream as usize
+        };
+        &mut self.rngs[index]
+    }
+    // The state of each generator, for scenes.
+    pub fn states(&self) -> Vec<(RngKind, u64, u64)> {
+        self.rngs.iter().map(SeededRng::state).collect()
+    }
+    pub fn resumed(states: &[(RngKind, u64, u64)]) -> Self {
+        Self {
+            rngs: states
+                .iter()
+                .map(|&(kind, seed, used)| SeededRng::resumed(kind, seed, used))
+                .collect(),
+        }
+    }
+}
+
+pub fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+// FNV-1a, so text and file seeds stay stable across Rust versions.
+pub fn seed_from_bytes(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
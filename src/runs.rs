@@ -0,0 +1,262 @@
+#[cfg(feature = "files")]
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+
+#[cfg(feature = "files")]
+use crate::hook::Event;
+
+pub const MAGIC: &[u8; 4] = b"SPRN";
+
+// The longest side of the thumbnail kept with each run; smaller outputs
+// are kept whole.
+pub const THUMBNAIL: u32 = 64;
+
+// A finished run, as kept in a run database: a file of records, each a
+// 4-byte length and then a text header, with one `key value` line per
+// field and one `arg` line per argument, a blank line and the thumbnail's
+// PNG bytes, if the output could be read back as an image. Text values
+// escape backslashes, newlines and carriage returns with backslashes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunRecord {
+    // Counting from 1, in the order the runs finished.
+    pub id: u64,
+    // Seconds since the Unix epoch.
+    pub time: u64,
+    pub args: Vec<String>,
+    pub seed: u64,
+    pub seconds: f64,
+    pub placed: usize,
+    pub total: usize,
+    pub coverage: f64,
+    pub output: String,
+    pub thumbnail: Vec<u8>,
+}
+
+impl RunRecord {
+    #[cfg(feature = "files")]
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut header = format!(
+            "id {}\ntime {}\nseed {}\nseconds {}\nplaced {}\ntotal {}\ncoverage {}\noutput {}\n",
+            self.id,
+            self.time,
+            self.seed,
+            self.seconds,
+            self.placed,
+            self.total,
+            self.coverage,
+            escape(&self.output)
+        );
+        for arg in &self.args {
+            header += &format!("arg {}\n", escape(arg));
+        }
+        header += "\n";
+        let mut bytes = header.into_bytes();
+        bytes.extend(&self.thumbnail);
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let end = bytes
+            .windows(2)
+            .position(|w| w == b"\n\n")
+            .ok_or("a run has no header")?;
+        let header =
+            std::str::from_utf8(&bytes[..end]).map_err(|_| "a run holds bad text".to_string())?;
+        let mut record = RunRecord {
+            thumbnail: bytes[end + 2..].to_vec(),
+            ..Default::default()
+        };
+        for line in header.lines() {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            let number = || format!("a run holds a bad {key}: {value}");
+            match key {
+                "id" => record.id = value.parse().map_err(|_| number())?,
+                "time" => record.time = value.parse().map_err(|_| number())?,
+                "seed" => record.seed = value.parse().map_err(|_| number())?,
+                "seconds" => record.seconds = value.parse().map_err(|_| number())?,
+                "placed" => record.placed = value.parse().map_err(|_| number())?,
+                "total" => record.total = value.parse().map_err(|_| number())?,
+                "coverage" => record.coverage = value.parse().map_err(|_| number())?,
+                "output" => record.output = unescape(value),
+                "arg" => record.args.push(unescape(value)),
+                // Fields from newer builds are skipped.
+                _ => {}
+            }
+        }
+        Ok(record)
+    }
+
+    // The value of the last flag among the arguments, as the run read it.
+    pub fn flag(&self, flag: &str) -> Option<&str> {
+        let at = self.args.iter().rposition(|a| a == flag)?;
+        Some(self.args.get(at + 1).map_or("", String::as_str))
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{:>4}  {}  {:>7.1}s  {:>5.1}%  {}  {}",
+            self.id,
+            timestamp(self.time),
+            self.seconds,
+            self.coverage * 100.0,
+            self.output,
+            self.args.join(" ")
+        )
+    }
+
+    pub fn details(&self) -> String {
+        [
+            format!("id: {}", self.id),
+            format!("finished: {}", timestamp(self.time)),
+            format!("output: {}", self.output),
+            format!("seed: {}", self.seed),
+            format!("seconds: {:.3}", self.seconds),
+            format!("placed: {} of {}", self.placed, self.total),
+            format!("coverage: {:.2}%", self.coverage * 100.0),
+            format!("args: {}", self.args.join(" ")),
+            format!("thumbnail: {} bytes", self.thumbnail.len()),
+        ]
+        .join("\n")
+    }
+}
+
+#[cfg(feature = "files")]
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+// Seconds since the epoch as a UTC date and time.
+pub fn timestamp(time: u64) -> String {
+    let (days, seconds) = (time / 86400, time % 86400);
+    format!(
+        "{} {:02}:{:02}:{:02}",
+        crate::daily::date_from_days(days as i64),
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+pub fn parse_runs(bytes: &[u8]) -> Result<Vec<RunRecord>, String> {
+    if bytes.is_empty() {
+        return Ok(vec![]);
+    }
+    if bytes.get(..4) != Some(MAGIC) {
+        return Err("not a run database".to_string());
+    }
+    let mut runs = vec![];
+    let mut rest = &bytes[4..];
+    while !rest.is_empty() {
+        let length = rest
+            .get(..4)
+            .map(|n| u32::from_le_bytes(n.try_into().expect("4 bytes")) as usize)
+            .filter(|&n| n <= rest.len() - 4)
+            .ok_or("the run database ends early")?;
+        runs.push(RunRecord::from_bytes(&rest[4..4 + length])?);
+        rest = &rest[4 + length..];
+    }
+    Ok(runs)
+}
+
+// Reads the database under a shared lock, so a run being added is read
+// whole or not at all.
+#[cfg(feature = "files")]
+pub fn load_runs(path: &str) -> Result<Vec<RunRecord>, String> {
+    let mut file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(format!("could not read {path}: {e}")),
+    };
+    file.lock_shared()
+        .map_err(|e| format!("could not lock {path}: {e}"))?;
+    let mut bytes = vec![];
+    file.read_to_end(&mut bytes)
+        .map_err(|e| format!("could not read {path}: {e}"))?;
+    parse_runs(&bytes).map_err(|e| format!("{path}: {e}"))
+}
+
+// Adds record to the database at path, creating it if need be, under the
+// next id, which is returned. The file is locked from reading the last id to
+// writing the record, so runs finishing at once, like the jobs of a batch,
+// each get their own id.
+#[cfg(feature = "files")]
+pub fn append_run(path: &str, mut record: RunRecord) -> Result<u64, String> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .read(true)
+        .write(true)
+        .open(path)
+        .map_err(|e| format!("could not open {path}: {e}"))?;
+    file.lock()
+        .map_err(|e| format!("could not lock {path}: {e}"))?;
+    let mut existing = vec![];
+    file.read_to_end(&mut existing)
+        .map_err(|e| format!("could not read {path}: {e}"))?;
+    let runs = parse_runs(&existing).map_err(|e| format!("{path}: {e}"))?;
+    record.id = runs.last().map_or(0, |r| r.id) + 1;
+    let body = record.to_bytes();
+    let mut bytes = vec![];
+    if existing.is_empty() {
+        bytes.extend(MAGIC);
+    }
+    bytes.extend((body.len() as u32).to_le_bytes());
+    bytes.extend(body);
+    file.seek(SeekFrom::End(0))
+        .and_then(|_| file.write_all(&bytes))
+        .map_err(|e| format!("could not write {path}: {e}"))?;
+    Ok(record.id)
+}
+
+// Records a finished run, with a thumbnail of its output when that is an
+// image.
+#[cfg(feature = "files")]
+pub fn record_run(path: &str, event: &Event) -> Result<u64, String> {
+    let time = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let mut thumbnail = vec![];
+    if let Ok(mut image) = image::open(event.output) {
+        if image.width().max(image.height()) > THUMBNAIL {
+            image = image.thumbnail(THUMBNAIL, THUMBNAIL);
+        }
+        image
+            .to_rgba8()
+            .write_to(&mut Cursor::new(&mut thumbnail), image::ImageFormat::Png)
+            .map_err(|e| format!("could not encode a thumbnail: {e}"))?;
+    }
+    append_run(
+        path,
+        RunRecord {
+            id: 0,
+            time,
+            args: event.args.to_vec(),
+            seed: event.seed,
+            seconds: event.seconds,
+            placed: event.placed,
+            total: event.total,
+            coverage: event.coverage,
+            output: event.output.to_string(),
+            thumbnail,
+        },
+    )
+}
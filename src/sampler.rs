@@ -0,0 +1,266 @@
+use rand::prelude::*;
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::Location;
+use crate::canvas::Canvas;
+use crate::scene::{SceneReader, SceneWriter};
+
+// How random insertions pick among the open locations.
+// Written as `uniform`, `center`, `edges`, `sparse` or `map:PATH`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Sampling {
+    // The original: every open location equally likely.
+    #[default]
+    Uniform,
+    // Favor locations near the middle of the canvas.
+    Center,
+    // Favor locations near the border.
+    Edges,
+    // Favor areas with few placed pixels so far.
+    Sparse,
+    // Brighter areas of a grayscale image are favored.
+    Map(Arc<DensityMap>),
+}
+
+impl FromStr for Sampling {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.split_once(':') {
+            Some(("map", path)) => Ok(Sampling::Map(Arc::new(DensityMap::load(path)?))),
+            _ => match s {
+                "uniform" => Ok(Sampling::Uniform),
+                "center" => Ok(Sampling::Center),
+                "edges" => Ok(Sampling::Edges),
+                "sparse" => Ok(Sampling::Sparse),
+                _ => Err(format!("unknown sampling {s}")),
+            },
+        }
+    }
+}
+
+// Grayscale image stretched over the canvas, as values in 0..=1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DensityMap {
+    width: usize,
+    height: usize,
+    values: Vec<f64>,
+}
+
+impl DensityMap {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let map = image::open(path)
+            .map_err(|e| format!("could not open {path}: {e}"))?
+            .to_luma8();
+        Ok(Self {
+            width: map.width() as usize,
+            height: map.height() as usize,
+            values: map.pixels().map(|p| p.0[0] as f64 / 255.0).collect(),
+        })
+    }
+
+    pub fn at(&self, loc: Location, size: usize) -> f64 {
+        let x = (loc[0] * self.width / size).min(self.width - 1);
+        let y = (loc[1] * self.height / size).min(self.height - 1);
+        self.values[y * self.width + x]
+    }
+}
+
+// Keeps every location reachable, however small its weight.
+const FLOOR: f64 = 0.001;
+
+const BLOCK: usize = 8;
+
+// Weighted choice among open locations. Locations are grouped into square
+// blocks; a Fenwick tree over block weights picks a block, then a scan picks
+// a location within it. Sparse sampling scales each block's weight by how
+// empty it still is.
+pub struct Sampler {
+    size: usize,
+    blocks_across: usize,
+    sparse: bool,
+    // Weight of each location, zero once filled.
+    weights: Vec<f64>,
+    block_sums: Vec<f64>,
+    block_open: Vec<usize>,
+    block_area: Vec<usize>,
+    tree: Fenwick,
+}
+
+impl Sampler {
+    // None for uniform sampling, which stays with VecMap.
+    pub fn new(sampling: &Sampling, canvas: Canvas, size: usize) -> Option<Self> {
+        let half = size as f64 / 2.0;
+        let max_dist = half * 2f64.sqrt();
+        let dist = |loc: Location| {
+            let dx = loc[0] as f64 + 0.5 - half;
+            let dy = loc[1] as f64 + 0.5 - half;
+            (dx * dx + dy * dy).sqrt() / max_dist
+        };
+        let weight: Box<dyn Fn(Location) -> f64> = match sampling {
+            Sampling::Uniform => return None,
+            Sampling::Center => Box::new(|loc| (1.0 - dist(loc)).powi(2)),
+            Sampling::Edges => Box::new(|loc| dist(loc).powi(2)),
+            Sampling::Sparse => Box::new(|_| 1.0),
+            Sampling::Map(map) => Box::new(|loc| map.at(loc, size)),
+        };
+        let blocks_across = size.div_ceil(BLOCK);
+        let num_blocks = blocks_across * blocks_across;
+        let mut sampler = Self {
+            size,
+            blocks_across,
+            sparse: *sampling == Sampling::Sparse,
+            weights: vec![0.0; size * size],
+            block_sums: vec![0.0; num_blocks],
+            block_open: vec![0; num_blocks],
+            block_area: vec![0; num_blocks],
+            tree: Fenwick::new(num_blocks),
+        };
+        for loc in canvas.locations(size) {
+            let w = weight(loc).max(0.0) + FLOOR;
+            let block = sampler.block(loc);
+            sampler.weights[loc[1] * size + loc[0]] = w;
+            sampler.block_sums[block] += w;
+            sampler.block_open[block] += 1;
+            sampler.block_area[block] += 1;
+        }
+        for block in 0..num_blocks {
+            sampler.tree.add(block, sampler.block_weight(block));
+        }
+        Some(sampler)
+    }
+
+    fn block(&self, loc: Location) -> usize {
+        loc[1] / BLOCK * self.blocks_across + loc[0] / BLOCK
+    }
+
+    fn block_weight(&self, block: usize) -> f64 {
+        if self.block_open[block] == 0 {
+            return 0.0;
+        }
+        let sum = self.block_sums[block].max(0.0);
+        if self.sparse {
+            let open = self.block_open[block] as f64 / self.block_area[block] as f64;
+            sum * open.powi(3)
+        } else {
+            sum
+        }
+    }
+
+    // Marks loc as filled.
+    pub fn remove(&mut self, loc: Location) {
+        let index = loc[1] * self.size + loc[0];
+        let w = self.weights[index];
+        if w == 0.0 {
+            return;
+        }
+        let block = self.block(loc);
+        let before = self.block_weight(block);
+        self.weights[index] = 0.0;
+        self.block_sums[block] -= w;
+        self.block_open[block] -= 1;
+        self.tree.add(block, self.block_weight(block) - before);
+    }
+
+    // The weights left, exactly, for scenes.
+    pub fn write_scene(&self, w: &mut SceneWriter) {
+        for values in [&self.weights, &self.block_sums, &self.tree.tree] {
+            values.iter().for_each(|&v| w.f64(v));
+        }
+        self.block_open.iter().for_each(|&n| w.usize(n));
+    }
+
+    pub fn read_scene(&mut self, r: &mut SceneReader) -> Result<(), String> {
+        for values in [&mut self.weights, &mut self.block_sums, &mut self.tree.tree] {
+            for v in values.iter_mut() {
+                *v = r.f64()?;
+            }
+        }
+        for n in &mut self.block_open {
+            *n = r.usize()?;
+        }
+        Ok(())
+    }
+
+    // Draws an open location without removing it.
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Option<Location> {
+        let total = self.tree.total();
+        let block = if total > 0.0 {
+            self.tree.find(rng.random_range(0.0..total))
+        } else {
+            None
+        };
+        // Rounding can leave a closed block selected; fall back to any open one.
+        let block = block
+            .filter(|&b| self.block_open[b] > 0)
+            .or_else(|| (0..self.block_open.len()).find(|&b| self.block_open[b] > 0))?;
+        let (bx, by) = (
+            block % self.blocks_across * BLOCK,
+            block / self.blocks_across * BLOCK,
+        );
+        let cells: Vec<Location> = (by..(by + BLOCK).min(self.size))
+            .flat_map(|y| (bx..(bx + BLOCK).min(self.size)).map(move |x| [x, y]))
+            .filter(|loc| self.weights[loc[1] * self.size + loc[0]] > 0.0)
+            .collect();
+        let sum: f64 = cells
+            .iter()
+            .map(|loc| self.weights[loc[1] * self.size + loc[0]])
+            .sum();
+        let mut target = rng.random_range(0.0..sum);
+        let last = *cells.last().expect("open block");
+        Some(
+            cells
+                .into_iter()
+                .find(|loc| {
+                    target -= self.weights[loc[1] * self.size + loc[0]];
+                    target < 0.0
+                })
+                .unwrap_or(last),
+        )
+    }
+}
+
+// Prefix sums of f64 weights with O(log n) update and search.
+struct Fenwick {
+    tree: Vec<f64>,
+}
+
+impl Fenwick {
+    fn new(len: usize) -> Self {
+        Self {
+            tree: vec![0.0; len + 1],
+        }
+    }
+    fn add(&mut self, index: usize, delta: f64) {
+        let mut i = index + 1;
+        while i < self.tree.len() {
+            self.tree[i] += delta;
+            i += i & i.wrapping_neg();
+        }
+    }
+    fn total(&self) -> f64 {
+        let mut i = self.tree.len() - 1;
+        let mut sum = 0.0;
+        while i > 0 {
+            sum += self.tree[i];
+            i -= i & i.wrapping_neg();
+        }
+        sum
+    }
+    // The index whose prefix range contains target.
+    fn find(&self, mut target: f64) -> Option<usize> {
+        let len = self.tree.len() - 1;
+        let mut pos = 0;
+        let mut step = len.next_power_of_two();
+        while step > 0 {
+            let next = pos + step;
+            if next <= len && self.tree[next] <= target {
+                target -= self.tree[next];
+                pos = next;
+            }
+            step /= 2;
+        }
+        (pos < len).then_some(pos)
+    }
+}
@@ -0,0 +1,275 @@
+use crate::generator::Generator;
+use crate::params::{parse, parse_list};
+use crate::{Color, Location, Params, Pixel};
+
+// A scene is a run paused partway: the arguments it started with and the
+// whole state of its generator, so it can be finished on another machine or
+// branched into several. It starts with MAGIC and the version as a
+// little-endian u32, then the arguments, then the generator's state as laid
+// out by Generator::write_scene. Numbers are little-endian u64s and f64s,
+// colors three bytes. Readers branch on the version for anything added
+// since version 1; scenes from a newer version are refused. Version 2 added
+// each center's tilt and stretch, and version 3 its ring width.
+pub const MAGIC: &[u8; 4] = b"SPSC";
+pub const VERSION: u32 = 3;
+
+// Flags that stopped or saved the run, left out of its scene so a resumed
+// run goes on to the end unless told otherwise. Each takes one value.
+const STOPPING_FLAGS: [&str; 4] = [
+    "--coverage",
+    "--max-pixels",
+    "--max-seconds",
+    "--save-scene",
+];
+
+#[derive(Debug, Default)]
+pub struct SceneWriter {
+    bytes: Vec<u8>,
+}
+
+impl SceneWriter {
+    pub fn u8(&mut self, v: u8) {
+        self.bytes.push(v);
+    }
+    pub fn u32(&mut self, v: u32) {
+        self.bytes.extend(v.to_le_bytes());
+    }
+    pub fn u64(&mut self, v: u64) {
+        self.bytes.extend(v.to_le_bytes());
+    }
+    pub fn usize(&mut self, v: usize) {
+        self.u64(v as u64);
+    }
+    pub fn f64(&mut self, v: f64) {
+        self.bytes.extend(v.to_le_bytes());
+    }
+    pub fn bool(&mut self, v: bool) {
+        self.u8(v as u8);
+    }
+    pub fn str(&mut self, s: &str) {
+        self.usize(s.len());
+        self.bytes.extend(s.as_bytes());
+    }
+    pub fn color(&mut self, color: Color) {
+        self.bytes.extend(color);
+    }
+    pub fn location(&mut self, loc: Location) {
+        loc.into_iter().for_each(|c| self.usize(c));
+    }
+    pub fn pixel(&mut self, pixel: &Pixel) {
+        self.color(pixel.color);
+        self.location(pixel.loc);
+        self.location(pixel.center);
+        self.usize(pixel.center_id);
+    }
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+#[derive(Debug)]
+pub struct SceneReader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+    // The version of the scene being read.
+    pub version: u32,
+}
+
+impl<'a> SceneReader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .at
+            .checked_add(n)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or("the scene ends early")?;
+        let taken = &self.bytes[self.at..end];
+        self.at = end;
+        Ok(taken)
+    }
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.take(N)?.try_into().expect("took N bytes"))
+    }
+    pub fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+    pub fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+    pub fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+    pub fn usize(&mut self) -> Result<usize, String> {
+        usize::try_from(self.u64()?).map_err(|_| "the scene holds a number too large".to_string())
+    }
+    pub fn f64(&mut self) -> Result<f64, String> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+    pub fn bool(&mut self) -> Result<bool, String> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            b => Err(format!("the scene holds {b} where a flag belongs")),
+        }
+    }
+    // A count of the items that follow, each at least one byte.
+    pub fn count(&mut self) -> Result<usize, String> {
+        let n = self.usize()?;
+        if n > self.bytes.len() - self.at {
+            return Err("the scene ends early".to_string());
+        }
+        Ok(n)
+    }
+    pub fn str(&mut self) -> Result<String, String> {
+        let n = self.count()?;
+        String::from_utf8(self.take(n)?.to_vec())
+            .map_err(|_| "the scene holds bad text".to_string())
+    }
+    pub fn color(&mut self) -> Result<Color, String> {
+        self.array()
+    }
+    pub fn location(&mut self) -> Result<Location, String> {
+        Ok([self.usize()?, self.usize()?])
+    }
+    pub fn pixel(&mut self) -> Result<Pixel, String> {
+        Ok(Pixel {
+            color: self.color()?,
+            loc: self.location()?,
+            center: self.location()?,
+            center_id: self.usize()?,
+        })
+    }
+    // Whether an optional part follows, which must agree with whether the
+    // resumed parameters have it.
+    pub fn present(&mut self, expected: bool, flag: &str) -> Result<bool, String> {
+        let present = self.bool()?;
+        if present != expected {
+            return Err(format!("resuming cannot change {flag}"));
+        }
+        Ok(present)
+    }
+}
+
+// A scene read back, with its generator state still to be restored.
+#[derive(Debug, Clone)]
+pub struct Scene {
+    pub args: Vec<String>,
+    version: u32,
+    bytes: Vec<u8>,
+    // Where the generator's state starts.
+    state: usize,
+}
+
+impl Scene {
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, String> {
+        if bytes.get(..4) != Some(MAGIC) {
+            return Err("not a scene".to_string());
+        }
+        let mut reader = SceneReader {
+            bytes: &bytes,
+            at: 4,
+            version: 0,
+        };
+        let version = reader.u32()?;
+        if version == 0 || version > VERSION {
+            return Err(format!(
+                "scene version {version} is newer than this build reads ({VERSION})"
+            ));
+        }
+        let args = (0..reader.count()?)
+            .map(|_| reader.str())
+            .collect::<Result<_, _>>()?;
+        let state = reader.at;
+        Ok(Self {
+            args,
+            version,
+            bytes,
+            state,
+        })
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("could not read {path}: {e}"))?;
+        Self::from_bytes(bytes).map_err(|e| format!("{path}: {e}"))
+    }
+
+    // The scene's arguments followed by extra, which override them.
+    pub fn args_with(&self, extra: &[String]) -> Vec<String> {
+        self.args.iter().chain(extra).cloned().collect()
+    }
+
+    // The paused generator, under params parsed from args_with. With reseed,
+    // its random streams start over from the parameters' seed, so branches
+    // from one scene differ.
+    pub fn generator(
+        &self,
+        params: &Params,
+        record: bool,
+        reseed: bool,
+    ) -> Result<Generator, String> {
+        if params.voxels || params.big.is_some() {
+            return Err("scenes cannot be combined with --voxels or --big".to_string());
+        }
+        let mut generator = Generator::new(params, record);
+        let mut reader = SceneReader {
+            bytes: &self.bytes,
+            at: self.state,
+            version: self.version,
+        };
+        generator.read_scene(&mut reader, reseed)?;
+        if reader.at != self.bytes.len() {
+            return Err("the scene has bytes left over".to_string());
+        }
+        Ok(generator)
+    }
+
+    // The scene continued with seed and extra flags: one branch of a family
+    // that shares the image so far.
+    pub fn branch(&self, seed: u64, extra: &[String]) -> Result<(Params, Generator), String> {
+        let mut extra = extra.to_vec();
+        extra.extend(["--seed".to_string(), seed.to_string()]);
+        let params = Params::from_args(self.args_with(&extra).into_iter())?;
+        let generator = self.generator(&params, params.needs_placements(), true)?;
+        Ok((params, generator))
+    }
+}
+
+// Seeds given as a half-open range, as in 1..8, or a list, as in 3,5,9.
+pub fn parse_seeds(value: &str) -> Result<Vec<u64>, String> {
+    let seeds: Vec<u64> = match value.split_once("..") {
+        Some((lo, hi)) => (parse("--seeds", lo)?..parse("--seeds", hi)?).collect(),
+        None => parse_list("--seeds", value)?,
+    };
+    if seeds.is_empty() {
+        return Err(format!("--seeds {value} holds no seeds"));
+    }
+    Ok(seeds)
+}
+
+// The generator's run so far as a scene.
+pub fn scene_bytes(generator: &Generator) -> Vec<u8> {
+    let mut writer = SceneWriter::default();
+    writer.bytes.extend(MAGIC);
+    writer.u32(VERSION);
+    let args = scene_args(&generator.params().args);
+    writer.usize(args.len());
+    args.iter().for_each(|arg| writer.str(arg));
+    generator.write_scene(&mut writer);
+    writer.into_bytes()
+}
+
+pub fn save_scene(generator: &Generator, path: &str) -> Result<(), String> {
+    std::fs::write(path, scene_bytes(generator)).map_err(|e| format!("could not write {path}: {e}"))
+}
+
+fn scene_args(args: &[String]) -> Vec<String> {
+    let mut kept = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if STOPPING_FLAGS.contains(&arg.as_str()) {
+            args.next();
+        } else {
+            kept.push(arg.clone());
+        }
+    }
+    kept
+}
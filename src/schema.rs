@@ -0,0 +1,885 @@
+use crate::Params;
+use crate::gallery::json_string;
+use crate::topology::MAX_WALKERS;
+
+// The values a number may take: from min, or just above it, up to max, or
+// just below it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Range {
+    pub min: Option<f64>,
+    pub exclusive_min: bool,
+    pub max: Option<f64>,
+    pub exclusive_max: bool,
+}
+
+const ANY: Range = Range {
+    min: None,
+    exclusive_min: false,
+    max: None,
+    exclusive_max: false,
+};
+
+const fn at_least(min: f64) -> Range {
+    Range {
+        min: Some(min),
+        ..ANY
+    }
+}
+
+const fn above(min: f64) -> Range {
+    Range {
+        exclusive_min: true,
+        ..at_least(min)
+    }
+}
+
+const fn within(min: f64, max: f64) -> Range {
+    Range {
+        max: Some(max),
+        ..at_least(min)
+    }
+}
+
+const fn below(min: f64, max: f64) -> Range {
+    Range {
+        exclusive_max: true,
+        ..within(min, max)
+    }
+}
+
+impl Range {
+    pub fn contains(&self, value: f64) -> bool {
+        let low = self.min.is_none_or(|min| {
+            if self.exclusive_min {
+                value > min
+            } else {
+                value >= min
+            }
+        });
+        let high = self.max.is_none_or(|max| {
+            if self.exclusive_max {
+                value < max
+            } else {
+                value <= max
+            }
+        });
+        low && high && value.is_finite()
+    }
+
+    fn describe(&self) -> String {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) if self.exclusive_max => format!("in {min}..{max}"),
+            (Some(min), Some(max)) => format!("in {min}..={max}"),
+            (Some(min), None) if self.exclusive_min => format!("above {min}"),
+            (Some(min), None) => format!("at least {min}"),
+            _ => "a finite number".to_string(),
+        }
+    }
+}
+
+// What a flag takes.
+#[derive(Debug, Clone, Copy)]
+pub enum Kind {
+    // A number in range, read back from parsed parameters as the value
+    // they ended up with, or None if unset or not kept as given.
+    Integer(Range, fn(&Params) -> Option<f64>),
+    Number(Range, fn(&Params) -> Option<f64>),
+    // One of a fixed set of words, and the one used if the flag is left out.
+    Choice(&'static [&'static str], Option<&'static str>),
+    // A bare flag, off if left out.
+    Switch,
+    // Text in the form shown, such as LO..HI.
+    Text(&'static str),
+    // The path of a file to read.
+    File,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Field {
+    pub flag: &'static str,
+    pub kind: Kind,
+    pub description: &'static str,
+}
+
+const fn field(flag: &'static str, kind: Kind, description: &'static str) -> Field {
+    Field {
+        flag,
+        kind,
+        description,
+    }
+}
+
+// The generation parameters, in the order the README introduces them:
+// every flag but those in OUTPUTS.
+pub static FIELDS: &[Field] = &[
+    field(
+        "--size",
+        Kind::Integer(at_least(1.0), |p| Some(p.size as f64)),
+        "Width and height of the image in pixels.",
+    ),
+    field(
+        "--fast-profile",
+        Kind::Switch,
+        "Scales the run down to a miniature at most 96 pixels across.",
+    ),
+    field(
+        "--progressive",
+        Kind::Text("FILE.png"),
+        "Saves a miniature of the run before rendering it at full size.",
+    ),
+    field(
+        "--progressive-size",
+        Kind::Integer(at_least(1.0), |p| Some(p.progressive_size as f64)),
+        "The side of the --progressive miniature, 96 by default.",
+    ),
+    field(
+        "--centers",
+        Kind::Integer(at_least(0.0), |p| Some(p.num_centers as f64)),
+        "Centers started before the first color is matched.",
+    ),
+    field(
+        "--lookback",
+        Kind::Integer(at_least(0.0), |p| Some(p.num_lookback as f64)),
+        "Recent pixels each new color is matched against.",
+    ),
+    field(
+        "--start-spread",
+        Kind::Number(at_least(0.0), |p| Some(p.start_spread)),
+        "How far from its first pixel a new center may start, as a share of the size.",
+    ),
+    field(
+        "--cont-spread",
+        Kind::Number(at_least(0.0), |p| Some(p.cont_spread)),
+        "How far a continuing pixel's center is jittered, per unit of color distance.",
+    ),
+    field(
+        "--region-grid",
+        Kind::Text("COLSxROWS"),
+        "Splits the canvas into regions with parameters of their own.",
+    ),
+    field(
+        "--region-cont-spread",
+        Kind::Text("S,S,..."),
+        "Each region's continuation spread, row-major, or one for all.",
+    ),
+    field(
+        "--region-lookback",
+        Kind::Text("N,N,..."),
+        "Each region's lookback size, row-major, or one for all.",
+    ),
+    field(
+        "--region-aspect",
+        Kind::Text("A,A,..."),
+        "Each region's orbit aspect ratio, row-major, or one for all.",
+    ),
+    field(
+        "--param-map",
+        Kind::File,
+        "An image whose pixels are regions, scaling the spreads, lookback and aspect.",
+    ),
+    field(
+        "--seed",
+        Kind::Integer(at_least(0.0), |p| Some(p.seed as f64)),
+        "Seed of every random choice.",
+    ),
+    field(
+        "--seed-text",
+        Kind::Text("TEXT"),
+        "A seed derived from text.",
+    ),
+    field(
+        "--seed-file",
+        Kind::File,
+        "Seeds the run from the hash of a file's contents.",
+    ),
+    field(
+        "--algorithm",
+        Kind::Text("spinning, continuous, frontier or dla:STICKINESS:BIAS"),
+        "How each color finds its cell.",
+    ),
+    field(
+        "--orbit",
+        Kind::Text("circle or epicycle[:RATIO[:SPEED]]"),
+        "The path walks follow around their center.",
+    ),
+    field(
+        "--phase",
+        Kind::Text("hue:TURNS or epicycle:TURNS"),
+        "A cyclic offset, for looping animations.",
+    ),
+    field(
+        "--spiral",
+        Kind::Text("out:RATE or in:RATE"),
+        "Change in orbit radius per walk step, in pixels.",
+    ),
+    field(
+        "--arms",
+        Kind::Text("N[:WIDTH[:TWIST]]"),
+        "Sectors around each center that walks may place in.",
+    ),
+    field(
+        "--skip-prob",
+        Kind::Number(below(0.0, 1.0), |p| Some(p.skip_prob)),
+        "Chance that a walk passes over an open cell.",
+    ),
+    field(
+        "--skip-dashes",
+        Kind::Text("DASH:GAP"),
+        "Open cells walks pass over in a regular pattern.",
+    ),
+    field(
+        "--neighborhood",
+        Kind::Choice(&["8", "4", "knight", "ring2"], Some("8")),
+        "The cells a walk steps to.",
+    ),
+    field(
+        "--gap-jump",
+        Kind::Integer(at_least(0.0), |p| Some(p.gap_jump as f64)),
+        "Filled pixels a walk may leap over for the cost of one step.",
+    ),
+    field(
+        "--reflect",
+        Kind::Switch,
+        "Orbits mirror back off the edges.",
+    ),
+    field(
+        "--canvas",
+        Kind::Choice(&["square", "disc"], Some("square")),
+        "The set of cells that get filled.",
+    ),
+    field(
+        "--cubemap",
+        Kind::Choice(&["faces", "cross"], None),
+        "Renders the six faces of a skybox, as six images or one cross.",
+    ),
+    field(
+        "--sphere",
+        Kind::Switch,
+        "Renders an equirectangular sphere, twice as wide as --size.",
+    ),
+    field(
+        "--voxels",
+        Kind::Switch,
+        "Runs on a cube of voxels, saved with --slices or --nrrd.",
+    ),
+    field(
+        "--big",
+        Kind::Text("SCRATCH"),
+        "Keeps the grid in a memory-mapped scratch file, for prints too large for memory.",
+    ),
+    field(
+        "--panorama",
+        Kind::Text("WIDTHxHEIGHT"),
+        "A wide band whose walks wrap around the left and right edges.",
+    ),
+    field(
+        "--unfilled",
+        Kind::Text("black, transparent or rrggbb"),
+        "What the cells left empty become.",
+    ),
+    field(
+        "--transparent",
+        Kind::Switch,
+        "The same as --unfilled transparent.",
+    ),
+    field(
+        "--coverage",
+        Kind::Number(within(0.0, 1.0), |p| Some(p.coverage)),
+        "Share of the cells filled before the run stops.",
+    ),
+    field(
+        "--max-pixels",
+        Kind::Integer(at_least(0.0), |p| p.max_pixels.map(|n| n as f64)),
+        "Placements after which the run stops.",
+    ),
+    field(
+        "--max-seconds",
+        Kind::Number(at_least(0.0), |p| p.max_seconds),
+        "Seconds after which the run stops.",
+    ),
+    field(
+        "--center-max-pixels",
+        Kind::Integer(at_least(1.0), |p| p.center_max_pixels.map(|n| n as f64)),
+        "Pixels after which a center leaves the lookback.",
+    ),
+    field(
+        "--center-max-age",
+        Kind::Integer(at_least(0.0), |p| p.center_max_age.map(|n| n as f64)),
+        "Placements after which a center leaves the lookback.",
+    ),
+    field(
+        "--center-stretch",
+        Kind::Text("V or LO..HI"),
+        "How many times as long as wide each center's orbits are.",
+    ),
+    field(
+        "--center-tilt",
+        Kind::Text("V or LO..HI"),
+        "The tilt of stretched orbits, in degrees.",
+    ),
+    field(
+        "--ring-width",
+        Kind::Text("V or LO..HI"),
+        "Orbit radii snap to multiples of this many pixels.",
+    ),
+    field(
+        "--center-gap",
+        Kind::Number(above(0.0), |p| p.center_gap),
+        "Least distance between live centers, in pixels.",
+    ),
+    field(
+        "--center-drift",
+        Kind::Text("walk:SPEED, vector:DX,DY or field:SPEED"),
+        "How centers move as their pixels are placed.",
+    ),
+    field(
+        "--merge-centers",
+        Kind::Integer(at_least(1.0), |p| p.merge_centers.map(|n| n as f64)),
+        "Walks ending against another center's pixels before the two merge.",
+    ),
+    field(
+        "--gravity",
+        Kind::Number(ANY, |p| Some(p.gravity)),
+        "Pull of the heaviest centers on continuing pixels' centers.",
+    ),
+    field(
+        "--modulate",
+        Kind::Text("TARGET=PATH[:COLUMN]"),
+        "A parameter following a time series over the run; repeatable.",
+    ),
+    field(
+        "--stencil",
+        Kind::Text("text:WORD or svg:PATH"),
+        "A shape whose outline the initial centers are placed along.",
+    ),
+    field(
+        "--center-layout",
+        Kind::Choice(&["random", "relaxed"], Some("random")),
+        "Where the initial centers start.",
+    ),
+    field(
+        "--relax-iters",
+        Kind::Integer(at_least(0.0), |p| Some(p.relax_iters as f64)),
+        "Lloyd iterations for the relaxed layout.",
+    ),
+    field(
+        "--sampling",
+        Kind::Text("uniform, center, edges, sparse or map:PATH"),
+        "Where new centers' first pixels are drawn.",
+    ),
+    field(
+        "--density",
+        Kind::File,
+        "An image whose bright areas attract new centers.",
+    ),
+    field(
+        "--base",
+        Kind::File,
+        "Artwork whose opaque pixels are kept; sets the size.",
+    ),
+    field(
+        "--inpaint",
+        Kind::File,
+        "Artwork whose holes are filled from the pixels around them.",
+    ),
+    field(
+        "--base-threshold",
+        Kind::Integer(within(0.0, 255.0), |_| None),
+        "Alpha at or above which a base pixel is kept.",
+    ),
+    field(
+        "--key",
+        Kind::Text("rrggbb"),
+        "Also keeps the base pixels of exactly this color.",
+    ),
+    field(
+        "--warm-start",
+        Kind::File,
+        "An image the lookback and first centers are sampled from.",
+    ),
+    field(
+        "--warm-samples",
+        Kind::Integer(at_least(1.0), |p| p.warm_samples.map(|n| n as f64)),
+        "Pixels sampled from the warm start image.",
+    ),
+    field(
+        "--multiscale",
+        Kind::Number(at_least(0.0), |p| Some(p.multiscale)),
+        "Share of the way each color moves toward a quarter-size pass grown first.",
+    ),
+    field(
+        "--eviction",
+        Kind::Choice(&["fifo", "random", "diverse", "spatial"], Some("fifo")),
+        "Which pixel leaves a full lookback.",
+    ),
+    field(
+        "--lookback-pools",
+        Kind::Text("N or N:luma"),
+        "Splits the lookback into pools by hue or brightness.",
+    ),
+    field(
+        "--adaptive-lookback",
+        Kind::Text("open[:FLOOR] or forced[:RATE[:FLOOR]]"),
+        "Searches less of the lookback as the canvas crowds.",
+    ),
+    field(
+        "--fallback",
+        Kind::Text("random, retry[:K], nearest or requeue[:K]"),
+        "What a walk does when it finds no open pixel.",
+    ),
+    field(
+        "--endgame",
+        Kind::Number(within(0.0, 1.0), |p| p.endgame),
+        "Coverage past which colors fill the open cell nearest their match.",
+    ),
+    field(
+        "--refine",
+        Kind::Number(
+            Range {
+                max: Some(1.0),
+                ..above(0.0)
+            },
+            |p| p.refine,
+        ),
+        "Share of random placements past which a block is cleared and placed again.",
+    ),
+    field(
+        "--refine-block",
+        Kind::Integer(at_least(2.0), |p| Some(p.refine_block as f64)),
+        "Side of the blocks --refine checks.",
+    ),
+    field(
+        "--spatial-weight",
+        Kind::Number(at_least(0.0), |p| Some(p.spatial_weight)),
+        "Weight of the distance from the last pixel in matching.",
+    ),
+    field(
+        "--max-match-distance",
+        Kind::Number(at_least(0.0), |p| p.max_match_distance),
+        "Color distance beyond which a color starts a new center.",
+    ),
+    field(
+        "--rng",
+        Kind::Choice(&["std", "chacha8", "chacha20", "xoshiro256pp"], Some("std")),
+        "The random number generator.",
+    ),
+    field(
+        "--streams",
+        Kind::Switch,
+        "Separate random streams for colors, layout and walks.",
+    ),
+    field(
+        "--concurrent-walkers",
+        Kind::Integer(within(1.0, MAX_WALKERS as f64), |p| {
+            p.concurrent_walkers.map(|n| n as f64)
+        }),
+        "Walkers placing pixels at once, each with its own colors and lookback.",
+    ),
+    field(
+        "--mode",
+        Kind::Text("rgb, grayscale or duotone RRGGBB RRGGBB"),
+        "Where the colors are drawn from.",
+    ),
+    field(
+        "--palette",
+        Kind::Text("NAME or RRGGBB,..."),
+        "Draws the colors from a named or listed palette.",
+    ),
+    field(
+        "--colorblind-safe",
+        Kind::Switch,
+        "Keeps the colors to a palette colorblind viewers can tell apart.",
+    ),
+    field(
+        "--palette-file",
+        Kind::File,
+        "Palettes to draw the colors from, one per line.",
+    ),
+    field(
+        "--seed-colors",
+        Kind::Text("spread, a palette name or rrggbb,rrggbb,..."),
+        "Colors the first centers' pixels with these colors, or ones spread far apart.",
+    ),
+    field(
+        "--center-palettes",
+        Kind::Switch,
+        "Gives each center a palette of its own.",
+    ),
+    field(
+        "--center-palette-size",
+        Kind::Integer(at_least(1.0), |_| None),
+        "Colors in each center's random palette.",
+    ),
+    field(
+        "--palette-drift",
+        Kind::Integer(within(0.0, 255.0), |p| Some(p.palette_drift as f64)),
+        "How far palette entries wander per channel each time they are used.",
+    ),
+    field(
+        "--palette-jitter",
+        Kind::Integer(within(0.0, 255.0), |_| None),
+        "Per-channel noise added to every drawn color.",
+    ),
+    field(
+        "--time-gradient",
+        Kind::Text("NAME or RRGGBB,..."),
+        "Colors each placement by how far the run has progressed.",
+    ),
+    field(
+        "--noise-colors",
+        Kind::Number(above(0.0), |_| None),
+        "Draws colors from noise over placement time, at this scale.",
+    ),
+    field(
+        "--harmony",
+        Kind::Choice(
+            &[
+                "monochromatic",
+                "analogous",
+                "complementary",
+                "split-complementary",
+                "triadic",
+                "tetradic",
+            ],
+            None,
+        ),
+        "Draws colors around the hues of a color wheel rule.",
+    ),
+    field(
+        "--base-hue",
+        Kind::Number(ANY, |_| None),
+        "Base hue of --harmony, in degrees; follows the seed if left out.",
+    ),
+    field(
+        "--color-sequence",
+        Kind::File,
+        "Colors to place in order, one per line of a .csv or as raw RGB bytes.",
+    ),
+    field(
+        "--inks",
+        Kind::Text("RRGGBB:SHARE,..."),
+        "A few colors, each with a budget.",
+    ),
+    field(
+        "--curve",
+        Kind::Text("hilbert or morton[:lch]"),
+        "Draws the colors in order along a curve through color space.",
+    ),
+    field(
+        "--curve-step",
+        Kind::Integer(at_least(1.0), |_| None),
+        "Curve cells advanced per color.",
+    ),
+    field(
+        "--curve-offset",
+        Kind::Number(below(0.0, 1.0), |_| None),
+        "Where along the curve the colors start.",
+    ),
+    field(
+        "--color-jitter",
+        Kind::Number(at_least(0.0), |p| Some(p.color_jitter)),
+        "Standard deviation of noise added to each placed pixel.",
+    ),
+    field(
+        "--post",
+        Kind::Text("STEP,STEP,..."),
+        "Steps applied to the finished image, in order.",
+    ),
+    field(
+        "--post-file",
+        Kind::File,
+        "Post steps read from a file, one per line.",
+    ),
+    field(
+        "--smear",
+        Kind::Integer(at_least(1.0), |p| p.smear.map(|n| n as f64)),
+        "Pixels the finished image is smeared along the orbits, either way.",
+    ),
+    field(
+        "--smear-strength",
+        Kind::Number(within(0.0, 1.0), |p| Some(p.smear_strength)),
+        "How much of the --smear is mixed over the image.",
+    ),
+    field(
+        "--fade",
+        Kind::Integer(at_least(0.0), |p| Some(p.fade as f64)),
+        "Output pixels at the border faded out.",
+    ),
+    field(
+        "--vignette",
+        Kind::Number(within(0.0, 1.0), |p| Some(p.vignette)),
+        "How far the corners fade out; 1 fades them entirely.",
+    ),
+    field(
+        "--fade-to",
+        Kind::Text("black, transparent or rrggbb"),
+        "What --fade and --vignette fade to; the unfilled policy by default.",
+    ),
+    field(
+        "--layer",
+        Kind::Text("BLEND[@OPACITY] FLAGS..."),
+        "Another image, generated with its own flags and blended on.",
+    ),
+    field(
+        "--trace-colors",
+        Kind::Integer(within(1.0, 256.0), |p| Some(p.trace_colors as f64)),
+        "Colors the --trace outlines are drawn in, one layer each.",
+    ),
+    field(
+        "--center-stats",
+        Kind::Text("FILE.csv"),
+        "Writes each center's pixel count, mean radius and dominant hue as CSV.",
+    ),
+    field(
+        "--center-labels",
+        Kind::Text("FILE.png"),
+        "Saves a copy of the image with the largest centers marked and numbered.",
+    ),
+    field(
+        "--simulate-cvd",
+        Kind::Text("all or protanopia,deuteranopia,tritanopia"),
+        "Writes companions of the output as colorblind viewers see it.",
+    ),
+    field(
+        "--mosaic",
+        Kind::Number(within(0.0, 255.0), |p| p.mosaic),
+        "Flattens blocks whose colors spread less than this into quadtree tiles.",
+    ),
+    field(
+        "--max-colors",
+        Kind::Integer(within(1.0, 256.0), |p| p.max_colors.map(|n| n as f64)),
+        "Reduces the image to at most this many colors.",
+    ),
+    field(
+        "--quantize",
+        Kind::Choice(&["image", "generation"], Some("image")),
+        "Whether --max-colors reduces the finished image or the colors drawn.",
+    ),
+    field(
+        "--blend-seeds",
+        Kind::Integer(at_least(2.0), |p| p.blend_seeds.map(|n| n as f64)),
+        "Runs this many seeds and blends them.",
+    ),
+    field(
+        "--blend",
+        Kind::Choice(&["mean", "median", "oldest"], Some("mean")),
+        "How --blend-seeds combines its runs.",
+    ),
+    field(
+        "--blend-weights",
+        Kind::Text("W,W,..."),
+        "Weighs the --blend-seeds runs, one weight per seed.",
+    ),
+    field(
+        "--channel-seeds",
+        Kind::Text("three seeds, like 1,2,3"),
+        "Takes the red, green and blue channels from runs of these seeds.",
+    ),
+    field(
+        "--scale",
+        Kind::Integer(at_least(1.0), |p| Some(p.scale as f64)),
+        "Output pixels per canvas pixel.",
+    ),
+    field(
+        "--crop",
+        Kind::Text("x,y,w,h"),
+        "Keeps only this rectangle of the canvas.",
+    ),
+    field(
+        "--kernel",
+        Kind::Choice(&["square", "disc"], Some("square")),
+        "The shape drawn for each pixel.",
+    ),
+    field(
+        "--stroke",
+        Kind::Number(above(0.0), |p| Some(p.stroke)),
+        "The width of each pixel's shape, in canvas pixels.",
+    ),
+    field(
+        "--subpixel",
+        Kind::Switch,
+        "Draws each pixel at its curve point rather than the middle of its cell.",
+    ),
+    field(
+        "--accumulate",
+        Kind::Switch,
+        "Averages the shapes over each output pixel rather than drawing them in turn.",
+    ),
+    field(
+        "--stereo",
+        Kind::Choice(&["anaglyph", "side-by-side"], None),
+        "Saves a stereo pair instead of the plain image.",
+    ),
+    field(
+        "--depth",
+        Kind::Choice(&["radius", "age"], Some("radius")),
+        "What brings a pixel forward in --stereo.",
+    ),
+    field(
+        "--parallax",
+        Kind::Number(ANY, |p| Some(p.parallax)),
+        "The most a pixel moves between the --stereo views, in pixels.",
+    ),
+    field(
+        "--color-space",
+        Kind::Choice(&["srgb", "display-p3", "adobe-rgb"], None),
+        "The color space the pixel values are coordinates in.",
+    ),
+    field(
+        "--walk-map",
+        Kind::Text("FILE.png"),
+        "Also saves how often walks passed through each cell.",
+    ),
+    field(
+        "--annotate",
+        Kind::Switch,
+        "Adds a margin holding the command that renders the image again.",
+    ),
+    field(
+        "--sign",
+        Kind::File,
+        "Hides a signature made with this key in the pixels.",
+    ),
+    field(
+        "--author",
+        Kind::Text("NAME"),
+        "The name --sign signs along with the image.",
+    ),
+    field(
+        "--raw-every",
+        Kind::Integer(at_least(1.0), |p| p.raw_every.map(|n| n as f64)),
+        "Placements between frames written by --raw-stdout.",
+    ),
+];
+
+// The flags left out of FIELDS: where the image and other files go, the
+// outputs saved alongside it, and how the program reports and runs.
+pub static OUTPUTS: &[&str] = &[
+    "--out",
+    "--out-dir",
+    "--gallery",
+    "--no-raster",
+    "--thumbnail",
+    "--checkpoints",
+    "--milestones",
+    "--save-scene",
+    "--svg",
+    "--export",
+    "--depth-map",
+    "--trace",
+    "--gcode",
+    "--hpgl",
+    "--pen-levels",
+    "--plot-width",
+    "--cmyk",
+    "--print-profile",
+    "--soft-proof",
+    "--slices",
+    "--nrrd",
+    "--raw-stdout",
+    "--term-preview",
+    "--on-complete",
+    "--on-milestone",
+    "--log-format",
+    "--run-db",
+    "--strict",
+];
+
+// The first value params holds outside its flag's range.
+pub fn check_ranges(params: &Params) -> Result<(), String> {
+    for field in FIELDS {
+        if let Kind::Integer(range, get) | Kind::Number(range, get) = field.kind
+            && let Some(value) = get(params)
+            && !range.contains(value)
+        {
+            return Err(format!(
+                "{} must be {}, got {value}",
+                field.flag,
+                range.describe()
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn property(field: &Field) -> String {
+    let defaults = Params::default();
+    let mut parts = vec![];
+    match field.kind {
+        Kind::Integer(range, get) | Kind::Number(range, get) => {
+            let kind = if matches!(field.kind, Kind::Integer(..)) {
+                "integer"
+            } else {
+                "number"
+            };
+            parts.push(format!("\"type\": \"{kind}\""));
+            if let Some(min) = range.min {
+                let name = if range.exclusive_min {
+                    "exclusiveMinimum"
+                } else {
+                    "minimum"
+                };
+                parts.push(format!("\"{name}\": {min}"));
+            }
+            if let Some(max) = range.max {
+                let name = if range.exclusive_max {
+                    "exclusiveMaximum"
+                } else {
+                    "maximum"
+                };
+                parts.push(format!("\"{name}\": {max}"));
+            }
+            if let Some(value) = get(&defaults) {
+                parts.push(format!("\"default\": {value}"));
+            }
+        }
+        Kind::Choice(choices, default) => {
+            let choices: Vec<String> = choices.iter().map(|c| json_string(c)).collect();
+            parts.push("\"type\": \"string\"".to_string());
+            parts.push(format!("\"enum\": [{}]", choices.join(", ")));
+            if let Some(default) = default {
+                parts.push(format!("\"default\": {}", json_string(default)));
+            }
+        }
+        Kind::Switch => {
+            parts.push("\"type\": \"boolean\"".to_string());
+            parts.push("\"default\": false".to_string());
+        }
+        Kind::Text(form) => {
+            parts.push("\"type\": \"string\"".to_string());
+            parts.push(format!("\"x-form\": {}", json_string(form)));
+        }
+        Kind::File => {
+            parts.push("\"type\": \"string\"".to_string());
+            parts.push("\"x-file\": true".to_string());
+        }
+    }
+    parts.push(format!(
+        "\"description\": {}",
+        json_string(field.description)
+    ));
+    parts.push(format!("\"x-flag\": {}", json_string(field.flag)));
+    format!(
+        "    {}: {{{}}}",
+        json_string(field.flag.trim_start_matches("--")),
+        parts.join(", ")
+    )
+}
+
+// FIELDS as a JSON Schema of an object keyed by flag without its dashes.
+// Each property also gives its flag as x-flag and the form of text values
+// as x-form, and marks files with x-file. Whole numbers print without a
+// fraction, as JSON integers.
+pub fn json_schema() -> String {
+    let properties: Vec<String> = FIELDS.iter().map(property).collect();
+    format!(
+        "{{\n  \"$schema\": \"https://json-schema.org/draft/2020-12/schema\",\n  \
+         \"title\": \"spinning parameters\",\n  \"type\": \"object\",\n  \
+         \"properties\": {{\n{}\n  }}\n}}\n",
+        properties
+            .iter()
+            .map(|p| format!("  {p}"))
+            .collect::<Vec<_>>()
+            .join(",\n")
+    )
+}
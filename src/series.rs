@@ -0,0 +1,153 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+// Samples per amplitude value when reading audio.
+#[cfg(feature = "files")]
+const WINDOW: usize = 1024;
+
+// A time series stretched over the run, as values in 0..=1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    values: Vec<f64>,
+}
+
+impl Series {
+    // A WAV file gives its loudness, as the RMS amplitude over short windows.
+    // Anything else is read as CSV, taking the given column and skipping
+    // rows where it is not a number, such as a header.
+    pub fn load(path: &str, column: usize) -> Result<Self, String> {
+        let raw = if path.to_lowercase().ends_with(".wav") {
+            load_wav(path)?
+        } else {
+            load_csv(path, column)?
+        };
+        Self::normalized(raw).ok_or(format!("no samples in {path}"))
+    }
+
+    // Scales values into 0..=1; None if there are none.
+    pub fn normalized(raw: Vec<f64>) -> Option<Self> {
+        let lo = raw.iter().copied().reduce(f64::min)?;
+        let hi = raw.iter().copied().reduce(f64::max)?;
+        let span = if hi > lo { hi - lo } else { 1.0 };
+        Some(Self {
+            values: raw.into_iter().map(|v| (v - lo) / span).collect(),
+        })
+    }
+
+    // Linearly interpolated value at t in 0..=1.
+    pub fn at(&self, t: f64) -> f64 {
+        let pos = t.clamp(0.0, 1.0) * (self.values.len() - 1) as f64;
+        let index = pos.floor() as usize;
+        let next = (index + 1).min(self.values.len() - 1);
+        let frac = pos - index as f64;
+        self.values[index] * (1.0 - frac) + self.values[next] * frac
+    }
+}
+
+#[cfg(not(feature = "files"))]
+fn load_wav(path: &str) -> Result<Vec<f64>, String> {
+    Err(format!(
+        "reading {path} needs spinning built with --features files"
+    ))
+}
+
+#[cfg(feature = "files")]
+fn load_wav(path: &str) -> Result<Vec<f64>, String> {
+    let error = |e: hound::Error| format!("could not read {path}: {e}");
+    let mut reader = hound::WavReader::open(path).map_err(error)?;
+    let spec = reader.spec();
+    let samples: Vec<f64> = match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .samples::<f32>()
+            .map(|s| s.map(|s| s as f64))
+            .collect::<Result<_, _>>()
+            .map_err(error)?,
+        hound::SampleFormat::Int => {
+            let full = (1i64 << (spec.bits_per_sample - 1)) as f64;
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| s as f64 / full))
+                .collect::<Result<_, _>>()
+                .map_err(error)?
+        }
+    };
+    let frame = WINDOW * spec.channels as usize;
+    Ok(samples
+        .chunks(frame)
+        .map(|chunk| (chunk.iter().map(|s| s * s).sum::<f64>() / chunk.len() as f64).sqrt())
+        .collect())
+}
+
+fn load_csv(path: &str, column: usize) -> Result<Vec<f64>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("could not read {path}: {e}"))?;
+    Ok(text
+        .lines()
+        .filter_map(|line| line.split(',').nth(column)?.trim().parse().ok())
+        .collect())
+}
+
+// What a series modulates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    // Scaled by twice the value, so an even series averages out.
+    ContSpread,
+    StartSpread,
+    Lookback,
+    RingWidth,
+    // Rotated by the value times a full turn.
+    Hue,
+}
+
+// A parameter following a series over placement time.
+// Written as `TARGET=PATH` or `TARGET=PATH:COLUMN`, with TARGET one of
+// `cont-spread`, `start-spread`, `lookback`, `ring-width` or `hue`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Modulation {
+    pub target: Target,
+    pub series: Arc<Series>,
+}
+
+impl FromStr for Modulation {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        let (target, source) = s
+            .split_once('=')
+            .ok_or(format!("--modulate expects TARGET=PATH, got {s}"))?;
+        let target = match target {
+            "cont-spread" => Target::ContSpread,
+            "start-spread" => Target::StartSpread,
+            "lookback" => Target::Lookback,
+            "ring-width" => Target::RingWidth,
+            "hue" => Target::Hue,
+            _ => {
+                return Err(format!(
+                    "unknown modulation target {target}, \
+                     expected cont-spread, start-spread, lookback, ring-width or hue"
+                ));
+            }
+        };
+        let (path, column) = match source.rsplit_once(':') {
+            Some((path, column)) if column.parse::<usize>().is_ok() => {
+                (path, column.parse().expect("checked"))
+            }
+            _ => (source, 0),
+        };
+        Ok(Self {
+            target,
+            series: Arc::new(Series::load(path, column)?),
+        })
+    }
+}
+
+// The combined effect of the modulations on a target at t: a factor for the
+// spreads and lookback, or a rotation in turns for the hue.
+pub fn modulate(modulations: &[Modulation], target: Target, t: f64) -> f64 {
+    let values = modulations
+        .iter()
+        .filter(|m| m.target == target)
+        .map(|m| m.series.at(t));
+    match target {
+        Target::Hue => values.sum(),
+        _ => values.map(|v| 2.0 * v).product(),
+    }
+}
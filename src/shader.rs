@@ -0,0 +1,63 @@
+use image::{Rgba, RgbaImage};
+
+use crate::Placement;
+
+// The replay buffer starts with MAGIC, then the version, canvas size and
+// placement count as little-endian u32s, then two u32s per canvas cell, row
+// by row: the order the cell was placed in (u32::MAX for never) and its
+// color as RGBA8, red in the low byte. That's 8 bytes a cell however long
+// the run, and a frame is one lookup per pixel against the count shown.
+pub const MAGIC: &[u8; 4] = b"SPGB";
+pub const VERSION: u32 = 1;
+pub const HEADER: usize = 16;
+
+// Reference shaders drawing a frame of the buffer: WGSL reading it as a
+// storage buffer, GLSL ES reading it as an RG32UI texture.
+pub const WGSL: &str = include_str!("replay.wgsl");
+pub const GLSL: &str = include_str!("replay.glsl");
+
+// A cell placed more than once appears with its first placement, in the
+// color it ends with.
+pub fn replay_buffer(placements: &[Placement], size: usize) -> Vec<u8> {
+    let mut cells = vec![[u32::MAX, 0]; size * size];
+    for (i, placement) in placements.iter().enumerate() {
+        let [x, y] = placement.pixel.loc;
+        let [r, g, b] = placement.pixel.color;
+        let cell = &mut cells[y * size + x];
+        cell[0] = cell[0].min(i as u32);
+        cell[1] = u32::from_le_bytes([r, g, b, 255]);
+    }
+    let mut out = Vec::with_capacity(HEADER + 8 * cells.len());
+    out.extend(MAGIC);
+    for n in [VERSION, size as u32, placements.len() as u32] {
+        out.extend(n.to_le_bytes());
+    }
+    for n in cells.into_iter().flatten() {
+        out.extend(n.to_le_bytes());
+    }
+    out
+}
+
+// What the shaders draw with shown placements made, for checking a buffer
+// without a GPU.
+pub fn replay_frame(buffer: &[u8], shown: u32, background: Rgba<u8>) -> Result<RgbaImage, String> {
+    let word = |i: usize| u32::from_le_bytes(buffer[i..i + 4].try_into().expect("four bytes"));
+    if buffer.len() < HEADER || &buffer[..4] != MAGIC {
+        return Err("not a replay buffer".to_string());
+    }
+    if word(4) != VERSION {
+        return Err(format!("unsupported replay buffer version {}", word(4)));
+    }
+    let size = word(8) as usize;
+    if buffer.len() != HEADER + 8 * size * size {
+        return Err("the replay buffer is truncated".to_string());
+    }
+    Ok(RgbaImage::from_fn(size as u32, size as u32, |x, y| {
+        let at = HEADER + 8 * (y as usize * size + x as usize);
+        if word(at) >= shown {
+            background
+        } else {
+            Rgba(word(at + 4).to_le_bytes())
+        }
+    }))
+}
@@ -0,0 +1,229 @@
+use image::RgbImage;
+
+use crate::canvas::Unfilled;
+use crate::rng::splitmix64;
+use crate::{Params, generate};
+
+// A sharded render splits the canvas into a grid of count square tiles,
+// count a square number, and grows each tile as a run of its own: its own
+// seed, derived from the render's and the tile's index, and a share of the
+// centers as large as its share of the canvas. Tiles depend on nothing but
+// the arguments, so they can be rendered anywhere, in any order, and merged.
+// The walks can't cross tiles, so the seams show as a mosaic.
+//
+// A part file holds one rendered tile: MAGIC, the version as a u32, the
+// arguments, the canvas size, the shard count and index, whether unfilled cells are
+// transparent, then the tile's RGB pixels row by row and a byte per pixel
+// saying whether it was filled. Numbers are little-endian u64s; strings are
+// a length and UTF-8.
+pub const MAGIC: &[u8; 4] = b"SPSH";
+pub const VERSION: u32 = 1;
+
+// Where tile index of count sits on a canvas of size: its left and top
+// edges and its side, cut short at the right and bottom edges.
+pub fn tile(size: usize, count: usize, index: usize) -> ([usize; 2], [usize; 2]) {
+    let across = count.isqrt();
+    let side = size.div_ceil(across);
+    let origin = [index % across, index / across].map(|k| (k * side).min(size));
+    (origin, origin.map(|o| side.min(size - o)))
+}
+
+fn check(count: usize, index: usize) -> Result<(), String> {
+    if count == 0 || count.isqrt().pow(2) != count {
+        return Err(format!(
+            "the shard count must be a square number, got {count}"
+        ));
+    }
+    if index >= count {
+        return Err(format!("shard {index} is out of range for {count} shards"));
+    }
+    Ok(())
+}
+
+// The parameters of tile index: args with the tile's size, seed and centers.
+pub fn shard_params(args: &[String], count: usize, index: usize) -> Result<Params, String> {
+    check(count, index)?;
+    let params = Params::from_args(args.iter().cloned())?;
+    if params.voxels || params.big.is_some() || params.base.is_some() || params.canvas.wraps() {
+        return Err(
+            "shards cannot be combined with --voxels, --big, --base, --inpaint or --panorama"
+                .to_string(),
+        );
+    }
+    let across = count.isqrt();
+    let side = params.size.div_ceil(across);
+    let centers = params.num_centers.div_ceil(count).max(1);
+    let seed = splitmix64(params.seed.wrapping_add(index as u64));
+    let mut args = args.to_vec();
+    for (flag, value) in [
+        ("--size", side.to_string()),
+        ("--centers", centers.to_string()),
+        ("--seed", seed.to_string()),
+    ] {
+        args.extend([flag.to_string(), value]);
+    }
+    Params::from_args(args.into_iter())
+}
+
+// One rendered tile of a sharded render.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Part {
+    pub args: Vec<String>,
+    // The side of the whole canvas.
+    pub size: usize,
+    pub count: usize,
+    pub index: usize,
+    pub transparent: bool,
+    pub image: RgbImage,
+    // Indexed [x][y].
+    pub filled: Vec<Vec<bool>>,
+}
+
+// Renders tile index of the render args describe, split into count shards.
+pub fn render(args: &[String], count: usize, index: usize) -> Result<Part, String> {
+    let size = Params::from_args(args.iter().cloned())?.size;
+    let params = shard_params(args, count, index)?;
+    let (_, [width, height]) = tile(size, count, index);
+    let run = generate(&params, false);
+    let image = image::imageops::crop_imm(&run.image, 0, 0, width as u32, height as u32).to_image();
+    let filled = run.filled[..width]
+        .iter()
+        .map(|column| column[..height].to_vec())
+        .collect();
+    Ok(Part {
+        args: args.to_vec(),
+        size,
+        count,
+        index,
+        transparent: params.unfilled == Unfilled::Transparent,
+        image,
+        filled,
+    })
+}
+
+fn put(bytes: &mut Vec<u8>, n: usize) {
+    bytes.extend((n as u64).to_le_bytes());
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let taken = self
+            .at
+            .checked_add(n)
+            .and_then(|end| self.bytes.get(self.at..end))
+            .ok_or("the part ends early")?;
+        self.at += n;
+        Ok(taken)
+    }
+
+    fn number(&mut self) -> Result<usize, String> {
+        let n = u64::from_le_bytes(self.take(8)?.try_into().expect("took 8"));
+        usize::try_from(n).map_err(|_| "the part holds a number too large".to_string())
+    }
+}
+
+impl Part {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(VERSION.to_le_bytes());
+        put(&mut bytes, self.args.len());
+        for arg in &self.args {
+            put(&mut bytes, arg.len());
+            bytes.extend(arg.as_bytes());
+        }
+        for n in [self.size, self.count, self.index] {
+            put(&mut bytes, n);
+        }
+        bytes.push(self.transparent as u8);
+        bytes.extend(self.image.as_raw());
+        for y in 0..self.image.height() as usize {
+            bytes.extend(self.filled.iter().map(|column| column[y] as u8));
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut r = Reader { bytes, at: 0 };
+        if r.take(4)? != MAGIC {
+            return Err("not a shard part".to_string());
+        }
+        let version = u32::from_le_bytes(r.take(4)?.try_into().expect("took 4"));
+        if version == 0 || version > VERSION {
+            return Err(format!(
+                "part version {version} is newer than this build reads ({VERSION})"
+            ));
+        }
+        let mut args = vec![];
+        for _ in 0..r.number()? {
+            let n = r.number()?;
+            let arg = String::from_utf8(r.take(n)?.to_vec())
+                .map_err(|_| "the part holds bad text".to_string())?;
+            args.push(arg);
+        }
+        let [size, count, index] = [r.number()?, r.number()?, r.number()?];
+        check(count, index)?;
+        let transparent = r.take(1)?[0] == 1;
+        let (_, [width, height]) = tile(size, count, index);
+        let cells = width.checked_mul(height).ok_or("the part is too large")?;
+        let pixels = r.take(cells.checked_mul(3).ok_or("the part is too large")?)?;
+        let image =
+            RgbImage::from_raw(width as u32, height as u32, pixels.to_vec()).expect("sized");
+        let mut filled = vec![vec![false; height]; width];
+        for (i, &flag) in r.take(cells)?.iter().enumerate() {
+            filled[i % width][i / width] = flag == 1;
+        }
+        if r.at != bytes.len() {
+            return Err("the part has bytes left over".to_string());
+        }
+        Ok(Self {
+            args,
+            size,
+            count,
+            index,
+            transparent,
+            image,
+            filled,
+        })
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("could not read {path}: {e}"))?;
+        Self::from_bytes(&bytes).map_err(|e| format!("{path}: {e}"))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, self.to_bytes()).map_err(|e| format!("could not write {path}: {e}"))
+    }
+}
+
+// The whole render from every one of its parts, each once, in any order,
+// and which cells are filled, indexed [x][y].
+pub fn merge(parts: &[Part]) -> Result<(RgbImage, Vec<Vec<bool>>), String> {
+    let first = parts.first().ok_or("no parts to merge")?;
+    let size = first.size;
+    let mut seen = vec![false; first.count];
+    let mut image = RgbImage::new(size as u32, size as u32);
+    let mut filled = vec![vec![false; size]; size];
+    for part in parts {
+        if (&part.args, part.size, part.count) != (&first.args, first.size, first.count) {
+            return Err("the parts come from different renders".to_string());
+        }
+        if std::mem::replace(&mut seen[part.index], true) {
+            return Err(format!("shard {} is given twice", part.index));
+        }
+        let (origin, [width, _]) = tile(size, part.count, part.index);
+        image::imageops::replace(&mut image, &part.image, origin[0] as i64, origin[1] as i64);
+        for (x, column) in part.filled.iter().enumerate().take(width) {
+            filled[origin[0] + x][origin[1]..origin[1] + column.len()].copy_from_slice(column);
+        }
+    }
+    if let Some(missing) = seen.iter().position(|&s| !s) {
+        return Err(format!("shard {missing} of {} is missing", first.count));
+    }
+    Ok((image, filled))
+}
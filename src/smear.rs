@@ -0,0 +1,79 @@
+use image::{Rgb, RgbImage};
+
+use crate::linear::{color_from_linear, color_to_linear};
+use crate::{Location, Placement};
+
+// Line integral convolution along the orbits: each filled cell becomes the
+// mean, in linear light, of the cells met stepping length pixels forward and
+// back along the circle about its pixel's center, turning at every cell to
+// follow that cell's own center. A walk stops early at an unfilled cell or the
+// edge. strength mixes the smear over the image, 1.0 replacing it.
+// Placements give each cell's center; where one cell was placed twice, the
+// later placement counts.
+pub fn smear(image: &RgbImage, placements: &[Placement], length: usize, strength: f64) -> RgbImage {
+    let (width, height) = image.dimensions();
+    let (width, height) = (width as usize, height as usize);
+    let mut centers: Vec<Option<Location>> = vec![None; width * height];
+    for placement in placements {
+        let [x, y] = placement.pixel.loc;
+        if x < width && y < height {
+            centers[y * width + x] = Some(placement.pixel.center);
+        }
+    }
+    // The unit tangent at a point of cell at, turning the way the walk went.
+    let tangent = |at: [usize; 2], pos: [f64; 2], before: [f64; 2]| {
+        let center = centers[at[1] * width + at[0]]?;
+        let [dx, dy] = [0, 1].map(|i| pos[i] - (center[i] as f64 + 0.5));
+        let len = dx.hypot(dy);
+        if len == 0.0 {
+            return None;
+        }
+        let along = [-dy / len, dx / len];
+        Some(if along[0] * before[0] + along[1] * before[1] < 0.0 {
+            along.map(|c| -c)
+        } else {
+            along
+        })
+    };
+    let mut out = image.clone();
+    for y in 0..height {
+        for x in 0..width {
+            let start = [x as f64 + 0.5, y as f64 + 0.5];
+            let Some(first) = tangent([x, y], start, [0.0, 0.0]) else {
+                continue;
+            };
+            let mut sum = color_to_linear(image.get_pixel(x as u32, y as u32).0);
+            let mut count = 1;
+            for sign in [1.0, -1.0] {
+                let mut pos = start;
+                let mut dir = first.map(|c| c * sign);
+                for _ in 0..length {
+                    pos = [pos[0] + dir[0], pos[1] + dir[1]];
+                    if pos[0] < 0.0 || pos[1] < 0.0 {
+                        break;
+                    }
+                    let at = [pos[0] as usize, pos[1] as usize];
+                    if at[0] >= width || at[1] >= height {
+                        break;
+                    }
+                    let Some(next) = tangent(at, pos, dir) else {
+                        break;
+                    };
+                    let color = color_to_linear(image.get_pixel(at[0] as u32, at[1] as u32).0);
+                    for ch in 0..3 {
+                        sum[ch] += color[ch];
+                    }
+                    count += 1;
+                    dir = next;
+                }
+            }
+            let own = color_to_linear(image.get_pixel(x as u32, y as u32).0);
+            let mixed: [f32; 3] = std::array::from_fn(|ch| {
+                let smeared = sum[ch] / count as f32;
+                own[ch] + strength as f32 * (smeared - own[ch])
+            });
+            out.put_pixel(x as u32, y as u32, Rgb(color_from_linear(mixed)));
+        }
+    }
+    out
+}
@@ -0,0 +1,159 @@
+use image::{Rgb, RgbImage};
+use rand::prelude::*;
+
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+
+use crate::Location;
+use crate::params::Params;
+use crate::rng::SeededRng;
+use crate::topology::{Topology, grow};
+
+type Direction = [f64; 3];
+
+// Pixel [x, y] of a width by height equirectangular image, as a point of the
+// unit sphere: longitude runs with x from the left edge round to the right,
+// latitude from the north pole at the top to the south pole at the bottom.
+fn direction([x, y]: Location, width: usize, height: usize) -> Direction {
+    let lon = (x as f64 + 0.5) / width as f64 * TAU;
+    let lat = FRAC_PI_2 - (y as f64 + 0.5) / height as f64 * PI;
+    [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()]
+}
+
+fn dot(a: Direction, b: Direction) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn angle(a: Direction, b: Direction) -> f64 {
+    dot(a, b).clamp(-1.0, 1.0).acos()
+}
+
+// A direction up to max_angle from c, toward a random side.
+fn perturb<R: Rng + ?Sized>(c: Direction, max_angle: f64, rng: &mut R) -> Direction {
+    let side = loop {
+        let v: Direction = [0; 3].map(|_| rng.random_range(-1.0..1.0));
+        let along = dot(v, c);
+        let t = [0, 1, 2].map(|k| v[k] - along * c[k]);
+        let len = dot(t, t).sqrt();
+        if len > 1e-3 {
+            break t.map(|k| k / len);
+        }
+    };
+    let a = rng.random_range(0.0..=max_angle);
+    [0, 1, 2].map(|k| c[k] * a.cos() + side[k] * a.sin())
+}
+
+// The sphere as an equirectangular image: cells are its pixels, centers are
+// points of the sphere, and spreads are in pixels along a meridian.
+struct Sphere {
+    width: usize,
+    height: usize,
+}
+
+impl Sphere {
+    // Radians per pixel along a meridian.
+    fn step(&self) -> f64 {
+        PI / self.height as f64
+    }
+}
+
+impl Topology for Sphere {
+    type Center = Direction;
+
+    fn side(&self) -> usize {
+        self.height
+    }
+
+    fn slots(&self) -> usize {
+        self.width * self.height
+    }
+
+    fn slot(&self, [x, y]: Location) -> usize {
+        y * self.width + x
+    }
+
+    fn location(&self, slot: usize) -> Location {
+        [slot % self.width, slot / self.width]
+    }
+
+    fn start_center(&self, loc: Location, width: f64, rng: &mut SeededRng) -> Direction {
+        let at = direction(loc, self.width, self.height);
+        perturb(at, width * self.step(), rng)
+    }
+
+    fn jitter(&self, center: Direction, width: f64, rng: &mut SeededRng) -> Direction {
+        perturb(center, width * self.step(), rng)
+    }
+
+    fn walk(
+        &self,
+        center: Direction,
+        start: Location,
+        is_open: impl Fn(Location) -> bool,
+    ) -> Option<Location> {
+        walk(center, start, self.width, self.height, is_open)
+    }
+}
+
+// The original process on a sphere, drawn as an equirectangular image twice
+// as wide as size and size tall. Centers are points of the sphere, and each
+// walk follows the circle on the sphere about its center through its start,
+// wrapping round the sides and over the poles, so the image maps back onto a
+// sphere with round orbits everywhere and no pinching at the poles. The
+// placing itself is topology::grow's, with a single walker.
+pub fn generate_sphere(params: &Params) -> RgbImage {
+    let (width, height) = (2 * params.size, params.size);
+    let colors = grow(params, &Sphere { width, height }, 1, 1);
+    RgbImage::from_fn(width as u32, height as u32, |x, y| {
+        Rgb(colors[y as usize * width + x as usize])
+    })
+}
+
+// The eight pixels around loc on the sphere: across the left and right edges
+// to the other side, and over a pole to the row beside it, half way round.
+fn neighbors([x, y]: Location, width: usize, height: usize) -> impl Iterator<Item = Location> {
+    let (x, y) = (x as isize, y as isize);
+    let (w, h) = (width as isize, height as isize);
+    (-1..=1)
+        .flat_map(|dy| (-1..=1).map(move |dx| [dx, dy]))
+        .filter(|&d| d != [0, 0])
+        .map(move |[dx, dy]| {
+            let (mut nx, mut ny) = (x + dx, y + dy);
+            if ny < 0 || ny >= h {
+                ny = if ny < 0 { -1 - ny } else { 2 * h - 1 - ny };
+                nx += w / 2;
+            }
+            [nx.rem_euclid(w) as usize, ny as usize]
+        })
+}
+
+// Steps from start keeping as near as it can to the circle about center
+// through start, until an open pixel is found. Gives up on returning to start
+// or running too long.
+fn walk(
+    center: Direction,
+    start: Location,
+    width: usize,
+    height: usize,
+    is_open: impl Fn(Location) -> bool,
+) -> Option<Location> {
+    let radius = angle(direction(start, width, height), center);
+    let score = |loc: Location| (angle(direction(loc, width, height), center) - radius).abs();
+    // Near a pole a circle can run the whole width of many rows.
+    let max_steps = 2 * width + (8.0 * radius / PI * height as f64).ceil() as usize + 8;
+    let mut last = start;
+    let mut cur = start;
+    for _ in 0..max_steps {
+        let next = neighbors(cur, width, height)
+            .filter(|&n| n != last && n != cur)
+            .min_by(|&a, &b| score(a).total_cmp(&score(b)))?;
+        if next == start {
+            return None;
+        }
+        if is_open(next) {
+            return Some(next);
+        }
+        last = cur;
+        cur = next;
+    }
+    None
+}
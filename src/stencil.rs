@@ -0,0 +1,358 @@
+use image::{Rgb, RgbImage};
+
+use std::str::FromStr;
+
+use crate::Location;
+
+// A word or shape whose outline the initial centers are placed along.
+// Written as `text:WORD` or `svg:PATH`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stencil {
+    // Drawn in a built-in 5x7 pixel font.
+    Text(String),
+    // Closed polygons from an SVG file's paths, in its coordinates.
+    Shape(Vec<Vec<[f64; 2]>>),
+}
+
+impl FromStr for Stencil {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.split_once(':') {
+            Some(("text", text)) => {
+                let text = text.to_uppercase();
+                if let Some(c) = text.chars().find(|&c| glyph(c).is_none()) {
+                    return Err(format!("the stencil font has no character {c:?}"));
+                }
+                Ok(Stencil::Text(text))
+            }
+            Some(("svg", path)) => {
+                let svg = std::fs::read_to_string(path)
+                    .map_err(|e| format!("could not read {path}: {e}"))?;
+                let polygons = svg_polygons(&svg)?;
+                if polygons.iter().all(|p| p.len() < 3) {
+                    return Err(format!("no filled paths in {path}"));
+                }
+                Ok(Stencil::Shape(polygons))
+            }
+            _ => Err(format!(
+                "unknown stencil {s}, expected text:WORD or svg:PATH"
+            )),
+        }
+    }
+}
+
+// Fraction of the canvas left clear around the stencil.
+const MARGIN: f64 = 0.1;
+
+impl Stencil {
+    // Which cells the stencil covers, scaled to fit the canvas, row-major.
+    pub fn mask(&self, size: usize) -> Vec<bool> {
+        let mut mask = vec![false; size * size];
+        match self {
+            Stencil::Text(text) => {
+                let cols = 6 * text.chars().count().max(1) - 1;
+                let cell = ((1.0 - 2.0 * MARGIN) * size as f64 / cols as f64).max(1.0);
+                let left = (size as f64 - cell * cols as f64) / 2.0;
+                let top = (size as f64 - cell * 7.0) / 2.0;
+                for y in 0..size {
+                    for x in 0..size {
+                        let col = ((x as f64 + 0.5 - left) / cell).floor();
+                        let row = ((y as f64 + 0.5 - top) / cell).floor();
+                        if col < 0.0 || row < 0.0 || col >= cols as f64 || row >= 7.0 {
+                            continue;
+                        }
+                        let (col, row) = (col as usize, row as usize);
+                        let Some(c) = text.chars().nth(col / 6) else {
+                            continue;
+                        };
+                        let bits = glyph(c).expect("checked")[row];
+                        mask[y * size + x] = col % 6 < 5 && bits >> (4 - col % 6) & 1 == 1;
+                    }
+                }
+            }
+            Stencil::Shape(polygons) => {
+                let points = polygons.iter().flatten();
+                let lo = points
+                    .clone()
+                    .fold([f64::MAX; 2], |a, p| [a[0].min(p[0]), a[1].min(p[1])]);
+                let hi = points.fold([f64::MIN; 2], |a, p| [a[0].max(p[0]), a[1].max(p[1])]);
+                let span = (hi[0] - lo[0]).max(hi[1] - lo[1]).max(1e-9);
+                let scale = (1.0 - 2.0 * MARGIN) * size as f64 / span;
+                let offset = [0, 1].map(|k| (size as f64 - (hi[k] - lo[k]) * scale) / 2.0);
+                let polygons: Vec<Vec<[f64; 2]>> = polygons
+                    .iter()
+                    .map(|p| {
+                        p.iter()
+                            .map(|&q| [0, 1].map(|k| (q[k] - lo[k]) * scale + offset[k]))
+                            .collect()
+                    })
+                    .collect();
+                // Even-odd fill, scanning each row at the pixel centers.
+                for y in 0..size {
+                    let py = y as f64 + 0.5;
+                    let mut crossings: Vec<f64> = polygons
+                        .iter()
+                        .filter(|p| p.len() >= 3)
+                        .flat_map(|p| {
+                            (0..p.len()).filter_map(move |i| {
+                                let (a, b) = (p[i], p[(i + 1) % p.len()]);
+                                ((a[1] <= py) != (b[1] <= py))
+                                    .then(|| a[0] + (py - a[1]) / (b[1] - a[1]) * (b[0] - a[0]))
+                            })
+                        })
+                        .collect();
+                    crossings.sort_by(f64::total_cmp);
+                    for pair in crossings.chunks_exact(2) {
+                        let from = (pair[0] - 0.5).ceil().max(0.0) as usize;
+                        let to = ((pair[1] - 0.5).floor() + 1.0).clamp(0.0, size as f64) as usize;
+                        for x in from..to {
+                            mask[y * size + x] = true;
+                        }
+                    }
+                }
+            }
+        }
+        mask
+    }
+
+    // Covered cells next to an uncovered one, in scan order.
+    pub fn outline(&self, size: usize) -> Vec<Location> {
+        let mask = self.mask(size);
+        let covered = |x: isize, y: isize| {
+            x >= 0
+                && y >= 0
+                && (x as usize) < size
+                && (y as usize) < size
+                && mask[y as usize * size + x as usize]
+        };
+        (0..size)
+            .flat_map(|y| (0..size).map(move |x| [x, y]))
+            .filter(|&[x, y]| {
+                let (x, y) = (x as isize, y as isize);
+                covered(x, y)
+                    && [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                        .iter()
+                        .any(|&(dx, dy)| !covered(x + dx, y + dy))
+            })
+            .collect()
+    }
+}
+
+// Rows of a 5x7 glyph, top first, with the leftmost column in bit 4.
+pub(crate) fn glyph(c: char) -> Option<[u8; 7]> {
+    Some(match c {
+        'A' => [0x0e, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11],
+        'B' => [0x1e, 0x11, 0x11, 0x1e, 0x11, 0x11, 0x1e],
+        'C' => [0x0e, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0e],
+        'D' => [0x1e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x1e],
+        'E' => [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x1f],
+        'F' => [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x10],
+        'G' => [0x0e, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0f],
+        'H' => [0x11, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11],
+        'I' => [0x0e, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1f],
+        'M' => [0x11, 0x1b, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e],
+        'P' => [0x1e, 0x11, 0x11, 0x1e, 0x10, 0x10, 0x10],
+        'Q' => [0x0e, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0d],
+        'R' => [0x1e, 0x11, 0x11, 0x1e, 0x14, 0x12, 0x11],
+        'S' => [0x0f, 0x10, 0x10, 0x0e, 0x01, 0x01, 0x1e],
+        'T' => [0x1f, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0a, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0a],
+        'X' => [0x11, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x0a, 0x04, 0x04, 0x04, 0x04],
+        'Z' => [0x1f, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1f],
+        '0' => [0x0e, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0e],
+        '1' => [0x04, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x0e],
+        '2' => [0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f],
+        '3' => [0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e],
+        '4' => [0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02],
+        '5' => [0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e],
+        '6' => [0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e],
+        '7' => [0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e],
+        '9' => [0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c],
+        ' ' => [0; 7],
+        '!' => [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04],
+        '?' => [0x0e, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x0c],
+        '-' => [0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00],
+        ':' => [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x0c, 0x00],
+        ',' => [0x00, 0x00, 0x00, 0x00, 0x0c, 0x04, 0x08],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        '_' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1f],
+        '=' => [0x00, 0x00, 0x1f, 0x00, 0x1f, 0x00, 0x00],
+        '#' => [0x0a, 0x0a, 0x1f, 0x0a, 0x1f, 0x0a, 0x0a],
+        _ => return None,
+    })
+}
+
+// Draws text in the glyph font, capitalized, each dot scale pixels square
+// and each character 6 dots wide. Characters without a glyph are skipped.
+pub(crate) fn draw_text(
+    image: &mut RgbImage,
+    text: &str,
+    [left, top]: [u32; 2],
+    scale: u32,
+    ink: Rgb<u8>,
+) {
+    for (i, c) in text.to_uppercase().chars().enumerate() {
+        let Some(rows) = glyph(c) else {
+            continue;
+        };
+        for (row, bits) in rows.iter().enumerate() {
+            for col in 0..5 {
+                if bits >> (4 - col) & 1 == 0 {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        let x = left + (6 * i as u32 + col) * scale + dx;
+                        let y = top + row as u32 * scale + dy;
+                        if x < image.width() && y < image.height() {
+                            image.put_pixel(x, y, ink);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Points along a curve, not counting its start.
+const CURVE_STEPS: usize = 16;
+
+// The polygons traced by every `d` attribute in the file. Supports the
+// M, L, H, V, C, Q and Z commands, absolute and relative; curves are
+// flattened into line segments.
+fn svg_polygons(svg: &str) -> Result<Vec<Vec<[f64; 2]>>, String> {
+    let mut polygons = vec![];
+    for part in svg.split(" d=").skip(1) {
+        let quote = part.chars().next().ok_or("unterminated path")?;
+        let data = part[1..].split(quote).next().ok_or("unterminated path")?;
+        parse_path(data, &mut polygons)?;
+    }
+    Ok(polygons)
+}
+
+fn parse_path(data: &str, polygons: &mut Vec<Vec<[f64; 2]>>) -> Result<(), String> {
+    // Split into command letters and numbers.
+    let mut tokens: Vec<String> = vec![];
+    let mut number = String::new();
+    for c in data.chars() {
+        let starts_number = c == '-' && !number.ends_with(['e', 'E']);
+        if c.is_ascii_alphabetic() && c != 'e' && c != 'E'
+            || c == ','
+            || c.is_whitespace()
+            || starts_number
+        {
+            if !number.is_empty() {
+                tokens.push(std::mem::take(&mut number));
+            }
+            if c.is_ascii_alphabetic() {
+                tokens.push(c.to_string());
+            } else if starts_number {
+                number.push(c);
+            }
+        } else {
+            number.push(c);
+        }
+    }
+    if !number.is_empty() {
+        tokens.push(number);
+    }
+    let mut tokens = tokens.into_iter().peekable();
+    let mut command = 'M';
+    let mut cur = [0.0, 0.0];
+    let mut polygon: Vec<[f64; 2]> = vec![];
+    while let Some(token) = tokens.peek() {
+        if let Some(c) = token.chars().next().filter(|c| c.is_ascii_alphabetic()) {
+            command = c;
+            tokens.next();
+            if c == 'Z' || c == 'z' {
+                polygons.push(std::mem::take(&mut polygon));
+                continue;
+            }
+        }
+        let mut next = || -> Result<f64, String> {
+            let token = tokens
+                .next()
+                .ok_or("path ends in the middle of a command")?;
+            token
+                .parse()
+                .map_err(|_| format!("invalid path number {token}"))
+        };
+        let relative = command.is_ascii_lowercase();
+        let point = |x: f64, y: f64| {
+            if relative {
+                [cur[0] + x, cur[1] + y]
+            } else {
+                [x, y]
+            }
+        };
+        match command.to_ascii_uppercase() {
+            'M' => {
+                if polygon.len() >= 3 {
+                    polygons.push(std::mem::take(&mut polygon));
+                }
+                polygon.clear();
+                cur = point(next()?, next()?);
+                polygon.push(cur);
+                // Further pairs after a move are lines.
+                command = if relative { 'l' } else { 'L' };
+            }
+            'L' => {
+                cur = point(next()?, next()?);
+                polygon.push(cur);
+            }
+            'H' => {
+                let x = next()?;
+                cur = [if relative { cur[0] + x } else { x }, cur[1]];
+                polygon.push(cur);
+            }
+            'V' => {
+                let y = next()?;
+                cur = [cur[0], if relative { cur[1] + y } else { y }];
+                polygon.push(cur);
+            }
+            'C' => {
+                let c1 = point(next()?, next()?);
+                let c2 = point(next()?, next()?);
+                let end = point(next()?, next()?);
+                for k in 1..=CURVE_STEPS {
+                    let t = k as f64 / CURVE_STEPS as f64;
+                    let u = 1.0 - t;
+                    polygon.push([0, 1].map(|i| {
+                        u * u * u * cur[i]
+                            + 3.0 * u * u * t * c1[i]
+                            + 3.0 * u * t * t * c2[i]
+                            + t * t * t * end[i]
+                    }));
+                }
+                cur = end;
+            }
+            'Q' => {
+                let c1 = point(next()?, next()?);
+                let end = point(next()?, next()?);
+                for k in 1..=CURVE_STEPS {
+                    let t = k as f64 / CURVE_STEPS as f64;
+                    let u = 1.0 - t;
+                    polygon.push(
+                        [0, 1].map(|i| u * u * cur[i] + 2.0 * u * t * c1[i] + t * t * end[i]),
+                    );
+                }
+                cur = end;
+            }
+            c => return Err(format!("unsupported path command {c}")),
+        }
+    }
+    if polygon.len() >= 3 {
+        polygons.push(polygon);
+    }
+    Ok(())
+}
@@ -0,0 +1,145 @@
+use image::{GrayImage, Luma, Rgb, RgbImage};
+
+use std::str::FromStr;
+
+use crate::{Color, Placement};
+
+// How far a pixel seems to stand out of the image, from 0 to 1.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Depth {
+    // Tight rings near their center come forward; wide ones recede.
+    #[default]
+    Radius,
+    // The first pixels placed come forward; the last recede.
+    Age,
+}
+
+impl FromStr for Depth {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "radius" => Ok(Depth::Radius),
+            "age" => Ok(Depth::Age),
+            _ => Err(format!("unknown depth {s}, expected radius or age")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StereoFormat {
+    // Red from the left eye and green and blue from the right, for red-cyan
+    // glasses.
+    Anaglyph,
+    // The left eye's view beside the right's, for parallel viewing.
+    SideBySide,
+}
+
+impl FromStr for StereoFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "anaglyph" => Ok(StereoFormat::Anaglyph),
+            "side-by-side" => Ok(StereoFormat::SideBySide),
+            _ => Err(format!(
+                "unknown stereo format {s}, expected anaglyph or side-by-side"
+            )),
+        }
+    }
+}
+
+// Redraws the placements for one eye, moving each pixel's center, and so the
+// pixel, sideways by up to half the parallax according to its depth. Nearer
+// pixels win where two land together; gaps opened by the shift take the
+// color beside them.
+fn eye_view(
+    placements: &[Placement],
+    depths: &[f64],
+    size: usize,
+    shift: f64,
+    background: Color,
+) -> RgbImage {
+    let mut image = RgbImage::from_pixel(size as u32, size as u32, Rgb(background));
+    let mut nearest = vec![f64::NEG_INFINITY; size * size];
+    let mut filled = vec![false; size * size];
+    for (placement, &depth) in placements.iter().zip(depths) {
+        let [x, y] = placement.pixel.loc;
+        filled[y * size + x] = true;
+        let moved = x as isize + (shift * depth).round() as isize;
+        if moved < 0 || moved >= size as isize {
+            continue;
+        }
+        let cell = y * size + moved as usize;
+        if depth > nearest[cell] {
+            nearest[cell] = depth;
+            image.put_pixel(moved as u32, y as u32, Rgb(placement.pixel.color));
+        }
+    }
+    for y in 0..size {
+        let mut last = None;
+        for x in 0..size {
+            let cell = y * size + x;
+            if nearest[cell] > f64::NEG_INFINITY {
+                last = Some(image.get_pixel(x as u32, y as u32).0);
+            } else if let Some(color) = last.filter(|_| filled[cell]) {
+                image.put_pixel(x as u32, y as u32, Rgb(color));
+            }
+        }
+    }
+    image
+}
+
+pub fn stereo_image(
+    placements: &[Placement],
+    size: usize,
+    format: StereoFormat,
+    depth: Depth,
+    parallax: f64,
+    background: Color,
+) -> RgbImage {
+    let depths: Vec<f64> = match depth {
+        Depth::Radius => radii(placements).iter().map(|r| 1.0 - r).collect(),
+        Depth::Age => (0..placements.len())
+            .map(|i| 1.0 - i as f64 / placements.len() as f64)
+            .collect(),
+    };
+    let left = eye_view(placements, &depths, size, parallax / 2.0, background);
+    let right = eye_view(placements, &depths, size, -parallax / 2.0, background);
+    match format {
+        StereoFormat::Anaglyph => RgbImage::from_fn(size as u32, size as u32, |x, y| {
+            let [r, _, _] = left.get_pixel(x, y).0;
+            let [_, g, b] = right.get_pixel(x, y).0;
+            Rgb([r, g, b])
+        }),
+        StereoFormat::SideBySide => {
+            let mut pair = RgbImage::new(2 * size as u32, size as u32);
+            image::imageops::replace(&mut pair, &left, 0, 0);
+            image::imageops::replace(&mut pair, &right, size as i64, 0);
+            pair
+        }
+    }
+}
+
+// Each placement's distance from its center, as a fraction of the largest.
+fn radii(placements: &[Placement]) -> Vec<f64> {
+    let radii: Vec<f64> = placements
+        .iter()
+        .map(|p| {
+            let [x, y] = p.pixel.loc;
+            let [cx, cy] = p.pixel.center;
+            (x as f64 - cx as f64).hypot(y as f64 - cy as f64)
+        })
+        .collect();
+    let max = radii.iter().copied().fold(1.0, f64::max);
+    radii.into_iter().map(|r| r / max).collect()
+}
+
+// Each pixel's distance from its center, from black for none to white for the
+// largest; unfilled pixels are black.
+pub fn depth_map(placements: &[Placement], width: usize, height: usize) -> GrayImage {
+    let mut map = GrayImage::new(width as u32, height as u32);
+    for (placement, r) in placements.iter().zip(radii(placements)) {
+        let [x, y] = placement.pixel.loc;
+        map.put_pixel(x as u32, y as u32, Luma([(r * 255.0).round() as u8]));
+    }
+    map
+}
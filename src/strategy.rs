@@ -0,0 +1,655 @@
+use rand::prelude::*;
+
+use std::str::FromStr;
+
+use crate::center::Centers;
+use crate::grid::Grid;
+use crate::lookback::{Lookback, Query};
+use crate::openindex::OpenIndex;
+use crate::params::Params;
+use crate::region::RegionMap;
+use crate::rng::{Rngs, Stream};
+use crate::series::{Target, modulate};
+use crate::walk::{WalkSettings, trace, walk_exact};
+use crate::{Color, Location, Pixel, color_dist_sq};
+
+// What the generator knows when a color needs a place. The lookback is never
+// empty: the generator starts new centers itself until there is something to
+// match.
+pub struct Context<'a> {
+    pub params: &'a Params,
+    pub grid: &'a Grid,
+    pub lookback: &'a mut Lookback,
+    pub rngs: &'a mut Rngs,
+    pub centers: &'a Centers,
+    pub regions: &'a RegionMap,
+    // The cells still to fill.
+    pub open: &'a OpenIndex,
+    // Visits to each cell by walks, indexed by x then y, if recorded.
+    pub walks: Option<&'a mut [u32]>,
+    // Pairs of a walk's center and the center whose pixel it last crossed
+    // before ending, if that was another, when --merge-centers is set.
+    pub collisions: Option<&'a mut Vec<[usize; 2]>>,
+    // The lookback query for this color.
+    pub query: Query,
+    // Fraction of the cells placed so far.
+    pub coverage: f64,
+}
+
+impl Context<'_> {
+    pub fn is_open(&self, loc: Location) -> bool {
+        let size = self.params.size;
+        !self.grid.is_filled(loc) && self.params.canvas.contains(loc, size)
+    }
+
+    pub fn modulated(&self, target: Target) -> f64 {
+        modulate(&self.params.modulations, target, self.coverage)
+    }
+
+    // The palette of the pixel's center.
+    pub fn palette(&self, pixel: &Pixel) -> usize {
+        self.centers.get(pixel.center_id).map_or(0, |c| c.palette)
+    }
+}
+
+pub enum Decision {
+    // Fill the open cell loc, continuing from the lookback pixel at parent.
+    // exact is the sub-pixel position the cell stands for, if any, and steps
+    // the cells tried on the way there.
+    Place {
+        loc: Location,
+        center: Location,
+        center_id: usize,
+        parent: Location,
+        exact: Option<[f64; 2]>,
+        steps: usize,
+    },
+    // Start a new center at a random open cell, with this palette.
+    Random {
+        palette: usize,
+    },
+}
+
+// Decides where each color goes. The generator owns the loop: it draws the
+// colors, applies palettes and gravity, starts centers and records the
+// result, so a strategy only chooses the cell.
+pub trait PlacementStrategy {
+    fn place(&mut self, ctx: &mut Context, color: Color) -> Decision;
+}
+
+// Which built-in strategy to use.
+// Written as `spinning`, `continuous`, `frontier` or `dla:STICKINESS:BIAS`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Algorithm {
+    // Walk around the match's center to the next open pixel.
+    #[default]
+    Spinning,
+    // The same walk along the orbit's own curve, off the grid.
+    Continuous,
+    // Fill the open cell beside the match that best fits its neighbors.
+    Frontier,
+    // Diffusion-limited aggregation.
+    Dla {
+        stickiness: f64,
+        bias: f64,
+    },
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        let mut parts = s.split(':');
+        match parts.next().expect("split is nonempty") {
+            "spinning" => Ok(Algorithm::Spinning),
+            "continuous" => Ok(Algorithm::Continuous),
+            "frontier" => Ok(Algorithm::Frontier),
+            "dla" => {
+                let mut arg = |default: f64| match parts.next() {
+                    Some(a) => a
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|v| (0.0..=1.0).contains(v))
+                        .ok_or(format!("dla arguments must be in 0..1, got {a}")),
+                    None => Ok(default),
+                };
+                let stickiness = arg(1.0)?;
+                if stickiness == 0.0 {
+                    return Err("dla stickiness must be above 0".to_string());
+                }
+                let bias = arg(0.2)?;
+                if parts.next().is_some() {
+                    return Err(format!("too many dla arguments in {s}"));
+                }
+                Ok(Algorithm::Dla { stickiness, bias })
+            }
+            other => Err(format!("unknown algorithm {other}")),
+        }
+    }
+}
+
+impl Algorithm {
+    pub fn strategy(self) -> Box<dyn PlacementStrategy> {
+        match self {
+            Algorithm::Spinning => Box::new(Spinning),
+            Algorithm::Continuous => Box::new(Continuous::default()),
+            Algorithm::Frontier => Box::new(Frontier),
+            Algorithm::Dla { stickiness, bias } => Box::new(Dla { stickiness, bias }),
+        }
+    }
+}
+
+fn neighbors(loc: Location, size: usize) -> impl Iterator<Item = Location> {
+    crate::walk::Neighborhood::Eight
+        .offsets()
+        .iter()
+        .filter_map(move |d| {
+            let x = loc[0].checked_add_signed(d[0]).filter(|&x| x < size)?;
+            let y = loc[1].checked_add_signed(d[1]).filter(|&y| y < size)?;
+            Some([x, y])
+        })
+}
+
+fn too_far(params: &Params, color: Color, nearest: &Pixel) -> bool {
+    params
+        .max_match_distance
+        .is_some_and(|max| color_dist_sq(color, nearest.color) as f64 > max * max)
+}
+
+// What the orbit walk does when it finds no open pixel. Written as
+// `random`, `retry[:K]`, `nearest` or `requeue[:K]`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Fallback {
+    // Start a new center at a random open cell.
+    #[default]
+    Random,
+    // Walk from the next K best matches in turn, then fall back to random.
+    Retry(usize),
+    // Fill the open cell closest to the match, continuing its orbit.
+    Nearest,
+    // Set the color aside to try again later, up to K times, then random.
+    Requeue(usize),
+}
+
+impl FromStr for Fallback {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        let (name, arg) = match s.split_once(':') {
+            Some((name, arg)) => (name, Some(arg)),
+            None => (s, None),
+        };
+        let count = |default: usize| match arg {
+            Some(a) => a
+                .parse::<usize>()
+                .ok()
+                .filter(|&k| k > 0)
+                .ok_or(format!("{name} count must be a positive integer, got {a}")),
+            None => Ok(default),
+        };
+        match name {
+            "random" | "nearest" if arg.is_some() => {
+                Err(format!("fallback {name} takes no argument"))
+            }
+            "random" => Ok(Fallback::Random),
+            "retry" => Ok(Fallback::Retry(count(2)?)),
+            "nearest" => Ok(Fallback::Nearest),
+            "requeue" => Ok(Fallback::Requeue(count(3)?)),
+            _ => Err(format!(
+                "unknown fallback {name}, expected random, retry, nearest or requeue"
+            )),
+        }
+    }
+}
+
+// Jittered centers drawn for a pixel before it keeps its match's center.
+const JITTER_TRIES: usize = 8;
+
+// The original: walk the orbit of the best match's center from the match
+// until an open pixel turns up, then jitter the center by the color distance.
+pub struct Spinning;
+
+impl PlacementStrategy for Spinning {
+    fn place(&mut self, ctx: &mut Context, color: Color) -> Decision {
+        let params = ctx.params;
+        let nearest = *ctx.lookback.nearest(&ctx.query).expect("find one");
+        let random = Decision::Random {
+            palette: ctx.palette(&nearest),
+        };
+        if too_far(params, color, &nearest) {
+            return random;
+        }
+        // Past the endgame nearly every walk ends on filled cells, so the
+        // color goes straight to the open cell nearest its match.
+        let endgame = params.endgame.is_some_and(|at| ctx.coverage >= at);
+        if !endgame && let Some(decision) = Spinning::walk_from(ctx, color, nearest) {
+            return decision;
+        }
+        match params.fallback {
+            Fallback::Random | Fallback::Requeue(_) if !endgame => random,
+            Fallback::Retry(k) if !endgame => {
+                let matches: Vec<Pixel> = ctx
+                    .lookback
+                    .ranked(&ctx.query, k + 1)
+                    .into_iter()
+                    .filter(|pixel| pixel.loc != nearest.loc)
+                    .take(k)
+                    .copied()
+                    .collect();
+                matches
+                    .into_iter()
+                    .filter(|pixel| !too_far(params, color, pixel))
+                    .find_map(|pixel| Spinning::walk_from(ctx, color, pixel))
+                    .unwrap_or(random)
+            }
+            _ => match ctx.open.nearest(nearest.loc) {
+                Some(loc) => Spinning::continue_at(ctx, color, nearest, loc, None),
+                None => random,
+            },
+        }
+    }
+}
+
+impl Spinning {
+    fn walk_from(ctx: &mut Context, color: Color, nearest: Pixel) -> Option<Decision> {
+        let params = ctx.params;
+        let size = params.size;
+        let aspect = ctx.regions.at(nearest.loc, size).aspect;
+        let center = ctx.centers.drifted(nearest.center_id, nearest.center, size);
+        let (tilt, stretch, ring) = ctx
+            .centers
+            .get(nearest.center_id)
+            .map_or((0.0, 1.0, 0.0), |c| (c.tilt, c.stretch, c.ring));
+        let ring = ring * ctx.modulated(Target::RingWidth);
+        // On a wrapping canvas the orbit goes round the copy of the center
+        // nearest the start, shifting whichever of the two is further left a
+        // canvas to the right.
+        let (mut center, mut start) = (center, nearest.loc);
+        if params.canvas.wraps() && center[0].abs_diff(start[0]) > size / 2 {
+            if center[0] < start[0] {
+                center[0] += size;
+            } else {
+                start[0] += size;
+            }
+        }
+        let orbit = params
+            .orbit
+            .orbit(center, start, aspect * stretch, tilt, ring, params.spiral);
+        let settings = WalkSettings {
+            neighborhood: params.neighborhood,
+            gap_jump: params.gap_jump,
+            reflect: params.reflect,
+            wrap: params.canvas.wraps(),
+        };
+        let (grid, canvas) = (ctx.grid, params.canvas);
+        let center = center.map(|c| c as f64);
+        let mut walks = ctx.walks.as_deref_mut();
+        let rng = ctx.rngs.get(Stream::Skip);
+        let track = ctx.collisions.is_some();
+        let mut crossed = None;
+        let mut steps = 0;
+        let occupied = Some(grid.occupancy());
+        let found = walk_exact(orbit.as_ref(), start, size, &settings, occupied, |loc| {
+            steps += 1;
+            if let Some(walks) = &mut walks {
+                walks[loc[0] * size + loc[1]] += 1;
+            }
+            if track && let Some(pixel) = grid.get(loc) {
+                crossed = Some(pixel.center_id);
+            }
+            !grid.is_filled(loc)
+                && canvas.contains(loc, size)
+                && params
+                    .arms
+                    .is_none_or(|arms| arms.allows(center, loc, size))
+                && params
+                    .skip_dashes
+                    .is_none_or(|dashes| dashes.allows(center, loc))
+                && (params.skip_prob == 0.0 || !rng.random_bool(params.skip_prob))
+        });
+        if let Some(collisions) = &mut ctx.collisions
+            && let Some(other) = crossed.filter(|&id| id != nearest.center_id)
+        {
+            collisions.push([nearest.center_id, other]);
+        }
+        let (loc, exact) = found?;
+        let mut decision = Spinning::continue_at(ctx, color, nearest, loc, Some(exact));
+        if let Decision::Place { steps: walked, .. } = &mut decision {
+            *walked = steps;
+        }
+        Some(decision)
+    }
+
+    // Places the color at loc as the next pixel of nearest's center.
+    fn continue_at(
+        ctx: &mut Context,
+        color: Color,
+        nearest: Pixel,
+        loc: Location,
+        exact: Option<[f64; 2]>,
+    ) -> Decision {
+        let size = ctx.params.size;
+        let color_dist_sq = color_dist_sq(color, nearest.color);
+        let cont_spread = ctx.regions.at(loc, size).cont_spread * ctx.modulated(Target::ContSpread);
+        let width = (((color_dist_sq as f64).sqrt() * cont_spread) as usize).max(1);
+        let rng = ctx.rngs.get(Stream::Jitter);
+        let mut jitter = || {
+            [0, 1].map(|k| {
+                rng.random_range(
+                    nearest.center[k].saturating_sub(width)..=(nearest.center[k] + width).min(size),
+                )
+            })
+        };
+        let mut center = jitter();
+        // Under --center-gap, a center that strays within the gap of another
+        // is drawn again, and failing that the pixel keeps its own.
+        if !ctx.centers.clear_of(center, Some(nearest.center_id)) {
+            center = (1..JITTER_TRIES)
+                .map(|_| jitter())
+                .find(|&c| ctx.centers.clear_of(c, Some(nearest.center_id)))
+                .unwrap_or(nearest.center);
+        }
+        Decision::Place {
+            loc,
+            center,
+            center_id: nearest.center_id,
+            parent: nearest.loc,
+            exact,
+            steps: 0,
+        }
+    }
+}
+
+// Spinning off the grid. Each pixel keeps the point of its orbit it was
+// placed at and a center anywhere between the cells, and the walk from it
+// follows the orbit's curve around that center, filling the first open cell
+// the curve enters. The grid only says which cells are taken, so the canvas
+// still fills once, while positions, centers and radii stay continuous.
+#[derive(Default)]
+pub struct Continuous {
+    // The pixels placed here, indexed by x then y.
+    spots: Vec<Option<Spot>>,
+}
+
+// Where a pixel sits and the center it turns around, in canvas units.
+#[derive(Debug, Clone, Copy)]
+struct Spot {
+    pos: [f64; 2],
+    center: [f64; 2],
+    center_id: usize,
+}
+
+impl Continuous {
+    // The pixel's spot, as placed if its cell still holds that placement.
+    // Pixels placed otherwise, or whose center has been moved since, stand
+    // at the middle of their cell and turn around the middle of their
+    // center's.
+    fn spot(&self, pixel: &Pixel, size: usize) -> Spot {
+        let middle = |loc: Location| loc.map(|c| c as f64 + 0.5);
+        self.spots[pixel.loc[0] * size + pixel.loc[1]]
+            .filter(|spot| {
+                spot.center_id == pixel.center_id && spot.center.map(|c| c as usize) == pixel.center
+            })
+            .unwrap_or(Spot {
+                pos: middle(pixel.loc),
+                center: middle(pixel.center),
+                center_id: pixel.center_id,
+            })
+    }
+}
+
+impl PlacementStrategy for Continuous {
+    fn place(&mut self, ctx: &mut Context, color: Color) -> Decision {
+        let params = ctx.params;
+        let size = params.size;
+        if self.spots.len() != size * size {
+            self.spots = vec![None; size * size];
+        }
+        let nearest = *ctx.lookback.nearest(&ctx.query).expect("find one");
+        let random = Decision::Random {
+            palette: ctx.palette(&nearest),
+        };
+        if too_far(params, color, &nearest) {
+            return random;
+        }
+        let spot = self.spot(&nearest, size);
+        let aspect = ctx.regions.at(nearest.loc, size).aspect;
+        let (tilt, stretch, ring, offset) = ctx
+            .centers
+            .get(nearest.center_id)
+            .map_or((0.0, 1.0, 0.0, [0.0; 2]), |c| {
+                (c.tilt, c.stretch, c.ring, c.offset)
+            });
+        let ring = ring * ctx.modulated(Target::RingWidth);
+        let mut center = [0, 1].map(|k| (spot.center[k] + offset[k]).clamp(0.0, size as f64));
+        let mut start = spot.pos;
+        // As for Spinning, round the copy of the center nearest the start.
+        if params.canvas.wraps() && (center[0] - start[0]).abs() > size as f64 / 2.0 {
+            if center[0] < start[0] {
+                center[0] += size as f64;
+            } else {
+                start[0] += size as f64;
+            }
+        }
+        let [lattice_center, lattice_start] = [center, start].map(|p| p.map(|c| c - 0.5));
+        let orbit = params.orbit.orbit_exact(
+            lattice_center,
+            lattice_start,
+            aspect * stretch,
+            tilt,
+            ring,
+            params.spiral,
+        );
+        let settings = WalkSettings {
+            neighborhood: params.neighborhood,
+            gap_jump: params.gap_jump,
+            reflect: params.reflect,
+            wrap: params.canvas.wraps(),
+        };
+        let (grid, canvas) = (ctx.grid, params.canvas);
+        let mut steps = 0;
+        let found = trace(orbit.as_ref(), start, size, &settings, |loc| {
+            steps += 1;
+            !grid.is_filled(loc) && canvas.contains(loc, size)
+        });
+        let Some((loc, pos)) = found else {
+            return random;
+        };
+        // The center is jittered by the color distance, as for Spinning, but
+        // to anywhere within it.
+        let dist = (color_dist_sq(color, nearest.color) as f64).sqrt();
+        let cont_spread = ctx.regions.at(loc, size).cont_spread * ctx.modulated(Target::ContSpread);
+        let width = (dist * cont_spread).max(1.0);
+        let rng = ctx.rngs.get(Stream::Jitter);
+        let center = spot
+            .center
+            .map(|c| (c + rng.random_range(-width..=width)).clamp(0.0, size as f64));
+        self.spots[loc[0] * size + loc[1]] = Some(Spot {
+            pos,
+            center,
+            center_id: nearest.center_id,
+        });
+        Decision::Place {
+            loc,
+            center: center.map(|c| c as usize),
+            center_id: nearest.center_id,
+            parent: nearest.loc,
+            exact: Some(pos),
+            steps,
+        }
+    }
+}
+
+// Frontier growth: the color goes next to its best match, in whichever open
+// neighboring cell has filled neighbors closest to it in color. Matches with
+// no open neighbor left are dropped from the lookback.
+pub struct Frontier;
+
+impl PlacementStrategy for Frontier {
+    fn place(&mut self, ctx: &mut Context, color: Color) -> Decision {
+        let size = ctx.params.size;
+        let offsets = ctx.params.neighborhood.offsets();
+        let neighbors = move |loc: Location| {
+            offsets.iter().filter_map(move |d| {
+                let x = loc[0].checked_add_signed(d[0]).filter(|&x| x < size)?;
+                let y = loc[1].checked_add_signed(d[1]).filter(|&y| y < size)?;
+                Some([x, y])
+            })
+        };
+        while let Some(&nearest) = ctx.lookback.nearest(&ctx.query) {
+            if too_far(ctx.params, color, &nearest) {
+                return Decision::Random {
+                    palette: ctx.palette(&nearest),
+                };
+            }
+            let grid = ctx.grid;
+            let best = neighbors(nearest.loc)
+                .filter(|&loc| ctx.is_open(loc))
+                .min_by_key(|&loc| {
+                    let (sum, count) = neighbors(loc)
+                        .filter_map(|n| grid.get(n))
+                        .fold((0, 0), |(sum, count), p| {
+                            (sum + color_dist_sq(color, p.color), count + 1)
+                        });
+                    sum / count.max(1)
+                });
+            match best {
+                Some(loc) => {
+                    return Decision::Place {
+                        loc,
+                        center: nearest.center,
+                        center_id: nearest.center_id,
+                        parent: nearest.loc,
+                        exact: None,
+                        steps: 1,
+                    };
+                }
+                None => ctx.lookback.retain(|p| p.loc != nearest.loc),
+            }
+        }
+        Decision::Random { palette: 0 }
+    }
+}
+
+// Tries at launching a walker before giving up on this color.
+const LAUNCH_TRIES: usize = 32;
+// Distance from its match at which a walker is released.
+const LAUNCH_RADIUS: f64 = 6.0;
+// Color distance over which stickiness falls by a factor of e.
+const STICK_FALLOFF: f64 = 24.0;
+
+// Diffusion-limited aggregation: each color is a walker released on a circle
+// around its best match. It random-walks over open cells, stepping toward the
+// match with probability `bias`. Beside filled pixels it sticks with
+// probability `stickiness`, less the further the nearest of them is in color,
+// and joins that pixel's center. Low stickiness lets walkers slip deeper into
+// the structure, giving denser clusters. A walker that never sticks settles
+// beside its match.
+pub struct Dla {
+    pub stickiness: f64,
+    pub bias: f64,
+}
+
+impl Dla {
+    fn launch(&self, ctx: &mut Context, around: Location) -> Option<Location> {
+        let size = ctx.params.size as f64;
+        for _ in 0..LAUNCH_TRIES {
+            let angle = ctx
+                .rngs
+                .get(Stream::Locations)
+                .random_range(0.0..std::f64::consts::TAU);
+            let loc = [angle.cos(), angle.sin()].map(|d| d * LAUNCH_RADIUS);
+            let loc =
+                [0, 1].map(|k| (around[k] as f64 + loc[k]).round().clamp(0.0, size - 1.0) as usize);
+            if ctx.is_open(loc) {
+                return Some(loc);
+            }
+        }
+        None
+    }
+}
+
+impl PlacementStrategy for Dla {
+    fn place(&mut self, ctx: &mut Context, color: Color) -> Decision {
+        let size = ctx.params.size;
+        // Matches buried in the structure can't be reached.
+        let nearest = loop {
+            let Some(&nearest) = ctx.lookback.nearest(&ctx.query) else {
+                return Decision::Random { palette: 0 };
+            };
+            if neighbors(nearest.loc, size).any(|n| ctx.is_open(n)) {
+                break nearest;
+            }
+            ctx.lookback.retain(|p| p.loc != nearest.loc);
+        };
+        if too_far(ctx.params, color, &nearest) {
+            return Decision::Random {
+                palette: ctx.palette(&nearest),
+            };
+        }
+        let mut cur = nearest.loc;
+        let steps = match self.launch(ctx, nearest.loc) {
+            Some(start) => {
+                cur = start;
+                16 * LAUNCH_RADIUS as usize
+            }
+            None => 0,
+        };
+        let mut walked = 0;
+        for _ in 0..steps {
+            walked += 1;
+            let grid = ctx.grid;
+            let touched = neighbors(cur, size)
+                .filter_map(|n| grid.get(n))
+                .min_by_key(|p| color_dist_sq(color, p.color));
+            let open: Vec<Location> = neighbors(cur, size).filter(|&n| ctx.is_open(n)).collect();
+            let rng = ctx.rngs.get(Stream::Locations);
+            if let Some(touched) = touched
+                && (open.is_empty()
+                    || rng.random::<f64>()
+                        < self.stickiness
+                            * (-(color_dist_sq(color, touched.color) as f64).sqrt()
+                                / STICK_FALLOFF)
+                                .exp())
+            {
+                return Decision::Place {
+                    loc: cur,
+                    center: touched.center,
+                    center_id: touched.center_id,
+                    parent: touched.loc,
+                    exact: None,
+                    steps: walked,
+                };
+            }
+            if open.is_empty() {
+                break;
+            }
+
+            cur = if rng.random::<f64>() < self.bias {
+                let toward = |c: usize, t: usize| c as isize + (t as isize - c as isize).signum();
+                let step = [
+                    toward(cur[0], nearest.loc[0]),
+                    toward(cur[1], nearest.loc[1]),
+                ];
+                open.iter()
+                    .copied()
+                    .find(|n| n.map(|c| c as isize) == step)
+                    .unwrap_or(open[rng.random_range(0..open.len())])
+            } else {
+                open[rng.random_range(0..open.len())]
+            };
+        }
+        // A walker that never sticks, or has nowhere to start, settles beside
+        // its match.
+        let settle = neighbors(nearest.loc, size)
+            .filter(|&n| ctx.is_open(n))
+            .min_by_key(|n| n[0].abs_diff(cur[0]) + n[1].abs_diff(cur[1]))
+            .expect("an open neighbor");
+        Decision::Place {
+            loc: settle,
+            center: nearest.center,
+            center_id: nearest.center_id,
+            parent: nearest.loc,
+            exact: None,
+            steps: walked,
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use std::fmt::Write as _;
+
+use crate::Placement;
+use crate::chains::polylines;
+
+// One <g> per chain, one stroked polyline per unbranched run of continuations,
+// in pixel coordinates with points at pixel centers, on a width by height
+// canvas.
+pub fn write_svg(
+    placements: &[Placement],
+    width: usize,
+    height: usize,
+    path: &str,
+) -> Result<(), String> {
+    let mut out = String::new();
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    )
+    .unwrap();
+    let mut current_chain = None;
+    for line in polylines(placements) {
+        if current_chain != Some(line.chain) {
+            if current_chain.is_some() {
+                out.push_str("</g>\n");
+            }
+            writeln!(
+                out,
+                r#"<g id="chain-{}" fill="none" stroke-linecap="round" stroke-linejoin="round">"#,
+                line.chain
+            )
+            .unwrap();
+            current_chain = Some(line.chain);
+        }
+        let [r, g, b] = line.color;
+        write!(
+            out,
+            r##"<polyline stroke="#{r:02x}{g:02x}{b:02x}" points=""##
+        )
+        .unwrap();
+        for (i, [x, y]) in line.points.iter().enumerate() {
+            if i > 0 {
+                out.push(' ');
+            }
+            write!(out, "{}.5,{}.5", x, y).unwrap();
+        }
+        out.push_str("\"/>\n");
+    }
+    if current_chain.is_some() {
+        out.push_str("</g>\n");
+    }
+    out.push_str("</svg>\n");
+    std::fs::write(path, out).map_err(|e| format!("could not write {path}: {e}"))
+}
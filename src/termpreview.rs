@@ -0,0 +1,190 @@
+#[cfg(feature = "preview")]
+use std::fmt::Write;
+use std::str::FromStr;
+
+#[cfg(feature = "preview")]
+use image::RgbImage;
+#[cfg(feature = "preview")]
+use image::imageops::{FilterType, resize};
+
+#[cfg(feature = "preview")]
+use crate::quantize::quantize_image;
+
+// Width of the preview in pixels unless given.
+const DEFAULT_WIDTH: u32 = 320;
+// Largest base64 payload of one Kitty escape.
+#[cfg(feature = "preview")]
+const KITTY_CHUNK: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Graphics {
+    Kitty,
+    Sixel,
+}
+
+// Shows the image in the terminal, scaled down to width pixels, for
+// checking a render over SSH. Written `auto`, `kitty` or `sixel`, with an
+// optional `:WIDTH`; auto picks Kitty's protocol in terminals known to
+// speak it and sixel elsewhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TermPreview {
+    pub graphics: Option<Graphics>,
+    pub width: u32,
+}
+
+impl FromStr for TermPreview {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        let (name, width) = match s.split_once(':') {
+            Some((name, width)) => (
+                name,
+                width
+                    .parse()
+                    .ok()
+                    .filter(|&w| w > 0)
+                    .ok_or(format!("bad preview width {width}"))?,
+            ),
+            None => (s, DEFAULT_WIDTH),
+        };
+        let graphics = match name {
+            "auto" => None,
+            "kitty" => Some(Graphics::Kitty),
+            "sixel" => Some(Graphics::Sixel),
+            _ => return Err(format!("unknown terminal graphics {name}")),
+        };
+        Ok(Self { graphics, width })
+    }
+}
+
+impl TermPreview {
+    pub fn graphics(&self) -> Graphics {
+        self.graphics.unwrap_or_else(|| {
+            let var = |name| std::env::var(name).unwrap_or_default();
+            let kitty = std::env::var_os("KITTY_WINDOW_ID").is_some()
+                || var("TERM").contains("kitty")
+                || matches!(var("TERM_PROGRAM").as_str(), "WezTerm" | "ghostty");
+            if kitty {
+                Graphics::Kitty
+            } else {
+                Graphics::Sixel
+            }
+        })
+    }
+
+    // The escape sequence drawing the image, followed by a newline.
+    #[cfg(feature = "preview")]
+    pub fn encode(&self, img: &RgbImage) -> String {
+        let (width, height) = img.dimensions();
+        let scaled;
+        let img = if width > self.width {
+            let h = ((height as u64 * self.width as u64) / width as u64).max(1) as u32;
+            scaled = resize(img, self.width, h, FilterType::Triangle);
+            &scaled
+        } else {
+            img
+        };
+        match self.graphics() {
+            Graphics::Kitty => kitty(img),
+            Graphics::Sixel => sixel(img),
+        }
+    }
+}
+
+// Raw RGB in Kitty's graphics protocol, split into chunks; q=2 keeps the
+// terminal from answering.
+#[cfg(feature = "preview")]
+pub fn kitty(img: &RgbImage) -> String {
+    let data = base64(img.as_raw());
+    let chunks: Vec<&str> = data
+        .as_bytes()
+        .chunks(KITTY_CHUNK)
+        .map(|c| std::str::from_utf8(c).expect("base64 is ascii"))
+        .collect();
+    let mut out = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = (i + 1 < chunks.len()) as u8;
+        if i == 0 {
+            write!(
+                out,
+                "\x1b_Ga=T,f=24,q=2,s={},v={},m={more};{chunk}\x1b\\",
+                img.width(),
+                img.height()
+            )
+        } else {
+            write!(out, "\x1b_Gm={more};{chunk}\x1b\\")
+        }
+        .expect("writes to a string");
+    }
+    out.push('\n');
+    out
+}
+
+// Sixel graphics in up to 256 colors: each band of six rows is drawn once
+// per color in it, with runs of the same column pattern compressed.
+#[cfg(feature = "preview")]
+pub fn sixel(img: &RgbImage) -> String {
+    let (width, height) = (img.width() as usize, img.height() as usize);
+    let (palette, indices) = quantize_image(img, 256);
+    let mut out = format!("\x1bPq\"1;1;{width};{height}");
+    for (i, [r, g, b]) in palette.iter().enumerate() {
+        let pct = |c: u8| c as u32 * 100 / 255;
+        write!(out, "#{i};2;{};{};{}", pct(*r), pct(*g), pct(*b)).expect("writes to a string");
+    }
+    for top in (0..height).step_by(6) {
+        let rows = (height - top).min(6);
+        let mut used = vec![false; palette.len()];
+        for y in top..top + rows {
+            for &index in &indices[y * width..(y + 1) * width] {
+                used[index as usize] = true;
+            }
+        }
+        let mut first = true;
+        for color in (0..palette.len()).filter(|&c| used[c]) {
+            if !first {
+                out.push('$');
+            }
+            first = false;
+            write!(out, "#{color}").expect("writes to a string");
+            let column = |x: usize| {
+                let bits = (0..rows)
+                    .filter(|&dy| indices[(top + dy) * width + x] as usize == color)
+                    .fold(0, |bits, dy| bits | 1 << dy);
+                (63 + bits) as u8 as char
+            };
+            let mut x = 0;
+            while x < width {
+                let c = column(x);
+                let run = (x..width).take_while(|&x| column(x) == c).count();
+                if run > 3 {
+                    write!(out, "!{run}{c}").expect("writes to a string");
+                } else {
+                    (0..run).for_each(|_| out.push(c));
+                }
+                x += run;
+            }
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\\n");
+    out
+}
+
+#[cfg(feature = "preview")]
+pub fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for k in 0..4 {
+            if k <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * k) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
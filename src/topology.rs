@@ -0,0 +1,285 @@
+use rand::prelude::*;
+
+use std::collections::HashSet;
+use std::sync::Barrier;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::thread;
+
+use crate::lookback::{Lookback, Query};
+use crate::params::Params;
+use crate::rng::{Rngs, SeededRng, Stream, splitmix64};
+use crate::{Color, Location, Pixel, color_dist_sq};
+
+// The most walkers a run may have. Each keeps a lookback of its own, so
+// memory grows with their number; the threads they run on stop at the
+// machine's cores however many there are.
+pub const MAX_WALKERS: usize = 256;
+
+// Colors each walker tries to place in a round.
+const BATCH: usize = 64;
+// Random cells tried for a new center before scanning for an open one.
+const RANDOM_TRIES: usize = 32;
+
+// A cell's word: 0 while open, else FILLED with the color in the low bytes.
+// Slots without a cell start filled, black.
+const FILLED: u32 = 1 << 24;
+const UNCLAIMED: u32 = u32::MAX;
+
+// A surface the process runs on away from the generator's flat canvas: its
+// cells, where a pixel's center goes, and how a walk goes round one. Cells
+// are Locations, so the lookback holds them as it does on the canvas, and
+// each has a slot of its own below slots().
+pub trait Topology: Sync {
+    // What a walk turns about.
+    type Center: Copy;
+    // The pixels spreads are measured against, as size is on the canvas.
+    fn side(&self) -> usize;
+    fn slots(&self) -> usize;
+    fn slot(&self, loc: Location) -> usize;
+    fn location(&self, slot: usize) -> Location;
+    // Whether a slot holds a cell; those that don't are never filled.
+    fn is_cell(&self, _slot: usize) -> bool {
+        true
+    }
+    // The center of a pixel started at loc, up to width pixels from it.
+    fn start_center(&self, loc: Location, width: f64, rng: &mut SeededRng) -> Self::Center;
+    // A center up to width pixels from center, for a pixel continuing it.
+    fn jitter(&self, center: Self::Center, width: f64, rng: &mut SeededRng) -> Self::Center;
+    // Walks from start round center to the first open cell, if any.
+    fn walk(
+        &self,
+        center: Self::Center,
+        start: Location,
+        is_open: impl Fn(Location) -> bool,
+    ) -> Option<Location>;
+}
+
+// The cells the walkers share: what's in each, and which walker has claimed
+// it this round.
+struct Claims {
+    cells: Vec<AtomicU32>,
+    claims: Vec<AtomicU32>,
+    filled: AtomicUsize,
+    total: usize,
+    barrier: Barrier,
+}
+
+impl Claims {
+    fn is_open(&self, slot: usize) -> bool {
+        self.cells[slot].load(Ordering::Relaxed) == 0
+    }
+}
+
+// Runs the process over topology with the given number of walkers, on up to
+// threads threads, and gives the color of each slot. Each walker has its own
+// colors, random streams and lookback. They work in rounds: in the first half
+// each proposes cells for a batch of colors, walking over the cells as they
+// were when the round began, and claims them with an atomic minimum of its
+// number, so whichever proposes a cell first by number gets it however the
+// walkers are scheduled. In the second half each fills the cells it won and
+// takes its other colors into the next round. The result is the same for a
+// seed and a number of walkers on any number of threads, and a single walker
+// is the plain one-at-a-time process. Only the core options apply: the size,
+// centers, lookback, spreads, color mode, seed, eviction, spatial weight,
+// maximum match distance and the walk settings.
+pub fn grow<T: Topology>(
+    params: &Params,
+    topology: &T,
+    walkers: usize,
+    threads: usize,
+) -> Vec<Color> {
+    let walkers = walkers.max(1);
+    let threads = threads.clamp(1, walkers);
+    let slots = topology.slots();
+    let claims = Claims {
+        cells: (0..slots)
+            .map(|slot| AtomicU32::new(if topology.is_cell(slot) { 0 } else { FILLED }))
+            .collect(),
+        claims: (0..slots).map(|_| AtomicU32::new(UNCLAIMED)).collect(),
+        filled: AtomicUsize::new(0),
+        total: (0..slots).filter(|&slot| topology.is_cell(slot)).count(),
+        barrier: Barrier::new(threads),
+    };
+    thread::scope(|scope| {
+        for t in 0..threads {
+            let claims = &claims;
+            scope.spawn(move || {
+                // A lookback isn't Send, so each thread makes its own walkers.
+                let mut mine: Vec<Walker<T::Center>> = (t..walkers)
+                    .step_by(threads)
+                    .map(|k| Walker::new(params, k, walkers))
+                    .collect();
+                while claims.filled.load(Ordering::Relaxed) < claims.total {
+                    for walker in &mut mine {
+                        walker.propose(params, topology, claims);
+                    }
+                    claims.barrier.wait();
+                    for walker in &mut mine {
+                        walker.settle(topology, claims);
+                    }
+                    claims.barrier.wait();
+                }
+            });
+        }
+    });
+    claims
+        .cells
+        .iter()
+        .map(|cell| {
+            let [r, g, b, _] = cell.load(Ordering::Relaxed).to_le_bytes();
+            [r, g, b]
+        })
+        .collect()
+}
+
+struct Walker<C> {
+    k: usize,
+    walkers: usize,
+    rngs: Rngs,
+    lookback: Lookback,
+    // The centers of the pixels proposed so far, by their center_id.
+    centers: Vec<C>,
+    // This walker's share of the initial centers, still to start.
+    starts: usize,
+    // Colors drawn so far, and those waiting for a cell.
+    drawn: usize,
+    waiting: Vec<Color>,
+    proposed: Vec<(Location, Color)>,
+}
+
+impl<C: Copy> Walker<C> {
+    fn new(params: &Params, k: usize, walkers: usize) -> Self {
+        let seed = splitmix64(params.seed ^ splitmix64(k as u64 + 1));
+        Walker {
+            k,
+            walkers,
+            rngs: if params.streams {
+                Rngs::split(params.rng, seed)
+            } else {
+                Rngs::single(params.rng, seed)
+            },
+            lookback: Lookback::new(params.num_lookback, params.eviction.policy()),
+            centers: vec![],
+            starts: (params.num_centers + walkers - 1 - k) / walkers,
+            drawn: 0,
+            waiting: vec![],
+            proposed: vec![],
+        }
+    }
+
+    fn propose<T: Topology<Center = C>>(&mut self, params: &Params, topology: &T, claims: &Claims) {
+        let total = claims.total;
+        let mut taken: HashSet<Location> = HashSet::new();
+        let mut queue = std::mem::take(&mut self.waiting).into_iter();
+        for _ in 0..BATCH {
+            let (color, new) = match queue.next() {
+                Some(color) => (color, false),
+                None => {
+                    // Walker k draws every walkers'th color of the run.
+                    let i = (self.drawn * self.walkers + self.k).min(total - 1);
+                    self.drawn += 1;
+                    let rng = self.rngs.get(Stream::Color);
+                    (params.mode.next(rng, i, total), true)
+                }
+            };
+            let is_open =
+                |loc: Location| claims.is_open(topology.slot(loc)) && !taken.contains(&loc);
+            let start = new && self.starts > 0;
+            if start {
+                self.starts -= 1;
+            }
+            let nearest = (!self.lookback.is_empty() && !start)
+                .then(|| {
+                    let cursor = self.lookback.front().expect("nonempty").loc;
+                    *self
+                        .lookback
+                        .nearest(&Query {
+                            color,
+                            take: params.num_lookback,
+                            cursor,
+                            spatial_weight: params.spatial_weight,
+                            channel: params.mode.match_channel(),
+                        })
+                        .expect("find one")
+                })
+                .filter(|nearest| {
+                    params
+                        .max_match_distance
+                        .is_none_or(|max| color_dist_sq(color, nearest.color) as f64 <= max * max)
+                });
+            let walked = nearest.and_then(|nearest| {
+                let center = self.centers[nearest.center_id];
+                let loc = topology.walk(center, nearest.loc, is_open)?;
+                let dist = (color_dist_sq(color, nearest.color) as f64).sqrt();
+                let width = (dist * params.cont_spread).max(1.0);
+                let center = topology.jitter(center, width, self.rngs.get(Stream::Jitter));
+                Some((loc, center))
+            });
+            let placed = walked.or_else(|| {
+                let rng = self.rngs.get(Stream::Locations);
+                let loc = random_open(topology, rng, is_open)?;
+                let width = topology.side() as f64 * params.start_spread;
+                let center = topology.start_center(loc, width, self.rngs.get(Stream::Layout));
+                Some((loc, center))
+            });
+            // Every open cell is already proposed by this walker.
+            let Some((loc, center)) = placed else {
+                self.waiting.push(color);
+                break;
+            };
+            taken.insert(loc);
+            claims.claims[topology.slot(loc)].fetch_min(self.k as u32, Ordering::Relaxed);
+            self.centers.push(center);
+            let pixel = Pixel {
+                color,
+                loc,
+                center: loc,
+                center_id: self.centers.len() - 1,
+            };
+            self.lookback.push(pixel, self.rngs.get(Stream::Lookback));
+            self.proposed.push((loc, color));
+        }
+        self.waiting.extend(queue);
+    }
+
+    fn settle<T: Topology>(&mut self, topology: &T, claims: &Claims) {
+        let mut lost = vec![];
+        for (loc, color) in self.proposed.drain(..) {
+            let slot = topology.slot(loc);
+            let claim = &claims.claims[slot];
+            if claim.load(Ordering::Relaxed) == self.k as u32 {
+                let [r, g, b] = color;
+                let word = FILLED | u32::from_le_bytes([r, g, b, 0]);
+                claims.cells[slot].store(word, Ordering::Relaxed);
+                claim.store(UNCLAIMED, Ordering::Relaxed);
+                claims.filled.fetch_add(1, Ordering::Relaxed);
+            } else {
+                lost.push(loc);
+                self.waiting.push(color);
+            }
+        }
+        if !lost.is_empty() {
+            self.lookback.retain(|pixel| !lost.contains(&pixel.loc));
+        }
+    }
+}
+
+// A random open cell: a few random tries, then the first open cell from a
+// random start, so the end of a run doesn't wait on luck.
+fn random_open<T: Topology>(
+    topology: &T,
+    rng: &mut SeededRng,
+    is_open: impl Fn(Location) -> bool,
+) -> Option<Location> {
+    let slots = topology.slots();
+    let open = |slot: usize| {
+        let loc = topology.location(slot);
+        (topology.is_cell(slot) && is_open(loc)).then_some(loc)
+    };
+    (0..RANDOM_TRIES)
+        .find_map(|_| open(rng.random_range(0..slots)))
+        .or_else(|| {
+            let start = rng.random_range(0..slots);
+            (0..slots).find_map(|i| open((start + i) % slots))
+        })
+}
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+use image::RgbImage;
+
+use crate::quantize::quantize_image;
+
+type Corner = [u32; 2];
+
+// The outlines of the cells of a width by height grid where inside holds, as
+// closed loops of cell corners. Each loop runs clockwise on screen around
+// what it encloses, so holes run the other way, and corners along a straight
+// edge are left out. A corner where two cells touch diagonally may join their
+// loops into one; filled even-odd, the shape is the same either way.
+pub fn outlines(width: u32, height: u32, inside: impl Fn(u32, u32) -> bool) -> Vec<Vec<Corner>> {
+    let is_in = |x: i64, y: i64| {
+        x >= 0 && y >= 0 && x < width as i64 && y < height as i64 && inside(x as u32, y as u32)
+    };
+    let mut next: HashMap<Corner, Vec<Corner>> = HashMap::new();
+    for y in 0..height {
+        for x in 0..width {
+            if !inside(x, y) {
+                continue;
+            }
+            let (xi, yi) = (x as i64, y as i64);
+            let mut edge = |from: Corner, to: Corner| next.entry(from).or_default().push(to);
+            if !is_in(xi, yi - 1) {
+                edge([x, y], [x + 1, y]);
+            }
+            if !is_in(xi + 1, yi) {
+                edge([x + 1, y], [x + 1, y + 1]);
+            }
+            if !is_in(xi, yi + 1) {
+                edge([x + 1, y + 1], [x, y + 1]);
+            }
+            if !is_in(xi - 1, yi) {
+                edge([x, y + 1], [x, y]);
+            }
+        }
+    }
+    let mut starts: Vec<Corner> = next.keys().copied().collect();
+    starts.sort_by_key(|&[x, y]| (y, x));
+    let mut loops = vec![];
+    for start in starts {
+        while let Some(mut at) = next.get_mut(&start).and_then(Vec::pop) {
+            let mut corners = vec![start];
+            while at != start {
+                corners.push(at);
+                at = next
+                    .get_mut(&at)
+                    .and_then(Vec::pop)
+                    .expect("every outline closes");
+            }
+            loops.push(straighten(corners));
+        }
+    }
+    loops
+}
+
+// Drops the corners in the middle of straight runs.
+fn straighten(corners: Vec<Corner>) -> Vec<Corner> {
+    let n = corners.len();
+    let turns = |i: usize| {
+        let [a, b, c] = [corners[(i + n - 1) % n], corners[i], corners[(i + 1) % n]];
+        !(a[0] == b[0] && b[0] == c[0] || a[1] == b[1] && b[1] == c[1])
+    };
+    (0..n).filter(|&i| turns(i)).map(|i| corners[i]).collect()
+}
+
+// The image reduced to at most colors colors, each traced into one layer of
+// filled outlines, the most common color first. Cells outside filled, if
+// given, belong to no layer. The outlines follow the pixel edges exactly, so
+// the layers tile the image without gaps or overlaps.
+pub fn trace_svg(image: &RgbImage, filled: Option<&[Vec<bool>]>, colors: usize) -> String {
+    let (width, height) = image.dimensions();
+    let (palette, indices) = quantize_image(image, colors);
+    let cluster = |x: u32, y: u32| {
+        let kept = filled.is_none_or(|filled| filled[x as usize][y as usize]);
+        kept.then(|| indices[(y * width + x) as usize] as usize)
+    };
+    let mut counts = vec![0; palette.len()];
+    for y in 0..height {
+        for x in 0..width {
+            if let Some(c) = cluster(x, y) {
+                counts[c] += 1;
+            }
+        }
+    }
+    let mut order: Vec<usize> = (0..palette.len()).filter(|&c| counts[c] > 0).collect();
+    order.sort_by_key(|&c| std::cmp::Reverse(counts[c]));
+    let mut out = String::new();
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    )
+    .unwrap();
+    for c in order {
+        let [r, g, b] = palette[c];
+        let hex = format!("{r:02x}{g:02x}{b:02x}");
+        writeln!(
+            out,
+            r##"<g id="color-{hex}" inkscape:groupmode="layer" inkscape:label="#{hex}" fill="#{hex}">"##
+        )
+        .unwrap();
+        out.push_str(r#"<path fill-rule="evenodd" d=""#);
+        for (i, corners) in outlines(width, height, |x, y| cluster(x, y) == Some(c))
+            .iter()
+            .enumerate()
+        {
+            if i > 0 {
+                out.push(' ');
+            }
+            let [x, y] = corners[0];
+            write!(out, "M{x} {y}").unwrap();
+            // Edges alternate between across and down.
+            for pair in corners[1..].iter().zip(&corners[..corners.len() - 1]) {
+                let ([x, y], [px, _]) = (*pair.0, *pair.1);
+                if x != px {
+                    write!(out, "H{x}").unwrap();
+                } else {
+                    write!(out, "V{y}").unwrap();
+                }
+            }
+            out.push('Z');
+        }
+        out.push_str("\"/>\n</g>\n");
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+pub fn write_trace(
+    image: &RgbImage,
+    filled: Option<&[Vec<bool>]>,
+    colors: usize,
+    path: &str,
+) -> Result<(), String> {
+    std::fs::write(path, trace_svg(image, filled, colors))
+        .map_err(|e| format!("could not write {path}: {e}"))
+}
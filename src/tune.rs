@@ -0,0 +1,348 @@
+use image::RgbImage;
+
+use crate::rng::splitmix64;
+use crate::{Params, make_image};
+
+// One parameter the tuner adjusts, as a flag and its value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Knob {
+    pub flag: &'static str,
+    pub value: f64,
+    pub min: f64,
+    pub max: f64,
+    // Each step multiplies by this, or adds it for a linear knob.
+    pub step: f64,
+    pub linear: bool,
+    pub integer: bool,
+}
+
+impl Knob {
+    // Where the value sits between min and max, from 0 to 1, on the scale
+    // the knob steps along.
+    pub fn position(&self) -> f64 {
+        let at = if self.linear {
+            (self.value - self.min) / (self.max - self.min)
+        } else {
+            (self.value / self.min).ln() / (self.max / self.min).ln()
+        };
+        at.clamp(0.0, 1.0)
+    }
+
+    pub fn shown(&self) -> String {
+        if self.integer {
+            format!("{}", self.value.round() as u64)
+        } else {
+            format!("{:.3}", self.value)
+        }
+    }
+
+    fn nudge(&mut self, steps: i32) {
+        let value = if self.linear {
+            self.value + self.step * steps as f64
+        } else {
+            self.value.max(self.min) * self.step.powi(steps)
+        };
+        let value = value.clamp(self.min, self.max);
+        self.value = if self.integer { value.round() } else { value };
+    }
+}
+
+// The parameters being tuned, with the rest of the command line kept as
+// given.
+#[derive(Debug, Clone)]
+pub struct Tuner {
+    pub knobs: Vec<Knob>,
+    pub selected: usize,
+    // The arguments that aren't knobs.
+    rest: Vec<String>,
+}
+
+impl Tuner {
+    pub fn new(args: &[String]) -> Result<Self, String> {
+        let params = Params::from_args(args.iter().cloned())?;
+        if params.voxels || params.big.is_some() {
+            return Err("tune cannot be combined with --voxels or --big".to_string());
+        }
+        let knob = |flag, value, (min, max), step, linear, integer| Knob {
+            flag,
+            value,
+            min,
+            max,
+            step,
+            linear,
+            integer,
+        };
+        let knobs = vec![
+            knob(
+                "--centers",
+                params.num_centers as f64,
+                (1.0, 1000.0),
+                1.25,
+                false,
+                true,
+            ),
+            knob(
+                "--lookback",
+                params.num_lookback as f64,
+                (1.0, 20000.0),
+                1.25,
+                false,
+                true,
+            ),
+            knob(
+                "--start-spread",
+                params.start_spread,
+                (0.01, 1.0),
+                1.25,
+                false,
+                false,
+            ),
+            knob(
+                "--cont-spread",
+                params.cont_spread,
+                (0.005, 1.0),
+                1.25,
+                false,
+                false,
+            ),
+            knob(
+                "--seed",
+                params.seed as f64,
+                (0.0, u32::MAX as f64),
+                1.0,
+                true,
+                true,
+            ),
+        ];
+        let mut rest = vec![];
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if knobs.iter().any(|k| k.flag == arg) {
+                args.next();
+            } else {
+                rest.push(arg.clone());
+            }
+        }
+        Ok(Self {
+            knobs,
+            selected: 0,
+            rest,
+        })
+    }
+
+    // Moves the selection, wrapping around.
+    pub fn select(&mut self, by: isize) {
+        let n = self.knobs.len() as isize;
+        self.selected = (self.selected as isize + by).rem_euclid(n) as usize;
+    }
+
+    // Steps the selected knob up or down.
+    pub fn adjust(&mut self, steps: i32) {
+        let knob = &mut self.knobs[self.selected];
+        let before = knob.value;
+        knob.nudge(steps);
+        // Small integers scale by less than one, so step them by one instead.
+        if knob.integer && knob.value == before && steps != 0 {
+            knob.value = (before + steps.signum() as f64).clamp(knob.min, knob.max);
+        }
+    }
+
+    // Jumps to another seed, derived from the current one.
+    pub fn reseed(&mut self) {
+        if let Some(knob) = self.knobs.iter_mut().find(|k| k.flag == "--seed") {
+            knob.value = (splitmix64(knob.value as u64) as u32) as f64;
+        }
+    }
+
+    // The command line for a full render with the current values.
+    pub fn args(&self) -> Vec<String> {
+        let mut args = self.rest.clone();
+        for knob in &self.knobs {
+            args.push(knob.flag.to_string());
+            args.push(knob.shown());
+        }
+        args
+    }
+
+    pub fn params(&self) -> Result<Params, String> {
+        Params::from_args(self.args().into_iter())
+    }
+
+    // The image at a low resolution, leaving out anything written to disk
+    // or announced.
+    pub fn preview(&self, size: usize) -> Result<RgbImage, String> {
+        let full = self.params()?;
+        let params = Params {
+            size: size.max(1),
+            max_seconds: None,
+            checkpoints: vec![],
+            on_complete: vec![],
+            on_milestone: vec![],
+            raw_stdout: false,
+            ..full
+        };
+        Ok(make_image(&params))
+    }
+}
+
+#[cfg(feature = "tui")]
+mod ui {
+    use std::time::Duration;
+
+    use image::RgbImage;
+    use ratatui::buffer::Buffer;
+    use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+    use ratatui::layout::{Constraint, Layout, Rect};
+    use ratatui::style::{Color, Modifier, Style};
+    use ratatui::text::{Line, Span};
+    use ratatui::widgets::{Block, Paragraph, Widget, Wrap};
+    use ratatui::{DefaultTerminal, Frame};
+
+    use super::Tuner;
+
+    // Largest preview side in pixels, so each change redraws quickly.
+    const PREVIEW_MAX: usize = 160;
+    const SLIDER: usize = 20;
+
+    // Draws each cell as two pixels, the upper one in the foreground of a
+    // half block and the lower one in its background.
+    struct Preview<'a>(&'a RgbImage);
+
+    impl Widget for Preview<'_> {
+        fn render(self, area: Rect, buf: &mut Buffer) {
+            let rgb = |x: u32, y: u32| {
+                let [r, g, b] = self.0.get_pixel(x, y).0;
+                Color::Rgb(r, g, b)
+            };
+            let (width, height) = self.0.dimensions();
+            for row in 0..(height.div_ceil(2) as u16).min(area.height) {
+                for col in 0..(width as u16).min(area.width) {
+                    let (x, y) = (col as u32, row as u32 * 2);
+                    if let Some(cell) = buf.cell_mut((area.x + col, area.y + row)) {
+                        cell.set_char('\u{2580}').set_fg(rgb(x, y));
+                        if y + 1 < height {
+                            cell.set_bg(rgb(x, y + 1));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // The preview for some arguments at some size, kept until either changes.
+    struct Shown {
+        args: Vec<String>,
+        size: usize,
+        image: Result<RgbImage, String>,
+    }
+
+    // Runs the tuner until Enter, returning true, or q or Esc, false.
+    pub fn run(tuner: &mut Tuner) -> Result<bool, String> {
+        let mut terminal = ratatui::init();
+        let result = event_loop(&mut terminal, tuner);
+        ratatui::restore();
+        result
+    }
+
+    fn event_loop(terminal: &mut DefaultTerminal, tuner: &mut Tuner) -> Result<bool, String> {
+        let mut shown: Option<Shown> = None;
+        loop {
+            terminal
+                .draw(|frame| draw(frame, tuner, &mut shown))
+                .map_err(|e| format!("could not draw: {e}"))?;
+            if !event::poll(Duration::from_millis(250)).map_err(|e| e.to_string())? {
+                continue;
+            }
+            let Event::Key(key) = event::read().map_err(|e| e.to_string())? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Enter => return Ok(true),
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+                KeyCode::Up | KeyCode::Char('k') => tuner.select(-1),
+                KeyCode::Down | KeyCode::Char('j') => tuner.select(1),
+                KeyCode::Right | KeyCode::Char('l') => tuner.adjust(1),
+                KeyCode::Left | KeyCode::Char('h') => tuner.adjust(-1),
+                KeyCode::PageUp => tuner.adjust(5),
+                KeyCode::PageDown => tuner.adjust(-5),
+                KeyCode::Char('r') => tuner.reseed(),
+                _ => {}
+            }
+        }
+    }
+
+    fn draw(frame: &mut Frame, tuner: &Tuner, shown: &mut Option<Shown>) {
+        let [preview, panel] =
+            Layout::horizontal([Constraint::Min(10), Constraint::Length(44)]).areas(frame.area());
+        let [knobs, help] =
+            Layout::vertical([Constraint::Min(3), Constraint::Length(8)]).areas(panel);
+
+        let block = Block::bordered().title(" preview ");
+        let inner = block.inner(preview);
+        frame.render_widget(block, preview);
+        let size = (inner.width as usize)
+            .min(inner.height as usize * 2)
+            .clamp(1, PREVIEW_MAX);
+        let args = tuner.args();
+        if shown
+            .as_ref()
+            .is_none_or(|s| s.args != args || s.size != size)
+        {
+            *shown = Some(Shown {
+                image: tuner.preview(size),
+                args,
+                size,
+            });
+        }
+        match &shown.as_ref().expect("just drawn").image {
+            Ok(image) => frame.render_widget(Preview(image), inner),
+            Err(e) => {
+                frame.render_widget(Paragraph::new(e.as_str()).wrap(Wrap { trim: true }), inner)
+            }
+        }
+
+        let lines: Vec<Line> = tuner
+            .knobs
+            .iter()
+            .enumerate()
+            .map(|(i, knob)| {
+                let at = (knob.position() * (SLIDER - 1) as f64).round() as usize;
+                let slider: String = (0..SLIDER)
+                    .map(|k| if k == at { '\u{25cf}' } else { '\u{2500}' })
+                    .collect();
+                let style = if i == tuner.selected {
+                    Style::new().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::new()
+                };
+                Line::from(vec![
+                    Span::styled(format!("{:<13}", knob.flag.trim_start_matches('-')), style),
+                    Span::raw(format!(" {slider} ")),
+                    Span::raw(knob.shown()),
+                ])
+            })
+            .collect();
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(" parameters ")),
+            knobs,
+        );
+        frame.render_widget(
+            Paragraph::new(vec![
+                Line::raw("up/down choose, left/right change"),
+                Line::raw("pgup/pgdn change by five steps"),
+                Line::raw("r another seed"),
+                Line::raw("enter renders at full size, q quits"),
+                Line::raw(format!("spinning {}", tuner.args().join(" "))),
+            ])
+            .wrap(Wrap { trim: true })
+            .block(Block::bordered()),
+            help,
+        );
+    }
+}
+
+#[cfg(feature = "tui")]
+pub use ui::run;
@@ -0,0 +1,121 @@
+use rand::prelude::*;
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+// A set with O(1) removal of a given or a uniformly random element.
+pub struct VecMap<T> {
+    vec: Vec<T>,
+    map: HashMap<T, usize>,
+}
+impl<T: Copy + Eq + Hash> VecMap<T> {
+    pub fn new() -> Self {
+        Self {
+            vec: vec![],
+            map: HashMap::new(),
+        }
+    }
+    // The vec must not contain duplicates.
+    pub fn new_from_vec(vec: Vec<T>) -> Self {
+        let map = vec.iter().enumerate().map(|(i, &v)| (v, i)).collect();
+        Self { vec, map }
+    }
+    pub fn len(&self) -> usize {
+        self.vec.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.vec.is_empty()
+    }
+    pub fn contains(&self, item: &T) -> bool {
+        self.map.contains_key(item)
+    }
+    // Returns false if the item was already present.
+    pub fn insert(&mut self, item: T) -> bool {
+        if self.map.contains_key(&item) {
+            return false;
+        }
+        self.map.insert(item, self.vec.len());
+        self.vec.push(item);
+        true
+    }
+    pub fn remove_random<R: Rng>(&mut self, rng: &mut R) -> Option<T> {
+        if self.vec.is_empty() {
+            return None;
+        }
+        let index = rng.random_range(0..self.vec.len());
+        let last = *self.vec.last().unwrap();
+        let out = self.vec.swap_remove(index);
+        self.map.remove(&out);
+        if index != self.vec.len() {
+            self.map.insert(last, index);
+        }
+        Some(out)
+    }
+    // Removes an element chosen with probability proportional to its weight.
+    // Weights must be finite; negative ones count as zero. Takes time linear
+    // in the length, and returns None if every weight is zero.
+    pub fn remove_random_weighted<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        weight: impl Fn(&T) -> f64,
+    ) -> Option<T> {
+        let weights: Vec<f64> = self.vec.iter().map(|item| weight(item).max(0.0)).collect();
+        let total: f64 = weights.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut target = rng.random_range(0.0..total);
+        let index = weights
+            .iter()
+            .position(|&w| {
+                target -= w;
+                target < 0.0
+            })
+            .unwrap_or_else(|| weights.iter().rposition(|&w| w > 0.0).expect("positive"));
+        let item = self.vec[index];
+        self.remove(&item);
+        Some(item)
+    }
+    // In no particular order.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.vec.iter()
+    }
+    pub fn remove(&mut self, item: &T) -> bool {
+        let maybe_index = self.map.remove(item);
+        match maybe_index {
+            Some(index) => {
+                let last = *self.vec.last().unwrap();
+                self.vec.swap_remove(index);
+                if index != self.vec.len() {
+                    self.map.insert(last, index);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl<T: Copy + Eq + Hash> Default for VecMap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy + Eq + Hash> FromIterator<T> for VecMap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::new();
+        for item in iter {
+            set.insert(item);
+        }
+        set
+    }
+}
+
+impl<'a, T: Copy + Eq + Hash> IntoIterator for &'a VecMap<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
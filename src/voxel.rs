@@ -0,0 +1,238 @@
+use image::{ImageBuffer, RgbImage};
+use rand::prelude::*;
+
+use std::collections::VecDeque;
+#[cfg(feature = "files")]
+use std::fs;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+#[cfg(feature = "files")]
+use crate::output::save_png;
+use crate::params::Params;
+use crate::rng::{Rngs, Stream};
+use crate::{Color, color_dist_sq};
+
+pub type Voxel = [usize; 3];
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    color: Color,
+    loc: Voxel,
+    center: Voxel,
+}
+
+// Open cells by flat index, with O(1) removal of a given or random cell.
+// A HashMap per cell, as in VecMap, is too large for a volume.
+struct OpenSet {
+    cells: Vec<u32>,
+    // Position of each cell in `cells`, or u32::MAX once removed.
+    position: Vec<u32>,
+}
+
+impl OpenSet {
+    fn new(len: usize) -> Self {
+        Self {
+            cells: (0..len as u32).collect(),
+            position: (0..len as u32).collect(),
+        }
+    }
+    fn remove_at(&mut self, index: usize) -> u32 {
+        let out = self.cells.swap_remove(index);
+        if index < self.cells.len() {
+            self.position[self.cells[index] as usize] = index as u32;
+        }
+        self.position[out as usize] = u32::MAX;
+        out
+    }
+    fn remove_random<R: Rng>(&mut self, rng: &mut R) -> Option<u32> {
+        if self.cells.is_empty() {
+            return None;
+        }
+        let index = rng.random_range(0..self.cells.len());
+        Some(self.remove_at(index))
+    }
+    fn contains(&self, cell: usize) -> bool {
+        self.position[cell] != u32::MAX
+    }
+    fn remove(&mut self, cell: u32) {
+        let index = self.position[cell as usize];
+        if index != u32::MAX {
+            self.remove_at(index as usize);
+        }
+    }
+}
+
+// The original process on a cube of voxels. Each walk follows a great circle
+// of the sphere through its start, in a plane of random orientation.
+pub struct Volume {
+    pub size: usize,
+    // Colors with x varying fastest, then y, then z; black where unfilled.
+    pub colors: Vec<Color>,
+}
+
+impl Volume {
+    fn index(&self, loc: Voxel) -> usize {
+        (loc[2] * self.size + loc[1]) * self.size + loc[0]
+    }
+
+    pub fn at(&self, loc: Voxel) -> Color {
+        self.colors[self.index(loc)]
+    }
+
+    pub fn slice(&self, z: usize) -> RgbImage {
+        ImageBuffer::from_fn(self.size as u32, self.size as u32, |x, y| {
+            image::Rgb(self.at([x as usize, y as usize, z]))
+        })
+    }
+
+    // One PNG per z slice, named slice-0000.png and so on.
+    #[cfg(feature = "files")]
+    pub fn save_slices(&self, dir: &str, metadata: &[(String, String)]) -> Result<(), String> {
+        fs::create_dir_all(dir).map_err(|e| format!("could not create {dir}: {e}"))?;
+        for z in 0..self.size {
+            save_png(&self.slice(z), &format!("{dir}/slice-{z:04}.png"), metadata)?;
+        }
+        Ok(())
+    }
+
+    // Raw RGB volume behind an attached NRRD header.
+    pub fn save_nrrd(&self, path: &str) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| format!("could not create {path}: {e}"))?;
+        let mut out = BufWriter::new(file);
+        let n = self.size;
+        write!(
+            out,
+            "NRRD0004\ntype: uint8\ndimension: 4\nsizes: 3 {n} {n} {n}\n\
+             kinds: RGB-color space space space\nencoding: raw\n\n"
+        )
+        .and_then(|_| out.write_all(&self.colors.concat()))
+        .map_err(|e| format!("could not write {path}: {e}"))
+    }
+}
+
+pub fn generate_volume(params: &Params) -> Volume {
+    let size = params.size;
+    let mut rngs = if params.streams {
+        Rngs::split(params.rng, params.seed)
+    } else {
+        Rngs::single(params.rng, params.seed)
+    };
+    let total = size * size * size;
+    let mut volume = Volume {
+        size,
+        colors: vec![[0, 0, 0]; total],
+    };
+    let mut open = OpenSet::new(total);
+    let mut lookback: VecDeque<Entry> = VecDeque::with_capacity(params.num_lookback + 1);
+    let to_loc = |cell: u32| {
+        let cell = cell as usize;
+        [cell % size, cell / size % size, cell / (size * size)]
+    };
+    for i in 0..total {
+        let color = params.mode.next(rngs.get(Stream::Color), i, total);
+        let nearest = if i < params.num_centers {
+            None
+        } else {
+            lookback
+                .iter()
+                .min_by_key(|entry| color_dist_sq(color, entry.color))
+                .copied()
+        };
+        let placed = nearest.and_then(|nearest| {
+            let normal = random_unit(rngs.get(Stream::Layout));
+            let loc = walk(nearest.center, nearest.loc, normal, size, |loc| {
+                open.contains(volume.index(loc))
+            })?;
+            let width =
+                ((color_dist_sq(color, nearest.color) as f64).sqrt() * params.cont_spread) as usize;
+            let rng = rngs.get(Stream::Jitter);
+            let center = nearest.center.map(|c| {
+                rng.random_range(c.saturating_sub(width.max(1))..=(c + width.max(1)).min(size - 1))
+            });
+            open.remove(volume.index(loc) as u32);
+            Some(Entry { color, loc, center })
+        });
+        let entry = placed.unwrap_or_else(|| {
+            let loc = to_loc(
+                open.remove_random(rngs.get(Stream::Locations))
+                    .expect("nonempty"),
+            );
+            let width = (size as f64 * params.start_spread) as usize;
+            let rng = rngs.get(Stream::Layout);
+            let center =
+                loc.map(|c| rng.random_range(c.saturating_sub(width)..=(c + width).min(size - 1)));
+            Entry { color, loc, center }
+        });
+        let index = volume.index(entry.loc);
+        volume.colors[index] = entry.color;
+        lookback.push_front(entry);
+        if lookback.len() > params.num_lookback {
+            lookback.pop_back();
+        }
+    }
+    volume
+}
+
+fn random_unit<R: Rng>(rng: &mut R) -> [f64; 3] {
+    loop {
+        let v: [f64; 3] = [
+            rng.random_range(-1.0..1.0),
+            rng.random_range(-1.0..1.0),
+            rng.random_range(-1.0..1.0),
+        ];
+        let len = dot(v, v).sqrt();
+        if len > 1e-3 && len <= 1.0 {
+            return v.map(|c| c / len);
+        }
+    }
+}
+
+// Like the 2D walk, but scoring both the distance from the sphere and the
+// distance from a plane through the center, so the walker circles the sphere.
+fn walk(
+    center: Voxel,
+    start: Voxel,
+    normal: [f64; 3],
+    size: usize,
+    is_open: impl Fn(Voxel) -> bool,
+) -> Option<Voxel> {
+    let center = center.map(|c| c as f64);
+    let offset = |p: [isize; 3]| [0, 1, 2].map(|k| p[k] as f64 - center[k]);
+    let start = start.map(|c| c as isize);
+    let origin = offset(start);
+    // Remove the normal's component along the start, so the plane contains it.
+    let along = dot(origin, normal);
+    let normal = [0, 1, 2].map(|k| normal[k] - along * origin[k] / dot(origin, origin).max(1.0));
+    let normal_len = dot(normal, normal).sqrt().max(1e-9);
+    let radius = dot(origin, origin).sqrt();
+    let score = |p: [isize; 3]| {
+        let d = offset(p);
+        (dot(d, d).sqrt() - radius).abs() + (dot(d, normal) / normal_len).abs()
+    };
+    let mut last = start;
+    let mut cur = start;
+    for _ in 0..(8.0 * radius).ceil() as usize + 8 {
+        let next = (-1..=1)
+            .flat_map(|x| (-1..=1).flat_map(move |y| (-1..=1).map(move |z| [x, y, z])))
+            .filter(|d| *d != [0, 0, 0])
+            .map(|d: [isize; 3]| [cur[0] + d[0], cur[1] + d[1], cur[2] + d[2]])
+            .filter(|&n| n != last)
+            .min_by(|&a, &b| score(a).total_cmp(&score(b)))
+            .expect("Still one left");
+        if next == start || next.iter().any(|&c| c < 0 || c >= size as isize) {
+            return None;
+        }
+        let loc = next.map(|c| c as usize);
+        if is_open(loc) {
+            return Some(loc);
+        }
+        last = cur;
+        cur = next;
+    }
+    None
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
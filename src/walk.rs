@@ -0,0 +1,439 @@
+use std::f64::consts::TAU;
+use std::str::FromStr;
+
+use crate::Location;
+use crate::geometry::{Circle, Epicycle, Frame, Orbit, OrbitWalker};
+use crate::grid::Occupancy;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OrbitShape {
+    Circle,
+    // The phase is in turns.
+    Epicycle { ratio: f64, speed: f64, phase: f64 },
+}
+
+impl FromStr for OrbitShape {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        let mut parts = s.split(':');
+        match parts.next().expect("split is nonempty") {
+            "circle" => Ok(OrbitShape::Circle),
+            "epicycle" => {
+                let mut arg = |default: f64| match parts.next() {
+                    Some(a) => a.parse().map_err(|_| format!("invalid orbit argument {a}")),
+                    None => Ok(default),
+                };
+                Ok(OrbitShape::Epicycle {
+                    ratio: arg(0.3)?,
+                    speed: arg(4.0)?,
+                    phase: 0.0,
+                })
+            }
+            other => Err(format!("unknown orbit {other}")),
+        }
+    }
+}
+
+impl OrbitShape {
+    // The orbit through start around center, its y axis scaled by aspect
+    // after the axes are turned by tilt, in radians, and its radius snapped
+    // to a multiple of ring unless that is 0.
+    pub fn orbit(
+        self,
+        center: Location,
+        start: Location,
+        aspect: f64,
+        tilt: f64,
+        ring: f64,
+        drift: f64,
+    ) -> Box<dyn Orbit> {
+        let [center, start] = [center, start].map(|p| p.map(|c| c as f64));
+        self.orbit_exact(center, start, aspect, tilt, ring, drift)
+    }
+
+    // The same for a center and start anywhere between the lattice points.
+    pub fn orbit_exact(
+        self,
+        center: [f64; 2],
+        start: [f64; 2],
+        aspect: f64,
+        tilt: f64,
+        ring: f64,
+        drift: f64,
+    ) -> Box<dyn Orbit> {
+        let frame = Frame::new(aspect, tilt, ring);
+        match self {
+            OrbitShape::Circle => Box::new(Circle::new(center, start, frame, drift)),
+            OrbitShape::Epicycle {
+                ratio,
+                speed,
+                phase,
+            } => Box::new(Epicycle::new(
+                center,
+                start,
+                frame,
+                ratio,
+                speed,
+                drift,
+                phase * TAU,
+            )),
+        }
+    }
+}
+
+// "out:RATE" or "in:RATE", in pixels of radius per step, as a signed drift.
+pub fn parse_spiral(s: &str) -> Result<f64, String> {
+    let (direction, rate) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected out:RATE or in:RATE, got {s}"))?;
+    let rate: f64 = rate
+        .parse()
+        .map_err(|_| format!("invalid spiral rate {rate}"))?;
+    match direction {
+        "out" => Ok(rate),
+        "in" => Ok(-rate),
+        _ => Err(format!("unknown spiral direction {direction}")),
+    }
+}
+
+// Splits the turn around each center into count sectors and lets walks
+// place only in the first width of each, leaving that many arms. With a
+// twist the sectors turn by that many turns across the canvas width,
+// curving the arms into spirals. Written `N[:WIDTH[:TWIST]]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Arms {
+    pub count: usize,
+    pub width: f64,
+    pub twist: f64,
+}
+
+impl FromStr for Arms {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        let mut parts = s.split(':');
+        let count = parts.next().expect("split is nonempty");
+        let count = count
+            .parse()
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or(format!("arm count must be a positive integer, got {count}"))?;
+        let width = match parts.next() {
+            Some(w) => w
+                .parse()
+                .ok()
+                .filter(|w| *w > 0.0 && *w <= 1.0)
+                .ok_or(format!("arm width must be in (0, 1], got {w}"))?,
+            None => 0.5,
+        };
+        let twist = match parts.next() {
+            Some(t) => t
+                .parse()
+                .ok()
+                .filter(|t: &f64| t.is_finite())
+                .ok_or(format!("invalid arm twist {t}"))?,
+            None => 0.0,
+        };
+        if parts.next().is_some() {
+            return Err(format!("too many arm arguments in {s}"));
+        }
+        Ok(Self {
+            count,
+            width,
+            twist,
+        })
+    }
+}
+
+impl Arms {
+    // Whether loc falls in an arm of the center at center.
+    pub fn allows(&self, center: [f64; 2], loc: Location, size: usize) -> bool {
+        let [dx, dy] = [0, 1].map(|k| loc[k] as f64 + 0.5 - center[k]);
+        let radius = dx.hypot(dy);
+        let turns = dy.atan2(dx) / TAU - self.twist * radius / size as f64;
+        (turns * self.count as f64).rem_euclid(1.0) < self.width
+    }
+}
+
+// Dashed rings: along the circle through a cell around a center, runs of
+// dash pixels of arc open to walks alternate with gaps of gap pixels they
+// pass over. The period is rounded so a whole number fits each circle.
+// Written `DASH:GAP`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dashes {
+    pub dash: f64,
+    pub gap: f64,
+}
+
+impl FromStr for Dashes {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        let (dash, gap) = s
+            .split_once(':')
+            .ok_or(format!("expected DASH:GAP, got {s}"))?;
+        let length = |v: &str| {
+            v.parse()
+                .ok()
+                .filter(|v: &f64| *v > 0.0 && v.is_finite())
+                .ok_or(format!("dash and gap lengths must be positive, got {v}"))
+        };
+        Ok(Self {
+            dash: length(dash)?,
+            gap: length(gap)?,
+        })
+    }
+}
+
+impl Dashes {
+    // Whether loc falls in a dash around the center at center.
+    pub fn allows(&self, center: [f64; 2], loc: Location) -> bool {
+        let [dx, dy] = [0, 1].map(|k| loc[k] as f64 + 0.5 - center[k]);
+        let circumference = TAU * dx.hypot(dy);
+        let periods = (circumference / (self.dash + self.gap)).round().max(1.0);
+        let turns = (dy.atan2(dx) / TAU).rem_euclid(1.0);
+        (turns * periods).fract() < self.dash / (self.dash + self.gap)
+    }
+}
+
+// The lattice moves a walker may take from one step to the next.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Neighborhood {
+    // The 8 surrounding pixels.
+    #[default]
+    Eight,
+    // Horizontal and vertical neighbors only, giving blockier arcs.
+    Four,
+    // Chess knight moves, leaving dashed rings.
+    Knight,
+    // The 16 pixels at distance 2, moving faster and leaving gaps.
+    Ring2,
+}
+
+impl FromStr for Neighborhood {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "8" => Ok(Neighborhood::Eight),
+            "4" => Ok(Neighborhood::Four),
+            "knight" => Ok(Neighborhood::Knight),
+            "ring2" => Ok(Neighborhood::Ring2),
+            _ => Err(format!("unknown neighborhood {s}")),
+        }
+    }
+}
+
+impl Neighborhood {
+    pub fn offsets(self) -> &'static [[isize; 2]] {
+        match self {
+            Neighborhood::Eight => &[
+                [1, 1],
+                [0, 1],
+                [-1, 1],
+                [1, 0],
+                [-1, 0],
+                [1, -1],
+                [0, -1],
+                [-1, -1],
+            ],
+            Neighborhood::Four => &[[0, 1], [1, 0], [-1, 0], [0, -1]],
+            Neighborhood::Knight => &[
+                [1, 2],
+                [-1, 2],
+                [2, 1],
+                [-2, 1],
+                [2, -1],
+                [-2, -1],
+                [1, -2],
+                [-1, -2],
+            ],
+            Neighborhood::Ring2 => &[
+                [2, 2],
+                [1, 2],
+                [0, 2],
+                [-1, 2],
+                [-2, 2],
+                [2, 1],
+                [-2, 1],
+                [2, 0],
+                [-2, 0],
+                [2, -1],
+                [-2, -1],
+                [2, -2],
+                [1, -2],
+                [0, -2],
+                [-1, -2],
+                [-2, -2],
+            ],
+        }
+    }
+    // Orbit steps advanced per move, so time-dependent orbits keep pace.
+    pub fn reach(self) -> usize {
+        match self {
+            Neighborhood::Eight | Neighborhood::Four => 1,
+            Neighborhood::Knight | Neighborhood::Ring2 => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WalkSettings {
+    pub neighborhood: Neighborhood,
+    // Where every cell this many steps around is filled, the walk leaps
+    // them untried, for a single step of its budget, so long walks late in
+    // a run still finish. Needs the filled cells given to walk_exact.
+    pub gap_jump: usize,
+    // Reflect off the canvas border instead of giving up there.
+    pub reflect: bool,
+    // Come back in across the opposite side on leaving by the left or right
+    // edge; start and the orbit may then lie past the right edge.
+    pub wrap: bool,
+}
+
+// Folds a coordinate back into 0..size by mirroring it across the edges.
+fn fold(x: isize, size: usize) -> usize {
+    let size = size as isize;
+    let m = x.rem_euclid(2 * size);
+    (if m < size { m } else { 2 * size - 1 - m }) as usize
+}
+
+// The same fold for a continuous coordinate, with cell k spanning k..k+1.
+fn fold_exact(x: f64, size: usize) -> f64 {
+    let size = size as f64;
+    let m = x.rem_euclid(2.0 * size);
+    if m <= size { m } else { 2.0 * size - m }
+}
+
+// Walks from start along the orbit until an open pixel is found.
+// Gives up on returning to start, leaving the canvas, or running too long.
+// When reflecting, the walker keeps to the unbounded orbit and its position is
+// mirrored into the canvas, as if the center were mirrored across the edge.
+pub fn walk(
+    orbit: &dyn Orbit,
+    start: Location,
+    size: usize,
+    settings: &WalkSettings,
+    is_open: impl Fn(Location) -> bool,
+) -> Option<Location> {
+    walk_exact(orbit, start, size, settings, None, is_open).map(|(loc, _)| loc)
+}
+
+// Like walk, also giving the orbit's own position at the found pixel, in
+// canvas units where cell [x, y] spans x..x+1 and y..y+1. It is kept within a
+// pixel of the cell's middle. With the filled cells in occupied, runs of them
+// are leapt as settings.gap_jump says.
+pub fn walk_exact(
+    orbit: &dyn Orbit,
+    start: Location,
+    size: usize,
+    settings: &WalkSettings,
+    occupied: Option<&Occupancy>,
+    mut is_open: impl FnMut(Location) -> bool,
+) -> Option<(Location, [f64; 2])> {
+    let mut walker = OrbitWalker::along(orbit, start).neighborhood(settings.neighborhood);
+    // The next gap_jump points all lie within this many cells either way.
+    let reach = (settings.gap_jump * settings.neighborhood.reach()) as isize;
+    // Whether the box that far around p lies in the canvas, all filled.
+    let full = |p: [isize; 2]| {
+        let Some(occupied) = occupied.filter(|_| reach > 0) else {
+            return false;
+        };
+        let [low, high] = [p.map(|c| c - reach), p.map(|c| c + reach + 1)];
+        if low.iter().any(|&c| c < 0) || high.iter().any(|&c| c > size as isize) {
+            return false;
+        }
+        let [xs, ys] = [0, 1].map(|k| low[k] as usize..high[k] as usize);
+        occupied.count_in(xs, ys) == (2 * reach + 1).pow(2) as usize
+    };
+    while let Some(next) = walker.next() {
+        let across = |c: isize| c < 0 || c >= size as isize;
+        let outside = (across(next[0]) && !settings.wrap) || across(next[1]);
+        if outside && !settings.reflect {
+            return None;
+        }
+        let loc = if settings.wrap {
+            [
+                next[0].rem_euclid(size as isize) as usize,
+                fold(next[1], size),
+            ]
+        } else {
+            [fold(next[0], size), fold(next[1], size)]
+        };
+        if is_open(loc) {
+            let point = orbit.point(next, walker.step());
+            let exact = [0, 1].map(|i| {
+                let c = point[i].clamp(next[i] as f64 - 1.0, next[i] as f64 + 1.0);
+                if i == 0 && settings.wrap {
+                    (c + 0.5).rem_euclid(size as f64)
+                } else {
+                    fold_exact(c + 0.5, size)
+                }
+            });
+            return Some((loc, exact));
+        }
+        if full(next) && !walker.leap(settings.gap_jump) {
+            return None;
+        }
+    }
+    None
+}
+
+// The longest move along the curve between the points trace tries, in pixels.
+const TRACE_STEP: f64 = 0.25;
+
+// Like walk_exact, but off the lattice: follows the orbit's curve itself in
+// moves of at most TRACE_STEP, trying each cell as the curve enters it, and
+// gives the curve's point there. Start and the point are in canvas units,
+// and the orbit is in lattice units, half a pixel below them. Gives up on
+// coming back to start's cell, leaving the canvas, or running past the
+// orbit's max_steps. Skips no cells the curve crosses by more than a move.
+pub fn trace(
+    orbit: &dyn Orbit,
+    start: [f64; 2],
+    size: usize,
+    settings: &WalkSettings,
+    mut is_open: impl FnMut(Location) -> bool,
+) -> Option<(Location, [f64; 2])> {
+    let cell = |p: [f64; 2]| p.map(|c| (c.max(0.0) as usize).min(size - 1));
+    let home = cell(start);
+    let mut last = home;
+    let mut prev = orbit.curve(0.0);
+    let (mut t, mut dt) = (0.0, TRACE_STEP);
+    while t < orbit.max_steps() {
+        let next = orbit.curve(t + dt);
+        let moved = (next[0] - prev[0]).hypot(next[1] - prev[1]);
+        if moved > TRACE_STEP && dt > TRACE_STEP / 1024.0 {
+            dt /= 2.0;
+            continue;
+        }
+        t += dt;
+        prev = next;
+        if moved < TRACE_STEP / 4.0 {
+            dt *= 2.0;
+        }
+        let p = next.map(|c| c + 0.5);
+        let across = |c: f64| c < 0.0 || c >= size as f64;
+        let outside = (across(p[0]) && !settings.wrap) || across(p[1]);
+        if outside && !settings.reflect {
+            return None;
+        }
+        let pos = [
+            if settings.wrap {
+                p[0].rem_euclid(size as f64)
+            } else {
+                fold_exact(p[0], size)
+            },
+            fold_exact(p[1], size),
+        ];
+        let loc = cell(pos);
+        if loc == last {
+            continue;
+        }
+        if loc == home {
+            return None;
+        }
+        last = loc;
+        if is_open(loc) {
+            return Some((loc, pos));
+        }
+    }
+    None
+}
@@ -0,0 +1,33 @@
+use image::RgbImage;
+
+use crate::{Color, Location};
+
+// An image whose colors the run starts from: the lookback begins with pixels
+// sampled from it, stretched over the canvas, and the first centers sit among
+// them, so new colors settle where the image had similar ones without
+// copying it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WarmStart {
+    image: RgbImage,
+}
+
+impl WarmStart {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let image = image::open(path)
+            .map_err(|e| format!("could not open {path}: {e}"))?
+            .to_rgb8();
+        Ok(Self::new(image))
+    }
+
+    pub fn new(image: RgbImage) -> Self {
+        Self { image }
+    }
+
+    // The image's color at loc on a canvas of size.
+    pub fn at(&self, loc: Location, size: usize) -> Color {
+        let (width, height) = (self.image.width() as usize, self.image.height() as usize);
+        let x = (loc[0] * width / size).min(width - 1);
+        let y = (loc[1] * height / size).min(height - 1);
+        self.image.get_pixel(x as u32, y as u32).0
+    }
+}
@@ -0,0 +1,188 @@
+// Browser bindings, built with `wasm-pack build --target web -- --features wasm`.
+// A page creates a Generator, calls stepMany between animation frames and
+// draws getImageData() into a canvas to show the image growing.
+use image::DynamicImage;
+use js_sys::{Array, Object};
+use wasm_bindgen::Clamped;
+use wasm_bindgen::prelude::*;
+use web_sys::ImageData;
+
+use crate::Params;
+use crate::canvas::{Unfilled, with_alpha};
+
+#[wasm_bindgen(typescript_custom_section)]
+const GENERATOR_PARAMS: &str = r#"
+/**
+ * The command-line flags, in camelCase: startSpread is --start-spread.
+ * true gives a bare flag, and arrays are joined into comma lists.
+ * Flags that read files are not available in the browser.
+ */
+export interface GeneratorParams {
+  size?: number;
+  centers?: number;
+  lookback?: number;
+  startSpread?: number;
+  contSpread?: number;
+  seed?: number;
+  seedText?: string;
+  mode?: string;
+  rng?: "std" | "chacha8" | "chacha20" | "xoshiro256pp";
+  streams?: boolean;
+  orbit?: string;
+  canvas?: string;
+  sampling?: "uniform" | "center" | "edges" | "sparse";
+  eviction?: string;
+  spatialWeight?: number;
+  maxMatchDistance?: number;
+  palette?: string;
+  centerPalettes?: number;
+  timeGradient?: string;
+  coverage?: number;
+  unfilled?: string;
+  transparent?: boolean;
+  multiscale?: number;
+  centerMaxPixels?: number;
+  centerMaxAge?: number;
+  centerDrift?: string;
+  gravity?: number;
+  stencil?: string;
+  [flag: string]: string | number | boolean | number[] | undefined;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "GeneratorParams")]
+    pub type GeneratorParams;
+}
+
+#[wasm_bindgen]
+pub struct Generator {
+    inner: crate::Generator,
+    size: u32,
+    transparent: bool,
+}
+
+#[wasm_bindgen]
+impl Generator {
+    #[wasm_bindgen(constructor)]
+    pub fn new(params: Option<GeneratorParams>) -> Result<Generator, JsError> {
+        let args = match params {
+            Some(params) => to_args(&params)?,
+            None => vec![],
+        };
+        let params = Params::from_args(args.into_iter()).map_err(|e| JsError::new(&e))?;
+        Ok(Self {
+            inner: crate::Generator::new(&params, false),
+            size: params.size as u32,
+            transparent: params.unfilled == Unfilled::Transparent,
+        })
+    }
+
+    // Places up to n pixels, returning how many were placed.
+    #[wasm_bindgen(js_name = stepMany)]
+    pub fn step_many(&mut self, n: usize) -> usize {
+        (0..n).take_while(|_| self.inner.step()).count()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn done(&self) -> bool {
+        self.inner.is_done()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn coverage(&self) -> f64 {
+        self.inner.coverage()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    // The image so far, ready for putImageData.
+    #[wasm_bindgen(js_name = getImageData)]
+    pub fn get_image_data(&self) -> Result<ImageData, JsValue> {
+        let image = self.inner.image();
+        let rgba = if self.transparent {
+            with_alpha(&image, &self.inner.filled())
+        } else {
+            DynamicImage::ImageRgb8(image).to_rgba8()
+        };
+        ImageData::new_with_u8_clamped_array_and_sh(Clamped(rgba.as_raw()), self.size, self.size)
+    }
+}
+
+// The JSON Schema of the parameters, keyed by flag as in --start-spread
+// rather than in camelCase.
+#[wasm_bindgen(js_name = paramsSchema)]
+pub fn params_schema() -> String {
+    crate::schema::json_schema()
+}
+
+// Throws the error new Generator(params) would, without starting one.
+#[wasm_bindgen(js_name = validateParams)]
+pub fn validate_params(params: Option<GeneratorParams>) -> Result<(), JsError> {
+    let args = match params {
+        Some(params) => to_args(&params)?,
+        None => vec![],
+    };
+    Params::from_args(args.into_iter()).map_err(|e| JsError::new(&e))?;
+    Ok(())
+}
+
+fn to_args(params: &JsValue) -> Result<Vec<String>, JsError> {
+    if params.is_undefined() || params.is_null() {
+        return Ok(vec![]);
+    }
+    let object = params
+        .dyn_ref::<Object>()
+        .ok_or_else(|| JsError::new("params must be an object"))?;
+    let mut args = vec![];
+    for entry in Object::entries(object).iter() {
+        let entry: Array = entry.into();
+        let key = entry.get(0).as_string().expect("string keys");
+        let value = entry.get(1);
+        let flag = format!("--{}", kebab_case(&key));
+        if value.is_undefined() || value.as_bool() == Some(false) {
+            continue;
+        }
+        if value.as_bool() == Some(true) {
+            args.push(flag);
+            continue;
+        }
+        let value = if Array::is_array(&value) {
+            let items: Result<Vec<String>, JsError> = Array::from(&value)
+                .iter()
+                .map(|item| scalar(&item, &key))
+                .collect();
+            items?.join(",")
+        } else {
+            scalar(&value, &key)?
+        };
+        args.push(flag);
+        args.push(value);
+    }
+    Ok(args)
+}
+
+fn scalar(value: &JsValue, key: &str) -> Result<String, JsError> {
+    value
+        .as_f64()
+        .map(|n| n.to_string())
+        .or_else(|| value.as_string())
+        .ok_or_else(|| JsError::new(&format!("{key} must be a number or a string")))
+}
+
+fn kebab_case(key: &str) -> String {
+    let mut flag = String::new();
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            flag.push('-');
+            flag.push(c.to_ascii_lowercase());
+        } else {
+            flag.push(c);
+        }
+    }
+    flag
+}
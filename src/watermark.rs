@@ -0,0 +1,147 @@
+use image::RgbImage;
+use ring::digest::{SHA256, digest};
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::signature::{ED25519, Ed25519KeyPair, KeyPair, UnparsedPublicKey};
+
+// A watermark is carried in the lowest bit of each pixel's blue channel, in
+// rows from the top left: MAGIC, then the length of the rest as a u32, then
+// the author, the recipe, the 32-byte public key and the 64-byte Ed25519
+// signature. Strings are a u32 length and UTF-8; numbers are little-endian.
+// The signature covers the author, the recipe and a SHA-256 of the image with
+// those bits cleared, so it vouches for the pixels as well as the parameters.
+const MAGIC: &[u8; 4] = b"SPWM";
+const CONTEXT: &[u8] = b"spinning watermark 1";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Watermark {
+    pub author: String,
+    pub recipe: String,
+    pub public_key: [u8; 32],
+    signature: [u8; 64],
+}
+
+// A signing key, stored as the hex of its 32-byte seed.
+pub struct Key(Ed25519KeyPair);
+
+impl Key {
+    pub fn generate() -> Result<(Self, String), String> {
+        let mut seed = [0; 32];
+        SystemRandom::new()
+            .fill(&mut seed)
+            .map_err(|_| "could not get randomness for a key".to_string())?;
+        let text = hex(&seed);
+        Ok((Self::from_hex(&text)?, text))
+    }
+
+    pub fn from_hex(text: &str) -> Result<Self, String> {
+        let seed = unhex(text.trim()).filter(|seed| seed.len() == 32);
+        let seed = seed.ok_or("a key is 64 hex digits")?;
+        Ed25519KeyPair::from_seed_unchecked(&seed)
+            .map(Self)
+            .map_err(|_| "invalid key".to_string())
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("could not read {path}: {e}"))?;
+        Self::from_hex(&text).map_err(|e| format!("{path}: {e}"))
+    }
+
+    pub fn public_key(&self) -> String {
+        hex(self.0.public_key().as_ref())
+    }
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn message(author: &str, recipe: &str, image: &RgbImage) -> Vec<u8> {
+    let mut cleared = image.clone();
+    cleared.pixels_mut().for_each(|p| p[2] &= !1);
+    let mut message = CONTEXT.to_vec();
+    for part in [author.as_bytes(), recipe.as_bytes()] {
+        message.extend((part.len() as u32).to_le_bytes());
+        message.extend(part);
+    }
+    message.extend(digest(&SHA256, cleared.as_raw()).as_ref());
+    message
+}
+
+// Signs the image and hides the signature in it.
+pub fn embed(image: &mut RgbImage, key: &Key, author: &str, recipe: &str) -> Result<(), String> {
+    let signature = key.0.sign(&message(author, recipe, image));
+    let mut body = vec![];
+    for part in [author.as_bytes(), recipe.as_bytes()] {
+        body.extend((part.len() as u32).to_le_bytes());
+        body.extend(part);
+    }
+    body.extend(key.0.public_key().as_ref());
+    body.extend(signature.as_ref());
+    let mut payload = MAGIC.to_vec();
+    payload.extend((body.len() as u32).to_le_bytes());
+    payload.extend(body);
+    let pixels = image.width() as usize * image.height() as usize;
+    if payload.len() * 8 > pixels {
+        return Err(format!(
+            "the watermark needs {} pixels, more than the image's {pixels}",
+            payload.len() * 8
+        ));
+    }
+    let bits = payload
+        .iter()
+        .flat_map(|byte| (0..8).map(move |i| byte >> i & 1));
+    for (pixel, bit) in image.pixels_mut().zip(bits) {
+        pixel[2] = pixel[2] & !1 | bit;
+    }
+    Ok(())
+}
+
+fn hidden_bytes(image: &RgbImage) -> impl Iterator<Item = u8> + '_ {
+    let bits: Vec<u8> = image.pixels().map(|p| p[2] & 1).collect();
+    (0..bits.len() / 8).map(move |i| (0..8).fold(0, |byte, j| byte | bits[8 * i + j] << j))
+}
+
+// The watermark hidden in the image, if its signature holds.
+pub fn verify(image: &RgbImage) -> Result<Watermark, String> {
+    let mut bytes = hidden_bytes(image);
+    let mut take = |n: usize| -> Result<Vec<u8>, String> {
+        let taken: Vec<u8> = bytes.by_ref().take(n).collect();
+        if taken.len() < n {
+            return Err("the watermark is cut short".to_string());
+        }
+        Ok(taken)
+    };
+    if take(4)? != MAGIC {
+        return Err("no watermark found".to_string());
+    }
+    take(4)?;
+    let mut text = || -> Result<String, String> {
+        let n = u32::from_le_bytes(take(4)?.try_into().expect("took 4"));
+        String::from_utf8(take(n as usize)?).map_err(|_| "the watermark holds bad text".to_string())
+    };
+    let (author, recipe) = (text()?, text()?);
+    let watermark = Watermark {
+        author,
+        recipe,
+        public_key: take(32)?.try_into().expect("took 32"),
+        signature: take(64)?.try_into().expect("took 64"),
+    };
+    UnparsedPublicKey::new(&ED25519, watermark.public_key)
+        .verify(
+            &message(&watermark.author, &watermark.recipe, image),
+            &watermark.signature,
+        )
+        .map_err(|_| "the watermark's signature does not match the image".to_string())?;
+    Ok(watermark)
+}
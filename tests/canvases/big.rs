@@ -0,0 +1,23 @@
+use spinning::big::render_big;
+use spinning::{Params, make_image};
+
+#[test]
+fn big_render_matches_in_memory() {
+    let dir = std::env::temp_dir();
+    for (seed, lookback) in [(1, 100), (2, 20)] {
+        let params = Params {
+            size: 96,
+            seed,
+            num_centers: 5,
+            num_lookback: lookback,
+            ..Params::default()
+        };
+        let scratch = dir.join(format!("spinning-big-{seed}.grid"));
+        let out = dir.join(format!("spinning-big-{seed}.png"));
+        render_big(&params, scratch.to_str().unwrap(), out.to_str().unwrap()).expect("rendered");
+        let big = image::open(&out).expect("decoded").to_rgb8();
+        assert!(big == make_image(&params), "seed {seed} differs");
+        std::fs::remove_file(scratch).ok();
+        std::fs::remove_file(out).ok();
+    }
+}
@@ -0,0 +1,53 @@
+use spinning::concurrent::{generate_concurrent, generate_concurrent_on};
+
+use crate::common::from_line;
+
+#[test]
+fn deterministic_for_walkers_and_seed() {
+    let p = from_line("--size 48 --lookback 200 --concurrent-walkers 4 --seed 5").unwrap();
+    let first = generate_concurrent(&p, 4);
+    for _ in 0..3 {
+        assert_eq!(generate_concurrent(&p, 4).image, first.image);
+    }
+    assert!(first.filled.iter().flatten().all(|&f| f));
+    // Every cell got a color of its own: none is left black.
+    assert!(first.image.pixels().all(|p| p.0 != [0, 0, 0]));
+    assert_ne!(generate_concurrent(&p, 3).image, first.image);
+
+    let other_seed = from_line("--size 48 --lookback 200 --concurrent-walkers 4 --seed 6").unwrap();
+    assert_ne!(generate_concurrent(&other_seed, 4).image, first.image);
+}
+
+#[test]
+fn threads_only_change_the_time_taken() {
+    let p = from_line("--size 32 --lookback 100 --concurrent-walkers 6 --seed 2").unwrap();
+    let one = generate_concurrent_on(&p, 6, 1);
+    assert_eq!(generate_concurrent_on(&p, 6, 4).image, one.image);
+    assert_eq!(generate_concurrent_on(&p, 6, 6).image, one.image);
+}
+
+#[test]
+fn walkers_are_capped() {
+    assert!(from_line("--concurrent-walkers 256").is_ok());
+    let e = from_line("--concurrent-walkers 257").unwrap_err();
+    assert!(e.contains("1..=256"), "{e}");
+    let e = from_line("--size 8 --concurrent-walkers 65").unwrap_err();
+    assert!(e.contains("64 cells"), "{e}");
+}
+
+#[test]
+fn only_takes_core_options() {
+    assert!(from_line("--concurrent-walkers 2 --palette viridis --orbit epicycle").is_ok());
+    assert!(from_line("--concurrent-walkers 0").is_err());
+    for flag in [
+        "--arms 3",
+        "--checkpoints 0.5",
+        "--export run.bin",
+        "--layer a.png",
+    ] {
+        assert!(
+            from_line(&format!("--concurrent-walkers 2 {flag}")).is_err(),
+            "{flag}"
+        );
+    }
+}
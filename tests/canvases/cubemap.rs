@@ -0,0 +1,56 @@
+use spinning::cubemap::generate_cubemap;
+
+use crate::common::params;
+
+#[test]
+fn faces_agree_along_shared_edges() {
+    let map = generate_cubemap(&params(&["--cubemap", "faces", "--size", "24"]).expect("parsed"));
+    let [px, nx, py, ny, pz, nz] = &map.faces;
+    let m = 23;
+    for k in 0..24 {
+        // Around the middle band: nx, pz, px, nz, and back to nx.
+        assert_eq!(pz.get_pixel(m, k), px.get_pixel(0, k));
+        assert_eq!(px.get_pixel(m, k), nz.get_pixel(0, k));
+        assert_eq!(nz.get_pixel(m, k), nx.get_pixel(0, k));
+        assert_eq!(nx.get_pixel(m, k), pz.get_pixel(0, k));
+        // Above and below the front.
+        assert_eq!(py.get_pixel(k, m), pz.get_pixel(k, 0));
+        assert_eq!(ny.get_pixel(k, 0), pz.get_pixel(k, m));
+        // Where the top meets the back and the sides.
+        assert_eq!(py.get_pixel(k, 0), nz.get_pixel(m - k, 0));
+        assert_eq!(py.get_pixel(m, k), px.get_pixel(m - k, 0));
+        assert_eq!(py.get_pixel(0, k), nx.get_pixel(k, 0));
+    }
+    let filled = map
+        .faces
+        .iter()
+        .flat_map(|f| f.pixels())
+        .filter(|p| p.0 != [0; 3]);
+    assert!(filled.count() > 6 * 24 * 24 - 8);
+}
+
+#[test]
+fn the_cross_holds_every_face() {
+    let map = generate_cubemap(&params(&["--cubemap", "cross", "--size", "16"]).expect("parsed"));
+    let cross = map.cross();
+    assert_eq!(cross.dimensions(), (64, 48));
+    assert_eq!(
+        cross.get_pixel(16 + 3, 16 + 5),
+        map.faces[4].get_pixel(3, 5)
+    );
+    assert_eq!(cross.get_pixel(16 + 3, 5), map.faces[2].get_pixel(3, 5));
+    assert_eq!(
+        cross.get_pixel(48 + 3, 16 + 5),
+        map.faces[5].get_pixel(3, 5)
+    );
+    assert_eq!(cross.get_pixel(3, 5).0, [0; 3]);
+}
+
+#[test]
+fn cubemap_flags_are_checked() {
+    assert!(params(&["--cubemap", "sphere"]).is_err());
+    assert!(params(&["--cubemap", "faces", "--slices", "dir"]).is_err());
+    assert!(params(&["--cubemap", "faces", "--arms", "3"]).is_err());
+    let p = params(&["--cubemap", "cross", "--size", "10"]).expect("parsed");
+    assert!(p.voxels);
+}
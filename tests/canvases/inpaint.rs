@@ -0,0 +1,33 @@
+use spinning::chains::{chain_ids, polylines};
+use spinning::generate;
+
+use crate::common::{fixture, parsed};
+
+#[test]
+fn inpainted_runs_draw_chains_from_the_kept_pixels() {
+    let dir = std::env::temp_dir().join(format!("spinning-inpaint-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("made a directory");
+    // A 24 pixel square with a transparent 8 pixel hole in the middle.
+    let hole = fixture("hole.png");
+    let svg = dir.join("e.svg");
+    let params = parsed(&["--inpaint", &hole, "--svg", svg.to_str().unwrap()]);
+    let run = generate(&params, true);
+    assert_eq!(run.placements.len(), 64);
+    // The first placements continue from kept pixels, which aren't recorded.
+    let placed: Vec<_> = run.placements.iter().map(|p| p.pixel.loc).collect();
+    let parent = run.placements[0]
+        .parent
+        .expect("continued from a kept pixel");
+    assert!(!placed.contains(&parent));
+    let chains = chain_ids(&run.placements);
+    assert_eq!(chains.len(), 64);
+    assert!(!polylines(&run.placements).is_empty());
+    spinning::svg::write_svg(
+        &run.placements,
+        params.size,
+        params.size,
+        svg.to_str().unwrap(),
+    )
+    .unwrap();
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,13 @@
+// Surfaces other than the plain square: concurrent walkers, cube maps,
+// spheres, panoramas, big and sharded runs and inpainting.
+
+#[path = "../common/mod.rs"]
+mod common;
+
+mod big;
+mod concurrent;
+mod cubemap;
+mod inpaint;
+mod panorama;
+mod shard;
+mod sphere;
@@ -0,0 +1,68 @@
+use spinning::canvas::Canvas;
+use spinning::generate;
+use spinning::generator::Generator;
+use spinning::stereo::depth_map;
+use spinning::svg::write_svg;
+
+use crate::common::params;
+
+#[test]
+fn a_panorama_fills_a_band_and_crops_to_it() {
+    let p = params(&["--panorama", "128x32", "--lookback", "100", "--seed", "2"]).expect("parsed");
+    assert_eq!((p.size, p.canvas), (128, Canvas::Band(32)));
+    assert_eq!(p.canvas.rows(128), 48..80);
+    let run = generate(&p, true);
+    assert_eq!(run.placements.len(), 128 * 32);
+    assert_eq!(run.image.dimensions(), (128, 32));
+    assert!(run.filled.iter().flatten().all(|&f| f));
+    // Placements are moved up with the band, so they match the image.
+    for pl in &run.placements {
+        let [x, y] = pl.pixel.loc;
+        assert_eq!(run.image.get_pixel(x as u32, y as u32).0, pl.pixel.color);
+    }
+}
+
+// Every output is cropped to the band, not just the image.
+#[test]
+fn other_outputs_are_cropped() {
+    let dir = std::env::temp_dir().join(format!("spinning-panorama-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+    let p = params(&["--panorama", "64x16", "--seed", "3"]).expect("parsed");
+    let run = generate(&p, true);
+    assert_eq!(depth_map(&run.placements, 64, 16).dimensions(), (64, 16));
+    write_svg(&run.placements, 64, 16, &path("band.svg")).unwrap();
+    let svg = std::fs::read_to_string(path("band.svg")).unwrap();
+    assert!(svg.contains(r#"viewBox="0 0 64 16""#));
+    let mut generator = Generator::new(&p, false);
+    while generator.step() {}
+    assert_eq!(generator.image(), run.image);
+    assert_eq!(generator.filled(), run.filled);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn walks_wrap_around_the_sides() {
+    let p = params(&["--panorama", "96x24", "--lookback", "100", "--seed", "5"]).expect("parsed");
+    let run = generate(&p, true);
+    // A continued pixel and the one it continued from on opposite sides.
+    let wrapped = run.placements.iter().filter(|pl| {
+        pl.parent
+            .is_some_and(|parent| parent[0].abs_diff(pl.pixel.loc[0]) > 96 / 2)
+    });
+    assert!(wrapped.count() > 0);
+}
+
+#[test]
+fn panorama_flags_are_checked() {
+    assert!(params(&["--panorama", "100"]).is_err());
+    assert!(params(&["--panorama", "100x0"]).is_err());
+    assert!(params(&["--panorama", "100x200"]).is_err());
+    assert!(params(&["--panorama", "100x20", "--canvas", "disc"]).is_err());
+    assert!(params(&["--panorama", "100x20", "--scale", "2"]).is_err());
+    assert!(
+        !params(&["--panorama", "100x20"])
+            .expect("parsed")
+            .streams_output()
+    );
+}
@@ -0,0 +1,45 @@
+use spinning::make_image;
+use spinning::shard::{Part, merge, render, shard_params, tile};
+
+fn args(extra: &[&str]) -> Vec<String> {
+    ["--size", "41", "--seed", "8"]
+        .iter()
+        .chain(extra)
+        .map(|a| a.to_string())
+        .collect()
+}
+
+#[test]
+fn tiles_cover_the_canvas() {
+    assert_eq!(tile(41, 4, 0), ([0, 0], [21, 21]));
+    assert_eq!(tile(41, 4, 3), ([21, 21], [20, 20]));
+    assert_eq!(tile(41, 9, 5), ([28, 14], [13, 14]));
+    assert!(render(&args(&[]), 3, 0).is_err());
+    assert!(render(&args(&[]), 4, 4).is_err());
+}
+
+#[test]
+fn shards_merge_into_the_render() {
+    let args = args(&[]);
+    let parts: Vec<Part> = (0..4)
+        .map(|i| render(&args, 4, i).expect("rendered"))
+        .collect();
+    // Each part survives its file, and is the tile's own run cut to size.
+    for part in &parts {
+        assert_eq!(&Part::from_bytes(&part.to_bytes()).expect("read"), part);
+        let params = shard_params(&args, 4, part.index).expect("parsed");
+        let run = make_image(&params);
+        assert_eq!(part.image.get_pixel(3, 5), run.get_pixel(3, 5));
+    }
+    let (image, filled) = merge(&parts).expect("merged");
+    assert_eq!(image.dimensions(), (41, 41));
+    assert!(filled.iter().flatten().all(|&f| f));
+    assert_eq!(image.get_pixel(22, 23), parts[3].image.get_pixel(1, 2));
+    // In any order, the same image.
+    let reversed: Vec<Part> = parts.iter().rev().cloned().collect();
+    assert_eq!(merge(&reversed).expect("merged").0, image);
+    assert!(merge(&parts[..3]).is_err());
+    assert!(merge(&[parts[0].clone(), parts[0].clone()]).is_err());
+    let other = render(&self::args(&["--centers", "3"]), 4, 1).expect("rendered");
+    assert!(merge(&[parts[0].clone(), other, parts[2].clone(), parts[3].clone()]).is_err());
+}
@@ -0,0 +1,45 @@
+use image::RgbImage;
+use spinning::color_dist_sq;
+use spinning::sphere::generate_sphere;
+
+use crate::common::params;
+
+fn column_distance(image: &RgbImage, a: u32, b: u32) -> u64 {
+    (0..image.height())
+        .map(|y| color_dist_sq(image.get_pixel(a, y).0, image.get_pixel(b, y).0) as u64)
+        .sum()
+}
+
+#[test]
+fn the_sphere_is_twice_as_wide_and_full() {
+    let p = params(&["--sphere", "--size", "48", "--seed", "3"]).expect("parsed");
+    let image = generate_sphere(&p);
+    assert_eq!(image.dimensions(), (96, 48));
+    assert!(image.pixels().filter(|p| p.0 == [0; 3]).count() < 2);
+    assert_eq!(image, generate_sphere(&p));
+}
+
+#[test]
+fn orbits_run_on_across_the_seam() {
+    let p = params(&["--sphere", "--size", "48", "--seed", "8"]).expect("parsed");
+    let image = generate_sphere(&p);
+    // The two edges meet on the sphere, so they look about as alike as
+    // neighboring columns do, and more than the left edge and the middle.
+    let seam = column_distance(&image, 0, 95);
+    let apart = column_distance(&image, 0, 48);
+    let neighbors = (0..95)
+        .map(|x| column_distance(&image, x, x + 1))
+        .sum::<u64>()
+        / 95;
+    assert!(
+        seam < apart && seam < 2 * neighbors,
+        "{seam} {apart} {neighbors}"
+    );
+}
+
+#[test]
+fn sphere_flags_are_checked() {
+    assert!(params(&["--sphere", "--nrrd", "x.nrrd"]).is_err());
+    assert!(params(&["--sphere", "--cubemap", "cross"]).is_err());
+    assert!(params(&["--sphere", "--arms", "3"]).is_err());
+}
@@ -0,0 +1,100 @@
+use std::process::Command;
+
+use spinning::color::ColorSource;
+use spinning::cvd::{DEFICIENCIES, Deficiency, companion_path, parse_deficiencies};
+use spinning::palette::{COLORBLIND_SAFE, Named, OKABE_ITO, named};
+use spinning::{Color, color_dist_sq};
+
+use crate::common::from_line;
+
+#[test]
+fn simulations_keep_grays_and_merge_confused_colors() {
+    for deficiency in DEFICIENCIES {
+        for v in [0, 64, 128, 255] {
+            let gray = deficiency.simulate([v; 3]);
+            assert!(gray.iter().all(|&c| c.abs_diff(v) <= 1), "{gray:?}");
+        }
+    }
+    let (red, green, blue) = ([220, 30, 30], [40, 160, 40], [40, 40, 220]);
+    let apart = color_dist_sq(red, green);
+    for deficiency in [Deficiency::Protanopia, Deficiency::Deuteranopia] {
+        let seen = color_dist_sq(deficiency.simulate(red), deficiency.simulate(green));
+        assert!(seen * 4 < apart, "{deficiency:?}: {seen} of {apart}");
+    }
+    let tritan = Deficiency::Tritanopia;
+    assert!(color_dist_sq(tritan.simulate(red), tritan.simulate(green)) * 2 > apart);
+    let teal = [40, 120, 120];
+    assert!(
+        color_dist_sq(tritan.simulate(blue), tritan.simulate(teal)) * 4 < color_dist_sq(blue, teal)
+    );
+}
+
+#[test]
+fn safe_schemes_stay_apart_under_every_deficiency() {
+    let schemes: Vec<&[Color]> = COLORBLIND_SAFE
+        .iter()
+        .map(|name| match named(name).expect("named") {
+            Named::Scheme(colors) | Named::Colormap(colors) => colors,
+        })
+        .collect();
+    for colors in schemes {
+        for deficiency in DEFICIENCIES {
+            for (i, &a) in colors.iter().enumerate() {
+                for &b in &colors[i + 1..] {
+                    let seen = color_dist_sq(deficiency.simulate(a), deficiency.simulate(b));
+                    assert!(seen >= 300, "{a:?} and {b:?} under {deficiency:?}");
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn deficiency_lists_parse() {
+    assert_eq!(parse_deficiencies("all").unwrap(), DEFICIENCIES.to_vec());
+    assert_eq!(
+        parse_deficiencies("tritan,deuteranopia,tritanopia").unwrap(),
+        vec![Deficiency::Tritanopia, Deficiency::Deuteranopia]
+    );
+    assert!(parse_deficiencies("achromatopsia").is_err());
+    assert_eq!(
+        companion_path("out/piece.png", Deficiency::Protanopia),
+        "out/piece-protanopia.png"
+    );
+}
+
+#[test]
+fn colorblind_safe_keeps_to_safe_palettes() {
+    let p = from_line("--colorblind-safe").expect("parsed");
+    assert!(matches!(&p.mode, ColorSource::Palette(colors) if colors == OKABE_ITO));
+    let p = from_line("--colorblind-safe --palette cividis").expect("parsed");
+    assert!(matches!(p.mode, ColorSource::Colormap(_)));
+    assert!(from_line("--colorblind-safe --palette magma").is_err());
+    assert!(from_line("--colorblind-safe --palette ff0000,00ff00").is_err());
+    assert!(from_line("--colorblind-safe --mode grayscale").is_err());
+    assert!(from_line("--palette magma").is_ok());
+}
+
+#[test]
+fn companions_are_written_beside_the_output() {
+    assert!(from_line("--simulate-cvd all --voxels --slices s").is_err());
+    assert!(!from_line("--simulate-cvd protan").unwrap().streams_output());
+
+    let dir = std::env::temp_dir().join(format!("spinning-cvd-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("made a directory");
+    let out = dir.join("piece.png");
+    let output = Command::new(env!("CARGO_BIN_EXE_spinning"))
+        .args(["--size", "24", "--simulate-cvd", "deutan,tritan", "--out"])
+        .arg(&out)
+        .output()
+        .expect("ran");
+    assert!(output.status.success());
+    let image = image::open(&out).expect("saved").to_rgb8();
+    for deficiency in [Deficiency::Deuteranopia, Deficiency::Tritanopia] {
+        let path = companion_path(out.to_str().unwrap(), deficiency);
+        let seen = image::open(&path).expect("saved companion").to_rgb8();
+        assert_eq!(seen, deficiency.simulate_image(&image));
+    }
+    assert!(!dir.join("piece-protanopia.png").exists());
+    std::fs::remove_dir_all(&dir).ok();
+}
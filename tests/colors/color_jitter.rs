@@ -0,0 +1,37 @@
+use spinning::generate;
+
+use crate::common::params;
+
+#[test]
+fn grain_leaves_the_layout_alone() {
+    let base = ["--size", "48", "--lookback", "200", "--seed", "3"];
+    let plain = generate(&params(&base).expect("parsed"), true);
+    let grainy = generate(
+        &params(&[&base[..], &["--color-jitter", "10"]].concat()).expect("parsed"),
+        true,
+    );
+    assert_eq!(plain.placements.len(), grainy.placements.len());
+    let mut diffs = vec![];
+    for (a, b) in plain.placements.iter().zip(&grainy.placements) {
+        assert_eq!(a.pixel.loc, b.pixel.loc);
+        for c in 0..3 {
+            diffs.push((a.pixel.color[c] as f64 - b.pixel.color[c] as f64).abs());
+        }
+    }
+    // Half-normal noise of sigma 10 averages 8, a little less where it clamps.
+    let mean = diffs.iter().sum::<f64>() / diffs.len() as f64;
+    assert!(mean > 5.0 && mean < 9.0, "{mean}");
+    assert_ne!(plain.image, grainy.image);
+}
+
+#[test]
+fn color_jitter_must_be_nonnegative() {
+    assert!(params(&["--color-jitter", "-1"]).is_err());
+    assert!(params(&["--color-jitter", "5", "--voxels"]).is_err());
+    assert_eq!(
+        params(&["--color-jitter", "2.5"])
+            .expect("parsed")
+            .color_jitter,
+        2.5
+    );
+}
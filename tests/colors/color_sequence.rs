@@ -0,0 +1,57 @@
+use spinning::generate;
+use spinning::palette::{load_sequence, parse_sequence};
+
+use crate::common::params;
+
+fn temp(name: &str, bytes: &[u8]) -> String {
+    let path = std::env::temp_dir().join(format!("spinning_seq_{}_{name}", std::process::id()));
+    std::fs::write(&path, bytes).expect("wrote");
+    path.to_str().expect("utf8").to_string()
+}
+
+#[test]
+fn placements_take_the_colors_in_order() {
+    let colors: Vec<u8> = (0..400u32)
+        .flat_map(|i| [i as u8, (i / 2) as u8, 7])
+        .collect();
+    let path = temp("order.rgb", &colors);
+    let p = params(&["--size", "20", "--color-sequence", &path]).expect("parsed");
+    let run = generate(&p, true);
+    assert_eq!(run.placements.len(), 400);
+    let mut placed: Vec<_> = run.placements.iter().map(|p| p.pixel.color).collect();
+    let mut given: Vec<_> = colors.chunks(3).map(|c| [c[0], c[1], c[2]]).collect();
+    placed.sort();
+    given.sort();
+    assert_eq!(placed, given);
+    std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn a_short_sequence_starts_over_with_a_warning() {
+    let path = temp("short.csv", b"r,g,b\n255,0,0\n# blue\n0000ff\n");
+    assert_eq!(
+        load_sequence(&path).expect("loaded"),
+        vec![[255, 0, 0], [0, 0, 255]]
+    );
+    let p = params(&["--size", "8", "--color-sequence", &path]).expect("parsed");
+    assert!(p.warnings().iter().any(|w| w.contains("starts over")));
+    let run = generate(&p, true);
+    let reds = run
+        .placements
+        .iter()
+        .filter(|p| p.pixel.color == [255, 0, 0]);
+    assert_eq!(reds.count(), 32);
+    std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn bad_sequences_are_refused() {
+    assert!(parse_sequence("10,20,30\n10,20\n").is_err());
+    assert!(parse_sequence("1,2,3\n10,20,300\n").is_err());
+    let path = temp("odd.rgb", &[1, 2, 3, 4]);
+    assert!(params(&["--color-sequence", &path]).is_err());
+    std::fs::remove_file(path).ok();
+    let path = temp("empty.csv", b"# nothing\n");
+    assert!(params(&["--color-sequence", &path]).is_err());
+    std::fs::remove_file(path).ok();
+}
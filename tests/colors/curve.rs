@@ -0,0 +1,73 @@
+use spinning::curve::{Curve, hilbert_point, morton_point};
+use spinning::generate;
+
+use crate::common::params;
+
+#[test]
+fn hilbert_steps_are_unit_moves() {
+    let mut seen = std::collections::HashSet::new();
+    let mut last = hilbert_point(0);
+    assert_eq!(last, [0, 0, 0]);
+    for index in 1..1 << 15 {
+        let point = hilbert_point(index);
+        let moved: u32 = (0..3).map(|k| point[k].abs_diff(last[k])).sum();
+        assert_eq!(moved, 1, "at {index}");
+        assert!(seen.insert(point));
+        last = point;
+    }
+    // The first 8^5 cells fill a 32-cube.
+    assert!(seen.iter().all(|p| p.iter().all(|&c| c < 32)));
+    assert_eq!(morton_point(0b111_000), [2, 2, 2]);
+    assert_eq!(morton_point(0b100), [1, 0, 0]);
+}
+
+#[test]
+fn curve_colors_stay_close_and_turn_back() {
+    let mut curve: Curve = "hilbert".parse().unwrap();
+    curve.step = Some(1);
+    let colors: Vec<_> = (0..100).map(|i| curve.color(i, 100)).collect();
+    assert!(colors.windows(2).all(|w| {
+        (0..3)
+            .map(|k| w[0][k].abs_diff(w[1][k]) as u32)
+            .sum::<u32>()
+            == 1
+    }));
+    // Past the end of the curve the walk retraces it.
+    curve.offset = 1.0 - 2.0 / (1u64 << 24) as f64;
+    assert_eq!(curve.color(0, 10), curve.color(3, 10));
+    assert_eq!(curve.color(1, 10), curve.color(2, 10));
+}
+
+#[test]
+fn curve_flags() {
+    let args = [
+        "--size",
+        "40",
+        "--curve",
+        "morton:lch",
+        "--curve-step",
+        "50",
+    ];
+    let run = generate(&params(&args).unwrap(), false);
+    assert!(run.filled.iter().flatten().all(|&f| f));
+    assert_eq!(generate(&params(&args).unwrap(), false).image, run.image);
+    assert!(params(&["--curve", "peano"]).is_err());
+    assert!(params(&["--curve", "hilbert:hsv"]).is_err());
+    assert!(params(&["--curve-step", "4"]).is_err());
+    assert!(params(&["--curve", "hilbert", "--curve-offset", "1.5"]).is_err());
+    assert!(params(&["--curve", "hilbert", "--curve-step", "0"]).is_err());
+}
+
+#[test]
+fn huge_curve_steps_wrap_round() {
+    let mut curve: Curve = "hilbert".parse().unwrap();
+    curve.step = Some(u64::MAX);
+    let huge: Vec<_> = (0..1000).map(|i| curve.color(i, 1000)).collect();
+    curve.step = Some(u64::MAX % (1 << 25));
+    let reduced: Vec<_> = (0..1000).map(|i| curve.color(i, 1000)).collect();
+    assert_eq!(huge, reduced);
+    let step = u64::MAX.to_string();
+    let args = ["--size", "8", "--curve", "hilbert", "--curve-step", &step];
+    let run = generate(&params(&args).unwrap(), false);
+    assert!(run.filled.iter().flatten().all(|&f| f));
+}
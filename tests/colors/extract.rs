@@ -0,0 +1,37 @@
+use image::{Rgb, RgbImage};
+use spinning::extract::extract_palette;
+use spinning::palette::{format_palettes, parse_palettes};
+use spinning::print::{from_lab, lab};
+
+use crate::common::fixture;
+
+#[test]
+fn lab_round_trips() {
+    for r in (0..=255).step_by(15) {
+        for g in (0..=255).step_by(15) {
+            for b in (0..=255).step_by(15) {
+                assert_eq!(from_lab(lab([r, g, b])), [r, g, b]);
+            }
+        }
+    }
+}
+
+#[test]
+fn flat_areas_give_their_colors() {
+    let colors = [[200, 30, 40], [20, 40, 160], [240, 230, 200], [10, 10, 10]];
+    let img = RgbImage::from_fn(40, 40, |x, y| Rgb(colors[(x / 20 + 2 * (y / 20)) as usize]));
+    let dark_to_light = vec![colors[3], colors[1], colors[0], colors[2]];
+    assert_eq!(extract_palette(&img, 4), dark_to_light);
+    assert_eq!(extract_palette(&img, 10), dark_to_light);
+    assert_eq!(extract_palette(&img, 2).len(), 2);
+}
+
+#[test]
+fn gradients_give_as_many_distinct_colors_as_asked() {
+    let img = image::open(fixture("gradient.png")).unwrap().to_rgb8();
+    let palette = extract_palette(&img, 12);
+    assert_eq!(palette.len(), 12);
+    assert_eq!(palette, extract_palette(&img, 12));
+    let text = format_palettes(std::slice::from_ref(&palette));
+    assert_eq!(parse_palettes(&text).expect("parsed"), vec![palette]);
+}
@@ -0,0 +1,79 @@
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use spinning::color::{ColorSource, hsv_to_rgb};
+use spinning::generate;
+use spinning::harmony::{Harmony, HarmonyRule};
+
+use crate::common::params;
+
+// Hue in degrees of a saturated color.
+fn hue([r, g, b]: [u8; 3]) -> f64 {
+    let [r, g, b] = [r, g, b].map(|c| c as f64);
+    let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+    let d = max - min;
+    let h = if max == r {
+        (g - b) / d
+    } else if max == g {
+        (b - r) / d + 2.0
+    } else {
+        (r - g) / d + 4.0
+    };
+    (h * 60.0).rem_euclid(360.0)
+}
+
+#[test]
+fn hsv_corners() {
+    assert_eq!(hsv_to_rgb(0.0, 1.0, 1.0), [255, 0, 0]);
+    assert_eq!(hsv_to_rgb(120.0, 1.0, 1.0), [0, 255, 0]);
+    assert_eq!(hsv_to_rgb(240.0, 1.0, 0.5), [0, 0, 128]);
+    assert_eq!(hsv_to_rgb(360.0, 0.0, 1.0), [255, 255, 255]);
+}
+
+#[test]
+fn colors_stay_near_the_rule_hues() {
+    let harmony = Harmony {
+        rule: HarmonyRule::Triadic,
+        base_hue: 200.0,
+    };
+    assert_eq!(harmony.hues(), vec![200.0, 320.0, 80.0]);
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut near = [0; 3];
+    for _ in 0..3000 {
+        let h = hue(harmony.next(&mut rng));
+        let k = harmony
+            .hues()
+            .iter()
+            .position(|&t| {
+                let d = (h - t).rem_euclid(360.0);
+                d.min(360.0 - d) <= 14.0
+            })
+            .expect("near a rule hue");
+        near[k] += 1;
+    }
+    assert!(near.iter().all(|&n| n > 800), "{near:?}");
+}
+
+#[test]
+fn harmony_flags() {
+    let p = params(&["--harmony", "complementary", "--base-hue", "30"]).unwrap();
+    let ColorSource::Harmony(h) = p.mode else {
+        panic!("harmony mode");
+    };
+    assert_eq!(h.hues(), vec![30.0, 210.0]);
+    let seeded = |seed| match params(&["--harmony", "analogous", "--seed", seed])
+        .unwrap()
+        .mode
+    {
+        ColorSource::Harmony(h) => h.base_hue,
+        _ => panic!("harmony mode"),
+    };
+    assert_eq!(seeded("4"), seeded("4"));
+    assert_ne!(seeded("4"), seeded("5"));
+    let run = generate(
+        &params(&["--size", "30", "--harmony", "tetradic"]).unwrap(),
+        false,
+    );
+    assert!(run.filled.iter().flatten().all(|&f| f));
+    assert!(params(&["--harmony", "pentadic"]).is_err());
+    assert!(params(&["--base-hue", "30"]).is_err());
+}
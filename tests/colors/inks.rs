@@ -0,0 +1,53 @@
+use spinning::Generator;
+use spinning::color::ColorSource;
+use spinning::palette::Inks;
+
+use crate::common::params;
+
+#[test]
+fn inks_keep_to_their_budget_in_every_prefix() {
+    let inks: Inks = "1f2a44:40,f3e9d2:30,b7410e:20,d4a017:10".parse().unwrap();
+    assert_eq!(inks.shares, vec![0.4, 0.3, 0.2, 0.1]);
+    let mut counts = [0usize; 4];
+    for i in 0..5000 {
+        let k = inks.colors.iter().position(|&c| c == inks.pick(i)).unwrap();
+        counts[k] += 1;
+        if i % 500 == 499 {
+            for (n, share) in counts.iter().zip(&inks.shares) {
+                assert!(
+                    (*n as f64 - share * (i + 1) as f64).abs() <= 3.0,
+                    "{counts:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn the_report_matches_the_image() {
+    let p = params(&["--size", "50", "--inks", "000080:3,ffffff:1", "--seed", "2"]).unwrap();
+    let ColorSource::Inks(inks) = &p.mode else {
+        panic!("inks mode");
+    };
+    let mut generator = Generator::new(&p, false);
+    while generator.step() {}
+    let report = inks.report(generator.placed_colors());
+    assert_eq!(report.len(), 2);
+    assert!(
+        report[0].starts_with("ink 000080: 1875 pixels, 75.00%"),
+        "{}",
+        report[0]
+    );
+    assert!(
+        report[1].starts_with("ink ffffff: 625 pixels, 25.00%"),
+        "{}",
+        report[1]
+    );
+}
+
+#[test]
+fn ink_arguments_are_checked() {
+    assert!(params(&["--inks", "ff0000"]).is_err());
+    assert!(params(&["--inks", "ff0000:0,00ff00:1"]).is_err());
+    assert!(params(&["--inks", "ff0000:1", "--center-palettes"]).is_err());
+}
@@ -0,0 +1,20 @@
+use spinning::color::{lerp, sample_colormap};
+use spinning::linear::{from_linear, to_linear};
+
+#[test]
+fn bytes_round_trip() {
+    for v in 0..=255u8 {
+        assert_eq!(from_linear(to_linear(v)), v);
+    }
+    assert_eq!(to_linear(0), 0.0);
+    assert_eq!(to_linear(255), 1.0);
+}
+
+#[test]
+fn mixes_in_linear_light() {
+    assert_eq!(lerp([0, 0, 0], [255, 255, 255], 0.5), [188, 188, 188]);
+    assert_eq!(lerp([10, 20, 30], [200, 100, 0], 0.0), [10, 20, 30]);
+    assert_eq!(lerp([10, 20, 30], [200, 100, 0], 1.0), [200, 100, 0]);
+    let stops = [[255, 0, 0], [0, 0, 255]];
+    assert_eq!(sample_colormap(&stops, 0.5), [188, 0, 188]);
+}
@@ -0,0 +1,18 @@
+// Where colors come from and how they are checked.
+
+#[path = "../common/mod.rs"]
+mod common;
+
+mod accessibility;
+mod color_jitter;
+mod color_sequence;
+mod curve;
+mod extract;
+mod harmony;
+mod inks;
+mod linear;
+mod noise;
+mod phase;
+mod seed_colors;
+mod series;
+mod time_gradient;
@@ -0,0 +1,56 @@
+use spinning::color::ColorSource;
+use spinning::generate;
+use spinning::noise::{NoiseField, gradient_noise};
+
+use crate::common::params;
+
+#[test]
+fn noise_is_smooth_and_zero_on_the_lattice() {
+    for k in -3..3 {
+        assert_eq!(gradient_noise(k as f64, 7), 0.0);
+    }
+    let values: Vec<f64> = (0..1000)
+        .map(|i| gradient_noise(i as f64 / 100.0, 7))
+        .collect();
+    assert!(values.iter().all(|v| v.abs() <= 0.5));
+    assert!(values.windows(2).all(|w| (w[0] - w[1]).abs() < 0.02));
+}
+
+#[test]
+fn noise_colors_drift_slowly_over_a_wide_range() {
+    let noise = NoiseField {
+        scale: 16.0,
+        seed: 1,
+    };
+    let colors: Vec<_> = (0..10000).map(|i| noise.color(i, 10000)).collect();
+    for w in colors.windows(2) {
+        assert!((0..3).all(|ch| w[0][ch].abs_diff(w[1][ch]) <= 8), "{w:?}");
+    }
+    for ch in 0..3 {
+        let (lo, hi) = (
+            colors.iter().map(|c| c[ch]).min(),
+            colors.iter().map(|c| c[ch]).max(),
+        );
+        assert!(hi.unwrap() - lo.unwrap() > 100);
+    }
+    let other = NoiseField {
+        scale: 16.0,
+        seed: 2,
+    };
+    assert_ne!(other.color(5000, 10000), noise.color(5000, 10000));
+}
+
+#[test]
+fn noise_colors_flag() {
+    let p = params(&["--size", "40", "--noise-colors", "4", "--seed", "9"]).unwrap();
+    assert!(matches!(
+        p.mode,
+        ColorSource::Noise(NoiseField { seed: 9, .. })
+    ));
+    let run = generate(&p, false);
+    assert!(run.filled.iter().flatten().all(|&f| f));
+    assert_eq!(generate(&p, false).image, run.image);
+    assert!(params(&["--noise-colors", "0"]).is_err());
+    assert!(params(&["--noise-colors", "NaN"]).is_err());
+    assert!(params(&["--noise-colors", "inf"]).is_err());
+}
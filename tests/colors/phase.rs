@@ -0,0 +1,35 @@
+use spinning::color::rotate_hue;
+use spinning::phase::Phase;
+use spinning::{Params, generate};
+
+fn render(args: &str) -> image::RgbImage {
+    let params = Params::from_args(args.split_whitespace().map(String::from)).unwrap();
+    generate(&params, false).image
+}
+
+#[test]
+fn whole_turns_wrap_to_the_start() {
+    assert_eq!("hue:1.25".parse::<Phase>().unwrap(), Phase::Hue(0.25));
+    assert_eq!(
+        "epicycle:-0.25".parse::<Phase>().unwrap(),
+        Phase::Epicycle(0.75)
+    );
+    let start = render("--size 48 --orbit epicycle --phase epicycle:0");
+    assert_eq!(
+        render("--size 48 --orbit epicycle --phase epicycle:1"),
+        start
+    );
+    assert_ne!(
+        render("--size 48 --orbit epicycle --phase epicycle:0.5"),
+        start
+    );
+}
+
+#[test]
+fn hue_phase_only_turns_colors() {
+    let mut plain = render("--size 48");
+    for pixel in plain.pixels_mut() {
+        pixel.0 = rotate_hue(pixel.0, 0.3);
+    }
+    assert_eq!(render("--size 48 --phase hue:0.3"), plain);
+}
@@ -0,0 +1,71 @@
+use spinning::color::spread_colors;
+use spinning::params::SeedColors;
+use spinning::{color_dist_sq, generate};
+
+use crate::common::from_line;
+
+#[test]
+fn the_first_centers_take_the_given_colors() {
+    let base = "--size 32 --centers 3 --seed 7";
+    let plain = generate(&from_line(base).unwrap(), true);
+    let seeded = generate(
+        &from_line(&format!("{base} --seed-colors ff0000,#0000ff")).unwrap(),
+        true,
+    );
+    let seeds: Vec<[u8; 3]> = seeded.placements[..3]
+        .iter()
+        .map(|p| p.pixel.color)
+        .collect();
+    assert_eq!(seeds, [[255, 0, 0], [0, 0, 255], [255, 0, 0]]);
+    assert_ne!(plain.placements[0].pixel.color, [255, 0, 0]);
+    assert_eq!(seeded.placements.len(), 32 * 32);
+
+    let p = from_line("--seed-colors okabe-ito").unwrap();
+    assert!(matches!(p.seed_colors, Some(SeedColors::Given(colors)) if colors.len() == 8));
+}
+
+#[test]
+fn spread_seeds_are_far_apart() {
+    let candidates = [[10, 10, 10], [12, 12, 12], [250, 250, 250], [128, 0, 0]];
+    assert_eq!(
+        spread_colors(&candidates, 3),
+        [[10, 10, 10], [250, 250, 250], [128, 0, 0]]
+    );
+    assert!(spread_colors(&[], 3).is_empty());
+
+    let nearest = |colors: &[[u8; 3]]| {
+        let mut nearest = i64::MAX;
+        for (i, &a) in colors.iter().enumerate() {
+            for &b in &colors[i + 1..] {
+                nearest = nearest.min(color_dist_sq(a, b));
+            }
+        }
+        nearest
+    };
+    let base = "--size 32 --centers 6 --seed 2";
+    let first = |run: &spinning::Run| -> Vec<[u8; 3]> {
+        run.placements[..6].iter().map(|p| p.pixel.color).collect()
+    };
+    let plain = generate(&from_line(base).unwrap(), true);
+    let spread = generate(
+        &from_line(&format!("{base} --seed-colors spread")).unwrap(),
+        true,
+    );
+    assert!(nearest(&first(&spread)) > nearest(&first(&plain)));
+    // Grays spread out along the gray ramp.
+    let grays = generate(
+        &from_line(&format!("{base} --mode grayscale --seed-colors spread")).unwrap(),
+        true,
+    );
+    assert!(first(&grays).iter().all(|c| c[0] == c[1] && c[1] == c[2]));
+    assert!(first(&grays).contains(&[0; 3]) || first(&grays).contains(&[255; 3]));
+}
+
+#[test]
+fn seed_colors_are_refused_without_centers_to_seed() {
+    assert!(from_line("--seed-colors nothex").is_err());
+    assert!(from_line("--seed-colors spread --centers 0").is_err());
+    assert!(from_line("--seed-colors spread --center-palettes").is_err());
+    assert!(from_line("--seed-colors spread --colorblind-safe").is_ok());
+    assert!(from_line("--seed-colors ff0000 --colorblind-safe").is_err());
+}
@@ -0,0 +1,36 @@
+use spinning::color::rotate_hue;
+use spinning::series::Series;
+
+#[test]
+fn normalized_to_unit_range() {
+    let series = Series::normalized(vec![2.0, 4.0, 10.0]).unwrap();
+    assert_eq!(series.at(0.0), 0.0);
+    assert_eq!(series.at(1.0), 1.0);
+    assert_eq!(series.at(0.5), 0.25);
+}
+
+#[test]
+fn interpolates_between_samples() {
+    let series = Series::normalized(vec![0.0, 1.0]).unwrap();
+    assert!((series.at(0.3) - 0.3).abs() < 1e-12);
+    assert_eq!(series.at(-1.0), 0.0);
+    assert_eq!(series.at(2.0), 1.0);
+}
+
+#[test]
+fn constant_and_empty_series() {
+    let series = Series::normalized(vec![5.0; 4]).unwrap();
+    assert_eq!(series.at(0.7), 0.0);
+    assert!(Series::normalized(vec![]).is_none());
+}
+
+#[test]
+fn hue_rotation() {
+    let colors = [[200, 40, 10], [0, 0, 0], [255, 255, 255], [13, 180, 99]];
+    for color in colors {
+        assert_eq!(rotate_hue(color, 0.0), color);
+        assert_eq!(rotate_hue(color, 1.0), color);
+    }
+    assert_eq!(rotate_hue([255, 0, 0], 1.0 / 3.0), [0, 255, 0]);
+    assert_eq!(rotate_hue([128, 128, 128], 0.25), [128, 128, 128]);
+}
@@ -0,0 +1,15 @@
+use rand::prelude::*;
+use spinning::color::ColorSource;
+
+use crate::common::parsed;
+
+#[test]
+fn time_gradients_run_from_the_first_stop_to_the_last() {
+    let p = parsed(&["--time-gradient", "000000,808080,ffffff"]);
+    assert!(matches!(p.mode, ColorSource::Timed(_)));
+    let mut rng = StdRng::seed_from_u64(1);
+    assert_eq!(p.mode.next(&mut rng, 0, 100), [0, 0, 0]);
+    assert_eq!(p.mode.next(&mut rng, 99, 100), [255, 255, 255]);
+    // A single placement takes the first stop.
+    assert_eq!(p.mode.next(&mut rng, 0, 1), [0, 0, 0]);
+}
@@ -0,0 +1,49 @@
+use image::{Rgb, RgbImage};
+use spinning::ab::{outcome, parse_cases, report};
+
+#[test]
+fn cases_parse_from_tables() {
+    let cases = parse_cases(
+        "# suite\n[[case]]\nname = \"small\"\nargs = \"--size 32 --seed 1\"\n\n\
+         [[case]]\nargs = \"--size 16\"\nname = \"tiny\"\n",
+    )
+    .expect("parsed");
+    assert_eq!(cases.len(), 2);
+    assert_eq!(cases[0].name, "small");
+    assert_eq!(cases[0].args, ["--size", "32", "--seed", "1"]);
+    assert_eq!(cases[1].name, "tiny");
+    // Unseeded cases render with a fixed seed on both sides.
+    assert_eq!(cases[1].args, ["--size", "16", "--seed", "19"]);
+}
+
+#[test]
+fn bad_cases_are_refused() {
+    assert!(parse_cases("").is_err());
+    assert!(parse_cases("name = \"x\"\n").is_err());
+    assert!(parse_cases("[[case]]\nname = \"x\"\n").is_err());
+    assert!(parse_cases("[[case]]\nname = x\nargs = \"\"\n").is_err());
+    assert!(parse_cases("[[case]]\nname = \"a/b\"\nargs = \"\"\n").is_err());
+    assert!(parse_cases("[[case]]\nname = \"x\"\nargs = \"--out x.png\"\n").is_err());
+    let twice = "[[case]]\nname = \"x\"\nargs = \"\"\n";
+    assert!(parse_cases(&twice.repeat(2)).is_err());
+}
+
+#[test]
+fn outcomes_tell_exact_matches_from_near_ones() {
+    let a = RgbImage::from_fn(32, 32, |x, y| Rgb([(x * 8) as u8, (y * 8) as u8, 0]));
+    let mut b = a.clone();
+    b.put_pixel(3, 3, Rgb([255, 255, 255]));
+    let same = outcome("same", &a, &a);
+    let near = outcome("near", &a, &b);
+    let sized = outcome("sized", &a, &RgbImage::new(16, 16));
+    assert!(same.identical());
+    assert!(!near.identical());
+    assert_eq!(near.differing, 1);
+    assert!(near.difference.as_ref().expect("same size").ssim > 0.9);
+    assert!(sized.difference.is_none());
+    let text = report(&[same, near, sized]);
+    assert!(text.contains("same: identical"));
+    assert!(text.contains("near: 1 pixels differ"));
+    assert!(text.contains("sized: different sizes"));
+    assert!(text.ends_with("1 of 3 cases match exactly"));
+}
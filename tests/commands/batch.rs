@@ -0,0 +1,83 @@
+use spinning::batch::{BatchSettings, Status, parse_jobs, run_batch, summary};
+use std::time::Duration;
+
+fn settings(timeout: Option<f64>, retries: usize) -> BatchSettings {
+    BatchSettings {
+        exe: env!("CARGO_BIN_EXE_spinning").into(),
+        jobs: 3,
+        timeout: timeout.map(Duration::from_secs_f64),
+        retries,
+    }
+}
+
+#[test]
+fn batch_files_skip_blanks_and_comments() {
+    let jobs = parse_jobs("# sweep\n--size 32 --seed 1\n\n  --size 32   --seed 2  \n");
+    assert_eq!(
+        jobs,
+        vec![
+            vec!["--size", "32", "--seed", "1"],
+            vec!["--size", "32", "--seed", "2"]
+        ]
+    );
+}
+
+#[test]
+fn failing_jobs_leave_the_rest_of_the_batch_alone() {
+    let dir = std::env::temp_dir().join("spinning-batch");
+    std::fs::create_dir_all(&dir).expect("created test directory");
+    let good = dir.join("good.png").display().to_string();
+    let missing = dir.join("missing/bad.png").display().to_string();
+    let text = format!("--size 32 --out {good}\n--size 32 --bogus 1\n--size 32 --out {missing}\n");
+    let results = run_batch(&parse_jobs(&text), &settings(None, 1), |_| {});
+    let statuses: Vec<_> = results.iter().map(|r| r.status).collect();
+    assert_eq!(statuses, [Status::Done, Status::Invalid, Status::Failed]);
+    assert_eq!(
+        results.iter().map(|r| r.attempts).collect::<Vec<_>>(),
+        [1, 0, 2]
+    );
+    assert!(std::path::Path::new(&good).exists());
+    let table = summary(&results);
+    assert!(
+        table.contains(&good) && table.contains("unknown flag --bogus"),
+        "{table}"
+    );
+    assert!(table.ends_with("1 of 3 jobs done"), "{table}");
+}
+
+#[test]
+fn slow_jobs_are_stopped_at_the_timeout() {
+    let results = run_batch(
+        &parse_jobs("--size 4096 --out /dev/null\n"),
+        &settings(Some(0.2), 0),
+        |_| {},
+    );
+    assert_eq!(results[0].status, Status::TimedOut);
+    assert!(results[0].seconds < 5.0);
+}
+
+#[test]
+fn unseeded_jobs_write_the_file_they_report() {
+    let dir = std::env::temp_dir().join(format!("spinning-batch-seed-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("created test directory");
+    let text = format!("--size 16 --out-dir {}\n", dir.display());
+    let results = run_batch(&parse_jobs(&text), &settings(None, 0), |_| {});
+    assert_eq!(results[0].status, Status::Done);
+    assert!(
+        std::path::Path::new(&results[0].output).exists(),
+        "{}",
+        results[0].output
+    );
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn timeouts_too_long_to_hold_are_refused() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_spinning"))
+        .args(["batch", "jobs.txt", "--timeout", "1e300"])
+        .output()
+        .expect("ran");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--timeout 1e300 is too long"), "{stderr}");
+}
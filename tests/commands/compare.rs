@@ -0,0 +1,26 @@
+use image::{Rgb, RgbImage};
+
+use spinning::compare::{dhash, difference, duplicates};
+
+#[test]
+fn near_duplicates_point_to_the_first_kept() {
+    let hashes = [0b0000, 0b0001, 0xffff, 0b0011, 0xfffe];
+    assert_eq!(
+        duplicates(&hashes, 1),
+        [None, Some((0, 1)), None, None, Some((2, 1))]
+    );
+}
+
+#[test]
+fn hash_ignores_size_and_sees_mirroring() {
+    let gradient = |w, flip: bool| {
+        RgbImage::from_fn(w, w, move |x, _| {
+            let v = (x * 255 / (w - 1)) as u8;
+            Rgb([if flip { 255 - v } else { v }; 3])
+        })
+    };
+    assert_eq!(dhash(&gradient(64, false)), dhash(&gradient(200, false)));
+    let d = difference(&gradient(64, false), &gradient(64, true));
+    assert_eq!(d.hash_distance, 64);
+    assert!(d.mean_abs > 100.0);
+}
@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use spinning::daemon::{parse_interval, prune, wallpaper_name};
+
+#[test]
+fn intervals_take_units() {
+    assert_eq!(
+        parse_interval("90").expect("parsed"),
+        Duration::from_secs(90)
+    );
+    assert_eq!(
+        parse_interval("30m").expect("parsed"),
+        Duration::from_secs(1800)
+    );
+    assert_eq!(
+        parse_interval("1.5h").expect("parsed"),
+        Duration::from_secs(5400)
+    );
+    assert!(parse_interval("1w").is_err());
+    assert!(parse_interval("0s").is_err());
+    assert!(parse_interval("h").is_err());
+}
+
+#[test]
+fn intervals_too_long_to_hold_are_refused() {
+    assert_eq!(
+        parse_interval("999999999999999999999d"),
+        Err("invalid value for --interval: 999999999999999999999d".to_string())
+    );
+}
+
+#[test]
+fn pruning_keeps_the_newest() {
+    let dir = std::env::temp_dir().join(format!("spinning-daemon-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("created");
+    for (seconds, seed) in [(300, 1), (100, 7), (200, 3)] {
+        std::fs::write(dir.join(wallpaper_name(seconds, seed)), b"").expect("wrote");
+    }
+    std::fs::write(dir.join("other.png"), b"").expect("wrote");
+    let removed = prune(&dir, 2).expect("pruned");
+    assert_eq!(removed, [dir.join(wallpaper_name(100, 7))]);
+    let mut left: Vec<_> = std::fs::read_dir(&dir)
+        .expect("read")
+        .map(|e| e.expect("entry").file_name().into_string().expect("utf-8"))
+        .collect();
+    left.sort();
+    assert_eq!(
+        left,
+        [
+            "other.png",
+            &wallpaper_name(200, 3),
+            &wallpaper_name(300, 1)
+        ]
+    );
+    std::fs::remove_dir_all(&dir).expect("removed");
+}
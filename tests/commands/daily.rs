@@ -0,0 +1,39 @@
+use spinning::daily::{daily_args, date_from_days, parse_date, today};
+use spinning::{Params, generate};
+
+#[test]
+fn one_piece_per_day_and_namespace() {
+    let args = daily_args("2025-06-01", "");
+    assert_eq!(args, daily_args("2025-06-01", ""));
+    assert_ne!(args, daily_args("2025-06-02", ""));
+    assert_ne!(args, daily_args("2025-06-01", "bot"));
+    Params::from_args(args.into_iter()).unwrap();
+}
+
+#[test]
+fn a_piece_renders_as_a_miniature() {
+    let mut args = daily_args("2025-06-01", "");
+    args.push("--fast-profile".to_string());
+    let run = generate(&Params::from_args(args.into_iter()).unwrap(), false);
+    assert_eq!(run.image.dimensions(), (96, 96));
+    assert!(run.filled.iter().flatten().all(|&f| f));
+}
+
+#[test]
+fn reads_calendar_dates() {
+    assert_eq!(date_from_days(0), "1970-01-01");
+    assert_eq!(date_from_days(11017), "2000-03-01");
+    assert_eq!(date_from_days(-1), "1969-12-31");
+    assert_eq!(parse_date("2024-02-29").unwrap(), "2024-02-29");
+    for bad in [
+        "2023-02-29",
+        "2024-13-01",
+        "2024-04-31",
+        "2024-4-1",
+        "24-04-01",
+        "today",
+    ] {
+        assert!(parse_date(bad).is_err(), "{bad}");
+    }
+    assert!(parse_date(&today()).is_ok());
+}
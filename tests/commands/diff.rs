@@ -0,0 +1,45 @@
+use image::{Rgb, RgbImage};
+use spinning::compare::{DiffStyle, diff_image, pixel_distances};
+use std::process::Command;
+
+#[test]
+fn distances_run_from_agreement_to_black_against_white() {
+    let a = RgbImage::from_fn(3, 1, |x, _| {
+        Rgb([[0, 0, 0], [255; 3], [10, 20, 30]][x as usize])
+    });
+    let b = RgbImage::from_fn(3, 1, |x, _| {
+        Rgb([[255; 3], [255; 3], [10, 20, 40]][x as usize])
+    });
+    let d = pixel_distances(&a, &b);
+    assert_eq!(d[0], 1.0);
+    assert_eq!(d[1], 0.0);
+    assert!(d[2] > 0.0 && d[2] < 0.1);
+    let ghost = diff_image(&a, &b, DiffStyle::Ghost);
+    assert_eq!(ghost.get_pixel(0, 0).0, [0; 3]);
+    assert_eq!(ghost.get_pixel(1, 0).0, [255; 3]);
+    let heat = diff_image(&a, &b, DiffStyle::Heat);
+    assert_ne!(heat.get_pixel(0, 0), heat.get_pixel(1, 0));
+}
+
+#[test]
+fn diff_renders_both_seeds() {
+    let dir = std::env::temp_dir().join(format!("spinning-diff-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("made a directory");
+    let out = dir.join("diff.png");
+    let run = |seed_b: &str| {
+        let output = Command::new(env!("CARGO_BIN_EXE_spinning"))
+            .args(["diff", "--size", "32", "--lookback", "100", "--seed-a", "3"])
+            .args(["--seed-b", seed_b, "--out", out.to_str().expect("utf-8")])
+            .output()
+            .expect("ran");
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).expect("utf-8")
+    };
+    assert!(run("3").contains("100.0% of pixels the same"));
+    let heat = image::open(&out).expect("saved").to_rgb8();
+    assert_eq!(heat.dimensions(), (32, 32));
+    let agree = heat.get_pixel(0, 0).0;
+    assert!(heat.pixels().all(|p| p.0 == agree));
+    assert!(!run("4").contains("100.0%"));
+    std::fs::remove_dir_all(&dir).expect("cleaned up");
+}
@@ -0,0 +1,14 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use spinning::Params;
+use spinning::explore::random_args;
+
+#[test]
+fn random_arguments_parse() {
+    let mut rng = ChaCha8Rng::seed_from_u64(7);
+    for _ in 0..200 {
+        let args = random_args(&mut rng);
+        Params::from_args(args.iter().cloned()).unwrap_or_else(|e| panic!("{args:?}: {e}"));
+    }
+}
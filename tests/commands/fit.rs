@@ -0,0 +1,44 @@
+use image::{Rgb, RgbImage};
+
+use spinning::fit::{fit, ssim};
+
+#[test]
+fn ssim_of_identical_and_different_images() {
+    let a = RgbImage::from_fn(32, 32, |x, y| Rgb([(x * 8) as u8, (y * 8) as u8, 128]));
+    let b = RgbImage::from_fn(32, 32, |x, y| Rgb([(y * 8) as u8, (x * 8) as u8, 128]));
+    assert!((ssim(&a, &a) - 1.0).abs() < 1e-12);
+    assert!(ssim(&a, &b) < 0.9);
+}
+
+#[test]
+fn a_target_of_one_color_fits() {
+    let target = RgbImage::from_pixel(32, 32, Rgb([200, 30, 30]));
+    let fit = fit(&target, 16, std::time::Duration::ZERO, 1, |_| {}).expect("fitted");
+    let palette = fit.args.iter().position(|a| a == "--palette").unwrap();
+    assert_eq!(fit.args[palette + 1], "c81e1e");
+    assert!(fit.image.pixels().all(|p| p.0 == [200, 30, 30]));
+}
+
+#[test]
+fn bad_budgets_are_refused() {
+    for seconds in ["-1", "NaN", "inf"] {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_spinning"))
+            .args(["fit", "target.png", "--seconds", seconds])
+            .output()
+            .expect("ran");
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("--seconds must be at least 0"), "{stderr}");
+    }
+}
+
+#[test]
+fn budgets_too_long_to_hold_are_refused() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_spinning"))
+        .args(["fit", "target.png", "--seconds", "1e300"])
+        .output()
+        .expect("ran");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--seconds 1e300 is too long"), "{stderr}");
+}
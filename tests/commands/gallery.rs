@@ -0,0 +1,43 @@
+use image::RgbImage;
+
+use spinning::Params;
+use spinning::gallery::add_to_gallery;
+use spinning::output::save_png;
+
+#[test]
+fn reruns_replace_their_entry() {
+    let dir = std::env::temp_dir().join(format!("spinning-gallery-{}", std::process::id()));
+    let dir = dir.to_str().unwrap();
+    std::fs::create_dir_all(dir).unwrap();
+    for (seed, name) in [(1, "a.png"), (2, "b.png"), (3, "a.png")] {
+        let params = Params::from_args(
+            [
+                "--seed",
+                &seed.to_string(),
+                "--out-dir",
+                dir,
+                "--out",
+                name,
+                "--gallery",
+            ]
+            .map(String::from)
+            .into_iter(),
+        )
+        .unwrap();
+        let path = params.filename();
+        save_png(&RgbImage::new(8, 8), &path, &[]).unwrap();
+        add_to_gallery(dir, &path, &params).unwrap();
+    }
+    let manifest = std::fs::read_to_string(format!("{dir}/gallery.json")).unwrap();
+    let lines: Vec<&str> = manifest.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[1].contains("\"file\": \"a.png\"") && lines[1].contains("\"seed\": 3"));
+    assert!(lines[2].contains("\"file\": \"b.png\""));
+    assert!(std::path::Path::new(&format!("{dir}/thumbs/a.png")).exists());
+    assert!(
+        std::fs::read_to_string(format!("{dir}/index.html"))
+            .unwrap()
+            .contains("b.png")
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
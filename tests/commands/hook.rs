@@ -0,0 +1,101 @@
+use spinning::hook::{Event, EventKind, Hook};
+use std::io::{Read, Write};
+use std::net::TcpListener;
+
+use crate::common::params;
+
+fn event(args: &[String]) -> Event<'_> {
+    Event {
+        kind: EventKind::Complete,
+        output: "out \"1\".png",
+        coverage: 1.0,
+        placed: 100,
+        total: 100,
+        seconds: 1.5,
+        seed: 7,
+        args,
+    }
+}
+
+#[test]
+fn urls_are_posted_and_anything_else_runs() {
+    assert_eq!(
+        "https://example.com/hook".parse(),
+        Ok(Hook::Post("https://example.com/hook".to_string()))
+    );
+    assert_eq!(
+        "notify-send done".parse(),
+        Ok(Hook::Command("notify-send done".to_string()))
+    );
+    assert!("  ".parse::<Hook>().is_err());
+}
+
+#[test]
+fn milestones_default_to_quarters_and_need_a_hook() {
+    let p = params(&["--on-milestone", "true"]).expect("parsed");
+    assert_eq!(p.milestones, [0.25, 0.5, 0.75]);
+    let p = params(&["--on-milestone", "true", "--milestones", "0.9,0.1"]).expect("parsed");
+    assert_eq!(p.milestones, [0.1, 0.9]);
+    assert!(params(&["--milestones", "0.5"]).is_err());
+    assert!(params(&["--on-milestone", "true", "--milestones", "1"]).is_err());
+    let p = params(&["--on-complete", "a", "--on-complete", "http://b"]).expect("parsed");
+    assert_eq!(p.on_complete.len(), 2);
+}
+
+#[test]
+fn events_are_json_with_a_message() {
+    let args = vec!["--size".to_string(), "10".to_string()];
+    let json = event(&args).json();
+    assert!(json.starts_with(
+        "{\"event\":\"complete\",\"content\":\"Finished out \\\"1\\\".png: 100 of 100"
+    ));
+    assert!(json.contains("\"output\":\"out \\\"1\\\".png\""), "{json}");
+    assert!(
+        json.ends_with("\"seed\":7,\"args\":[\"--size\",\"10\"]}"),
+        "{json}"
+    );
+}
+
+#[test]
+fn commands_get_the_event_in_the_environment_and_on_stdin() {
+    let path = std::env::temp_dir().join("spinning-hook.txt");
+    let hook = Hook::Command(format!(
+        "(echo $SPINNING_EVENT $SPINNING_COVERAGE; cat) > {}",
+        path.display()
+    ));
+    hook.fire(&event(&[])).expect("ran");
+    let written = std::fs::read_to_string(&path).expect("written");
+    assert!(
+        written.starts_with("complete 1.0000\n{\"event\":\"complete\""),
+        "{written}"
+    );
+    assert!(
+        Hook::Command("exit 3".to_string())
+            .fire(&event(&[]))
+            .is_err()
+    );
+}
+
+#[test]
+fn posts_send_the_json() {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bound");
+    let url = format!("http://{}/hook", listener.local_addr().expect("address"));
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().expect("accepted");
+        let mut request = vec![];
+        let mut buf = [0; 4096];
+        while !String::from_utf8_lossy(&request).ends_with('}') {
+            let n = stream.read(&mut buf).expect("read");
+            request.extend_from_slice(&buf[..n]);
+        }
+        stream
+            .write_all(b"HTTP/1.1 204 No Content\r\n\r\n")
+            .expect("answered");
+        String::from_utf8(request).expect("text")
+    });
+    Hook::Post(url).fire(&event(&[])).expect("posted");
+    let request = server.join().expect("served");
+    assert!(request.starts_with("POST /hook "), "{request}");
+    assert!(request.contains("application/json"));
+    assert!(request.ends_with(&event(&[]).json()));
+}
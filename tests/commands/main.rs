@@ -0,0 +1,19 @@
+// The subcommands built on rendering: comparisons, batches, the daemon,
+// hooks, panels, fitting and tuning.
+
+#[path = "../common/mod.rs"]
+mod common;
+
+mod ab;
+mod batch;
+mod compare;
+mod daemon;
+mod daily;
+mod diff;
+mod explore;
+mod fit;
+mod gallery;
+mod hook;
+mod morph;
+mod panels;
+mod tune;
@@ -0,0 +1,45 @@
+use spinning::morph::{Value, interpolate, parse_params_file};
+
+#[test]
+fn parses_params_files() {
+    let entries = parse_params_file(
+        "# a comment\nstart_spread = 0.3\ncenters = 20\npalette = \"viridis\"\n\
+         reflect = true\nstreams = false\ncheckpoints = [0.25, 0.5]\n",
+    )
+    .unwrap();
+    assert_eq!(
+        entries,
+        [
+            ("start-spread".to_string(), Value::Number(0.3, false)),
+            ("centers".to_string(), Value::Number(20.0, true)),
+            ("palette".to_string(), Value::Text("viridis".to_string())),
+            ("reflect".to_string(), Value::Flag),
+            (
+                "checkpoints".to_string(),
+                Value::Text("0.25,0.5".to_string())
+            ),
+        ]
+    );
+    assert!(parse_params_file("centers 20").is_err());
+}
+
+#[test]
+fn interpolates_numbers_and_keeps_the_seed() {
+    let from = parse_params_file("centers = 10\ncont-spread = 0.1\nseed = 3\nmode = \"grayscale\"")
+        .unwrap();
+    let to =
+        parse_params_file("centers = 21\ncont-spread = 0.5\nseed = 9\nreflect = true").unwrap();
+    let args = |t| interpolate(&from, &to, t).join(" ");
+    assert_eq!(
+        args(0.0),
+        "--centers 10 --cont-spread 0.1 --seed 3 --mode grayscale"
+    );
+    assert_eq!(
+        args(0.5),
+        "--centers 16 --cont-spread 0.3 --seed 3 --reflect"
+    );
+    assert_eq!(
+        args(1.0),
+        "--centers 21 --cont-spread 0.5 --seed 3 --reflect"
+    );
+}
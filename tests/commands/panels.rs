@@ -0,0 +1,129 @@
+use std::process::Command;
+
+use image::{Rgb, RgbImage};
+use spinning::generate;
+use spinning::panels::{Matte, compose, panel_args, panel_params, panel_path};
+
+fn strings(s: &str) -> Vec<String> {
+    s.split_whitespace().map(String::from).collect()
+}
+
+#[test]
+fn panels_share_their_start_and_differ_in_the_varied_flag() {
+    let args = panel_args(
+        &strings("--size 32 --centers 3 --seed 9"),
+        "--cont-spread",
+        &strings("1 12"),
+    )
+    .unwrap();
+    assert_eq!(
+        args[1],
+        strings("--size 32 --centers 3 --seed 9 --streams --cont-spread 12")
+    );
+    let runs: Vec<_> = args
+        .iter()
+        .map(|args| generate(&panel_params(args).unwrap(), true))
+        .collect();
+    let start = |run: &spinning::Run| {
+        let pixels: Vec<_> = run.placements[..3]
+            .iter()
+            .map(|p| (p.pixel.loc, p.pixel.color))
+            .collect();
+        pixels
+    };
+    assert_eq!(start(&runs[0]), start(&runs[1]));
+    assert_ne!(runs[0].image, runs[1].image);
+}
+
+#[test]
+fn panel_arguments_are_checked() {
+    let flags = strings("--size 32");
+    assert!(panel_args(&flags, "--cont-spread", &strings("4")).is_err());
+    assert!(panel_args(&flags, "--size", &strings("16 32")).is_err());
+    assert!(panel_args(&flags, "--seed", &strings("1 2")).is_err());
+    assert!(panel_args(&strings("--lookback 10"), "--lookback", &strings("5 50")).is_err());
+    assert!(panel_params(&strings("--voxels --slices s")).is_err());
+    assert_eq!(panel_path("out/piece.png", 2), "out/piece-2.png");
+}
+
+#[test]
+fn panels_compose_side_by_side_in_their_matte() {
+    let panels = [
+        RgbImage::from_pixel(4, 3, Rgb([10, 0, 0])),
+        RgbImage::from_pixel(4, 3, Rgb([0, 10, 0])),
+        RgbImage::from_pixel(4, 3, Rgb([0, 0, 10])),
+    ];
+    let matte = Matte {
+        gap: 2,
+        frame: 1,
+        color: [200; 3],
+    };
+    let image = compose(&panels, &matte).unwrap();
+    assert_eq!(image.dimensions(), (3 * 4 + 2 * 2 + 2, 3 + 2));
+    assert_eq!(image.get_pixel(0, 0).0, [200; 3]);
+    assert_eq!(image.get_pixel(1, 1).0, [10, 0, 0]);
+    assert_eq!(image.get_pixel(5, 2).0, [200; 3]);
+    assert_eq!(image.get_pixel(7, 2).0, [0, 10, 0]);
+    assert_eq!(image.get_pixel(13, 3).0, [0, 0, 10]);
+    assert_eq!(compose(&panels, &Matte::default()).unwrap().width(), 12);
+
+    let uneven = [RgbImage::new(4, 3), RgbImage::new(3, 3)];
+    assert!(compose(&uneven, &matte).is_err());
+}
+
+#[test]
+fn panels_are_saved_and_composed() {
+    let dir = std::env::temp_dir().join(format!("spinning-panels-{}", std::process::id()));
+    let output = Command::new(env!("CARGO_BIN_EXE_spinning"))
+        .args([
+            "panels",
+            "--vary",
+            "--cont-spread",
+            "--value",
+            "2",
+            "--value",
+            "8",
+        ])
+        .args([
+            "--composite",
+            "--gap",
+            "4",
+            "--frame",
+            "2",
+            "--matte",
+            "000000",
+        ])
+        .args(["--size", "24", "--out", "piece.png", "--out-dir"])
+        .arg(&dir)
+        .output()
+        .expect("ran");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    for k in 1..=2 {
+        let panel = image::open(dir.join(format!("piece-{k}.png"))).expect("saved panel");
+        assert_eq!((panel.width(), panel.height()), (24, 24));
+    }
+    let piece = image::open(dir.join("piece.png"))
+        .expect("composed")
+        .to_rgb8();
+    assert_eq!(piece.dimensions(), (2 * 24 + 4 + 4, 24 + 4));
+    std::fs::remove_dir_all(&dir).ok();
+
+    let refused = Command::new(env!("CARGO_BIN_EXE_spinning"))
+        .args([
+            "panels",
+            "--vary",
+            "--cont-spread",
+            "--value",
+            "2",
+            "--value",
+            "8",
+        ])
+        .args(["--gap", "4", "--size", "24"])
+        .output()
+        .expect("ran");
+    assert!(!refused.status.success());
+}
@@ -0,0 +1,58 @@
+use spinning::Params;
+use spinning::tune::Tuner;
+
+fn args(list: &[&str]) -> Vec<String> {
+    list.iter().map(|a| a.to_string()).collect()
+}
+
+#[test]
+fn knobs_replace_their_flags_and_keep_the_rest() {
+    let tuner = Tuner::new(&args(&[
+        "--size",
+        "300",
+        "--centers",
+        "7",
+        "--out",
+        "a.png",
+    ]))
+    .expect("parsed");
+    let full = tuner.args();
+    assert_eq!(&full[..4], &args(&["--size", "300", "--out", "a.png"])[..]);
+    assert_eq!(full.iter().filter(|a| *a == "--centers").count(), 1);
+    let params = tuner.params().expect("parsed");
+    let given =
+        Params::from_args(args(&["--size", "300", "--centers", "7"]).into_iter()).expect("parsed");
+    assert_eq!(params.num_centers, 7);
+    assert_eq!(params.num_lookback, given.num_lookback);
+    assert_eq!(params.seed, given.seed);
+    assert_eq!(params.out.as_deref(), Some("a.png"));
+}
+
+#[test]
+fn knobs_step_within_their_range() {
+    let mut tuner = Tuner::new(&args(&["--centers", "1"])).expect("parsed");
+    tuner.adjust(-1);
+    assert_eq!(tuner.knobs[0].value, 1.0);
+    // Too small to scale, so it steps by one.
+    tuner.adjust(1);
+    assert_eq!(tuner.knobs[0].value, 2.0);
+    tuner.adjust(100);
+    assert_eq!(tuner.knobs[0].value, 1000.0);
+    assert_eq!(tuner.knobs[0].position(), 1.0);
+    tuner.select(-1);
+    assert_eq!(tuner.knobs[tuner.selected].flag, "--seed");
+    let seed = tuner.knobs[tuner.selected].value;
+    tuner.adjust(1);
+    assert_eq!(tuner.knobs[tuner.selected].value, seed + 1.0);
+    tuner.reseed();
+    assert_ne!(tuner.knobs[tuner.selected].value, seed + 1.0);
+    assert!(tuner.params().is_ok());
+}
+
+#[test]
+fn previews_are_small_renders_of_the_same_run() {
+    let tuner = Tuner::new(&args(&["--size", "2000", "--on-complete", "false"])).expect("parsed");
+    let preview = tuner.preview(40).expect("rendered");
+    assert_eq!(preview.dimensions(), (40, 40));
+    assert!(Tuner::new(&args(&["--voxels", "--slices", "out"])).is_err());
+}
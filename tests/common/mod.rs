@@ -0,0 +1,24 @@
+// Helpers shared by the test binaries, each of which uses only some.
+#![allow(dead_code)]
+
+use spinning::Params;
+
+// Parameters from flags, as the command line gives them.
+pub fn params(args: &[&str]) -> Result<Params, String> {
+    Params::from_args(args.iter().map(|a| a.to_string()))
+}
+
+// Parameters from flags that parse.
+pub fn parsed(args: &[&str]) -> Params {
+    params(args).expect("parsed")
+}
+
+// Parameters from a line of flags split at spaces.
+pub fn from_line(line: &str) -> Result<Params, String> {
+    Params::from_args(line.split_whitespace().map(String::from))
+}
+
+// The path of an image in tests/fixtures.
+pub fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"))
+}
@@ -0,0 +1,59 @@
+use spinning::generate;
+use spinning::lookback::{AdaptiveLookback, LookbackScale};
+
+use crate::common::params;
+
+#[test]
+fn modes_parse_with_defaults() {
+    assert_eq!("open".parse(), Ok(AdaptiveLookback::Open { floor: 0.05 }));
+    assert_eq!(
+        "forced:0.1:0.2".parse(),
+        Ok(AdaptiveLookback::Forced {
+            rate: 0.1,
+            floor: 0.2
+        })
+    );
+    for bad in ["closed", "open:0", "open:1.5", "forced:x", "open:0.5:1"] {
+        assert!(bad.parse::<AdaptiveLookback>().is_err(), "{bad}");
+    }
+    assert!(params(&["--adaptive-lookback", "open", "--big", "scratch"]).is_err());
+}
+
+#[test]
+fn open_mode_follows_the_open_area() {
+    let mut scale = LookbackScale::new(AdaptiveLookback::Open { floor: 0.1 });
+    scale.observe(false, 0.25);
+    assert_eq!(scale.apply(1000), 750);
+    scale.observe(false, 0.99);
+    assert_eq!(scale.apply(1000), 100);
+    assert_eq!(scale.apply(3), 1);
+}
+
+#[test]
+fn forced_mode_shrinks_while_placements_are_forced() {
+    let mut scale = LookbackScale::new(AdaptiveLookback::Forced {
+        rate: 0.05,
+        floor: 0.1,
+    });
+    for _ in 0..5000 {
+        scale.observe(true, 0.5);
+    }
+    assert!((scale.share() - 0.1).abs() < 1e-9);
+    for _ in 0..5000 {
+        scale.observe(false, 0.5);
+    }
+    assert_eq!(scale.share(), 1.0);
+}
+
+#[test]
+fn adaptive_runs_are_full_and_repeatable() {
+    let base = ["--size", "64", "--lookback", "500"];
+    let plain = generate(&params(&base).expect("parsed"), false).image;
+    for mode in ["open", "forced"] {
+        let p = params(&[&base[..], &["--adaptive-lookback", mode]].concat()).expect("parsed");
+        let run = generate(&p, false);
+        assert!(run.filled.iter().flatten().all(|&f| f));
+        assert_ne!(run.image, plain, "{mode}");
+        assert_eq!(generate(&p, false).image, run.image);
+    }
+}
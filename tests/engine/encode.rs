@@ -0,0 +1,87 @@
+use image::RgbImage;
+use spinning::output::{adler32_combine, save_png_bands};
+use spinning::{Generator, Params};
+
+fn adler32(data: &[u8]) -> u32 {
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), &x| {
+        let a = (a + x as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    b << 16 | a
+}
+
+#[test]
+fn combines_checksums() {
+    let data: Vec<u8> = (0..100_000u32).map(|i| (i * 31 % 251) as u8).collect();
+    for split in [0, 1, 5552, 70_000, 100_000] {
+        let (a, b) = data.split_at(split);
+        assert_eq!(
+            adler32_combine(adler32(a), adler32(b), b.len()),
+            adler32(&data)
+        );
+    }
+}
+
+#[test]
+fn bands_decode_to_the_same_pixels() {
+    // Three bands, the last one short, and noise that exercises every filter.
+    let (width, height) = (300, 600);
+    let img = RgbImage::from_fn(width, height, |x, y| {
+        let n = (x.wrapping_mul(2654435761) ^ y.wrapping_mul(40503)).rotate_left(x % 7);
+        image::Rgb([(x / 3) as u8, n as u8 & 0x1f, (y / 5) as u8])
+    });
+    let dir = std::env::temp_dir();
+    let path = dir.join(format!("spinning-bands-{}.png", std::process::id()));
+    let path = path.to_str().unwrap();
+    let row = width as usize * 3;
+    let metadata = [("Seed".to_string(), "7".to_string())];
+    save_png_bands(path, width, height, &metadata, Some(32), |y, out| {
+        out.copy_from_slice(&img.as_raw()[y as usize * row..(y as usize + 1) * row])
+    })
+    .unwrap();
+    let read = image::open(path).unwrap().to_rgb8();
+    let thumb = path.replace(".png", "-thumb.png");
+    assert_eq!(image::open(&thumb).unwrap().width(), 16);
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(thumb).unwrap();
+    assert_eq!(read, img);
+}
+
+// Tall enough for several bands; the run stops early, since the rows are
+// what's checked, and unfilled cells take the background.
+#[test]
+fn assembles_the_image_the_grid_holds() {
+    let params = Params::from_args(
+        ["--size", "300", "--phase", "hue:0.3", "--coverage", "0.1"]
+            .map(String::from)
+            .into_iter(),
+    )
+    .unwrap();
+    let mut generator = Generator::new(&params, false);
+    while generator.step() {}
+    let image = generator.image();
+    let path = std::env::temp_dir().join(format!("spinning-assemble-{}.png", std::process::id()));
+    generator.save_png(path.to_str().unwrap(), &[]).unwrap();
+    let saved = image::open(&path).unwrap().to_rgb8();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(saved, image);
+}
+
+#[test]
+fn metadata_beyond_latin1_is_kept_as_utf8() {
+    let path = std::env::temp_dir().join(format!("spinning-itxt-{}.png", std::process::id()));
+    let path = path.to_str().unwrap();
+    let metadata = [
+        ("Seed".to_string(), "crème".to_string()),
+        ("Seed text".to_string(), "poème ☕".to_string()),
+    ];
+    spinning::output::save_png(&RgbImage::new(2, 2), path, &metadata).unwrap();
+    let decoder = png::Decoder::new(std::io::BufReader::new(std::fs::File::open(path).unwrap()));
+    let reader = decoder.read_info().unwrap();
+    let info = reader.info();
+    assert_eq!(info.uncompressed_latin1_text[0].text, "crème");
+    let utf8 = &info.utf8_text[0];
+    assert_eq!(utf8.keyword, "Seed text");
+    assert_eq!(utf8.get_text().unwrap(), "poème ☕");
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,41 @@
+use spinning::generate;
+use spinning::params::{FAST_SIZE, FAST_VOXELS};
+
+use crate::common::from_line;
+
+#[test]
+fn scales_heavy_runs_down() {
+    let fast =
+        from_line("--size 1920 --lookback 40000 --centers 2000 --cont-spread 4 --fast-profile")
+            .unwrap();
+    assert_eq!(fast.size, FAST_SIZE);
+    assert_eq!(fast.num_lookback, 100);
+    assert_eq!(fast.num_centers, 5);
+    assert!((fast.cont_spread - 0.2).abs() < 1e-9);
+    let run = generate(&fast, false);
+    assert_eq!(run.image.dimensions(), (96, 96));
+    assert!(run.filled.iter().flatten().all(|&f| f));
+
+    // The default thousand pixels shrinks too, and small runs are left alone.
+    assert_eq!(from_line("--fast-profile").unwrap().size, FAST_SIZE);
+    // Centers shrink to one at least, unless there were none.
+    assert_eq!(from_line("--fast-profile").unwrap().num_centers, 1);
+    assert_eq!(
+        from_line("--centers 0 --fast-profile").unwrap().num_centers,
+        0
+    );
+    let small = from_line("--size 64 --lookback 500 --fast-profile").unwrap();
+    assert_eq!((small.size, small.num_lookback), (64, 500));
+
+    let panorama = from_line("--panorama 1920x480 --fast-profile").unwrap();
+    assert_eq!(panorama.size, FAST_SIZE);
+    assert_eq!(panorama.canvas.rows(FAST_SIZE).len(), 24);
+    assert_eq!(
+        from_line("--voxels --slices slices --size 200 --fast-profile")
+            .unwrap()
+            .size,
+        FAST_VOXELS
+    );
+
+    assert!(from_line("--crop 0,0,10,10 --fast-profile").is_err());
+}
@@ -0,0 +1,99 @@
+use std::cell::Cell;
+
+use spinning::geometry::{Frame, OrbitWalker};
+use spinning::grid::Occupancy;
+use spinning::walk::{OrbitShape, WalkSettings, walk, walk_exact};
+
+fn round() -> Frame {
+    Frame::new(1.0, 0.0, 0.0)
+}
+
+#[test]
+fn walks_around_a_circle() {
+    let points: Vec<[isize; 2]> = OrbitWalker::new([20, 20], [25, 20], round()).collect();
+    assert!(points.len() > 20 && points.len() < 40);
+    for p in &points {
+        let d = (p[0] - 20).pow(2) + (p[1] - 20).pow(2);
+        assert!((20..=30).contains(&d), "{p:?} strays from the circle");
+    }
+    // It ends one step short of coming back round to the start.
+    let last = points.last().unwrap();
+    assert!((last[0] - 25).abs() <= 1 && (last[1] - 20).abs() <= 1);
+
+    // A walker starting on its center has nowhere to go.
+    assert_eq!(OrbitWalker::new([7, 7], [7, 7], round()).next(), None);
+}
+
+#[test]
+fn keeps_to_the_canvas_edges() {
+    // The lattice walk itself is unbounded.
+    assert!(OrbitWalker::new([1, 5], [4, 5], round()).any(|p| p[0] < 0));
+
+    let steps = OrbitWalker::new([1, 5], [4, 5], round()).count();
+    let orbit = OrbitShape::Circle.orbit([1, 5], [4, 5], 1.0, 0.0, 0.0, 0.0);
+    let visited = |settings: &WalkSettings| {
+        let calls = Cell::new(0);
+        let found = walk(&*orbit, [4, 5], 10, settings, |loc| {
+            assert!(loc[0] < 10 && loc[1] < 10);
+            calls.set(calls.get() + 1);
+            false
+        });
+        assert_eq!(found, None);
+        calls.get()
+    };
+    // Stopping at the left edge, or mirrored back in off it.
+    let settings = WalkSettings::default();
+    assert!(visited(&settings) < steps);
+    let reflect = WalkSettings {
+        reflect: true,
+        ..settings
+    };
+    assert_eq!(visited(&reflect), steps);
+
+    // Wrapping comes back in across the right edge.
+    let orbit = OrbitShape::Circle.orbit([0, 5], [3, 5], 1.0, 0.0, 0.0, 0.0);
+    let right = |loc: [usize; 2]| loc[0] >= 7;
+    assert_eq!(walk(&*orbit, [3, 5], 10, &settings, right), None);
+    let wrap = WalkSettings {
+        wrap: true,
+        ..settings
+    };
+    let loc = walk(&*orbit, [3, 5], 10, &wrap, right).unwrap();
+    assert!(loc[0] >= 7 && loc[0] < 10);
+}
+
+#[test]
+fn gap_jumps_leap_filled_stretches() {
+    // Everything filled but a cell on the far side of the circle.
+    let size = 64;
+    let mut occupied = Occupancy::new(size);
+    for x in 0..size {
+        for y in 0..size {
+            occupied.fill([x, y]);
+        }
+    }
+    let open = [12, 32];
+    occupied.clear(open);
+    let orbit = OrbitShape::Circle.orbit([32, 32], [52, 32], 1.0, 0.0, 0.0, 0.0);
+    let tried = |gap_jump: usize| {
+        let settings = WalkSettings {
+            gap_jump,
+            ..WalkSettings::default()
+        };
+        let mut calls = 0;
+        let found = walk_exact(&*orbit, [52, 32], size, &settings, Some(&occupied), |loc| {
+            calls += 1;
+            !occupied.is_filled(loc)
+        });
+        (found.map(|(loc, _)| loc), calls)
+    };
+    let (found, every) = tried(0);
+    assert_eq!(found, Some(open));
+    let (found, leaping) = tried(4);
+    assert_eq!(found, Some(open));
+    assert!(leaping * 3 < every, "{leaping} of {every}");
+    // Leaps only pass cells known to be filled, so a walk still stops at
+    // the first open one.
+    let (found, _) = tried(40);
+    assert_eq!(found, Some(open));
+}
@@ -0,0 +1,151 @@
+use spinning::canvas::Canvas;
+use spinning::color::ColorSource;
+use spinning::lookback::Eviction;
+use spinning::rng::RngKind;
+use spinning::walk::Neighborhood;
+use spinning::{Params, make_image};
+
+// FNV-1a, so the hashes don't depend on std's unspecified hasher.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn render_hash(rng: RngKind, seed: u64, num_centers: usize, num_lookback: usize) -> u64 {
+    let params = Params {
+        size: 64,
+        num_centers,
+        num_lookback,
+        rng,
+        seed,
+        ..Params::default()
+    };
+    fnv1a(make_image(&params).as_raw())
+}
+
+// The ChaCha streams are portable, so these images must not change across
+// platforms. A failure here means published seeds no longer reproduce.
+#[test]
+fn chacha_goldens() {
+    let cases = [
+        (RngKind::ChaCha8, 1, 4, 100, 0x566daed1b3bcb1e3),
+        (RngKind::ChaCha8, 2, 20, 1000, 0x77d94c98091e49f8),
+        (RngKind::ChaCha20, 3, 4, 100, 0xdc3ab9f88cb2508),
+        (RngKind::ChaCha20, 4, 1, 10, 0x42966c08ad836b94),
+    ];
+    for (rng, seed, num_centers, num_lookback, expected) in cases {
+        let hash = render_hash(rng, seed, num_centers, num_lookback);
+        assert_eq!(hash, expected, "{rng:?} seed {seed}: got {hash:#x}");
+    }
+}
+
+fn hash(params: Params) -> u64 {
+    fnv1a(make_image(&Params { size: 64, ..params }).as_raw())
+}
+
+// Output of the default generator and of each option that changes how pixels
+// are placed. A failure here means a refactor changed the images; update an
+// expected hash only when the change in output is intended.
+// StdRng cases also change if `rand` changes its algorithm, and the epicycle
+// and disc cases rely on the platform's sin and cos.
+#[test]
+fn feature_goldens() {
+    let base = Params::default;
+    let cases = [
+        ("default", base(), 0x9cdbcffafbfa5047),
+        ("seed 7", Params { seed: 7, ..base() }, 0xedf37f935a1dd9de),
+        (
+            "streams",
+            Params {
+                streams: true,
+                ..base()
+            },
+            0x5a5bccd33e87f14a,
+        ),
+        (
+            "xoshiro",
+            Params {
+                rng: RngKind::Xoshiro,
+                ..base()
+            },
+            0x19040782c06bf860,
+        ),
+        (
+            "random eviction",
+            Params {
+                eviction: Eviction::Random,
+                ..base()
+            },
+            0x73db1251cf52d723,
+        ),
+        (
+            "spatial weight",
+            Params {
+                spatial_weight: 0.5,
+                ..base()
+            },
+            0x824febf7b39eb01b,
+        ),
+        (
+            "max match distance",
+            Params {
+                max_match_distance: Some(40.0),
+                ..base()
+            },
+            0xab3e0faf9b1543d6,
+        ),
+        (
+            "epicycle",
+            Params {
+                orbit: "epicycle".parse().unwrap(),
+                ..base()
+            },
+            0x38545240264257c9,
+        ),
+        (
+            "spiral",
+            Params {
+                spiral: 0.05,
+                ..base()
+            },
+            0x329ccd72831f35d1,
+        ),
+        (
+            "knight",
+            Params {
+                neighborhood: Neighborhood::Knight,
+                ..base()
+            },
+            0xa0fd6ce96ecaad77,
+        ),
+        (
+            "reflect",
+            Params {
+                reflect: true,
+                ..base()
+            },
+            0x7d10d4706fa943a4,
+        ),
+        (
+            "disc",
+            Params {
+                canvas: Canvas::Disc,
+                ..base()
+            },
+            0x61392291e06a0c91,
+        ),
+        (
+            "grayscale",
+            Params {
+                mode: ColorSource::Grayscale,
+                ..base()
+            },
+            0x834effd96918c0a4,
+        ),
+    ];
+    for (name, params, expected) in cases {
+        let hash = hash(params);
+        assert_eq!(hash, expected, "{name}: got {hash:#x}");
+    }
+}
@@ -0,0 +1,45 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use spinning::lookback::{Eviction, Lookback, Query};
+use spinning::{Color, Pixel};
+
+fn query(color: Color, take: usize) -> Query {
+    Query {
+        color,
+        take,
+        cursor: [0, 0],
+        spatial_weight: 0.0,
+        channel: None,
+    }
+}
+
+// The color scan agrees with ranking every entry, as entries come and go
+// from anywhere in the lookback and the planes wrap round.
+#[test]
+fn nearest_matches_the_full_ranking() {
+    for eviction in [Eviction::Fifo, Eviction::Random, Eviction::Diverse] {
+        let mut lookback = Lookback::new(50, eviction.policy());
+        let mut rng = StdRng::seed_from_u64(4);
+        for i in 0..2000 {
+            // Few distinct colors, so there are ties to break.
+            let color = [0; 3].map(|_| rng.random_range(0..4u8) * 60);
+            let pixel = Pixel {
+                color,
+                loc: [i % 97, i / 97],
+                center: [0, 0],
+                center_id: i % 7,
+            };
+            lookback.push(pixel, &mut rng);
+            if i % 300 == 299 {
+                lookback.retain(|p| p.center_id != i % 7);
+            }
+            let target = [0; 3].map(|_| rng.random());
+            for take in [1, 17, 50] {
+                let q = query(target, take);
+                let nearest = lookback.nearest(&q).map(|p| p.loc);
+                let ranked = lookback.ranked(&q, 1).first().map(|p| p.loc);
+                assert_eq!(nearest, ranked, "{eviction:?} at {i}, take {take}");
+            }
+        }
+    }
+}
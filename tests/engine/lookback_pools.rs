@@ -0,0 +1,77 @@
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use spinning::lookback::{ColorClass, Eviction, Lookback, LookbackPools, Query};
+use spinning::{Color, Pixel, make_image};
+
+use crate::common::params;
+
+fn query(color: Color) -> Query {
+    Query {
+        color,
+        take: 100,
+        cursor: [0, 0],
+        spatial_weight: 0.0,
+        channel: None,
+    }
+}
+
+#[test]
+fn pools_parse_and_classify() {
+    let hue: LookbackPools = "6".parse().expect("parsed");
+    assert_eq!((hue.count, hue.class), (6, ColorClass::Hue));
+    assert_eq!(hue.pool([255, 0, 0]), 0);
+    assert_eq!(hue.pool([0, 255, 0]), 2);
+    assert_eq!(hue.pool([0, 0, 255]), 4);
+    assert_eq!(hue.pool([255, 0, 10]), 5);
+    let luma: LookbackPools = "4:luma".parse().expect("parsed");
+    assert_eq!(luma.pool([0, 0, 0]), 0);
+    assert_eq!(luma.pool([255, 255, 255]), 3);
+    for bad in ["0", "x", "4:chroma"] {
+        assert!(bad.parse::<LookbackPools>().is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn matches_stay_in_their_pool() {
+    let pools: LookbackPools = "6".parse().expect("parsed");
+    let mut lookback = Lookback::pooled(10, pools, || Eviction::Fifo.policy());
+    let mut rng = StdRng::seed_from_u64(1);
+    let pixel = |color, x| Pixel {
+        color,
+        loc: [x, 0],
+        center: [0, 0],
+        center_id: 0,
+    };
+    lookback.push(pixel([200, 30, 30], 0), &mut rng);
+    lookback.push(pixel([120, 120, 250], 1), &mut rng);
+    assert_eq!(lookback.front().expect("nonempty").loc, [1, 0]);
+    // Bluer than it is red, but in the red pool.
+    let reddish = [130, 110, 120];
+    assert_eq!(
+        lookback.nearest(&query(reddish)).expect("found").loc,
+        [0, 0]
+    );
+    // Sky blue has no pool of its own, and blue is the nearest around the circle.
+    assert_eq!(
+        lookback.nearest(&query([0, 180, 255])).expect("found").loc,
+        [1, 0]
+    );
+    lookback.retain(|p| p.loc != [1, 0]);
+    assert_eq!(
+        lookback.nearest(&query([0, 0, 255])).expect("found").loc,
+        [0, 0]
+    );
+    lookback.retain(|_| false);
+    assert!(lookback.is_empty() && lookback.nearest(&query(reddish)).is_none());
+}
+
+#[test]
+fn one_pool_is_the_plain_lookback() {
+    let base = ["--size", "48", "--lookback", "200", "--seed", "3"];
+    let plain = make_image(&params(&base).expect("parsed"));
+    let one = params(&[&base[..], &["--lookback-pools", "1"]].concat()).expect("parsed");
+    assert_eq!(make_image(&one), plain);
+    let six = params(&[&base[..], &["--lookback-pools", "6"]].concat()).expect("parsed");
+    assert_ne!(make_image(&six), plain);
+    assert!(params(&["--lookback-pools", "6", "--voxels"]).is_err());
+}
@@ -0,0 +1,18 @@
+// The placement engine: orbits and walks, the grid, the lookback and the
+// fixed renders that pin it down.
+
+#[path = "../common/mod.rs"]
+mod common;
+
+mod adaptive_lookback;
+mod encode;
+mod fast_profile;
+mod geometry;
+mod golden;
+mod lookback;
+mod lookback_pools;
+mod observer;
+mod occupancy;
+mod properties;
+mod strategy;
+mod vecmap;
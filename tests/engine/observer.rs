@@ -0,0 +1,64 @@
+use std::sync::{Arc, Mutex};
+
+use spinning::observer::Observer;
+use spinning::{Generator, Params, Pixel, Placement, make_image};
+
+#[derive(Default)]
+struct Seen {
+    placements: Vec<Placement>,
+    inserts: Vec<Pixel>,
+    milestones: Vec<f64>,
+}
+
+struct Recorder(Arc<Mutex<Seen>>);
+
+impl Observer for Recorder {
+    fn on_placement(&mut self, placement: &Placement) {
+        self.0.lock().unwrap().placements.push(*placement);
+    }
+
+    fn on_random_insert(&mut self, pixel: &Pixel) {
+        self.0.lock().unwrap().inserts.push(*pixel);
+    }
+
+    fn on_milestone(&mut self, coverage: f64) {
+        self.0.lock().unwrap().milestones.push(coverage);
+    }
+}
+
+#[test]
+fn observers_see_every_step() {
+    let args = ["--size", "48", "--seed", "5"].map(String::from);
+    let params = Params::from_args(args.into_iter()).expect("parsed");
+    let seen = Arc::new(Mutex::new(Seen::default()));
+    let mut generator = Generator::new(&params, false);
+    generator.observe(Box::new(Recorder(seen.clone())));
+    while generator.step() {}
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.placements.len(), 48 * 48);
+    assert_eq!(seen.inserts.len(), generator.centers().iter().count());
+    assert!(seen.placements[..20].iter().all(|p| p.parent.is_none()));
+    assert_eq!(seen.milestones.len(), 9);
+    assert!(seen.milestones.windows(2).all(|w| w[0] < w[1]));
+    // Observing leaves the image alone.
+    assert_eq!(generator.image(), make_image(&params));
+}
+
+#[test]
+fn observers_match_the_recording() {
+    let args = ["--size", "32", "--seed", "2"].map(String::from);
+    let params = Params::from_args(args.into_iter()).expect("parsed");
+    let seen = Arc::new(Mutex::new(Seen::default()));
+    let mut generator = Generator::new(&params, true);
+    generator.observe(Box::new(Recorder(seen.clone())));
+    while generator.step() {}
+    let seen = seen.lock().unwrap();
+    let recorded = generator.into_placements();
+    assert_eq!(seen.placements.len(), recorded.len());
+    for (a, b) in seen.placements.iter().zip(&recorded) {
+        assert_eq!(
+            (a.pixel.loc, a.parent, a.steps),
+            (b.pixel.loc, b.parent, b.steps)
+        );
+    }
+}
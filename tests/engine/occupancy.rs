@@ -0,0 +1,52 @@
+use proptest::prelude::*;
+use spinning::Generator;
+use spinning::grid::Occupancy;
+
+use crate::common::parsed;
+
+proptest! {
+    // Popcounts over a rectangle count the same cells as checking each one.
+    #[test]
+    fn counts_match_checking_each_cell(size in 1usize..150,
+                                       filled in prop::collection::vec(any::<(u16, u16)>(), 0..3000),
+                                       x in any::<(u16, u16)>(),
+                                       y in any::<(u16, u16)>()) {
+        let mut occupancy = Occupancy::new(size);
+        let mut newly = 0;
+        for (fx, fy) in filled {
+            newly += occupancy.fill([fx as usize % size, fy as usize % size]) as usize;
+        }
+        prop_assert_eq!(occupancy.count(), newly);
+        let range = |(a, b): (u16, u16)| {
+            let (a, b) = (a as usize % (size + 1), b as usize % (size + 1));
+            a.min(b)..a.max(b)
+        };
+        let (xs, ys) = (range(x), range(y));
+        let expected = xs
+            .clone()
+            .flat_map(|x| ys.clone().map(move |y| [x, y]))
+            .filter(|&loc| occupancy.is_filled(loc))
+            .count();
+        prop_assert_eq!(occupancy.count_in(xs, ys), expected);
+        prop_assert_eq!(occupancy.count_in(0..size, 0..size), occupancy.count());
+    }
+}
+
+#[test]
+fn region_coverage_follows_the_placements() {
+    let p = parsed(&["--size", "50", "--region-grid", "3x2", "--seed", "4"]);
+    let mut generator = Generator::new(&p, false);
+    while generator.placed() < 1000 {
+        generator.step();
+    }
+    let coverage = generator.region_coverage();
+    assert_eq!(coverage.len(), 6);
+    assert_eq!(generator.occupancy().count(), 1000);
+    // Regions of 16 or 17 columns and 25 rows, so weighting by area gives
+    // back the whole canvas.
+    let widths = [17.0, 17.0, 16.0, 17.0, 17.0, 16.0];
+    let placed: f64 = coverage.iter().zip(widths).map(|(c, w)| c * w * 25.0).sum();
+    assert!((placed - 1000.0).abs() < 1e-6, "{placed}");
+    while generator.step() {}
+    assert!(generator.region_coverage().iter().all(|&c| c == 1.0));
+}
@@ -0,0 +1,117 @@
+use proptest::prelude::*;
+use rand::prelude::*;
+use spinning::canvas::Canvas;
+use spinning::openindex::OpenIndex;
+use spinning::{Generator, Params, VecMap};
+
+use std::collections::HashSet;
+
+#[derive(Debug, Clone)]
+enum Op {
+    Insert(u8),
+    Remove(u8),
+    RemoveRandom(u64),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        any::<u8>().prop_map(Op::Insert),
+        any::<u8>().prop_map(Op::Remove),
+        any::<u64>().prop_map(Op::RemoveRandom),
+    ]
+}
+
+proptest! {
+    // VecMap behaves like a set under any sequence of operations,
+    // which holds only while its vec and map agree.
+    #[test]
+    fn vecmap_matches_a_set(initial in prop::collection::hash_set(any::<u8>(), 0..64),
+                            ops in prop::collection::vec(op(), 0..256)) {
+        let mut set = VecMap::new_from_vec(initial.iter().copied().collect());
+        let mut model: HashSet<u8> = initial;
+        for op in ops {
+            match op {
+                Op::Insert(x) => prop_assert_eq!(set.insert(x), model.insert(x)),
+                Op::Remove(x) => prop_assert_eq!(set.remove(&x), model.remove(&x)),
+                Op::RemoveRandom(seed) => {
+                    let removed = set.remove_random(&mut StdRng::seed_from_u64(seed));
+                    match removed {
+                        Some(x) => prop_assert!(model.remove(&x)),
+                        None => prop_assert!(model.is_empty()),
+                    }
+                }
+            }
+            prop_assert_eq!(set.len(), model.len());
+        }
+        for x in 0..=u8::MAX {
+            prop_assert_eq!(set.contains(&x), model.contains(&x));
+        }
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut drained = HashSet::new();
+        while let Some(x) = set.remove_random(&mut rng) {
+            prop_assert!(drained.insert(x));
+        }
+        prop_assert_eq!(drained, model);
+    }
+
+    // Every cell on the canvas is either open or filled, never both.
+    #[test]
+    fn cells_are_open_or_filled(seed in any::<u64>(),
+                                num_centers in 1usize..8,
+                                num_lookback in 1usize..50,
+                                disc in any::<bool>(),
+                                reflect in any::<bool>()) {
+        let canvas = if disc { Canvas::Disc } else { Canvas::Square };
+        let params = Params {
+            size: 16,
+            seed,
+            num_centers,
+            num_lookback,
+            canvas,
+            reflect,
+            ..Params::default()
+        };
+        let mut generator = Generator::new(&params, false);
+        let size = generator.size();
+        let check = |generator: &Generator| -> Result<(), TestCaseError> {
+            for x in 0..size {
+                for y in 0..size {
+                    let loc = [x, y];
+                    let filled = generator.pixel(loc).is_some();
+                    if canvas.contains(loc, size) {
+                        prop_assert!(filled != generator.is_open(loc), "{:?}", loc);
+                    } else {
+                        prop_assert!(!filled && !generator.is_open(loc), "{:?}", loc);
+                    }
+                }
+            }
+            Ok(())
+        };
+        while generator.step() {
+            check(&generator)?;
+        }
+        check(&generator)?;
+    }
+
+    // The index's nearest open cell is the brute-force nearest, the first in
+    // x then y among equals, however the cells were closed.
+    #[test]
+    fn open_index_finds_the_nearest_open_cell(size in 1usize..40,
+                                              closed in prop::collection::vec(any::<(u16, u16)>(), 0..2000),
+                                              queries in prop::collection::vec(any::<(u16, u16)>(), 1..20)) {
+        let all: Vec<[usize; 2]> = (0..size).flat_map(|x| (0..size).map(move |y| [x, y])).collect();
+        let mut index = OpenIndex::new(size, &all);
+        let mut open: HashSet<[usize; 2]> = all.iter().copied().collect();
+        for (x, y) in closed {
+            let loc = [x as usize % size, y as usize % size];
+            prop_assert_eq!(index.remove(loc), open.remove(&loc));
+        }
+        prop_assert_eq!(index.len(), open.len());
+        for (x, y) in queries {
+            let loc = [x as usize % size, y as usize % size];
+            let distance = |c: &[usize; 2]| c[0].abs_diff(loc[0]).pow(2) + c[1].abs_diff(loc[1]).pow(2);
+            let expected = open.iter().min_by_key(|&&c| (distance(&c), c)).copied();
+            prop_assert_eq!(index.nearest(loc), expected);
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use spinning::strategy::{Context, Decision, PlacementStrategy};
+use spinning::{Color, Generator, Params};
+
+// Fills the canvas in reading order, each pixel continuing from the match.
+struct Scanline;
+
+impl PlacementStrategy for Scanline {
+    fn place(&mut self, ctx: &mut Context, _: Color) -> Decision {
+        let nearest = *ctx.lookback.nearest(&ctx.query).unwrap();
+        let size = ctx.params.size;
+        let loc = (0..size * size)
+            .map(|i| [i % size, i / size])
+            .find(|&loc| ctx.is_open(loc))
+            .unwrap();
+        Decision::Place {
+            loc,
+            center: nearest.center,
+            center_id: nearest.center_id,
+            parent: nearest.loc,
+            exact: None,
+            steps: 0,
+        }
+    }
+}
+
+#[test]
+fn runs_a_custom_strategy() {
+    let params = Params::from_args(
+        ["--size", "20", "--centers", "1"]
+            .map(String::from)
+            .into_iter(),
+    )
+    .unwrap();
+    let mut generator = Generator::with_strategy(&params, true, Box::new(Scanline));
+    while generator.step() {}
+    let placements = generator.into_placements();
+    assert_eq!(placements.len(), 400);
+    assert!(placements[1..].iter().all(|p| p.parent.is_some()));
+    let first = placements[0].pixel.loc;
+    let order: Vec<_> = placements[1..].iter().map(|p| p.pixel.loc).collect();
+    let expected: Vec<_> = (0..400)
+        .map(|i| [i % 20, i / 20])
+        .filter(|&loc| loc != first)
+        .collect();
+    assert_eq!(order, expected);
+}
@@ -0,0 +1,97 @@
+use rand::prelude::*;
+use spinning::VecMap;
+
+use std::collections::HashSet;
+
+#[test]
+fn remove_random_drains_every_element_once() {
+    let mut set = VecMap::new_from_vec((0..1000).collect());
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut seen = HashSet::new();
+    while let Some(item) = set.remove_random(&mut rng) {
+        assert!(seen.insert(item), "{item} removed twice");
+        assert_eq!(set.len(), 1000 - seen.len());
+    }
+    assert_eq!(seen.len(), 1000);
+    assert!(set.is_empty());
+}
+
+#[test]
+fn remove_reports_membership() {
+    let mut set = VecMap::new_from_vec(vec![[0, 0], [0, 1], [1, 0], [1, 1]]);
+    assert!(set.remove(&[0, 1]));
+    assert!(!set.remove(&[0, 1]));
+    assert!(!set.remove(&[2, 2]));
+    assert_eq!(set.len(), 3);
+    // Removing the last element and then one swapped into its place.
+    assert!(set.remove(&[1, 1]));
+    assert!(set.remove(&[0, 0]));
+    assert!(set.remove(&[1, 0]));
+    assert!(set.is_empty());
+}
+
+#[test]
+fn mixed_removals_keep_the_set_consistent() {
+    let mut set = VecMap::new_from_vec((0..200).collect());
+    let mut rng = StdRng::seed_from_u64(2);
+    let mut remaining: HashSet<i32> = (0..200).collect();
+    for step in 0..200 {
+        if step % 2 == 0 {
+            let target = rng.random_range(0..200);
+            assert_eq!(set.remove(&target), remaining.remove(&target));
+        } else if let Some(item) = set.remove_random(&mut rng) {
+            assert!(remaining.remove(&item), "{item} was not present");
+        }
+        assert_eq!(set.len(), remaining.len());
+    }
+    // Everything left can still be removed exactly once.
+    for item in remaining.clone() {
+        assert!(set.remove(&item));
+    }
+    assert!(set.is_empty());
+}
+
+#[test]
+fn insert_contains_and_iter() {
+    let mut set = VecMap::new();
+    assert!(set.is_empty());
+    assert!(set.insert('a'));
+    assert!(set.insert('b'));
+    assert!(!set.insert('a'));
+    assert!(set.contains(&'a'));
+    assert!(!set.contains(&'c'));
+    assert_eq!(set.len(), 2);
+    let mut items: Vec<char> = set.iter().copied().collect();
+    items.sort();
+    assert_eq!(items, ['a', 'b']);
+    set.remove(&'a');
+    assert!(!set.contains(&'a'));
+    assert!(set.insert('a'));
+    let collected: VecMap<char> = "abcabc".chars().collect();
+    assert_eq!(collected.len(), 3);
+}
+
+#[test]
+fn weighted_removal_follows_weights() {
+    let mut rng = StdRng::seed_from_u64(3);
+    // Zero-weight elements are never drawn.
+    let mut set = VecMap::new_from_vec((0..10).collect());
+    let mut drawn = vec![];
+    while let Some(item) = set.remove_random_weighted(&mut rng, |&x| (x % 2) as f64) {
+        drawn.push(item);
+    }
+    drawn.sort();
+    assert_eq!(drawn, [1, 3, 5, 7, 9]);
+    assert_eq!(set.len(), 5);
+    assert!(set.iter().all(|x| x % 2 == 0));
+    // A heavy element is drawn first far more often than a light one.
+    let mut heavy_first = 0;
+    for _ in 0..1000 {
+        let mut set = VecMap::new_from_vec(vec![0, 1]);
+        let first = set.remove_random_weighted(&mut rng, |&x| if x == 0 { 9.0 } else { 1.0 });
+        if first == Some(0) {
+            heavy_first += 1;
+        }
+    }
+    assert!((850..950).contains(&heavy_first), "{heavy_first}");
+}
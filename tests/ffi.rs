@@ -0,0 +1,91 @@
+use std::ffi::{CStr, CString, c_char};
+
+use spinning::ffi::*;
+use spinning::{Params, generate};
+
+fn params(args: &[&str]) -> *mut Params {
+    let args: Vec<CString> = args.iter().map(|a| CString::new(*a).unwrap()).collect();
+    let argv: Vec<*const c_char> = args.iter().map(|a| a.as_ptr()).collect();
+    unsafe { spinning_params_new(argv.len() as i32, argv.as_ptr()) }
+}
+
+fn last_error() -> String {
+    let error = spinning_last_error();
+    assert!(!error.is_null());
+    unsafe { CStr::from_ptr(error) }
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn generating_matches_the_library() {
+    let args = ["--size", "16", "--seed", "3"];
+    let expected = generate(
+        &Params::from_args(args.map(String::from).into_iter()).unwrap(),
+        false,
+    )
+    .image
+    .into_raw();
+    let params = params(&args);
+    assert!(!params.is_null());
+    assert_eq!(unsafe { spinning_params_size(params) }, 16);
+    let mut buffer = vec![0; 16 * 16 * 3];
+    assert_eq!(
+        unsafe { spinning_generate(params, buffer.as_mut_ptr(), buffer.len()) },
+        0
+    );
+    assert_eq!(buffer, expected);
+
+    let generator = unsafe { spinning_generator_new(params) };
+    unsafe { spinning_params_free(params) };
+    assert!(!generator.is_null());
+    assert_eq!(unsafe { spinning_generator_step(generator, 100) }, 100);
+    assert!(unsafe { spinning_generator_coverage(generator) } < 1.0);
+    assert!(unsafe { spinning_generator_step(generator, 1000) } < 1000);
+    assert_eq!(unsafe { spinning_generator_coverage(generator) }, 1.0);
+    let mut stepped = vec![0; buffer.len()];
+    assert_eq!(
+        unsafe { spinning_generator_copy_rgb(generator, stepped.as_mut_ptr(), stepped.len()) },
+        0
+    );
+    assert_eq!(stepped, expected);
+    unsafe { spinning_generator_free(generator) };
+}
+
+#[test]
+fn errors_are_reported() {
+    assert!(params(&["--size"]).is_null());
+    assert!(last_error().contains("--size"));
+
+    let params = params(&["--size", "8"]);
+    let mut buffer = vec![0; 10];
+    assert_eq!(
+        unsafe { spinning_generate(params, buffer.as_mut_ptr(), buffer.len()) },
+        -1
+    );
+    assert!(last_error().contains("192 bytes"));
+    unsafe { spinning_params_free(params) };
+}
+
+#[test]
+fn panics_do_not_cross_into_c() {
+    // Too big to allocate, which panics inside the generator.
+    let params = params(&["--size", "2147483648"]);
+    assert!(!params.is_null());
+    assert!(unsafe { spinning_generator_new(params) }.is_null());
+    assert!(last_error().starts_with("panicked"));
+    let mut buffer = vec![0; 3];
+    assert_eq!(
+        unsafe { spinning_generate(params, buffer.as_mut_ptr(), buffer.len()) },
+        -1
+    );
+    assert!(last_error().starts_with("panicked"));
+    unsafe { spinning_params_free(params) };
+}
+
+#[test]
+fn the_schema_is_json() {
+    let schema = unsafe { CStr::from_ptr(spinning_schema()) };
+    assert!(schema.to_str().unwrap().starts_with('{'));
+}
@@ -0,0 +1,51 @@
+use spinning::estimate::{bytes, duration, estimate, memory};
+
+use crate::common::parsed;
+
+fn names(args: &[&str]) -> Vec<&'static str> {
+    memory(&parsed(args))
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect()
+}
+
+#[test]
+fn memory_follows_the_outputs() {
+    assert_eq!(
+        names(&["--size", "64"]),
+        ["grid", "open locations", "open index", "lookback"]
+    );
+    assert!(names(&["--size", "64", "--export", "run.bin"]).contains(&"placements"));
+    assert!(names(&["--size", "64", "--post", "blur:1"]).contains(&"output image"));
+    let total = |size: &str| {
+        memory(&parsed(&["--size", size]))
+            .iter()
+            .map(|m| m.1)
+            .sum::<usize>()
+    };
+    // Four times the cells, about four times the memory.
+    let ratio = total("400") as f64 / total("200") as f64;
+    assert!((3.5..4.5).contains(&ratio), "{ratio}");
+}
+
+#[test]
+fn small_runs_are_timed_without_a_proxy() {
+    let e = estimate(&parsed(&["--size", "64"]), 0.5);
+    assert_eq!((e.sampled, e.target), (2048, 4096));
+    assert!(e.crowding.is_none());
+    assert!(e.seconds >= e.setup_seconds + e.sample_seconds);
+    assert!(
+        e.report()
+            .iter()
+            .any(|l| l.starts_with("Estimated run time"))
+    );
+}
+
+#[test]
+fn sizes_and_times_read_plainly() {
+    assert_eq!(bytes(512), "512 B");
+    assert_eq!(bytes(3 << 29), "1.5 GiB");
+    assert_eq!(duration(2.5), "2.50s");
+    assert_eq!(duration(754.0), "12m 34s");
+    assert_eq!(duration(3.0 * 3600.0 + 120.0), "3h 02m");
+}
@@ -0,0 +1,11 @@
+// Parsing and checking parameters: the schema, seeds, warnings, presets
+// and estimates.
+
+#[path = "../common/mod.rs"]
+mod common;
+
+mod estimate;
+mod presets;
+mod schema;
+mod seed;
+mod warnings;
@@ -0,0 +1,28 @@
+use spinning::generate;
+use spinning::presets::PRESETS;
+
+use crate::common::params;
+
+#[test]
+fn every_preset_parses() {
+    for preset in PRESETS {
+        let params = preset.params(64).unwrap();
+        assert_eq!(params.size, 64, "{}", preset.name);
+    }
+}
+
+// Each preset at its full default size, as a miniature.
+#[test]
+fn every_preset_renders_a_miniature() {
+    for preset in PRESETS {
+        let args = [preset.args, &["--fast-profile"]].concat();
+        let p = params(&args).unwrap();
+        let run = generate(&p, false);
+        assert_eq!(run.image.dimensions(), (96, 96), "{}", preset.name);
+        for (x, column) in run.filled.iter().enumerate() {
+            for (y, &filled) in column.iter().enumerate() {
+                assert_eq!(filled, p.canvas.contains([x, y], 96), "{}", preset.name);
+            }
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use spinning::Params;
+use spinning::schema::{FIELDS, Kind, OUTPUTS, json_schema};
+
+#[test]
+fn fields_are_flags() {
+    for field in FIELDS {
+        let mut args = vec![field.flag.to_string()];
+        if !matches!(field.kind, Kind::Switch) {
+            args.push("x".to_string());
+        }
+        if let Err(e) = Params::from_args(args.into_iter()) {
+            assert!(!e.starts_with("unknown flag"), "{e}");
+        }
+        if let Kind::Choice(choices, _) = field.kind {
+            for choice in choices {
+                // Some need other flags, but every choice is understood.
+                let args = [field.flag, choice].map(String::from);
+                if let Err(e) = Params::from_args(args.into_iter()) {
+                    assert!(e.contains(" need"), "{e}");
+                }
+            }
+        }
+    }
+}
+
+// The flags left out are flags too, and only left out once.
+#[test]
+fn outputs_are_flags_left_out() {
+    for flag in OUTPUTS {
+        if let Err(e) = Params::from_args([flag.to_string()].into_iter()) {
+            assert!(!e.starts_with("unknown flag"), "{e}");
+        }
+        assert!(FIELDS.iter().all(|field| field.flag != *flag), "{flag}");
+    }
+}
+
+#[test]
+fn defaults_are_in_range() {
+    let defaults = Params::default();
+    for field in FIELDS {
+        if let Kind::Integer(range, get) | Kind::Number(range, get) = field.kind
+            && let Some(value) = get(&defaults)
+        {
+            assert!(range.contains(value), "{} {value}", field.flag);
+        }
+    }
+}
+
+#[test]
+fn schema_lists_every_field() {
+    let schema = json_schema();
+    for field in FIELDS {
+        let key = format!("\"{}\": {{", field.flag.trim_start_matches("--"));
+        assert!(schema.contains(&key), "{key}");
+    }
+    assert!(schema.contains("\"size\": {\"type\": \"integer\", \"minimum\": 1, \"default\": 1000"));
+    assert!(schema.contains("\"exclusiveMaximum\": 1"));
+    assert_eq!(schema.matches('{').count(), schema.matches('}').count());
+}
+
+#[test]
+fn validate_checks_fields_set_directly() {
+    assert!(Params::default().validate().is_ok());
+    let coverage = Params {
+        coverage: 1.5,
+        ..Params::default()
+    };
+    assert_eq!(
+        coverage.validate(),
+        Err("--coverage must be in 0..=1, got 1.5".to_string())
+    );
+    let gallery = Params {
+        gallery: true,
+        ..Params::default()
+    };
+    assert!(gallery.validate().is_err());
+    // from_args checks the same ranges.
+    let args = ["--skip-prob", "1"].map(String::from);
+    assert_eq!(
+        Params::from_args(args.into_iter()).err(),
+        Some("--skip-prob must be in 0..1, got 1".to_string())
+    );
+}
+
+// A center can't be retired before its first pixel.
+#[test]
+fn center_max_pixels_must_be_at_least_one() {
+    let args = ["--center-max-pixels", "0"].map(String::from);
+    assert_eq!(
+        Params::from_args(args.into_iter()).err(),
+        Some("--center-max-pixels must be at least 1, got 0".to_string())
+    );
+    let args = ["--center-max-pixels", "1"].map(String::from);
+    assert!(Params::from_args(args.into_iter()).is_ok());
+}
@@ -0,0 +1,58 @@
+use spinning::Params;
+use spinning::params::SeedSource;
+use std::process::Command;
+
+fn args(s: &str) -> Vec<String> {
+    s.split_whitespace().map(String::from).collect()
+}
+
+#[test]
+fn a_given_seed_is_kept() {
+    let params = Params::from_args_with_clock_seed(args("--seed 7")).expect("parsed");
+    assert_eq!(params.seed, 7);
+    assert_eq!(params.seed_source, SeedSource::Number);
+    assert_eq!(
+        Params::from_args(std::iter::empty()).expect("parsed").seed,
+        19
+    );
+}
+
+#[test]
+fn a_clock_seed_is_recorded() {
+    let params = Params::from_args_with_clock_seed(args("--size 16")).expect("parsed");
+    assert_eq!(params.seed_source, SeedSource::Clock);
+    let seed = params.seed.to_string();
+    assert!(params.args.ends_with(&["--seed".to_string(), seed.clone()]));
+    let metadata = params.metadata();
+    assert!(metadata.contains(&("Seed".to_string(), seed)));
+    assert!(metadata.contains(&("Seed source".to_string(), "clock".to_string())));
+}
+
+#[test]
+fn the_reported_seed_renders_the_same_image() {
+    let dir = std::env::temp_dir().join(format!("spinning-seed-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("made a directory");
+    let run = |name: &str, extra: &[&str]| {
+        let out = dir.join(name);
+        let output = Command::new(env!("CARGO_BIN_EXE_spinning"))
+            .args(["--size", "32", "--lookback", "100", "--out"])
+            .arg(&out)
+            .args(extra)
+            .output()
+            .expect("ran");
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).expect("utf-8");
+        (stdout, image::open(&out).expect("saved").to_rgb8())
+    };
+    let (stdout, first) = run("first.png", &[]);
+    let seed = stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("Seed "))
+        .and_then(|rest| rest.split_whitespace().next())
+        .expect("seed reported")
+        .to_string();
+    let (stdout, again) = run("again.png", &["--seed", &seed]);
+    assert!(!stdout.contains("from the clock"));
+    assert_eq!(first, again);
+    std::fs::remove_dir_all(&dir).expect("cleaned up");
+}
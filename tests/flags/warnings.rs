@@ -0,0 +1,48 @@
+use crate::common::{params, parsed};
+
+fn warnings(args: &[&str]) -> Vec<String> {
+    parsed(args).warnings()
+}
+
+#[test]
+fn the_defaults_have_no_warnings() {
+    assert!(warnings(&[]).is_empty());
+    assert!(warnings(&["--size", "64", "--lookback", "100", "--centers", "3"]).is_empty());
+}
+
+#[test]
+fn degenerate_parameters_are_explained() {
+    let cases: [(&[&str], &str); 6] = [
+        (&["--cont-spread", "1.0"], "--cont-spread"),
+        (&["--size", "32", "--lookback", "2000"], "--lookback 2000"),
+        (&["--lookback", "0"], "--lookback 0"),
+        (
+            &["--size", "20", "--lookback", "50", "--centers", "500"],
+            "--centers 500",
+        ),
+        (
+            &["--size", "8", "--lookback", "9", "--start-spread", "0.1"],
+            "--start-spread",
+        ),
+        (&["--palette", "#ff0000,#ff0000"], "the same"),
+    ];
+    for (args, expected) in cases {
+        let found = warnings(args);
+        assert_eq!(found.len(), 1, "{args:?}: {found:?}");
+        assert!(found[0].contains(expected), "{args:?}: {found:?}");
+    }
+    // Fewer pixels to place make a smaller lookback too large.
+    assert!(warnings(&["--size", "64", "--lookback", "3000"]).is_empty());
+    assert_eq!(
+        warnings(&["--size", "64", "--lookback", "3000", "--coverage", "0.5"]).len(),
+        1
+    );
+}
+
+#[test]
+fn strict_refuses_what_would_warn() {
+    assert!(params(&["--strict"]).is_ok());
+    let err = params(&["--strict", "--cont-spread", "2"]).expect_err("refused");
+    assert!(err.contains("--cont-spread 2"), "{err}");
+    assert!(params(&["--size", "0"]).is_err());
+}
@@ -0,0 +1,34 @@
+use spinning::annotate::{annotate, recipe};
+use spinning::{Params, generate};
+
+use crate::common::parsed;
+
+#[test]
+fn the_margin_holds_the_recipe() {
+    let p = parsed(&["--size", "64", "--seed", "3", "--annotate"]);
+    let run = generate(&p, false);
+    let text = recipe(&p);
+    assert_eq!(text, "spinning --size 64 --seed 3 --annotate");
+    let (image, filled) = annotate(&run.image, &run.filled, &text);
+    assert_eq!(image.width(), 64);
+    assert!(image.height() > 64);
+    assert_eq!(filled.len(), 64);
+    assert!(filled.iter().all(|c| c.len() == image.height() as usize));
+    for (x, y, pixel) in run.image.enumerate_pixels() {
+        assert_eq!(image.get_pixel(x, y), pixel);
+    }
+    // A recipe wider than the image wraps onto more lines.
+    let (longer, _) = annotate(&run.image, &run.filled, &text.repeat(3));
+    assert!(longer.height() > image.height());
+}
+
+#[test]
+fn a_default_seed_is_spelled_out() {
+    let p = parsed(&["--size", "64"]);
+    assert_eq!(recipe(&p), format!("spinning --size 64 --seed {}", p.seed));
+}
+
+#[test]
+fn annotating_needs_a_raster() {
+    assert!(Params::from_args(["--annotate", "--voxels"].map(String::from).into_iter()).is_err());
+}
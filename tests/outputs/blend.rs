@@ -0,0 +1,92 @@
+use image::{Rgb, RgbImage};
+use spinning::blend::{Blend, blend, blend_seeds};
+use spinning::{Pixel, Placement, Run, generate};
+
+use crate::common::params;
+
+// A 2x1 run of one color, filling its cells in the given order.
+fn run(color: [u8; 3], order: &[usize]) -> Run {
+    let mut filled = vec![vec![false]; 2];
+    let placements = order
+        .iter()
+        .map(|&x| {
+            filled[x][0] = true;
+            Placement {
+                pixel: Pixel {
+                    color,
+                    loc: [x, 0],
+                    center: [x, 0],
+                    center_id: 0,
+                },
+                parent: None,
+                pos: [x as f64, 0.0],
+                steps: 0,
+            }
+        })
+        .collect();
+    Run {
+        image: RgbImage::from_pixel(2, 1, Rgb(color)),
+        filled,
+        placements,
+    }
+}
+
+#[test]
+fn blends_weigh_each_run() {
+    let (a, b, c) = (
+        run([0, 0, 0], &[0, 1]),
+        run([90, 90, 90], &[1, 0]),
+        run([30, 30, 30], &[1]),
+    );
+    let runs = [&a, &b, &c];
+    let at = |img: RgbImage, x| img.get_pixel(x, 0).0[0];
+    let mean = blend(&runs, &[1.0, 2.0, 1.0], Blend::Mean, [255; 3]);
+    assert_eq!((at(mean.clone(), 0), at(mean, 1)), (60, 53));
+    let median = blend(&runs, &[1.0, 1.0, 1.0], Blend::Median, [255; 3]);
+    assert_eq!((at(median.clone(), 0), at(median, 1)), (0, 30));
+    // Cell 0 was first in a, cell 1 first in b and c, but c only if it
+    // weighs enough to counter filling its one cell last in its run.
+    let oldest = blend(&runs, &[1.0, 1.0, 1.0], Blend::Oldest, [255; 3]);
+    assert_eq!((at(oldest.clone(), 0), at(oldest, 1)), (0, 90));
+    let oldest = blend(&runs, &[1.0, 1.0, 4.0], Blend::Oldest, [255; 3]);
+    assert_eq!(at(oldest, 1), 30);
+    // Only weighted runs count, and empty cells show the background.
+    let none = blend(&[&c], &[1.0], Blend::Mean, [255; 3]);
+    assert_eq!(at(none, 0), 255);
+}
+
+#[test]
+fn blended_seeds_are_deterministic() {
+    for mode in ["mean", "median", "oldest"] {
+        let p = params(&[
+            "--size",
+            "48",
+            "--lookback",
+            "200",
+            "--blend-seeds",
+            "3",
+            "--blend",
+            mode,
+        ])
+        .expect("parsed");
+        let first = generate(&p, p.needs_placements());
+        let image = blend_seeds(&p, &first);
+        assert_eq!(
+            blend_seeds(&p, &generate(&p, p.needs_placements())),
+            image,
+            "{mode}"
+        );
+        assert_ne!(image, first.image, "{mode}");
+    }
+}
+
+#[test]
+fn blend_flags_are_checked() {
+    assert!(params(&["--blend", "median"]).is_err());
+    assert!(params(&["--blend-seeds", "1"]).is_err());
+    assert!(params(&["--blend-seeds", "3", "--blend-weights", "1,2"]).is_err());
+    assert!(params(&["--blend-seeds", "2", "--blend-weights", "0,0"]).is_err());
+    assert!(params(&["--blend-seeds", "2", "--blend-weights", "1,-1"]).is_err());
+    assert!(params(&["--blend-seeds", "2", "--blend-weights", "1,0.5"]).is_ok());
+    assert!(params(&["--blend", "mode", "--blend-seeds", "2"]).is_err());
+}
@@ -0,0 +1,85 @@
+use std::process::Command;
+
+use spinning::centerstats::{center_stats, label_overlay, stats_csv};
+use spinning::generate;
+
+use crate::common::from_line;
+
+#[test]
+fn stats_cover_every_placed_pixel() {
+    let run = generate(&from_line("--size 48 --centers 5 --seed 3").unwrap(), true);
+    let stats = center_stats(&run.placements);
+    // Random starts spawn centers of their own, so there are more than 5.
+    let ids: std::collections::BTreeSet<usize> =
+        run.placements.iter().map(|p| p.pixel.center_id).collect();
+    assert_eq!(stats.len(), ids.len());
+    assert_eq!(stats.iter().map(|s| s.pixels).sum::<usize>(), 48 * 48);
+    assert!(stats.windows(2).all(|w| w[0].pixels >= w[1].pixels));
+    for s in &stats {
+        let [x0, y0, x1, y1] = s.bounds;
+        assert!(x0 <= x1 && y0 <= y1 && x1 < 48 && y1 < 48);
+        assert!(s.mean_radius >= 0.0);
+        assert!(s.hue.is_none_or(|h| (0.0..360.0).contains(&h)));
+    }
+
+    let csv = stats_csv(&stats);
+    assert_eq!(csv.lines().count(), stats.len() + 1);
+    assert!(csv.starts_with("id,pixels,center_x,center_y,mean_radius,dominant_hue,"));
+
+    let labeled = label_overlay(&run.image, &stats, 2);
+    assert_eq!(labeled.dimensions(), run.image.dimensions());
+    assert_ne!(labeled, run.image);
+    assert_eq!(label_overlay(&run.image, &stats, 0), run.image);
+}
+
+#[test]
+fn grays_have_no_dominant_hue() {
+    let run = generate(&from_line("--size 16 --mode grayscale").unwrap(), true);
+    assert!(
+        center_stats(&run.placements)
+            .iter()
+            .all(|s| s.hue.is_none())
+    );
+}
+
+#[test]
+fn stats_and_labels_are_written_beside_the_output() {
+    assert!(from_line("--center-stats s.csv --voxels --slices s").is_err());
+    assert!(from_line("--center-stats s.csv").unwrap().streams_output());
+    assert!(!from_line("--center-labels l.png").unwrap().streams_output());
+
+    let dir = std::env::temp_dir().join(format!("spinning-centers-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("made a directory");
+    let output = Command::new(env!("CARGO_BIN_EXE_spinning"))
+        .args(["--size", "32", "--centers", "3", "--out-dir"])
+        .arg(&dir)
+        .args([
+            "--out",
+            "piece.png",
+            "--center-stats",
+            "centers.csv",
+            "--center-labels",
+            "labels.png",
+        ])
+        .output()
+        .expect("ran");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let csv = std::fs::read_to_string(dir.join("centers.csv")).expect("wrote stats");
+    let pixels: usize = csv
+        .lines()
+        .skip(1)
+        .map(|line| line.split(',').nth(1).unwrap().parse::<usize>().unwrap())
+        .sum();
+    assert_eq!(pixels, 32 * 32);
+    let piece = image::open(dir.join("piece.png")).expect("saved").to_rgb8();
+    let labels = image::open(dir.join("labels.png"))
+        .expect("labeled")
+        .to_rgb8();
+    assert_eq!(labels.dimensions(), piece.dimensions());
+    assert_ne!(labels, piece);
+    std::fs::remove_dir_all(&dir).ok();
+}
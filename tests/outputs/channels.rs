@@ -0,0 +1,39 @@
+use spinning::channels::split_channels;
+use spinning::{Params, generate};
+
+use crate::common::params;
+
+#[test]
+fn each_channel_comes_from_its_own_seed() {
+    let p = params(&["--size", "32", "--lookback", "100", "--seed", "1"]).expect("parsed");
+    let first = generate(&p, false);
+    let (image, filled) = split_channels(&p, &first, [1, 2, 3]);
+    let runs = [2, 3].map(|seed| generate(&Params { seed, ..p.clone() }, false));
+    for (x, y, pixel) in image.enumerate_pixels() {
+        assert_eq!(pixel.0[0], first.image.get_pixel(x, y).0[0]);
+        assert_eq!(pixel.0[1], runs[0].image.get_pixel(x, y).0[1]);
+        assert_eq!(pixel.0[2], runs[1].image.get_pixel(x, y).0[2]);
+    }
+    assert!(filled.iter().flatten().all(|&f| f));
+}
+
+#[test]
+fn one_seed_for_every_channel_gives_the_plain_run() {
+    let p = params(&["--size", "32", "--lookback", "100", "--seed", "4"]).expect("parsed");
+    let first = generate(&p, false);
+    let (image, _) = split_channels(&p, &first, [4, 4, 4]);
+    assert_eq!(image, first.image);
+}
+
+#[test]
+fn channel_seeds_are_checked() {
+    assert_eq!(
+        params(&["--channel-seeds", "1,2,3"])
+            .expect("parsed")
+            .channel_seeds,
+        Some([1, 2, 3])
+    );
+    assert!(params(&["--channel-seeds", "1,2"]).is_err());
+    assert!(params(&["--channel-seeds", "1,2,3", "--voxels"]).is_err());
+    assert!(params(&["--channel-seeds", "1,2,3", "--blend-seeds", "2"]).is_err());
+}
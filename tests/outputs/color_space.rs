@@ -0,0 +1,88 @@
+use image::{ImageBuffer, RgbImage};
+use spinning::Params;
+use spinning::output::{COLOR_SPACE_KEY, ColorSpace, Thumbnail, save_png};
+
+fn tags(path: &str) -> (Option<Vec<u8>>, bool) {
+    let decoder = png::Decoder::new(std::io::BufReader::new(std::fs::File::open(path).unwrap()));
+    let reader = decoder.read_info().unwrap();
+    let info = reader.info();
+    (
+        info.icc_profile.as_ref().map(|p| p.to_vec()),
+        info.srgb.is_some(),
+    )
+}
+
+fn saved(space: Option<&str>) -> String {
+    let mut args = vec!["--seed", "4"];
+    if let Some(space) = space {
+        args.extend(["--color-space", space]);
+    }
+    let params = Params::from_args(args.into_iter().map(String::from)).unwrap();
+    let image: RgbImage = ImageBuffer::from_pixel(4, 4, image::Rgb([0, 255, 0]));
+    let path = std::env::temp_dir().join(format!("spinning-space-{}.png", space.unwrap_or("none")));
+    let path = path.to_str().unwrap().to_string();
+    save_png(&image, &path, &params.metadata()).unwrap();
+    path
+}
+
+#[test]
+fn outputs_are_tagged_with_the_color_space() {
+    assert_eq!(tags(&saved(None)), (None, false));
+    assert_eq!(tags(&saved(Some("srgb"))), (None, true));
+    for (name, space) in [
+        ("display-p3", ColorSpace::DisplayP3),
+        ("adobe-rgb", ColorSpace::AdobeRgb),
+    ] {
+        let (icc, srgb) = tags(&saved(Some(name)));
+        assert!(!srgb);
+        let profile =
+            moxcms::ColorProfile::new_from_slice(&icc.expect("embedded profile")).unwrap();
+        assert_eq!(profile.red_colorant, space.profile().red_colorant);
+    }
+    assert!(Params::from_args(["--color-space", "rec2020"].map(String::from).into_iter()).is_err());
+}
+
+#[test]
+fn thumbnails_keep_only_the_color_space() {
+    let path = std::env::temp_dir().join("spinning-space-thumb.png");
+    let path = path.to_str().unwrap();
+    let metadata = [
+        ("Seed".to_string(), "1".to_string()),
+        (COLOR_SPACE_KEY.to_string(), "display-p3".to_string()),
+    ];
+    Thumbnail::of_image(&[9; 48], 4, 4, 3, 2)
+        .save(path, &metadata)
+        .unwrap();
+    let decoder = png::Decoder::new(std::io::BufReader::new(std::fs::File::open(path).unwrap()));
+    let reader = decoder.read_info().unwrap();
+    assert!(reader.info().icc_profile.is_some());
+    assert!(
+        reader
+            .info()
+            .uncompressed_latin1_text
+            .iter()
+            .all(|t| t.keyword != "Seed")
+    );
+}
+
+#[test]
+fn separations_read_the_image_in_its_space() {
+    use spinning::print::{PrintProfile, separate};
+    let image: RgbImage = ImageBuffer::from_pixel(1, 1, image::Rgb([0, 255, 0]));
+    let srgb = separate(&image, ColorSpace::Srgb, &PrintProfile::Swop).unwrap();
+    let p3 = separate(&image, ColorSpace::DisplayP3, &PrintProfile::Swop).unwrap();
+    // The built-in presses clip P3's wider green to sRGB's, so both print alike.
+    assert_eq!(srgb.cmyk, p3.cmyk);
+    let gray: RgbImage = ImageBuffer::from_pixel(1, 1, image::Rgb([128, 128, 128]));
+    let proof = separate(&gray, ColorSpace::AdobeRgb, &PrintProfile::Swop)
+        .unwrap()
+        .proof;
+    assert!(
+        proof
+            .get_pixel(0, 0)
+            .0
+            .iter()
+            .all(|&c| c.abs_diff(128) <= 12),
+        "{proof:?}"
+    );
+}
@@ -0,0 +1,28 @@
+use spinning::Params;
+use spinning::raster::{Crop, Raster, rasterize};
+
+#[test]
+fn crop_matches_the_full_image() {
+    let params = Params::from_args(["--size", "40"].map(String::from).into_iter()).unwrap();
+    let run = spinning::generate(&params, true);
+    let crop: Crop = "10,5,20,12".parse().unwrap();
+    let raster = Raster {
+        scale: 3,
+        crop: Some(crop),
+        ..Raster::default()
+    };
+    let image = rasterize(&run.placements, 40, &raster, |c| c);
+    assert_eq!(image.dimensions(), (60, 36));
+    for (x, y, pixel) in image.enumerate_pixels() {
+        assert_eq!(pixel, run.image.get_pixel(10 + x / 3, 5 + y / 3));
+    }
+}
+
+#[test]
+fn rejects_bad_crops() {
+    let parse = |args: &[&str]| Params::from_args(args.iter().map(|a| a.to_string()));
+    assert!(parse(&["--size", "40", "--crop", "30,0,20,20"]).is_err());
+    assert!(parse(&["--crop", "0,0,0,10"]).is_err());
+    assert!(parse(&["--crop", "0,0,10,10", "--stereo", "anaglyph"]).is_err());
+    assert_eq!(parse(&["--scale", "2"]).unwrap().crop, None);
+}
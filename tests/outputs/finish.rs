@@ -0,0 +1,66 @@
+use image::{Rgb, RgbImage, Rgba, RgbaImage};
+use spinning::canvas::Unfilled;
+use spinning::finish::Finish;
+
+use crate::common::params;
+
+#[test]
+fn the_border_fades_to_the_color() {
+    let finish = Finish {
+        fade: 4,
+        to: Unfilled::Color([0, 0, 255]),
+        ..Finish::default()
+    };
+    let mut image = RgbImage::from_pixel(16, 16, Rgb([255, 255, 255]));
+    finish.apply(&mut image);
+    assert_eq!(image.get_pixel(8, 8), &Rgb([255, 255, 255]));
+    assert_eq!(image.get_pixel(4, 8), &Rgb([255, 255, 255]));
+    let edge = image.get_pixel(0, 8).0;
+    assert!(edge[0] < 80 && edge[2] == 255, "{edge:?}");
+    let between = image.get_pixel(2, 8).0;
+    assert!(between[0] > edge[0] && between[0] < 255, "{between:?}");
+}
+
+#[test]
+fn the_vignette_fades_the_corners_to_transparent() {
+    let finish = Finish {
+        vignette: 1.0,
+        to: Unfilled::Transparent,
+        ..Finish::default()
+    };
+    let mut opaque = RgbImage::from_pixel(16, 16, Rgb([9, 9, 9]));
+    finish.apply(&mut opaque);
+    assert_eq!(opaque, RgbImage::from_pixel(16, 16, Rgb([9, 9, 9])));
+    let mut image = RgbaImage::from_pixel(16, 16, Rgba([9, 9, 9, 255]));
+    finish.alpha(&mut image);
+    assert!(image.get_pixel(8, 8).0[3] > 240);
+    assert!(image.get_pixel(0, 0).0[3] < 40);
+    assert!(image.get_pixel(0, 8).0[3] > image.get_pixel(0, 0).0[3]);
+}
+
+#[test]
+fn the_fade_goes_to_the_unfilled_policy_by_default() {
+    let p = params(&["--fade", "3", "--unfilled", "ff0000"]).expect("parsed");
+    assert_eq!(p.finish().to, Unfilled::Color([255, 0, 0]));
+    let p = params(&["--vignette", "0.5", "--fade-to", "transparent"]).expect("parsed");
+    assert_eq!(p.finish().to, Unfilled::Transparent);
+    assert!(!p.streams_output());
+}
+
+#[test]
+fn fade_flags_are_checked() {
+    assert!(params(&["--fade-to", "black"]).is_err());
+    assert!(params(&["--vignette", "1.5"]).is_err());
+    assert!(params(&["--fade", "2", "--voxels", "--slices", "x"]).is_err());
+    assert!(
+        params(&[
+            "--fade",
+            "2",
+            "--fade-to",
+            "transparent",
+            "--max-colors",
+            "8"
+        ])
+        .is_err()
+    );
+}
@@ -0,0 +1,24 @@
+// Turning a run into images: rasters, post-processing, finishes, print
+// and the extra files saved alongside.
+
+#[path = "../common/mod.rs"]
+mod common;
+
+mod annotate;
+mod blend;
+mod center_stats;
+mod channels;
+mod color_space;
+mod crop;
+mod finish;
+mod mosaic;
+mod print;
+mod print_layout;
+mod raster;
+mod smear;
+mod stereo;
+mod term_preview;
+mod thumbnail;
+mod trace;
+mod vector;
+mod watermark;
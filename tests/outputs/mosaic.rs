@@ -0,0 +1,66 @@
+use image::{Rgb, RgbImage};
+use spinning::generate;
+use spinning::mosaic::mosaic;
+
+use crate::common::params;
+
+fn colors(image: &RgbImage) -> usize {
+    let mut colors: Vec<_> = image.pixels().map(|p| p.0).collect();
+    colors.sort();
+    colors.dedup();
+    colors.len()
+}
+
+#[test]
+fn a_higher_threshold_gives_fewer_tiles() {
+    let run = generate(
+        &params(&["--size", "64", "--lookback", "200"]).expect("parsed"),
+        false,
+    );
+    let exact = mosaic(&run.image, Some(&run.filled), 0.0);
+    assert_eq!(exact, run.image);
+    let fine = colors(&mosaic(&run.image, Some(&run.filled), 64.0));
+    let coarse = colors(&mosaic(&run.image, Some(&run.filled), 160.0));
+    assert!(
+        coarse < fine && fine < colors(&run.image),
+        "{coarse} {fine}"
+    );
+    let flat = mosaic(&run.image, Some(&run.filled), 255.0);
+    assert_eq!(colors(&flat), 1);
+}
+
+#[test]
+fn blocks_split_where_colors_differ() {
+    let mut image = RgbImage::from_pixel(8, 8, Rgb([200, 10, 10]));
+    for y in 0..4 {
+        for x in 0..4 {
+            let shade = (x + y) as u8;
+            image.put_pixel(x, y, Rgb([shade, shade, 100]));
+        }
+    }
+    let out = mosaic(&image, None, 10.0);
+    let corner = out.get_pixel(0, 0);
+    assert!((0..4).all(|y| (0..4).all(|x| out.get_pixel(x, y) == corner)));
+    assert_ne!(*corner, image.get_pixel(0, 0).to_owned());
+    assert_eq!(out.get_pixel(7, 7), &Rgb([200, 10, 10]));
+}
+
+#[test]
+fn unfilled_cells_are_left_alone() {
+    let image = RgbImage::from_fn(4, 4, |x, _| Rgb([x as u8 * 10, 0, 0]));
+    let filled = vec![vec![true; 4], vec![true; 4], vec![false; 4], vec![false; 4]];
+    let out = mosaic(&image, Some(&filled), 255.0);
+    assert_eq!(out.get_pixel(2, 0), image.get_pixel(2, 0));
+    assert_eq!(out.get_pixel(0, 0), out.get_pixel(1, 3));
+}
+
+#[test]
+fn mosaic_threshold_is_checked() {
+    assert!(params(&["--mosaic", "-1"]).is_err());
+    assert!(params(&["--mosaic", "300"]).is_err());
+    assert!(params(&["--mosaic", "20", "--voxels"]).is_err());
+    assert_eq!(
+        params(&["--mosaic", "20"]).expect("parsed").mosaic,
+        Some(20.0)
+    );
+}
@@ -0,0 +1,65 @@
+use image::{ImageBuffer, RgbImage};
+use spinning::output::ColorSpace;
+use spinning::print::{PrintProfile, delta_e, save_cmyk_tiff, separate};
+use tiff::ColorType;
+use tiff::decoder::{Decoder, DecodingResult};
+
+fn swatches(colors: &[[u8; 3]]) -> RgbImage {
+    ImageBuffer::from_fn(colors.len() as u32, 1, |x, _| {
+        image::Rgb(colors[x as usize])
+    })
+}
+
+#[test]
+fn separation_proofs_printable_colors_closely() {
+    let image = swatches(&[
+        [255, 255, 255],
+        [128, 128, 128],
+        [200, 160, 120],
+        [0, 0, 0],
+        [0, 0, 255],
+    ]);
+    for profile in [PrintProfile::Swop, PrintProfile::Fogra39] {
+        let separation = separate(&image, ColorSpace::Srgb, &profile).unwrap();
+        assert_eq!(&separation.cmyk[..4], &[0, 0, 0, 0]);
+        // Black stays within the ink limit, up to rounding.
+        let black: u32 = separation.cmyk[12..16].iter().map(|&v| v as u32).sum();
+        assert!(black <= 255 * 33 / 10 + 2, "{black}");
+        for x in 0..3 {
+            let d = delta_e(image.get_pixel(x, 0).0, separation.proof.get_pixel(x, 0).0);
+            assert!(d < 2.0, "{} at {x}: {d}", profile.name());
+        }
+        // Paper and ink can't reach pure black, though they come close,
+        // and pure screen blue is far outside any press.
+        assert!(spinning::print::lab(separation.proof.get_pixel(3, 0).0)[0] < 25.0);
+        assert_eq!(separation.out_of_gamut, 2);
+        assert!(separation.worst > 20.0);
+    }
+}
+
+#[test]
+fn writes_a_cmyk_tiff() {
+    let image = swatches(&[[10, 120, 200], [250, 240, 20]]);
+    let separation = separate(&image, ColorSpace::Srgb, &PrintProfile::Swop).unwrap();
+    let path = std::env::temp_dir().join("spinning-print-test.tif");
+    let path = path.to_str().unwrap();
+    save_cmyk_tiff(&separation, &PrintProfile::Swop, path).unwrap();
+    let mut decoder = Decoder::new(std::fs::File::open(path).unwrap()).unwrap();
+    assert_eq!(decoder.dimensions().unwrap(), (2, 1));
+    assert_eq!(decoder.colortype().unwrap(), ColorType::CMYK(8));
+    let DecodingResult::U8(data) = decoder.read_image().unwrap() else {
+        panic!("8-bit data");
+    };
+    assert_eq!(data, separation.cmyk);
+}
+
+#[test]
+fn icc_profiles_must_be_cmyk() {
+    let path = std::env::temp_dir().join("spinning-print-srgb.icc");
+    std::fs::write(&path, moxcms::ColorProfile::new_srgb().encode().unwrap()).unwrap();
+    let err = path.to_str().unwrap().parse::<PrintProfile>().unwrap_err();
+    assert!(err.contains("not a CMYK profile"), "{err}");
+    assert!("missing.icc".parse::<PrintProfile>().is_err());
+    let soft = ["--soft-proof", "proof.png"].map(String::from);
+    assert!(spinning::Params::from_args(soft.into_iter()).is_err());
+}
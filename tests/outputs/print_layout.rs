@@ -0,0 +1,70 @@
+use image::{Rgb, RgbImage};
+
+use spinning::paper::{Paper, Resample, Sheet, parse_length};
+
+fn sheet(paper: &str, dpi: f64, margin: &str) -> Sheet {
+    Sheet {
+        paper: paper.parse().unwrap(),
+        landscape: false,
+        dpi,
+        margin_mm: parse_length(margin).unwrap(),
+        resample: None,
+        background: [255; 3],
+    }
+}
+
+#[test]
+fn reads_papers_and_lengths() {
+    let a2: Paper = "A2".parse().unwrap();
+    assert_eq!((a2.width_mm, a2.height_mm), (420.0, 594.0));
+    let poster: Paper = "18x24in".parse().unwrap();
+    assert!((poster.width_mm - 457.2).abs() < 1e-9);
+    assert_eq!("500x700mm".parse::<Paper>().unwrap().height_mm, 700.0);
+    assert!("B5".parse::<Paper>().is_err() && "10x20".parse::<Paper>().is_err());
+    assert_eq!(parse_length("2cm").unwrap(), 20.0);
+    assert!(parse_length("20").is_err());
+
+    // A2 at 300 dpi, the size print shops expect.
+    assert_eq!(sheet("A2", 300.0, "0mm").size(), (4961, 7016));
+    let landscape = Sheet {
+        landscape: true,
+        ..sheet("A2", 300.0, "0mm")
+    };
+    assert_eq!(landscape.size(), (7016, 4961));
+}
+
+#[test]
+fn centers_the_image_at_full_resolution() {
+    // A6 at 50 dpi is 207x291 dots; a 1in margin leaves 107 across.
+    let sheet = sheet("A6", 50.0, "1in");
+    assert_eq!(sheet.fit(100, 100).unwrap(), (107, 107));
+    let image = RgbImage::from_pixel(100, 100, Rgb([10, 20, 30]));
+    assert!(sheet.lay_out(&image).unwrap_err().contains("--resample"));
+
+    let upscaled = Sheet {
+        resample: Some(Resample::Nearest),
+        ..sheet
+    };
+    let out = upscaled.lay_out(&image).unwrap();
+    assert_eq!(out.dimensions(), (207, 291));
+    assert_eq!(*out.get_pixel(103, 145), Rgb([10, 20, 30]));
+    assert_eq!(*out.get_pixel(49, 145), Rgb([255; 3]));
+    assert_eq!(*out.get_pixel(50, 145), Rgb([10, 20, 30]));
+
+    // Larger images are scaled down without asking.
+    let big = RgbImage::from_pixel(400, 400, Rgb([10, 20, 30]));
+    assert_eq!(sheet.lay_out(&big).unwrap().dimensions(), (207, 291));
+    assert!(self::sheet("A6", 50.0, "60mm").fit(10, 10).is_err());
+}
+
+#[test]
+fn sheets_too_large_to_hold_are_refused() {
+    let e = sheet("A6", 100_000.0, "0mm").fit(10, 10).unwrap_err();
+    assert!(e.contains("lower --dpi"), "{e}");
+    assert!(
+        sheet("A6", 100_000.0, "1in")
+            .lay_out(&RgbImage::new(10, 10))
+            .is_err()
+    );
+    assert!(sheet("A0", 600.0, "0mm").fit(10, 10).is_ok());
+}
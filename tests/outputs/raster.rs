@@ -0,0 +1,100 @@
+use spinning::raster::{Kernel, Raster, rasterize};
+use spinning::{Params, generate};
+
+use crate::common::fixture;
+
+#[test]
+fn positions_follow_the_orbits() {
+    let params = Params::from_args(["--size", "60"].map(String::from).into_iter()).unwrap();
+    let run = generate(&params, true);
+    let mut off_center = 0;
+    for placement in &run.placements {
+        let loc = placement.pixel.loc;
+        for (pos, cell) in placement.pos.into_iter().zip(loc) {
+            let offset = pos - (cell as f64 + 0.5);
+            assert!(offset.abs() <= 1.0, "{offset}");
+            if offset.abs() > 1e-9 {
+                off_center += 1;
+            }
+        }
+    }
+    assert!(off_center > run.placements.len() / 2);
+}
+
+#[test]
+fn splats_with_a_kernel() {
+    let params = Params::from_args(["--size", "30"].map(String::from).into_iter()).unwrap();
+    let run = generate(&params, true);
+    let raster = Raster {
+        scale: 4,
+        subpixel: true,
+        kernel: Kernel::Disc,
+        stroke: 0.5,
+        background: [1, 2, 3],
+        ..Raster::default()
+    };
+    let image = rasterize(&run.placements, 30, &raster, |c| c);
+    assert_eq!(image.dimensions(), (120, 120));
+    // Small discs leave some background between them.
+    assert!(image.pixels().any(|p| p.0 == [1, 2, 3]));
+    assert!(
+        Raster::default()
+            == Raster {
+                ..Raster::default()
+            }
+    );
+    assert!(raster.is_custom() && !Raster::default().is_custom());
+}
+
+#[test]
+fn accumulates_coverage() {
+    let params = Params::from_args(["--size", "30"].map(String::from).into_iter()).unwrap();
+    let run = generate(&params, true);
+    // Whole cells blend to exactly the last-write image.
+    let exact = Raster {
+        accumulate: true,
+        ..Raster::default()
+    };
+    assert_eq!(rasterize(&run.placements, 30, &exact, |c| c), run.image);
+    // A half-covered pixel is half background, in linear light.
+    let lone = &run.placements[..1];
+    let raster = Raster {
+        scale: 2,
+        stroke: 1.0,
+        subpixel: true,
+        accumulate: true,
+        ..Raster::default()
+    };
+    let mut shifted = lone.to_vec();
+    shifted[0].pos = [10.25, 10.5];
+    let image = rasterize(&shifted, 30, &raster, |_| [200, 100, 0]);
+    assert_eq!(image.get_pixel(20, 20).0, [200, 100, 0]);
+    assert_eq!(image.get_pixel(19, 20).0, [146, 71, 0]);
+    assert_eq!(image.get_pixel(22, 20).0, [0, 0, 0]);
+}
+
+#[test]
+fn the_base_is_drawn_under_the_raster() {
+    let dir = std::env::temp_dir().join(format!("spinning-raster-base-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("made a directory");
+    // Green on the left half, transparent on the right.
+    let base = fixture("left-half.png");
+    let out = dir.join("out.png");
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_spinning"))
+        .arg("--base")
+        .arg(&base)
+        .args(["--scale", "2", "--out"])
+        .arg(&out)
+        .output()
+        .expect("ran");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let image = image::open(&out).unwrap().to_rgb8();
+    assert_eq!(image.dimensions(), (32, 32));
+    assert_eq!(image.get_pixel(0, 0).0, [10, 200, 30]);
+    assert_eq!(image.get_pixel(15, 31).0, [10, 200, 30]);
+    std::fs::remove_dir_all(&dir).ok();
+}
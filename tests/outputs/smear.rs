@@ -0,0 +1,75 @@
+use image::{Rgb, RgbImage};
+use spinning::smear::smear;
+use spinning::{Pixel, Placement, generate};
+
+use crate::common::params;
+
+fn placement(loc: [usize; 2], center: [usize; 2]) -> Placement {
+    Placement {
+        pixel: Pixel {
+            color: [0, 0, 0],
+            loc,
+            center,
+            center_id: 0,
+        },
+        parent: None,
+        pos: loc.map(|c| c as f64 + 0.5),
+        steps: 0,
+    }
+}
+
+#[test]
+fn smears_along_the_orbit_not_across_it() {
+    // Centered far below, the orbits through the top rows run sideways, so
+    // a smear mixes a vertical stripe into its row but not into its column.
+    let mut image = RgbImage::from_pixel(9, 9, Rgb([0, 0, 0]));
+    for y in 0..9 {
+        image.put_pixel(4, y, Rgb([255, 255, 255]));
+    }
+    let placements: Vec<_> = (0..9)
+        .flat_map(|y| (0..9).map(move |x| placement([x, y], [4, 1000])))
+        .collect();
+    let out = smear(&image, &placements, 2, 1.0);
+    assert!(out.get_pixel(4, 4).0[0] < 255);
+    assert!(out.get_pixel(3, 4).0[0] > 0);
+    assert_eq!(out.get_pixel(0, 4).0[0], 0);
+    let half = smear(&image, &placements, 2, 0.5);
+    assert!(half.get_pixel(3, 4).0[0] < out.get_pixel(3, 4).0[0]);
+    assert_eq!(smear(&image, &placements, 2, 0.0), image);
+}
+
+#[test]
+fn smear_leaves_unplaced_cells_alone() {
+    let image = RgbImage::from_fn(5, 5, |x, _| Rgb([x as u8 * 50, 0, 0]));
+    let out = smear(&image, &[placement([2, 2], [2, 100])], 3, 1.0);
+    assert_eq!(out.get_pixel(1, 1), image.get_pixel(1, 1));
+    // Its neighbors along the orbit are unplaced, so the walk stops at once.
+    assert_eq!(out.get_pixel(2, 2), image.get_pixel(2, 2));
+}
+
+#[test]
+fn smearing_a_run_softens_it() {
+    let base = ["--size", "48", "--lookback", "200"];
+    let plain = generate(&params(&base).expect("parsed"), true);
+    let smeared = smear(&plain.image, &plain.placements, 4, 1.0);
+    assert_ne!(smeared, plain.image);
+    let roughness = |image: &RgbImage| -> u64 {
+        image
+            .enumerate_pixels()
+            .filter(|(x, _, _)| *x > 0)
+            .map(|(x, y, p)| p.0[0].abs_diff(image.get_pixel(x - 1, y).0[0]) as u64)
+            .sum()
+    };
+    assert!(roughness(&smeared) < roughness(&plain.image));
+}
+
+#[test]
+fn smear_flags_are_checked() {
+    assert!(params(&["--smear", "0"]).is_err());
+    assert!(params(&["--smear", "3", "--smear-strength", "2"]).is_err());
+    assert!(params(&["--smear-strength", "0.5"]).is_err());
+    assert!(params(&["--smear", "3", "--scale", "2"]).is_err());
+    let p = params(&["--smear", "3", "--smear-strength", "0.5"]).expect("parsed");
+    assert_eq!((p.smear, p.smear_strength), (Some(3), 0.5));
+    assert!(p.needs_placements());
+}
@@ -0,0 +1,37 @@
+use spinning::stereo::{Depth, StereoFormat, depth_map, stereo_image};
+use spinning::{Params, generate};
+
+#[test]
+fn no_parallax_gives_the_plain_image() {
+    let params = Params::from_args(["--size", "40"].map(String::from).into_iter()).unwrap();
+    let run = generate(&params, true);
+    for depth in [Depth::Radius, Depth::Age] {
+        let anaglyph = stereo_image(
+            &run.placements,
+            40,
+            StereoFormat::Anaglyph,
+            depth,
+            0.0,
+            [0; 3],
+        );
+        assert_eq!(anaglyph, run.image);
+        let pair = stereo_image(
+            &run.placements,
+            40,
+            StereoFormat::SideBySide,
+            depth,
+            6.0,
+            [0; 3],
+        );
+        assert_eq!(pair.dimensions(), (80, 40));
+    }
+}
+
+#[test]
+fn depth_map_spans_the_gray_range() {
+    let params = Params::from_args(["--size", "40"].map(String::from).into_iter()).unwrap();
+    let run = generate(&params, true);
+    let map = depth_map(&run.placements, 40, 40);
+    assert_eq!(map.dimensions(), (40, 40));
+    assert_eq!(map.pixels().map(|p| p.0[0]).max(), Some(255));
+}
@@ -0,0 +1,146 @@
+use image::{Rgb, RgbImage};
+use spinning::Params;
+use spinning::termpreview::{Graphics, TermPreview, base64, kitty, sixel};
+
+fn gradient(width: u32, height: u32) -> RgbImage {
+    RgbImage::from_fn(width, height, |x, y| {
+        Rgb([(x * 4) as u8, (y * 4) as u8, 128])
+    })
+}
+
+fn unbase64(text: &str) -> Vec<u8> {
+    let value = |c: u8| match c {
+        b'A'..=b'Z' => c - b'A',
+        b'a'..=b'z' => c - b'a' + 26,
+        b'0'..=b'9' => c - b'0' + 52,
+        b'+' => 62,
+        _ => 63,
+    } as u32;
+    let mut out = vec![];
+    for quad in text.as_bytes().chunks(4) {
+        let pad = quad.iter().filter(|&&c| c == b'=').count();
+        let n = quad
+            .iter()
+            .map(|&c| if c == b'=' { 0 } else { value(c) })
+            .fold(0, |n, v| n << 6 | v);
+        out.extend(&n.to_be_bytes()[1..4 - pad]);
+    }
+    out
+}
+
+#[test]
+fn previews_parse_with_widths() {
+    let parsed = |s: &str| s.parse::<TermPreview>();
+    assert_eq!(
+        parsed("kitty").map(|p| p.graphics),
+        Ok(Some(Graphics::Kitty))
+    );
+    assert_eq!(parsed("sixel:100").map(|p| p.width), Ok(100));
+    assert_eq!(parsed("auto").map(|p| p.graphics), Ok(None));
+    for bad in ["png", "kitty:0", "sixel:wide"] {
+        assert!(parsed(bad).is_err(), "{bad}");
+    }
+    let args = ["--term-preview", "kitty", "--voxels", "--slices", "out"];
+    assert!(Params::from_args(args.iter().map(|a| a.to_string())).is_err());
+}
+
+#[test]
+fn base64_round_trips() {
+    for len in 0..10 {
+        let data: Vec<u8> = (0..len).map(|i| (i * 37) as u8).collect();
+        assert_eq!(unbase64(&base64(&data)), data);
+    }
+    assert_eq!(base64(b"spin"), "c3Bpbg==");
+}
+
+#[test]
+fn kitty_carries_the_pixels_in_chunks() {
+    let img = gradient(50, 40);
+    let escape = kitty(&img);
+    assert!(escape.starts_with("\x1b_Ga=T,f=24,q=2,s=50,v=40,m=1;"));
+    let payload: String = escape
+        .trim_end()
+        .split("\x1b\\")
+        .filter(|part| !part.is_empty())
+        .map(|part| part.split_once(';').expect("payload").1)
+        .collect();
+    assert_eq!(unbase64(&payload), img.as_raw().clone());
+    // Only the last chunk says no more follow.
+    let chunks = escape.matches("\x1b_G").count();
+    assert!(chunks > 1);
+    assert_eq!(escape.matches("m=1;").count(), chunks - 1);
+    assert!(escape.contains("\x1b_Gm=0;"));
+}
+
+// Decodes the sixel data back to a palette index per pixel.
+fn unsixel(escape: &str) -> (usize, usize, Vec<Option<usize>>) {
+    let body = escape
+        .strip_prefix("\x1bPq\"1;1;")
+        .and_then(|s| s.strip_suffix("\x1b\\\n"))
+        .expect("framed");
+    let (size, mut rest) = body.split_at(body.find('#').expect("palette"));
+    let (width, height) = size.split_once(';').expect("size");
+    let (width, height): (usize, usize) = (width.parse().unwrap(), height.parse().unwrap());
+    let mut pixels = vec![None; width * height];
+    let (mut color, mut x, mut top) = (0, 0, 0);
+    while let Some(c) = rest.chars().next() {
+        rest = &rest[1..];
+        let number = |rest: &mut &str| {
+            let len = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let (n, tail) = rest.split_at(len);
+            *rest = tail;
+            n.parse::<usize>().expect("number")
+        };
+        match c {
+            '#' => {
+                color = number(&mut rest);
+                // Skips a color definition.
+                while rest.starts_with(|c: char| c == ';' || c.is_ascii_digit()) {
+                    rest = &rest[1..];
+                }
+            }
+            '$' => x = 0,
+            '-' => (x, top) = (0, top + 6),
+            _ => {
+                let (run, c) = if c == '!' {
+                    let run = number(&mut rest);
+                    let c = rest.chars().next().expect("sixel");
+                    rest = &rest[1..];
+                    (run, c)
+                } else {
+                    (1, c)
+                };
+                let bits = c as usize - 63;
+                for _ in 0..run {
+                    for dy in 0..6 {
+                        if bits & 1 << dy != 0 {
+                            pixels[(top + dy) * width + x] = Some(color);
+                        }
+                    }
+                    x += 1;
+                }
+            }
+        }
+    }
+    (width, height, pixels)
+}
+
+#[test]
+fn sixel_draws_every_pixel_once() {
+    let img = gradient(45, 17);
+    let (width, height, pixels) = unsixel(&sixel(&img));
+    assert_eq!((width, height), (45, 17));
+    assert!(pixels.iter().all(Option::is_some));
+    let (_, indices) = spinning::quantize::quantize_image(&img, 256);
+    let decoded: Vec<u8> = pixels.iter().map(|p| p.unwrap() as u8).collect();
+    assert_eq!(decoded, indices);
+}
+
+#[test]
+fn previews_are_scaled_to_the_width() {
+    let preview: TermPreview = "kitty:20".parse().unwrap();
+    assert!(preview.encode(&gradient(60, 30)).contains("s=20,v=10,"));
+    assert!(preview.encode(&gradient(10, 30)).contains("s=10,v=30,"));
+}
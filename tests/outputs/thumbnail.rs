@@ -0,0 +1,28 @@
+use spinning::output::{Thumbnail, thumbnail_path};
+
+#[test]
+fn averages_covered_pixels() {
+    // A 4x2 image: left half black and white stripes, right half red.
+    let mut data = vec![];
+    for y in 0..2 {
+        for x in 0..4 {
+            let v = if x < 2 { 255 * ((x + y) % 2) } else { 0 };
+            data.extend(if x < 2 { [v, v, v] } else { [200, 0, 0] });
+        }
+    }
+    let path = std::env::temp_dir().join(format!("spinning-thumb-{}.png", std::process::id()));
+    let path = path.to_str().unwrap();
+    Thumbnail::of_image(&data, 4, 2, 3, 2)
+        .save(path, &[])
+        .unwrap();
+    let thumb = image::open(path).unwrap().to_rgb8();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(thumb.dimensions(), (2, 1));
+    assert_eq!(thumb.get_pixel(0, 0).0, [188, 188, 188]);
+    assert_eq!(thumb.get_pixel(1, 0).0, [200, 0, 0]);
+}
+
+#[test]
+fn suffix_before_extension() {
+    assert_eq!(thumbnail_path("out/img-025.png"), "out/img-025-thumb.png");
+}
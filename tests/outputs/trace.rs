@@ -0,0 +1,75 @@
+use image::{Rgb, RgbImage};
+
+use spinning::trace::{outlines, trace_svg};
+use spinning::{Params, generate};
+
+// The area a path's loops enclose, holes taken away.
+fn area(d: &str) -> i64 {
+    let mut total = 0;
+    for part in d.split('M').filter(|p| !p.is_empty()) {
+        let part = part.trim().trim_end_matches('Z');
+        let (start, moves) = part.split_at(part.find(['H', 'V']).unwrap());
+        let (x, y) = start.split_once(' ').unwrap();
+        let first: [i64; 2] = [x.parse().unwrap(), y.parse().unwrap()];
+        let mut at = first;
+        let mut corners = vec![at];
+        let mut rest = moves;
+        while !rest.is_empty() {
+            let end = rest[1..].find(['H', 'V']).map_or(rest.len(), |i| i + 1);
+            let value = rest[1..end].parse().unwrap();
+            if rest.starts_with('H') {
+                at[0] = value;
+            } else {
+                at[1] = value;
+            }
+            corners.push(at);
+            rest = &rest[end..];
+        }
+        corners.push(first);
+        total += corners
+            .windows(2)
+            .map(|w| w[0][0] * w[1][1] - w[1][0] * w[0][1])
+            .sum::<i64>();
+    }
+    total / 2
+}
+
+fn paths(svg: &str) -> Vec<(String, String)> {
+    svg.split("<g id=\"color-")
+        .skip(1)
+        .map(|g| {
+            let hex = g[..6].to_string();
+            let d = g.split(" d=\"").nth(1).unwrap();
+            (hex, d[..d.find('"').unwrap()].to_string())
+        })
+        .collect()
+}
+
+#[test]
+fn traces_squares_and_holes() {
+    let loops = outlines(4, 4, |x, y| (1..3).contains(&x) && (1..3).contains(&y));
+    assert_eq!(loops, vec![vec![[1, 1], [3, 1], [3, 3], [1, 3]]]);
+
+    let mut image = RgbImage::from_pixel(4, 4, Rgb([200, 0, 0]));
+    for (x, y) in [(1, 1), (2, 1), (1, 2), (2, 2)] {
+        image.put_pixel(x, y, Rgb([0, 0, 200]));
+    }
+    let svg = trace_svg(&image, None, 8);
+    let layers = paths(&svg);
+    assert_eq!(layers.len(), 2);
+    assert_eq!(layers[0].0, "c80000");
+    assert_eq!(area(&layers[0].1), 12);
+    assert_eq!(layers[1], ("0000c8".to_string(), "M1 1H3V3H1Z".to_string()));
+}
+
+#[test]
+fn layers_tile_a_run() {
+    let params = Params::from_args(["--size", "48"].map(String::from).into_iter()).unwrap();
+    let image = generate(&params, false).image;
+    let svg = trace_svg(&image, None, 6);
+    let layers = paths(&svg);
+    assert!(layers.len() <= 6 && layers.len() > 1);
+    assert_eq!(layers.iter().map(|(_, d)| area(d)).sum::<i64>(), 48 * 48);
+
+    assert!(Params::from_args(["--trace-colors", "4"].map(String::from).into_iter()).is_err());
+}
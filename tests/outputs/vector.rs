@@ -0,0 +1,120 @@
+use spinning::chains::{chain_ids, polylines};
+use spinning::plot::{PlotFormat, PlotSettings, write_plot};
+use spinning::svg::write_svg;
+use spinning::{Color, Location, Pixel, Placement, generate};
+
+use crate::common::parsed;
+
+fn placement(loc: Location, parent: Option<Location>, color: Color) -> Placement {
+    Placement {
+        pixel: Pixel {
+            color,
+            loc,
+            center: loc,
+            center_id: 0,
+        },
+        parent,
+        pos: loc.map(|c| c as f64 + 0.5),
+        steps: 0,
+    }
+}
+
+// Two insertions: the first grows a line that branches at [1, 0], the
+// second a single step. The last placement continues from a pixel the
+// record doesn't hold.
+fn branching() -> Vec<Placement> {
+    vec![
+        placement([0, 0], None, [0, 0, 0]),
+        placement([1, 0], Some([0, 0]), [100, 0, 0]),
+        placement([5, 5], None, [0, 0, 0]),
+        placement([2, 0], Some([1, 0]), [200, 0, 0]),
+        placement([1, 1], Some([1, 0]), [0, 90, 0]),
+        placement([5, 6], Some([5, 5]), [0, 0, 30]),
+        placement([3, 0], Some([2, 0]), [0, 0, 0]),
+        placement([9, 9], Some([8, 8]), [10, 10, 10]),
+    ]
+}
+
+#[test]
+fn chains_follow_parents_from_each_insertion() {
+    assert_eq!(chain_ids(&branching()), [0, 0, 1, 0, 0, 1, 0, 2]);
+}
+
+#[test]
+fn branches_split_into_polylines() {
+    let lines = polylines(&branching());
+    let points: Vec<(usize, Vec<Location>)> =
+        lines.iter().map(|l| (l.chain, l.points.clone())).collect();
+    assert_eq!(
+        points,
+        [
+            (0, vec![[0, 0], [1, 0], [2, 0], [3, 0]]),
+            (0, vec![[1, 0], [1, 1]]),
+            (1, vec![[5, 5], [5, 6]]),
+            (2, vec![[8, 8], [9, 9]]),
+        ]
+    );
+    // Stroked with the mean of the placements drawn, not the start.
+    assert_eq!(lines[0].color, [100, 0, 0]);
+    assert_eq!(lines[1].color, [0, 90, 0]);
+}
+
+// Every continuation lands on exactly one polyline, after its start.
+#[test]
+fn polylines_cover_every_continuation() {
+    let run = generate(&parsed(&["--size", "24", "--seed", "7"]), true);
+    let lines = polylines(&run.placements);
+    let continued = run.placements.iter().filter(|p| p.parent.is_some()).count();
+    let drawn: usize = lines.iter().map(|l| l.points.len() - 1).sum();
+    assert_eq!(drawn, continued);
+    let chains = chain_ids(&run.placements);
+    assert!(lines.windows(2).all(|w| w[0].chain <= w[1].chain));
+    assert!(
+        lines
+            .iter()
+            .all(|l| l.chain <= *chains.iter().max().unwrap())
+    );
+}
+
+#[test]
+fn svg_groups_polylines_by_chain() {
+    let path = std::env::temp_dir().join(format!("spinning-vector-{}.svg", std::process::id()));
+    write_svg(&branching(), 10, 8, path.to_str().unwrap()).unwrap();
+    let svg = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(svg.starts_with(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="8" viewBox="0 0 10 8">"#
+    ));
+    assert_eq!(svg.matches("<g id=").count(), 3);
+    assert_eq!(svg.matches("<polyline").count(), 4);
+    assert!(
+        svg.contains(r##"<polyline stroke="#640000" points="0.5,0.5 1.5,0.5 2.5,0.5 3.5,0.5"/>"##)
+    );
+    assert_eq!(svg.matches("<g").count(), svg.matches("</g>").count());
+    assert!(svg.ends_with("</svg>\n"));
+}
+
+#[test]
+fn plots_flip_to_millimeters_with_a_pen_per_color() {
+    let settings = PlotSettings {
+        pen_levels: 4,
+        width_mm: 100.0,
+    };
+    let path = std::env::temp_dir().join(format!("spinning-vector-{}.gcode", std::process::id()));
+    let path = path.to_str().unwrap();
+    write_plot(&branching(), 10, 10, PlotFormat::Gcode, &settings, path).unwrap();
+    let gcode = std::fs::read_to_string(path).unwrap();
+    // Four levels a channel quantize the strokes to black, green and red pens.
+    assert_eq!(gcode.matches("M0\n").count(), 3);
+    // Pixel [0, 0] is 10mm square, so its middle is 5mm in and 95mm up.
+    assert!(gcode.contains("G0 X5.00 Y95.00\nG1 Z0 F1000\nG1 X15.00 Y95.00 F3000\n"));
+    assert!(gcode.ends_with("G0 X0 Y0\nM2\n"));
+
+    write_plot(&branching(), 10, 10, PlotFormat::Hpgl, &settings, path).unwrap();
+    let hpgl = std::fs::read_to_string(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert!(hpgl.starts_with("IN;\nSP1;\n"));
+    assert!(hpgl.contains("SP3;\nPU200,3800;\nPD600,3800,1000,3800,1400,3800;\n"));
+    assert_eq!(hpgl.matches("PD").count(), 4);
+    assert!(hpgl.ends_with("PU0,0;SP0;\n"));
+}
@@ -0,0 +1,39 @@
+use spinning::watermark::{Key, embed, hex, verify};
+use spinning::{Params, make_image};
+
+const SECRET: &str = "0101010101010101010101010101010101010101010101010101010101010101";
+
+fn image() -> image::RgbImage {
+    let args = ["--size", "64", "--seed", "8"].map(String::from);
+    make_image(&Params::from_args(args.into_iter()).expect("parsed"))
+}
+
+#[test]
+fn signed_images_verify() {
+    let key = Key::from_hex(SECRET).expect("key");
+    let mut signed = image();
+    embed(&mut signed, &key, "Ada", "spinning --size 64 --seed 8").expect("signed");
+    let found = verify(&signed).expect("verified");
+    assert_eq!(found.author, "Ada");
+    assert_eq!(found.recipe, "spinning --size 64 --seed 8");
+    assert_eq!(hex(&found.public_key), key.public_key());
+    // Only the lowest blue bits change.
+    for (a, b) in image().pixels().zip(signed.pixels()) {
+        assert_eq!(a.0[..2], b.0[..2]);
+        assert!(a[2].abs_diff(b[2]) <= 1);
+    }
+}
+
+#[test]
+fn tampering_breaks_the_signature() {
+    let key = Key::from_hex(SECRET).expect("key");
+    let mut signed = image();
+    embed(&mut signed, &key, "", "spinning --size 64 --seed 8").expect("signed");
+    let mut edited = signed.clone();
+    edited.get_pixel_mut(63, 63)[0] ^= 0x10;
+    assert!(verify(&edited).is_err());
+    assert!(verify(&image()).is_err());
+    let mut tiny = image::RgbImage::new(8, 8);
+    assert!(embed(&mut tiny, &key, "", "spinning").is_err());
+    assert!(Key::from_hex("abc").is_err());
+}
@@ -0,0 +1,62 @@
+use spinning::export::{MAGIC, RECORD, read_placements, write_placements};
+use spinning::{Params, Pixel, Placement, generate};
+
+#[test]
+fn writes_every_placement() {
+    let params = Params::from_args(["--size", "30"].map(String::from).into_iter()).unwrap();
+    let run = generate(&params, true);
+    let dir = std::env::temp_dir();
+    let id = std::process::id();
+
+    let bin = dir.join(format!("spinning-export-{id}.bin"));
+    write_placements(&run.placements, 30, bin.to_str().unwrap()).unwrap();
+    let data = std::fs::read(&bin).unwrap();
+    std::fs::remove_file(&bin).unwrap();
+    assert_eq!(&data[..4], MAGIC);
+    assert_eq!(u32::from_le_bytes(data[8..12].try_into().unwrap()), 30);
+    assert_eq!(data.len(), 16 + 900 * RECORD);
+
+    let json = dir.join(format!("spinning-export-{id}.ndjson"));
+    write_placements(&run.placements, 30, json.to_str().unwrap()).unwrap();
+    let text = std::fs::read_to_string(&json).unwrap();
+    std::fs::remove_file(&json).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 900);
+    assert!(lines[0].starts_with(r#"{"index":0,"#) && lines[0].contains(r#""parent":null"#));
+}
+
+#[test]
+fn parents_outside_the_record_are_written_as_none() {
+    let placement = |loc, parent| Placement {
+        pixel: Pixel {
+            color: [1, 2, 3],
+            loc,
+            center: [0, 0],
+            center_id: 0,
+        },
+        parent,
+        pos: [0.0; 2],
+        steps: 1,
+    };
+    // The first continues from a pixel that was never recorded.
+    let placements = [
+        placement([1, 1], Some([0, 0])),
+        placement([2, 1], Some([1, 1])),
+    ];
+    let dir = std::env::temp_dir();
+    let id = std::process::id();
+    let json = dir.join(format!("spinning-export-orphan-{id}.ndjson"));
+    write_placements(&placements, 4, json.to_str().unwrap()).unwrap();
+    let text = std::fs::read_to_string(&json).unwrap();
+    std::fs::remove_file(&json).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert!(lines[0].contains(r#""parent":null"#));
+    assert!(lines[1].contains(r#""parent":0"#));
+
+    let bin = dir.join(format!("spinning-export-orphan-{id}.bin"));
+    write_placements(&placements, 4, bin.to_str().unwrap()).unwrap();
+    let (_, read) = read_placements(bin.to_str().unwrap()).unwrap();
+    std::fs::remove_file(&bin).unwrap();
+    assert_eq!(read[0].parent, None);
+    assert_eq!(read[1].parent, Some([1, 1]));
+}
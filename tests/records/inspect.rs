@@ -0,0 +1,51 @@
+use spinning::export::{read_placements, write_placements};
+use spinning::generate;
+use spinning::inspect::provenance;
+
+use crate::common::parsed;
+
+#[test]
+fn traces_a_pixel_to_its_match() {
+    let run = generate(&parsed(&["--size", "40", "--seed", "2"]), true);
+    let path = std::env::temp_dir().join(format!("spinning-inspect-{}.bin", std::process::id()));
+    write_placements(&run.placements, 40, path.to_str().unwrap()).unwrap();
+    let (_, placements) = read_placements(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(
+        placements
+            .iter()
+            .zip(&run.placements)
+            .all(|(read, placed)| read.steps == placed.steps)
+    );
+    // Walked pixels try at least the cell they land on.
+    assert!(placements.iter().any(|p| p.steps > 1));
+    assert!(
+        placements
+            .iter()
+            .filter(|p| p.parent.is_some())
+            .all(|p| p.steps > 0)
+    );
+
+    let walked = placements.iter().rposition(|p| p.parent.is_some()).unwrap();
+    let loc = placements[walked].pixel.loc;
+    let found = provenance(&placements, loc).expect("filled");
+    assert_eq!(found.index, walked);
+    let (at, matched) = found.matched.expect("walked from a match");
+    assert!(at < walked);
+    assert_eq!(Some(matched.pixel.loc), placements[walked].parent);
+    assert!(found.report()[0].starts_with(&format!("Pixel {},{}", loc[0], loc[1])));
+
+    let first = provenance(&placements, placements[0].pixel.loc).expect("filled");
+    assert!(first.matched.is_none());
+}
+
+#[test]
+fn unfilled_cells_have_no_provenance() {
+    let run = generate(&parsed(&["--size", "20", "--max-pixels", "50"]), true);
+    let filled: Vec<_> = run.placements.iter().map(|p| p.pixel.loc).collect();
+    let open = (0..20)
+        .flat_map(|x| (0..20).map(move |y| [x, y]))
+        .find(|loc| !filled.contains(loc))
+        .unwrap();
+    assert!(provenance(&run.placements, open).is_none());
+}
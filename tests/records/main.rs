@@ -0,0 +1,12 @@
+// Runs written down and read back: exports, replays, scenes and the run
+// database.
+
+#[path = "../common/mod.rs"]
+mod common;
+
+mod export;
+mod inspect;
+mod replay;
+mod runs;
+mod scene;
+mod shader;
@@ -0,0 +1,44 @@
+use spinning::export::{read_placements, write_placements};
+use spinning::raster::Raster;
+use spinning::replay::{Style, parse_palette, render_replay};
+use spinning::{Params, generate};
+
+#[test]
+fn replays_an_export() {
+    let params = Params::from_args(["--size", "40"].map(String::from).into_iter()).unwrap();
+    let run = generate(&params, true);
+    let path = std::env::temp_dir().join(format!("spinning-replay-{}.bin", std::process::id()));
+    write_placements(&run.placements, 40, path.to_str().unwrap()).unwrap();
+    let (size, placements) = read_placements(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(size, 40);
+    assert_eq!(placements.len(), run.placements.len());
+    assert_eq!(placements[5].parent, run.placements[5].parent);
+    for (read, placed) in placements.iter().zip(&run.placements) {
+        assert!((0..2).all(|i| (read.pos[i] - placed.pos[i]).abs() < 1e-3));
+    }
+
+    assert_eq!(
+        render_replay(&placements, size, &Style::default()),
+        run.image
+    );
+
+    let style = Style {
+        raster: Raster {
+            scale: 3,
+            ..Raster::default()
+        },
+        palette: Some(parse_palette("viridis").unwrap()),
+    };
+    let image = render_replay(&placements, size, &style);
+    assert_eq!(image.dimensions(), (120, 120));
+    assert_ne!(image.get_pixel(0, 0).0, run.image.get_pixel(0, 0).0);
+}
+
+#[test]
+fn rejects_other_files() {
+    let path = std::env::temp_dir().join(format!("spinning-replay-bad-{}.bin", std::process::id()));
+    std::fs::write(&path, b"not a replay").unwrap();
+    assert!(read_placements(path.to_str().unwrap()).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
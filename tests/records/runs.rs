@@ -0,0 +1,152 @@
+use std::process::Command;
+
+use spinning::runs::{RunRecord, append_run, load_runs, parse_runs, timestamp};
+
+fn spinning(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_spinning"))
+        .args(args)
+        .output()
+        .expect("ran");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("utf-8")
+}
+
+#[test]
+fn records_round_trip_in_order() {
+    let dir = std::env::temp_dir().join(format!("spinning-runs-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("made a directory");
+    let db = dir.join("runs.db");
+    let db = db.to_str().unwrap();
+    assert!(load_runs(db).expect("missing is empty").is_empty());
+    let record = RunRecord {
+        time: 86400 * 365 + 3661,
+        args: [
+            "--size",
+            "8",
+            "--palette",
+            "magma",
+            "--seed-text",
+            "line1\n\nline2\\n",
+            "",
+        ]
+        .map(String::from)
+        .to_vec(),
+        seed: 19,
+        seconds: 0.25,
+        placed: 64,
+        total: 64,
+        coverage: 1.0,
+        output: "out dir/piece.png".to_string(),
+        thumbnail: b"\x89PNG\n\n\0bytes".to_vec(),
+        ..Default::default()
+    };
+    assert_eq!(append_run(db, record.clone()).unwrap(), 1);
+    assert_eq!(append_run(db, record.clone()).unwrap(), 2);
+    let runs = load_runs(db).unwrap();
+    assert_eq!(runs.len(), 2);
+    assert_eq!(runs[1], RunRecord { id: 2, ..record });
+    assert_eq!(runs[0].flag("--palette"), Some("magma"));
+    assert_eq!(runs[0].flag("--seed"), None);
+    assert_eq!(runs[0].flag("--seed-text"), Some("line1\n\nline2\\n"));
+    assert_eq!(timestamp(runs[0].time), "1971-01-01 01:01:01");
+
+    let bytes = std::fs::read(db).unwrap();
+    assert!(parse_runs(&bytes[..bytes.len() - 1]).is_err());
+    assert!(parse_runs(b"PNG!").is_err());
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn runs_added_at_once_get_their_own_ids() {
+    let dir = std::env::temp_dir().join(format!("spinning-runs-lock-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("made a directory");
+    let db = dir.join("runs.db");
+    let db = db.to_str().unwrap();
+    let ids: Vec<u64> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..8)
+            .map(|k| {
+                scope.spawn(move || {
+                    (0..5)
+                        .map(|n| {
+                            let record = RunRecord {
+                                output: format!("{k}-{n}.png"),
+                                ..Default::default()
+                            };
+                            append_run(db, record).unwrap()
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|w| w.join().unwrap())
+            .collect()
+    });
+    let mut sorted = ids.clone();
+    sorted.sort();
+    assert_eq!(sorted, (1..=40).collect::<Vec<u64>>());
+    let runs = load_runs(db).unwrap();
+    assert_eq!(runs.len(), 40);
+    assert!(runs.iter().zip(1..).all(|(run, id)| run.id == id));
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn runs_are_listed_shown_and_rendered_again() {
+    let dir = std::env::temp_dir().join(format!("spinning-runs-cli-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("made a directory");
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+    let db = path("runs.db");
+    spinning(&["--size", "24", "--run-db", &db, "--out", &path("a.png")]);
+    spinning(&[
+        "--size",
+        "24",
+        "--palette",
+        "magma",
+        "--run-db",
+        &db,
+        "--out",
+        &path("b.png"),
+    ]);
+
+    let list = spinning(&["runs", "list", "--db", &db]);
+    assert_eq!(list.lines().count(), 2);
+    let list = spinning(&["runs", "list", "--db", &db, "--with", "--palette=magma"]);
+    assert_eq!(list.lines().count(), 1);
+    assert!(list.contains("b.png"));
+    assert!(spinning(&["runs", "list", "--db", &db, "--with", "--palette=viridis"]).is_empty());
+
+    let show = spinning(&[
+        "runs",
+        "show",
+        "1",
+        "--db",
+        &db,
+        "--thumbnail",
+        &path("t.png"),
+    ]);
+    assert!(show.contains("placed: 576 of 576"));
+    let thumbnail = image::open(path("t.png")).expect("thumbnail").to_rgb8();
+    assert_eq!(thumbnail.dimensions(), (24, 24));
+
+    spinning(&[
+        "runs",
+        "re-render",
+        "2",
+        "--db",
+        &db,
+        "--out",
+        &path("c.png"),
+    ]);
+    let again = image::open(path("c.png")).expect("rendered").to_rgb8();
+    assert_eq!(again, image::open(path("b.png")).unwrap().to_rgb8());
+    let runs = load_runs(&db).unwrap();
+    assert_eq!(runs.len(), 3);
+    assert_eq!(runs[2].output, path("c.png"));
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,239 @@
+use rand::RngCore;
+use spinning::center::UNKNOWN_CENTER;
+use spinning::rng::{RngKind, SeededRng};
+use spinning::scene::{Scene, SceneWriter, parse_seeds, scene_bytes};
+use spinning::{Generator, Params, make_image};
+
+fn params(args: &[String]) -> Params {
+    Params::from_args(args.iter().cloned()).expect("parsed")
+}
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|a| a.to_string()).collect()
+}
+
+// Runs args to coverage 0.4, saves the scene and resumes it with extra.
+fn resumed(base: &[&str], extra: &[&str]) -> Generator {
+    let mut partial = args(base);
+    partial.extend(args(&["--coverage", "0.4"]));
+    let mut generator = Generator::new(&params(&partial), false);
+    while generator.step() {}
+    let scene = Scene::from_bytes(scene_bytes(&generator)).expect("read back");
+    let extra = args(extra);
+    let reseed = extra.iter().any(|a| a == "--seed");
+    let p = params(&scene.args_with(&extra));
+    let mut generator = scene.generator(&p, false, reseed).expect("resumed");
+    assert!(generator.placed() > 0 && !generator.is_done());
+    while generator.step() {}
+    generator
+}
+
+#[test]
+fn resuming_finishes_the_same_image() {
+    for base in [
+        &["--size", "64", "--seed", "4"][..],
+        &[
+            "--size",
+            "64",
+            "--seed",
+            "4",
+            "--streams",
+            "--rng",
+            "chacha8",
+        ],
+        &[
+            "--size",
+            "64",
+            "--seed",
+            "4",
+            "--eviction",
+            "spatial",
+            "--sampling",
+            "center",
+        ],
+        &[
+            "--size",
+            "64",
+            "--seed",
+            "4",
+            "--center-palettes",
+            "--merge-centers",
+            "3",
+        ],
+        &[
+            "--size",
+            "64",
+            "--seed",
+            "4",
+            "--rng",
+            "xoshiro256pp",
+            "--color-jitter",
+            "5",
+        ],
+        &["--size", "64", "--seed", "4", "--center-stretch", "0.5..2"],
+    ] {
+        let generator = resumed(base, &[]);
+        assert_eq!(
+            generator.image(),
+            make_image(&params(&args(base))),
+            "{base:?}"
+        );
+    }
+}
+
+#[test]
+fn a_new_seed_branches() {
+    let base = ["--size", "64", "--seed", "4"];
+    let branch = resumed(&base, &["--seed", "5"]);
+    let image = branch.image();
+    assert_ne!(image, make_image(&params(&args(&base))));
+    assert_ne!(
+        image,
+        make_image(&params(&args(&["--size", "64", "--seed", "5"])))
+    );
+}
+
+#[test]
+fn resumed_generators_continue_the_stream() {
+    for kind in [
+        RngKind::Std,
+        RngKind::ChaCha8,
+        RngKind::ChaCha20,
+        RngKind::Xoshiro,
+    ] {
+        let mut rng = kind.seeded(7);
+        rng.next_u32();
+        rng.next_u64();
+        rng.fill_bytes(&mut [0; 13]);
+        let (kind, seed, used) = rng.state();
+        let mut resumed = SeededRng::resumed(kind, seed, used);
+        assert_eq!(rng.next_u64(), resumed.next_u64(), "{kind:?}");
+    }
+}
+
+#[test]
+fn bad_scenes_are_refused() {
+    let p = params(&args(&["--size", "32", "--seed", "1", "--coverage", "0.5"]));
+    let mut generator = Generator::new(&p, false);
+    while generator.step() {}
+    let bytes = scene_bytes(&generator);
+    let scene = Scene::from_bytes(bytes.clone()).expect("read back");
+    assert!(!scene.args.contains(&"--coverage".to_string()));
+    let bigger = params(&scene.args_with(&args(&["--size", "33"])));
+    assert!(scene.generator(&bigger, false, false).is_err());
+    let mut newer = bytes.clone();
+    newer[4] = 9;
+    assert!(Scene::from_bytes(newer).is_err());
+    assert!(Scene::from_bytes(bytes[..4].to_vec()).is_err());
+    let short = Scene::from_bytes(bytes[..bytes.len() - 1].to_vec()).expect("header");
+    assert!(short.generator(&p, false, false).is_err());
+}
+
+#[test]
+fn scenes_naming_missing_centers_are_refused() {
+    let p = params(&args(&["--size", "32", "--seed", "1", "--coverage", "0.5"]));
+    let mut generator = Generator::new(&p, false);
+    while generator.step() {}
+    let mut bytes = scene_bytes(&generator);
+    // The first time a placed pixel is written is in the grid.
+    let pixel = (0..32)
+        .flat_map(|y| (0..32).map(move |x| [x, y]))
+        .find_map(|loc| generator.pixel(loc))
+        .expect("placed");
+    let mut writer = SceneWriter::default();
+    writer.pixel(pixel);
+    let written = writer.into_bytes();
+    let at = bytes
+        .windows(written.len())
+        .position(|w| w == written)
+        .expect("written");
+    let id = at + written.len() - 8;
+    bytes[id..id + 8].copy_from_slice(&1_000_000u64.to_le_bytes());
+    let scene = Scene::from_bytes(bytes).expect("header");
+    assert_eq!(
+        scene.generator(&p, false, false).err(),
+        Some(UNKNOWN_CENTER.to_string())
+    );
+}
+
+#[test]
+fn branches_share_the_scene() {
+    let p = params(&args(&["--size", "48", "--seed", "2", "--coverage", "0.3"]));
+    let mut generator = Generator::new(&p, false);
+    while generator.step() {}
+    let (start, filled) = (generator.image(), generator.filled());
+    let scene = Scene::from_bytes(scene_bytes(&generator)).expect("read back");
+    let images: Vec<_> = parse_seeds("5..7")
+        .expect("parsed")
+        .into_iter()
+        .map(|seed| {
+            let (params, mut generator) = scene.branch(seed, &[]).expect("branched");
+            assert_eq!(params.seed, seed);
+            while generator.step() {}
+            generator.image()
+        })
+        .collect();
+    assert_eq!(images.len(), 2);
+    assert_ne!(images[0], images[1]);
+    for image in &images {
+        for (x, column) in filled.iter().enumerate() {
+            for (y, _) in column.iter().enumerate().filter(|(_, f)| **f) {
+                let (x, y) = (x as u32, y as u32);
+                assert_eq!(image.get_pixel(x, y), start.get_pixel(x, y));
+            }
+        }
+    }
+    assert_eq!(parse_seeds("3,9").expect("parsed"), [3, 9]);
+    assert!(parse_seeds("4..4").is_err());
+}
+
+#[test]
+fn resumed_runs_export_only_their_second_part() {
+    let dir = std::env::temp_dir().join(format!("spinning-resume-export-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("made a directory");
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+    let run = |args: &[&str]| {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_spinning"))
+            .args(args)
+            .output()
+            .expect("ran");
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    };
+    let scene = path("s.scene");
+    run(&[
+        "--size",
+        "32",
+        "--coverage",
+        "0.4",
+        "--save-scene",
+        &scene,
+        "--out",
+        &path("a.png"),
+    ]);
+    run(&[
+        "resume",
+        &scene,
+        "--svg",
+        &path("r.svg"),
+        "--export",
+        &path("r.ndjson"),
+        "--out",
+        &path("b.png"),
+    ]);
+    let export = std::fs::read_to_string(path("r.ndjson")).expect("exported");
+    let lines: Vec<&str> = export.lines().collect();
+    let first = (32.0 * 32.0 * 0.4_f64).ceil() as usize;
+    assert_eq!(lines.len(), 32 * 32 - first);
+    // Continuations from the lookback the scene kept have no parent here.
+    assert!(lines[0].contains(r#""parent":null"#));
+    assert!(
+        std::fs::read_to_string(path("r.svg"))
+            .expect("drawn")
+            .contains("<polyline")
+    );
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,34 @@
+use image::Rgba;
+
+use spinning::shader::{GLSL, HEADER, WGSL, replay_buffer, replay_frame};
+use spinning::{Params, generate};
+
+#[test]
+fn replays_the_growth() {
+    let params = Params::from_args(["--size", "24"].map(String::from).into_iter()).unwrap();
+    let run = generate(&params, true);
+    let buffer = replay_buffer(&run.placements, 24);
+    assert_eq!(buffer.len(), HEADER + 8 * 24 * 24);
+
+    let background = Rgba([1, 2, 3, 0]);
+    let empty = replay_frame(&buffer, 0, background).unwrap();
+    assert!(empty.pixels().all(|p| *p == background));
+
+    let total = run.placements.len() as u32;
+    let full = replay_frame(&buffer, total, background).unwrap();
+    for (x, y, pixel) in full.enumerate_pixels() {
+        let [r, g, b] = run.image.get_pixel(x, y).0;
+        assert_eq!(pixel.0, [r, g, b, 255]);
+    }
+
+    // Partway through, exactly the placements made so far show.
+    let shown = total / 3;
+    let frame = replay_frame(&buffer, shown, background).unwrap();
+    let drawn = frame.pixels().filter(|p| **p != background).count();
+    assert_eq!(drawn, shown as usize);
+    let [x, y] = run.placements[0].pixel.loc;
+    assert_ne!(*frame.get_pixel(x as u32, y as u32), background);
+
+    assert!(replay_frame(&buffer[..40], 1, background).is_err());
+    assert!(WGSL.contains("@fragment") && GLSL.contains("#version 300 es"));
+}
@@ -0,0 +1,42 @@
+use spinning::make_image;
+use spinning::walk::Arms;
+
+use crate::common::params;
+
+#[test]
+fn arms_parse_with_defaults() {
+    let arms: Arms = "4".parse().expect("parsed");
+    assert_eq!((arms.count, arms.width, arms.twist), (4, 0.5, 0.0));
+    let arms: Arms = "3:0.25:-1.5".parse().expect("parsed");
+    assert_eq!((arms.count, arms.width, arms.twist), (3, 0.25, -1.5));
+    for bad in ["0", "two", "4:0", "4:1.5", "4:0.5:x", "4:0.5:1:2"] {
+        assert!(bad.parse::<Arms>().is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn arms_are_sectors_that_twist_outward() {
+    let arms: Arms = "4:0.5".parse().expect("parsed");
+    let center = [50.0, 50.0];
+    // The first eighth of each quarter turn, counterclockwise in y-down
+    // coordinates from the +x axis.
+    assert!(arms.allows(center, [80, 53], 100));
+    assert!(!arms.allows(center, [80, 40], 100));
+    assert!(arms.allows(center, [40, 80], 100));
+    assert!(!arms.allows(center, [53, 80], 100));
+    let twisted: Arms = "4:0.5:0.5".parse().expect("parsed");
+    // Half a turn across the canvas is an eighth of a turn 25 pixels out,
+    // moving the arm off the +x axis.
+    assert!(!twisted.allows(center, [75, 51], 100));
+}
+
+#[test]
+fn arms_change_the_render_reproducibly() {
+    let base = ["--size", "48", "--lookback", "200", "--seed", "3"];
+    let armed = params(&[&base[..], &["--arms", "3:0.3:1"]].concat()).expect("parsed");
+    let img = make_image(&armed);
+    assert_eq!(img, make_image(&armed));
+    assert_ne!(img, make_image(&params(&base).expect("parsed")));
+    assert!(params(&["--arms", "4", "--algorithm", "frontier"]).is_err());
+    assert!(params(&["--arms", "4", "--voxels"]).is_err());
+}
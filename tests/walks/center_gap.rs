@@ -0,0 +1,51 @@
+use spinning::center::Centers;
+use spinning::{Generator, Params, make_image};
+
+use crate::common::parsed;
+
+#[test]
+fn gaps_keep_points_clear() {
+    let mut centers = Centers::default().with_gap(Some(5.0));
+    let id = centers.spawn([10, 10], 0, 0);
+    assert!(!centers.clear_of([13, 10], None));
+    assert!(!centers.clear_of([7, 13], None));
+    assert!(centers.clear_of([13, 10], Some(id)));
+    assert!(centers.clear_of([16, 10], None));
+    centers.retire(id);
+    assert!(centers.clear_of([10, 10], None));
+    // Without a gap, everywhere is clear.
+    let mut centers = Centers::default();
+    centers.spawn([10, 10], 0, 0);
+    assert!(centers.clear_of([10, 10], None));
+}
+
+// Pairs of the first n centers closer than gap.
+fn crowded(generator: &Generator, n: usize, gap: f64) -> usize {
+    let centers: Vec<_> = generator.centers().iter().take(n).collect();
+    let mut pairs = 0;
+    for (i, a) in centers.iter().enumerate() {
+        for b in &centers[i + 1..] {
+            let [dx, dy] = [0, 1].map(|k| a.position[k].abs_diff(b.position[k]) as f64);
+            if dx.hypot(dy) < gap {
+                pairs += 1;
+            }
+        }
+    }
+    pairs
+}
+
+#[test]
+fn gaps_spread_centers_out() {
+    let args = ["--size", "96", "--seed", "3", "--centers", "12"];
+    let p = parsed(&[&args[..], &["--center-gap", "12"]].concat());
+    let mut generator = Generator::new(&p, false);
+    while generator.step() {}
+    let mut plain = Generator::new(&parsed(&args), false);
+    while plain.step() {}
+    // The first centers have room to keep apart.
+    assert_eq!(crowded(&generator, 12, 12.0), 0);
+    assert!(crowded(&plain, 12, 12.0) > 0);
+    assert_eq!(generator.image(), make_image(&p));
+    assert_ne!(generator.image(), plain.image());
+    assert!(Params::from_args(["--center-gap", "0"].map(String::from).into_iter()).is_err());
+}
@@ -0,0 +1,52 @@
+use std::f64::consts::FRAC_PI_2;
+
+use spinning::center::Draw;
+use spinning::walk::OrbitShape;
+use spinning::{Generator, Params, make_image};
+
+use crate::common::parsed;
+
+#[test]
+fn tilted_orbits_stretch_along_the_tilt() {
+    // Tilted a quarter turn, twice as long down the canvas as across it.
+    let orbit = OrbitShape::Circle.orbit([50, 50], [50, 60], 2.0, FRAC_PI_2, 0.0, 0.0);
+    assert!(orbit.score([50, 40], 0) < 1e-6);
+    assert!(orbit.score([55, 50], 0) < 1e-6);
+    assert!(orbit.score([60, 50], 0) > 1.0);
+}
+
+#[test]
+fn stretched_centers_draw_their_shapes() {
+    let p = parsed(&[
+        "--size",
+        "64",
+        "--seed",
+        "2",
+        "--center-stretch",
+        "0.3..0.6",
+        "--center-tilt",
+        "10..80",
+    ]);
+    let mut generator = Generator::new(&p, false);
+    while generator.step() {}
+    let centers = generator.centers();
+    assert!(!centers.is_empty());
+    for center in centers.iter() {
+        assert!((0.3..0.6).contains(&center.stretch));
+        assert!((10f64.to_radians()..80f64.to_radians()).contains(&center.tilt));
+    }
+    assert_eq!(generator.image(), make_image(&p));
+    assert_ne!(
+        generator.image(),
+        make_image(&parsed(&["--size", "64", "--seed", "2"]))
+    );
+}
+
+#[test]
+fn draws_parse() {
+    assert_eq!("2".parse::<Draw>(), Ok(Draw { lo: 2.0, hi: 2.0 }));
+    assert_eq!("0..90".parse::<Draw>(), Ok(Draw { lo: 0.0, hi: 90.0 }));
+    assert!("3..1".parse::<Draw>().is_err());
+    assert!(Params::from_args(["--center-tilt", "30"].map(String::from).into_iter()).is_err());
+    assert!(Params::from_args(["--center-stretch", "0"].map(String::from).into_iter()).is_err());
+}
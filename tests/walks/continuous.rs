@@ -0,0 +1,128 @@
+use spinning::generate;
+use spinning::raster::rasterize;
+use spinning::walk::{OrbitShape, WalkSettings, trace};
+
+use crate::common::{params, parsed};
+
+#[test]
+fn continuous_fills_every_cell_once() {
+    let p = parsed(&["--size", "40", "--algorithm", "continuous"]);
+    let run = generate(&p, true);
+    assert_eq!(run.placements.len(), 1600);
+    assert!(run.filled.iter().flatten().all(|&f| f));
+    assert_eq!(generate(&p, false).image, run.image);
+}
+
+#[test]
+fn positions_lie_anywhere_in_their_cells() {
+    let run = generate(
+        &parsed(&["--size", "40", "--algorithm", "continuous"]),
+        true,
+    );
+    let walked: Vec<_> = run
+        .placements
+        .iter()
+        .filter(|p| p.parent.is_some())
+        .collect();
+    assert!(walked.len() > 1100, "{}", walked.len());
+    for placement in &walked {
+        let [x, y] = placement.pixel.loc.map(|c| c as f64);
+        let [px, py] = placement.pos;
+        assert!((x..=x + 1.0).contains(&px) && (y..=y + 1.0).contains(&py));
+    }
+    // Off the grid, the points aren't the cells' middles.
+    let off = walked
+        .iter()
+        .filter(|p| p.pos != p.pixel.loc.map(|c| c as f64 + 0.5))
+        .count();
+    assert!(off > walked.len() / 2, "{off}");
+}
+
+#[test]
+fn continuous_leaves_the_spinning_image_alone() {
+    let spinning = generate(&parsed(&["--size", "40"]), false).image;
+    let again = generate(&parsed(&["--size", "40", "--algorithm", "spinning"]), false).image;
+    assert_eq!(spinning, again);
+    let continuous = generate(
+        &parsed(&["--size", "40", "--algorithm", "continuous"]),
+        false,
+    );
+    assert_ne!(continuous.image, spinning);
+}
+
+#[test]
+fn continuous_runs_are_drawn_at_their_positions() {
+    let p = parsed(&["--size", "40", "--algorithm", "continuous"]);
+    assert!(p.raster_settings().subpixel && !p.custom_raster());
+    let run = generate(&p, true);
+    // One output pixel per cell gives the grid's own image.
+    assert_eq!(
+        rasterize(&run.placements, 40, &p.raster_settings(), |c| c),
+        run.image
+    );
+    let scaled = parsed(&["--size", "40", "--algorithm", "continuous", "--scale", "4"]);
+    assert!(scaled.custom_raster());
+    let image = rasterize(&run.placements, 40, &scaled.raster_settings(), |c| c);
+    assert_eq!(image.dimensions(), (160, 160));
+}
+
+#[test]
+fn traces_follow_the_curve_between_the_cells() {
+    let settings = WalkSettings {
+        neighborhood: Default::default(),
+        gap_jump: 0,
+        reflect: false,
+        wrap: false,
+    };
+    let (center, start) = ([20.3, 20.7], [27.9, 20.2]);
+    let orbit = OrbitShape::Circle.orbit_exact(center, start, 1.0, 0.0, 0.0, 0.0);
+    let radius = (start[0] - center[0]).hypot(start[1] - center[1]);
+    let mut cells = Vec::new();
+    let found = trace(
+        orbit.as_ref(),
+        start.map(|c| c + 0.5),
+        64,
+        &settings,
+        |loc| {
+            cells.push(loc);
+            cells.len() == 20
+        },
+    );
+    let (loc, pos) = found.expect("an open cell");
+    assert_eq!(loc, cells[19]);
+    assert_eq!(pos.map(|c| c as usize), loc);
+    let d = [0, 1].map(|k| pos[k] - 0.5 - center[k]);
+    assert!((d[0].hypot(d[1]) - radius).abs() < 1e-9);
+    // Every cell is tried once, and each is beside the one before.
+    cells.windows(2).for_each(|w| {
+        assert_ne!(w[0], w[1]);
+        assert!(w[0][0].abs_diff(w[1][0]) <= 1 && w[0][1].abs_diff(w[1][1]) <= 1);
+    });
+    // With nothing open, the trace comes back round to its start and stops.
+    let mut tried = 0;
+    let none = trace(
+        orbit.as_ref(),
+        start.map(|c| c + 0.5),
+        64,
+        &settings,
+        |_| {
+            tried += 1;
+            false
+        },
+    );
+    assert!(none.is_none());
+    assert!(tried > 40 && tried < 70, "{tried}");
+}
+
+#[test]
+fn continuous_takes_the_plain_walk_only() {
+    let args = |extra: &[&str]| {
+        let mut args = vec!["--algorithm", "continuous"];
+        args.extend(extra);
+        params(&args)
+    };
+    assert!(args(&[]).is_ok());
+    let err = args(&["--fallback", "nearest"]).unwrap_err();
+    assert_eq!(err, "--fallback needs --algorithm spinning");
+    assert!(args(&["--big", "scratch"]).is_err());
+}
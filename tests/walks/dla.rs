@@ -0,0 +1,25 @@
+use spinning::{Params, generate};
+
+use crate::common::parsed;
+
+#[test]
+fn dla_fills_the_canvas_deterministically() {
+    let p = parsed(&["--size", "40", "--algorithm", "dla:0.5:0.3"]);
+    let run = generate(&p, true);
+    assert_eq!(run.placements.len(), 1600);
+    assert!(run.filled.iter().flatten().all(|&f| f));
+    // Most walkers stick to existing structure rather than starting anew.
+    let attached = run.placements.iter().filter(|p| p.parent.is_some()).count();
+    assert!(attached > 1400, "{attached}");
+    assert_eq!(generate(&p, false).image, run.image);
+}
+
+#[test]
+fn dla_arguments_are_checked() {
+    for ok in ["dla", "dla:0.3", "dla:1:0"] {
+        assert!(Params::from_args(["--algorithm", ok].map(String::from).into_iter()).is_ok());
+    }
+    for bad in ["dla:0", "dla:2", "dla:0.5:-1", "dla:x", "dla:0.5:0.5:1"] {
+        assert!(Params::from_args(["--algorithm", bad].map(String::from).into_iter()).is_err());
+    }
+}
@@ -0,0 +1,32 @@
+use spinning::generate;
+
+use crate::common::params;
+
+#[test]
+fn endgame_changes_only_the_last_placements() {
+    let base = ["--size", "64", "--lookback", "300", "--seed", "5"];
+    let plain = generate(&params(&base).expect("parsed"), true);
+    let p = params(&[&base[..], &["--endgame", "0.9"]].concat()).expect("parsed");
+    let run = generate(&p, true);
+    assert!(run.filled.iter().flatten().all(|&f| f));
+    let before = 64 * 64 * 9 / 10;
+    let same = |a: &spinning::Placement, b: &spinning::Placement| {
+        a.pixel.loc == b.pixel.loc && a.pixel.color == b.pixel.color
+    };
+    assert!(
+        run.placements[..before]
+            .iter()
+            .zip(&plain.placements)
+            .all(|(a, b)| same(a, b))
+    );
+    assert_ne!(run.image, plain.image);
+    // Each endgame pixel continues the center of its match.
+    assert!(run.placements[before..].iter().all(|p| p.parent.is_some()));
+}
+
+#[test]
+fn endgame_takes_a_coverage() {
+    assert!(params(&["--endgame", "1.5"]).is_err());
+    assert!(params(&["--endgame", "0.9", "--algorithm", "frontier"]).is_err());
+    assert!(params(&["--endgame", "0.9", "--voxels"]).is_err());
+}
@@ -0,0 +1,48 @@
+use spinning::generate;
+use spinning::strategy::Fallback;
+
+use crate::common::params;
+
+#[test]
+fn fallbacks_parse_with_counts() {
+    assert_eq!("random".parse(), Ok(Fallback::Random));
+    assert_eq!("retry".parse(), Ok(Fallback::Retry(2)));
+    assert_eq!("retry:5".parse(), Ok(Fallback::Retry(5)));
+    assert_eq!("nearest".parse(), Ok(Fallback::Nearest));
+    assert_eq!("requeue".parse(), Ok(Fallback::Requeue(3)));
+    for bad in ["retry:0", "requeue:x", "nearest:2", "skip"] {
+        assert!(bad.parse::<Fallback>().is_err(), "{bad}");
+    }
+    assert!(params(&["--fallback", "retry", "--algorithm", "frontier"]).is_err());
+}
+
+#[test]
+fn every_fallback_fills_the_canvas() {
+    let base = ["--size", "64", "--lookback", "300", "--seed", "3"];
+    let plain = generate(&params(&base).expect("parsed"), true);
+    for fallback in ["retry:3", "nearest", "requeue:2"] {
+        let p = params(&[&base[..], &["--fallback", fallback]].concat()).expect("parsed");
+        let run = generate(&p, true);
+        assert!(run.filled.iter().flatten().all(|&f| f), "{fallback}");
+        assert_eq!(run.placements.len(), 64 * 64);
+        assert_ne!(run.image, plain.image, "{fallback}");
+        assert_eq!(generate(&p, false).image, run.image, "{fallback}");
+    }
+}
+
+#[test]
+fn nearest_continues_from_a_match_instead_of_starting_centers() {
+    let random_starts = |fallback: &str| {
+        let p =
+            params(&["--size", "64", "--lookback", "300", "--fallback", fallback]).expect("parsed");
+        generate(&p, true)
+            .placements
+            .iter()
+            .filter(|placement| placement.parent.is_none())
+            .count()
+    };
+    // Only the initial centers start anywhere.
+    let centers = params(&["--size", "64"]).expect("parsed").num_centers;
+    assert_eq!(random_starts("nearest"), centers);
+    assert!(random_starts("random") > centers);
+}
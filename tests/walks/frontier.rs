@@ -0,0 +1,28 @@
+use spinning::{Params, generate};
+
+use crate::common::parsed;
+
+#[test]
+fn frontier_grows_beside_its_matches() {
+    let run = generate(&parsed(&["--size", "40", "--algorithm", "frontier"]), true);
+    assert_eq!(run.placements.len(), 1600);
+    assert!(run.filled.iter().flatten().all(|&f| f));
+    let mut adjacent = 0;
+    for placement in &run.placements {
+        if let Some(parent) = placement.parent {
+            let loc = placement.pixel.loc;
+            assert!(loc[0].abs_diff(parent[0]) <= 1 && loc[1].abs_diff(parent[1]) <= 1);
+            adjacent += 1;
+        }
+    }
+    assert!(adjacent > 1500);
+    let again = generate(&parsed(&["--size", "40", "--algorithm", "frontier"]), false);
+    assert_eq!(again.image, run.image);
+}
+
+#[test]
+fn rejects_unknown_algorithms() {
+    assert!(Params::from_args(["--algorithm", "dla2"].map(String::from).into_iter()).is_err());
+    let big = ["--algorithm", "frontier", "--big", "scratch"];
+    assert!(Params::from_args(big.map(String::from).into_iter()).is_err());
+}
@@ -0,0 +1,60 @@
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use spinning::canvas::Canvas;
+use spinning::center::relaxed_layout;
+use spinning::{Location, Params, generate};
+
+use crate::common::parsed;
+
+fn closest_pair(points: &[Location]) -> f64 {
+    let mut best = f64::MAX;
+    for (i, a) in points.iter().enumerate() {
+        for b in &points[i + 1..] {
+            let d = (a[0] as f64 - b[0] as f64).hypot(a[1] as f64 - b[1] as f64);
+            best = best.min(d);
+        }
+    }
+    best
+}
+
+#[test]
+fn relaxation_spreads_the_points() {
+    let layout = |iters| {
+        relaxed_layout(
+            Canvas::Square,
+            200,
+            25,
+            iters,
+            &mut StdRng::seed_from_u64(3),
+        )
+    };
+    let random = layout(0);
+    let relaxed = layout(20);
+    assert_eq!(relaxed.len(), 25);
+    assert!(relaxed.iter().all(|p| p[0] < 200 && p[1] < 200));
+    // Evenly spaced, 25 points sit about 40 apart.
+    assert!(closest_pair(&relaxed) > 25.0, "{}", closest_pair(&relaxed));
+    assert!(closest_pair(&relaxed) > closest_pair(&random));
+    let disc = relaxed_layout(Canvas::Disc, 200, 25, 20, &mut StdRng::seed_from_u64(3));
+    assert!(disc.iter().all(|&p| Canvas::Disc.contains(p, 200)));
+}
+
+#[test]
+fn initial_centers_follow_the_layout() {
+    let args = [
+        "--size",
+        "100",
+        "--centers",
+        "9",
+        "--center-layout",
+        "relaxed",
+    ];
+    let run = generate(&parsed(&args), true);
+    let centers: Vec<Location> = run.placements[..9].iter().map(|p| p.pixel.center).collect();
+    assert!(closest_pair(&centers) > 15.0, "{}", closest_pair(&centers));
+    assert!(run.filled.iter().flatten().all(|&f| f));
+    assert_eq!(generate(&parsed(&args), false).image, run.image);
+    let stencil = ["--center-layout", "relaxed", "--stencil", "text:A"];
+    assert!(Params::from_args(stencil.map(String::from).into_iter()).is_err());
+    assert!(Params::from_args(["--center-layout", "grid"].map(String::from).into_iter()).is_err());
+}
@@ -0,0 +1,22 @@
+// Options that shape the walks and centers.
+
+#[path = "../common/mod.rs"]
+mod common;
+
+mod arms;
+mod center_gap;
+mod center_shape;
+mod continuous;
+mod dla;
+mod endgame;
+mod fallback;
+mod frontier;
+mod layout;
+mod merge_centers;
+mod progressive;
+mod refine;
+mod ring_width;
+mod skip;
+mod stencil;
+mod walk_map;
+mod warm_start;
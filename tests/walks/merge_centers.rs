@@ -0,0 +1,47 @@
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use spinning::center::Centers;
+use spinning::{Generator, make_image};
+
+use crate::common::params;
+
+#[test]
+fn merging_averages_by_weight() {
+    let mut centers = Centers::default();
+    let a = centers.spawn([10, 10], 0, 0);
+    let b = centers.spawn([40, 10], 1, 0);
+    let mut rng = StdRng::seed_from_u64(1);
+    for _ in 0..3 {
+        centers.attach(a, None, 100, &mut rng);
+    }
+    centers.attach(b, None, 100, &mut rng);
+    assert_eq!(centers.collide(a, b), 1);
+    assert_eq!(centers.collide(b, a), 2);
+    assert_eq!(centers.merge(a, b, 100), [17, 10]);
+    let (kept, gone) = (centers.get(a).expect("kept"), centers.get(b).expect("gone"));
+    assert_eq!(kept.pixels, 4);
+    assert!(gone.retired && !kept.retired);
+}
+
+#[test]
+fn colliding_centers_merge() {
+    let base = ["--size", "64", "--lookback", "200", "--seed", "3"];
+    let p = params(&[&base[..], &["--merge-centers", "2"]].concat()).expect("parsed");
+    let mut generator = Generator::new(&p, false);
+    while generator.step() {}
+    assert!(generator.merged() > 0);
+    assert_eq!(make_image(&p), make_image(&p));
+
+    let plain = params(&base).expect("parsed");
+    let mut generator = Generator::new(&plain, false);
+    while generator.step() {}
+    assert_eq!(generator.merged(), 0);
+    assert_ne!(make_image(&p), make_image(&plain));
+}
+
+#[test]
+fn merging_needs_spinning_walks() {
+    assert!(params(&["--merge-centers", "0"]).is_err());
+    assert!(params(&["--merge-centers", "3", "--algorithm", "frontier"]).is_err());
+    assert!(params(&["--merge-centers", "3", "--voxels"]).is_err());
+}
@@ -0,0 +1,74 @@
+use std::process::Command;
+
+use spinning::generate;
+
+use crate::common::from_line;
+
+#[test]
+fn previews_are_scaled_down_runs() {
+    let p = from_line("--size 400 --lookback 2000 --centers 6 --cont-spread 8 --progressive p.png")
+        .unwrap();
+    let preview = p.preview();
+    assert_eq!(preview.size, 96);
+    assert_eq!(preview.num_centers, 6);
+    assert_eq!(preview.num_lookback, 115);
+    assert!((preview.cont_spread - 8.0 * 0.24).abs() < 1e-9);
+    assert!(preview.progressive.is_none());
+    assert_eq!(p.preview().seed, p.seed);
+
+    let p = from_line("--size 400 --progressive p.png --progressive-size 40").unwrap();
+    assert_eq!(p.preview().size, 40);
+    assert_eq!(
+        from_line("--size 30 --progressive p.png")
+            .unwrap()
+            .preview()
+            .size,
+        30
+    );
+}
+
+#[test]
+fn progressive_is_refused_where_it_cannot_scale() {
+    assert!(from_line("--progressive-size 40").is_err());
+    assert!(from_line("--progressive p.png --progressive-size 0").is_err());
+    assert!(from_line("--progressive p.png --voxels --slices s").is_err());
+    assert!(from_line("--progressive p.png --concurrent-walkers 2").is_err());
+}
+
+#[test]
+fn the_preview_is_saved_before_the_run() {
+    let dir = std::env::temp_dir().join(format!("spinning-progressive-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("made a directory");
+    let output = Command::new(env!("CARGO_BIN_EXE_spinning"))
+        .args([
+            "--size",
+            "192",
+            "--seed",
+            "4",
+            "--palette",
+            "magma",
+            "--out-dir",
+        ])
+        .arg(&dir)
+        .args(["--out", "full.png", "--progressive", "preview.png"])
+        .output()
+        .expect("ran");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let saved = stdout.find("preview to").expect("reported the preview");
+    assert!(saved < stdout.find("Placed").expect("reported the run"));
+
+    let preview = image::open(dir.join("preview.png"))
+        .expect("saved")
+        .to_rgb8();
+    assert_eq!(preview.dimensions(), (96, 96));
+    let p = from_line("--size 192 --seed 4 --palette magma --progressive p.png").unwrap();
+    assert_eq!(preview, generate(&p.preview(), false).image);
+    let full = image::open(dir.join("full.png")).expect("saved").to_rgb8();
+    assert_eq!(full.dimensions(), (192, 192));
+    std::fs::remove_dir_all(&dir).ok();
+}
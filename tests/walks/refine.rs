@@ -0,0 +1,44 @@
+use spinning::generate;
+
+use crate::common::from_line;
+
+fn sorted_colors(image: &image::RgbImage) -> Vec<[u8; 3]> {
+    let mut colors: Vec<[u8; 3]> = image.pixels().map(|p| p.0).collect();
+    colors.sort();
+    colors
+}
+
+#[test]
+fn refining_places_the_same_colors_with_fewer_random_starts() {
+    let base = "--size 96 --lookback 200 --centers 4 --seed 5";
+    let plain = generate(&from_line(base).unwrap(), true);
+    let refined = generate(&from_line(&format!("{base} --refine 0.05")).unwrap(), true);
+    assert!(refined.filled.iter().flatten().all(|&f| f));
+    assert_eq!(refined.placements.len(), 96 * 96);
+    assert_eq!(sorted_colors(&plain.image), sorted_colors(&refined.image));
+    assert_ne!(plain.image, refined.image);
+
+    let random = |run: &spinning::Run| run.placements.iter().filter(|p| p.parent.is_none()).count();
+    assert!(
+        random(&refined) < random(&plain),
+        "{} random starts refined, {} before",
+        random(&refined),
+        random(&plain)
+    );
+
+    // Blocks never reach a threshold of 1 here, so nothing changes.
+    let untouched = generate(&from_line(&format!("{base} --refine 1")).unwrap(), false);
+    assert_eq!(untouched.image, plain.image);
+}
+
+#[test]
+fn refinement_is_refused_where_it_cannot_run() {
+    assert!(from_line("--refine 0").is_err());
+    assert!(from_line("--refine 1.5").is_err());
+    assert!(from_line("--refine-block 8").is_err());
+    assert!(from_line("--refine 0.3 --refine-block 8").is_ok());
+    assert!(from_line("--refine 0.3 --coverage 0.5").is_err());
+    assert!(from_line("--refine 0.3 --sampling center").is_err());
+    assert!(from_line("--refine 0.3 --save-scene s.scene").is_err());
+    assert!(from_line("--refine 0.3 --voxels --slices s").is_err());
+}
@@ -0,0 +1,32 @@
+use spinning::walk::OrbitShape;
+use spinning::{Generator, Params, make_image};
+
+use crate::common::parsed;
+
+#[test]
+fn orbits_snap_to_rings() {
+    // Starting 7 from the center, the orbit keeps to the ring at 5.
+    let orbit = OrbitShape::Circle.orbit([50, 50], [57, 50], 1.0, 0.0, 5.0, 0.0);
+    assert!(orbit.score([55, 50], 0) < 1e-6);
+    assert!(orbit.score([50, 45], 0) < 1e-6);
+    assert!(orbit.score([57, 50], 0) > 1.0);
+    // Never onto the center itself.
+    let orbit = OrbitShape::Circle.orbit([50, 50], [51, 50], 1.0, 0.0, 5.0, 0.0);
+    assert!(orbit.score([55, 50], 0) < 1e-6);
+}
+
+#[test]
+fn centers_draw_their_ring_widths() {
+    let p = parsed(&["--size", "64", "--seed", "6", "--ring-width", "3..6"]);
+    let mut generator = Generator::new(&p, false);
+    while generator.step() {}
+    for center in generator.centers().iter() {
+        assert!((3.0..6.0).contains(&center.ring));
+    }
+    assert_eq!(generator.image(), make_image(&p));
+    assert_ne!(
+        generator.image(),
+        make_image(&parsed(&["--size", "64", "--seed", "6"]))
+    );
+    assert!(Params::from_args(["--ring-width", "0"].map(String::from).into_iter()).is_err());
+}
@@ -0,0 +1,41 @@
+use spinning::make_image;
+use spinning::walk::Dashes;
+
+use crate::common::params;
+
+#[test]
+fn dashes_cover_their_share_of_each_ring() {
+    assert!("0:4".parse::<Dashes>().is_err());
+    assert!("6".parse::<Dashes>().is_err());
+    let dashes: Dashes = "6:4".parse().expect("parsed");
+    let center = [100.0, 100.0];
+    let ring: Vec<[usize; 2]> = (0..2000)
+        .map(|i| {
+            let a = i as f64 / 2000.0 * std::f64::consts::TAU;
+            [0, 1].map(|k| (100.0 + 40.0 * [a.cos(), a.sin()][k]).floor() as usize)
+        })
+        .collect();
+    let open = ring
+        .iter()
+        .filter(|&&loc| dashes.allows(center, loc))
+        .count();
+    let share = open as f64 / ring.len() as f64;
+    assert!((share - 0.6).abs() < 0.05, "{share}");
+}
+
+#[test]
+fn skips_change_the_render_reproducibly() {
+    let base = ["--size", "48", "--lookback", "200", "--seed", "3"];
+    let plain = make_image(&params(&base).expect("parsed"));
+    let none = params(&[&base[..], &["--skip-prob", "0"]].concat()).expect("parsed");
+    assert_eq!(make_image(&none), plain);
+    for skip in [["--skip-prob", "0.4"], ["--skip-dashes", "3:2"]] {
+        let p = params(&[&base[..], &skip].concat()).expect("parsed");
+        let img = make_image(&p);
+        assert_eq!(img, make_image(&p));
+        assert_ne!(img, plain);
+    }
+    assert!(params(&["--skip-prob", "1"]).is_err());
+    assert!(params(&["--skip-prob", "0.5", "--algorithm", "frontier"]).is_err());
+    assert!(params(&["--skip-dashes", "3:2", "--big", "scratch"]).is_err());
+}
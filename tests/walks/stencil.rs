@@ -0,0 +1,37 @@
+use spinning::stencil::Stencil;
+
+fn svg_stencil(svg: &str) -> Stencil {
+    let path = std::env::temp_dir().join(format!("spinning-stencil-{}.svg", svg.len()));
+    std::fs::write(&path, svg).unwrap();
+    let stencil = format!("svg:{}", path.display()).parse().expect("parsed");
+    std::fs::remove_file(path).ok();
+    stencil
+}
+
+#[test]
+fn square_path_fills_the_middle() {
+    let stencil = svg_stencil(r#"<svg><path d="M0,0 h10 v10 h-10 z"/></svg>"#);
+    let size = 50;
+    let mask = stencil.mask(size);
+    // Scaled to fit with a tenth of the canvas clear on every side.
+    assert!(mask[25 * size + 25]);
+    assert!(mask[5 * size + 5]);
+    assert!(!mask[4 * size + 4]);
+    assert!(!mask[size * size - 1]);
+    assert_eq!(mask.iter().filter(|&&m| m).count(), 40 * 40);
+    assert_eq!(stencil.outline(size).len(), 4 * 39);
+}
+
+#[test]
+fn text_outline_stays_inside_margin() {
+    let stencil: Stencil = "text:Hi".parse().unwrap();
+    let size = 100;
+    let outline = stencil.outline(size);
+    assert!(!outline.is_empty());
+    assert!(
+        outline
+            .iter()
+            .all(|&[x, y]| (10..90).contains(&x) && (10..90).contains(&y))
+    );
+    assert!("text:~".parse::<Stencil>().is_err());
+}
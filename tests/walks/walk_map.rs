@@ -0,0 +1,29 @@
+use spinning::{Generator, make_image};
+
+use crate::common::params;
+
+#[test]
+fn walk_map_leaves_the_image_alone() {
+    let base = ["--size", "48", "--lookback", "200", "--seed", "3"];
+    let p = params(&[&base[..], &["--walk-map", "walks.png"]].concat()).expect("parsed");
+    assert_eq!(make_image(&p), make_image(&params(&base).expect("parsed")));
+
+    let mut generator = Generator::new(&p, false);
+    while generator.step() {}
+    let map = generator.walk_map().expect("walks recorded");
+    assert_eq!(map.dimensions(), (48, 48));
+    // Walks cross cells on their way to open ones, so the map isn't flat.
+    let first = map.get_pixel(0, 0);
+    assert!(map.pixels().any(|px| px != first));
+    assert!(
+        Generator::new(&params(&base).expect("parsed"), false)
+            .walk_map()
+            .is_none()
+    );
+}
+
+#[test]
+fn walk_map_needs_spinning_walks() {
+    assert!(params(&["--walk-map", "w.png", "--algorithm", "frontier"]).is_err());
+    assert!(params(&["--walk-map", "w.png", "--voxels"]).is_err());
+}
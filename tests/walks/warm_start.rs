@@ -0,0 +1,58 @@
+use image::RgbImage;
+use spinning::{Params, make_image};
+
+use crate::common::{fixture, parsed};
+
+fn mean_luma(image: &RgbImage, columns: std::ops::Range<u32>) -> f64 {
+    let mut sum = 0.0;
+    let mut n = 0.0;
+    for x in columns {
+        for y in 0..image.height() {
+            sum += image
+                .get_pixel(x, y)
+                .0
+                .iter()
+                .map(|&c| c as f64)
+                .sum::<f64>();
+            n += 3.0;
+        }
+    }
+    sum / n
+}
+
+#[test]
+fn the_run_follows_the_image() {
+    // Varied colors, dark on the left and light on the right, so that the
+    // samples cover color space and new colors match them.
+    let halves = fixture("halves.png");
+    let p = parsed(&[
+        "--size",
+        "64",
+        "--seed",
+        "5",
+        "--lookback",
+        "500",
+        "--warm-start",
+        &halves,
+    ]);
+    let image = make_image(&p);
+    assert_eq!(image, make_image(&p));
+    assert_ne!(
+        image,
+        make_image(&parsed(&[
+            "--size",
+            "64",
+            "--seed",
+            "5",
+            "--lookback",
+            "500"
+        ]))
+    );
+    let (left, right) = (mean_luma(&image, 0..32), mean_luma(&image, 32..64));
+    assert!(left + 10.0 < right, "left {left:.1}, right {right:.1}");
+}
+
+#[test]
+fn warm_samples_need_a_warm_start() {
+    assert!(Params::from_args(["--warm-samples", "10"].map(String::from).into_iter()).is_err());
+}